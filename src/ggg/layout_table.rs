@@ -7,6 +7,8 @@ use super::LookupList;
 #[cfg(feature = "variable-fonts")]
 use crate::parser::Offset32;
 use crate::parser::{FromData, LazyArray16, Offset, Offset16, Stream};
+#[cfg(feature = "variable-fonts")]
+use crate::NormalizedCoordinate;
 use crate::Tag;
 
 /// A [Layout Table](https://docs.microsoft.com/en-us/typography/opentype/spec/chapter2#table-organization).
@@ -69,6 +71,72 @@ impl<'a> LayoutTable<'a> {
             })
         }
     }
+
+    /// Returns lookup indices of a feature in the specified script and language system.
+    ///
+    /// When `script` is not present, the `DFLT` script is used instead.
+    /// When `language` is `None` or not present, the default language system is used.
+    ///
+    /// [`FeatureVariations`] are ignored. Use [`LayoutTable::feature_lookups_at`]
+    /// for variable fonts.
+    pub fn feature_lookups(
+        &self,
+        script: Tag,
+        language: Option<Tag>,
+        feature: Tag,
+    ) -> Option<LazyArray16<'a, LookupIndex>> {
+        let index = self.find_feature_index(script, language, feature)?;
+        Some(self.features.get(index)?.lookup_indices)
+    }
+
+    /// Returns lookup indices of a feature in the specified script and language system
+    /// at the specified variation coordinates.
+    ///
+    /// Same as [`LayoutTable::feature_lookups`], but the feature is substituted
+    /// according to [`FeatureVariations`], if any.
+    #[cfg(feature = "variable-fonts")]
+    pub fn feature_lookups_at(
+        &self,
+        script: Tag,
+        language: Option<Tag>,
+        feature: Tag,
+        coordinates: &[NormalizedCoordinate],
+    ) -> Option<LazyArray16<'a, LookupIndex>> {
+        let index = self.find_feature_index(script, language, feature)?;
+
+        if let Some(ref variations) = self.variations {
+            if let Some(variation_index) = variations.find_index(coordinates) {
+                if let Some(substitute) = variations.find_substitute(index, variation_index) {
+                    return Some(substitute.lookup_indices);
+                }
+            }
+        }
+
+        Some(self.features.get(index)?.lookup_indices)
+    }
+
+    fn find_feature_index(
+        &self,
+        script: Tag,
+        language: Option<Tag>,
+        feature: Tag,
+    ) -> Option<FeatureIndex> {
+        let script = self
+            .scripts
+            .find(script)
+            .or_else(|| self.scripts.find(Tag::from_bytes(b"DFLT")))?;
+
+        let language = match language {
+            Some(tag) => script.languages.find(tag).or(script.default_language),
+            None => script.default_language,
+        }?;
+
+        language
+            .required_feature
+            .into_iter()
+            .chain(language.feature_indices)
+            .find(|index| self.features.get(*index).map(|f| f.tag) == Some(feature))
+    }
 }
 
 /// An index in [`ScriptList`].
@@ -0,0 +1,1849 @@
+//! The TrueType bytecode interpreter.
+//!
+//! Follows the behavior of the FreeType v35 interpreter,
+//! including its handling of undocumented edge cases and malformed fonts.
+
+use core::convert::TryFrom;
+use std::vec::Vec;
+
+use super::graphics_state::{GraphicsState, RoundMode};
+use super::math::{self, F26Dot6, Point, UnitVector};
+use super::zone::{Zone, ZoneId, ON_CURVE, TOUCHED_BOTH, TOUCHED_X, TOUCHED_Y};
+
+/// The maximum number of instructions a single program can execute.
+const MAX_INSTRUCTIONS: u32 = 1_000_000;
+
+/// The maximum depth of nested function calls.
+const MAX_CALL_DEPTH: usize = 32;
+
+/// Interpreter version reported by `GETINFO`.
+const INTERPRETER_VERSION: i32 = 35;
+
+/// A program kind.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum ProgramKind {
+    /// The `fpgm` table.
+    Font,
+    /// The `prep` table.
+    ControlValue,
+    /// Glyph instructions.
+    Glyph,
+}
+
+/// A function or instruction definition.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Definition {
+    program: ProgramKind,
+    start: usize,
+    end: usize,
+}
+
+/// An interpreter error.
+///
+/// Any error aborts the current program.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Error {
+    StackOverflow,
+    CodeOverflow,
+    InvalidOpcode,
+    InvalidReference,
+    DivideByZero,
+    BadArgument,
+    NestedDefinitions,
+    DefinitionInGlyphProgram,
+    EndfInExecStream,
+    TooManyDefinitions,
+    ExecutionTooLong,
+    DebugOpcode,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct CallRecord {
+    caller_program: ProgramKind,
+    caller_ip: usize,
+    count: i32,
+    definition: Definition,
+}
+
+/// The interpreter state that is preserved between programs.
+#[derive(Clone, Debug)]
+pub(crate) struct State {
+    /// The graphics state used at the start of each program.
+    pub default_gs: GraphicsState,
+    pub functions: Vec<Option<Definition>>,
+    pub instructions: Vec<Option<Definition>>,
+    /// Scaled control values in 26.6.
+    pub cvt: Vec<F26Dot6>,
+    pub storage: Vec<i32>,
+    pub twilight: Zone,
+}
+
+/// Per-size interpreter parameters.
+#[derive(Clone, Debug)]
+pub(crate) struct Metrics {
+    /// Integer pixels per em.
+    pub ppem: i32,
+    /// Font units to 26.6 scale in 16.16.
+    pub scale: i32,
+    /// Normalized variation coordinates in 2.14.
+    pub coords: Vec<i16>,
+    pub is_variable: bool,
+}
+
+/// Program sources.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Programs<'a> {
+    pub font: &'a [u8],
+    pub control_value: &'a [u8],
+    pub glyph: &'a [u8],
+}
+
+impl<'a> Programs<'a> {
+    #[inline]
+    fn get(&self, kind: ProgramKind) -> &'a [u8] {
+        match kind {
+            ProgramKind::Font => self.font,
+            ProgramKind::ControlValue => self.control_value,
+            ProgramKind::Glyph => self.glyph,
+        }
+    }
+}
+
+pub(crate) struct Engine<'a> {
+    pub programs: Programs<'a>,
+    pub state: State,
+    pub metrics: Metrics,
+    pub gs: GraphicsState,
+    pub glyph: Zone,
+    /// Scale used to convert unscaled glyph points into 26.6.
+    ///
+    /// Is 1.0 for composite glyphs, since their unscaled points are already scaled.
+    pub points_scale: i32,
+
+    stack: Vec<i32>,
+    top: usize,
+    args: usize,
+    new_top: usize,
+    call_stack: Vec<CallRecord>,
+    initial_program: ProgramKind,
+    program: ProgramKind,
+    ip: usize,
+    opcode: u8,
+    opcode_len: usize,
+    step_ip: bool,
+}
+
+impl<'a> Engine<'a> {
+    pub fn new(programs: Programs<'a>, state: State, metrics: Metrics, max_stack: usize) -> Self {
+        Engine {
+            programs,
+            gs: state.default_gs,
+            state,
+            points_scale: metrics.scale,
+            metrics,
+            glyph: Zone::default(),
+            stack: vec![0; max_stack],
+            top: 0,
+            args: 0,
+            new_top: 0,
+            call_stack: Vec::with_capacity(MAX_CALL_DEPTH),
+            initial_program: ProgramKind::Font,
+            program: ProgramKind::Font,
+            ip: 0,
+            opcode: 0,
+            opcode_len: 0,
+            step_ip: true,
+        }
+    }
+
+    /// Runs a program.
+    ///
+    /// The graphics state must be set up by the caller.
+    pub fn run(&mut self, program: ProgramKind) -> Result<(), Error> {
+        self.initial_program = program;
+        self.program = program;
+        self.ip = 0;
+        self.top = 0;
+        self.call_stack.clear();
+
+        let mut count = 0;
+        while self.ip < self.code().len() {
+            let code = self.code();
+            self.opcode = code[self.ip];
+            self.opcode_len = instruction_len(code, self.ip).ok_or(Error::CodeOverflow)?;
+
+            let (pops, pushes) = stack_effect(self.opcode);
+            if self.top < pops {
+                // Missing arguments are zeros.
+                let len = pops.min(self.stack.len());
+                for v in &mut self.stack[..len] {
+                    *v = 0;
+                }
+
+                self.args = 0;
+            } else {
+                self.args = self.top - pops;
+            }
+
+            self.new_top = self.args + pushes;
+            if self.new_top > self.stack.len() {
+                return Err(Error::StackOverflow);
+            }
+
+            self.step_ip = true;
+            self.execute()?;
+            self.top = self.new_top;
+
+            if self.step_ip {
+                self.ip += self.opcode_len;
+            }
+
+            count += 1;
+            if count > MAX_INSTRUCTIONS {
+                return Err(Error::ExecutionTooLong);
+            }
+
+            if self.ip >= self.code().len() && !self.call_stack.is_empty() {
+                return Err(Error::CodeOverflow);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn code(&self) -> &'a [u8] {
+        self.programs.get(self.program)
+    }
+
+    #[inline]
+    fn arg(&self, index: usize) -> i32 {
+        self.stack[self.args + index]
+    }
+
+    #[inline]
+    fn set_arg(&mut self, index: usize, value: i32) {
+        self.stack[self.args + index] = value;
+    }
+
+    #[inline]
+    fn zone(&self, id: ZoneId) -> &Zone {
+        match id {
+            ZoneId::Twilight => &self.state.twilight,
+            ZoneId::Glyph => &self.glyph,
+        }
+    }
+
+    #[inline]
+    fn zone_mut(&mut self, id: ZoneId) -> &mut Zone {
+        match id {
+            ZoneId::Twilight => &mut self.state.twilight,
+            ZoneId::Glyph => &mut self.glyph,
+        }
+    }
+
+    #[inline]
+    fn zone_len(&self, id: ZoneId) -> usize {
+        self.zone(id).len()
+    }
+
+    #[inline]
+    fn project(&self, p1: Point, p2: Point) -> F26Dot6 {
+        math::dot_fix14(
+            p1.x.wrapping_sub(p2.x),
+            p1.y.wrapping_sub(p2.y),
+            self.gs.proj_vector,
+        )
+    }
+
+    #[inline]
+    fn dual_project(&self, p1: Point, p2: Point) -> F26Dot6 {
+        math::dot_fix14(
+            p1.x.wrapping_sub(p2.x),
+            p1.y.wrapping_sub(p2.y),
+            self.gs.dual_vector,
+        )
+    }
+
+    #[inline]
+    fn fast_project(&self, p: Point) -> F26Dot6 {
+        math::dot_fix14(p.x, p.y, self.gs.proj_vector)
+    }
+
+    #[inline]
+    fn round(&self, distance: F26Dot6) -> F26Dot6 {
+        self.gs.round_state.round(distance)
+    }
+
+    /// Moves a point along the freedom vector and marks it as touched.
+    fn move_point(&mut self, zone: ZoneId, point: usize, distance: F26Dot6) {
+        let fv = self.gs.free_vector;
+        let f_dot_p = self.gs.f_dot_p();
+        let zone = self.zone_mut(zone);
+        if fv.x != 0 {
+            let p = &mut zone.points[point];
+            p.x = p.x.wrapping_add(math::mul_div(distance, fv.x, f_dot_p));
+            zone.flags[point] |= TOUCHED_X;
+        }
+
+        if fv.y != 0 {
+            let p = &mut zone.points[point];
+            p.y = p.y.wrapping_add(math::mul_div(distance, fv.y, f_dot_p));
+            zone.flags[point] |= TOUCHED_Y;
+        }
+    }
+
+    /// Moves an original point along the freedom vector.
+    fn move_original(&mut self, zone: ZoneId, point: usize, distance: F26Dot6) {
+        let fv = self.gs.free_vector;
+        let f_dot_p = self.gs.f_dot_p();
+        let p = &mut self.zone_mut(zone).original[point];
+        if fv.x != 0 {
+            p.x = p.x.wrapping_add(math::mul_div(distance, fv.x, f_dot_p));
+        }
+
+        if fv.y != 0 {
+            p.y = p.y.wrapping_add(math::mul_div(distance, fv.y, f_dot_p));
+        }
+    }
+
+    fn move_zp2_point(&mut self, point: usize, dx: F26Dot6, dy: F26Dot6, touch: bool) {
+        let fv = self.gs.free_vector;
+        let zone = self.zone_mut(self.gs.zp2);
+        if fv.x != 0 {
+            zone.points[point].x = zone.points[point].x.wrapping_add(dx);
+            if touch {
+                zone.flags[point] |= TOUCHED_X;
+            }
+        }
+
+        if fv.y != 0 {
+            zone.points[point].y = zone.points[point].y.wrapping_add(dy);
+            if touch {
+                zone.flags[point] |= TOUCHED_Y;
+            }
+        }
+    }
+
+    fn execute(&mut self) -> Result<(), Error> {
+        let opcode = self.opcode;
+        match opcode {
+            // SVTCA
+            0x00 | 0x01 => {
+                let v = axis_vector(opcode);
+                self.gs.proj_vector = v;
+                self.gs.free_vector = v;
+                self.gs.dual_vector = v;
+            }
+            // SPVTCA
+            0x02 | 0x03 => {
+                let v = axis_vector(opcode);
+                self.gs.proj_vector = v;
+                self.gs.dual_vector = v;
+            }
+            // SFVTCA
+            0x04 | 0x05 => self.gs.free_vector = axis_vector(opcode),
+            // SPVTL
+            0x06 | 0x07 => {
+                if let Some(v) = self.vector_to_line() {
+                    self.gs.proj_vector = v;
+                    self.gs.dual_vector = v;
+                }
+            }
+            // SFVTL
+            0x08 | 0x09 => {
+                if let Some(v) = self.vector_to_line() {
+                    self.gs.free_vector = v;
+                }
+            }
+            // SPVFS
+            0x0A => {
+                if let Some(v) =
+                    math::normalize(i32::from(self.arg(0) as i16), i32::from(self.arg(1) as i16))
+                {
+                    self.gs.proj_vector = v;
+                    self.gs.dual_vector = v;
+                }
+            }
+            // SFVFS
+            0x0B => {
+                if let Some(v) =
+                    math::normalize(i32::from(self.arg(0) as i16), i32::from(self.arg(1) as i16))
+                {
+                    self.gs.free_vector = v;
+                }
+            }
+            // GPV
+            0x0C => {
+                self.set_arg(0, self.gs.proj_vector.x);
+                self.set_arg(1, self.gs.proj_vector.y);
+            }
+            // GFV
+            0x0D => {
+                self.set_arg(0, self.gs.free_vector.x);
+                self.set_arg(1, self.gs.free_vector.y);
+            }
+            // SFVTPV
+            0x0E => self.gs.free_vector = self.gs.proj_vector,
+            // ISECT
+            0x0F => self.op_isect(),
+            // SRP0
+            0x10 => self.gs.rp0 = usize::from(self.arg(0) as u16),
+            // SRP1
+            0x11 => self.gs.rp1 = usize::from(self.arg(0) as u16),
+            // SRP2
+            0x12 => self.gs.rp2 = usize::from(self.arg(0) as u16),
+            // SZP0, SZP1, SZP2, SZPS
+            0x13..=0x16 => {
+                let zone = match self.arg(0) {
+                    0 => ZoneId::Twilight,
+                    1 => ZoneId::Glyph,
+                    _ => return Ok(()),
+                };
+
+                match opcode {
+                    0x13 => self.gs.zp0 = zone,
+                    0x14 => self.gs.zp1 = zone,
+                    0x15 => self.gs.zp2 = zone,
+                    _ => {
+                        self.gs.zp0 = zone;
+                        self.gs.zp1 = zone;
+                        self.gs.zp2 = zone;
+                    }
+                }
+            }
+            // SLOOP
+            0x17 => {
+                let n = self.arg(0);
+                if n < 0 {
+                    return Err(Error::BadArgument);
+                }
+
+                // Limit to 16 bits, just like FreeType does.
+                self.gs.loop_counter = n.min(0xFFFF) as u32;
+            }
+            // RTG
+            0x18 => self.gs.round_state.mode = RoundMode::ToGrid,
+            // RTHG
+            0x19 => self.gs.round_state.mode = RoundMode::ToHalfGrid,
+            // SMD
+            0x1A => self.gs.min_distance = self.arg(0),
+            // ELSE
+            0x1B => self.op_else()?,
+            // JMPR
+            0x1C => self.jump(self.arg(0))?,
+            // SCVTCI
+            0x1D => self.gs.control_value_cutin = self.arg(0),
+            // SSWCI
+            0x1E => self.gs.single_width_cutin = self.arg(0),
+            // SSW
+            0x1F => self.gs.single_width_value = math::mul_fix(self.arg(0), self.metrics.scale),
+            // DUP
+            0x20 => self.set_arg(1, self.arg(0)),
+            // POP
+            0x21 => {}
+            // CLEAR
+            0x22 => self.new_top = 0,
+            // SWAP
+            0x23 => {
+                let a = self.arg(0);
+                self.set_arg(0, self.arg(1));
+                self.set_arg(1, a);
+            }
+            // DEPTH
+            0x24 => self.set_arg(0, self.args as i32),
+            // CINDEX
+            0x25 => {
+                let n = self.arg(0);
+                let v = if n <= 0 || n as usize > self.args {
+                    0
+                } else {
+                    self.stack[self.args - n as usize]
+                };
+                self.set_arg(0, v);
+            }
+            // MINDEX
+            0x26 => {
+                let n = self.arg(0);
+                if n > 0 && n as usize <= self.args {
+                    let index = self.args - n as usize;
+                    let v = self.stack[index];
+                    self.stack.copy_within(index + 1..self.args, index);
+                    self.stack[self.args - 1] = v;
+                }
+            }
+            // ALIGNPTS
+            0x27 => {
+                let p1 = usize::from(self.arg(0) as u16);
+                let p2 = usize::from(self.arg(1) as u16);
+                if p1 < self.zone_len(self.gs.zp1) && p2 < self.zone_len(self.gs.zp0) {
+                    let distance = self.project(
+                        self.zone(self.gs.zp0).points[p2],
+                        self.zone(self.gs.zp1).points[p1],
+                    ) / 2;
+                    self.move_point(self.gs.zp1, p1, distance);
+                    self.move_point(self.gs.zp0, p2, distance.wrapping_neg());
+                }
+            }
+            // UTP
+            0x29 => {
+                let point = usize::from(self.arg(0) as u16);
+                if point < self.zone_len(self.gs.zp0) {
+                    let mut mask = 0xFF;
+                    if self.gs.free_vector.x != 0 {
+                        mask &= !TOUCHED_X;
+                    }
+
+                    if self.gs.free_vector.y != 0 {
+                        mask &= !TOUCHED_Y;
+                    }
+
+                    self.zone_mut(self.gs.zp0).flags[point] &= mask;
+                }
+            }
+            // LOOPCALL
+            0x2A => {
+                let definition = self.function(self.arg(1))?;
+                let count = self.arg(0);
+                if self.call_stack.len() >= MAX_CALL_DEPTH {
+                    return Err(Error::StackOverflow);
+                }
+
+                if count > 0 {
+                    self.call(definition, count);
+                }
+            }
+            // CALL
+            0x2B => {
+                let definition = self.function(self.arg(0))?;
+                if self.call_stack.len() >= MAX_CALL_DEPTH {
+                    return Err(Error::StackOverflow);
+                }
+
+                self.call(definition, 1);
+            }
+            // FDEF
+            0x2C => {
+                if self.initial_program == ProgramKind::Glyph {
+                    return Err(Error::DefinitionInGlyphProgram);
+                }
+
+                let n = usize::try_from(self.arg(0)).map_err(|_| Error::TooManyDefinitions)?;
+                if n > 0xFFFF {
+                    return Err(Error::TooManyDefinitions);
+                }
+
+                let definition = self.skip_definition()?;
+                if self.state.functions.len() <= n {
+                    self.state.functions.resize(n + 1, None);
+                }
+
+                self.state.functions[n] = Some(definition);
+            }
+            // ENDF
+            0x2D => {
+                let mut record = self.call_stack.pop().ok_or(Error::EndfInExecStream)?;
+                record.count -= 1;
+                self.step_ip = false;
+                if record.count > 0 {
+                    self.ip = record.definition.start;
+                    self.call_stack.push(record);
+                } else {
+                    self.program = record.caller_program;
+                    self.ip = record.caller_ip;
+                }
+            }
+            // MDAP
+            0x2E | 0x2F => {
+                let point = usize::from(self.arg(0) as u16);
+                if point >= self.zone_len(self.gs.zp0) {
+                    return Ok(());
+                }
+
+                let distance = if opcode & 1 != 0 {
+                    let d = self.fast_project(self.zone(self.gs.zp0).points[point]);
+                    self.round(d).wrapping_sub(d)
+                } else {
+                    0
+                };
+
+                self.move_point(self.gs.zp0, point, distance);
+                self.gs.rp0 = point;
+                self.gs.rp1 = point;
+            }
+            // IUP
+            0x30 | 0x31 => {
+                // Applies only to the glyph zone.
+                self.glyph.interpolate_untouched(opcode & 1 != 0);
+            }
+            // SHP
+            0x32 | 0x33 => self.op_shp(),
+            // SHC
+            0x34 | 0x35 => self.op_shc(),
+            // SHZ
+            0x36 | 0x37 => self.op_shz(),
+            // SHPIX
+            0x38 => self.op_shpix(),
+            // IP
+            0x39 => self.op_ip(),
+            // MSIRP
+            0x3A | 0x3B => self.op_msirp(),
+            // ALIGNRP
+            0x3C => self.op_alignrp(),
+            // RTDG
+            0x3D => self.gs.round_state.mode = RoundMode::ToDoubleGrid,
+            // MIAP
+            0x3E | 0x3F => self.op_miap(),
+            // NPUSHB, NPUSHW, PUSHB, PUSHW
+            0x40 | 0x41 | 0xB0..=0xBF => self.op_push()?,
+            // WS
+            0x42 => {
+                let value = self.arg(1);
+                let index = usize::try_from(self.arg(0)).unwrap_or(usize::MAX);
+                if let Some(v) = self.state.storage.get_mut(index) {
+                    *v = value;
+                }
+            }
+            // RS
+            0x43 => {
+                let v = usize::try_from(self.arg(0))
+                    .ok()
+                    .and_then(|i| self.state.storage.get(i).copied())
+                    .unwrap_or(0);
+                self.set_arg(0, v);
+            }
+            // WCVTP
+            0x44 => {
+                let value = self.arg(1);
+                if let Some(v) = self.cvt_mut(self.arg(0)) {
+                    *v = value;
+                }
+            }
+            // RCVT
+            0x45 => {
+                let v = self.cvt(self.arg(0)).unwrap_or(0);
+                self.set_arg(0, v);
+            }
+            // GC
+            0x46 | 0x47 => {
+                let point = usize::try_from(self.arg(0)).unwrap_or(usize::MAX);
+                let zone = self.zone(self.gs.zp2);
+                let v = if point >= zone.len() {
+                    0
+                } else if opcode & 1 != 0 {
+                    math::dot_fix14(
+                        zone.original[point].x,
+                        zone.original[point].y,
+                        self.gs.dual_vector,
+                    )
+                } else {
+                    self.fast_project(zone.points[point])
+                };
+                self.set_arg(0, v);
+            }
+            // SCFS
+            0x48 => {
+                let point = usize::from(self.arg(0) as u16);
+                if point < self.zone_len(self.gs.zp2) {
+                    let k = self.fast_project(self.zone(self.gs.zp2).points[point]);
+                    self.move_point(self.gs.zp2, point, self.arg(1).wrapping_sub(k));
+                    if self.gs.zp2 == ZoneId::Twilight {
+                        let zone = &mut self.state.twilight;
+                        zone.original[point] = zone.points[point];
+                    }
+                }
+            }
+            // MD
+            0x49 | 0x4A => {
+                let d = self.op_md();
+                self.set_arg(0, d);
+            }
+            // MPPEM, MPS
+            0x4B | 0x4C => self.set_arg(0, self.metrics.ppem),
+            // FLIPON
+            0x4D => self.gs.auto_flip = true,
+            // FLIPOFF
+            0x4E => self.gs.auto_flip = false,
+            // DEBUG
+            0x4F => return Err(Error::DebugOpcode),
+            // LT, LTEQ, GT, GTEQ, EQ, NEQ
+            0x50..=0x55 => {
+                let (a, b) = (self.arg(0), self.arg(1));
+                let v = match opcode {
+                    0x50 => a < b,
+                    0x51 => a <= b,
+                    0x52 => a > b,
+                    0x53 => a >= b,
+                    0x54 => a == b,
+                    _ => a != b,
+                };
+                self.set_arg(0, i32::from(v));
+            }
+            // ODD
+            0x56 => {
+                let v = self.round(self.arg(0)) & 127 == 64;
+                self.set_arg(0, i32::from(v));
+            }
+            // EVEN
+            0x57 => {
+                let v = self.round(self.arg(0)) & 127 == 0;
+                self.set_arg(0, i32::from(v));
+            }
+            // IF
+            0x58 => {
+                if self.arg(0) == 0 {
+                    self.op_if()?;
+                }
+            }
+            // EIF
+            0x59 => {}
+            // AND
+            0x5A => {
+                let v = self.arg(0) != 0 && self.arg(1) != 0;
+                self.set_arg(0, i32::from(v));
+            }
+            // OR
+            0x5B => {
+                let v = self.arg(0) != 0 || self.arg(1) != 0;
+                self.set_arg(0, i32::from(v));
+            }
+            // NOT
+            0x5C => {
+                let v = self.arg(0) == 0;
+                self.set_arg(0, i32::from(v));
+            }
+            // DELTAP1, DELTAP2, DELTAP3
+            0x5D | 0x71 | 0x72 => self.op_deltap(),
+            // SDB
+            0x5E => self.gs.delta_base = self.arg(0) as u16,
+            // SDS
+            0x5F => {
+                let n = self.arg(0);
+                if !(0..=6).contains(&n) {
+                    return Err(Error::BadArgument);
+                }
+
+                self.gs.delta_shift = n as u16;
+            }
+            // ADD
+            0x60 => self.set_arg(0, self.arg(0).wrapping_add(self.arg(1))),
+            // SUB
+            0x61 => self.set_arg(0, self.arg(0).wrapping_sub(self.arg(1))),
+            // DIV
+            0x62 => {
+                if self.arg(1) == 0 {
+                    return Err(Error::DivideByZero);
+                }
+
+                self.set_arg(0, math::mul_div_no_round(self.arg(0), 64, self.arg(1)));
+            }
+            // MUL
+            0x63 => self.set_arg(0, math::mul_div(self.arg(0), self.arg(1), 64)),
+            // ABS
+            0x64 => self.set_arg(0, self.arg(0).wrapping_abs()),
+            // NEG
+            0x65 => self.set_arg(0, self.arg(0).wrapping_neg()),
+            // FLOOR
+            0x66 => self.set_arg(0, math::floor(self.arg(0))),
+            // CEILING
+            0x67 => self.set_arg(0, math::ceil(self.arg(0))),
+            // ROUND
+            0x68..=0x6B => self.set_arg(0, self.round(self.arg(0))),
+            // NROUND
+            0x6C..=0x6F => {}
+            // WCVTF
+            0x70 => {
+                let value = math::mul_fix(self.arg(1), self.metrics.scale);
+                if let Some(v) = self.cvt_mut(self.arg(0)) {
+                    *v = value;
+                }
+            }
+            // DELTAC1, DELTAC2, DELTAC3
+            0x73..=0x75 => self.op_deltac(),
+            // SROUND
+            0x76 => {
+                self.gs.round_state.set_super(0x4000, self.arg(0));
+                self.gs.round_state.mode = RoundMode::Super;
+            }
+            // S45ROUND
+            0x77 => {
+                self.gs.round_state.set_super(0x2D41, self.arg(0));
+                self.gs.round_state.mode = RoundMode::Super45;
+            }
+            // JROT
+            0x78 => {
+                if self.arg(1) != 0 {
+                    self.jump(self.arg(0))?;
+                }
+            }
+            // JROF
+            0x79 => {
+                if self.arg(1) == 0 {
+                    self.jump(self.arg(0))?;
+                }
+            }
+            // ROFF
+            0x7A => self.gs.round_state.mode = RoundMode::Off,
+            // RUTG
+            0x7C => self.gs.round_state.mode = RoundMode::UpToGrid,
+            // RDTG
+            0x7D => self.gs.round_state.mode = RoundMode::DownToGrid,
+            // SANGW, AA
+            0x7E | 0x7F => {}
+            // FLIPPT
+            0x80 => self.op_flippt(),
+            // FLIPRGON, FLIPRGOFF
+            0x81 | 0x82 => {
+                let start = usize::from(self.arg(0) as u16);
+                let end = usize::from(self.arg(1) as u16);
+                if start < self.glyph.len() && end < self.glyph.len() {
+                    for flags in self.glyph.flags.iter_mut().take(end + 1).skip(start) {
+                        if opcode == 0x81 {
+                            *flags |= ON_CURVE;
+                        } else {
+                            *flags &= !ON_CURVE;
+                        }
+                    }
+                }
+            }
+            // SCANCTRL
+            0x85 => self.op_scanctrl(),
+            // SDPVTL
+            0x86 | 0x87 => self.op_sdpvtl(),
+            // GETINFO
+            0x88 => {
+                let selector = self.arg(0);
+                let mut v = 0;
+                if selector & 1 != 0 {
+                    v = INTERPRETER_VERSION;
+                }
+
+                if selector & 8 != 0 && self.metrics.is_variable {
+                    v |= 1 << 10;
+                }
+
+                // Grayscale rendering.
+                if selector & 32 != 0 {
+                    v |= 1 << 12;
+                }
+
+                self.set_arg(0, v);
+            }
+            // IDEF
+            0x89 => {
+                if self.initial_program == ProgramKind::Glyph {
+                    return Err(Error::DefinitionInGlyphProgram);
+                }
+
+                let n = u8::try_from(self.arg(0)).map_err(|_| Error::TooManyDefinitions)?;
+                let definition = self.skip_definition()?;
+                if self.state.instructions.is_empty() {
+                    self.state.instructions.resize(256, None);
+                }
+
+                self.state.instructions[usize::from(n)] = Some(definition);
+            }
+            // ROLL
+            0x8A => {
+                let (a, b, c) = (self.arg(2), self.arg(1), self.arg(0));
+                self.set_arg(2, c);
+                self.set_arg(1, a);
+                self.set_arg(0, b);
+            }
+            // MAX
+            0x8B => self.set_arg(0, self.arg(0).max(self.arg(1))),
+            // MIN
+            0x8C => self.set_arg(0, self.arg(0).min(self.arg(1))),
+            // SCANTYPE
+            0x8D => {
+                if self.arg(0) >= 0 {
+                    self.gs.scan_type = self.arg(0) & 0xFFFF;
+                }
+            }
+            // INSTCTRL
+            0x8E => {
+                let selector = self.arg(1);
+                if !(1..=3).contains(&selector) {
+                    return Ok(());
+                }
+
+                // Allowed only in the control value program.
+                if self.initial_program != ProgramKind::ControlValue {
+                    return Ok(());
+                }
+
+                let mask = 1u8 << (selector - 1);
+                self.gs.instruct_control &= !mask;
+                if self.arg(0) != 0 {
+                    self.gs.instruct_control |= mask;
+                }
+            }
+            // GETVARIATION
+            0x91 if self.metrics.is_variable => {
+                let count = self.metrics.coords.len();
+                if self.new_top + count > self.stack.len() {
+                    return Err(Error::StackOverflow);
+                }
+
+                for i in 0..count {
+                    self.stack[self.args + i] = i32::from(self.metrics.coords[i]);
+                }
+
+                self.new_top += count;
+            }
+            // GETDATA
+            0x92 if self.metrics.is_variable => self.set_arg(0, 17),
+            // MDRP
+            0xC0..=0xDF => self.op_mdrp(),
+            // MIRP
+            0xE0..=0xFF => self.op_mirp(),
+            _ => self.op_unknown()?,
+        }
+
+        Ok(())
+    }
+
+    /// Calls an instruction definition for unknown opcodes.
+    fn op_unknown(&mut self) -> Result<(), Error> {
+        let definition = self
+            .state
+            .instructions
+            .get(usize::from(self.opcode))
+            .copied()
+            .flatten()
+            .ok_or(Error::InvalidOpcode)?;
+
+        if self.call_stack.len() >= MAX_CALL_DEPTH {
+            return Err(Error::StackOverflow);
+        }
+
+        self.call(definition, 1);
+        Ok(())
+    }
+
+    fn function(&self, n: i32) -> Result<Definition, Error> {
+        usize::try_from(n)
+            .ok()
+            .and_then(|n| self.state.functions.get(n).copied().flatten())
+            .ok_or(Error::InvalidReference)
+    }
+
+    fn call(&mut self, definition: Definition, count: i32) {
+        self.call_stack.push(CallRecord {
+            caller_program: self.program,
+            caller_ip: self.ip + 1,
+            count,
+            definition,
+        });
+
+        self.program = definition.program;
+        self.ip = definition.start;
+        self.step_ip = false;
+    }
+
+    fn jump(&mut self, offset: i32) -> Result<(), Error> {
+        // An infinite loop.
+        if offset == 0 && self.args == 0 {
+            return Err(Error::BadArgument);
+        }
+
+        let ip = self.ip as i64 + i64::from(offset);
+        if ip < 0 {
+            return Err(Error::BadArgument);
+        }
+
+        if let Some(record) = self.call_stack.last() {
+            if ip > record.definition.end as i64 {
+                return Err(Error::BadArgument);
+            }
+        }
+
+        self.ip = ip as usize;
+        self.step_ip = false;
+        Ok(())
+    }
+
+    /// Moves to the next instruction, without executing the current one.
+    ///
+    /// Returns the new opcode.
+    fn skip_instruction(&mut self) -> Result<u8, Error> {
+        self.ip += self.opcode_len;
+        let code = self.code();
+        if self.ip < code.len() {
+            if let Some(len) = instruction_len(code, self.ip) {
+                self.opcode = code[self.ip];
+                self.opcode_len = len;
+                return Ok(self.opcode);
+            }
+        }
+
+        Err(Error::CodeOverflow)
+    }
+
+    /// Skips until the matching `ENDF`.
+    fn skip_definition(&mut self) -> Result<Definition, Error> {
+        let start = self.ip + 1;
+        loop {
+            match self.skip_instruction()? {
+                // IDEF, FDEF
+                0x89 | 0x2C => return Err(Error::NestedDefinitions),
+                // ENDF
+                0x2D => {
+                    return Ok(Definition {
+                        program: self.program,
+                        start,
+                        end: self.ip,
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Skips until the matching `ELSE` or `EIF`.
+    fn op_if(&mut self) -> Result<(), Error> {
+        let mut depth = 1;
+        loop {
+            match self.skip_instruction()? {
+                // IF
+                0x58 => depth += 1,
+                // ELSE
+                0x1B if depth == 1 => return Ok(()),
+                // EIF
+                0x59 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Skips until the matching `EIF`.
+    fn op_else(&mut self) -> Result<(), Error> {
+        let mut depth = 1;
+        loop {
+            match self.skip_instruction()? {
+                // IF
+                0x58 => depth += 1,
+                // EIF
+                0x59 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn op_push(&mut self) -> Result<(), Error> {
+        let code = self.code();
+        let (count, is_word, data_start) = match self.opcode {
+            0x40 => (usize::from(code[self.ip + 1]), false, self.ip + 2),
+            0x41 => (usize::from(code[self.ip + 1]), true, self.ip + 2),
+            0xB0..=0xB7 => (usize::from(self.opcode - 0xB0) + 1, false, self.ip + 1),
+            _ => (usize::from(self.opcode - 0xB8) + 1, true, self.ip + 1),
+        };
+
+        if self.opcode == 0x40 || self.opcode == 0x41 {
+            // `NPUSH*` stack effect is not known in advance.
+            if self.new_top + count > self.stack.len() {
+                return Err(Error::StackOverflow);
+            }
+
+            self.new_top += count;
+        }
+
+        for i in 0..count {
+            let v = if is_word {
+                let offset = data_start + i * 2;
+                i32::from(i16::from_be_bytes([code[offset], code[offset + 1]]))
+            } else {
+                i32::from(code[data_start + i])
+            };
+
+            self.stack[self.args + i] = v;
+        }
+
+        Ok(())
+    }
+
+    fn cvt(&self, index: i32) -> Option<F26Dot6> {
+        self.state.cvt.get(usize::try_from(index).ok()?).copied()
+    }
+
+    fn cvt_mut(&mut self, index: i32) -> Option<&mut F26Dot6> {
+        self.state.cvt.get_mut(usize::try_from(index).ok()?)
+    }
+
+    /// Parses a line-based vector for `SPVTL` and `SFVTL`.
+    fn vector_to_line(&self) -> Option<UnitVector> {
+        let p2 = usize::from(self.arg(1) as u16);
+        let p1 = usize::from(self.arg(0) as u16);
+        if p2 >= self.zone_len(self.gs.zp2) || p1 >= self.zone_len(self.gs.zp1) {
+            return None;
+        }
+
+        let a = self.zone(self.gs.zp1).points[p1];
+        let b = self.zone(self.gs.zp2).points[p2];
+        line_vector(a, b, &mut (self.opcode & 1 != 0))
+    }
+
+    fn op_sdpvtl(&mut self) {
+        let p2 = usize::from(self.arg(1) as u16);
+        let p1 = usize::from(self.arg(0) as u16);
+        if p1 >= self.zone_len(self.gs.zp1) || p2 >= self.zone_len(self.gs.zp2) {
+            return;
+        }
+
+        // Like in FreeType, coincident original points disable the rotation
+        // for the projection vector as well.
+        let mut perpendicular = self.opcode & 1 != 0;
+
+        let a = self.zone(self.gs.zp1).original[p1];
+        let b = self.zone(self.gs.zp2).original[p2];
+        if let Some(v) = line_vector(a, b, &mut perpendicular) {
+            self.gs.dual_vector = v;
+        }
+
+        let a = self.zone(self.gs.zp1).points[p1];
+        let b = self.zone(self.gs.zp2).points[p2];
+        if let Some(v) = line_vector(a, b, &mut perpendicular) {
+            self.gs.proj_vector = v;
+        }
+    }
+
+    fn op_isect(&mut self) {
+        let point = usize::from(self.arg(0) as u16);
+        let a0 = usize::from(self.arg(1) as u16);
+        let a1 = usize::from(self.arg(2) as u16);
+        let b0 = usize::from(self.arg(3) as u16);
+        let b1 = usize::from(self.arg(4) as u16);
+
+        let zp0_len = self.zone_len(self.gs.zp0);
+        let zp1_len = self.zone_len(self.gs.zp1);
+        if b0 >= zp0_len
+            || b1 >= zp0_len
+            || a0 >= zp1_len
+            || a1 >= zp1_len
+            || point >= self.zone_len(self.gs.zp2)
+        {
+            return;
+        }
+
+        let zp0 = self.zone(self.gs.zp0);
+        let zp1 = self.zone(self.gs.zp1);
+        let (pa0, pa1) = (zp1.points[a0], zp1.points[a1]);
+        let (pb0, pb1) = (zp0.points[b0], zp0.points[b1]);
+
+        let dbx = pb1.x.wrapping_sub(pb0.x);
+        let dby = pb1.y.wrapping_sub(pb0.y);
+        let dax = pa1.x.wrapping_sub(pa0.x);
+        let day = pa1.y.wrapping_sub(pa0.y);
+        let dx = pb0.x.wrapping_sub(pa0.x);
+        let dy = pb0.y.wrapping_sub(pa0.y);
+
+        let discriminant = math::mul_div(dax, dby.wrapping_neg(), 0x40)
+            .wrapping_add(math::mul_div(day, dbx, 0x40));
+        let dot_product = math::mul_div(dax, dbx, 0x40).wrapping_add(math::mul_div(day, dby, 0x40));
+
+        // Reject grazing intersections, i.e. when the angle between lines is less than 3 degrees.
+        let result = if 19 * i64::from(discriminant).abs() > i64::from(dot_product).abs() {
+            let v = math::mul_div(dx, dby.wrapping_neg(), 0x40)
+                .wrapping_add(math::mul_div(dy, dbx, 0x40));
+            Point::new(
+                pa0.x.wrapping_add(math::mul_div(v, dax, discriminant)),
+                pa0.y.wrapping_add(math::mul_div(v, day, discriminant)),
+            )
+        } else {
+            // Take the middle of the middles.
+            let sum = |a: i32, b: i32, c: i32, d: i32| {
+                ((i64::from(a) + i64::from(b) + i64::from(c) + i64::from(d)) / 4) as i32
+            };
+            Point::new(
+                sum(pa0.x, pa1.x, pb0.x, pb1.x),
+                sum(pa0.y, pa1.y, pb0.y, pb1.y),
+            )
+        };
+
+        let zp2 = self.zone_mut(self.gs.zp2);
+        zp2.points[point] = result;
+        zp2.flags[point] |= TOUCHED_BOTH;
+    }
+
+    /// Returns the displacement of the reference point used by `SHP`, `SHC` and `SHZ`.
+    fn point_displacement(&self) -> Option<(F26Dot6, F26Dot6, ZoneId, usize)> {
+        let (zone, point) = if self.opcode & 1 != 0 {
+            (self.gs.zp0, self.gs.rp1)
+        } else {
+            (self.gs.zp1, self.gs.rp2)
+        };
+
+        let z = self.zone(zone);
+        if point >= z.len() {
+            return None;
+        }
+
+        let d = self.project(z.points[point], z.original[point]);
+        let f_dot_p = self.gs.f_dot_p();
+        let dx = math::mul_div(d, self.gs.free_vector.x, f_dot_p);
+        let dy = math::mul_div(d, self.gs.free_vector.y, f_dot_p);
+        Some((dx, dy, zone, point))
+    }
+
+    /// Pops the next point for looping instructions.
+    #[inline]
+    fn pop_loop_point(&mut self) -> usize {
+        self.args -= 1;
+        usize::from(self.stack[self.args] as u16)
+    }
+
+    fn finish_loop(&mut self) {
+        self.gs.loop_counter = 1;
+        self.new_top = self.args;
+    }
+
+    fn op_shp(&mut self) {
+        if self.args < self.gs.loop_counter as usize {
+            self.finish_loop();
+            return;
+        }
+
+        let (dx, dy, _, _) = match self.point_displacement() {
+            Some(v) => v,
+            None => return,
+        };
+
+        while self.gs.loop_counter > 0 {
+            let point = self.pop_loop_point();
+            if point < self.zone_len(self.gs.zp2) {
+                self.move_zp2_point(point, dx, dy, true);
+            }
+
+            self.gs.loop_counter -= 1;
+        }
+
+        self.finish_loop();
+    }
+
+    fn op_shc(&mut self) {
+        let contour = usize::from(self.arg(0) as u16);
+        let is_twilight = self.gs.zp2 == ZoneId::Twilight;
+        let contours_count = if is_twilight {
+            1
+        } else {
+            self.zone(self.gs.zp2).contours.len()
+        };
+
+        if contour >= contours_count {
+            return;
+        }
+
+        let (dx, dy, ref_zone, ref_point) = match self.point_displacement() {
+            Some(v) => v,
+            None => return,
+        };
+
+        let zp2 = self.zone(self.gs.zp2);
+        let start = if contour == 0 {
+            0
+        } else {
+            usize::from(zp2.contours[contour - 1]) + 1
+        };
+
+        let end = if is_twilight {
+            zp2.len()
+        } else {
+            (usize::from(zp2.contours[contour]) + 1).min(zp2.len())
+        };
+
+        for i in start..end {
+            if ref_zone != self.gs.zp2 || ref_point != i {
+                self.move_zp2_point(i, dx, dy, true);
+            }
+        }
+    }
+
+    fn op_shz(&mut self) {
+        if !(0..2).contains(&self.arg(0)) {
+            return;
+        }
+
+        let (dx, dy, ref_zone, ref_point) = match self.point_displacement() {
+            Some(v) => v,
+            None => return,
+        };
+
+        // Phantom points are not moved.
+        let zp2 = self.zone(self.gs.zp2);
+        let end = match self.gs.zp2 {
+            ZoneId::Twilight => zp2.len(),
+            ZoneId::Glyph => zp2
+                .contours
+                .last()
+                .map(|n| (usize::from(*n) + 1).min(zp2.len()))
+                .unwrap_or(0),
+        };
+
+        for i in 0..end {
+            if ref_zone != self.gs.zp2 || ref_point != i {
+                self.move_zp2_point(i, dx, dy, false);
+            }
+        }
+    }
+
+    fn op_shpix(&mut self) {
+        if self.top < self.gs.loop_counter as usize + 1 {
+            self.finish_loop();
+            return;
+        }
+
+        let amount = self.arg(0);
+        let dx = math::mul_fix14(amount, self.gs.free_vector.x);
+        let dy = math::mul_fix14(amount, self.gs.free_vector.y);
+        while self.gs.loop_counter > 0 {
+            let point = self.pop_loop_point();
+            if point < self.zone_len(self.gs.zp2) {
+                self.move_zp2_point(point, dx, dy, true);
+            }
+
+            self.gs.loop_counter -= 1;
+        }
+
+        self.finish_loop();
+    }
+
+    fn op_ip(&mut self) {
+        if self.top < self.gs.loop_counter as usize {
+            self.finish_loop();
+            return;
+        }
+
+        let (zp0, zp1, zp2) = (self.gs.zp0, self.gs.zp1, self.gs.zp2);
+        let (rp1, rp2) = (self.gs.rp1, self.gs.rp2);
+        // The twilight zone has no unscaled points, so the original ones are used instead.
+        let twilight =
+            zp0 == ZoneId::Twilight || zp1 == ZoneId::Twilight || zp2 == ZoneId::Twilight;
+        if rp1 >= self.zone_len(zp0) {
+            self.finish_loop();
+            return;
+        }
+
+        let unscaled = |engine: &Self, zone: ZoneId, point: usize| {
+            let z = engine.zone(zone);
+            if twilight {
+                z.original[point]
+            } else {
+                z.unscaled[point]
+            }
+        };
+
+        let base_unscaled = unscaled(self, zp0, rp1);
+        let base_point = self.zone(zp0).points[rp1];
+        let (old_range, cur_range) = if rp2 >= self.zone_len(zp1) {
+            (0, 0)
+        } else {
+            (
+                self.dual_project(unscaled(self, zp1, rp2), base_unscaled),
+                self.project(self.zone(zp1).points[rp2], base_point),
+            )
+        };
+
+        while self.gs.loop_counter > 0 {
+            let point = self.pop_loop_point();
+            self.gs.loop_counter -= 1;
+            if point >= self.zone_len(zp2) {
+                continue;
+            }
+
+            let original_distance = self.dual_project(unscaled(self, zp2, point), base_unscaled);
+            let current_distance = self.project(self.zone(zp2).points[point], base_point);
+            let new_distance = if original_distance == 0 {
+                0
+            } else if old_range != 0 {
+                math::mul_div(original_distance, cur_range, old_range)
+            } else {
+                original_distance
+            };
+
+            self.move_point(zp2, point, new_distance.wrapping_sub(current_distance));
+        }
+
+        self.finish_loop();
+    }
+
+    fn op_msirp(&mut self) {
+        let point = usize::from(self.arg(0) as u16);
+        let distance = self.arg(1);
+        let (zp0, zp1, rp0) = (self.gs.zp0, self.gs.zp1, self.gs.rp0);
+        if point >= self.zone_len(zp1) || rp0 >= self.zone_len(zp0) {
+            return;
+        }
+
+        if zp1 == ZoneId::Twilight {
+            let origin = self.zone(zp0).original[rp0];
+            self.zone_mut(zp1).original[point] = origin;
+            self.move_original(zp1, point, distance);
+            let z = self.zone_mut(zp1);
+            z.points[point] = z.original[point];
+        }
+
+        let current = self.project(self.zone(zp1).points[point], self.zone(zp0).points[rp0]);
+        self.move_point(zp1, point, distance.wrapping_sub(current));
+        self.gs.rp1 = rp0;
+        self.gs.rp2 = point;
+        if self.opcode & 1 != 0 {
+            self.gs.rp0 = point;
+        }
+    }
+
+    fn op_alignrp(&mut self) {
+        let (zp0, zp1, rp0) = (self.gs.zp0, self.gs.zp1, self.gs.rp0);
+        if self.top < self.gs.loop_counter as usize || rp0 >= self.zone_len(zp0) {
+            self.finish_loop();
+            return;
+        }
+
+        while self.gs.loop_counter > 0 {
+            let point = self.pop_loop_point();
+            self.gs.loop_counter -= 1;
+            if point >= self.zone_len(zp1) {
+                continue;
+            }
+
+            let distance = self.project(self.zone(zp1).points[point], self.zone(zp0).points[rp0]);
+            self.move_point(zp1, point, distance.wrapping_neg());
+        }
+
+        self.finish_loop();
+    }
+
+    fn op_miap(&mut self) {
+        let point = usize::from(self.arg(0) as u16);
+        let zp0 = self.gs.zp0;
+        let cvt = self.cvt(self.arg(1));
+        if let (true, Some(mut distance)) = (point < self.zone_len(zp0), cvt) {
+            if zp0 == ZoneId::Twilight {
+                let p = Point::new(
+                    math::mul_fix14(distance, self.gs.free_vector.x),
+                    math::mul_fix14(distance, self.gs.free_vector.y),
+                );
+                let z = self.zone_mut(zp0);
+                z.original[point] = p;
+                z.points[point] = p;
+            }
+
+            let original_distance = self.fast_project(self.zone(zp0).points[point]);
+            if self.opcode & 1 != 0 {
+                if distance.wrapping_sub(original_distance).wrapping_abs()
+                    > self.gs.control_value_cutin
+                {
+                    distance = original_distance;
+                }
+
+                distance = self.round(distance);
+            }
+
+            self.move_point(zp0, point, distance.wrapping_sub(original_distance));
+        }
+
+        self.gs.rp0 = point;
+        self.gs.rp1 = point;
+    }
+
+    fn op_md(&self) -> F26Dot6 {
+        let l = usize::from(self.arg(0) as u16);
+        let k = usize::from(self.arg(1) as u16);
+        let (zp0, zp1) = (self.zone(self.gs.zp0), self.zone(self.gs.zp1));
+        if l >= zp0.len() || k >= zp1.len() {
+            return 0;
+        }
+
+        if self.opcode & 1 != 0 {
+            self.project(zp0.points[l], zp1.points[k])
+        } else if self.gs.zp0 == ZoneId::Twilight || self.gs.zp1 == ZoneId::Twilight {
+            self.dual_project(zp0.original[l], zp1.original[k])
+        } else {
+            let d = self.dual_project(zp0.unscaled[l], zp1.unscaled[k]);
+            math::mul_fix(d, self.points_scale)
+        }
+    }
+
+    fn op_mdrp(&mut self) {
+        let point = usize::from(self.arg(0) as u16);
+        let (zp0, zp1, rp0) = (self.gs.zp0, self.gs.zp1, self.gs.rp0);
+        if point < self.zone_len(zp1) && rp0 < self.zone_len(zp0) {
+            let mut original_distance = if zp0 == ZoneId::Twilight || zp1 == ZoneId::Twilight {
+                self.dual_project(self.zone(zp1).original[point], self.zone(zp0).original[rp0])
+            } else {
+                let d =
+                    self.dual_project(self.zone(zp1).unscaled[point], self.zone(zp0).unscaled[rp0]);
+                math::mul_fix(d, self.points_scale)
+            };
+
+            // Single width cut-in test.
+            let sw_value = self.gs.single_width_value;
+            let sw_cutin = self.gs.single_width_cutin;
+            if sw_cutin > 0
+                && original_distance < sw_value.wrapping_add(sw_cutin)
+                && original_distance > sw_value.wrapping_sub(sw_cutin)
+            {
+                original_distance = if original_distance >= 0 {
+                    sw_value
+                } else {
+                    sw_value.wrapping_neg()
+                };
+            }
+
+            let mut distance = if self.opcode & 4 != 0 {
+                self.round(original_distance)
+            } else {
+                original_distance
+            };
+
+            distance = self.apply_min_distance(original_distance, distance);
+
+            let current = self.project(self.zone(zp1).points[point], self.zone(zp0).points[rp0]);
+            self.move_point(zp1, point, distance.wrapping_sub(current));
+        }
+
+        self.gs.rp1 = rp0;
+        self.gs.rp2 = point;
+        if self.opcode & 16 != 0 {
+            self.gs.rp0 = point;
+        }
+    }
+
+    fn op_mirp(&mut self) {
+        let point = usize::from(self.arg(0) as u16);
+        // CVT entry -1 is always zero.
+        let cvt_index = i64::from(self.arg(1)) + 1;
+        let (zp0, zp1, rp0) = (self.gs.zp0, self.gs.zp1, self.gs.rp0);
+        let cvt_in_bounds = cvt_index >= 0 && cvt_index <= self.state.cvt.len() as i64;
+        if point < self.zone_len(zp1) && cvt_in_bounds && rp0 < self.zone_len(zp0) {
+            let mut cvt_distance = if cvt_index == 0 {
+                0
+            } else {
+                self.state.cvt[cvt_index as usize - 1]
+            };
+
+            // Single width test.
+            let sw_value = self.gs.single_width_value;
+            if cvt_distance.wrapping_sub(sw_value).wrapping_abs() < self.gs.single_width_cutin {
+                cvt_distance = if cvt_distance >= 0 {
+                    sw_value
+                } else {
+                    sw_value.wrapping_neg()
+                };
+            }
+
+            if zp1 == ZoneId::Twilight {
+                let origin = self.zone(zp0).original[rp0];
+                let p = Point::new(
+                    origin
+                        .x
+                        .wrapping_add(math::mul_fix14(cvt_distance, self.gs.free_vector.x)),
+                    origin
+                        .y
+                        .wrapping_add(math::mul_fix14(cvt_distance, self.gs.free_vector.y)),
+                );
+                let z = self.zone_mut(zp1);
+                z.original[point] = p;
+                z.points[point] = p;
+            }
+
+            let original_distance =
+                self.dual_project(self.zone(zp1).original[point], self.zone(zp0).original[rp0]);
+            let current_distance =
+                self.project(self.zone(zp1).points[point], self.zone(zp0).points[rp0]);
+
+            // Auto-flip test.
+            if self.gs.auto_flip && (original_distance ^ cvt_distance) < 0 {
+                cvt_distance = cvt_distance.wrapping_neg();
+            }
+
+            let mut distance = if self.opcode & 4 != 0 {
+                // Cut-in test is performed only when both points are in the same zone.
+                if zp0 == zp1
+                    && cvt_distance.wrapping_sub(original_distance).wrapping_abs()
+                        > self.gs.control_value_cutin
+                {
+                    cvt_distance = original_distance;
+                }
+
+                self.round(cvt_distance)
+            } else {
+                cvt_distance
+            };
+
+            distance = self.apply_min_distance(original_distance, distance);
+            self.move_point(zp1, point, distance.wrapping_sub(current_distance));
+        }
+
+        self.gs.rp1 = rp0;
+        if self.opcode & 16 != 0 {
+            self.gs.rp0 = point;
+        }
+
+        self.gs.rp2 = point;
+    }
+
+    fn apply_min_distance(&self, original_distance: F26Dot6, distance: F26Dot6) -> F26Dot6 {
+        if self.opcode & 8 == 0 {
+            return distance;
+        }
+
+        let min_distance = self.gs.min_distance;
+        if original_distance >= 0 {
+            distance.max(min_distance)
+        } else {
+            distance.min(min_distance.wrapping_neg())
+        }
+    }
+
+    /// Returns a delta for `DELTAP*` and `DELTAC*` if it applies to the current ppem.
+    fn delta_value(&self, range: i32, arg: i32) -> Option<F26Dot6> {
+        let ppem = ((arg as u32 & 0xF0) >> 4) as i32 + range + i32::from(self.gs.delta_base);
+        if ppem != self.metrics.ppem {
+            return None;
+        }
+
+        let mut step = (arg & 0xF) - 8;
+        if step >= 0 {
+            step += 1;
+        }
+
+        Some(step * (1 << (6 - self.gs.delta_shift.min(6))))
+    }
+
+    fn op_deltap(&mut self) {
+        let range = match self.opcode {
+            0x5D => 0,
+            0x71 => 16,
+            _ => 32,
+        };
+
+        let count = self.arg(0) as u32;
+        let zp0 = self.gs.zp0;
+        for _ in 0..count {
+            if self.args < 2 {
+                self.args = 0;
+                break;
+            }
+
+            self.args -= 2;
+            let point = usize::from(self.stack[self.args + 1] as u16);
+            let arg = self.stack[self.args];
+            // Invalid points are ignored.
+            if point < self.zone_len(zp0) {
+                if let Some(delta) = self.delta_value(range, arg) {
+                    self.move_point(zp0, point, delta);
+                }
+            }
+        }
+
+        self.new_top = self.args;
+    }
+
+    fn op_deltac(&mut self) {
+        let range = match self.opcode {
+            0x73 => 0,
+            0x74 => 16,
+            _ => 32,
+        };
+
+        let count = self.arg(0) as u32;
+        for _ in 0..count {
+            if self.args < 2 {
+                self.args = 0;
+                break;
+            }
+
+            self.args -= 2;
+            let index = self.stack[self.args + 1];
+            let arg = self.stack[self.args];
+            if let Some(delta) = self.delta_value(range, arg) {
+                if let Some(v) = self.cvt_mut(index) {
+                    *v = v.wrapping_add(delta);
+                }
+            }
+        }
+
+        self.new_top = self.args;
+    }
+
+    fn op_flippt(&mut self) {
+        if self.top < self.gs.loop_counter as usize {
+            self.finish_loop();
+            return;
+        }
+
+        while self.gs.loop_counter > 0 {
+            let point = self.pop_loop_point();
+            // Always applies to the glyph zone.
+            if let Some(flags) = self.glyph.flags.get_mut(point) {
+                *flags ^= ON_CURVE;
+            }
+
+            self.gs.loop_counter -= 1;
+        }
+
+        self.finish_loop();
+    }
+
+    fn op_scanctrl(&mut self) {
+        let arg = self.arg(0);
+        let threshold = arg & 0xFF;
+        if threshold == 0xFF {
+            self.gs.scan_control = true;
+            return;
+        } else if threshold == 0 {
+            self.gs.scan_control = false;
+            return;
+        }
+
+        // Glyphs are never rotated or stretched.
+        if arg & 0x100 != 0 && self.metrics.ppem <= threshold {
+            self.gs.scan_control = true;
+        }
+
+        if arg & 0x800 != 0 && self.metrics.ppem > threshold {
+            self.gs.scan_control = false;
+        }
+    }
+}
+
+#[inline]
+fn axis_vector(opcode: u8) -> UnitVector {
+    if opcode & 1 != 0 {
+        UnitVector::X_AXIS
+    } else {
+        UnitVector::Y_AXIS
+    }
+}
+
+/// Returns a unit vector from `b` to `a`, optionally rotated by 90 degrees counter-clockwise.
+///
+/// Coincident points produce the X-axis and reset `perpendicular`.
+fn line_vector(a: Point, b: Point, perpendicular: &mut bool) -> Option<UnitVector> {
+    let mut dx = a.x.wrapping_sub(b.x);
+    let mut dy = a.y.wrapping_sub(b.y);
+    if dx == 0 && dy == 0 {
+        dx = 0x4000;
+        *perpendicular = false;
+    }
+
+    if *perpendicular {
+        let c = dy;
+        dy = dx;
+        dx = c.wrapping_neg();
+    }
+
+    math::normalize(dx, dy)
+}
+
+/// Returns the instruction length, including inline data.
+fn instruction_len(code: &[u8], ip: usize) -> Option<usize> {
+    let opcode = *code.get(ip)?;
+    let len = match opcode {
+        // NPUSHB
+        0x40 => 2 + usize::from(*code.get(ip + 1)?),
+        // NPUSHW
+        0x41 => 2 + usize::from(*code.get(ip + 1)?) * 2,
+        // PUSHB
+        0xB0..=0xB7 => 2 + usize::from(opcode - 0xB0),
+        // PUSHW
+        0xB8..=0xBF => 3 + usize::from(opcode - 0xB8) * 2,
+        _ => 1,
+    };
+
+    if ip + len <= code.len() {
+        Some(len)
+    } else {
+        None
+    }
+}
+
+/// Returns the number of values popped and pushed by an instruction.
+///
+/// Instructions that use the loop counter or have a variable number of
+/// arguments manipulate the stack themselves.
+fn stack_effect(opcode: u8) -> (usize, usize) {
+    match opcode {
+        0x00..=0x05 => (0, 0),
+        0x06..=0x0B => (2, 0),
+        0x0C | 0x0D => (0, 2),
+        0x0E => (0, 0),
+        0x0F => (5, 0),
+        0x10..=0x17 => (1, 0),
+        0x18 | 0x19 => (0, 0),
+        0x1A => (1, 0),
+        0x1B => (0, 0),
+        0x1C..=0x1F => (1, 0),
+        0x20 => (1, 2),
+        0x21 => (1, 0),
+        0x22 => (0, 0),
+        0x23 => (2, 2),
+        0x24 => (0, 1),
+        0x25 => (1, 1),
+        0x26 => (1, 0),
+        0x27 => (2, 0),
+        0x28 => (0, 0),
+        0x29 => (1, 0),
+        0x2A => (2, 0),
+        0x2B | 0x2C => (1, 0),
+        0x2D => (0, 0),
+        0x2E | 0x2F => (1, 0),
+        0x30..=0x33 => (0, 0),
+        0x34..=0x38 => (1, 0),
+        0x39 => (0, 0),
+        0x3A | 0x3B => (2, 0),
+        0x3C | 0x3D => (0, 0),
+        0x3E | 0x3F => (2, 0),
+        0x40 | 0x41 => (0, 0),
+        0x42 => (2, 0),
+        0x43 => (1, 1),
+        0x44 => (2, 0),
+        0x45..=0x47 => (1, 1),
+        0x48 => (2, 0),
+        0x49 | 0x4A => (2, 1),
+        0x4B | 0x4C => (0, 1),
+        0x4D | 0x4E => (0, 0),
+        0x4F => (1, 0),
+        0x50..=0x55 => (2, 1),
+        0x56 | 0x57 => (1, 1),
+        0x58 => (1, 0),
+        0x59 => (0, 0),
+        0x5A | 0x5B => (2, 1),
+        0x5C => (1, 1),
+        0x5D..=0x5F => (1, 0),
+        0x60..=0x63 => (2, 1),
+        0x64..=0x6F => (1, 1),
+        0x70 => (2, 0),
+        0x71..=0x77 => (1, 0),
+        0x78 | 0x79 => (2, 0),
+        0x7A..=0x7D => (0, 0),
+        0x7E | 0x7F => (1, 0),
+        0x80 => (0, 0),
+        0x81 | 0x82 => (2, 0),
+        0x83 | 0x84 => (0, 0),
+        0x85 => (1, 0),
+        0x86 | 0x87 => (2, 0),
+        0x88 => (1, 1),
+        0x89 => (1, 0),
+        0x8A => (3, 3),
+        0x8B | 0x8C => (2, 1),
+        0x8D => (1, 0),
+        0x8E => (2, 0),
+        0x92 => (0, 1),
+        0xB0..=0xB7 => (0, usize::from(opcode - 0xB0) + 1),
+        0xB8..=0xBF => (0, usize::from(opcode - 0xB8) + 1),
+        0xC0..=0xDF => (1, 0),
+        0xE0..=0xFF => (2, 0),
+        _ => (0, 0),
+    }
+}
@@ -0,0 +1,264 @@
+use super::math::{self, F26Dot6, UnitVector};
+use super::zone::ZoneId;
+
+/// A rounding mode.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum RoundMode {
+    ToHalfGrid,
+    ToGrid,
+    ToDoubleGrid,
+    DownToGrid,
+    UpToGrid,
+    Off,
+    Super,
+    Super45,
+}
+
+/// The super rounding parameters.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RoundState {
+    pub mode: RoundMode,
+    pub period: F26Dot6,
+    pub phase: F26Dot6,
+    pub threshold: F26Dot6,
+}
+
+impl Default for RoundState {
+    fn default() -> Self {
+        RoundState {
+            mode: RoundMode::ToGrid,
+            period: 64,
+            phase: 0,
+            threshold: 0,
+        }
+    }
+}
+
+impl RoundState {
+    /// Parses the `SROUND` and `S45ROUND` argument.
+    pub fn set_super(&mut self, grid_period: i32, selector: i32) {
+        let period = match selector & 0xC0 {
+            0x00 => grid_period / 2,
+            0x80 => grid_period * 2,
+            _ => grid_period,
+        };
+
+        let phase = match selector & 0x30 {
+            0x00 => 0,
+            0x10 => period / 4,
+            0x20 => period / 2,
+            _ => period * 3 / 4,
+        };
+
+        let threshold = if selector & 0x0F == 0 {
+            period - 1
+        } else {
+            ((selector & 0x0F) - 4) * period / 8
+        };
+
+        // Convert to 26.6.
+        self.period = period >> 8;
+        self.phase = phase >> 8;
+        self.threshold = threshold >> 8;
+    }
+
+    pub fn round(&self, distance: F26Dot6) -> F26Dot6 {
+        match self.mode {
+            RoundMode::ToHalfGrid => {
+                if distance >= 0 {
+                    let v = math::floor(distance).wrapping_add(32);
+                    if v < 0 {
+                        32
+                    } else {
+                        v
+                    }
+                } else {
+                    let v = math::floor(distance.wrapping_neg())
+                        .wrapping_add(32)
+                        .wrapping_neg();
+                    if v > 0 {
+                        -32
+                    } else {
+                        v
+                    }
+                }
+            }
+            RoundMode::ToGrid => round_with(distance, math::round),
+            RoundMode::ToDoubleGrid => round_with(distance, |d| d.wrapping_add(16) & -32),
+            RoundMode::DownToGrid => round_with(distance, math::floor),
+            RoundMode::UpToGrid => round_with(distance, math::ceil),
+            RoundMode::Off => distance,
+            RoundMode::Super => {
+                if distance >= 0 {
+                    let v = distance
+                        .wrapping_sub(self.phase)
+                        .wrapping_add(self.threshold)
+                        & -self.period;
+                    let v = v.wrapping_add(self.phase);
+                    if v < 0 {
+                        self.phase
+                    } else {
+                        v
+                    }
+                } else {
+                    let v = (self
+                        .threshold
+                        .wrapping_sub(self.phase)
+                        .wrapping_sub(distance)
+                        & -self.period)
+                        .wrapping_neg();
+                    let v = v.wrapping_sub(self.phase);
+                    if v > 0 {
+                        -self.phase
+                    } else {
+                        v
+                    }
+                }
+            }
+            RoundMode::Super45 => {
+                if self.period == 0 {
+                    return distance;
+                }
+
+                if distance >= 0 {
+                    let v = distance
+                        .wrapping_sub(self.phase)
+                        .wrapping_add(self.threshold)
+                        / self.period
+                        * self.period;
+                    let v = v.wrapping_add(self.phase);
+                    if v < 0 {
+                        self.phase
+                    } else {
+                        v
+                    }
+                } else {
+                    let v = (self
+                        .threshold
+                        .wrapping_sub(self.phase)
+                        .wrapping_sub(distance)
+                        / self.period
+                        * self.period)
+                        .wrapping_neg();
+                    let v = v.wrapping_sub(self.phase);
+                    if v > 0 {
+                        -self.phase
+                    } else {
+                        v
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[inline]
+fn round_with(distance: F26Dot6, f: impl Fn(F26Dot6) -> F26Dot6) -> F26Dot6 {
+    if distance >= 0 {
+        f(distance).max(0)
+    } else {
+        f(distance.wrapping_neg()).wrapping_neg().min(0)
+    }
+}
+
+/// The interpreter graphics state.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct GraphicsState {
+    pub rp0: usize,
+    pub rp1: usize,
+    pub rp2: usize,
+    pub dual_vector: UnitVector,
+    pub proj_vector: UnitVector,
+    pub free_vector: UnitVector,
+    pub loop_counter: u32,
+    pub min_distance: F26Dot6,
+    pub round_state: RoundState,
+    pub auto_flip: bool,
+    pub control_value_cutin: F26Dot6,
+    pub single_width_cutin: F26Dot6,
+    pub single_width_value: F26Dot6,
+    pub delta_base: u16,
+    pub delta_shift: u16,
+    pub instruct_control: u8,
+    pub scan_control: bool,
+    pub scan_type: i32,
+    pub zp0: ZoneId,
+    pub zp1: ZoneId,
+    pub zp2: ZoneId,
+}
+
+impl Default for GraphicsState {
+    fn default() -> Self {
+        GraphicsState {
+            rp0: 0,
+            rp1: 0,
+            rp2: 0,
+            dual_vector: UnitVector::X_AXIS,
+            proj_vector: UnitVector::X_AXIS,
+            free_vector: UnitVector::X_AXIS,
+            loop_counter: 1,
+            min_distance: 64,
+            round_state: RoundState::default(),
+            auto_flip: true,
+            control_value_cutin: 68,
+            single_width_cutin: 0,
+            single_width_value: 0,
+            delta_base: 9,
+            delta_shift: 3,
+            instruct_control: 0,
+            scan_control: false,
+            scan_type: 0,
+            zp0: ZoneId::Glyph,
+            zp1: ZoneId::Glyph,
+            zp2: ZoneId::Glyph,
+        }
+    }
+}
+
+impl GraphicsState {
+    /// Resets the state that the control value program is not allowed to modify.
+    pub fn reset_after_prep(&mut self) {
+        self.dual_vector = UnitVector::X_AXIS;
+        self.proj_vector = UnitVector::X_AXIS;
+        self.free_vector = UnitVector::X_AXIS;
+        self.rp0 = 0;
+        self.rp1 = 0;
+        self.rp2 = 0;
+        self.zp0 = ZoneId::Glyph;
+        self.zp1 = ZoneId::Glyph;
+        self.zp2 = ZoneId::Glyph;
+        self.loop_counter = 1;
+    }
+
+    /// Resets the state before running a glyph program.
+    pub fn reset_for_glyph(&mut self) {
+        self.zp0 = ZoneId::Glyph;
+        self.zp1 = ZoneId::Glyph;
+        self.zp2 = ZoneId::Glyph;
+        self.proj_vector = UnitVector::X_AXIS;
+        self.free_vector = UnitVector::X_AXIS;
+        self.dual_vector = UnitVector::X_AXIS;
+        self.round_state.mode = RoundMode::ToGrid;
+        self.loop_counter = 1;
+    }
+
+    /// Returns a scaled projection of the freedom vector onto the projection vector.
+    pub fn f_dot_p(&self) -> i32 {
+        let fv = self.free_vector;
+        let pv = self.proj_vector;
+        let d = if fv.x == 0x4000 {
+            pv.x
+        } else if fv.y == 0x4000 {
+            pv.y
+        } else {
+            ((i64::from(pv.x) * i64::from(fv.x) + i64::from(pv.y) * i64::from(fv.y)) >> 14) as i32
+        };
+
+        // Almost perpendicular vectors.
+        if d.abs() < 0x400 {
+            0x4000
+        } else {
+            d
+        }
+    }
+}
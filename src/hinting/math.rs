@@ -0,0 +1,213 @@
+//! Fixed-point arithmetic used by the interpreter.
+//!
+//! All functions are symmetric around zero, i.e. rounding doesn't depend on the sign,
+//! which is what the reference implementations do.
+
+/// A 26.6 fixed-point value.
+pub(crate) type F26Dot6 = i32;
+
+/// A vector in the 2.14 fixed-point format.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct UnitVector {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl UnitVector {
+    pub const X_AXIS: Self = UnitVector { x: 0x4000, y: 0 };
+    pub const Y_AXIS: Self = UnitVector { x: 0, y: 0x4000 };
+}
+
+/// A point in the 26.6 fixed-point format.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub(crate) struct Point {
+    pub x: F26Dot6,
+    pub y: F26Dot6,
+}
+
+impl Point {
+    #[inline]
+    pub fn new(x: F26Dot6, y: F26Dot6) -> Self {
+        Point { x, y }
+    }
+}
+
+#[inline]
+fn saturate(n: i64) -> i32 {
+    n.max(i64::from(i32::MIN)).min(i64::from(i32::MAX)) as i32
+}
+
+/// Calculates `a * b / c` with rounding.
+#[inline]
+pub(crate) fn mul_div(a: i32, b: i32, c: i32) -> i32 {
+    if c == 0 {
+        return i32::MAX;
+    }
+
+    let negative = (a < 0) ^ (b < 0) ^ (c < 0);
+    let (a, b, c) = (i64::from(a).abs(), i64::from(b).abs(), i64::from(c).abs());
+    let d = (a * b + (c >> 1)) / c;
+    saturate(if negative { -d } else { d })
+}
+
+/// Calculates `a * b / c` without rounding.
+#[inline]
+pub(crate) fn mul_div_no_round(a: i32, b: i32, c: i32) -> i32 {
+    if c == 0 {
+        return i32::MAX;
+    }
+
+    let negative = (a < 0) ^ (b < 0) ^ (c < 0);
+    let (a, b, c) = (i64::from(a).abs(), i64::from(b).abs(), i64::from(c).abs());
+    let d = a * b / c;
+    saturate(if negative { -d } else { d })
+}
+
+/// Multiplies by a 16.16 value.
+#[inline]
+pub(crate) fn mul_fix(a: i32, b: i32) -> i32 {
+    let ab = i64::from(a) * i64::from(b);
+    saturate((ab + 0x8000 - i64::from(ab < 0)) >> 16)
+}
+
+/// Divides producing a 16.16 value.
+#[inline]
+pub(crate) fn div_fix(a: i32, b: i32) -> i32 {
+    mul_div(a, 0x10000, b)
+}
+
+/// Multiplies by a 2.14 value.
+#[inline]
+pub(crate) fn mul_fix14(a: i32, b: i32) -> i32 {
+    let ab = i64::from(a) * i64::from(b);
+    saturate((ab + 0x2000 - i64::from(ab < 0)) >> 14)
+}
+
+/// Calculates a dot product with a 2.14 vector.
+#[inline]
+pub(crate) fn dot_fix14(x: i32, y: i32, v: UnitVector) -> i32 {
+    let ab = i64::from(x) * i64::from(v.x) + i64::from(y) * i64::from(v.y);
+    saturate((ab + 0x2000 - i64::from(ab < 0)) >> 14)
+}
+
+/// Converts a vector into a 2.14 unit vector.
+///
+/// Uses the same integer approximation as FreeType, since even a one unit
+/// difference in a vector can move a point by a pixel fraction.
+///
+/// Returns `None` for a zero vector.
+pub(crate) fn normalize(x: i32, y: i32) -> Option<UnitVector> {
+    if x == 0 && y == 0 {
+        return None;
+    }
+
+    let (sx, sy) = (x < 0, y < 0);
+    let mut ux = x.unsigned_abs();
+    let mut uy = y.unsigned_abs();
+
+    let (nx, ny) = if ux == 0 {
+        (0, 0x10000)
+    } else if uy == 0 {
+        (0x10000, 0)
+    } else {
+        // Estimate the length and prenormalize, so the new approximate length
+        // is between 2/3 and 4/3 in 16.16.
+        let estimate = |x: u32, y: u32| {
+            if x > y {
+                x.wrapping_add(y >> 1)
+            } else {
+                y.wrapping_add(x >> 1)
+            }
+        };
+
+        let mut len = estimate(ux, uy);
+        let mut shift = len.leading_zeros() as i32;
+        shift -= 15 + i32::from(len >= (0xAAAA_AAAAu32 >> shift));
+        if shift > 0 {
+            ux <<= shift;
+            uy <<= shift;
+            len = estimate(ux, uy);
+        } else {
+            ux >>= -shift;
+            uy >>= -shift;
+            len >>= -shift;
+        }
+
+        // Newton's iterations for the reciprocal length.
+        let mut b = 0x10000 - len as i32;
+        let (x, y) = (ux as i32, uy as i32);
+        loop {
+            let u = x.wrapping_add(x.wrapping_mul(b) >> 16) as u32;
+            let v = y.wrapping_add(y.wrapping_mul(b) >> 16) as u32;
+            let mut z =
+                (u.wrapping_mul(u).wrapping_add(v.wrapping_mul(v)) as i32).wrapping_neg() / 0x200;
+            z = z.wrapping_mul((0x10000 + b) >> 8) / 0x10000;
+            b += z;
+            if z <= 0 {
+                break (u as i32, v as i32);
+            }
+        }
+    };
+
+    let nx = if sx { -nx } else { nx };
+    let ny = if sy { -ny } else { ny };
+    Some(UnitVector {
+        x: nx / 4,
+        y: ny / 4,
+    })
+}
+
+#[inline]
+pub(crate) fn round(n: F26Dot6) -> F26Dot6 {
+    n.wrapping_add(32) & -64
+}
+
+#[inline]
+pub(crate) fn floor(n: F26Dot6) -> F26Dot6 {
+    n & -64
+}
+
+#[inline]
+pub(crate) fn ceil(n: F26Dot6) -> F26Dot6 {
+    n.wrapping_add(63) & -64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_rounding() {
+        assert_eq!(mul_div(3, 1, 2), 2);
+        assert_eq!(mul_div(-3, 1, 2), -2);
+        assert_eq!(mul_div(1, 1, 0), i32::MAX);
+        assert_eq!(mul_div_no_round(3, 1, 2), 1);
+        assert_eq!(mul_div_no_round(-3, 1, 2), -1);
+    }
+
+    #[test]
+    fn fixed_multiplication() {
+        assert_eq!(mul_fix(64, 0x8000), 32);
+        assert_eq!(mul_fix(-64, 0x8000), -32);
+        assert_eq!(mul_fix14(100, 0x2000), 50);
+        assert_eq!(mul_fix14(-100, 0x2000), -50);
+        assert_eq!(div_fix(1, 2), 0x8000);
+    }
+
+    #[test]
+    fn normalize_vector() {
+        assert_eq!(normalize(0, 0), None);
+        assert_eq!(normalize(10, 0), Some(UnitVector::X_AXIS));
+        assert_eq!(normalize(0, 7), Some(UnitVector::Y_AXIS));
+        assert_eq!(normalize(5, 5), Some(UnitVector { x: 11585, y: 11585 }));
+    }
+
+    #[test]
+    fn pixel_rounding() {
+        assert_eq!(round(31), 0);
+        assert_eq!(round(32), 64);
+        assert_eq!(round(-33), -64);
+        assert_eq!(floor(-1), -64);
+        assert_eq!(ceil(1), 64);
+    }
+}
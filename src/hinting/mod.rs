@@ -0,0 +1,699 @@
+//! TrueType bytecode hinting.
+//!
+//! Runs the instructions stored in the `fpgm`, `prep` and `glyf` tables
+//! to grid-fit glyph outlines for a specific size.
+//! The interpreter mimics the FreeType v35 one.
+//!
+//! The [`HintingInstance`] executes the font and control value programs once
+//! and then can be used to outline any number of glyphs at the same size.
+
+use core::num::NonZeroU16;
+use std::vec::Vec;
+
+use crate::glyf::{self, CompositeGlyphInfo, CompositeGlyphIter};
+use crate::parser::Stream;
+use crate::{Face, GlyphId, NormalizedCoordinate, OutlineBuilder, RectF, Transform};
+
+mod engine;
+mod graphics_state;
+mod math;
+mod zone;
+
+use engine::{Engine, Metrics, ProgramKind, Programs, State};
+use graphics_state::GraphicsState;
+use math::{F26Dot6, Point};
+use zone::{Zone, ON_CURVE, TOUCHED_BOTH};
+
+/// A font instance prepared for hinting at a specific size.
+///
+/// Stores the interpreter state produced by the font and control value programs.
+/// Should be recreated when the size or the variation coordinates change.
+#[derive(Clone)]
+pub struct HintingInstance {
+    state: State,
+    metrics: Metrics,
+    max_stack: usize,
+    ppem: f32,
+    coordinates: Vec<NormalizedCoordinate>,
+    is_hinting_enabled: bool,
+}
+
+impl core::fmt::Debug for HintingInstance {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "HintingInstance {{ ppem: {}, ... }}", self.ppem)
+    }
+}
+
+impl HintingInstance {
+    /// Creates a new instance for the specified pixels per em.
+    ///
+    /// Uses the face's current variation coordinates.
+    ///
+    /// Returns `None` when the face has no `glyf` table, `ppem` is not positive
+    /// or the font program has failed.
+    pub fn new(face: &Face, ppem: f32) -> Option<Self> {
+        let tables = face.tables();
+        tables.glyf?;
+
+        if !(ppem > 0.0 && ppem < f32::from(u16::MAX)) {
+            return None;
+        }
+
+        let units_per_em = i32::from(tables.head.units_per_em);
+        let scale = math::div_fix((ppem * 64.0).round() as i32, units_per_em);
+
+        let metrics = Metrics {
+            ppem: ppem.round() as i32,
+            scale,
+            coords: variation_coordinates(face)
+                .iter()
+                .map(|c| c.get())
+                .collect(),
+            is_variable: face.is_variable(),
+        };
+
+        let maxp = &tables.maxp;
+        let max_stack = usize::from(maxp.max_stack_elements) + 32;
+        let mut state = State {
+            default_gs: GraphicsState::default(),
+            functions: Vec::new(),
+            instructions: Vec::new(),
+            cvt: scaled_cvt(face, scale),
+            storage: vec![0; usize::from(maxp.max_storage)],
+            // Like FreeType, reserve space for phantom points.
+            twilight: Zone::new_twilight(usize::from(maxp.max_twilight_points) + 4),
+        };
+
+        let font_metrics = Metrics {
+            ppem: 0,
+            scale: 0,
+            ..metrics.clone()
+        };
+
+        let programs = Programs {
+            font: tables.fpgm.unwrap_or_default(),
+            control_value: tables.prep.unwrap_or_default(),
+            glyph: &[],
+        };
+
+        let mut engine = Engine::new(programs, state, font_metrics, max_stack);
+        engine.run(ProgramKind::Font).ok()?;
+
+        // The font program can modify only functions and instruction definitions.
+        engine.metrics = metrics.clone();
+        engine.points_scale = scale;
+        engine.gs = GraphicsState::default();
+        engine.state.storage.iter_mut().for_each(|v| *v = 0);
+        engine.state.twilight = Zone::new_twilight(engine.state.twilight.len());
+        // Errors in the control value program are not fatal.
+        let _ = engine.run(ProgramKind::ControlValue);
+        engine.gs.reset_after_prep();
+
+        state = engine.state;
+        let instruct_control = engine.gs.instruct_control;
+        state.default_gs = if instruct_control & 2 != 0 {
+            GraphicsState {
+                instruct_control,
+                ..GraphicsState::default()
+            }
+        } else {
+            engine.gs
+        };
+
+        Some(HintingInstance {
+            state,
+            metrics,
+            max_stack,
+            ppem,
+            coordinates: variation_coordinates(face).to_vec(),
+            is_hinting_enabled: instruct_control & 1 == 0,
+        })
+    }
+
+    /// Returns the pixels per em this instance was created for.
+    #[inline]
+    pub fn ppem(&self) -> f32 {
+        self.ppem
+    }
+
+    /// Checks that the control value program hasn't disabled hinting.
+    ///
+    /// When disabled, glyphs are only scaled.
+    #[inline]
+    pub fn is_hinting_enabled(&self) -> bool {
+        self.is_hinting_enabled
+    }
+
+    /// Outlines a hinted glyph.
+    ///
+    /// Coordinates are in pixels, with the origin at the glyph's hinted left side bearing point.
+    ///
+    /// Returns `None` when the glyph has no outline, is malformed or when the face
+    /// variation coordinates differ from the ones this instance was created with.
+    pub fn outline_glyph(
+        &self,
+        face: &Face,
+        glyph_id: GlyphId,
+        builder: &mut dyn OutlineBuilder,
+    ) -> Option<RectF> {
+        if variation_coordinates(face) != self.coordinates.as_slice() {
+            return None;
+        }
+
+        let tables = face.tables();
+        let programs = Programs {
+            font: tables.fpgm.unwrap_or_default(),
+            control_value: tables.prep.unwrap_or_default(),
+            glyph: &[],
+        };
+
+        let engine = Engine::new(
+            programs,
+            self.state.clone(),
+            self.metrics.clone(),
+            self.max_stack,
+        );
+
+        let mut loader = Loader {
+            face,
+            glyf: tables.glyf?,
+            engine,
+            is_hinted: self.is_hinting_enabled,
+            scale: self.metrics.scale,
+            outline: Outline::default(),
+            phantoms: [Point::default(); 4],
+        };
+
+        loader.load(glyph_id, 0)?;
+        loader.outline.emit(loader.phantoms[0].x, builder)
+    }
+}
+
+#[cfg(feature = "variable-fonts")]
+#[inline]
+fn variation_coordinates<'a>(face: &'a Face) -> &'a [NormalizedCoordinate] {
+    face.variation_coordinates()
+}
+
+#[cfg(not(feature = "variable-fonts"))]
+#[inline]
+fn variation_coordinates<'a>(_: &'a Face) -> &'a [NormalizedCoordinate] {
+    &[]
+}
+
+/// Returns control values in 26.6 scaled to the current size.
+fn scaled_cvt(face: &Face, scale: i32) -> Vec<F26Dot6> {
+    let mut cvt: Vec<F26Dot6> = match face.tables().cvt {
+        Some(table) => table
+            .values
+            .into_iter()
+            .map(|v| i32::from(v) * 64)
+            .collect(),
+        None => return Vec::new(),
+    };
+
+    #[cfg(feature = "variable-fonts")]
+    {
+        if let Some(cvar) = face.tables().cvar {
+            if face.has_non_default_variation_coordinates() {
+                let mut deltas = vec![0.0; cvt.len()];
+                if cvar
+                    .apply(face.variation_coordinates(), &mut deltas)
+                    .is_some()
+                {
+                    for (value, delta) in cvt.iter_mut().zip(deltas) {
+                        *value = value.wrapping_add(to_f26dot6(delta));
+                    }
+                }
+            }
+        }
+    }
+
+    // Just like in FreeType, the fractional part is dropped before scaling.
+    for value in &mut cvt {
+        *value = math::mul_fix(*value / 64, scale);
+    }
+
+    cvt
+}
+
+#[cfg(feature = "variable-fonts")]
+#[inline]
+fn to_f26dot6(n: f32) -> F26Dot6 {
+    (n * 64.0 + 0.5).floor() as i32
+}
+
+#[inline]
+fn to_fixed(n: f32) -> i32 {
+    (n * 65536.0).round() as i32
+}
+
+/// A glyph outline in 26.6.
+#[derive(Default)]
+struct Outline {
+    points: Vec<Point>,
+    flags: Vec<u8>,
+    /// Contour end point indices.
+    contours: Vec<usize>,
+}
+
+impl Outline {
+    fn emit(&self, origin: F26Dot6, builder: &mut dyn OutlineBuilder) -> Option<RectF> {
+        let mut b = glyf::Builder::new(Transform::default(), RectF::new(), builder);
+        let mut start = 0;
+        for &end in &self.contours {
+            for i in start..=end {
+                let p = self.points[i];
+                b.push_point(
+                    p.x.wrapping_sub(origin) as f32 / 64.0,
+                    p.y as f32 / 64.0,
+                    self.flags[i] & ON_CURVE != 0,
+                    i == end,
+                );
+            }
+
+            start = end + 1;
+        }
+
+        if b.bbox.is_default() {
+            None
+        } else {
+            Some(b.bbox)
+        }
+    }
+}
+
+struct Loader<'a> {
+    face: &'a Face<'a>,
+    glyf: glyf::Table<'a>,
+    engine: Engine<'a>,
+    is_hinted: bool,
+    scale: i32,
+    outline: Outline,
+    /// The phantom points of the last loaded glyph.
+    phantoms: [Point; 4],
+}
+
+impl<'a> Loader<'a> {
+    fn load(&mut self, glyph_id: GlyphId, depth: u8) -> Option<()> {
+        if depth >= glyf::MAX_COMPONENTS {
+            return None;
+        }
+
+        let data = self.glyf.get(glyph_id).unwrap_or_default();
+        let mut s = Stream::new(data);
+        let (number_of_contours, x_min, y_max) = if data.is_empty() {
+            (0, 0, 0)
+        } else {
+            let number_of_contours = s.read::<i16>()?;
+            let x_min = s.read::<i16>()?;
+            s.skip::<i16>(); // y_min
+            s.skip::<i16>(); // x_max
+            let y_max = s.read::<i16>()?;
+            (number_of_contours, x_min, y_max)
+        };
+
+        let phantoms = self.unscaled_phantoms(glyph_id, x_min, y_max);
+
+        if number_of_contours > 0 {
+            // u16 casting is safe, since we already checked that the value is positive.
+            let number_of_contours = NonZeroU16::new(number_of_contours as u16)?;
+            self.load_simple(glyph_id, s.tail()?, number_of_contours, phantoms)
+        } else if number_of_contours < 0 {
+            self.load_composite(glyph_id, s.tail()?, phantoms, depth)
+        } else {
+            // An empty glyph still has metrics.
+            let mut phantoms = phantoms;
+            self.apply_phantom_deltas(glyph_id, 0, &mut phantoms);
+            for (i, unscaled) in phantoms.iter().enumerate() {
+                self.phantoms[i] = self.scale_point(*unscaled);
+            }
+
+            Some(())
+        }
+    }
+
+    /// Returns phantom points in font units.
+    fn unscaled_phantoms(&self, glyph_id: GlyphId, x_min: i16, y_max: i16) -> [Point; 4] {
+        let tables = self.face.tables();
+        let (advance, lsb) = match tables.hmtx {
+            Some(hmtx) => (
+                hmtx.advance(glyph_id).unwrap_or(0),
+                hmtx.side_bearing(glyph_id).unwrap_or(0),
+            ),
+            None => (0, 0),
+        };
+
+        let y_max = i32::from(y_max);
+        let (vertical_advance, tsb) = match tables.vmtx {
+            Some(vmtx) => (
+                i32::from(vmtx.advance(glyph_id).unwrap_or(0)),
+                i32::from(vmtx.side_bearing(glyph_id).unwrap_or(0)),
+            ),
+            None => {
+                let (ascender, descender) = match tables.os2 {
+                    Some(os2) => (os2.typographic_ascender(), os2.typographic_descender()),
+                    None => (tables.hhea.ascender, tables.hhea.descender),
+                };
+
+                let (ascender, descender) = (i32::from(ascender), i32::from(descender));
+                ((ascender - descender).abs(), ascender - y_max)
+            }
+        };
+
+        let pp1 = Point::new(i32::from(x_min) - i32::from(lsb), 0);
+        let pp3 = Point::new(0, y_max + tsb);
+        [
+            pp1,
+            Point::new(pp1.x + i32::from(advance), 0),
+            pp3,
+            Point::new(0, pp3.y - vertical_advance),
+        ]
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn glyph_deltas(
+        &self,
+        glyph_id: GlyphId,
+        points: glyf::GlyphPointsIter,
+        count: usize,
+    ) -> Option<Vec<crate::PointF>> {
+        if !self.face.has_non_default_variation_coordinates() {
+            return None;
+        }
+
+        let gvar = self.face.tables().gvar?;
+        let mut deltas = vec![crate::PointF { x: 0.0, y: 0.0 }; count + 4];
+        gvar.glyph_deltas(
+            glyph_id,
+            self.face.variation_coordinates(),
+            points,
+            &mut deltas,
+        )?;
+        Some(deltas)
+    }
+
+    /// Applies variation deltas to the phantom points of a glyph with `count` points.
+    #[allow(unused_variables)]
+    fn apply_phantom_deltas(&self, glyph_id: GlyphId, count: usize, phantoms: &mut [Point; 4]) {
+        #[cfg(feature = "variable-fonts")]
+        {
+            if let Some(deltas) =
+                self.glyph_deltas(glyph_id, glyf::GlyphPointsIter::default(), count)
+            {
+                for (p, d) in phantoms.iter_mut().zip(&deltas[count..]) {
+                    p.x += round_delta(d.x);
+                    p.y += round_delta(d.y);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn scale_point(&self, p: Point) -> Point {
+        Point::new(
+            math::mul_fix(p.x, self.scale),
+            math::mul_fix(p.y, self.scale),
+        )
+    }
+
+    fn load_simple(
+        &mut self,
+        glyph_id: GlyphId,
+        data: &'a [u8],
+        number_of_contours: NonZeroU16,
+        phantoms: [Point; 4],
+    ) -> Option<()> {
+        let points_iter = glyf::parse_simple_outline(data, number_of_contours)?;
+        let points: Vec<glyf::GlyphPoint> = points_iter.clone().collect();
+        let count = points.len();
+
+        // Font units with rounded variation deltas. Used by the interpreter.
+        let unscaled: Vec<Point> = points
+            .iter()
+            .map(|p| Point::new(i32::from(p.x), i32::from(p.y)))
+            .chain(phantoms.iter().copied())
+            .collect();
+
+        #[cfg(feature = "variable-fonts")]
+        let deltas = self.glyph_deltas(glyph_id, points_iter, count);
+        #[cfg(not(feature = "variable-fonts"))]
+        let _ = glyph_id;
+
+        let mut scaled: Vec<Point> = Vec::with_capacity(unscaled.len());
+
+        #[cfg(feature = "variable-fonts")]
+        let mut unscaled = unscaled;
+        #[cfg(feature = "variable-fonts")]
+        {
+            if self.face.has_non_default_variation_coordinates() {
+                // Variable glyphs are scaled with a higher precision.
+                for (i, p) in unscaled.iter_mut().enumerate() {
+                    let (mut x, mut y) = (p.x * 64, p.y * 64);
+                    if let Some(d) = deltas.as_ref().and_then(|d| d.get(i)) {
+                        p.x += round_delta(d.x);
+                        p.y += round_delta(d.y);
+                        x += to_f26dot6(d.x);
+                        y += to_f26dot6(d.y);
+                    }
+
+                    scaled.push(Point::new(
+                        (math::mul_fix(x, self.scale) + 32) >> 6,
+                        (math::mul_fix(y, self.scale) + 32) >> 6,
+                    ));
+                }
+            }
+        }
+
+        if scaled.is_empty() {
+            scaled.extend(unscaled.iter().map(|p| self.scale_point(*p)));
+        }
+
+        let start = self.outline.points.len();
+        let contours_start = self.outline.contours.len();
+        self.outline.points.extend_from_slice(&scaled[..count]);
+        for (i, p) in points.iter().enumerate() {
+            self.outline
+                .flags
+                .push(if p.on_curve_point { ON_CURVE } else { 0 });
+            if p.last_point {
+                self.outline.contours.push(start + i);
+            }
+        }
+
+        self.phantoms.copy_from_slice(&scaled[count..]);
+
+        if self.is_hinted {
+            let instructions =
+                glyf::simple_glyph_instructions(data, number_of_contours).unwrap_or_default();
+            self.hint(start, contours_start, Some(unscaled), instructions);
+        }
+
+        Some(())
+    }
+
+    fn load_composite(
+        &mut self,
+        glyph_id: GlyphId,
+        data: &'a [u8],
+        phantoms: [Point; 4],
+        depth: u8,
+    ) -> Option<()> {
+        let components: Vec<CompositeGlyphInfo> = CompositeGlyphIter::new(data).collect();
+
+        let offsets: Vec<Point> = components
+            .iter()
+            .map(|c| Point::new(c.transform.e as i32, c.transform.f as i32))
+            .collect();
+
+        #[cfg(feature = "variable-fonts")]
+        let (mut offsets, mut phantoms) = (offsets, phantoms);
+        #[cfg(feature = "variable-fonts")]
+        {
+            if let Some(deltas) =
+                self.glyph_deltas(glyph_id, glyf::GlyphPointsIter::default(), components.len())
+            {
+                for ((offset, component), d) in offsets.iter_mut().zip(&components).zip(&deltas) {
+                    if component.flags.args_are_xy_values() {
+                        offset.x += round_delta(d.x);
+                        offset.y += round_delta(d.y);
+                    }
+                }
+
+                for (p, d) in phantoms.iter_mut().zip(&deltas[components.len()..]) {
+                    p.x += round_delta(d.x);
+                    p.y += round_delta(d.y);
+                }
+            }
+        }
+        #[cfg(not(feature = "variable-fonts"))]
+        let _ = glyph_id;
+
+        for (i, unscaled) in phantoms.iter().enumerate() {
+            self.phantoms[i] = self.scale_point(*unscaled);
+        }
+
+        let start = self.outline.points.len();
+        let contours_start = self.outline.contours.len();
+        for (component, offset) in components.iter().zip(&offsets) {
+            let phantoms = self.phantoms;
+            let base = self.outline.points.len();
+            self.load(component.glyph_id, depth + 1)?;
+
+            if !component.flags.use_my_metrics() {
+                self.phantoms = phantoms;
+            }
+
+            if self.outline.points.len() != base {
+                self.place_component(component, *offset, start, base)?;
+            }
+        }
+
+        if self.is_hinted && self.outline.points.len() > start {
+            let instructions = glyf::composite_glyph_instructions(data).unwrap_or_default();
+            if !instructions.is_empty() {
+                // Components could be already touched by their own instructions.
+                for flags in &mut self.outline.flags[start..] {
+                    *flags &= !TOUCHED_BOTH;
+                }
+
+                self.hint(start, contours_start, None, instructions);
+            }
+        }
+
+        Some(())
+    }
+
+    /// Transforms and positions component points starting at `base`.
+    fn place_component(
+        &mut self,
+        component: &CompositeGlyphInfo,
+        offset: Point,
+        start: usize,
+        base: usize,
+    ) -> Option<()> {
+        let ts = component.transform;
+        let flags = component.flags;
+        let has_scale = flags.we_have_a_scale()
+            || flags.we_have_an_x_and_y_scale()
+            || flags.we_have_a_two_by_two();
+        let (xx, yx, xy, yy) = (
+            to_fixed(ts.a),
+            to_fixed(ts.b),
+            to_fixed(ts.c),
+            to_fixed(ts.d),
+        );
+        if has_scale {
+            for p in &mut self.outline.points[base..] {
+                *p = Point::new(
+                    math::mul_fix(p.x, xx).wrapping_add(math::mul_fix(p.y, xy)),
+                    math::mul_fix(p.x, yx).wrapping_add(math::mul_fix(p.y, yy)),
+                );
+            }
+        }
+
+        let (dx, dy) = if let Some((parent, child)) = component.matching_points {
+            let parent = start + usize::from(parent);
+            let child = base + usize::from(child);
+            if parent >= base || child >= self.outline.points.len() {
+                return None;
+            }
+
+            let (p1, p2) = (self.outline.points[parent], self.outline.points[child]);
+            (p1.x.wrapping_sub(p2.x), p1.y.wrapping_sub(p2.y))
+        } else {
+            let (mut x, mut y) = (offset.x, offset.y);
+            if x == 0 && y == 0 {
+                return Some(());
+            }
+
+            if has_scale && component.flags.scaled_component_offset() {
+                x = math::mul_fix(x, hypot(xx, xy));
+                y = math::mul_fix(y, hypot(yy, yx));
+            }
+
+            x = math::mul_fix(x, self.scale);
+            y = math::mul_fix(y, self.scale);
+            if self.is_hinted && component.flags.round_xy_to_grid() {
+                x = math::round(x);
+                y = math::round(y);
+            }
+
+            (x, y)
+        };
+
+        for p in &mut self.outline.points[base..] {
+            p.x = p.x.wrapping_add(dx);
+            p.y = p.y.wrapping_add(dy);
+        }
+
+        Some(())
+    }
+
+    /// Runs glyph instructions on the outline points starting at `start`.
+    ///
+    /// Composite glyphs don't have `unscaled` points, since they refer to
+    /// already hinted components.
+    fn hint(
+        &mut self,
+        start: usize,
+        contours_start: usize,
+        unscaled: Option<Vec<Point>>,
+        instructions: &'a [u8],
+    ) {
+        let is_composite = unscaled.is_none();
+        let count = self.outline.points.len() - start;
+
+        let zone = &mut self.engine.glyph;
+        zone.clear();
+        zone.points.extend_from_slice(&self.outline.points[start..]);
+        zone.points.extend_from_slice(&self.phantoms);
+        zone.original.extend_from_slice(&zone.points);
+        match unscaled {
+            Some(points) => zone.unscaled = points,
+            None => zone.unscaled.extend_from_slice(&zone.points),
+        }
+
+        zone.flags.extend_from_slice(&self.outline.flags[start..]);
+        zone.flags.extend_from_slice(&[0; 4]);
+        // Contours are relative to the zone.
+        zone.contours.extend(
+            self.outline.contours[contours_start..]
+                .iter()
+                .map(|end| (end - start) as u16),
+        );
+
+        // Phantom points are always rounded.
+        zone.points[count].x = math::round(zone.points[count].x);
+        zone.points[count + 1].x = math::round(zone.points[count + 1].x);
+        zone.points[count + 2].y = math::round(zone.points[count + 2].y);
+        zone.points[count + 3].y = math::round(zone.points[count + 3].y);
+
+        if !instructions.is_empty() {
+            let engine = &mut self.engine;
+            engine.programs.glyph = instructions;
+            engine.gs = engine.state.default_gs;
+            engine.gs.reset_for_glyph();
+            engine.points_scale = if is_composite { 0x10000 } else { self.scale };
+            // Like FreeType, ignore errors and keep the points modified so far.
+            let _ = engine.run(ProgramKind::Glyph);
+        }
+
+        let zone = &self.engine.glyph;
+        self.outline.points[start..].copy_from_slice(&zone.points[..count]);
+        self.outline.flags[start..].copy_from_slice(&zone.flags[..count]);
+        self.phantoms
+            .copy_from_slice(&zone.points[count..count + 4]);
+    }
+}
+
+#[cfg(feature = "variable-fonts")]
+#[inline]
+fn round_delta(n: f32) -> i32 {
+    (n + 0.5).floor() as i32
+}
+
+/// Calculates a fixed-point vector length.
+fn hypot(x: i32, y: i32) -> i32 {
+    f64::from(x).hypot(f64::from(y)).round() as i32
+}
@@ -0,0 +1,183 @@
+use std::vec::Vec;
+
+use super::math::Point;
+
+pub(crate) const TOUCHED_X: u8 = 0x08;
+pub(crate) const TOUCHED_Y: u8 = 0x10;
+pub(crate) const TOUCHED_BOTH: u8 = TOUCHED_X | TOUCHED_Y;
+pub(crate) const ON_CURVE: u8 = 0x01;
+
+/// A zone identifier.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum ZoneId {
+    Twilight,
+    Glyph,
+}
+
+/// A set of points the interpreter operates on.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct Zone {
+    /// Original points in font units.
+    ///
+    /// Not used by the twilight zone.
+    pub unscaled: Vec<Point>,
+    /// Original points in 26.6.
+    pub original: Vec<Point>,
+    /// Current, i.e. hinted, points in 26.6.
+    pub points: Vec<Point>,
+    /// Per-point flags: the on-curve bit and the touched bits.
+    pub flags: Vec<u8>,
+    /// Contour end point indices.
+    pub contours: Vec<u16>,
+}
+
+impl Zone {
+    pub fn new_twilight(len: usize) -> Self {
+        Zone {
+            unscaled: vec![Point::default(); len],
+            original: vec![Point::default(); len],
+            points: vec![Point::default(); len],
+            flags: vec![0; len],
+            contours: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.unscaled.clear();
+        self.original.clear();
+        self.points.clear();
+        self.flags.clear();
+        self.contours.clear();
+    }
+
+    /// Untouched points interpolation. `x` selects the axis.
+    pub fn interpolate_untouched(&mut self, x: bool) {
+        let mask = if x { TOUCHED_X } else { TOUCHED_Y };
+        let len = self.len();
+        let mut point = 0;
+        for i in 0..self.contours.len() {
+            let first_point = point;
+            let end_point = usize::from(self.contours[i]).min(len.saturating_sub(1));
+
+            while point <= end_point && self.flags[point] & mask == 0 {
+                point += 1;
+            }
+
+            if point > end_point {
+                // Nothing is touched.
+                point = end_point + 1;
+                continue;
+            }
+
+            let first_touched = point;
+            let mut cur_touched = point;
+            point += 1;
+            while point <= end_point {
+                if self.flags[point] & mask != 0 {
+                    self.iup_interpolate(x, cur_touched + 1, point - 1, cur_touched, point);
+                    cur_touched = point;
+                }
+
+                point += 1;
+            }
+
+            if cur_touched == first_touched {
+                self.iup_shift(x, first_point, end_point, cur_touched);
+            } else {
+                self.iup_interpolate(x, cur_touched + 1, end_point, cur_touched, first_touched);
+                if first_touched > 0 {
+                    self.iup_interpolate(
+                        x,
+                        first_point,
+                        first_touched - 1,
+                        cur_touched,
+                        first_touched,
+                    );
+                }
+            }
+        }
+    }
+
+    fn iup_shift(&mut self, x: bool, p1: usize, p2: usize, p: usize) {
+        let delta = coord(self.points[p], x).wrapping_sub(coord(self.original[p], x));
+        if delta == 0 {
+            return;
+        }
+
+        for i in (p1..p).chain(p + 1..=p2) {
+            let c = coord_mut(&mut self.points[i], x);
+            *c = c.wrapping_add(delta);
+        }
+    }
+
+    fn iup_interpolate(&mut self, x: bool, p1: usize, p2: usize, ref1: usize, ref2: usize) {
+        if p1 > p2 || ref1 >= self.len() || ref2 >= self.len() {
+            return;
+        }
+
+        let (mut ref1, mut ref2) = (ref1, ref2);
+        let mut unscaled1 = coord(self.unscaled[ref1], x);
+        let mut unscaled2 = coord(self.unscaled[ref2], x);
+        if unscaled1 > unscaled2 {
+            core::mem::swap(&mut unscaled1, &mut unscaled2);
+            core::mem::swap(&mut ref1, &mut ref2);
+        }
+
+        let original1 = coord(self.original[ref1], x);
+        let original2 = coord(self.original[ref2], x);
+        let current1 = coord(self.points[ref1], x);
+        let current2 = coord(self.points[ref2], x);
+        let delta1 = current1.wrapping_sub(original1);
+        let delta2 = current2.wrapping_sub(original2);
+
+        let can_scale = current1 != current2 && unscaled1 != unscaled2;
+        let mut scale = None;
+        for i in p1..=p2 {
+            let original = coord(self.original[i], x);
+            let value = if original <= original1 {
+                original.wrapping_add(delta1)
+            } else if original >= original2 {
+                original.wrapping_add(delta2)
+            } else if can_scale {
+                let scale = *scale.get_or_insert_with(|| {
+                    super::math::div_fix(
+                        current2.wrapping_sub(current1),
+                        unscaled2.wrapping_sub(unscaled1),
+                    )
+                });
+                let unscaled = coord(self.unscaled[i], x);
+                current1.wrapping_add(super::math::mul_fix(
+                    unscaled.wrapping_sub(unscaled1),
+                    scale,
+                ))
+            } else {
+                current1
+            };
+
+            *coord_mut(&mut self.points[i], x) = value;
+        }
+    }
+}
+
+#[inline]
+fn coord(p: Point, x: bool) -> i32 {
+    if x {
+        p.x
+    } else {
+        p.y
+    }
+}
+
+#[inline]
+fn coord_mut(p: &mut Point, x: bool) -> &mut i32 {
+    if x {
+        &mut p.x
+    } else {
+        &mut p.y
+    }
+}
@@ -0,0 +1,552 @@
+//! `CFF2` to `CFF ` conversion.
+//!
+//! Charstrings are flattened at the current variation coordinates,
+//! so the resulting table has no subroutines.
+
+use core::convert::TryFrom;
+use std::collections::BTreeSet;
+use std::string::String;
+use std::vec::Vec;
+
+use super::{from_subset_error, Glyph, InstanceError};
+use crate::parser::Stream;
+use crate::subset::cff::{parse_dict, push_int, push_operator, write_index};
+use crate::tables::cff::dict::{is_dict_one_byte_op, parse_number};
+use crate::tables::cff::{cff2, CFFError, HintingBuilder};
+use crate::{name_id, Face, GlyphId, NormalizedCoordinate, OutlineBuilder, Rect, Tag};
+
+const HEADER_SIZE: u8 = 4;
+// Custom strings start after the 391 standard ones.
+const STANDARD_STRINGS_LEN: usize = 391;
+const TWO_BYTE_OPERATOR_MARK: u8 = 12;
+const LONG_INT: u8 = 29;
+const REAL_NUMBER: u8 = 30;
+const END_OF_FLOAT_FLAG: u8 = 0xf;
+
+// Type 2 charstrings allow up to 48 arguments, including the glyph width.
+const MAX_ARGUMENTS: usize = 47;
+
+// https://adobe-type-tools.github.io/font-tech-notes/pdfs/5177.Type2.pdf
+mod operator {
+    pub const HORIZONTAL_STEM: u8 = 1;
+    pub const VERTICAL_STEM: u8 = 3;
+    pub const LINE_TO: u8 = 5;
+    pub const CURVE_TO: u8 = 8;
+    pub const ENDCHAR: u8 = 14;
+    pub const HORIZONTAL_STEM_HINT_MASK: u8 = 18;
+    pub const HINT_MASK: u8 = 19;
+    pub const COUNTER_MASK: u8 = 20;
+    pub const MOVE_TO: u8 = 21;
+    pub const VERTICAL_STEM_HINT_MASK: u8 = 23;
+    pub const SHORT_INT: u8 = 28;
+}
+
+// https://adobe-type-tools.github.io/font-tech-notes/pdfs/5176.CFF.pdf
+mod dict_operator {
+    pub const FONT_BBOX: u16 = 5;
+    pub const CHARSET_OFFSET: u16 = 15;
+    pub const CHAR_STRINGS_OFFSET: u16 = 17;
+    pub const PRIVATE_DICT_SIZE_AND_OFFSET: u16 = 18;
+    pub const LOCAL_SUBROUTINES_OFFSET: u16 = 19;
+    pub const VS_INDEX: u16 = 22;
+    pub const BLEND: u16 = 23;
+    pub const FONT_MATRIX: u16 = 1207;
+}
+
+/// Writes a `CFF ` table from `CFF2` at the current variation coordinates.
+pub(crate) fn instance(face: &Face) -> Result<(Vec<u8>, Vec<Glyph>), InstanceError> {
+    let table = face.tables().cff2.ok_or(InstanceError::MalformedFont)?;
+    let coordinates = face.variation_coordinates();
+
+    let mut char_strings = Vec::with_capacity(usize::from(face.number_of_glyphs()));
+    let mut glyphs = Vec::with_capacity(usize::from(face.number_of_glyphs()));
+    for id in 0..face.number_of_glyphs() {
+        let glyph_id = GlyphId(id);
+        let width = face.glyph_hor_advance(glyph_id).unwrap_or(0);
+        let mut writer = CharStringWriter::new(width);
+        match table.outline_with_hints(coordinates, glyph_id, &mut writer) {
+            Ok(_) => {}
+            Err(CFFError::ZeroBBox) => {
+                // An empty glyph, like a space.
+                writer = CharStringWriter::new(width);
+            }
+            Err(_) => return Err(InstanceError::MalformedFont),
+        }
+
+        // The bounding box of rounded points.
+        glyphs.push(Glyph::new(writer.bbox));
+        char_strings.push(writer.finish());
+    }
+
+    let (charset, strings) = charset(face);
+    let strings: Vec<&[u8]> = strings.iter().map(|name| name.as_bytes()).collect();
+    let char_strings: Vec<&[u8]> = char_strings.iter().map(|data| data.as_slice()).collect();
+    let char_strings = write_index(&char_strings).map_err(from_subset_error)?;
+    let private_dict = private_dict(&table, coordinates)?;
+
+    let font_name = face
+        .names()
+        .into_iter()
+        .filter(|name| name.name_id == name_id::POST_SCRIPT_NAME)
+        .find_map(|name| name.to_string())
+        .unwrap_or_else(|| String::from("Instance"));
+    let name_index = write_index(&[font_name.as_bytes()]).map_err(from_subset_error)?;
+    let string_index = write_index(&strings).map_err(from_subset_error)?;
+    let global_subrs = write_index(&[]).map_err(from_subset_error)?;
+
+    let font_matrix = face
+        .raw_face()
+        .table(Tag::from_bytes(b"CFF2"))
+        .and_then(font_matrix);
+    let bbox = glyphs
+        .iter()
+        .filter_map(|glyph| glyph.bbox)
+        .reduce(super::union);
+
+    // DICT sizes do not depend on offsets, so we can write it twice.
+    let write_top_dict =
+        |charset_offset: usize, char_strings_offset: usize, private_offset: usize| {
+            let mut data = Vec::new();
+            if let Some(operands) = font_matrix {
+                data.extend_from_slice(operands);
+                push_operator(dict_operator::FONT_MATRIX, &mut data);
+            }
+
+            if let Some(bbox) = bbox {
+                for n in &[bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max] {
+                    push_number(f64::from(*n), &mut data);
+                }
+                push_operator(dict_operator::FONT_BBOX, &mut data);
+            }
+
+            push_int(charset_offset, &mut data).map_err(from_subset_error)?;
+            push_operator(dict_operator::CHARSET_OFFSET, &mut data);
+            push_int(char_strings_offset, &mut data).map_err(from_subset_error)?;
+            push_operator(dict_operator::CHAR_STRINGS_OFFSET, &mut data);
+            push_int(private_dict.len(), &mut data).map_err(from_subset_error)?;
+            push_int(private_offset, &mut data).map_err(from_subset_error)?;
+            push_operator(dict_operator::PRIVATE_DICT_SIZE_AND_OFFSET, &mut data);
+            write_index(&[&data]).map_err(from_subset_error)
+        };
+
+    let charset_offset = usize::from(HEADER_SIZE)
+        + name_index.len()
+        + write_top_dict(0, 0, 0)?.len()
+        + string_index.len()
+        + global_subrs.len();
+    let char_strings_offset = charset_offset + charset.len();
+    let private_offset = char_strings_offset + char_strings.len();
+
+    let mut data = vec![1, 0, HEADER_SIZE, 4]; // version, header size, offset size
+    data.extend_from_slice(&name_index);
+    data.extend_from_slice(&write_top_dict(
+        charset_offset,
+        char_strings_offset,
+        private_offset,
+    )?);
+    data.extend_from_slice(&string_index);
+    data.extend_from_slice(&global_subrs);
+    data.extend_from_slice(&charset);
+    data.extend_from_slice(&char_strings);
+    data.extend_from_slice(&private_dict);
+    Ok((data, glyphs))
+}
+
+/// Returns FontMatrix operands from a `CFF2` Top DICT.
+fn font_matrix(data: &[u8]) -> Option<&[u8]> {
+    let mut s = Stream::new(data);
+    s.skip::<u8>(); // major version
+    s.skip::<u8>(); // minor version
+    let header_size = s.read::<u8>()?;
+    let top_dict_length = s.read::<u16>()?;
+    let start = usize::from(header_size);
+    let top_dict = data.get(start..start + usize::from(top_dict_length))?;
+    parse_dict(top_dict)?
+        .into_iter()
+        .find(|entry| entry.operator == dict_operator::FONT_MATRIX)
+        .map(|entry| entry.operands)
+}
+
+/// Writes a format 0 charset and returns it along with custom glyph names.
+///
+/// Glyph names are taken from the `post` table, when possible.
+fn charset(face: &Face) -> (Vec<u8>, Vec<String>) {
+    let mut charset = vec![0]; // format
+    let mut strings = Vec::new();
+    let mut used = BTreeSet::new();
+    // `.notdef` is omitted.
+    for id in 1..face.number_of_glyphs() {
+        let name = match glyph_name(face, GlyphId(id)) {
+            Some(name) if !used.contains(name) => String::from(name),
+            _ => std::format!("glyph{:05}", id),
+        };
+
+        let sid = match standard_string_id(&name) {
+            Some(sid) => sid,
+            None => {
+                strings.push(name.clone());
+                STANDARD_STRINGS_LEN + strings.len() - 1
+            }
+        };
+
+        charset.extend_from_slice(&(sid as u16).to_be_bytes());
+        used.insert(name);
+    }
+
+    (charset, strings)
+}
+
+#[cfg(feature = "glyph-names")]
+fn glyph_name<'a>(face: &'a Face, glyph_id: GlyphId) -> Option<&'a str> {
+    face.glyph_name(glyph_id)
+}
+
+#[cfg(not(feature = "glyph-names"))]
+fn glyph_name<'a>(_: &'a Face, _: GlyphId) -> Option<&'a str> {
+    None
+}
+
+#[cfg(feature = "glyph-names")]
+fn standard_string_id(name: &str) -> Option<usize> {
+    crate::tables::cff::std_names::STANDARD_NAMES
+        .iter()
+        .position(|n| *n == name)
+}
+
+// Fallback glyph names are never standard ones.
+#[cfg(not(feature = "glyph-names"))]
+fn standard_string_id(_: &str) -> Option<usize> {
+    None
+}
+
+/// Writes a Private DICT with blended values.
+///
+/// Local subroutines are not needed, since charstrings are flattened.
+fn private_dict(
+    table: &cff2::Table,
+    coordinates: &[NormalizedCoordinate],
+) -> Result<Vec<u8>, InstanceError> {
+    let store = table.variation_store();
+    let data = table.private_dict_data();
+
+    let mut dict = Vec::new();
+    let mut operands: Vec<f64> = Vec::new();
+    let mut scalars = region_scalars(table, 0, coordinates);
+    let mut s = Stream::new(data);
+    while !s.at_end() {
+        let b0 = s.read::<u8>().ok_or(InstanceError::MalformedFont)?;
+        if !is_dict_one_byte_op(b0) {
+            operands.push(parse_number(b0, &mut s).ok_or(InstanceError::MalformedFont)?);
+            continue;
+        }
+
+        let operator = if b0 == TWO_BYTE_OPERATOR_MARK {
+            1200 + u16::from(s.read::<u8>().ok_or(InstanceError::MalformedFont)?)
+        } else {
+            u16::from(b0)
+        };
+
+        match operator {
+            dict_operator::VS_INDEX => {
+                let index = operands.last().copied().unwrap_or(0.0) as u16;
+                store
+                    .region_indices(index)
+                    .ok_or(InstanceError::MalformedFont)?;
+                scalars = region_scalars(table, index, coordinates);
+            }
+            dict_operator::BLEND => {
+                // num(0)..num(n-1), deltas(0)..deltas(n * k - 1), n
+                // Blended values stay on the stack for the next operator.
+                let n = operands.pop().ok_or(InstanceError::MalformedFont)? as usize;
+                let k = scalars.len();
+                let start = operands
+                    .len()
+                    .checked_sub(n * (k + 1))
+                    .ok_or(InstanceError::MalformedFont)?;
+                let deltas = operands.split_off(start + n);
+                for (i, value) in operands[start..].iter_mut().enumerate() {
+                    for (delta, scalar) in deltas[i * k..(i + 1) * k].iter().zip(&scalars) {
+                        *value += delta * f64::from(*scalar);
+                    }
+                }
+
+                continue;
+            }
+            dict_operator::LOCAL_SUBROUTINES_OFFSET => {}
+            _ => {
+                for n in &operands {
+                    push_number(*n, &mut dict);
+                }
+                push_operator(operator, &mut dict);
+            }
+        }
+
+        operands.clear();
+    }
+
+    Ok(dict)
+}
+
+fn region_scalars(
+    table: &cff2::Table,
+    index: u16,
+    coordinates: &[NormalizedCoordinate],
+) -> Vec<f32> {
+    let store = table.variation_store();
+    store
+        .region_indices(index)
+        .map(|indices| {
+            indices
+                .into_iter()
+                .map(|index| store.regions.evaluate_region(index, coordinates))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Writes a DICT number, using the shortest encoding.
+fn push_number(n: f64, data: &mut Vec<u8>) {
+    if n.fract() == 0.0 && n.abs() <= f64::from(i32::MAX) {
+        let n = n as i32;
+        if i16::try_from(n).is_ok() {
+            // DICT integers up to `i16` are encoded just like in charstrings.
+            push_charstring_int(n, data);
+        } else {
+            data.push(LONG_INT);
+            data.extend_from_slice(&n.to_be_bytes());
+        }
+
+        return;
+    }
+
+    // Adobe Technical Note #5176, Table 5 Nibble Definitions
+    let s = std::format!("{:.8}", n);
+    let s = s.trim_end_matches('0');
+    let mut nibbles: Vec<u8> = s
+        .bytes()
+        .map(|c| match c {
+            b'.' => 0xa,
+            b'-' => 0xe,
+            c => c - b'0',
+        })
+        .collect();
+    nibbles.push(END_OF_FLOAT_FLAG);
+    if nibbles.len() % 2 != 0 {
+        nibbles.push(END_OF_FLOAT_FLAG);
+    }
+
+    data.push(REAL_NUMBER);
+    for pair in nibbles.chunks(2) {
+        data.push(pair[0] << 4 | pair[1]);
+    }
+}
+
+/// Writes a Type 2 charstring with rounded coordinates.
+struct CharStringWriter {
+    data: Vec<u8>,
+    // Written before the first operator.
+    width: Option<u16>,
+    x: i32,
+    y: i32,
+    bbox: Option<Rect>,
+    // Stems are written before the first mask or path operator.
+    horizontal_stems: Vec<(i32, i32)>,
+    vertical_stems: Vec<(i32, i32)>,
+    has_stems: bool,
+    // Path operators with the same operator are merged.
+    operator: u8,
+    arguments: Vec<i32>,
+}
+
+impl CharStringWriter {
+    fn new(width: u16) -> Self {
+        CharStringWriter {
+            data: Vec::new(),
+            width: Some(width),
+            x: 0,
+            y: 0,
+            bbox: None,
+            horizontal_stems: Vec::new(),
+            vertical_stems: Vec::new(),
+            has_stems: false,
+            operator: 0,
+            arguments: Vec::new(),
+        }
+    }
+
+    fn push_operator(&mut self, arguments: &[i32], operator: u8) {
+        if let Some(width) = self.width.take() {
+            // Equals to the default width, which is zero.
+            if width != 0 {
+                push_charstring_int(i32::from(width), &mut self.data);
+            }
+        }
+
+        for n in arguments {
+            push_charstring_int(*n, &mut self.data);
+        }
+        self.data.push(operator);
+    }
+
+    fn flush(&mut self) {
+        if !self.arguments.is_empty() {
+            let arguments = core::mem::take(&mut self.arguments);
+            self.push_operator(&arguments, self.operator);
+        }
+    }
+
+    fn flush_stems(&mut self, with_masks: bool) {
+        if self.has_stems {
+            return;
+        }
+
+        self.has_stems = true;
+        let stems = [
+            (
+                core::mem::take(&mut self.horizontal_stems),
+                operator::HORIZONTAL_STEM,
+                operator::HORIZONTAL_STEM_HINT_MASK,
+            ),
+            (
+                core::mem::take(&mut self.vertical_stems),
+                operator::VERTICAL_STEM,
+                operator::VERTICAL_STEM_HINT_MASK,
+            ),
+        ];
+        for (stems, stem_operator, mask_operator) in &stems {
+            let operator = if with_masks {
+                *mask_operator
+            } else {
+                *stem_operator
+            };
+
+            // Edges are relative to the previous stem.
+            for chunk in stems.chunks(MAX_ARGUMENTS / 2) {
+                let mut arguments = Vec::with_capacity(chunk.len() * 2);
+                let mut prev = 0;
+                for (position, len) in chunk {
+                    arguments.push(position - prev);
+                    arguments.push(*len);
+                    prev = position + len;
+                }
+
+                self.push_operator(&arguments, operator);
+            }
+        }
+    }
+
+    fn path_operator(&mut self, points: &[(f32, f32)], operator: u8) {
+        self.flush_stems(false);
+        if self.operator != operator || self.arguments.len() + points.len() * 2 > MAX_ARGUMENTS {
+            self.flush();
+            self.operator = operator;
+        }
+
+        for (x, y) in points {
+            let x = x.round() as i32;
+            let y = y.round() as i32;
+            self.arguments.push(x - self.x);
+            self.arguments.push(y - self.y);
+            self.x = x;
+            self.y = y;
+
+            let point = Rect {
+                x_min: x as i16,
+                y_min: y as i16,
+                x_max: x as i16,
+                y_max: y as i16,
+            };
+            self.bbox = Some(self.bbox.map_or(point, |bbox| super::union(bbox, point)));
+        }
+
+        // Subsequent `rmoveto` operators are not merged.
+        if operator == operator::MOVE_TO {
+            self.flush();
+        }
+    }
+
+    fn mask(&mut self, mask: &[u8], operator: u8) {
+        self.flush();
+        self.flush_stems(true);
+        self.push_operator(&[], operator);
+        self.data.extend_from_slice(mask);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.flush();
+        self.flush_stems(false);
+        self.push_operator(&[], operator::ENDCHAR);
+        self.data
+    }
+}
+
+impl OutlineBuilder for CharStringWriter {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.path_operator(&[(x, y)], operator::MOVE_TO);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.path_operator(&[(x, y)], operator::LINE_TO);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        // Not used by CFF, but can be converted into a cubic curve anyway.
+        let (x0, y0) = (self.x as f32, self.y as f32);
+        let cx1 = x0 + (x1 - x0) * 2.0 / 3.0;
+        let cy1 = y0 + (y1 - y0) * 2.0 / 3.0;
+        let cx2 = x + (x1 - x) * 2.0 / 3.0;
+        let cy2 = y + (y1 - y) * 2.0 / 3.0;
+        self.curve_to(cx1, cy1, cx2, cy2, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.path_operator(&[(x1, y1), (x2, y2), (x, y)], operator::CURVE_TO);
+    }
+
+    fn close(&mut self) {}
+}
+
+impl HintingBuilder for CharStringWriter {
+    fn horizontal_stem(&mut self, y: f32, dy: f32) {
+        if !self.has_stems {
+            self.horizontal_stems
+                .push((y.round() as i32, dy.round() as i32));
+        }
+    }
+
+    fn vertical_stem(&mut self, x: f32, dx: f32) {
+        if !self.has_stems {
+            self.vertical_stems
+                .push((x.round() as i32, dx.round() as i32));
+        }
+    }
+
+    fn hint_mask(&mut self, mask: &[u8]) {
+        self.mask(mask, operator::HINT_MASK);
+    }
+
+    fn counter_mask(&mut self, mask: &[u8]) {
+        self.mask(mask, operator::COUNTER_MASK);
+    }
+}
+
+/// Writes a charstring integer.
+fn push_charstring_int(n: i32, data: &mut Vec<u8>) {
+    match n {
+        -107..=107 => data.push((n + 139) as u8),
+        108..=1131 => {
+            let n = n - 108;
+            data.extend_from_slice(&[(n / 256 + 247) as u8, (n % 256) as u8]);
+        }
+        -1131..=-108 => {
+            let n = -n - 108;
+            data.extend_from_slice(&[(n / 256 + 251) as u8, (n % 256) as u8]);
+        }
+        _ => {
+            // Coordinates are limited to `i16` by the outline bounding box.
+            let n = i16::try_from(n).unwrap_or(if n < 0 { i16::MIN } else { i16::MAX });
+            data.push(operator::SHORT_INT);
+            data.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! `glyf` and `loca` tables instancing.
+
+use core::convert::TryFrom;
+use core::num::NonZeroU16;
+use std::vec::Vec;
+
+use super::{union, Glyph, InstanceError};
+use crate::parser::Stream;
+use crate::subset::glyf::{
+    components, loca, GlyfTable, ARG_1_AND_2_ARE_WORDS, MORE_COMPONENTS, WE_HAVE_AN_X_AND_Y_SCALE,
+    WE_HAVE_A_SCALE, WE_HAVE_A_TWO_BY_TWO,
+};
+use crate::tables::{glyf, loca};
+use crate::{DummyOutline, Face, GlyphId, IndexToLocationFormat, PointF, Rect};
+
+// https://docs.microsoft.com/en-us/typography/opentype/spec/glyf#simple-glyph-description
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const REPEAT_FLAG: u8 = 0x08;
+const X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR: u8 = 0x20;
+const OVERLAP_SIMPLE: u8 = 0x40;
+
+// https://docs.microsoft.com/en-us/typography/opentype/spec/glyf#composite-glyph-description
+const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+
+const HEADER_SIZE: usize = 10;
+
+/// Instanced `glyf` and `loca` tables.
+pub(crate) struct Tables {
+    pub glyf: Vec<u8>,
+    pub loca: Vec<u8>,
+    pub index_to_location_format: IndexToLocationFormat,
+    pub glyphs: Vec<Glyph>,
+}
+
+/// Writes `glyf` and `loca` tables with `gvar` deltas applied.
+pub(crate) fn instance(face: &Face) -> Result<Tables, InstanceError> {
+    let table = GlyfTable::parse(face).map_err(|_| InstanceError::MalformedFont)?;
+    let gvar = face.tables().gvar;
+    let coordinates = face.variation_coordinates();
+
+    let mut glyf = Vec::new();
+    let mut glyphs = Vec::with_capacity(usize::from(face.number_of_glyphs()));
+    let mut composites = Vec::new();
+    let mut offsets = Vec::with_capacity(usize::from(face.number_of_glyphs()) + 1);
+    offsets.push(0);
+    for id in 0..face.number_of_glyphs() {
+        let glyph_id = GlyphId(id);
+        let data = table.glyph(glyph_id);
+        let number_of_contours = Stream::read_at::<i16>(data, 0).unwrap_or(0);
+        let old_bbox = bbox(data);
+
+        let points_iter = if number_of_contours > 0 {
+            simple_points(data, number_of_contours as u16)?
+        } else {
+            glyf::GlyphPointsIter::default()
+        };
+        let points: Vec<glyf::GlyphPoint> = points_iter.clone().collect();
+
+        let count = if number_of_contours < 0 {
+            composites.push(glyph_id);
+            components(data).len()
+        } else {
+            points.len()
+        };
+
+        // Each glyph is followed by four phantom points.
+        let mut deltas = std::vec![PointF { x: 0.0, y: 0.0 }; count + 4];
+        if let Some(gvar) = gvar {
+            if gvar
+                .glyph_deltas(glyph_id, coordinates, points_iter, &mut deltas)
+                .is_none()
+            {
+                // Ignore malformed variation data.
+                for delta in deltas.iter_mut() {
+                    *delta = PointF { x: 0.0, y: 0.0 };
+                }
+            }
+        }
+
+        let mut new_bbox = old_bbox;
+        if number_of_contours > 0 && !points.is_empty() {
+            let (data, bbox) = simple_glyph(data, &points, &deltas)?;
+            glyf.extend_from_slice(&data);
+            new_bbox = Some(bbox);
+        } else if number_of_contours < 0 {
+            glyf.extend_from_slice(&composite_glyph(data, &deltas[..count])?);
+        } else {
+            // An empty glyph or a glyph with a single point, which is ignored.
+            glyf.extend_from_slice(data);
+        }
+
+        // Short offsets are stored divided by two.
+        if glyf.len() % 2 != 0 {
+            glyf.push(0);
+        }
+
+        offsets.push(glyf.len());
+
+        let phantom = [
+            deltas[count],
+            deltas[count + 1],
+            deltas[count + 2],
+            deltas[count + 3],
+        ];
+        let mut glyph = Glyph::new(new_bbox);
+        glyph.phantom = phantom;
+        if let (Some(old_bbox), Some(new_bbox)) = (old_bbox, new_bbox) {
+            glyph.top_offset = phantom[2].y - f32::from(new_bbox.y_max - old_bbox.y_max);
+        }
+        glyphs.push(glyph);
+    }
+
+    let starts = offsets.clone();
+    let (loca, format) = loca(&glyf, offsets).map_err(|_| InstanceError::Overflow)?;
+
+    // Composite glyph bounding boxes depend on instanced components.
+    let new_table = loca::Table::parse(face.tables().maxp.number_of_glyphs, format, &loca)
+        .and_then(|loca| glyf::Table::parse(loca, &glyf))
+        .ok_or(InstanceError::MalformedFont)?;
+    let mut bboxes = Vec::with_capacity(composites.len());
+    for glyph_id in &composites {
+        bboxes.push(new_table.outline(*glyph_id, &mut DummyOutline));
+    }
+
+    for (glyph_id, bbox) in composites.iter().zip(bboxes) {
+        let index = usize::from(glyph_id.0);
+        let (start, old_bbox) = (starts[index], glyphs[index].bbox);
+        if let Some(bbox) = bbox {
+            write_bbox(&mut glyf[start..], bbox);
+            glyphs[index].bbox = Some(bbox);
+            if let Some(old_bbox) = old_bbox {
+                glyphs[index].top_offset -= f32::from(bbox.y_max - old_bbox.y_max);
+            }
+        }
+    }
+
+    Ok(Tables {
+        glyf,
+        loca,
+        index_to_location_format: format,
+        glyphs,
+    })
+}
+
+fn bbox(data: &[u8]) -> Option<Rect> {
+    let mut s = Stream::new_at(data, 2)?;
+    Some(Rect {
+        x_min: s.read::<i16>()?,
+        y_min: s.read::<i16>()?,
+        x_max: s.read::<i16>()?,
+        y_max: s.read::<i16>()?,
+    })
+}
+
+fn write_bbox(data: &mut [u8], bbox: Rect) {
+    for (i, n) in [bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max]
+        .iter()
+        .enumerate()
+    {
+        data[2 + i * 2..4 + i * 2].copy_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn simple_points(
+    data: &[u8],
+    number_of_contours: u16,
+) -> Result<glyf::GlyphPointsIter<'_>, InstanceError> {
+    let number_of_contours =
+        NonZeroU16::new(number_of_contours).ok_or(InstanceError::MalformedFont)?;
+    data.get(HEADER_SIZE..)
+        .and_then(|data| glyf::parse_simple_outline(data, number_of_contours))
+        .ok_or(InstanceError::MalformedFont)
+}
+
+/// Writes a simple glyph with new point coordinates.
+///
+/// Contour end points and instructions are copied as is.
+fn simple_glyph(
+    data: &[u8],
+    points: &[glyf::GlyphPoint],
+    deltas: &[PointF],
+) -> Result<(Vec<u8>, Rect), InstanceError> {
+    let number_of_contours = Stream::read_at::<u16>(data, 0).ok_or(InstanceError::MalformedFont)?;
+    let instructions_offset = HEADER_SIZE + usize::from(number_of_contours) * 2;
+    let instructions_len =
+        Stream::read_at::<u16>(data, instructions_offset).ok_or(InstanceError::MalformedFont)?;
+    let flags_offset = instructions_offset + 2 + usize::from(instructions_len);
+    let overlap = data.get(flags_offset).ok_or(InstanceError::MalformedFont)? & OVERLAP_SIMPLE;
+
+    let mut coordinates = Vec::with_capacity(points.len());
+    for (point, delta) in points.iter().zip(deltas) {
+        let x = super::to_i16(f32::from(point.x) + delta.x)?;
+        let y = super::to_i16(f32::from(point.y) + delta.y)?;
+        coordinates.push((x, y, point.on_curve_point));
+    }
+
+    let mut bbox: Option<Rect> = None;
+    for (x, y, _) in &coordinates {
+        let point = Rect {
+            x_min: *x,
+            y_min: *y,
+            x_max: *x,
+            y_max: *y,
+        };
+        bbox = Some(bbox.map_or(point, |bbox| union(bbox, point)));
+    }
+    let bbox = bbox.ok_or(InstanceError::MalformedFont)?;
+
+    let mut glyph = data[..flags_offset].to_vec();
+    write_bbox(&mut glyph, bbox);
+
+    let mut flags: Vec<u8> = Vec::with_capacity(coordinates.len());
+    let mut x_coordinates = Vec::new();
+    let mut y_coordinates = Vec::new();
+    // The last written flag and its repeat count.
+    let mut last: Option<(usize, u8)> = None;
+    let (mut prev_x, mut prev_y) = (0i16, 0i16);
+    for (i, (x, y, on_curve_point)) in coordinates.iter().enumerate() {
+        let mut flag = if *on_curve_point { ON_CURVE_POINT } else { 0 };
+        if i == 0 {
+            flag |= overlap;
+        }
+
+        let dx = i32::from(*x) - i32::from(prev_x);
+        let dy = i32::from(*y) - i32::from(prev_y);
+        flag |= encode_delta(
+            dx,
+            X_SHORT_VECTOR,
+            X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR,
+            &mut x_coordinates,
+        );
+        flag |= encode_delta(
+            dy,
+            Y_SHORT_VECTOR,
+            Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR,
+            &mut y_coordinates,
+        );
+        prev_x = *x;
+        prev_y = *y;
+
+        match last {
+            Some((index, count)) if flags[index] & !REPEAT_FLAG == flag && count < u8::MAX => {
+                if count == 0 {
+                    flags[index] |= REPEAT_FLAG;
+                    flags.push(1);
+                } else {
+                    flags[index + 1] = count + 1;
+                }
+
+                last = Some((index, count + 1));
+            }
+            _ => {
+                flags.push(flag);
+                last = Some((flags.len() - 1, 0));
+            }
+        }
+    }
+
+    glyph.extend_from_slice(&flags);
+    glyph.extend_from_slice(&x_coordinates);
+    glyph.extend_from_slice(&y_coordinates);
+    Ok((glyph, bbox))
+}
+
+/// Writes a coordinate delta and returns its flags.
+fn encode_delta(delta: i32, short_flag: u8, same_flag: u8, data: &mut Vec<u8>) -> u8 {
+    if delta == 0 {
+        same_flag
+    } else if delta.abs() <= i32::from(u8::MAX) {
+        data.push(delta.unsigned_abs() as u8);
+        if delta > 0 {
+            short_flag | same_flag
+        } else {
+            short_flag
+        }
+    } else {
+        // Cannot overflow, since both coordinates are `i16`, but the difference can.
+        data.extend_from_slice(&(delta as i16).to_be_bytes());
+        0
+    }
+}
+
+/// Writes a composite glyph with new component offsets.
+///
+/// The bounding box is updated later, once all components are instanced.
+fn composite_glyph(data: &[u8], deltas: &[PointF]) -> Result<Vec<u8>, InstanceError> {
+    let mut glyph = data
+        .get(..HEADER_SIZE)
+        .ok_or(InstanceError::MalformedFont)?
+        .to_vec();
+    let mut s = Stream::new_at(data, HEADER_SIZE).ok_or(InstanceError::MalformedFont)?;
+    for delta in deltas {
+        let mut flags = s.read::<u16>().ok_or(InstanceError::MalformedFont)?;
+        let glyph_id = s.read::<u16>().ok_or(InstanceError::MalformedFont)?;
+        let is_xy = flags & ARGS_ARE_XY_VALUES != 0;
+        let (mut arg1, mut arg2) = match (flags & ARG_1_AND_2_ARE_WORDS != 0, is_xy) {
+            (true, true) => (
+                s.read::<i16>().map(i32::from),
+                s.read::<i16>().map(i32::from),
+            ),
+            (true, false) => (
+                s.read::<u16>().map(i32::from),
+                s.read::<u16>().map(i32::from),
+            ),
+            (false, true) => (s.read::<i8>().map(i32::from), s.read::<i8>().map(i32::from)),
+            (false, false) => (s.read::<u8>().map(i32::from), s.read::<u8>().map(i32::from)),
+        };
+
+        let transform_len = if flags & WE_HAVE_A_SCALE != 0 {
+            2
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            4
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            8
+        } else {
+            0
+        };
+        let transform = s
+            .read_bytes(transform_len)
+            .ok_or(InstanceError::MalformedFont)?;
+
+        if is_xy {
+            // Point numbers are not affected.
+            arg1 = arg1.map(|n| n + delta.x.round() as i32);
+            arg2 = arg2.map(|n| n + delta.y.round() as i32);
+        }
+
+        let (arg1, arg2) = match (arg1, arg2) {
+            (Some(arg1), Some(arg2)) => (arg1, arg2),
+            _ => return Err(InstanceError::MalformedFont),
+        };
+
+        let fits_in_byte = |n: i32| {
+            if is_xy {
+                i8::try_from(n).is_ok()
+            } else {
+                u8::try_from(n).is_ok()
+            }
+        };
+        if fits_in_byte(arg1) && fits_in_byte(arg2) {
+            flags &= !ARG_1_AND_2_ARE_WORDS;
+        } else {
+            flags |= ARG_1_AND_2_ARE_WORDS;
+        }
+
+        glyph.extend_from_slice(&flags.to_be_bytes());
+        glyph.extend_from_slice(&glyph_id.to_be_bytes());
+        for arg in &[arg1, arg2] {
+            if flags & ARG_1_AND_2_ARE_WORDS == 0 {
+                glyph.push(*arg as u8);
+            } else if is_xy {
+                let arg = i16::try_from(*arg).map_err(|_| InstanceError::Overflow)?;
+                glyph.extend_from_slice(&arg.to_be_bytes());
+            } else {
+                let arg = u16::try_from(*arg).map_err(|_| InstanceError::MalformedFont)?;
+                glyph.extend_from_slice(&arg.to_be_bytes());
+            }
+        }
+        glyph.extend_from_slice(transform);
+
+        if flags & MORE_COMPONENTS == 0 {
+            // Instructions are copied as is.
+            glyph.extend_from_slice(s.tail().unwrap_or_default());
+            return Ok(glyph);
+        }
+    }
+
+    // More components than deltas means that `components` stopped on a truncated record.
+    Err(InstanceError::MalformedFont)
+}
@@ -0,0 +1,555 @@
+//! A variable font instancer.
+//!
+//! Produces a static font at a location in the design space,
+//! like the ones shipped alongside a variable font. Requires heap allocations.
+//!
+//! ```no_run
+//! use ttf_parser::{instance::Instancer, Face, Tag};
+//!
+//! let data = std::fs::read("font.ttf").unwrap();
+//! let face = Face::parse(&data, 0).unwrap();
+//! let mut instancer = Instancer::new(&face).unwrap();
+//! instancer.set_variation(Tag::from_bytes(b"wght"), 700.0).unwrap();
+//!
+//! let data = instancer.build().unwrap();
+//! std::fs::write("font-bold.ttf", data).unwrap();
+//! ```
+
+mod cff;
+mod glyf;
+
+use core::convert::TryFrom;
+use std::vec::Vec;
+
+use crate::parser::Stream;
+use crate::subset::layout::{self, Variations};
+use crate::subset::tables::{self as subset_tables, set_u16};
+use crate::subset::{GlyphMap, SubsetError};
+use crate::var_store::ItemVariationStore;
+use crate::writer::FontBuilder;
+use crate::{Face, GlyphId, NormalizedCoordinate, PointF, Rect, Tag};
+
+/// Tables that are meaningless in a static font.
+const VARIATION_TABLES: &[&[u8; 4]] = &[
+    b"avar", b"cvar", b"fvar", b"gvar", b"HVAR", b"MVAR", b"VVAR",
+];
+
+// https://docs.microsoft.com/en-us/typography/opentype/spec/head
+const HEAD_BBOX_OFFSET: usize = 36;
+
+// https://docs.microsoft.com/en-us/typography/opentype/spec/hhea
+const ADVANCE_MAX_OFFSET: usize = 10;
+const MIN_SIDE_BEARING_OFFSET: usize = 12;
+const MIN_OTHER_SIDE_BEARING_OFFSET: usize = 14;
+const MAX_EXTENT_OFFSET: usize = 16;
+
+// https://docs.microsoft.com/en-us/typography/opentype/spec/os2
+const AVG_CHAR_WIDTH_OFFSET: usize = 2;
+const WEIGHT_CLASS_OFFSET: usize = 4;
+const WIDTH_CLASS_OFFSET: usize = 6;
+
+/// Width percentages of `usWidthClass` values, starting from 1.
+const WIDTH_CLASSES: &[f32] = &[50.0, 62.5, 75.0, 87.5, 100.0, 112.5, 125.0, 150.0, 200.0];
+
+// https://docs.microsoft.com/en-us/typography/opentype/spec/mvar#value-tags
+// Tag, offset and whether the field is signed.
+const OS2_METRICS: &[(&[u8; 4], usize, bool)] = &[
+    (b"sbxs", 10, true),
+    (b"sbys", 12, true),
+    (b"sbxo", 14, true),
+    (b"sbyo", 16, true),
+    (b"spxs", 18, true),
+    (b"spys", 20, true),
+    (b"spxo", 22, true),
+    (b"spyo", 24, true),
+    (b"strs", 26, true),
+    (b"stro", 28, true),
+    (b"hasc", 68, true),
+    (b"hdsc", 70, true),
+    (b"hlgp", 72, true),
+    (b"hcla", 74, false),
+    (b"hcld", 76, false),
+    (b"xhgt", 86, true),
+    (b"cpht", 88, true),
+];
+
+const HHEA_METRICS: &[(&[u8; 4], usize, bool)] = &[
+    (b"hcrs", 18, true),
+    (b"hcrn", 20, true),
+    (b"hcof", 22, true),
+];
+
+const VHEA_METRICS: &[(&[u8; 4], usize, bool)] = &[
+    (b"vasc", 4, true),
+    (b"vdsc", 6, true),
+    (b"vlgp", 8, true),
+    (b"vcrs", 18, true),
+    (b"vcrn", 20, true),
+    (b"vcof", 22, true),
+];
+
+const POST_METRICS: &[(&[u8; 4], usize, bool)] = &[(b"undo", 8, true), (b"unds", 10, true)];
+
+const GASP_METRICS: &[(&[u8; 4], usize, bool)] = &[
+    (b"gsp0", 4, false),
+    (b"gsp1", 8, false),
+    (b"gsp2", 12, false),
+    (b"gsp3", 16, false),
+    (b"gsp4", 20, false),
+    (b"gsp5", 24, false),
+    (b"gsp6", 28, false),
+    (b"gsp7", 32, false),
+    (b"gsp8", 36, false),
+    (b"gsp9", 40, false),
+];
+
+/// A list of errors that can occur during instancing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InstanceError {
+    /// The font is not variable.
+    NotVariable,
+    /// A table required for instancing is missing or malformed.
+    MalformedFont,
+    /// An offset, a table size or a coordinate overflowed.
+    Overflow,
+}
+
+impl core::fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InstanceError::NotVariable => write!(f, "font is not variable"),
+            InstanceError::MalformedFont => write!(f, "malformed font"),
+            InstanceError::Overflow => write!(f, "offset overflow"),
+        }
+    }
+}
+
+impl std::error::Error for InstanceError {}
+
+fn from_subset_error(error: SubsetError) -> InstanceError {
+    match error {
+        SubsetError::Overflow => InstanceError::Overflow,
+        _ => InstanceError::MalformedFont,
+    }
+}
+
+/// A variable font instancer.
+///
+/// Writes a static font at the current variation coordinates:
+///
+/// - `gvar` deltas are applied to `glyf`
+/// - `CFF2` is converted into `CFF ` with flattened charstrings
+///   and a Private DICT from the first Font DICT
+/// - `HVAR`, `VVAR` and `MVAR` deltas are applied to metrics
+/// - `cvar` deltas are applied to `cvt `
+/// - `FeatureVariations` are resolved and variation device tables
+///   are applied to `GDEF` and `GPOS` values
+///
+/// `fvar`, `avar` and all of the above variation tables are dropped.
+/// `STAT` and all other tables are copied as is.
+pub struct Instancer<'a> {
+    face: Face<'a>,
+    // User-space coordinates set via `set_variation`.
+    values: Vec<(Tag, f32)>,
+}
+
+impl<'a> Instancer<'a> {
+    /// Creates a new instancer at the default location.
+    ///
+    /// Variation coordinates of `face` are ignored.
+    ///
+    /// Returns [`InstanceError::NotVariable`] when [`Face::is_variable`] is `false`.
+    pub fn new(face: &Face<'a>) -> Result<Self, InstanceError> {
+        if !face.is_variable() {
+            return Err(InstanceError::NotVariable);
+        }
+
+        let mut face = face.clone();
+        face.coordinates
+            .as_mut_slice()
+            .fill(NormalizedCoordinate::default());
+        face.unmapped_coordinates
+            .as_mut_slice()
+            .fill(NormalizedCoordinate::default());
+
+        Ok(Instancer {
+            face,
+            values: Vec::new(),
+        })
+    }
+
+    /// Sets a variation axis coordinate.
+    ///
+    /// Values are clamped to the axis range.
+    ///
+    /// Returns `None` when the face doesn't have such axis.
+    pub fn set_variation(&mut self, axis: Tag, value: f32) -> Option<()> {
+        let var_axis = self
+            .face
+            .variation_axes()
+            .into_iter()
+            .find(|var_axis| var_axis.tag == axis)?;
+        self.face.set_variation(axis, value)?;
+
+        let value = value.max(var_axis.min_value).min(var_axis.max_value);
+        match self.values.iter_mut().find(|(tag, _)| *tag == axis) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((axis, value)),
+        }
+
+        Some(())
+    }
+
+    /// Writes the static font.
+    pub fn build(&self) -> Result<Vec<u8>, InstanceError> {
+        let face = &self.face;
+        let raw_face = face.raw_face();
+        let tables = face.tables();
+        let coordinates = face.variation_coordinates();
+        let number_of_glyphs = face.number_of_glyphs();
+
+        let mut builder = FontBuilder::from_raw_face(raw_face);
+        for tag in VARIATION_TABLES {
+            builder.remove_table(Tag::from_bytes(tag));
+        }
+
+        let mut index_to_location_format = None;
+        let glyphs = if tables.glyf.is_some() {
+            let tables = glyf::instance(face)?;
+            builder.set_table(Tag::from_bytes(b"glyf"), tables.glyf);
+            builder.set_table(Tag::from_bytes(b"loca"), tables.loca);
+            index_to_location_format = Some(tables.index_to_location_format);
+            tables.glyphs
+        } else if tables.cff2.is_some() {
+            let (cff, glyphs) = cff::instance(face)?;
+            builder.remove_table(Tag::from_bytes(b"CFF2"));
+            builder.set_table(Tag::from_bytes(b"CFF "), cff);
+            glyphs
+        } else {
+            // Outlines are not variable, like in bitmap fonts.
+            (0..number_of_glyphs)
+                .map(|id| Glyph::new(face.glyph_bounding_box(GlyphId(id))))
+                .collect()
+        };
+
+        let head = raw_face
+            .table(Tag::from_bytes(b"head"))
+            .ok_or(InstanceError::MalformedFont)?;
+        let mut head =
+            subset_tables::head(head, index_to_location_format).map_err(from_subset_error)?;
+        if let Some(bbox) = glyphs.iter().filter_map(|glyph| glyph.bbox).reduce(union) {
+            for (i, n) in [bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max]
+                .iter()
+                .enumerate()
+            {
+                set_u16(&mut head, HEAD_BBOX_OFFSET + i * 2, *n as u16);
+            }
+        }
+        builder.set_table(Tag::from_bytes(b"head"), head);
+
+        let hhea = raw_face
+            .table(Tag::from_bytes(b"hhea"))
+            .ok_or(InstanceError::MalformedFont)?;
+        let hmtx = tables.hmtx.ok_or(InstanceError::MalformedFont)?;
+        let mut metrics = Vec::with_capacity(glyphs.len());
+        for (id, glyph) in glyphs.iter().enumerate() {
+            let glyph_id = GlyphId(id as u16);
+            let offset = match tables.hvar {
+                Some(hvar) => hvar.advance_offset(glyph_id, coordinates).unwrap_or(0.0),
+                None => glyph.phantom[1].x - glyph.phantom[0].x,
+            };
+            let advance = f32::from(hmtx.advance(glyph_id).unwrap_or(0)) + offset;
+            let side_bearing = match glyph.bbox {
+                Some(bbox) => bbox.x_min,
+                None if tables.glyf.is_some() || tables.cff2.is_some() => 0,
+                None => hmtx.side_bearing(glyph_id).unwrap_or(0),
+            };
+            metrics.push((to_u16(advance)?, side_bearing));
+        }
+
+        let (mut hhea, hmtx) =
+            subset_tables::write_metrics(hhea, &metrics).map_err(from_subset_error)?;
+        update_header(&mut hhea, &metrics, &glyphs, |bbox| {
+            (bbox.x_min, bbox.x_max)
+        });
+        apply_metrics(face, &mut hhea, HHEA_METRICS)?;
+        builder.set_table(Tag::from_bytes(b"hhea"), hhea);
+        builder.set_table(Tag::from_bytes(b"hmtx"), hmtx);
+
+        if let (Some(vhea), Some(vmtx)) = (raw_face.table(Tag::from_bytes(b"vhea")), tables.vmtx) {
+            let mut vertical_metrics = Vec::with_capacity(glyphs.len());
+            for (id, glyph) in glyphs.iter().enumerate() {
+                let glyph_id = GlyphId(id as u16);
+                let (advance_offset, side_bearing_offset) = match tables.vvar {
+                    Some(vvar) => (
+                        vvar.advance_offset(glyph_id, coordinates).unwrap_or(0.0),
+                        vvar.top_side_bearing_offset(glyph_id, coordinates)
+                            .unwrap_or(glyph.top_offset),
+                    ),
+                    None => (glyph.phantom[2].y - glyph.phantom[3].y, glyph.top_offset),
+                };
+                let advance = f32::from(vmtx.advance(glyph_id).unwrap_or(0)) + advance_offset;
+                let side_bearing =
+                    f32::from(vmtx.side_bearing(glyph_id).unwrap_or(0)) + side_bearing_offset;
+                vertical_metrics.push((to_u16(advance)?, to_i16(side_bearing)?));
+            }
+
+            let (mut vhea, vmtx) =
+                subset_tables::write_metrics(vhea, &vertical_metrics).map_err(from_subset_error)?;
+            // Top side bearings are measured down from the vertical origin.
+            update_header(&mut vhea, &vertical_metrics, &glyphs, |bbox| {
+                (-bbox.y_max, -bbox.y_min)
+            });
+            apply_metrics(face, &mut vhea, VHEA_METRICS)?;
+            builder.set_table(Tag::from_bytes(b"vhea"), vhea);
+            builder.set_table(Tag::from_bytes(b"vmtx"), vmtx);
+        }
+
+        if let Some(data) = raw_face.table(Tag::from_bytes(b"OS/2")) {
+            builder.set_table(Tag::from_bytes(b"OS/2"), self.os2(data, &metrics)?);
+        }
+
+        for (tag, fields) in &[(b"post", POST_METRICS), (b"gasp", GASP_METRICS)] {
+            let tag = Tag::from_bytes(tag);
+            if let Some(data) = raw_face.table(tag) {
+                let mut data = data.to_vec();
+                apply_metrics(face, &mut data, fields)?;
+                builder.set_table(tag, data);
+            }
+        }
+
+        if let Some(data) = raw_face.table(Tag::from_bytes(b"cvt ")) {
+            builder.set_table(Tag::from_bytes(b"cvt "), cvt(face, data)?);
+        }
+
+        let variations = Variations {
+            coordinates,
+            store: raw_face
+                .table(Tag::from_bytes(b"GDEF"))
+                .and_then(gdef_variation_store),
+        };
+        let map = GlyphMap::all(number_of_glyphs);
+        for tag in &[b"GDEF", b"GPOS", b"GSUB"] {
+            let tag = Tag::from_bytes(tag);
+            if let Some(data) = raw_face.table(tag) {
+                // Malformed tables are copied as is.
+                if let Some(data) =
+                    layout::instance(tag, data, &map, &variations).map_err(from_subset_error)?
+                {
+                    builder.set_table(tag, data);
+                }
+            }
+        }
+
+        builder.build().ok_or(InstanceError::Overflow)
+    }
+
+    /// Writes an `OS/2` table.
+    ///
+    /// Updates the weight and width classes from the `wght` and `wdth` axes,
+    /// the average character width and the `MVAR` metrics.
+    fn os2(&self, data: &[u8], metrics: &[(u16, i16)]) -> Result<Vec<u8>, InstanceError> {
+        if data.len() < WIDTH_CLASS_OFFSET + 2 {
+            return Err(InstanceError::MalformedFont);
+        }
+
+        let mut data = data.to_vec();
+        for (tag, value) in &self.values {
+            if *tag == Tag::from_bytes(b"wght") {
+                let weight = value.round().clamp(1.0, 1000.0);
+                set_u16(&mut data, WEIGHT_CLASS_OFFSET, weight as u16);
+            } else if *tag == Tag::from_bytes(b"wdth") {
+                let mut class = 0;
+                for (i, width) in WIDTH_CLASSES.iter().enumerate() {
+                    if (width - value).abs() < (WIDTH_CLASSES[class] - value).abs() {
+                        class = i;
+                    }
+                }
+
+                set_u16(&mut data, WIDTH_CLASS_OFFSET, class as u16 + 1);
+            }
+        }
+
+        let advances: Vec<u32> = metrics
+            .iter()
+            .map(|(advance, _)| u32::from(*advance))
+            .filter(|advance| *advance != 0)
+            .collect();
+        if !advances.is_empty() {
+            let sum: u32 = advances.iter().sum();
+            let average = (sum as f32 / advances.len() as f32).round();
+            set_u16(&mut data, AVG_CHAR_WIDTH_OFFSET, average as i16 as u16);
+        }
+
+        apply_metrics(&self.face, &mut data, OS2_METRICS)?;
+        Ok(data)
+    }
+}
+
+impl core::fmt::Debug for Instancer<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Instancer {{ ... }}")
+    }
+}
+
+/// An instanced glyph.
+pub(crate) struct Glyph {
+    /// The new bounding box.
+    bbox: Option<Rect>,
+    /// Deltas of the four phantom points.
+    phantom: [PointF; 4],
+    /// The change of the top side bearing.
+    top_offset: f32,
+}
+
+impl Glyph {
+    fn new(bbox: Option<Rect>) -> Self {
+        Glyph {
+            bbox,
+            phantom: [PointF { x: 0.0, y: 0.0 }; 4],
+            top_offset: 0.0,
+        }
+    }
+}
+
+fn union(a: Rect, b: Rect) -> Rect {
+    Rect {
+        x_min: a.x_min.min(b.x_min),
+        y_min: a.y_min.min(b.y_min),
+        x_max: a.x_max.max(b.x_max),
+        y_max: a.y_max.max(b.y_max),
+    }
+}
+
+fn to_i16(n: f32) -> Result<i16, InstanceError> {
+    i16::try_from(n.round() as i32).map_err(|_| InstanceError::Overflow)
+}
+
+fn to_u16(n: f32) -> Result<u16, InstanceError> {
+    // Negative advances are clamped to zero.
+    u16::try_from(n.round().max(0.0) as i32).map_err(|_| InstanceError::Overflow)
+}
+
+/// Updates maximum advance, minimum side bearings and maximum extent in a
+/// `hhea` or `vhea` table.
+///
+/// `f` returns the glyph extent along the advance direction.
+fn update_header(
+    data: &mut [u8],
+    metrics: &[(u16, i16)],
+    glyphs: &[Glyph],
+    f: impl Fn(Rect) -> (i16, i16),
+) {
+    if data.len() < MAX_EXTENT_OFFSET + 2 {
+        return;
+    }
+
+    let advance_max = metrics.iter().map(|(advance, _)| *advance).max();
+    set_u16(data, ADVANCE_MAX_OFFSET, advance_max.unwrap_or(0));
+
+    let mut min_side_bearing = i32::MAX;
+    let mut min_other_side_bearing = i32::MAX;
+    let mut max_extent = i32::MIN;
+    for ((advance, side_bearing), glyph) in metrics.iter().zip(glyphs) {
+        // Empty glyphs are ignored.
+        let (min, max) = match glyph.bbox {
+            Some(bbox) => f(bbox),
+            None => continue,
+        };
+
+        let extent = i32::from(*side_bearing) + i32::from(max) - i32::from(min);
+        min_side_bearing = min_side_bearing.min(i32::from(*side_bearing));
+        min_other_side_bearing = min_other_side_bearing.min(i32::from(*advance) - extent);
+        max_extent = max_extent.max(extent);
+    }
+
+    if max_extent != i32::MIN {
+        for (offset, n) in &[
+            (MIN_SIDE_BEARING_OFFSET, min_side_bearing),
+            (MIN_OTHER_SIDE_BEARING_OFFSET, min_other_side_bearing),
+            (MAX_EXTENT_OFFSET, max_extent),
+        ] {
+            let n = (*n).max(i32::from(i16::MIN)).min(i32::from(i16::MAX));
+            set_u16(data, *offset, n as i16 as u16);
+        }
+    }
+}
+
+/// Applies `MVAR` deltas to table fields.
+fn apply_metrics(
+    face: &Face,
+    data: &mut [u8],
+    fields: &[(&[u8; 4], usize, bool)],
+) -> Result<(), InstanceError> {
+    let mvar = match face.tables().mvar {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    for (tag, offset, signed) in fields {
+        // Older table versions may not have all fields.
+        let value = match Stream::read_at::<u16>(data, *offset) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let delta = match mvar.metric_offset(Tag::from_bytes(tag), face.variation_coordinates()) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let value = if *signed {
+            to_i16(f32::from(value as i16) + delta)? as u16
+        } else {
+            to_u16(f32::from(value) + delta)?
+        };
+        set_u16(data, *offset, value);
+    }
+
+    Ok(())
+}
+
+/// Writes a `cvt ` table with `cvar` deltas applied.
+fn cvt(face: &Face, data: &[u8]) -> Result<Vec<u8>, InstanceError> {
+    let cvar = match face
+        .raw_face()
+        .table(Tag::from_bytes(b"cvar"))
+        .and_then(crate::tables::cvar::Table::parse)
+    {
+        Some(v) => v,
+        None => return Ok(data.to_vec()),
+    };
+
+    let mut values: Vec<f32> = data
+        .chunks_exact(2)
+        .map(|n| f32::from(i16::from_be_bytes([n[0], n[1]])))
+        .collect();
+    cvar.apply(face.variation_coordinates(), &mut values)
+        .ok_or(InstanceError::MalformedFont)?;
+
+    let mut data = Vec::with_capacity(values.len() * 2);
+    for value in values {
+        data.extend_from_slice(&to_i16(value)?.to_be_bytes());
+    }
+
+    Ok(data)
+}
+
+/// Parses the item variation store of a `GDEF` table version 1.3.
+fn gdef_variation_store(data: &[u8]) -> Option<ItemVariationStore<'_>> {
+    let mut s = Stream::new(data);
+    let major_version = s.read::<u16>()?;
+    let minor_version = s.read::<u16>()?;
+    if major_version != 1 || minor_version < 3 {
+        return None;
+    }
+
+    s.advance(10); // glyph class, attach list, caret list, mark class and mark sets offsets
+    let offset = s.read::<u32>()?;
+    if offset == 0 {
+        return None;
+    }
+
+    let data = data.get(usize::try_from(offset).ok()?..)?;
+    ItemVariationStore::parse(Stream::new(data))
+}
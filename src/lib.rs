@@ -3,7 +3,7 @@ A high-level, safe, zero-allocation font parser for:
 * [TrueType](https://docs.microsoft.com/en-us/typography/truetype/),
 * [OpenType](https://docs.microsoft.com/en-us/typography/opentype/spec/), and
 * [AAT](https://developer.apple.com/fonts/TrueType-Reference-Manual/RM06/Chap6AATIntro.html)
-fonts.
+  fonts.
 
 Font parsing starts with a [`Face`].
 
@@ -61,11 +61,31 @@ mod aat;
 mod delta_set;
 #[cfg(feature = "opentype-layout")]
 mod ggg;
+#[cfg(feature = "hinting")]
+pub mod hinting;
+#[cfg(feature = "instance")]
+pub mod instance;
 mod language;
+mod owned;
 mod parser;
+mod resource_fork;
+#[cfg(feature = "shaping")]
+pub mod shaping;
+#[cfg(feature = "subset")]
+pub mod subset;
 mod tables;
+#[cfg(feature = "validate")]
+pub mod validate;
 #[cfg(feature = "variable-fonts")]
 mod var_store;
+#[cfg(feature = "woff")]
+pub mod woff;
+#[cfg(feature = "woff2")]
+pub mod woff2;
+#[cfg(feature = "writer")]
+pub mod writer;
+
+use core::convert::TryFrom;
 
 use head::IndexToLocationFormat;
 pub use parser::{Fixed, FromData, LazyArray16, LazyArray32, LazyArrayIter16, LazyArrayIter32};
@@ -77,15 +97,18 @@ pub use fvar::VariationAxis;
 pub use language::Language;
 pub use name::{name_id, PlatformId};
 pub use os2::{Permissions, ScriptMetrics, Style, UnicodeRanges, Weight, Width};
+pub use owned::OwnedFace;
+#[cfg(all(feature = "opentype-layout", feature = "variable-fonts"))]
+pub use tables::varc;
 pub use tables::CFFError;
 #[cfg(feature = "apple-layout")]
 pub use tables::{ankr, feat, kerx, morx, trak};
 #[cfg(feature = "variable-fonts")]
-pub use tables::{avar, cff2, fvar, gvar, hvar, mvar, vvar};
+pub use tables::{avar, cff2, cvar, fvar, gvar, hvar, mvar, vvar};
 pub use tables::{cbdt, cblc, cff1 as cff, vhea};
 pub use tables::{
-    cmap, colr, cpal, glyf, head, hhea, hmtx, kern, loca, maxp, name, os2, post, sbix, stat, svg,
-    vorg,
+    cmap, colr, cpal, cvt, glyf, head, hhea, hmtx, kern, loca, maxp, name, os2, post, sbix, stat,
+    svg, vorg,
 };
 #[cfg(feature = "opentype-layout")]
 pub use tables::{gdef, gpos, gsub, math};
@@ -703,7 +726,6 @@ pub struct RasterGlyphImage<'a> {
 #[allow(missing_docs)]
 pub struct TableRecord {
     pub tag: Tag,
-    #[allow(dead_code)]
     pub check_sum: u32,
     pub offset: u32,
     pub length: u32,
@@ -758,6 +780,68 @@ impl VarCoords {
     }
 }
 
+/// A location in the design space of a variable font.
+///
+/// Stores normalized coordinates with `avar` already applied.
+/// Can be created via [`VariationLocation::new`], [`Face::variation_location`]
+/// or [`Face::named_instance_location`] and then passed to the `*_at` methods
+/// of a [`Face`], like [`Face::outline_glyph_at`].
+///
+/// Unlike [`Face::set_variation`], doesn't require a mutable `Face`,
+/// so a single `Face` can be queried at multiple locations at the same time.
+/// The `*_at` methods use only the location coordinates and ignore the ones set on the `Face`.
+#[cfg(feature = "variable-fonts")]
+#[derive(Clone)]
+pub struct VariationLocation {
+    coordinates: VarCoords,
+}
+
+#[cfg(feature = "variable-fonts")]
+impl VariationLocation {
+    /// Creates a location from the current face variation coordinates.
+    ///
+    /// The location doesn't track the face, so later [`Face::set_variation`] calls
+    /// will not affect it.
+    #[inline]
+    pub fn new(face: &Face) -> Self {
+        VariationLocation {
+            coordinates: face.coordinates.clone(),
+        }
+    }
+
+    /// Returns the normalized coordinates, one per axis.
+    #[inline]
+    pub fn coordinates(&self) -> &[NormalizedCoordinate] {
+        self.coordinates.as_slice()
+    }
+
+    /// Checks that the location has non-default coordinates.
+    #[inline]
+    pub fn has_non_default_coordinates(&self) -> bool {
+        self.coordinates().iter().any(|c| c.0 != 0)
+    }
+}
+
+#[cfg(feature = "variable-fonts")]
+impl PartialEq for VariationLocation {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.coordinates() == other.coordinates()
+    }
+}
+
+#[cfg(feature = "variable-fonts")]
+impl Eq for VariationLocation {}
+
+#[cfg(feature = "variable-fonts")]
+impl core::fmt::Debug for VariationLocation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("VariationLocation")
+            .field(&self.coordinates())
+            .finish()
+    }
+}
+
 /// A list of font face parsing errors.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum FaceParsingError {
@@ -769,6 +853,12 @@ pub enum FaceParsingError {
     /// Face data must start with `0x00010000`, `0x74727565`, `0x4F54544F` or `0x74746366`.
     UnknownMagic,
 
+    /// Face data is a WOFF (`wOFF`) or WOFF 2.0 (`wOF2`) file.
+    ///
+    /// Such data must be decoded first via `woff::Woff` or `woff2::Woff2`,
+    /// which require the `woff` and `woff2` build features respectively.
+    WoffData,
+
     /// The face index is larger than the number of faces in the font.
     FaceIndexOutOfBounds,
 
@@ -787,6 +877,7 @@ impl core::fmt::Display for FaceParsingError {
         match self {
             FaceParsingError::MalformedFont => write!(f, "malformed font"),
             FaceParsingError::UnknownMagic => write!(f, "unknown magic"),
+            FaceParsingError::WoffData => write!(f, "WOFF data must be decoded first"),
             FaceParsingError::FaceIndexOutOfBounds => write!(f, "face index is out of bounds"),
             FaceParsingError::NoHeadTable => write!(f, "the head table is missing or malformed"),
             FaceParsingError::NoHheaTable => write!(f, "the hhea table is missing or malformed"),
@@ -798,6 +889,195 @@ impl core::fmt::Display for FaceParsingError {
 #[cfg(feature = "std")]
 impl std::error::Error for FaceParsingError {}
 
+/// A reason why a table was rejected.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TableParseErrorReason {
+    /// The table record points outside of the font data.
+    OutOfBounds,
+    /// A table required to parse this one is missing or malformed.
+    ///
+    /// For example, `glyf` cannot be parsed without `loca`.
+    MissingDependency(Tag),
+    /// The table data is malformed.
+    Malformed,
+}
+
+/// A table parsing error.
+///
+/// Unlike [`FaceParsingError`], doesn't prevent a face from being parsed.
+/// The table is simply treated as missing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TableParseError {
+    /// The table tag.
+    pub tag: Tag,
+    /// The table offset from the start of the font data.
+    ///
+    /// Points to the start of the table and not to the malformed data inside it,
+    /// since tables are parsed lazily and the exact position is not tracked.
+    pub table_offset: u32,
+    /// Why the table was rejected.
+    pub reason: TableParseErrorReason,
+}
+
+impl core::fmt::Display for TableParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "the '{}' table at offset {} ",
+            self.tag, self.table_offset
+        )?;
+        match self.reason {
+            TableParseErrorReason::OutOfBounds => write!(f, "is out of bounds"),
+            TableParseErrorReason::MissingDependency(tag) => {
+                write!(f, "requires a valid '{}' table", tag)
+            }
+            TableParseErrorReason::Malformed => write!(f, "is malformed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TableParseError {}
+
+const MAX_FAILED_TABLES: usize = 32;
+
+/// A list of tables that are present in a face, but failed to parse.
+///
+/// Stores up to 32 errors, which is more than a font usually has tables.
+/// Can be retrieved via [`Face::parse_with_report`].
+#[derive(Clone, Copy)]
+pub struct FailedTables {
+    errors: [TableParseError; MAX_FAILED_TABLES],
+    len: u8,
+}
+
+impl Default for FailedTables {
+    fn default() -> Self {
+        FailedTables {
+            errors: [TableParseError {
+                tag: Tag(0),
+                table_offset: 0,
+                reason: TableParseErrorReason::Malformed,
+            }; MAX_FAILED_TABLES],
+            len: 0,
+        }
+    }
+}
+
+impl core::fmt::Debug for FailedTables {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl FailedTables {
+    /// Returns the number of failed tables.
+    #[inline]
+    pub fn len(&self) -> usize {
+        usize::from(self.len)
+    }
+
+    /// Checks that all present tables were parsed successfully.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an error for a specific table.
+    #[inline]
+    pub fn get(&self, tag: Tag) -> Option<TableParseError> {
+        self.iter().find(|e| e.tag == tag)
+    }
+
+    /// Returns an iterator over errors.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = TableParseError> + '_ {
+        self.errors[..self.len()].iter().copied()
+    }
+
+    fn push(&mut self, error: TableParseError) {
+        if let Some(index) = self.errors[..self.len()]
+            .iter()
+            .position(|e| e.tag == error.tag)
+        {
+            self.errors[index] = error;
+        } else if self.len() < MAX_FAILED_TABLES {
+            self.errors[self.len()] = error;
+            self.len += 1;
+        }
+    }
+}
+
+/// Parses tables and records the failed ones.
+struct TablesParser<'a> {
+    // The whole font data. Empty for faces created from raw tables.
+    data: &'a [u8],
+    failed: FailedTables,
+}
+
+impl<'a> TablesParser<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        TablesParser {
+            data,
+            failed: FailedTables::default(),
+        }
+    }
+
+    fn offset(&self, table: &[u8]) -> u32 {
+        if self.data.is_empty() {
+            return 0;
+        }
+
+        let offset = (table.as_ptr() as usize).wrapping_sub(self.data.as_ptr() as usize);
+        u32::try_from(offset).unwrap_or(0)
+    }
+
+    fn fail(&mut self, tag: &[u8; 4], table: &[u8], reason: TableParseErrorReason) {
+        let error = TableParseError {
+            tag: Tag::from_bytes(tag),
+            table_offset: self.offset(table),
+            reason,
+        };
+        self.failed.push(error);
+    }
+
+    fn parse<T>(
+        &mut self,
+        tag: &[u8; 4],
+        data: Option<&'a [u8]>,
+        f: impl FnOnce(&'a [u8]) -> Option<T>,
+    ) -> Option<T> {
+        let data = data?;
+        let table = f(data);
+        if table.is_none() {
+            self.fail(tag, data, TableParseErrorReason::Malformed);
+        }
+
+        table
+    }
+
+    /// Parses a table that requires another table.
+    fn parse_with<T, D>(
+        &mut self,
+        tag: &[u8; 4],
+        data: Option<&'a [u8]>,
+        dependency_tag: &[u8; 4],
+        dependency: Option<D>,
+        f: impl FnOnce(D, &'a [u8]) -> Option<T>,
+    ) -> Option<T> {
+        let data = data?;
+        match dependency {
+            Some(dependency) => self.parse(tag, Some(data), |data| f(dependency, data)),
+            None => {
+                let reason =
+                    TableParseErrorReason::MissingDependency(Tag::from_bytes(dependency_tag));
+                self.fail(tag, data, reason);
+                None
+            }
+        }
+    }
+}
+
 /// A raw font face.
 ///
 /// You are probably looking for [`Face`]. This is a low-level type.
@@ -834,11 +1114,19 @@ impl<'a> RawFace<'a> {
     /// Set to 0 if unsure.
     ///
     /// While we do reuse [`FaceParsingError`], `No*Table` errors will not be throws.
+    ///
+    /// WOFF data is not supported and results in [`FaceParsingError::WoffData`].
     pub fn parse(data: &'a [u8], index: u32) -> Result<Self, FaceParsingError> {
         // https://docs.microsoft.com/en-us/typography/opentype/spec/otff#organization-of-an-opentype-font
 
         let mut s = Stream::new(data);
 
+        // 'wOFF' and 'wOF2'
+        let signature = s.clone().read::<u32>();
+        if signature == Some(0x774F4646) || signature == Some(0x774F4632) {
+            return Err(FaceParsingError::WoffData);
+        }
+
         // Read **font** magic.
         let magic = s.read::<Magic>().ok_or(FaceParsingError::UnknownMagic)?;
         if magic == Magic::FontCollection {
@@ -887,6 +1175,43 @@ impl<'a> RawFace<'a> {
         })
     }
 
+    /// Creates a new [`RawFace`] from an Apple resource fork.
+    ///
+    /// `index` indicates the specific `sfnt` resource.
+    /// Use [`fonts_in_resource_fork`] to get the total number of font faces.
+    ///
+    /// [`RawFace::data`] will contain only the resource data and not the whole fork.
+    pub fn parse_resource_fork(data: &'a [u8], index: u32) -> Result<Self, FaceParsingError> {
+        Self::parse(resource_fork::sfnt_resource(data, index)?, 0)
+    }
+
+    /// Returns the "sfnt version" of the face.
+    ///
+    /// Like `0x00010000` for TrueType and `OTTO` for CFF-based fonts.
+    ///
+    /// Read from the table directory, which precedes table records.
+    /// Returns `None` when table records are not stored in `data`,
+    /// like for faces created manually.
+    pub fn sfnt_version(&self) -> Option<u32> {
+        // The table directory header is 12 bytes long.
+        let start = self.table_records_range()?.start.checked_sub(12)?;
+        Stream::read_at::<u32>(self.data, start)
+    }
+
+    /// Returns the table records position in `data`.
+    ///
+    /// Returns `None` when table records are not stored in `data`.
+    pub(crate) fn table_records_range(&self) -> Option<core::ops::Range<usize>> {
+        let records = self.table_records.as_bytes();
+        let start = (records.as_ptr() as usize).checked_sub(self.data.as_ptr() as usize)?;
+        let end = start.checked_add(records.len())?;
+        if end > self.data.len() {
+            return None;
+        }
+
+        Some(start..end)
+    }
+
     /// Returns the raw data of a selected table.
     pub fn table(&self, tag: Tag) -> Option<&'a [u8]> {
         let (_, table) = self
@@ -897,6 +1222,85 @@ impl<'a> RawFace<'a> {
         let end = offset.checked_add(length)?;
         self.data.get(offset..end)
     }
+
+    /// Calculates a table checksum.
+    ///
+    /// `head.checkSumAdjustment` is treated as zero, as required by the spec.
+    ///
+    /// Returns `None` when the table record points outside of the font data.
+    pub fn calculate_table_checksum(&self, record: &TableRecord) -> Option<u32> {
+        let offset = usize::num_from(record.offset);
+        let end = offset.checked_add(usize::num_from(record.length))?;
+        let data = self.data.get(offset..end)?;
+
+        let mut sum = checksum(data);
+        if record.tag == Tag::from_bytes(b"head") {
+            if let Some(adjustment) = Stream::read_at::<u32>(data, 8) {
+                sum = sum.wrapping_sub(adjustment);
+            }
+        }
+
+        Some(sum)
+    }
+
+    /// Returns records of tables with mismatched checksums.
+    ///
+    /// Records that point outside of the font data are returned as well.
+    pub fn mismatched_table_checksums(&self) -> impl Iterator<Item = TableRecord> + 'a {
+        let face = *self;
+        self.table_records
+            .into_iter()
+            .filter(move |record| face.calculate_table_checksum(record) != Some(record.check_sum))
+    }
+
+    /// Calculates `head.checkSumAdjustment`.
+    ///
+    /// The face checksum is calculated from the table directory and table checksums.
+    /// Which is the same as the whole file checksum for a well-formed font,
+    /// but also works for faces in a font collection.
+    /// Note that fonts in a collection are often left with the adjustment
+    /// of the original standalone font, so a mismatch is not necessarily an error.
+    ///
+    /// Returns `None` when a table record points outside of the font data.
+    pub fn calculate_checksum_adjustment(&self) -> Option<u32> {
+        // The table directory is preceded by a 12 bytes header.
+        let records = self.table_records.as_bytes();
+        let records_start = (records.as_ptr() as usize).wrapping_sub(self.data.as_ptr() as usize);
+        let records_end = records_start.checked_add(records.len())?;
+        let directory = self.data.get(records_start.checked_sub(12)?..records_end)?;
+
+        let mut sum = checksum(directory);
+        for record in self.table_records {
+            sum = sum.wrapping_add(self.calculate_table_checksum(&record)?);
+        }
+
+        Some(0xB1B0AFBA_u32.wrapping_sub(sum))
+    }
+
+    /// Checks that `head.checkSumAdjustment` matches the face data.
+    ///
+    /// Returns `None` when there is no `head` table
+    /// or when a table record points outside of the font data.
+    pub fn is_checksum_adjustment_valid(&self) -> Option<bool> {
+        let head = self.table(Tag::from_bytes(b"head"))?;
+        let adjustment = Stream::read_at::<u32>(head, 8)?;
+        Some(self.calculate_checksum_adjustment()? == adjustment)
+    }
+}
+
+/// Calculates a checksum.
+///
+/// The data is padded with zeros to a multiple of four bytes.
+pub(crate) fn checksum(data: &[u8]) -> u32 {
+    let mut sum = 0u32;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        sum = sum.wrapping_add(u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+    }
+
+    let mut last = [0; 4];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    sum.wrapping_add(u32::from_be_bytes(last))
 }
 
 impl core::fmt::Debug for RawFace<'_> {
@@ -979,6 +1383,16 @@ pub struct RawFaceTables<'a> {
     pub mvar: Option<&'a [u8]>,
     #[cfg(feature = "variable-fonts")]
     pub vvar: Option<&'a [u8]>,
+    #[cfg(feature = "variable-fonts")]
+    pub cvar: Option<&'a [u8]>,
+    #[cfg(all(feature = "opentype-layout", feature = "variable-fonts"))]
+    pub varc: Option<&'a [u8]>,
+
+    pub cvt: Option<&'a [u8]>,
+    #[cfg(feature = "hinting")]
+    pub fpgm: Option<&'a [u8]>,
+    #[cfg(feature = "hinting")]
+    pub prep: Option<&'a [u8]>,
 }
 
 /// Parsed face tables.
@@ -1050,6 +1464,19 @@ pub struct FaceTables<'a> {
     pub mvar: Option<mvar::Table<'a>>,
     #[cfg(feature = "variable-fonts")]
     pub vvar: Option<vvar::Table<'a>>,
+    #[cfg(feature = "variable-fonts")]
+    pub cvar: Option<cvar::Table<'a>>,
+    #[cfg(all(feature = "opentype-layout", feature = "variable-fonts"))]
+    pub varc: Option<varc::Table<'a>>,
+
+    /// The control value table.
+    pub cvt: Option<cvt::Table<'a>>,
+    /// The font program.
+    #[cfg(feature = "hinting")]
+    pub fpgm: Option<&'a [u8]>,
+    /// The control value program.
+    #[cfg(feature = "hinting")]
+    pub prep: Option<&'a [u8]>,
 }
 
 /// A font face.
@@ -1063,8 +1490,7 @@ pub struct FaceTables<'a> {
 /// when needed, get required data and forget about it.
 /// That's why the initial parsing is highly optimized and should not become a bottleneck.
 ///
-/// If you still want to store `Face` - checkout
-/// [owned_ttf_parser](https://crates.io/crates/owned_ttf_parser). Requires `unsafe`.
+/// If you still want to store `Face` - checkout [`OwnedFace`].
 ///
 /// While `Face` is technically copyable, we disallow it because it's almost 2KB big.
 #[derive(Clone)]
@@ -1073,6 +1499,9 @@ pub struct Face<'a> {
     tables: FaceTables<'a>, // Parsed tables.
     #[cfg(feature = "variable-fonts")]
     coordinates: VarCoords,
+    // Coordinates before the `avar` mapping.
+    #[cfg(feature = "variable-fonts")]
+    unmapped_coordinates: VarCoords,
 }
 
 impl<'a> Face<'a> {
@@ -1088,6 +1517,7 @@ impl<'a> Face<'a> {
     /// Required tables: `head`, `hhea` and `maxp`.
     ///
     /// If an optional table has invalid data it will be skipped.
+    /// Use [`Face::parse_with_report`] to find out why.
     #[deprecated(since = "0.16.0", note = "use `parse` instead")]
     pub fn from_slice(data: &'a [u8], index: u32) -> Result<Self, FaceParsingError> {
         Self::parse(data, index)
@@ -1105,39 +1535,95 @@ impl<'a> Face<'a> {
     /// Required tables: `head`, `hhea` and `maxp`.
     ///
     /// If an optional table has invalid data it will be skipped.
+    /// Use [`Face::parse_with_report`] to find out why.
+    ///
+    /// WOFF and WOFF 2.0 data must be decoded first via `woff::Woff` and `woff2::Woff2`.
+    /// Otherwise, [`FaceParsingError::WoffData`] is returned.
     pub fn parse(data: &'a [u8], index: u32) -> Result<Self, FaceParsingError> {
         let raw_face = RawFace::parse(data, index)?;
-        let raw_tables = Self::collect_tables(raw_face);
+        Self::from_raw_face(raw_face)
+    }
+
+    /// Creates a new [`Face`] from a raw data and reports tables that failed to parse.
+    ///
+    /// Same as [`Face::parse`], but also returns optional tables that are present,
+    /// but were skipped because of invalid data.
+    pub fn parse_with_report(
+        data: &'a [u8],
+        index: u32,
+    ) -> Result<(Self, FailedTables), FaceParsingError> {
+        let raw_face = RawFace::parse(data, index)?;
+        Self::from_raw_face_with_report(raw_face)
+    }
+
+    /// Creates a new [`Face`] from an Apple resource fork.
+    ///
+    /// `index` indicates the specific `sfnt` resource.
+    /// Use [`fonts_in_resource_fork`] to get the total number of font faces.
+    ///
+    /// See [`Face::parse`] for details.
+    pub fn parse_resource_fork(data: &'a [u8], index: u32) -> Result<Self, FaceParsingError> {
+        Self::from_raw_face(RawFace::parse_resource_fork(data, index)?)
+    }
+
+    pub(crate) fn from_raw_face(raw_face: RawFace<'a>) -> Result<Self, FaceParsingError> {
+        Self::from_raw_face_with_report(raw_face).map(|(face, _)| face)
+    }
+
+    pub(crate) fn from_raw_face_with_report(
+        raw_face: RawFace<'a>,
+    ) -> Result<(Self, FailedTables), FaceParsingError> {
+        let mut parser = TablesParser::new(raw_face.data);
+        let raw_tables = Self::collect_tables(raw_face, &mut parser);
+        let tables = Self::parse_tables(raw_tables, &mut parser)?;
+        Ok((Self::from_parts(raw_face, tables), parser.failed))
+    }
+
+    /// Creates a new [`Face`] from already parsed mandatory tables.
+    ///
+    /// Unlike [`Face::from_raw_face`], cannot fail. Mandatory tables in `raw_face` are ignored.
+    pub(crate) fn from_mandatory_tables(
+        raw_face: RawFace<'a>,
+        head: head::Table,
+        hhea: hhea::Table,
+        maxp: maxp::Table,
+    ) -> Self {
+        let mut parser = TablesParser::new(raw_face.data);
+        let raw_tables = Self::collect_tables(raw_face, &mut parser);
+        let tables = Self::parse_optional_tables(head, hhea, maxp, raw_tables, &mut parser);
+        Self::from_parts(raw_face, tables)
+    }
 
+    fn from_parts(raw_face: RawFace<'a>, tables: FaceTables<'a>) -> Self {
         #[allow(unused_mut)]
         let mut face = Face {
             raw_face,
             #[cfg(feature = "variable-fonts")]
             coordinates: VarCoords::default(),
-            tables: Self::parse_tables(raw_tables)?,
+            #[cfg(feature = "variable-fonts")]
+            unmapped_coordinates: VarCoords::default(),
+            tables,
         };
 
         #[cfg(feature = "variable-fonts")]
         {
             if let Some(ref fvar) = face.tables.fvar {
                 face.coordinates.len = fvar.axes.len().min(MAX_VAR_COORDS as u16) as u8;
+                face.unmapped_coordinates.len = face.coordinates.len;
             }
         }
 
-        Ok(face)
+        face
     }
 
-    fn collect_tables(raw_face: RawFace<'a>) -> RawFaceTables<'a> {
+    fn collect_tables(raw_face: RawFace<'a>, parser: &mut TablesParser) -> RawFaceTables<'a> {
         let mut tables = RawFaceTables::default();
 
         for record in raw_face.table_records {
             let start = usize::num_from(record.offset);
-            let end = match start.checked_add(usize::num_from(record.length)) {
-                Some(v) => v,
-                None => continue,
-            };
-
-            let table_data = raw_face.data.get(start..end);
+            let table_data = start
+                .checked_add(usize::num_from(record.length))
+                .and_then(|end| raw_face.data.get(start..end));
             match &record.tag.to_bytes() {
                 b"bdat" => tables.bdat = table_data,
                 b"bloc" => tables.bloc = table_data,
@@ -1167,15 +1653,22 @@ impl<'a> Face<'a> {
                 b"VORG" => tables.vorg = table_data,
                 #[cfg(feature = "variable-fonts")]
                 b"VVAR" => tables.vvar = table_data,
+                #[cfg(all(feature = "opentype-layout", feature = "variable-fonts"))]
+                b"VARC" => tables.varc = table_data,
                 #[cfg(feature = "apple-layout")]
                 b"ankr" => tables.ankr = table_data,
                 #[cfg(feature = "variable-fonts")]
                 b"avar" => tables.avar = table_data,
                 b"cmap" => tables.cmap = table_data,
+                #[cfg(feature = "variable-fonts")]
+                b"cvar" => tables.cvar = table_data,
+                b"cvt " => tables.cvt = table_data,
                 #[cfg(feature = "apple-layout")]
                 b"feat" => tables.feat = table_data,
                 #[cfg(feature = "variable-fonts")]
                 b"fvar" => tables.fvar = table_data,
+                #[cfg(feature = "hinting")]
+                b"fpgm" => tables.fpgm = table_data,
                 b"glyf" => tables.glyf = table_data,
                 #[cfg(feature = "variable-fonts")]
                 b"gvar" => tables.gvar = table_data,
@@ -1191,13 +1684,23 @@ impl<'a> Face<'a> {
                 b"morx" => tables.morx = table_data,
                 b"name" => tables.name = table_data,
                 b"post" => tables.post = table_data,
+                #[cfg(feature = "hinting")]
+                b"prep" => tables.prep = table_data,
                 b"sbix" => tables.sbix = table_data,
                 b"STAT" => tables.stat = table_data,
                 #[cfg(feature = "apple-layout")]
                 b"trak" => tables.trak = table_data,
                 b"vhea" => tables.vhea = table_data,
                 b"vmtx" => tables.vmtx = table_data,
-                _ => {}
+                _ => continue,
+            }
+
+            if table_data.is_none() {
+                parser.failed.push(TableParseError {
+                    tag: record.tag,
+                    table_offset: record.offset,
+                    reason: TableParseErrorReason::OutOfBounds,
+                });
             }
         }
 
@@ -1206,160 +1709,145 @@ impl<'a> Face<'a> {
 
     /// Creates a new [`Face`] from provided [`RawFaceTables`].
     pub fn from_raw_tables(raw_tables: RawFaceTables<'a>) -> Result<Self, FaceParsingError> {
-        #[allow(unused_mut)]
-        let mut face = Face {
-            raw_face: RawFace {
-                data: &[],
-                table_records: LazyArray16::default(),
-            },
-            #[cfg(feature = "variable-fonts")]
-            coordinates: VarCoords::default(),
-            tables: Self::parse_tables(raw_tables)?,
+        let mut parser = TablesParser::new(&[]);
+        let raw_face = RawFace {
+            data: &[],
+            table_records: LazyArray16::default(),
         };
-
-        #[cfg(feature = "variable-fonts")]
-        {
-            if let Some(ref fvar) = face.tables.fvar {
-                face.coordinates.len = fvar.axes.len().min(MAX_VAR_COORDS as u16) as u8;
-            }
-        }
-
-        Ok(face)
+        Ok(Self::from_parts(
+            raw_face,
+            Self::parse_tables(raw_tables, &mut parser)?,
+        ))
     }
 
-    fn parse_tables(raw_tables: RawFaceTables<'a>) -> Result<FaceTables<'a>, FaceParsingError> {
+    fn parse_tables(
+        raw_tables: RawFaceTables<'a>,
+        p: &mut TablesParser<'a>,
+    ) -> Result<FaceTables<'a>, FaceParsingError> {
         let head = head::Table::parse(raw_tables.head).ok_or(FaceParsingError::NoHeadTable)?;
         let hhea = hhea::Table::parse(raw_tables.hhea).ok_or(FaceParsingError::NoHheaTable)?;
         let maxp = maxp::Table::parse(raw_tables.maxp).ok_or(FaceParsingError::NoMaxpTable)?;
+        Ok(Self::parse_optional_tables(head, hhea, maxp, raw_tables, p))
+    }
 
-        let hmtx = raw_tables.hmtx.and_then(|data| {
+    fn parse_optional_tables(
+        head: head::Table,
+        hhea: hhea::Table,
+        maxp: maxp::Table,
+        raw_tables: RawFaceTables<'a>,
+        p: &mut TablesParser<'a>,
+    ) -> FaceTables<'a> {
+        let hmtx = p.parse(b"hmtx", raw_tables.hmtx, |data| {
             hmtx::Table::parse(hhea.number_of_metrics, maxp.number_of_glyphs, data)
         });
 
-        let vhea = raw_tables.vhea.and_then(vhea::Table::parse);
-        let vmtx = if let Some(vhea) = vhea {
-            raw_tables.vmtx.and_then(|data| {
-                hmtx::Table::parse(vhea.number_of_metrics, maxp.number_of_glyphs, data)
-            })
-        } else {
-            None
-        };
+        let vhea = p.parse(b"vhea", raw_tables.vhea, vhea::Table::parse);
+        let vmtx = p.parse_with(b"vmtx", raw_tables.vmtx, b"vhea", vhea, |vhea, data| {
+            hmtx::Table::parse(vhea.number_of_metrics, maxp.number_of_glyphs, data)
+        });
 
-        let loca = raw_tables.loca.and_then(|data| {
+        let loca = p.parse(b"loca", raw_tables.loca, |data| {
             loca::Table::parse(maxp.number_of_glyphs, head.index_to_location_format, data)
         });
-        let glyf = if let Some(loca) = loca {
-            raw_tables
-                .glyf
-                .and_then(|data| glyf::Table::parse(loca, data))
-        } else {
-            None
-        };
+        let glyf = p.parse_with(b"glyf", raw_tables.glyf, b"loca", loca, glyf::Table::parse);
 
-        let bdat = if let Some(bloc) = raw_tables.bloc.and_then(cblc::Table::parse) {
-            raw_tables
-                .bdat
-                .and_then(|data| cbdt::Table::parse(bloc, data))
-        } else {
-            None
-        };
+        let bloc = p.parse(b"bloc", raw_tables.bloc, cblc::Table::parse);
+        let bdat = p.parse_with(b"bdat", raw_tables.bdat, b"bloc", bloc, cbdt::Table::parse);
 
-        let cbdt = if let Some(cblc) = raw_tables.cblc.and_then(cblc::Table::parse) {
-            raw_tables
-                .cbdt
-                .and_then(|data| cbdt::Table::parse(cblc, data))
-        } else {
-            None
-        };
+        let cblc = p.parse(b"CBLC", raw_tables.cblc, cblc::Table::parse);
+        let cbdt = p.parse_with(b"CBDT", raw_tables.cbdt, b"CBLC", cblc, cbdt::Table::parse);
 
-        let ebdt = if let Some(eblc) = raw_tables.eblc.and_then(cblc::Table::parse) {
-            raw_tables
-                .ebdt
-                .and_then(|data| cbdt::Table::parse(eblc, data))
-        } else {
-            None
-        };
+        let eblc = p.parse(b"EBLC", raw_tables.eblc, cblc::Table::parse);
+        let ebdt = p.parse_with(b"EBDT", raw_tables.ebdt, b"EBLC", eblc, cbdt::Table::parse);
 
-        let cpal = raw_tables.cpal.and_then(cpal::Table::parse);
-        let colr = if let Some(cpal) = cpal {
-            raw_tables
-                .colr
-                .and_then(|data| colr::Table::parse(cpal, data))
-        } else {
-            None
-        };
+        let cpal = p.parse(b"CPAL", raw_tables.cpal, cpal::Table::parse);
+        let colr = p.parse_with(b"COLR", raw_tables.colr, b"CPAL", cpal, colr::Table::parse);
 
-        Ok(FaceTables {
+        FaceTables {
             head,
             hhea,
             maxp,
 
             bdat,
             cbdt,
-            cff: raw_tables.cff.and_then(cff::Table::parse),
-            cmap: raw_tables.cmap.and_then(cmap::Table::parse),
+            cff: p.parse(b"CFF ", raw_tables.cff, cff::Table::parse),
+            cmap: p.parse(b"cmap", raw_tables.cmap, cmap::Table::parse),
             colr,
             ebdt,
             glyf,
             hmtx,
-            kern: raw_tables.kern.and_then(kern::Table::parse),
-            name: raw_tables.name.and_then(name::Table::parse),
-            os2: raw_tables.os2.and_then(os2::Table::parse),
-            post: raw_tables.post.and_then(post::Table::parse),
-            sbix: raw_tables
-                .sbix
-                .and_then(|data| sbix::Table::parse(maxp.number_of_glyphs, data)),
-            stat: raw_tables.stat.and_then(stat::Table::parse),
-            svg: raw_tables.svg.and_then(svg::Table::parse),
-            vhea: raw_tables.vhea.and_then(vhea::Table::parse),
+            kern: p.parse(b"kern", raw_tables.kern, kern::Table::parse),
+            name: p.parse(b"name", raw_tables.name, name::Table::parse),
+            os2: p.parse(b"OS/2", raw_tables.os2, os2::Table::parse),
+            post: p.parse(b"post", raw_tables.post, post::Table::parse),
+            sbix: p.parse(b"sbix", raw_tables.sbix, |data| {
+                sbix::Table::parse(maxp.number_of_glyphs, data)
+            }),
+            stat: p.parse(b"STAT", raw_tables.stat, stat::Table::parse),
+            svg: p.parse(b"SVG ", raw_tables.svg, svg::Table::parse),
+            vhea,
             vmtx,
-            vorg: raw_tables.vorg.and_then(vorg::Table::parse),
+            vorg: p.parse(b"VORG", raw_tables.vorg, vorg::Table::parse),
 
             #[cfg(feature = "opentype-layout")]
-            gdef: raw_tables.gdef.and_then(gdef::Table::parse),
+            gdef: p.parse(b"GDEF", raw_tables.gdef, gdef::Table::parse),
             #[cfg(feature = "opentype-layout")]
-            gpos: raw_tables
-                .gpos
-                .and_then(opentype_layout::LayoutTable::parse),
+            gpos: p.parse(
+                b"GPOS",
+                raw_tables.gpos,
+                opentype_layout::LayoutTable::parse,
+            ),
             #[cfg(feature = "opentype-layout")]
-            gsub: raw_tables
-                .gsub
-                .and_then(opentype_layout::LayoutTable::parse),
+            gsub: p.parse(
+                b"GSUB",
+                raw_tables.gsub,
+                opentype_layout::LayoutTable::parse,
+            ),
             #[cfg(feature = "opentype-layout")]
-            math: raw_tables.math.and_then(math::Table::parse),
+            math: p.parse(b"MATH", raw_tables.math, math::Table::parse),
 
             #[cfg(feature = "apple-layout")]
-            ankr: raw_tables
-                .ankr
-                .and_then(|data| ankr::Table::parse(maxp.number_of_glyphs, data)),
+            ankr: p.parse(b"ankr", raw_tables.ankr, |data| {
+                ankr::Table::parse(maxp.number_of_glyphs, data)
+            }),
             #[cfg(feature = "apple-layout")]
-            feat: raw_tables.feat.and_then(feat::Table::parse),
+            feat: p.parse(b"feat", raw_tables.feat, feat::Table::parse),
             #[cfg(feature = "apple-layout")]
-            kerx: raw_tables
-                .kerx
-                .and_then(|data| kerx::Table::parse(maxp.number_of_glyphs, data)),
+            kerx: p.parse(b"kerx", raw_tables.kerx, |data| {
+                kerx::Table::parse(maxp.number_of_glyphs, data)
+            }),
             #[cfg(feature = "apple-layout")]
-            morx: raw_tables
-                .morx
-                .and_then(|data| morx::Table::parse(maxp.number_of_glyphs, data)),
+            morx: p.parse(b"morx", raw_tables.morx, |data| {
+                morx::Table::parse(maxp.number_of_glyphs, data)
+            }),
             #[cfg(feature = "apple-layout")]
-            trak: raw_tables.trak.and_then(trak::Table::parse),
+            trak: p.parse(b"trak", raw_tables.trak, trak::Table::parse),
 
             #[cfg(feature = "variable-fonts")]
-            avar: raw_tables.avar.and_then(avar::Table::parse),
+            avar: p.parse(b"avar", raw_tables.avar, avar::Table::parse),
             #[cfg(feature = "variable-fonts")]
-            cff2: raw_tables.cff2.and_then(cff2::Table::parse),
+            cff2: p.parse(b"CFF2", raw_tables.cff2, cff2::Table::parse),
             #[cfg(feature = "variable-fonts")]
-            fvar: raw_tables.fvar.and_then(fvar::Table::parse),
+            fvar: p.parse(b"fvar", raw_tables.fvar, fvar::Table::parse),
             #[cfg(feature = "variable-fonts")]
-            gvar: raw_tables.gvar.and_then(gvar::Table::parse),
+            gvar: p.parse(b"gvar", raw_tables.gvar, gvar::Table::parse),
             #[cfg(feature = "variable-fonts")]
-            hvar: raw_tables.hvar.and_then(hvar::Table::parse),
+            hvar: p.parse(b"HVAR", raw_tables.hvar, hvar::Table::parse),
             #[cfg(feature = "variable-fonts")]
-            mvar: raw_tables.mvar.and_then(mvar::Table::parse),
+            mvar: p.parse(b"MVAR", raw_tables.mvar, mvar::Table::parse),
             #[cfg(feature = "variable-fonts")]
-            vvar: raw_tables.vvar.and_then(vvar::Table::parse),
-        })
+            vvar: p.parse(b"VVAR", raw_tables.vvar, vvar::Table::parse),
+            #[cfg(feature = "variable-fonts")]
+            cvar: p.parse(b"cvar", raw_tables.cvar, cvar::Table::parse),
+            #[cfg(all(feature = "opentype-layout", feature = "variable-fonts"))]
+            varc: p.parse(b"VARC", raw_tables.varc, varc::Table::parse),
+
+            cvt: p.parse(b"cvt ", raw_tables.cvt, cvt::Table::parse),
+            #[cfg(feature = "hinting")]
+            fpgm: raw_tables.fpgm,
+            #[cfg(feature = "hinting")]
+            prep: raw_tables.prep,
+        }
     }
 
     /// Returns low-level face tables.
@@ -1497,10 +1985,24 @@ impl<'a> Face<'a> {
     /// This method is affected by variation axes.
     #[inline]
     pub fn ascender(&self) -> i16 {
+        self.ascender_impl(self.coords())
+    }
+
+    /// Returns a horizontal face ascender at the specified variation location.
+    ///
+    /// See [`Face::ascender`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn ascender_at(&self, location: &VariationLocation) -> i16 {
+        self.ascender_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn ascender_impl(&self, coords: &[NormalizedCoordinate]) -> i16 {
         if let Some(os_2) = self.tables.os2 {
             if os_2.use_typographic_metrics() {
                 let value = os_2.typographic_ascender();
-                return self.apply_metrics_variation(Tag::from_bytes(b"hasc"), value);
+                return self.apply_metrics_variation(Tag::from_bytes(b"hasc"), value, coords);
             }
         }
 
@@ -1510,9 +2012,9 @@ impl<'a> Face<'a> {
                 value = os_2.typographic_ascender();
                 if value == 0 {
                     value = os_2.windows_ascender();
-                    value = self.apply_metrics_variation(Tag::from_bytes(b"hcla"), value);
+                    value = self.apply_metrics_variation(Tag::from_bytes(b"hcla"), value, coords);
                 } else {
-                    value = self.apply_metrics_variation(Tag::from_bytes(b"hasc"), value);
+                    value = self.apply_metrics_variation(Tag::from_bytes(b"hasc"), value, coords);
                 }
             }
         }
@@ -1525,10 +2027,24 @@ impl<'a> Face<'a> {
     /// This method is affected by variation axes.
     #[inline]
     pub fn descender(&self) -> i16 {
+        self.descender_impl(self.coords())
+    }
+
+    /// Returns a horizontal face descender at the specified variation location.
+    ///
+    /// See [`Face::descender`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn descender_at(&self, location: &VariationLocation) -> i16 {
+        self.descender_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn descender_impl(&self, coords: &[NormalizedCoordinate]) -> i16 {
         if let Some(os_2) = self.tables.os2 {
             if os_2.use_typographic_metrics() {
                 let value = os_2.typographic_descender();
-                return self.apply_metrics_variation(Tag::from_bytes(b"hdsc"), value);
+                return self.apply_metrics_variation(Tag::from_bytes(b"hdsc"), value, coords);
             }
         }
 
@@ -1538,9 +2054,9 @@ impl<'a> Face<'a> {
                 value = os_2.typographic_descender();
                 if value == 0 {
                     value = os_2.windows_descender();
-                    value = self.apply_metrics_variation(Tag::from_bytes(b"hcld"), value);
+                    value = self.apply_metrics_variation(Tag::from_bytes(b"hcld"), value, coords);
                 } else {
-                    value = self.apply_metrics_variation(Tag::from_bytes(b"hdsc"), value);
+                    value = self.apply_metrics_variation(Tag::from_bytes(b"hdsc"), value, coords);
                 }
             }
         }
@@ -1553,7 +2069,21 @@ impl<'a> Face<'a> {
     /// This method is affected by variation axes.
     #[inline]
     pub fn height(&self) -> i16 {
-        self.ascender() - self.descender()
+        self.height_impl(self.coords())
+    }
+
+    /// Returns face's height at the specified variation location.
+    ///
+    /// See [`Face::height`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn height_at(&self, location: &VariationLocation) -> i16 {
+        self.height_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn height_impl(&self, coords: &[NormalizedCoordinate]) -> i16 {
+        self.ascender_impl(coords) - self.descender_impl(coords)
     }
 
     /// Returns a horizontal face line gap.
@@ -1561,10 +2091,24 @@ impl<'a> Face<'a> {
     /// This method is affected by variation axes.
     #[inline]
     pub fn line_gap(&self) -> i16 {
+        self.line_gap_impl(self.coords())
+    }
+
+    /// Returns a horizontal face line gap at the specified variation location.
+    ///
+    /// See [`Face::line_gap`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn line_gap_at(&self, location: &VariationLocation) -> i16 {
+        self.line_gap_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn line_gap_impl(&self, coords: &[NormalizedCoordinate]) -> i16 {
         if let Some(os_2) = self.tables.os2 {
             if os_2.use_typographic_metrics() {
                 let value = os_2.typographic_line_gap();
-                return self.apply_metrics_variation(Tag::from_bytes(b"hlgp"), value);
+                return self.apply_metrics_variation(Tag::from_bytes(b"hlgp"), value, coords);
             }
         }
 
@@ -1574,7 +2118,7 @@ impl<'a> Face<'a> {
             if let Some(os_2) = self.tables.os2 {
                 if os_2.typographic_ascender() != 0 || os_2.typographic_descender() != 0 {
                     value = os_2.typographic_line_gap();
-                    value = self.apply_metrics_variation(Tag::from_bytes(b"hlgp"), value);
+                    value = self.apply_metrics_variation(Tag::from_bytes(b"hlgp"), value, coords);
                 } else {
                     value = 0;
                 }
@@ -1594,9 +2138,23 @@ impl<'a> Face<'a> {
     /// Returns `None` when OS/2 table is not present.
     #[inline]
     pub fn typographic_ascender(&self) -> Option<i16> {
+        self.typographic_ascender_impl(self.coords())
+    }
+
+    /// Returns a horizontal typographic face ascender at the specified variation location.
+    ///
+    /// See [`Face::typographic_ascender`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn typographic_ascender_at(&self, location: &VariationLocation) -> Option<i16> {
+        self.typographic_ascender_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn typographic_ascender_impl(&self, coords: &[NormalizedCoordinate]) -> Option<i16> {
         self.tables.os2.map(|table| {
             let v = table.typographic_ascender();
-            self.apply_metrics_variation(Tag::from_bytes(b"hasc"), v)
+            self.apply_metrics_variation(Tag::from_bytes(b"hasc"), v, coords)
         })
     }
 
@@ -1610,9 +2168,23 @@ impl<'a> Face<'a> {
     /// Returns `None` when OS/2 table is not present.
     #[inline]
     pub fn typographic_descender(&self) -> Option<i16> {
+        self.typographic_descender_impl(self.coords())
+    }
+
+    /// Returns a horizontal typographic face descender at the specified variation location.
+    ///
+    /// See [`Face::typographic_descender`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn typographic_descender_at(&self, location: &VariationLocation) -> Option<i16> {
+        self.typographic_descender_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn typographic_descender_impl(&self, coords: &[NormalizedCoordinate]) -> Option<i16> {
         self.tables.os2.map(|table| {
             let v = table.typographic_descender();
-            self.apply_metrics_variation(Tag::from_bytes(b"hdsc"), v)
+            self.apply_metrics_variation(Tag::from_bytes(b"hdsc"), v, coords)
         })
     }
 
@@ -1626,9 +2198,23 @@ impl<'a> Face<'a> {
     /// Returns `None` when OS/2 table is not present.
     #[inline]
     pub fn typographic_line_gap(&self) -> Option<i16> {
-        self.tables.os2.map(|table| {
+        self.typographic_line_gap_impl(self.coords())
+    }
+
+    /// Returns a horizontal typographic face line gap at the specified variation location.
+    ///
+    /// See [`Face::typographic_line_gap`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn typographic_line_gap_at(&self, location: &VariationLocation) -> Option<i16> {
+        self.typographic_line_gap_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn typographic_line_gap_impl(&self, coords: &[NormalizedCoordinate]) -> Option<i16> {
+        self.tables.os2.map(|table| {
             let v = table.typographic_line_gap();
-            self.apply_metrics_variation(Tag::from_bytes(b"hlgp"), v)
+            self.apply_metrics_variation(Tag::from_bytes(b"hlgp"), v, coords)
         })
     }
 
@@ -1637,10 +2223,24 @@ impl<'a> Face<'a> {
     /// This method is affected by variation axes.
     #[inline]
     pub fn vertical_ascender(&self) -> Option<i16> {
+        self.vertical_ascender_impl(self.coords())
+    }
+
+    /// Returns a vertical face ascender at the specified variation location.
+    ///
+    /// See [`Face::vertical_ascender`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn vertical_ascender_at(&self, location: &VariationLocation) -> Option<i16> {
+        self.vertical_ascender_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn vertical_ascender_impl(&self, coords: &[NormalizedCoordinate]) -> Option<i16> {
         self.tables
             .vhea
             .map(|vhea| vhea.ascender)
-            .map(|v| self.apply_metrics_variation(Tag::from_bytes(b"vasc"), v))
+            .map(|v| self.apply_metrics_variation(Tag::from_bytes(b"vasc"), v, coords))
     }
 
     /// Returns a vertical face descender.
@@ -1648,10 +2248,24 @@ impl<'a> Face<'a> {
     /// This method is affected by variation axes.
     #[inline]
     pub fn vertical_descender(&self) -> Option<i16> {
+        self.vertical_descender_impl(self.coords())
+    }
+
+    /// Returns a vertical face descender at the specified variation location.
+    ///
+    /// See [`Face::vertical_descender`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn vertical_descender_at(&self, location: &VariationLocation) -> Option<i16> {
+        self.vertical_descender_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn vertical_descender_impl(&self, coords: &[NormalizedCoordinate]) -> Option<i16> {
         self.tables
             .vhea
             .map(|vhea| vhea.descender)
-            .map(|v| self.apply_metrics_variation(Tag::from_bytes(b"vdsc"), v))
+            .map(|v| self.apply_metrics_variation(Tag::from_bytes(b"vdsc"), v, coords))
     }
 
     /// Returns a vertical face height.
@@ -1659,7 +2273,21 @@ impl<'a> Face<'a> {
     /// This method is affected by variation axes.
     #[inline]
     pub fn vertical_height(&self) -> Option<i16> {
-        Some(self.vertical_ascender()? - self.vertical_descender()?)
+        self.vertical_height_impl(self.coords())
+    }
+
+    /// Returns a vertical face height at the specified variation location.
+    ///
+    /// See [`Face::vertical_height`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn vertical_height_at(&self, location: &VariationLocation) -> Option<i16> {
+        self.vertical_height_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn vertical_height_impl(&self, coords: &[NormalizedCoordinate]) -> Option<i16> {
+        Some(self.vertical_ascender_impl(coords)? - self.vertical_descender_impl(coords)?)
     }
 
     /// Returns a vertical face line gap.
@@ -1667,10 +2295,24 @@ impl<'a> Face<'a> {
     /// This method is affected by variation axes.
     #[inline]
     pub fn vertical_line_gap(&self) -> Option<i16> {
+        self.vertical_line_gap_impl(self.coords())
+    }
+
+    /// Returns a vertical face line gap at the specified variation location.
+    ///
+    /// See [`Face::vertical_line_gap`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn vertical_line_gap_at(&self, location: &VariationLocation) -> Option<i16> {
+        self.vertical_line_gap_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn vertical_line_gap_impl(&self, coords: &[NormalizedCoordinate]) -> Option<i16> {
         self.tables
             .vhea
             .map(|vhea| vhea.line_gap)
-            .map(|v| self.apply_metrics_variation(Tag::from_bytes(b"vlgp"), v))
+            .map(|v| self.apply_metrics_variation(Tag::from_bytes(b"vlgp"), v, coords))
     }
 
     /// Returns face's units per EM.
@@ -1688,10 +2330,24 @@ impl<'a> Face<'a> {
     /// Returns `None` when OS/2 table is not present or when its version is < 2.
     #[inline]
     pub fn x_height(&self) -> Option<i16> {
+        self.x_height_impl(self.coords())
+    }
+
+    /// Returns face's x height at the specified variation location.
+    ///
+    /// See [`Face::x_height`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn x_height_at(&self, location: &VariationLocation) -> Option<i16> {
+        self.x_height_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn x_height_impl(&self, coords: &[NormalizedCoordinate]) -> Option<i16> {
         self.tables
             .os2
             .and_then(|os_2| os_2.x_height())
-            .map(|v| self.apply_metrics_variation(Tag::from_bytes(b"xhgt"), v))
+            .map(|v| self.apply_metrics_variation(Tag::from_bytes(b"xhgt"), v, coords))
     }
 
     /// Returns face's capital height.
@@ -1701,10 +2357,24 @@ impl<'a> Face<'a> {
     /// Returns `None` when OS/2 table is not present or when its version is < 2.
     #[inline]
     pub fn capital_height(&self) -> Option<i16> {
+        self.capital_height_impl(self.coords())
+    }
+
+    /// Returns face's capital height at the specified variation location.
+    ///
+    /// See [`Face::capital_height`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn capital_height_at(&self, location: &VariationLocation) -> Option<i16> {
+        self.capital_height_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn capital_height_impl(&self, coords: &[NormalizedCoordinate]) -> Option<i16> {
         self.tables
             .os2
             .and_then(|os_2| os_2.capital_height())
-            .map(|v| self.apply_metrics_variation(Tag::from_bytes(b"cpht"), v))
+            .map(|v| self.apply_metrics_variation(Tag::from_bytes(b"cpht"), v, coords))
     }
 
     /// Returns face's underline metrics.
@@ -1714,11 +2384,33 @@ impl<'a> Face<'a> {
     /// Returns `None` when `post` table is not present.
     #[inline]
     pub fn underline_metrics(&self) -> Option<LineMetrics> {
+        self.underline_metrics_impl(self.coords())
+    }
+
+    /// Returns face's underline metrics at the specified variation location.
+    ///
+    /// See [`Face::underline_metrics`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn underline_metrics_at(&self, location: &VariationLocation) -> Option<LineMetrics> {
+        self.underline_metrics_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn underline_metrics_impl(&self, coords: &[NormalizedCoordinate]) -> Option<LineMetrics> {
         let mut metrics = self.tables.post?.underline_metrics;
 
         if self.is_variable() {
-            self.apply_metrics_variation_to(Tag::from_bytes(b"undo"), &mut metrics.position);
-            self.apply_metrics_variation_to(Tag::from_bytes(b"unds"), &mut metrics.thickness);
+            self.apply_metrics_variation_to(
+                Tag::from_bytes(b"undo"),
+                &mut metrics.position,
+                coords,
+            );
+            self.apply_metrics_variation_to(
+                Tag::from_bytes(b"unds"),
+                &mut metrics.thickness,
+                coords,
+            );
         }
 
         Some(metrics)
@@ -1731,11 +2423,33 @@ impl<'a> Face<'a> {
     /// Returns `None` when OS/2 table is not present.
     #[inline]
     pub fn strikeout_metrics(&self) -> Option<LineMetrics> {
+        self.strikeout_metrics_impl(self.coords())
+    }
+
+    /// Returns face's strikeout metrics at the specified variation location.
+    ///
+    /// See [`Face::strikeout_metrics`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn strikeout_metrics_at(&self, location: &VariationLocation) -> Option<LineMetrics> {
+        self.strikeout_metrics_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn strikeout_metrics_impl(&self, coords: &[NormalizedCoordinate]) -> Option<LineMetrics> {
         let mut metrics = self.tables.os2?.strikeout_metrics();
 
         if self.is_variable() {
-            self.apply_metrics_variation_to(Tag::from_bytes(b"stro"), &mut metrics.position);
-            self.apply_metrics_variation_to(Tag::from_bytes(b"strs"), &mut metrics.thickness);
+            self.apply_metrics_variation_to(
+                Tag::from_bytes(b"stro"),
+                &mut metrics.position,
+                coords,
+            );
+            self.apply_metrics_variation_to(
+                Tag::from_bytes(b"strs"),
+                &mut metrics.thickness,
+                coords,
+            );
         }
 
         Some(metrics)
@@ -1748,13 +2462,35 @@ impl<'a> Face<'a> {
     /// Returns `None` when OS/2 table is not present.
     #[inline]
     pub fn subscript_metrics(&self) -> Option<ScriptMetrics> {
+        self.subscript_metrics_impl(self.coords())
+    }
+
+    /// Returns face's subscript metrics at the specified variation location.
+    ///
+    /// See [`Face::subscript_metrics`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn subscript_metrics_at(&self, location: &VariationLocation) -> Option<ScriptMetrics> {
+        self.subscript_metrics_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn subscript_metrics_impl(&self, coords: &[NormalizedCoordinate]) -> Option<ScriptMetrics> {
         let mut metrics = self.tables.os2?.subscript_metrics();
 
         if self.is_variable() {
-            self.apply_metrics_variation_to(Tag::from_bytes(b"sbxs"), &mut metrics.x_size);
-            self.apply_metrics_variation_to(Tag::from_bytes(b"sbys"), &mut metrics.y_size);
-            self.apply_metrics_variation_to(Tag::from_bytes(b"sbxo"), &mut metrics.x_offset);
-            self.apply_metrics_variation_to(Tag::from_bytes(b"sbyo"), &mut metrics.y_offset);
+            self.apply_metrics_variation_to(Tag::from_bytes(b"sbxs"), &mut metrics.x_size, coords);
+            self.apply_metrics_variation_to(Tag::from_bytes(b"sbys"), &mut metrics.y_size, coords);
+            self.apply_metrics_variation_to(
+                Tag::from_bytes(b"sbxo"),
+                &mut metrics.x_offset,
+                coords,
+            );
+            self.apply_metrics_variation_to(
+                Tag::from_bytes(b"sbyo"),
+                &mut metrics.y_offset,
+                coords,
+            );
         }
 
         Some(metrics)
@@ -1767,13 +2503,35 @@ impl<'a> Face<'a> {
     /// Returns `None` when OS/2 table is not present.
     #[inline]
     pub fn superscript_metrics(&self) -> Option<ScriptMetrics> {
+        self.superscript_metrics_impl(self.coords())
+    }
+
+    /// Returns face's superscript metrics at the specified variation location.
+    ///
+    /// See [`Face::superscript_metrics`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn superscript_metrics_at(&self, location: &VariationLocation) -> Option<ScriptMetrics> {
+        self.superscript_metrics_impl(location.coordinates())
+    }
+
+    #[inline]
+    fn superscript_metrics_impl(&self, coords: &[NormalizedCoordinate]) -> Option<ScriptMetrics> {
         let mut metrics = self.tables.os2?.superscript_metrics();
 
         if self.is_variable() {
-            self.apply_metrics_variation_to(Tag::from_bytes(b"spxs"), &mut metrics.x_size);
-            self.apply_metrics_variation_to(Tag::from_bytes(b"spys"), &mut metrics.y_size);
-            self.apply_metrics_variation_to(Tag::from_bytes(b"spxo"), &mut metrics.x_offset);
-            self.apply_metrics_variation_to(Tag::from_bytes(b"spyo"), &mut metrics.y_offset);
+            self.apply_metrics_variation_to(Tag::from_bytes(b"spxs"), &mut metrics.x_size, coords);
+            self.apply_metrics_variation_to(Tag::from_bytes(b"spys"), &mut metrics.y_size, coords);
+            self.apply_metrics_variation_to(
+                Tag::from_bytes(b"spxo"),
+                &mut metrics.x_offset,
+                coords,
+            );
+            self.apply_metrics_variation_to(
+                Tag::from_bytes(b"spyo"),
+                &mut metrics.y_offset,
+                coords,
+            );
         }
 
         Some(metrics)
@@ -1906,22 +2664,7 @@ impl<'a> Face<'a> {
     pub fn glyph_hor_advance(&self, glyph_id: GlyphId) -> Option<u16> {
         #[cfg(feature = "variable-fonts")]
         {
-            let mut advance = self.tables.hmtx?.advance(glyph_id)? as f32;
-
-            if self.is_variable() {
-                // Ignore variation offset when `hvar` is not set.
-                if let Some(hvar) = self.tables.hvar {
-                    if let Some(offset) = hvar.advance_offset(glyph_id, self.coords()) {
-                        // We can't use `round()` in `no_std`, so this is the next best thing.
-                        advance += offset + 0.5;
-                    }
-                } else if let Some(points) = self.glyph_phantom_points(glyph_id) {
-                    // We can't use `round()` in `no_std`, so this is the next best thing.
-                    advance += points.right.x + 0.5
-                }
-            }
-
-            u16::try_num_from(advance)
+            self.glyph_hor_advance_impl(glyph_id, self.coords())
         }
 
         #[cfg(not(feature = "variable-fonts"))]
@@ -1930,6 +2673,43 @@ impl<'a> Face<'a> {
         }
     }
 
+    /// Returns glyph's horizontal advance at the specified variation location.
+    ///
+    /// See [`Face::glyph_hor_advance`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn glyph_hor_advance_at(
+        &self,
+        glyph_id: GlyphId,
+        location: &VariationLocation,
+    ) -> Option<u16> {
+        self.glyph_hor_advance_impl(glyph_id, location.coordinates())
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn glyph_hor_advance_impl(
+        &self,
+        glyph_id: GlyphId,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<u16> {
+        let mut advance = self.tables.hmtx?.advance(glyph_id)? as f32;
+
+        if self.is_variable() {
+            // Ignore variation offset when `hvar` is not set.
+            if let Some(hvar) = self.tables.hvar {
+                if let Some(offset) = hvar.advance_offset(glyph_id, coords) {
+                    // We can't use `round()` in `no_std`, so this is the next best thing.
+                    advance += offset + 0.5;
+                }
+            } else if let Some(points) = self.glyph_phantom_points_impl(glyph_id, coords) {
+                // We can't use `round()` in `no_std`, so this is the next best thing.
+                advance += points.right.x + 0.5
+            }
+        }
+
+        u16::try_num_from(advance)
+    }
+
     /// Returns glyph's vertical advance.
     ///
     /// This method is affected by variation axes.
@@ -1937,22 +2717,7 @@ impl<'a> Face<'a> {
     pub fn glyph_ver_advance(&self, glyph_id: GlyphId) -> Option<u16> {
         #[cfg(feature = "variable-fonts")]
         {
-            let mut advance = self.tables.vmtx?.advance(glyph_id)? as f32;
-
-            if self.is_variable() {
-                // Ignore variation offset when `vvar` is not set.
-                if let Some(vvar) = self.tables.vvar {
-                    if let Some(offset) = vvar.advance_offset(glyph_id, self.coords()) {
-                        // We can't use `round()` in `no_std`, so this is the next best thing.
-                        advance += offset + 0.5;
-                    }
-                } else if let Some(points) = self.glyph_phantom_points(glyph_id) {
-                    // We can't use `round()` in `no_std`, so this is the next best thing.
-                    advance += points.bottom.y + 0.5
-                }
-            }
-
-            u16::try_num_from(advance)
+            self.glyph_ver_advance_impl(glyph_id, self.coords())
         }
 
         #[cfg(not(feature = "variable-fonts"))]
@@ -1961,6 +2726,43 @@ impl<'a> Face<'a> {
         }
     }
 
+    /// Returns glyph's vertical advance at the specified variation location.
+    ///
+    /// See [`Face::glyph_ver_advance`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn glyph_ver_advance_at(
+        &self,
+        glyph_id: GlyphId,
+        location: &VariationLocation,
+    ) -> Option<u16> {
+        self.glyph_ver_advance_impl(glyph_id, location.coordinates())
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn glyph_ver_advance_impl(
+        &self,
+        glyph_id: GlyphId,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<u16> {
+        let mut advance = self.tables.vmtx?.advance(glyph_id)? as f32;
+
+        if self.is_variable() {
+            // Ignore variation offset when `vvar` is not set.
+            if let Some(vvar) = self.tables.vvar {
+                if let Some(offset) = vvar.advance_offset(glyph_id, coords) {
+                    // We can't use `round()` in `no_std`, so this is the next best thing.
+                    advance += offset + 0.5;
+                }
+            } else if let Some(points) = self.glyph_phantom_points_impl(glyph_id, coords) {
+                // We can't use `round()` in `no_std`, so this is the next best thing.
+                advance += points.bottom.y + 0.5
+            }
+        }
+
+        u16::try_num_from(advance)
+    }
+
     /// Returns glyph's horizontal side bearing.
     ///
     /// This method is affected by variation axes.
@@ -1968,19 +2770,7 @@ impl<'a> Face<'a> {
     pub fn glyph_hor_side_bearing(&self, glyph_id: GlyphId) -> Option<i16> {
         #[cfg(feature = "variable-fonts")]
         {
-            let mut bearing = self.tables.hmtx?.side_bearing(glyph_id)? as f32;
-
-            if self.is_variable() {
-                // Ignore variation offset when `hvar` is not set.
-                if let Some(hvar) = self.tables.hvar {
-                    if let Some(offset) = hvar.left_side_bearing_offset(glyph_id, self.coords()) {
-                        // We can't use `round()` in `no_std`, so this is the next best thing.
-                        bearing += offset + 0.5;
-                    }
-                }
-            }
-
-            i16::try_num_from(bearing)
+            self.glyph_hor_side_bearing_impl(glyph_id, self.coords())
         }
 
         #[cfg(not(feature = "variable-fonts"))]
@@ -1989,6 +2779,40 @@ impl<'a> Face<'a> {
         }
     }
 
+    /// Returns glyph's horizontal side bearing at the specified variation location.
+    ///
+    /// See [`Face::glyph_hor_side_bearing`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn glyph_hor_side_bearing_at(
+        &self,
+        glyph_id: GlyphId,
+        location: &VariationLocation,
+    ) -> Option<i16> {
+        self.glyph_hor_side_bearing_impl(glyph_id, location.coordinates())
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn glyph_hor_side_bearing_impl(
+        &self,
+        glyph_id: GlyphId,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<i16> {
+        let mut bearing = self.tables.hmtx?.side_bearing(glyph_id)? as f32;
+
+        if self.is_variable() {
+            // Ignore variation offset when `hvar` is not set.
+            if let Some(hvar) = self.tables.hvar {
+                if let Some(offset) = hvar.left_side_bearing_offset(glyph_id, coords) {
+                    // We can't use `round()` in `no_std`, so this is the next best thing.
+                    bearing += offset + 0.5;
+                }
+            }
+        }
+
+        i16::try_num_from(bearing)
+    }
+
     /// Returns glyph's vertical side bearing.
     ///
     /// This method is affected by variation axes.
@@ -1996,19 +2820,7 @@ impl<'a> Face<'a> {
     pub fn glyph_ver_side_bearing(&self, glyph_id: GlyphId) -> Option<i16> {
         #[cfg(feature = "variable-fonts")]
         {
-            let mut bearing = self.tables.vmtx?.side_bearing(glyph_id)? as f32;
-
-            if self.is_variable() {
-                // Ignore variation offset when `vvar` is not set.
-                if let Some(vvar) = self.tables.vvar {
-                    if let Some(offset) = vvar.top_side_bearing_offset(glyph_id, self.coords()) {
-                        // We can't use `round()` in `no_std`, so this is the next best thing.
-                        bearing += offset + 0.5;
-                    }
-                }
-            }
-
-            i16::try_num_from(bearing)
+            self.glyph_ver_side_bearing_impl(glyph_id, self.coords())
         }
 
         #[cfg(not(feature = "variable-fonts"))]
@@ -2017,6 +2829,40 @@ impl<'a> Face<'a> {
         }
     }
 
+    /// Returns glyph's vertical side bearing at the specified variation location.
+    ///
+    /// See [`Face::glyph_ver_side_bearing`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn glyph_ver_side_bearing_at(
+        &self,
+        glyph_id: GlyphId,
+        location: &VariationLocation,
+    ) -> Option<i16> {
+        self.glyph_ver_side_bearing_impl(glyph_id, location.coordinates())
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn glyph_ver_side_bearing_impl(
+        &self,
+        glyph_id: GlyphId,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<i16> {
+        let mut bearing = self.tables.vmtx?.side_bearing(glyph_id)? as f32;
+
+        if self.is_variable() {
+            // Ignore variation offset when `vvar` is not set.
+            if let Some(vvar) = self.tables.vvar {
+                if let Some(offset) = vvar.top_side_bearing_offset(glyph_id, coords) {
+                    // We can't use `round()` in `no_std`, so this is the next best thing.
+                    bearing += offset + 0.5;
+                }
+            }
+        }
+
+        i16::try_num_from(bearing)
+    }
+
     /// Returns glyph's vertical origin according to
     /// [Vertical Origin Table](https://docs.microsoft.com/en-us/typography/opentype/spec/vorg).
     ///
@@ -2024,25 +2870,219 @@ impl<'a> Face<'a> {
     pub fn glyph_y_origin(&self, glyph_id: GlyphId) -> Option<i16> {
         #[cfg(feature = "variable-fonts")]
         {
-            let mut origin = self.tables.vorg.map(|vorg| vorg.glyph_y_origin(glyph_id))? as f32;
-
-            if self.is_variable() {
-                // Ignore variation offset when `vvar` is not set.
-                if let Some(vvar) = self.tables.vvar {
-                    if let Some(offset) = vvar.vertical_origin_offset(glyph_id, self.coords()) {
-                        // We can't use `round()` in `no_std`, so this is the next best thing.
-                        origin += offset + 0.5;
-                    }
+            self.glyph_y_origin_impl(glyph_id, self.coords())
+        }
+
+        #[cfg(not(feature = "variable-fonts"))]
+        {
+            self.tables.vorg.map(|vorg| vorg.glyph_y_origin(glyph_id))
+        }
+    }
+
+    /// Returns glyph's vertical origin at the specified variation location.
+    ///
+    /// See [`Face::glyph_y_origin`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn glyph_y_origin_at(
+        &self,
+        glyph_id: GlyphId,
+        location: &VariationLocation,
+    ) -> Option<i16> {
+        self.glyph_y_origin_impl(glyph_id, location.coordinates())
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn glyph_y_origin_impl(
+        &self,
+        glyph_id: GlyphId,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<i16> {
+        let mut origin = self.tables.vorg.map(|vorg| vorg.glyph_y_origin(glyph_id))? as f32;
+
+        if self.is_variable() {
+            // Ignore variation offset when `vvar` is not set.
+            if let Some(vvar) = self.tables.vvar {
+                if let Some(offset) = vvar.vertical_origin_offset(glyph_id, coords) {
+                    // We can't use `round()` in `no_std`, so this is the next best thing.
+                    origin += offset + 0.5;
                 }
             }
+        }
+
+        i16::try_num_from(origin)
+    }
+
+    /// Returns horizontal kerning between two glyphs.
+    ///
+    /// Uses pair adjustments from the `GPOS` table `kern` feature lookups
+    /// for the `DFLT` script, or `latn` when there is none.
+    /// When the feature is not present, falls back to the `kerx` and then to the `kern` table.
+    /// Contextual and state machine based kerning is ignored.
+    /// So are cross-stream and variable `kerx` and `kern` subtables.
+    ///
+    /// This method is affected by variation axes.
+    ///
+    /// Returns `None` when the pair is not kerned.
+    #[inline]
+    pub fn glyphs_kerning(&self, left: GlyphId, right: GlyphId) -> Option<f32> {
+        self.glyphs_kerning_impl(left, right, true, self.coords())
+    }
+
+    /// Returns horizontal kerning between two glyphs at the specified variation location.
+    ///
+    /// See [`Face::glyphs_kerning`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn glyphs_kerning_at(
+        &self,
+        left: GlyphId,
+        right: GlyphId,
+        location: &VariationLocation,
+    ) -> Option<f32> {
+        self.glyphs_kerning_impl(left, right, true, location.coordinates())
+    }
+
+    /// Returns vertical kerning between two glyphs.
+    ///
+    /// Same as [`Face::glyphs_kerning`], but uses the `GPOS` table `vkrn` feature
+    /// and vertical `kerx` and `kern` subtables.
+    ///
+    /// This method is affected by variation axes.
+    #[inline]
+    pub fn glyphs_ver_kerning(&self, top: GlyphId, bottom: GlyphId) -> Option<f32> {
+        self.glyphs_kerning_impl(top, bottom, false, self.coords())
+    }
+
+    /// Returns vertical kerning between two glyphs at the specified variation location.
+    ///
+    /// See [`Face::glyphs_ver_kerning`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn glyphs_ver_kerning_at(
+        &self,
+        top: GlyphId,
+        bottom: GlyphId,
+        location: &VariationLocation,
+    ) -> Option<f32> {
+        self.glyphs_kerning_impl(top, bottom, false, location.coordinates())
+    }
+
+    fn glyphs_kerning_impl(
+        &self,
+        left: GlyphId,
+        right: GlyphId,
+        horizontal: bool,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<f32> {
+        #[cfg(feature = "opentype-layout")]
+        {
+            let feature = Tag::from_bytes(if horizontal { b"kern" } else { b"vkrn" });
+            if let Some(lookups) = self.gpos_feature_lookups(feature, coords) {
+                return self.gpos_glyphs_kerning(lookups, left, right, horizontal, coords);
+            }
+        }
+
+        #[cfg(not(feature = "opentype-layout"))]
+        {
+            let _ = coords;
+        }
 
-            i16::try_num_from(origin)
+        // Variable subtables store tuple indices instead of values, so they are skipped.
+        #[cfg(feature = "apple-layout")]
+        {
+            if let Some(kerx) = self.tables.kerx {
+                return sum_kerning(
+                    kerx.subtables
+                        .into_iter()
+                        .filter(|st| st.horizontal == horizontal)
+                        .filter(|st| !st.variable && !st.has_cross_stream)
+                        .filter_map(|st| st.glyphs_kerning(left, right)),
+                );
+            }
         }
 
+        sum_kerning(
+            self.tables
+                .kern?
+                .subtables
+                .into_iter()
+                .filter(|st| st.horizontal == horizontal)
+                .filter(|st| !st.variable && !st.has_cross_stream)
+                .filter_map(|st| st.glyphs_kerning(left, right)),
+        )
+    }
+
+    #[cfg(feature = "opentype-layout")]
+    fn gpos_feature_lookups(
+        &self,
+        feature: Tag,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<LazyArray16<'a, opentype_layout::LookupIndex>> {
+        let gpos = self.tables.gpos?;
+        [Tag::from_bytes(b"DFLT"), Tag::from_bytes(b"latn")]
+            .iter()
+            .find_map(|script| {
+                #[cfg(feature = "variable-fonts")]
+                {
+                    gpos.feature_lookups_at(*script, None, feature, coords)
+                }
+
+                #[cfg(not(feature = "variable-fonts"))]
+                {
+                    let _ = coords;
+                    gpos.feature_lookups(*script, None, feature)
+                }
+            })
+    }
+
+    #[cfg(feature = "opentype-layout")]
+    fn gpos_glyphs_kerning(
+        &self,
+        lookups: LazyArray16<opentype_layout::LookupIndex>,
+        left: GlyphId,
+        right: GlyphId,
+        horizontal: bool,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<f32> {
         #[cfg(not(feature = "variable-fonts"))]
         {
-            self.tables.vorg.map(|vorg| vorg.glyph_y_origin(glyph_id))
+            let _ = coords;
+        }
+
+        let gpos = self.tables.gpos?;
+        let mut kerning = None;
+        for index in lookups {
+            let lookup = match gpos.lookups.get(index) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            // Like during shaping, only the first matching subtable is applied.
+            let record = lookup
+                .subtables
+                .into_iter::<gpos::PositioningSubtable>()
+                .find_map(|subtable| match subtable {
+                    gpos::PositioningSubtable::Pair(t) => t.get(left, right),
+                    _ => None,
+                })
+                .map(|records| records.0);
+
+            if let Some(record) = record {
+                let value = match (horizontal, self.tables.gdef) {
+                    #[cfg(feature = "variable-fonts")]
+                    (true, Some(gdef)) => record.x_advance_at(&gdef, coords),
+                    #[cfg(feature = "variable-fonts")]
+                    (false, Some(gdef)) => record.y_advance_at(&gdef, coords),
+                    (true, _) => f32::from(record.x_advance),
+                    (false, _) => f32::from(record.y_advance),
+                };
+
+                *kerning.get_or_insert(0.0) += value;
+            }
         }
+
+        kerning
     }
 
     /// Returns glyph's name.
@@ -2076,8 +3116,9 @@ impl<'a> Face<'a> {
     /// You must check `outline_glyph()` result before using
     /// `OutlineBuilder`'s output.
     ///
-    /// `gvar`, `glyf`, `CFF` and `CFF2` tables are supported.
+    /// `VARC`, `gvar`, `glyf`, `CFF` and `CFF2` tables are supported.
     /// And they will be accesses in this specific order.
+    /// `VARC` components are outlined using the other tables.
     ///
     /// This method is affected by variation axes.
     ///
@@ -2129,11 +3170,107 @@ impl<'a> Face<'a> {
     ) -> Option<Rect> {
         #[cfg(feature = "variable-fonts")]
         {
-            if let Some(ref gvar) = self.tables.gvar {
-                return gvar.outline(self.tables.glyf?, self.coords(), glyph_id, builder);
+            self.outline_glyph_impl(glyph_id, builder, self.coords())
+        }
+
+        #[cfg(not(feature = "variable-fonts"))]
+        {
+            self.outline_static_glyph(glyph_id, builder)
+        }
+    }
+
+    /// Outlines a glyph at the specified variation location and returns its tight bounding box.
+    ///
+    /// See [`Face::outline_glyph`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn outline_glyph_at(
+        &self,
+        glyph_id: GlyphId,
+        builder: &mut dyn OutlineBuilder,
+        location: &VariationLocation,
+    ) -> Option<Rect> {
+        self.outline_glyph_impl(glyph_id, builder, location.coordinates())
+    }
+
+    /// Outlines a glyph using a cache of decoded `gvar` deltas.
+    ///
+    /// Works just like [`Face::outline_glyph`], but `gvar` deltas of each glyph
+    /// are decoded only once for the current variation coordinates and stored in `cache`.
+    /// Useful when the same glyphs are outlined repeatedly.
+    ///
+    /// `VARC` glyphs are not cached, since their components
+    /// are outlined at different coordinates.
+    #[cfg(all(feature = "variable-fonts", feature = "std"))]
+    pub fn outline_glyph_with_cache(
+        &self,
+        glyph_id: GlyphId,
+        cache: &mut gvar::DeltaCache,
+        builder: &mut dyn OutlineBuilder,
+    ) -> Option<Rect> {
+        #[cfg(feature = "opentype-layout")]
+        {
+            if let Some(ref varc) = self.tables.varc {
+                if varc.coverage.contains(glyph_id) {
+                    return self.outline_glyph_impl(glyph_id, builder, self.coords());
+                }
+            }
+        }
+
+        if let Some(ref gvar) = self.tables.gvar {
+            let glyf = self.tables.glyf?;
+            return gvar.outline_with_cache(glyf, self.coords(), glyph_id, cache, builder);
+        }
+
+        self.outline_base_glyph(glyph_id, builder, self.coords())
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn outline_glyph_impl(
+        &self,
+        glyph_id: GlyphId,
+        builder: &mut dyn OutlineBuilder,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<Rect> {
+        #[cfg(feature = "opentype-layout")]
+        {
+            if let Some(ref varc) = self.tables.varc {
+                if varc.coverage.contains(glyph_id) {
+                    return varc.outline(coords, glyph_id, builder, &mut |id, coords, builder| {
+                        self.outline_base_glyph(id, builder, coords);
+                    });
+                }
             }
         }
 
+        self.outline_base_glyph(glyph_id, builder, coords)
+    }
+
+    // Outlines a glyph ignoring `VARC`.
+    #[cfg(feature = "variable-fonts")]
+    fn outline_base_glyph(
+        &self,
+        glyph_id: GlyphId,
+        builder: &mut dyn OutlineBuilder,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<Rect> {
+        if let Some(ref gvar) = self.tables.gvar {
+            return gvar.outline(self.tables.glyf?, coords, glyph_id, builder);
+        }
+
+        if self.tables.glyf.is_some() || self.tables.cff.is_some() {
+            return self.outline_static_glyph(glyph_id, builder);
+        }
+
+        self.tables.cff2?.outline(coords, glyph_id, builder).ok()
+    }
+
+    #[inline]
+    fn outline_static_glyph(
+        &self,
+        glyph_id: GlyphId,
+        builder: &mut dyn OutlineBuilder,
+    ) -> Option<Rect> {
         if let Some(table) = self.tables.glyf {
             return table.outline(glyph_id, builder);
         }
@@ -2142,13 +3279,6 @@ impl<'a> Face<'a> {
             return cff.outline(glyph_id, builder).ok();
         }
 
-        #[cfg(feature = "variable-fonts")]
-        {
-            if let Some(ref cff2) = self.tables.cff2 {
-                return cff2.outline(self.coords(), glyph_id, builder).ok();
-            }
-        }
-
         None
     }
 
@@ -2173,6 +3303,19 @@ impl<'a> Face<'a> {
         self.outline_glyph(glyph_id, &mut DummyOutline)
     }
 
+    /// Returns a tight glyph bounding box at the specified variation location.
+    ///
+    /// See [`Face::glyph_bounding_box`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn glyph_bounding_box_at(
+        &self,
+        glyph_id: GlyphId,
+        location: &VariationLocation,
+    ) -> Option<Rect> {
+        self.outline_glyph_at(glyph_id, &mut DummyOutline, location)
+    }
+
     /// Returns a bounding box that large enough to enclose any glyph from the face.
     #[inline]
     pub fn global_bounding_box(&self) -> Rect {
@@ -2204,7 +3347,7 @@ impl<'a> Face<'a> {
         &self,
         glyph_id: GlyphId,
         pixels_per_em: u16,
-    ) -> Option<RasterGlyphImage> {
+    ) -> Option<RasterGlyphImage<'_>> {
         if let Some(table) = self.tables.sbix {
             if let Some(strike) = table.best_strike(pixels_per_em) {
                 return strike.get(glyph_id);
@@ -2294,6 +3437,28 @@ impl<'a> Face<'a> {
         )
     }
 
+    /// Paints a color glyph from the `COLR` table at the specified variation location.
+    ///
+    /// See [`Face::paint_color_glyph`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn paint_color_glyph_at(
+        &self,
+        glyph_id: GlyphId,
+        palette: u16,
+        foreground_color: RgbaColor,
+        painter: &mut dyn colr::Painter<'a>,
+        location: &VariationLocation,
+    ) -> Option<()> {
+        self.tables.colr?.paint(
+            glyph_id,
+            palette,
+            painter,
+            location.coordinates(),
+            foreground_color,
+        )
+    }
+
     /// Returns an iterator over variation axes.
     #[cfg(feature = "variable-fonts")]
     #[inline]
@@ -2303,7 +3468,7 @@ impl<'a> Face<'a> {
 
     /// Sets a variation axis coordinate.
     ///
-    /// This is one of the two only mutable methods in the library.
+    /// This is one of the few mutable methods in the library.
     /// We can simplify the API a lot by storing the variable coordinates
     /// in the face object itself.
     ///
@@ -2322,17 +3487,124 @@ impl<'a> Face<'a> {
 
         for (i, var_axis) in self.variation_axes().into_iter().enumerate() {
             if var_axis.tag == axis {
-                self.coordinates.data[i] = var_axis.normalized_value(value);
+                self.unmapped_coordinates.data[i] = var_axis.normalized_value(value);
             }
         }
 
-        // TODO: optimize
+        // `avar` must be applied to all coordinates at once,
+        // since version 2 can map one axis based on others.
+        self.coordinates = self.map_variation_coordinates(self.unmapped_coordinates.clone());
+
+        Some(())
+    }
+
+    /// Creates a variation location from user-space axis values.
+    ///
+    /// Values are normalized using the `fvar` table and then mapped using the `avar` table.
+    /// Axes without a value use their default one and unknown axes are ignored.
+    ///
+    /// The face itself is not affected. Use the returned location with the `*_at` methods,
+    /// like [`Face::outline_glyph_at`].
+    ///
+    /// Since coordinates are stored on the stack, we allow only 64 of them.
+    ///
+    /// Returns `None` when face is not variable.
+    #[cfg(feature = "variable-fonts")]
+    pub fn variation_location(&self, variations: &[Variation]) -> Option<VariationLocation> {
+        let coordinates = self.normalize_variation_coordinates(|_, axis| {
+            variations
+                .iter()
+                .rev()
+                .find(|v| v.axis == axis.tag)
+                .map(|v| v.value)
+                .unwrap_or(axis.def_value)
+        })?;
+
+        Some(VariationLocation {
+            coordinates: self.map_variation_coordinates(coordinates),
+        })
+    }
+
+    /// Returns an iterator over named instances.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn named_instances(&self) -> fvar::NamedInstances<'a> {
+        self.tables
+            .fvar
+            .map(|fvar| fvar.named_instances)
+            .unwrap_or_default()
+    }
+
+    /// Creates a variation location from a named instance.
+    ///
+    /// See [`Face::variation_location`] for details.
+    ///
+    /// Returns `None` when face is not variable or doesn't have such instance.
+    #[cfg(feature = "variable-fonts")]
+    pub fn named_instance_location(&self, index: u16) -> Option<VariationLocation> {
+        let coordinates = self.named_instance_coordinates(index)?;
+        Some(VariationLocation {
+            coordinates: self.map_variation_coordinates(coordinates),
+        })
+    }
+
+    /// Sets variation coordinates from a named instance.
+    ///
+    /// Unlike [`Face::set_variation`], replaces all coordinates.
+    ///
+    /// Returns `None` when face is not variable or doesn't have such instance.
+    #[cfg(feature = "variable-fonts")]
+    pub fn set_named_instance(&mut self, index: u16) -> Option<()> {
+        let coordinates = self.named_instance_coordinates(index)?;
+        self.coordinates = self.map_variation_coordinates(coordinates.clone());
+        self.unmapped_coordinates = coordinates;
+        Some(())
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn named_instance_coordinates(&self, index: u16) -> Option<VarCoords> {
+        let instance = self.named_instances().get(index)?;
+        self.normalize_variation_coordinates(|i, axis| {
+            u16::try_from(i)
+                .ok()
+                .and_then(|i| instance.coordinates.get(i))
+                .map(|v| v.0)
+                .unwrap_or(axis.def_value)
+        })
+    }
+
+    // Normalizes user-space values using `fvar`.
+    #[cfg(feature = "variable-fonts")]
+    fn normalize_variation_coordinates(
+        &self,
+        mut value: impl FnMut(usize, VariationAxis) -> f32,
+    ) -> Option<VarCoords> {
+        if !self.is_variable() {
+            return None;
+        }
+
+        let axes = self.variation_axes();
+        if usize::from(axes.len()) >= MAX_VAR_COORDS {
+            return None;
+        }
+
+        let mut coordinates = VarCoords::default();
+        coordinates.len = axes.len() as u8;
+        for (i, axis) in axes.into_iter().enumerate() {
+            coordinates.data[i] = axis.normalized_value(value(i, axis));
+        }
+
+        Some(coordinates)
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn map_variation_coordinates(&self, mut coordinates: VarCoords) -> VarCoords {
         if let Some(avar) = self.tables.avar {
             // Ignore error.
-            let _ = avar.map_coordinates(self.coordinates.as_mut_slice());
+            let _ = avar.map_coordinates(coordinates.as_mut_slice());
         }
 
-        Some(())
+        coordinates
     }
 
     /// Returns the current normalized variation coordinates.
@@ -2354,31 +3626,93 @@ impl<'a> Face<'a> {
     /// Available only for variable fonts with the `gvar` table.
     #[cfg(feature = "variable-fonts")]
     pub fn glyph_phantom_points(&self, glyph_id: GlyphId) -> Option<PhantomPoints> {
+        self.glyph_phantom_points_impl(glyph_id, self.coords())
+    }
+
+    /// Parses glyph's phantom points at the specified variation location.
+    ///
+    /// See [`Face::glyph_phantom_points`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn glyph_phantom_points_at(
+        &self,
+        glyph_id: GlyphId,
+        location: &VariationLocation,
+    ) -> Option<PhantomPoints> {
+        self.glyph_phantom_points_impl(glyph_id, location.coordinates())
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn glyph_phantom_points_impl(
+        &self,
+        glyph_id: GlyphId,
+        coords: &[NormalizedCoordinate],
+    ) -> Option<PhantomPoints> {
         let glyf = self.tables.glyf?;
         let gvar = self.tables.gvar?;
-        gvar.phantom_points(glyf, self.coords(), glyph_id)
+        gvar.phantom_points(glyf, coords, glyph_id)
     }
 
+    /// Calculates CVT deltas for the current variation coordinates.
+    ///
+    /// `deltas` must have an entry for each value of the `cvt ` table
+    /// and will be overwritten. Deltas are in font units and not rounded.
+    ///
+    /// Available only for variable fonts with the `cvar` table.
+    /// Returns `None` when the table is missing or malformed.
+    #[cfg(feature = "variable-fonts")]
+    pub fn cvt_deltas(&self, deltas: &mut [f32]) -> Option<()> {
+        self.cvt_deltas_impl(deltas, self.coords())
+    }
+
+    /// Calculates CVT deltas at the specified variation location.
+    ///
+    /// See [`Face::cvt_deltas`] for details.
     #[cfg(feature = "variable-fonts")]
     #[inline]
-    fn metrics_var_offset(&self, tag: Tag) -> f32 {
+    pub fn cvt_deltas_at(&self, deltas: &mut [f32], location: &VariationLocation) -> Option<()> {
+        self.cvt_deltas_impl(deltas, location.coordinates())
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    fn cvt_deltas_impl(&self, deltas: &mut [f32], coords: &[NormalizedCoordinate]) -> Option<()> {
+        for delta in deltas.iter_mut() {
+            *delta = 0.0;
+        }
+
+        self.tables.cvar?.apply(coords, deltas)
+    }
+
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    fn metrics_var_offset(&self, tag: Tag, coords: &[NormalizedCoordinate]) -> f32 {
         self.tables
             .mvar
-            .and_then(|table| table.metric_offset(tag, self.coords()))
+            .and_then(|table| table.metric_offset(tag, coords))
             .unwrap_or(0.0)
     }
 
     #[inline]
-    fn apply_metrics_variation(&self, tag: Tag, mut value: i16) -> i16 {
-        self.apply_metrics_variation_to(tag, &mut value);
+    fn apply_metrics_variation(
+        &self,
+        tag: Tag,
+        mut value: i16,
+        coords: &[NormalizedCoordinate],
+    ) -> i16 {
+        self.apply_metrics_variation_to(tag, &mut value, coords);
         value
     }
 
     #[cfg(feature = "variable-fonts")]
     #[inline]
-    fn apply_metrics_variation_to(&self, tag: Tag, value: &mut i16) {
+    fn apply_metrics_variation_to(
+        &self,
+        tag: Tag,
+        value: &mut i16,
+        coords: &[NormalizedCoordinate],
+    ) {
         if self.is_variable() {
-            let v = f32::from(*value) + self.metrics_var_offset(tag);
+            let v = f32::from(*value) + self.metrics_var_offset(tag, coords);
             // TODO: Should probably round it, but f32::round is not available in core.
             if let Some(v) = i16::try_num_from(v) {
                 *value = v;
@@ -2388,15 +3722,26 @@ impl<'a> Face<'a> {
 
     #[cfg(not(feature = "variable-fonts"))]
     #[inline]
-    fn apply_metrics_variation_to(&self, _: Tag, _: &mut i16) {}
+    fn apply_metrics_variation_to(&self, _: Tag, _: &mut i16, _: &[NormalizedCoordinate]) {}
 
-    #[cfg(feature = "variable-fonts")]
     #[inline]
     fn coords(&self) -> &[NormalizedCoordinate] {
-        self.coordinates.as_slice()
+        #[cfg(feature = "variable-fonts")]
+        {
+            self.coordinates.as_slice()
+        }
+
+        #[cfg(not(feature = "variable-fonts"))]
+        {
+            &[]
+        }
     }
 }
 
+fn sum_kerning(values: impl Iterator<Item = i16>) -> Option<f32> {
+    values.fold(None, |sum, v| Some(sum.unwrap_or(0.0) + f32::from(v)))
+}
+
 impl core::fmt::Debug for Face<'_> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "Face()")
@@ -2416,3 +3761,14 @@ pub fn fonts_in_collection(data: &[u8]) -> Option<u32> {
     s.skip::<u32>(); // version
     s.read::<u32>()
 }
+
+/// Returns the number of fonts stored in an Apple resource fork.
+///
+/// Resource forks are used by old macOS fonts, including `.dfont` files.
+/// Only `sfnt` resources are counted.
+///
+/// Returns `None` if a provided data is not a resource fork.
+#[inline]
+pub fn fonts_in_resource_fork(data: &[u8]) -> Option<u32> {
+    resource_fork::sfnt_resources_count(data)
+}
@@ -0,0 +1,168 @@
+use core::ops::Range;
+
+use crate::parser::LazyArray16;
+use crate::{head, hhea, maxp, Face, FaceParsingError, RawFace};
+#[cfg(feature = "variable-fonts")]
+use crate::{NormalizedCoordinate, Tag, VarCoords};
+
+/// A font face that owns its data.
+///
+/// Unlike [`Face`], doesn't have a lifetime, so it can be stored in a cache
+/// or sent to another thread.
+/// The data can be anything that implements `AsRef<[u8]>`,
+/// like `Vec<u8>` or `Arc<[u8]>`. The latter makes cloning cheap.
+/// `AsRef` must return the same data each time.
+///
+/// Since self-referential types are impossible without `unsafe`,
+/// the mandatory tables and the table records position are stored instead of a [`Face`].
+/// [`OwnedFace::as_face`] builds a [`Face`] from them without parsing the font header
+/// and the mandatory tables again. The variation coordinates are stored as well
+/// and belong to this instance.
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// let data: Arc<[u8]> = std::fs::read("font.ttf").unwrap().into();
+/// let face = ttf_parser::OwnedFace::parse(data, 0).unwrap();
+/// std::thread::spawn(move || {
+///     println!("{}", face.as_face().number_of_glyphs());
+/// });
+/// ```
+#[derive(Clone)]
+pub struct OwnedFace<D> {
+    data: D,
+    index: u32,
+    table_records: Range<usize>,
+    head: head::Table,
+    hhea: hhea::Table,
+    maxp: maxp::Table,
+    #[cfg(feature = "variable-fonts")]
+    coordinates: VarCoords,
+    #[cfg(feature = "variable-fonts")]
+    unmapped_coordinates: VarCoords,
+}
+
+impl<D: AsRef<[u8]>> OwnedFace<D> {
+    /// Creates a new [`OwnedFace`] from a raw data.
+    ///
+    /// See [`Face::parse`] for details.
+    pub fn parse(data: D, index: u32) -> Result<Self, FaceParsingError> {
+        let raw_face = RawFace::parse(data.as_ref(), index)?;
+        let table_records = raw_face
+            .table_records_range()
+            .ok_or(FaceParsingError::MalformedFont)?;
+        let face = Face::from_raw_face(raw_face)?;
+        let tables = face.tables();
+        let (head, hhea, maxp) = (tables.head, tables.hhea, tables.maxp);
+        #[cfg(feature = "variable-fonts")]
+        let coordinates = face.coordinates.clone();
+        #[cfg(feature = "variable-fonts")]
+        let unmapped_coordinates = face.unmapped_coordinates.clone();
+
+        Ok(OwnedFace {
+            data,
+            index,
+            table_records,
+            head,
+            hhea,
+            maxp,
+            #[cfg(feature = "variable-fonts")]
+            coordinates,
+            #[cfg(feature = "variable-fonts")]
+            unmapped_coordinates,
+        })
+    }
+
+    /// Returns a [`Face`] that borrows this face data.
+    ///
+    /// Optional tables are parsed again, which is cheap, since tables are parsed lazily.
+    /// Variation coordinates of this face are applied.
+    pub fn as_face(&self) -> Face<'_> {
+        let data = self.data.as_ref();
+        // Table records were already found during parsing, so this can fail
+        // only when `AsRef` returns different data, in which case tables are ignored.
+        let table_records = data
+            .get(self.table_records.clone())
+            .map(LazyArray16::new)
+            .unwrap_or_default();
+        let raw_face = RawFace {
+            data,
+            table_records,
+        };
+
+        #[allow(unused_mut)]
+        let mut face = Face::from_mandatory_tables(raw_face, self.head, self.hhea, self.maxp);
+        #[cfg(feature = "variable-fonts")]
+        {
+            if face.coordinates.len == self.coordinates.len {
+                face.coordinates = self.coordinates.clone();
+                face.unmapped_coordinates = self.unmapped_coordinates.clone();
+            }
+        }
+
+        face
+    }
+
+    /// Returns the face data.
+    #[inline]
+    pub fn data(&self) -> &D {
+        &self.data
+    }
+
+    /// Returns the face data, consuming the face.
+    #[inline]
+    pub fn into_data(self) -> D {
+        self.data
+    }
+
+    /// Returns the face index in a font collection.
+    #[inline]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Sets a variation axis coordinate.
+    ///
+    /// See [`Face::set_variation`] for details.
+    #[cfg(feature = "variable-fonts")]
+    pub fn set_variation(&mut self, axis: Tag, value: f32) -> Option<()> {
+        let (coordinates, unmapped_coordinates) = {
+            let mut face = self.as_face();
+            face.set_variation(axis, value)?;
+            (face.coordinates, face.unmapped_coordinates)
+        };
+
+        self.coordinates = coordinates;
+        self.unmapped_coordinates = unmapped_coordinates;
+        Some(())
+    }
+
+    /// Sets variation coordinates from a named instance.
+    ///
+    /// See [`Face::set_named_instance`] for details.
+    #[cfg(feature = "variable-fonts")]
+    pub fn set_named_instance(&mut self, index: u16) -> Option<()> {
+        let (coordinates, unmapped_coordinates) = {
+            let mut face = self.as_face();
+            face.set_named_instance(index)?;
+            (face.coordinates, face.unmapped_coordinates)
+        };
+
+        self.coordinates = coordinates;
+        self.unmapped_coordinates = unmapped_coordinates;
+        Some(())
+    }
+
+    /// Returns the current normalized variation coordinates.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn variation_coordinates(&self) -> &[NormalizedCoordinate] {
+        self.coordinates.as_slice()
+    }
+}
+
+impl<D> core::fmt::Debug for OwnedFace<D> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "OwnedFace()")
+    }
+}
@@ -318,6 +318,12 @@ impl<'a, T: FromData> LazyArray16<'a, T> {
         (self.data.len() / T::SIZE) as u16
     }
 
+    /// Returns array's raw data.
+    #[inline]
+    pub(crate) fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
     /// Checks if array is empty.
     #[inline]
     pub fn is_empty(&self) -> bool {
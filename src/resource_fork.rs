@@ -0,0 +1,125 @@
+// An Apple resource fork parser.
+//
+// Old macOS fonts are stored as `sfnt` resources inside a resource fork,
+// either in the file's resource fork or in the data fork of a `.dfont` file.
+// Each `sfnt` resource is a regular TrueType/OpenType font.
+//
+// https://developer.apple.com/library/archive/documentation/mac/pdf/MoreMacintoshToolbox.pdf
+// Chapter 1-121 "Resource File Format"
+
+use core::convert::TryFrom;
+
+use crate::parser::{FromData, LazyArray16, NumFrom, Stream, U24};
+use crate::{FaceParsingError, Tag};
+
+const SFNT_TYPE: Tag = Tag::from_bytes(b"sfnt");
+
+#[derive(Clone, Copy)]
+struct TypeRecord {
+    kind: Tag,
+    // The number of resources is stored minus one.
+    last_index: u16,
+    references_offset: u16,
+}
+
+impl FromData for TypeRecord {
+    const SIZE: usize = 8;
+
+    #[inline]
+    fn parse(data: &[u8]) -> Option<Self> {
+        let mut s = Stream::new(data);
+        Some(TypeRecord {
+            kind: s.read::<Tag>()?,
+            last_index: s.read::<u16>()?,
+            references_offset: s.read::<u16>()?,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ReferenceRecord {
+    data_offset: U24,
+}
+
+impl FromData for ReferenceRecord {
+    const SIZE: usize = 12;
+
+    #[inline]
+    fn parse(data: &[u8]) -> Option<Self> {
+        let mut s = Stream::new(data);
+        s.skip::<u16>(); // resource ID
+        s.skip::<u16>(); // name offset
+        s.skip::<u8>(); // attributes
+        let data_offset = s.read::<U24>()?;
+        // Skip the reserved handle.
+        Some(ReferenceRecord { data_offset })
+    }
+}
+
+struct SfntResources<'a> {
+    // The resource data section.
+    data: &'a [u8],
+    references: LazyArray16<'a, ReferenceRecord>,
+}
+
+fn parse(data: &[u8]) -> Option<SfntResources<'_>> {
+    let mut s = Stream::new(data);
+    let data_offset = s.read::<u32>()?;
+    let map_offset = s.read::<u32>()?;
+    let data_length = s.read::<u32>()?;
+    let map_length = s.read::<u32>()?;
+
+    let resources_data = {
+        let start = usize::num_from(data_offset);
+        data.get(start..start.checked_add(usize::num_from(data_length))?)?
+    };
+    let map = {
+        let start = usize::num_from(map_offset);
+        data.get(start..start.checked_add(usize::num_from(map_length))?)?
+    };
+
+    let mut s = Stream::new_at(map, 24)?; // header copy + next map + file ref + attributes
+    let type_list_offset = s.read::<u16>()?;
+    let type_list = map.get(usize::from(type_list_offset)..)?;
+
+    let mut s = Stream::new(type_list);
+    // An empty type list stores 0xFFFF.
+    let types_count = s.read::<u16>()?.wrapping_add(1);
+    let types = s.read_array16::<TypeRecord>(types_count)?;
+    let references = match types.into_iter().find(|t| t.kind == SFNT_TYPE) {
+        Some(record) => {
+            let mut s = Stream::new_at(type_list, usize::from(record.references_offset))?;
+            s.read_array16::<ReferenceRecord>(record.last_index.checked_add(1)?)?
+        }
+        None => LazyArray16::default(),
+    };
+
+    Some(SfntResources {
+        data: resources_data,
+        references,
+    })
+}
+
+/// Returns the number of `sfnt` resources.
+pub(crate) fn sfnt_resources_count(data: &[u8]) -> Option<u32> {
+    parse(data).map(|resources| u32::from(resources.references.len()))
+}
+
+/// Returns the data of an `sfnt` resource.
+pub(crate) fn sfnt_resource(data: &[u8], index: u32) -> Result<&[u8], FaceParsingError> {
+    let resources = parse(data).ok_or(FaceParsingError::MalformedFont)?;
+    let record = u16::try_from(index)
+        .ok()
+        .and_then(|index| resources.references.get(index))
+        .ok_or(FaceParsingError::FaceIndexOutOfBounds)?;
+
+    // Each resource is prefixed with its length.
+    let start = usize::num_from(record.data_offset.0);
+    let length =
+        Stream::read_at::<u32>(resources.data, start).ok_or(FaceParsingError::MalformedFont)?;
+    let start = start + 4;
+    start
+        .checked_add(usize::num_from(length))
+        .and_then(|end| resources.data.get(start..end))
+        .ok_or(FaceParsingError::MalformedFont)
+}
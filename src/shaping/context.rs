@@ -0,0 +1,175 @@
+use std::vec::Vec;
+
+use super::{GlyphInfo, Matcher};
+use crate::opentype_layout::{
+    ChainedContextLookup, ClassDefinition, ContextLookup, Coverage, SequenceLookupRecord,
+};
+use crate::parser::{LazyArray16, LazyOffsetArray16};
+use crate::GlyphId;
+
+/// A matched (chained) context.
+pub(crate) struct ContextMatch<'a> {
+    /// Indices of the input glyphs.
+    pub positions: Vec<usize>,
+    /// Nested lookups to apply.
+    pub lookups: LazyArray16<'a, SequenceLookupRecord>,
+}
+
+impl<'a> ContextMatch<'a> {
+    /// Returns an index right after the last input glyph.
+    pub fn end(&self) -> usize {
+        self.positions.last().map(|i| i + 1).unwrap_or(0)
+    }
+}
+
+fn match_glyph(values: LazyArray16<'_, u16>) -> impl Fn(u16, GlyphId) -> bool + '_ {
+    move |k, glyph| values.get(k) == Some(glyph.0)
+}
+
+fn match_class<'a>(
+    values: LazyArray16<'a, u16>,
+    classes: ClassDefinition<'a>,
+) -> impl Fn(u16, GlyphId) -> bool + 'a {
+    move |k, glyph| values.get(k) == Some(classes.get(glyph))
+}
+
+pub(super) fn match_coverage<'a>(
+    coverages: LazyOffsetArray16<'a, Coverage<'a>>,
+) -> impl Fn(u16, GlyphId) -> bool + 'a {
+    move |k, glyph| coverages.get(k).map_or(false, |c| c.contains(glyph))
+}
+
+/// Matches a [`ContextLookup`] at `index`.
+pub(crate) fn match_context<'a>(
+    lookup: &ContextLookup<'a>,
+    matcher: &Matcher,
+    glyphs: &[GlyphInfo],
+    index: usize,
+) -> Option<ContextMatch<'a>> {
+    let glyph = glyphs[index].glyph_id;
+    match *lookup {
+        ContextLookup::Format1 { coverage, sets } => {
+            let set = sets.get(coverage.get(glyph)?)?;
+            set.into_iter().find_map(|rule| {
+                let f = match_glyph(rule.input);
+                let positions = matcher.match_input(glyphs, index, rule.input.len(), f)?;
+                Some(ContextMatch {
+                    positions,
+                    lookups: rule.lookups,
+                })
+            })
+        }
+        ContextLookup::Format2 {
+            coverage,
+            classes,
+            sets,
+        } => {
+            coverage.get(glyph)?;
+            let set = sets.get(classes.get(glyph))?;
+            set.into_iter().find_map(|rule| {
+                let f = match_class(rule.input, classes);
+                let positions = matcher.match_input(glyphs, index, rule.input.len(), f)?;
+                Some(ContextMatch {
+                    positions,
+                    lookups: rule.lookups,
+                })
+            })
+        }
+        ContextLookup::Format3 {
+            coverage,
+            coverages,
+            lookups,
+        } => {
+            coverage.get(glyph)?;
+            let f = match_coverage(coverages);
+            let positions = matcher.match_input(glyphs, index, coverages.len(), f)?;
+            Some(ContextMatch { positions, lookups })
+        }
+    }
+}
+
+/// Matches a [`ChainedContextLookup`] at `index`.
+pub(crate) fn match_chained_context<'a>(
+    lookup: &ChainedContextLookup<'a>,
+    matcher: &Matcher,
+    glyphs: &[GlyphInfo],
+    index: usize,
+) -> Option<ContextMatch<'a>> {
+    let glyph = glyphs[index].glyph_id;
+    match *lookup {
+        ChainedContextLookup::Format1 { coverage, sets } => {
+            let set = sets.get(coverage.get(glyph)?)?;
+            set.into_iter().find_map(|rule| {
+                let f = match_glyph(rule.input);
+                let positions = matcher.match_input(glyphs, index, rule.input.len(), f)?;
+                let last = *positions.last()?;
+                let f = match_glyph(rule.backtrack);
+                if !matcher.match_backtrack(glyphs, index, rule.backtrack.len(), f) {
+                    return None;
+                }
+
+                let f = match_glyph(rule.lookahead);
+                if !matcher.match_lookahead(glyphs, last, rule.lookahead.len(), f) {
+                    return None;
+                }
+
+                Some(ContextMatch {
+                    positions,
+                    lookups: rule.lookups,
+                })
+            })
+        }
+        ChainedContextLookup::Format2 {
+            coverage,
+            backtrack_classes,
+            input_classes,
+            lookahead_classes,
+            sets,
+        } => {
+            coverage.get(glyph)?;
+            let set = sets.get(input_classes.get(glyph))?;
+            set.into_iter().find_map(|rule| {
+                let f = match_class(rule.input, input_classes);
+                let positions = matcher.match_input(glyphs, index, rule.input.len(), f)?;
+                let last = *positions.last()?;
+                let f = match_class(rule.backtrack, backtrack_classes);
+                if !matcher.match_backtrack(glyphs, index, rule.backtrack.len(), f) {
+                    return None;
+                }
+
+                let f = match_class(rule.lookahead, lookahead_classes);
+                if !matcher.match_lookahead(glyphs, last, rule.lookahead.len(), f) {
+                    return None;
+                }
+
+                Some(ContextMatch {
+                    positions,
+                    lookups: rule.lookups,
+                })
+            })
+        }
+        ChainedContextLookup::Format3 {
+            coverage,
+            backtrack_coverages,
+            input_coverages,
+            lookahead_coverages,
+            lookups,
+        } => {
+            coverage.get(glyph)?;
+            let f = match_coverage(input_coverages);
+            let positions = matcher.match_input(glyphs, index, input_coverages.len(), f)?;
+            let last = *positions.last()?;
+            let f = match_coverage(backtrack_coverages);
+            if !matcher.match_backtrack(glyphs, index, backtrack_coverages.len(), f) {
+                return None;
+            }
+
+            let f = match_coverage(lookahead_coverages);
+            if !matcher.match_lookahead(glyphs, last, lookahead_coverages.len(), f) {
+                return None;
+            }
+
+            Some(ContextMatch { positions, lookups })
+        }
+    }
+}
@@ -0,0 +1,523 @@
+use std::vec::Vec;
+
+use super::context::{match_chained_context, match_context, ContextMatch};
+use super::{Buffer, GlyphInfo, GlyphPosition, Matcher, MAX_NESTING_LEVEL};
+use crate::gdef::{self, GlyphClass};
+use crate::gpos::{
+    Anchor, CursiveAdjustment, MarkToBaseAdjustment, MarkToLigatureAdjustment,
+    MarkToMarkAdjustment, PairAdjustment, PositioningSubtable, SingleAdjustment, ValueRecord,
+};
+use crate::opentype_layout::{LayoutTable, Lookup, LookupIndex};
+use crate::Face;
+#[cfg(feature = "variable-fonts")]
+use crate::NormalizedCoordinate;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum AttachmentKind {
+    Mark,
+    Cursive,
+}
+
+/// A glyph attachment to another glyph.
+#[derive(Clone, Copy, Debug)]
+struct Attachment {
+    kind: AttachmentKind,
+    /// A relative index of the glyph this glyph is attached to.
+    chain: isize,
+}
+
+/// Applies [GPOS](https://docs.microsoft.com/en-us/typography/opentype/spec/gpos)
+/// lookups to a [`Buffer`].
+///
+/// Assumes left-to-right horizontal layout.
+#[derive(Clone)]
+pub struct Positioner<'a> {
+    table: LayoutTable<'a>,
+    gdef: Option<gdef::Table<'a>>,
+    #[cfg(feature = "variable-fonts")]
+    coordinates: Vec<NormalizedCoordinate>,
+}
+
+impl core::fmt::Debug for Positioner<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Positioner {{ ... }}")
+    }
+}
+
+impl<'a> Positioner<'a> {
+    /// Creates a new positioner.
+    ///
+    /// `gdef` glyph classes are used to ignore glyphs according to lookup flags
+    /// and to find glyphs marks are attached to.
+    pub fn new(table: LayoutTable<'a>, gdef: Option<gdef::Table<'a>>) -> Self {
+        Positioner {
+            table,
+            gdef,
+            #[cfg(feature = "variable-fonts")]
+            coordinates: Vec::new(),
+        }
+    }
+
+    /// Creates a new positioner using face's GPOS and GDEF tables
+    /// and face's current variation coordinates.
+    ///
+    /// Returns `None` when the face has no GPOS table.
+    pub fn from_face(face: &Face<'a>) -> Option<Self> {
+        let tables = face.tables();
+        #[allow(unused_mut)]
+        let mut positioner = Self::new(tables.gpos?, tables.gdef);
+        #[cfg(feature = "variable-fonts")]
+        positioner.set_variation_coordinates(face.variation_coordinates());
+        Some(positioner)
+    }
+
+    /// Sets variation coordinates used to resolve `GDEF` variation deltas.
+    ///
+    /// Deltas are not applied by default.
+    #[cfg(feature = "variable-fonts")]
+    pub fn set_variation_coordinates(&mut self, coordinates: &[NormalizedCoordinate]) {
+        self.coordinates.clear();
+        self.coordinates.extend_from_slice(coordinates);
+    }
+
+    /// Applies lookups in the specified order.
+    ///
+    /// Adjusts buffer positions, which are filled with zeros first
+    /// when they weren't initialized via [`Buffer::reset_positions`].
+    /// Mark and cursive attachment offsets are resolved after all lookups were applied.
+    pub fn apply(&self, lookups: impl IntoIterator<Item = LookupIndex>, buffer: &mut Buffer) {
+        let len = buffer.glyphs.len();
+        buffer.positions.resize(len, GlyphPosition::default());
+
+        let mut ctx = Context {
+            positioner: self,
+            glyphs: &buffer.glyphs,
+            positions: &mut buffer.positions,
+            attachments: vec![None; len],
+        };
+
+        for index in lookups {
+            if let Some(lookup) = self.table.lookups.get(index) {
+                ctx.apply_lookup(&lookup);
+            }
+        }
+
+        ctx.resolve_attachments();
+    }
+
+    /// Applies a single lookup to the whole buffer.
+    ///
+    /// Nonexistent lookups are ignored.
+    ///
+    /// Mark and cursive attachment offsets are resolved right after the lookup was applied,
+    /// so adjustments made by lookups applied later are not propagated to attached glyphs.
+    /// Therefore, this method must not be chained. Use [`Positioner::apply`] instead.
+    #[inline]
+    pub fn apply_lookup(&self, index: LookupIndex, buffer: &mut Buffer) {
+        self.apply(core::iter::once(index), buffer);
+    }
+
+    fn value(&self, record: &ValueRecord) -> GlyphPosition {
+        #[cfg(feature = "variable-fonts")]
+        {
+            if let Some(ref gdef) = self.gdef {
+                let coords = self.coordinates.as_slice();
+                return GlyphPosition {
+                    x_advance: record.x_advance_at(gdef, coords),
+                    y_advance: record.y_advance_at(gdef, coords),
+                    x_offset: record.x_placement_at(gdef, coords),
+                    y_offset: record.y_placement_at(gdef, coords),
+                };
+            }
+        }
+
+        GlyphPosition {
+            x_advance: f32::from(record.x_advance),
+            y_advance: f32::from(record.y_advance),
+            x_offset: f32::from(record.x_placement),
+            y_offset: f32::from(record.y_placement),
+        }
+    }
+
+    fn anchor(&self, anchor: &Anchor) -> (f32, f32) {
+        #[cfg(feature = "variable-fonts")]
+        {
+            if let Some(ref gdef) = self.gdef {
+                let coords = self.coordinates.as_slice();
+                return (anchor.x_at(gdef, coords), anchor.y_at(gdef, coords));
+            }
+        }
+
+        (f32::from(anchor.x), f32::from(anchor.y))
+    }
+}
+
+struct Context<'a, 'b> {
+    positioner: &'b Positioner<'a>,
+    glyphs: &'b [GlyphInfo],
+    positions: &'b mut [GlyphPosition],
+    attachments: Vec<Option<Attachment>>,
+}
+
+impl<'a> Context<'a, '_> {
+    fn apply_lookup(&mut self, lookup: &Lookup<'a>) {
+        let matcher = Matcher::new(self.positioner.gdef, lookup);
+        let mut index = 0;
+        while index < self.glyphs.len() {
+            if !matcher.skip(self.glyphs[index].glyph_id) {
+                if let Some(next) = self.apply_at(lookup, &matcher, index, 0) {
+                    index = next;
+                    continue;
+                }
+            }
+
+            index += 1;
+        }
+    }
+
+    /// Applies the first matching subtable at `index`.
+    ///
+    /// Returns an index of the next glyph to process.
+    fn apply_at(
+        &mut self,
+        lookup: &Lookup<'a>,
+        matcher: &Matcher,
+        index: usize,
+        depth: u8,
+    ) -> Option<usize> {
+        lookup
+            .subtables
+            .into_iter::<PositioningSubtable>()
+            .find_map(|subtable| self.apply_subtable(&subtable, lookup, matcher, index, depth))
+    }
+
+    fn apply_subtable(
+        &mut self,
+        subtable: &PositioningSubtable<'a>,
+        lookup: &Lookup<'a>,
+        matcher: &Matcher,
+        index: usize,
+        depth: u8,
+    ) -> Option<usize> {
+        match subtable {
+            PositioningSubtable::Single(t) => self.apply_single(t, index),
+            PositioningSubtable::Pair(t) => self.apply_pair(t, matcher, index),
+            PositioningSubtable::Cursive(t) => self.apply_cursive(t, lookup, matcher, index),
+            PositioningSubtable::MarkToBase(t) => self.apply_mark_to_base(t, matcher, index),
+            PositioningSubtable::MarkToLigature(t) => {
+                self.apply_mark_to_ligature(t, matcher, index)
+            }
+            PositioningSubtable::MarkToMark(t) => self.apply_mark_to_mark(t, matcher, index),
+            PositioningSubtable::Context(t) => {
+                let context = match_context(t, matcher, self.glyphs, index)?;
+                Some(self.apply_nested(context, index, depth))
+            }
+            PositioningSubtable::ChainContext(t) => {
+                let context = match_chained_context(t, matcher, self.glyphs, index)?;
+                Some(self.apply_nested(context, index, depth))
+            }
+        }
+    }
+
+    fn adjust(&mut self, index: usize, record: &ValueRecord) {
+        let value = self.positioner.value(record);
+        let pos = &mut self.positions[index];
+        pos.x_advance += value.x_advance;
+        pos.y_advance += value.y_advance;
+        pos.x_offset += value.x_offset;
+        pos.y_offset += value.y_offset;
+    }
+
+    fn apply_single(&mut self, subtable: &SingleAdjustment<'a>, index: usize) -> Option<usize> {
+        let glyph = self.glyphs[index].glyph_id;
+        let record = match *subtable {
+            SingleAdjustment::Format1 { coverage, value } => {
+                coverage.get(glyph)?;
+                value
+            }
+            SingleAdjustment::Format2 { coverage, values } => values.get(coverage.get(glyph)?)?,
+        };
+
+        self.adjust(index, &record);
+        Some(index + 1)
+    }
+
+    fn apply_pair(
+        &mut self,
+        subtable: &PairAdjustment<'a>,
+        matcher: &Matcher,
+        index: usize,
+    ) -> Option<usize> {
+        let first = self.glyphs[index].glyph_id;
+        let next = matcher.next(self.glyphs, index)?;
+        let second = self.glyphs[next].glyph_id;
+        let records = subtable.get(first, second)?;
+
+        self.adjust(index, &records.0);
+        self.adjust(next, &records.1);
+
+        // The second glyph is processed again only when it has no value record.
+        if subtable.second_value_format() == 0 {
+            Some(next)
+        } else {
+            Some(next + 1)
+        }
+    }
+
+    fn apply_cursive(
+        &mut self,
+        subtable: &CursiveAdjustment<'a>,
+        lookup: &Lookup<'a>,
+        matcher: &Matcher,
+        index: usize,
+    ) -> Option<usize> {
+        let entry = subtable
+            .sets
+            .entry(subtable.coverage.get(self.glyphs[index].glyph_id)?)?;
+
+        let prev = matcher.prev(self.glyphs, index)?;
+        let exit = subtable
+            .sets
+            .exit(subtable.coverage.get(self.glyphs[prev].glyph_id)?)?;
+
+        let (entry_x, entry_y) = self.positioner.anchor(&entry);
+        let (exit_x, exit_y) = self.positioner.anchor(&exit);
+
+        // Glyphs are joined horizontally by adjusting advances.
+        self.positions[prev].x_advance = exit_x + self.positions[prev].x_offset;
+        let d = entry_x + self.positions[index].x_offset;
+        self.positions[index].x_advance -= d;
+        self.positions[index].x_offset -= d;
+
+        // And vertically by attaching one glyph to another.
+        let (mut child, mut parent) = (prev, index);
+        let mut y_offset = entry_y - exit_y;
+        if !lookup.flags.right_to_left() {
+            core::mem::swap(&mut child, &mut parent);
+            y_offset = -y_offset;
+        }
+
+        self.reverse_cursive_chain(child, parent);
+
+        let chain = parent as isize - child as isize;
+        self.attachments[child] = Some(Attachment {
+            kind: AttachmentKind::Cursive,
+            chain,
+        });
+        self.positions[child].y_offset = y_offset;
+
+        // Break a cycle when the parent was attached to the child.
+        if let Some(attachment) = self.attachments[parent] {
+            if attachment.chain == -chain {
+                self.attachments[parent] = None;
+                self.positions[parent].y_offset = 0.0;
+            }
+        }
+
+        Some(index + 1)
+    }
+
+    /// Reverses an existing cursive chain starting at `index`,
+    /// so the glyph can be attached to `new_parent`.
+    fn reverse_cursive_chain(&mut self, index: usize, new_parent: usize) {
+        let mut links = Vec::new();
+        let mut i = index;
+        while let Some(attachment) = self.attachments[i] {
+            if attachment.kind != AttachmentKind::Cursive {
+                break;
+            }
+
+            self.attachments[i] = None;
+            let j = i as isize + attachment.chain;
+            if j < 0 || j as usize >= self.attachments.len() || j as usize == new_parent {
+                break;
+            }
+
+            links.push((i, j as usize, attachment.chain));
+            i = j as usize;
+        }
+
+        for (i, j, chain) in links.into_iter().rev() {
+            self.positions[j].y_offset = -self.positions[i].y_offset;
+            self.attachments[j] = Some(Attachment {
+                kind: AttachmentKind::Cursive,
+                chain: -chain,
+            });
+        }
+    }
+
+    /// Returns an index of the previous glyph that is not a mark.
+    fn find_base(&self, matcher: &Matcher, index: usize) -> Option<usize> {
+        (0..index)
+            .rev()
+            .find(|i| matcher.glyph_class(self.glyphs[*i].glyph_id) != Some(GlyphClass::Mark))
+    }
+
+    fn attach_mark(&mut self, index: usize, base: usize, mark: &Anchor, base_anchor: &Anchor) {
+        let (mark_x, mark_y) = self.positioner.anchor(mark);
+        let (base_x, base_y) = self.positioner.anchor(base_anchor);
+        let pos = &mut self.positions[index];
+        pos.x_offset = base_x - mark_x;
+        pos.y_offset = base_y - mark_y;
+        self.attachments[index] = Some(Attachment {
+            kind: AttachmentKind::Mark,
+            chain: base as isize - index as isize,
+        });
+    }
+
+    fn apply_mark_to_base(
+        &mut self,
+        subtable: &MarkToBaseAdjustment<'a>,
+        matcher: &Matcher,
+        index: usize,
+    ) -> Option<usize> {
+        let mark_index = subtable.mark_coverage.get(self.glyphs[index].glyph_id)?;
+        let base = self.find_base(matcher, index)?;
+        let base_index = subtable.base_coverage.get(self.glyphs[base].glyph_id)?;
+
+        let (class, mark) = subtable.marks.get(mark_index)?;
+        let base_anchor = subtable.anchors.get(base_index, class)?;
+        self.attach_mark(index, base, &mark, &base_anchor);
+        Some(index + 1)
+    }
+
+    fn apply_mark_to_ligature(
+        &mut self,
+        subtable: &MarkToLigatureAdjustment<'a>,
+        matcher: &Matcher,
+        index: usize,
+    ) -> Option<usize> {
+        let mark_info = self.glyphs[index];
+        let mark_index = subtable.mark_coverage.get(mark_info.glyph_id)?;
+        let base = self.find_base(matcher, index)?;
+        let base_info = self.glyphs[base];
+        let ligature_index = subtable.ligature_coverage.get(base_info.glyph_id)?;
+        let anchors = subtable.ligature_array.get(ligature_index)?;
+        if anchors.rows == 0 {
+            return None;
+        }
+
+        // Marks that were placed between ligature components are attached
+        // to the corresponding component. Other marks use the last one.
+        let component = if base_info.ligature_id != 0
+            && base_info.ligature_id == mark_info.ligature_id
+            && mark_info.ligature_component > 0
+        {
+            core::cmp::min(anchors.rows, u16::from(mark_info.ligature_component)) - 1
+        } else {
+            anchors.rows - 1
+        };
+
+        let (class, mark) = subtable.marks.get(mark_index)?;
+        let base_anchor = anchors.get(component, class)?;
+        self.attach_mark(index, base, &mark, &base_anchor);
+        Some(index + 1)
+    }
+
+    fn apply_mark_to_mark(
+        &mut self,
+        subtable: &MarkToMarkAdjustment<'a>,
+        matcher: &Matcher,
+        index: usize,
+    ) -> Option<usize> {
+        let mark1_info = self.glyphs[index];
+        let mark1_index = subtable.mark1_coverage.get(mark1_info.glyph_id)?;
+
+        let prev = matcher.prev(self.glyphs, index)?;
+        let mark2_info = self.glyphs[prev];
+        if matcher.glyph_class(mark2_info.glyph_id) != Some(GlyphClass::Mark) {
+            return None;
+        }
+
+        // Marks must belong to the same ligature component.
+        let (id1, id2) = (mark1_info.ligature_id, mark2_info.ligature_id);
+        let (comp1, comp2) = (mark1_info.ligature_component, mark2_info.ligature_component);
+        let is_same_component = if id1 == id2 {
+            id1 == 0 || comp1 == comp2
+        } else {
+            // One of the marks is itself a ligature.
+            (id1 > 0 && comp1 == 0) || (id2 > 0 && comp2 == 0)
+        };
+
+        if !is_same_component {
+            return None;
+        }
+
+        let mark2_index = subtable.mark2_coverage.get(mark2_info.glyph_id)?;
+        let (class, mark) = subtable.marks.get(mark1_index)?;
+        let mark2_anchor = subtable.mark2_matrix.get(mark2_index, class)?;
+        self.attach_mark(index, prev, &mark, &mark2_anchor);
+        Some(index + 1)
+    }
+
+    /// Applies nested lookups of a matched context.
+    ///
+    /// Returns an index of the next glyph to process.
+    fn apply_nested(&mut self, context: ContextMatch<'a>, index: usize, depth: u8) -> usize {
+        let end = context.end();
+        if depth >= MAX_NESTING_LEVEL {
+            return end;
+        }
+
+        for record in context.lookups {
+            let pos = match context.positions.get(usize::from(record.sequence_index)) {
+                Some(v) => *v,
+                None => continue,
+            };
+
+            let lookups = self.positioner.table.lookups;
+            if let Some(lookup) = lookups.get(record.lookup_list_index) {
+                let matcher = Matcher::new(self.positioner.gdef, &lookup);
+                self.apply_at(&lookup, &matcher, pos, depth + 1);
+            }
+        }
+
+        core::cmp::max(end, index + 1)
+    }
+
+    /// Adds offsets of glyphs that other glyphs are attached to.
+    fn resolve_attachments(&mut self) {
+        for index in 0..self.positions.len() {
+            self.resolve_attachment(index, 0);
+        }
+    }
+
+    fn resolve_attachment(&mut self, index: usize, depth: u8) {
+        let attachment = match self.attachments[index].take() {
+            Some(v) => v,
+            None => return,
+        };
+
+        let parent = index as isize + attachment.chain;
+        if parent < 0 || parent as usize >= self.positions.len() {
+            return;
+        }
+        let parent = parent as usize;
+
+        // Parent offsets must be resolved first.
+        if depth < MAX_NESTING_LEVEL {
+            self.resolve_attachment(parent, depth + 1);
+        }
+
+        let parent_pos = self.positions[parent];
+        match attachment.kind {
+            AttachmentKind::Cursive => {
+                self.positions[index].y_offset += parent_pos.y_offset;
+            }
+            AttachmentKind::Mark => {
+                let (mut x_offset, mut y_offset) = (parent_pos.x_offset, parent_pos.y_offset);
+                // A mark is positioned relative to its base, not to the current pen position.
+                if parent < index {
+                    for pos in &self.positions[parent..index] {
+                        x_offset -= pos.x_advance;
+                        y_offset -= pos.y_advance;
+                    }
+                }
+
+                let pos = &mut self.positions[index];
+                pos.x_offset += x_offset;
+                pos.y_offset += y_offset;
+            }
+        }
+    }
+}
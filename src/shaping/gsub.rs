@@ -0,0 +1,382 @@
+use core::cmp;
+use std::vec::Vec;
+
+use super::context::{match_chained_context, match_context, match_coverage, ContextMatch};
+use super::{Buffer, GlyphInfo, Matcher, MAX_NESTING_LEVEL};
+use crate::gdef::{self, GlyphClass};
+use crate::gsub::{ReverseChainSingleSubstitution, SingleSubstitution, SubstitutionSubtable};
+use crate::opentype_layout::{LayoutTable, Lookup, LookupIndex};
+use crate::parser::LazyArray16;
+use crate::{Face, GlyphId};
+
+/// Applies [GSUB](https://docs.microsoft.com/en-us/typography/opentype/spec/gsub)
+/// lookups to a [`Buffer`].
+#[derive(Clone, Copy)]
+pub struct Substitutor<'a> {
+    table: LayoutTable<'a>,
+    gdef: Option<gdef::Table<'a>>,
+    alternate_index: u16,
+}
+
+impl core::fmt::Debug for Substitutor<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Substitutor {{ ... }}")
+    }
+}
+
+impl<'a> Substitutor<'a> {
+    /// Creates a new substitutor.
+    ///
+    /// `gdef` glyph classes are used to ignore glyphs according to lookup flags.
+    /// Without it, no glyphs will be ignored.
+    pub fn new(table: LayoutTable<'a>, gdef: Option<gdef::Table<'a>>) -> Self {
+        Substitutor {
+            table,
+            gdef,
+            alternate_index: 0,
+        }
+    }
+
+    /// Creates a new substitutor using face's GSUB and GDEF tables.
+    ///
+    /// Returns `None` when the face has no GSUB table.
+    pub fn from_face(face: &Face<'a>) -> Option<Self> {
+        let tables = face.tables();
+        Some(Self::new(tables.gsub?, tables.gdef))
+    }
+
+    /// Sets an index of a glyph picked by *Alternate Substitution* lookups.
+    ///
+    /// The first alternate is used by default.
+    /// Glyphs without an alternate at this index remain unchanged.
+    pub fn set_alternate_index(&mut self, index: u16) {
+        self.alternate_index = index;
+    }
+
+    /// Applies lookups in the specified order.
+    pub fn apply(&self, lookups: impl IntoIterator<Item = LookupIndex>, buffer: &mut Buffer) {
+        for index in lookups {
+            self.apply_lookup(index, buffer);
+        }
+    }
+
+    /// Applies a single lookup to the whole buffer.
+    ///
+    /// Nonexistent lookups are ignored.
+    pub fn apply_lookup(&self, index: LookupIndex, buffer: &mut Buffer) {
+        let lookup = match self.table.lookups.get(index) {
+            Some(v) => v,
+            None => return,
+        };
+
+        // Limits the buffer growth caused by malformed fonts.
+        let max_len = cmp::max(buffer.len().saturating_mul(32), 8192);
+        let mut ctx = Context {
+            substitutor: self,
+            buffer,
+            max_len,
+        };
+        ctx.apply_lookup(&lookup);
+    }
+}
+
+struct Context<'a, 'b> {
+    substitutor: &'b Substitutor<'a>,
+    buffer: &'b mut Buffer,
+    max_len: usize,
+}
+
+impl<'a> Context<'a, '_> {
+    fn apply_lookup(&mut self, lookup: &Lookup<'a>) {
+        let matcher = Matcher::new(self.substitutor.gdef, lookup);
+
+        let is_reverse = lookup
+            .subtables
+            .get::<SubstitutionSubtable>(0)
+            .map_or(false, |t| t.is_reverse());
+
+        if is_reverse {
+            // Reverse lookups never change the buffer length.
+            for index in (0..self.buffer.glyphs.len()).rev() {
+                if matcher.skip(self.buffer.glyphs[index].glyph_id) {
+                    continue;
+                }
+
+                for subtable in lookup.subtables.into_iter::<SubstitutionSubtable>() {
+                    if let SubstitutionSubtable::ReverseChainSingle(ref t) = subtable {
+                        if self.apply_reverse(t, &matcher, index).is_some() {
+                            break;
+                        }
+                    }
+                }
+            }
+        } else {
+            let mut index = 0;
+            while index < self.buffer.glyphs.len() {
+                if !matcher.skip(self.buffer.glyphs[index].glyph_id) {
+                    if let Some(next) = self.apply_at(lookup, &matcher, index, 0) {
+                        index = next;
+                        continue;
+                    }
+                }
+
+                index += 1;
+            }
+        }
+    }
+
+    /// Applies the first matching subtable at `index`.
+    ///
+    /// Returns an index of the next glyph to process.
+    fn apply_at(
+        &mut self,
+        lookup: &Lookup<'a>,
+        matcher: &Matcher,
+        index: usize,
+        depth: u8,
+    ) -> Option<usize> {
+        lookup
+            .subtables
+            .into_iter::<SubstitutionSubtable>()
+            .find_map(|subtable| self.apply_subtable(&subtable, matcher, index, depth))
+    }
+
+    fn apply_subtable(
+        &mut self,
+        subtable: &SubstitutionSubtable<'a>,
+        matcher: &Matcher,
+        index: usize,
+        depth: u8,
+    ) -> Option<usize> {
+        let glyph = self.buffer.glyphs[index].glyph_id;
+        match subtable {
+            SubstitutionSubtable::Single(t) => {
+                let substitute = match *t {
+                    SingleSubstitution::Format1 { coverage, delta } => {
+                        coverage.get(glyph)?;
+                        // Addition is modulo 65536.
+                        GlyphId(glyph.0.wrapping_add(delta as u16))
+                    }
+                    SingleSubstitution::Format2 {
+                        coverage,
+                        substitutes,
+                    } => substitutes.get(coverage.get(glyph)?)?,
+                };
+
+                self.buffer.glyphs[index].glyph_id = substitute;
+                Some(index + 1)
+            }
+            SubstitutionSubtable::Multiple(t) => {
+                let sequence = t.sequences.get(t.coverage.get(glyph)?)?;
+                self.multiply(index, sequence.substitutes)
+            }
+            SubstitutionSubtable::Alternate(t) => {
+                let set = t.alternate_sets.get(t.coverage.get(glyph)?)?;
+                let alternate = set.alternates.get(self.substitutor.alternate_index)?;
+                self.buffer.glyphs[index].glyph_id = alternate;
+                Some(index + 1)
+            }
+            SubstitutionSubtable::Ligature(t) => {
+                let set = t.ligature_sets.get(t.coverage.get(glyph)?)?;
+                let glyphs = &self.buffer.glyphs;
+                let (ligature, positions) = set.into_iter().find_map(|ligature| {
+                    let components = ligature.components;
+                    let positions =
+                        matcher.match_input(glyphs, index, components.len(), |k, glyph| {
+                            components.get(k) == Some(glyph)
+                        })?;
+                    Some((ligature.glyph, positions))
+                })?;
+
+                Some(self.ligate(matcher, &positions, ligature))
+            }
+            SubstitutionSubtable::Context(t) => {
+                let context = match_context(t, matcher, &self.buffer.glyphs, index)?;
+                Some(self.apply_nested(context, index, depth))
+            }
+            SubstitutionSubtable::ChainContext(t) => {
+                let context = match_chained_context(t, matcher, &self.buffer.glyphs, index)?;
+                Some(self.apply_nested(context, index, depth))
+            }
+            // Can be applied only in reverse order.
+            SubstitutionSubtable::ReverseChainSingle(_) => None,
+        }
+    }
+
+    /// Replaces a glyph at `index` with a sequence of glyphs.
+    ///
+    /// An empty sequence removes the glyph.
+    fn multiply(&mut self, index: usize, substitutes: LazyArray16<GlyphId>) -> Option<usize> {
+        let count = usize::from(substitutes.len());
+        if count == 1 {
+            self.buffer.glyphs[index].glyph_id = substitutes.get(0)?;
+            return Some(index + 1);
+        }
+
+        if self.buffer.glyphs.len() - 1 + count > self.max_len {
+            return None;
+        }
+
+        let info = self.buffer.glyphs[index];
+        let glyphs = substitutes
+            .into_iter()
+            .enumerate()
+            .map(|(i, glyph_id)| GlyphInfo {
+                glyph_id,
+                ligature_id: 0,
+                ligature_component: cmp::min(i + 1, usize::from(u8::MAX)) as u8,
+                ..info
+            });
+        self.buffer.glyphs.splice(index..=index, glyphs);
+        Some(index + count)
+    }
+
+    /// Replaces glyphs at `positions` with a ligature.
+    ///
+    /// Ignored glyphs between components are preserved and attached
+    /// to the preceding component.
+    fn ligate(&mut self, matcher: &Matcher, positions: &[usize], ligature: GlyphId) -> usize {
+        let first = positions[0];
+        let last = positions[positions.len() - 1];
+        if positions.len() == 1 {
+            self.buffer.glyphs[first].glyph_id = ligature;
+            return first + 1;
+        }
+
+        // Ligatures of marks are treated as marks and do not own other marks.
+        let is_mark_ligature = positions.iter().all(|i| {
+            let glyph = self.buffer.glyphs[*i].glyph_id;
+            matcher.glyph_class(glyph) == Some(GlyphClass::Mark)
+        });
+        let ligature_id = if is_mark_ligature {
+            0
+        } else {
+            self.buffer.allocate_ligature_id()
+        };
+
+        let range = first..=last;
+        let cluster = self.buffer.glyphs[range.clone()]
+            .iter()
+            .map(|info| info.cluster)
+            .min()
+            .unwrap_or(0);
+
+        let mut glyphs = Vec::with_capacity(last - first + 2 - positions.len());
+        let mut matched = 0;
+        for i in range.clone() {
+            let mut info = self.buffer.glyphs[i];
+            info.cluster = cluster;
+
+            if positions.get(matched) == Some(&i) {
+                matched += 1;
+                // Only the first component is kept and replaced with the ligature.
+                if matched == 1 {
+                    info.glyph_id = ligature;
+                    info.ligature_id = ligature_id;
+                    info.ligature_component = 0;
+                    glyphs.push(info);
+                }
+            } else {
+                if ligature_id != 0 {
+                    info.ligature_id = ligature_id;
+                    info.ligature_component = cmp::min(matched, usize::from(u8::MAX)) as u8;
+                }
+
+                glyphs.push(info);
+            }
+        }
+
+        let len = glyphs.len();
+        self.buffer.glyphs.splice(range, glyphs);
+        first + len
+    }
+
+    /// Applies nested lookups of a matched context.
+    ///
+    /// Returns an index of the next glyph to process.
+    fn apply_nested(&mut self, context: ContextMatch<'a>, index: usize, depth: u8) -> usize {
+        let mut end = context.end();
+        if depth >= MAX_NESTING_LEVEL {
+            return end;
+        }
+
+        let mut positions = context.positions;
+        for record in context.lookups {
+            let idx = usize::from(record.sequence_index);
+            let pos = match positions.get(idx) {
+                Some(v) if *v < self.buffer.glyphs.len() => *v,
+                _ => continue,
+            };
+
+            let lookup = match self.substitutor.table.lookups.get(record.lookup_list_index) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let matcher = Matcher::new(self.substitutor.gdef, &lookup);
+            let old_len = self.buffer.glyphs.len();
+            if self.apply_at(&lookup, &matcher, pos, depth + 1).is_none() {
+                continue;
+            }
+
+            let mut delta = self.buffer.glyphs.len() as isize - old_len as isize;
+            if delta == 0 {
+                continue;
+            }
+
+            // Multiple and ligature substitutions change the buffer length,
+            // therefore the remaining input positions must be adjusted.
+            // The same way HarfBuzz does.
+            let mut new_end = end as isize + delta;
+            if new_end < pos as isize {
+                delta += pos as isize - new_end;
+                new_end = pos as isize;
+            }
+            end = new_end as usize;
+
+            let mut next = idx + 1;
+            if delta > 0 {
+                // Newly added glyphs are the next input glyphs.
+                let count = delta as usize;
+                positions.splice(next..next, core::iter::repeat(0).take(count));
+                for i in next..next + count {
+                    positions[i] = positions[i - 1] + 1;
+                }
+                next += count;
+            } else {
+                let count = cmp::min(delta.unsigned_abs(), positions.len() - next);
+                positions.drain(next..next + count);
+                delta = -(count as isize);
+            }
+
+            for position in &mut positions[next..] {
+                *position = cmp::max(*position as isize + delta, 0) as usize;
+            }
+        }
+
+        cmp::max(end, index + 1)
+    }
+
+    fn apply_reverse(
+        &mut self,
+        subtable: &ReverseChainSingleSubstitution<'a>,
+        matcher: &Matcher,
+        index: usize,
+    ) -> Option<()> {
+        let glyphs = &self.buffer.glyphs;
+        let coverage_index = subtable.coverage.get(glyphs[index].glyph_id)?;
+
+        let coverages = subtable.backtrack_coverages;
+        if !matcher.match_backtrack(glyphs, index, coverages.len(), match_coverage(coverages)) {
+            return None;
+        }
+
+        let coverages = subtable.lookahead_coverages;
+        if !matcher.match_lookahead(glyphs, index, coverages.len(), match_coverage(coverages)) {
+            return None;
+        }
+
+        self.buffer.glyphs[index].glyph_id = subtable.substitutes.get(coverage_index)?;
+        Some(())
+    }
+}
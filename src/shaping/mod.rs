@@ -0,0 +1,304 @@
+//! OpenType Layout lookups application.
+//!
+//! Runs GSUB and GPOS lookups over a [`Buffer`] of glyphs.
+//! Lookups selection is up to the caller, usually via
+//! [`LayoutTable::feature_lookups`](crate::opentype_layout::LayoutTable::feature_lookups).
+//! For scripts without complex shaping requirements, [`shape`] does it all at once.
+//! Requires heap allocations.
+//!
+//! ```no_run
+//! use ttf_parser::{shaping::{Buffer, Positioner, Substitutor}, Face, Tag};
+//!
+//! let data = std::fs::read("font.ttf").unwrap();
+//! let face = Face::parse(&data, 0).unwrap();
+//!
+//! let mut buffer = Buffer::new();
+//! for (i, c) in "office".chars().enumerate() {
+//!     buffer.push(face.glyph_index(c).unwrap_or_default(), i as u32);
+//! }
+//!
+//! let gsub = face.tables().gsub.unwrap();
+//! let lookups = gsub.feature_lookups(Tag::from_bytes(b"latn"), None, Tag::from_bytes(b"liga"));
+//! let substitutor = Substitutor::new(gsub, face.tables().gdef);
+//! substitutor.apply(lookups.into_iter().flatten(), &mut buffer);
+//!
+//! buffer.reset_positions(&face);
+//! let gpos = face.tables().gpos.unwrap();
+//! let lookups = gpos.feature_lookups(Tag::from_bytes(b"latn"), None, Tag::from_bytes(b"kern"));
+//! let positioner = Positioner::from_face(&face).unwrap();
+//! positioner.apply(lookups.into_iter().flatten(), &mut buffer);
+//! ```
+//!
+//! Or simply:
+//!
+//! ```no_run
+//! # use ttf_parser::{shaping, Face, Tag};
+//! # let data = std::fs::read("font.ttf").unwrap();
+//! # let face = Face::parse(&data, 0).unwrap();
+//! let buffer = shaping::shape(&face, "office", Tag::from_bytes(b"latn"), None, &[]);
+//! ```
+
+mod context;
+mod gpos;
+mod gsub;
+mod shape;
+
+use std::vec::Vec;
+
+use crate::gdef::{self, GlyphClass};
+use crate::opentype_layout::{Lookup, LookupFlags};
+use crate::{Face, GlyphId};
+
+pub use gpos::Positioner;
+pub use gsub::Substitutor;
+pub use shape::{shape, Feature};
+
+/// A maximum depth of nested contextual lookups.
+const MAX_NESTING_LEVEL: u8 = 64;
+
+/// A glyph stored in a [`Buffer`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GlyphInfo {
+    /// Glyph ID.
+    pub glyph_id: GlyphId,
+    /// An index of the first character this glyph was produced from.
+    ///
+    /// Glyphs merged into a ligature share the smallest cluster.
+    pub cluster: u32,
+    ligature_id: u8,
+    ligature_component: u8,
+}
+
+impl GlyphInfo {
+    /// Returns an ID of a ligature this glyph belongs to.
+    ///
+    /// Ligature glyphs and marks that were placed between its components
+    /// share the same non-zero ID.
+    #[inline]
+    pub fn ligature_id(&self) -> u8 {
+        self.ligature_id
+    }
+
+    /// Returns a 1-based index of a ligature component this glyph is attached to.
+    ///
+    /// Zero for ligature glyphs themselves and for glyphs outside of a ligature.
+    /// For glyphs produced by a multiple substitution, a 1-based index in the produced sequence.
+    #[inline]
+    pub fn ligature_component(&self) -> u8 {
+        self.ligature_component
+    }
+}
+
+/// A glyph position stored in a [`Buffer`].
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct GlyphPosition {
+    /// Horizontal advance.
+    pub x_advance: f32,
+    /// Vertical advance.
+    pub y_advance: f32,
+    /// Horizontal offset from the current pen position.
+    pub x_offset: f32,
+    /// Vertical offset from the current pen position.
+    pub y_offset: f32,
+}
+
+/// A glyph buffer.
+#[derive(Clone, Default, Debug)]
+pub struct Buffer {
+    glyphs: Vec<GlyphInfo>,
+    positions: Vec<GlyphPosition>,
+    next_ligature_id: u8,
+}
+
+impl Buffer {
+    /// Creates a new, empty buffer.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a glyph.
+    #[inline]
+    pub fn push(&mut self, glyph_id: GlyphId, cluster: u32) {
+        self.glyphs.push(GlyphInfo {
+            glyph_id,
+            cluster,
+            ligature_id: 0,
+            ligature_component: 0,
+        });
+    }
+
+    /// Returns buffer glyphs.
+    #[inline]
+    pub fn glyphs(&self) -> &[GlyphInfo] {
+        &self.glyphs
+    }
+
+    /// Returns glyph positions.
+    ///
+    /// Empty until [`Buffer::reset_positions`] or [`Positioner::apply`] is called.
+    #[inline]
+    pub fn positions(&self) -> &[GlyphPosition] {
+        &self.positions
+    }
+
+    /// Resets glyph positions to horizontal advances from `face`, without offsets.
+    ///
+    /// Should be called after substitution and before positioning.
+    pub fn reset_positions(&mut self, face: &Face) {
+        self.positions.clear();
+        self.positions
+            .extend(self.glyphs.iter().map(|info| GlyphPosition {
+                x_advance: f32::from(face.glyph_hor_advance(info.glyph_id).unwrap_or(0)),
+                ..GlyphPosition::default()
+            }));
+    }
+
+    /// Returns the number of glyphs.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Checks that buffer is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Removes all glyphs.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.positions.clear();
+        self.next_ligature_id = 0;
+    }
+
+    fn allocate_ligature_id(&mut self) -> u8 {
+        // Zero is reserved for glyphs outside of a ligature.
+        self.next_ligature_id = self.next_ligature_id.checked_add(1).unwrap_or(1);
+        self.next_ligature_id
+    }
+}
+
+/// Glyph matching according to lookup flags.
+#[derive(Clone, Copy)]
+pub(crate) struct Matcher<'a> {
+    gdef: Option<gdef::Table<'a>>,
+    flags: LookupFlags,
+    mark_filtering_set: Option<u16>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(gdef: Option<gdef::Table<'a>>, lookup: &Lookup) -> Self {
+        Matcher {
+            gdef,
+            flags: lookup.flags,
+            mark_filtering_set: lookup.mark_filtering_set,
+        }
+    }
+
+    fn glyph_class(&self, glyph_id: GlyphId) -> Option<GlyphClass> {
+        self.gdef?.glyph_class(glyph_id)
+    }
+
+    /// Checks that glyph must be ignored by the lookup.
+    fn skip(&self, glyph_id: GlyphId) -> bool {
+        let gdef = match self.gdef {
+            Some(v) => v,
+            None => return false,
+        };
+
+        match gdef.glyph_class(glyph_id) {
+            Some(GlyphClass::Base) => self.flags.ignore_base_glyphs(),
+            Some(GlyphClass::Ligature) => self.flags.ignore_ligatures(),
+            Some(GlyphClass::Mark) => {
+                if self.flags.ignore_marks() {
+                    return true;
+                }
+
+                if let Some(set) = self.mark_filtering_set {
+                    return !gdef.is_mark_glyph(glyph_id, Some(set));
+                }
+
+                let kind = self.flags.mark_attachment_type();
+                kind != 0 && gdef.glyph_mark_attachment_class(glyph_id) != u16::from(kind)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns an index of the next not ignored glyph after `index`.
+    fn next(&self, glyphs: &[GlyphInfo], index: usize) -> Option<usize> {
+        (index + 1..glyphs.len()).find(|i| !self.skip(glyphs[*i].glyph_id))
+    }
+
+    /// Returns an index of the previous not ignored glyph before `index`.
+    fn prev(&self, glyphs: &[GlyphInfo], index: usize) -> Option<usize> {
+        (0..index).rev().find(|i| !self.skip(glyphs[*i].glyph_id))
+    }
+
+    /// Matches `count` glyphs after `index`.
+    ///
+    /// Returns indices of all matched glyphs, starting with `index`.
+    fn match_input(
+        &self,
+        glyphs: &[GlyphInfo],
+        index: usize,
+        count: u16,
+        mut f: impl FnMut(u16, GlyphId) -> bool,
+    ) -> Option<Vec<usize>> {
+        let mut positions = Vec::with_capacity(usize::from(count) + 1);
+        positions.push(index);
+
+        let mut i = index;
+        for k in 0..count {
+            i = self.next(glyphs, i)?;
+            if !f(k, glyphs[i].glyph_id) {
+                return None;
+            }
+
+            positions.push(i);
+        }
+
+        Some(positions)
+    }
+
+    /// Matches `count` glyphs before `index`, in reverse order.
+    fn match_backtrack(
+        &self,
+        glyphs: &[GlyphInfo],
+        index: usize,
+        count: u16,
+        mut f: impl FnMut(u16, GlyphId) -> bool,
+    ) -> bool {
+        let mut i = index;
+        for k in 0..count {
+            match self.prev(glyphs, i) {
+                Some(v) if f(k, glyphs[v].glyph_id) => i = v,
+                _ => return false,
+            }
+        }
+
+        true
+    }
+
+    /// Matches `count` glyphs after `index`.
+    fn match_lookahead(
+        &self,
+        glyphs: &[GlyphInfo],
+        index: usize,
+        count: u16,
+        mut f: impl FnMut(u16, GlyphId) -> bool,
+    ) -> bool {
+        let mut i = index;
+        for k in 0..count {
+            match self.next(glyphs, i) {
+                Some(v) if f(k, glyphs[v].glyph_id) => i = v,
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
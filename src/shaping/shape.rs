@@ -0,0 +1,187 @@
+//! Basic text shaping for scripts without complex shaping requirements.
+
+use core::convert::TryFrom;
+use std::vec::Vec;
+
+use super::{Buffer, Positioner, Substitutor};
+use crate::gdef::GlyphClass;
+use crate::opentype_layout::{LayoutTable, LookupIndex};
+use crate::parser::LazyArray16;
+use crate::{Face, GlyphId, Tag};
+
+/// `GSUB` features applied by [`shape`] unless disabled.
+const DEFAULT_GSUB_FEATURES: &[&[u8; 4]] = &[b"ccmp", b"locl", b"liga", b"clig", b"calt"];
+
+/// `GPOS` features applied by [`shape`] unless disabled.
+const DEFAULT_GPOS_FEATURES: &[&[u8; 4]] = &[b"kern", b"mark", b"mkmk"];
+
+/// A feature setting used by [`shape`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Feature {
+    /// Feature tag.
+    pub tag: Tag,
+    /// Feature value.
+    ///
+    /// Zero disables the feature.
+    /// For *Alternate Substitution* lookups, a 1-based index of the alternate glyph.
+    pub value: u32,
+}
+
+impl Feature {
+    /// Creates a new feature setting.
+    #[inline]
+    pub fn new(tag: Tag, value: u32) -> Self {
+        Feature { tag, value }
+    }
+}
+
+/// Shapes text using the `cmap`, `GSUB` and `GPOS` tables.
+///
+/// A simple shaper for scripts without complex shaping requirements,
+/// like Latin, Cyrillic and Greek. Assumes left-to-right horizontal layout.
+///
+/// Characters are mapped via [`Face::glyph_index`] and clusters are set to character indices.
+/// Then, the `ccmp`, `locl`, `liga`, `clig` and `calt` `GSUB` features
+/// and the `kern`, `mark` and `mkmk` `GPOS` features are applied
+/// for the specified script and language system.
+/// `features` can disable default features or enable additional ones.
+/// Lookups of all enabled features are applied in the lookup list order.
+///
+/// Advances of mark glyphs, according to `GDEF`, are set to zero.
+/// When `GPOS` has no `kern` feature, kerning from [`Face::glyphs_kerning`] is used instead.
+///
+/// Positions are in font units. Respects face's variation coordinates.
+pub fn shape(
+    face: &Face,
+    text: &str,
+    script: Tag,
+    language: Option<Tag>,
+    features: &[Feature],
+) -> Buffer {
+    let mut buffer = Buffer::new();
+    for (i, c) in text.chars().enumerate() {
+        buffer.push(face.glyph_index(c).unwrap_or(GlyphId(0)), i as u32);
+    }
+
+    if let Some(gsub) = face.tables().gsub {
+        let lookups = collect_lookups(
+            face,
+            gsub,
+            script,
+            language,
+            DEFAULT_GSUB_FEATURES,
+            features,
+        );
+        let substitutor = Substitutor::new(gsub, face.tables().gdef);
+        for (index, value) in lookups {
+            let mut substitutor = substitutor;
+            substitutor.set_alternate_index(u16::try_from(value - 1).unwrap_or(u16::MAX));
+            substitutor.apply_lookup(index, &mut buffer);
+        }
+    }
+
+    buffer.reset_positions(face);
+
+    if let Some(gdef) = face.tables().gdef {
+        for (info, pos) in buffer.glyphs.iter().zip(buffer.positions.iter_mut()) {
+            if gdef.glyph_class(info.glyph_id) == Some(GlyphClass::Mark) {
+                pos.x_advance = 0.0;
+            }
+        }
+    }
+
+    let kern = Tag::from_bytes(b"kern");
+    let gpos = face.tables().gpos;
+    let has_gpos_kern = gpos.map_or(false, |gpos| {
+        feature_lookups(face, gpos, script, language, kern).is_some()
+    });
+    if !has_gpos_kern && feature_value(kern, DEFAULT_GPOS_FEATURES, features).is_some() {
+        for i in 1..buffer.len() {
+            let (left, right) = (buffer.glyphs[i - 1].glyph_id, buffer.glyphs[i].glyph_id);
+            if let Some(kerning) = face.glyphs_kerning(left, right) {
+                buffer.positions[i - 1].x_advance += kerning;
+            }
+        }
+    }
+
+    if let (Some(gpos), Some(positioner)) = (gpos, Positioner::from_face(face)) {
+        let lookups = collect_lookups(
+            face,
+            gpos,
+            script,
+            language,
+            DEFAULT_GPOS_FEATURES,
+            features,
+        );
+        positioner.apply(lookups.into_iter().map(|(index, _)| index), &mut buffer);
+    }
+
+    buffer
+}
+
+/// Returns a value of a feature enabled either by default or by `features`.
+fn feature_value(tag: Tag, defaults: &[&[u8; 4]], features: &[Feature]) -> Option<u32> {
+    // The last setting wins.
+    let value = match features.iter().rev().find(|feature| feature.tag == tag) {
+        Some(feature) => feature.value,
+        None if defaults.iter().any(|v| Tag::from_bytes(v) == tag) => 1,
+        None => 0,
+    };
+
+    if value != 0 {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Returns lookups of all enabled features with feature values, sorted by lookup index.
+fn collect_lookups(
+    face: &Face,
+    table: LayoutTable,
+    script: Tag,
+    language: Option<Tag>,
+    defaults: &[&[u8; 4]],
+    features: &[Feature],
+) -> Vec<(LookupIndex, u32)> {
+    let tags = defaults
+        .iter()
+        .map(|tag| Tag::from_bytes(tag))
+        .chain(features.iter().map(|feature| feature.tag));
+
+    let mut lookups = Vec::new();
+    for tag in tags {
+        let value = match feature_value(tag, defaults, features) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        if let Some(indices) = feature_lookups(face, table, script, language, tag) {
+            lookups.extend(indices.into_iter().map(|index| (index, value)));
+        }
+    }
+
+    // A lookup shared by multiple features is still applied just once.
+    lookups.sort_by_key(|(index, _)| *index);
+    lookups.dedup_by_key(|(index, _)| *index);
+    lookups
+}
+
+fn feature_lookups<'a>(
+    face: &Face,
+    table: LayoutTable<'a>,
+    script: Tag,
+    language: Option<Tag>,
+    feature: Tag,
+) -> Option<LazyArray16<'a, LookupIndex>> {
+    #[cfg(feature = "variable-fonts")]
+    {
+        table.feature_lookups_at(script, language, feature, face.variation_coordinates())
+    }
+
+    #[cfg(not(feature = "variable-fonts"))]
+    {
+        let _ = face;
+        table.feature_lookups(script, language, feature)
+    }
+}
@@ -0,0 +1,720 @@
+//! `CFF ` table subsetting.
+//!
+//! Subroutines used by the remaining glyphs are kept as is, while unused ones are replaced
+//! with a single `return` operator. This way subroutine indices and biases are preserved
+//! and charstrings can be copied without changes.
+
+use core::convert::TryFrom;
+use core::num::NonZeroU16;
+use std::collections::BTreeSet;
+use std::vec::Vec;
+
+use super::{GlyphMap, SubsetError};
+use crate::parser::{NumFrom, Stream};
+use crate::tables::cff::cff1::{parse_fd_select, seac_code_to_glyph_id, FDSelect};
+use crate::tables::cff::charset::{parse_charset, Charset};
+use crate::tables::cff::dict::{is_dict_one_byte_op, parse_number, skip_number};
+use crate::tables::cff::index::{parse_index, skip_index, Index};
+use crate::tables::cff::{calc_subroutine_bias, conv_subroutine_index};
+use crate::GlyphId;
+
+// Limits according to the Adobe Technical Note #5177 Appendix B.
+const STACK_LIMIT: u8 = 10;
+const MAX_ARGUMENTS_STACK_LEN: usize = 48;
+
+const TWO_BYTE_OPERATOR_MARK: u8 = 12;
+const HEADER_SIZE: u8 = 4;
+
+/// Enumerates some operators defined in the Adobe Technical Note #5177.
+mod operator {
+    pub const HORIZONTAL_STEM: u8 = 1;
+    pub const VERTICAL_STEM: u8 = 3;
+    pub const CALL_LOCAL_SUBROUTINE: u8 = 10;
+    pub const RETURN: u8 = 11;
+    pub const ENDCHAR: u8 = 14;
+    pub const HORIZONTAL_STEM_HINT_MASK: u8 = 18;
+    pub const HINT_MASK: u8 = 19;
+    pub const COUNTER_MASK: u8 = 20;
+    pub const VERTICAL_STEM_HINT_MASK: u8 = 23;
+    pub const SHORT_INT: u8 = 28;
+    pub const CALL_GLOBAL_SUBROUTINE: u8 = 29;
+    pub const FIXED_16_16: u8 = 255;
+}
+
+/// Enumerates some operators defined in the Adobe Technical Note #5176.
+mod dict_operator {
+    pub const CHARSET_OFFSET: u16 = 15;
+    pub const ENCODING_OFFSET: u16 = 16;
+    pub const CHAR_STRINGS_OFFSET: u16 = 17;
+    pub const PRIVATE_DICT_SIZE_AND_OFFSET: u16 = 18;
+    pub const LOCAL_SUBROUTINES_OFFSET: u16 = 19;
+    pub const ROS: u16 = 1230;
+    pub const FD_ARRAY: u16 = 1236;
+    pub const FD_SELECT: u16 = 1237;
+}
+
+/// Enumerates Charset IDs defined in the Adobe Technical Note #5176, Table 22
+mod charset_id {
+    pub const ISO_ADOBE: usize = 0;
+    pub const EXPERT: usize = 1;
+    pub const EXPERT_SUBSET: usize = 2;
+}
+
+/// A DICT entry with raw operands.
+#[derive(Clone, Copy)]
+pub(crate) struct Entry<'a> {
+    pub operator: u16,
+    pub operands: &'a [u8],
+}
+
+pub(crate) fn parse_dict(data: &[u8]) -> Option<Vec<Entry<'_>>> {
+    let mut entries = Vec::new();
+    let mut s = Stream::new(data);
+    let mut start = 0;
+    while !s.at_end() {
+        let b0 = s.read::<u8>()?;
+        if is_dict_one_byte_op(b0) {
+            let operands = data.get(start..s.offset() - 1)?;
+            let operator = if b0 == TWO_BYTE_OPERATOR_MARK {
+                1200 + u16::from(s.read::<u8>()?)
+            } else {
+                u16::from(b0)
+            };
+
+            entries.push(Entry { operator, operands });
+            start = s.offset();
+        } else {
+            skip_number(b0, &mut s)?;
+        }
+    }
+
+    Some(entries)
+}
+
+/// Parses non-negative integer operands, like offsets and sizes.
+fn parse_offsets(data: &[u8]) -> Option<Vec<usize>> {
+    let mut operands = Vec::new();
+    let mut s = Stream::new(data);
+    while !s.at_end() {
+        let b0 = s.read::<u8>()?;
+        let n = parse_number(b0, &mut s)?;
+        operands.push(usize::try_from(n as i32).ok()?);
+    }
+
+    Some(operands)
+}
+
+fn find_offset(entries: &[Entry], operator: u16) -> Option<usize> {
+    let entry = entries.iter().find(|e| e.operator == operator)?;
+    match parse_offsets(entry.operands)?.as_slice() {
+        [offset] => Some(*offset),
+        _ => None,
+    }
+}
+
+#[derive(Clone)]
+struct PrivateDict<'a> {
+    entries: Vec<Entry<'a>>,
+    local_subrs: Option<Index<'a>>,
+}
+
+fn parse_private_dict<'a>(data: &'a [u8], entries: &[Entry]) -> Option<Option<PrivateDict<'a>>> {
+    let entry = match entries
+        .iter()
+        .find(|e| e.operator == dict_operator::PRIVATE_DICT_SIZE_AND_OFFSET)
+    {
+        Some(entry) => entry,
+        None => return Some(None),
+    };
+
+    let (len, start) = match parse_offsets(entry.operands)?.as_slice() {
+        [len, start] => (*len, *start),
+        _ => return None,
+    };
+
+    let entries = parse_dict(data.get(start..start.checked_add(len)?)?)?;
+    let local_subrs = match find_offset(&entries, dict_operator::LOCAL_SUBROUTINES_OFFSET) {
+        // 'The local subroutines offset is relative to the beginning
+        // of the Private DICT data.'
+        Some(offset) => {
+            let mut s = Stream::new_at(data, start.checked_add(offset)?)?;
+            Some(parse_index::<u16>(&mut s)?)
+        }
+        None => None,
+    };
+
+    Some(Some(PrivateDict {
+        entries,
+        local_subrs,
+    }))
+}
+
+struct Table<'a> {
+    name_index: &'a [u8],
+    top_dict: Vec<Entry<'a>>,
+    string_index: &'a [u8],
+    global_subrs: Index<'a>,
+    char_strings: Index<'a>,
+    charset: Charset<'a>,
+    // Font DICTs of a CID font.
+    font_dicts: Vec<Vec<Entry<'a>>>,
+    // A single one for SID fonts and one per Font DICT for CID fonts.
+    private_dicts: Vec<Option<PrivateDict<'a>>>,
+    fd_select: Option<FDSelect<'a>>,
+}
+
+impl<'a> Table<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let mut s = Stream::new(data);
+        let major = s.read::<u8>()?;
+        s.skip::<u8>(); // minor
+        let header_size = s.read::<u8>()?;
+        if major != 1 {
+            return None;
+        }
+
+        // Name INDEX is not necessarily right after the header.
+        let mut s = Stream::new_at(data, usize::from(header_size))?;
+        let start = s.offset();
+        skip_index::<u16>(&mut s)?;
+        let name_index = data.get(start..s.offset())?;
+
+        let top_dict_index = parse_index::<u16>(&mut s)?;
+        let top_dict = parse_dict(top_dict_index.get(0)?)?;
+
+        let start = s.offset();
+        skip_index::<u16>(&mut s)?;
+        let string_index = data.get(start..s.offset())?;
+
+        let global_subrs = parse_index::<u16>(&mut s)?;
+
+        let char_strings = {
+            let offset = find_offset(&top_dict, dict_operator::CHAR_STRINGS_OFFSET)?;
+            parse_index::<u16>(&mut Stream::new_at(data, offset)?)?
+        };
+
+        let number_of_glyphs = u16::try_from(char_strings.len())
+            .ok()
+            .and_then(NonZeroU16::new)?;
+
+        let charset = match find_offset(&top_dict, dict_operator::CHARSET_OFFSET) {
+            Some(charset_id::ISO_ADOBE) | None => Charset::ISOAdobe,
+            Some(charset_id::EXPERT) => Charset::Expert,
+            Some(charset_id::EXPERT_SUBSET) => Charset::ExpertSubset,
+            Some(offset) => parse_charset(number_of_glyphs, &mut Stream::new_at(data, offset)?)?,
+        };
+
+        let mut font_dicts = Vec::new();
+        let mut private_dicts = Vec::new();
+        let mut fd_select = None;
+        if top_dict.iter().any(|e| e.operator == dict_operator::ROS) {
+            let offset = find_offset(&top_dict, dict_operator::FD_ARRAY)?;
+            for font_dict in parse_index::<u16>(&mut Stream::new_at(data, offset)?)? {
+                let font_dict = parse_dict(font_dict)?;
+                private_dicts.push(parse_private_dict(data, &font_dict)?);
+                font_dicts.push(font_dict);
+            }
+
+            let offset = find_offset(&top_dict, dict_operator::FD_SELECT)?;
+            fd_select = Some(parse_fd_select(
+                number_of_glyphs.get(),
+                &mut Stream::new_at(data, offset)?,
+            )?);
+        } else {
+            private_dicts.push(parse_private_dict(data, &top_dict)?);
+        }
+
+        Some(Table {
+            name_index,
+            top_dict,
+            string_index,
+            global_subrs,
+            char_strings,
+            charset,
+            font_dicts,
+            private_dicts,
+            fd_select,
+        })
+    }
+
+    fn font_dict_index(&self, glyph_id: GlyphId) -> Option<usize> {
+        match self.fd_select {
+            Some(ref fd_select) => fd_select.font_dict_index(glyph_id).map(usize::from),
+            None => Some(0),
+        }
+    }
+
+    /// Marks subroutines used by a glyph.
+    ///
+    /// Returns `seac` base and accent codes, if any.
+    fn scan(&self, glyph_id: GlyphId, usage: &mut Usage) -> Option<Option<(f32, f32)>> {
+        let data = self.char_strings.get(u32::from(glyph_id.0))?;
+        let index = self.font_dict_index(glyph_id)?;
+        let private_dict = self.private_dicts.get(index)?;
+        let mut scanner = CharStringScanner {
+            global_subrs: self.global_subrs,
+            local_subrs: private_dict.as_ref().and_then(|dict| dict.local_subrs),
+            used_global: &mut usage.global,
+            used_local: usage.local.get_mut(index)?,
+            stack: Vec::new(),
+            stems: 0,
+            seac: None,
+        };
+
+        scanner.scan(data, 0)?;
+        Some(scanner.seac)
+    }
+}
+
+/// Subroutines usage.
+struct Usage {
+    global: Vec<bool>,
+    // Indexed by Private DICT.
+    local: Vec<Vec<bool>>,
+}
+
+impl Usage {
+    fn new(table: &Table) -> Self {
+        Usage {
+            global: vec![false; usize::num_from(table.global_subrs.len())],
+            local: table
+                .private_dicts
+                .iter()
+                .map(|dict| {
+                    let len = dict
+                        .as_ref()
+                        .and_then(|dict| dict.local_subrs)
+                        .map_or(0, |subrs| subrs.len());
+                    vec![false; usize::num_from(len)]
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A Type 2 charstring scanner.
+///
+/// Unlike the outliner, tracks only the arguments stack and the number of stems,
+/// which is enough to find called subroutines.
+struct CharStringScanner<'a, 'b> {
+    global_subrs: Index<'a>,
+    local_subrs: Option<Index<'a>>,
+    used_global: &'b mut [bool],
+    used_local: &'b mut [bool],
+    stack: Vec<f32>,
+    stems: usize,
+    seac: Option<(f32, f32)>,
+}
+
+impl CharStringScanner<'_, '_> {
+    fn push(&mut self, n: f32) -> Option<()> {
+        if self.stack.len() == MAX_ARGUMENTS_STACK_LEN {
+            return None;
+        }
+
+        self.stack.push(n);
+        Some(())
+    }
+
+    /// Returns `true` when `endchar` was reached.
+    fn scan(&mut self, data: &[u8], depth: u8) -> Option<bool> {
+        if depth == STACK_LIMIT {
+            return None;
+        }
+
+        let mut s = Stream::new(data);
+        while !s.at_end() {
+            let op = s.read::<u8>()?;
+            match op {
+                operator::SHORT_INT => {
+                    let n = s.read::<i16>()?;
+                    self.push(f32::from(n))?;
+                }
+                32..=246 => {
+                    let n = i16::from(op) - 139;
+                    self.push(f32::from(n))?;
+                }
+                247..=250 => {
+                    let b1 = i16::from(s.read::<u8>()?);
+                    let n = (i16::from(op) - 247) * 256 + b1 + 108;
+                    self.push(f32::from(n))?;
+                }
+                251..=254 => {
+                    let b1 = i16::from(s.read::<u8>()?);
+                    let n = -(i16::from(op) - 251) * 256 - b1 - 108;
+                    self.push(f32::from(n))?;
+                }
+                operator::FIXED_16_16 => {
+                    let n = s.read::<i32>()?;
+                    self.push(n as f32 / 65536.0)?;
+                }
+                operator::HORIZONTAL_STEM
+                | operator::VERTICAL_STEM
+                | operator::HORIZONTAL_STEM_HINT_MASK
+                | operator::VERTICAL_STEM_HINT_MASK => {
+                    // An odd argument is a width.
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                }
+                operator::HINT_MASK | operator::COUNTER_MASK => {
+                    // Arguments are an implicit `vstem`.
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                    s.advance_checked((self.stems + 7) / 8)?;
+                }
+                operator::CALL_LOCAL_SUBROUTINE => {
+                    let subrs = self.local_subrs?;
+                    let index = self.stack.pop()?;
+                    let bias = calc_subroutine_bias(subrs.len());
+                    let index = conv_subroutine_index(index, bias).ok()?;
+                    *self.used_local.get_mut(usize::num_from(index))? = true;
+                    if self.scan(subrs.get(index)?, depth + 1)? {
+                        return Some(true);
+                    }
+                }
+                operator::CALL_GLOBAL_SUBROUTINE => {
+                    let subrs = self.global_subrs;
+                    let index = self.stack.pop()?;
+                    let bias = calc_subroutine_bias(subrs.len());
+                    let index = conv_subroutine_index(index, bias).ok()?;
+                    *self.used_global.get_mut(usize::num_from(index))? = true;
+                    if self.scan(subrs.get(index)?, depth + 1)? {
+                        return Some(true);
+                    }
+                }
+                operator::RETURN => return Some(false),
+                operator::ENDCHAR => {
+                    // `adx ady bchar achar endchar` is an accented character.
+                    if self.stack.len() >= 4 {
+                        let len = self.stack.len();
+                        self.seac = Some((self.stack[len - 2], self.stack[len - 1]));
+                    }
+
+                    return Some(true);
+                }
+                TWO_BYTE_OPERATOR_MARK => {
+                    s.skip::<u8>();
+                    self.stack.clear();
+                }
+                _ => self.stack.clear(),
+            }
+        }
+
+        Some(false)
+    }
+}
+
+/// Adds `seac` components of accented glyphs.
+pub(crate) fn closure(data: &[u8], glyphs: &mut BTreeSet<GlyphId>) -> Result<(), SubsetError> {
+    let table = Table::parse(data).ok_or(SubsetError::MalformedFont)?;
+    // Only SID fonts can use standard encoding accents.
+    if table.fd_select.is_some() {
+        return Ok(());
+    }
+
+    let mut usage = Usage::new(&table);
+    let mut queue: Vec<GlyphId> = glyphs.iter().copied().collect();
+    while let Some(glyph_id) = queue.pop() {
+        let seac = table
+            .scan(glyph_id, &mut usage)
+            .ok_or(SubsetError::MalformedFont)?;
+        if let Some((base, accent)) = seac {
+            for code in &[base, accent] {
+                let component = seac_code_to_glyph_id(&table.charset, *code)
+                    .ok_or(SubsetError::MalformedFont)?;
+                if glyphs.insert(component) {
+                    queue.push(component);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes an INDEX.
+pub(crate) fn write_index(items: &[&[u8]]) -> Result<Vec<u8>, SubsetError> {
+    let count = u16::try_from(items.len()).map_err(|_| SubsetError::Overflow)?;
+    let mut data = Vec::new();
+    data.extend_from_slice(&count.to_be_bytes());
+    if count == 0 {
+        return Ok(data);
+    }
+
+    // Offsets start from one.
+    let last_offset = items.iter().map(|item| item.len()).sum::<usize>() + 1;
+    let offset_size: u8 = match last_offset {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x10000..=0xFFFFFF => 3,
+        _ => 4,
+    };
+    u32::try_from(last_offset).map_err(|_| SubsetError::Overflow)?;
+
+    data.push(offset_size);
+    let mut offset = 1u32;
+    for item in items
+        .iter()
+        .map(|item| item.len())
+        .chain(core::iter::once(0))
+    {
+        data.extend_from_slice(&offset.to_be_bytes()[usize::from(4 - offset_size)..]);
+        offset += item as u32;
+    }
+
+    for item in items {
+        data.extend_from_slice(item);
+    }
+
+    Ok(data)
+}
+
+/// Replaces unused subroutines with a `return` operator.
+fn prune<'a>(subrs: Option<Index<'a>>, used: &[bool]) -> Vec<&'a [u8]> {
+    match subrs {
+        Some(subrs) => subrs
+            .into_iter()
+            .zip(used)
+            .map(|(data, used)| if *used { data } else { &[operator::RETURN][..] })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Encodes an integer using the fixed-size five-byte form,
+/// so DICT sizes do not depend on offsets.
+pub(crate) fn push_int(n: usize, data: &mut Vec<u8>) -> Result<(), SubsetError> {
+    let n = i32::try_from(n).map_err(|_| SubsetError::Overflow)?;
+    data.push(29);
+    data.extend_from_slice(&n.to_be_bytes());
+    Ok(())
+}
+
+pub(crate) fn push_operator(operator: u16, data: &mut Vec<u8>) {
+    if operator >= 1200 {
+        data.push(TWO_BYTE_OPERATOR_MARK);
+        data.push((operator - 1200) as u8);
+    } else {
+        data.push(operator as u8);
+    }
+}
+
+/// Writes a DICT, replacing offset operands via `f`.
+///
+/// `f` returns `None` to keep operands as is and an empty slice to drop an entry.
+fn write_dict(
+    entries: &[Entry],
+    mut f: impl FnMut(u16) -> Option<Vec<usize>>,
+) -> Result<Vec<u8>, SubsetError> {
+    let mut data = Vec::new();
+    for entry in entries {
+        match f(entry.operator) {
+            Some(operands) => {
+                if operands.is_empty() {
+                    continue;
+                }
+
+                for n in operands {
+                    push_int(n, &mut data)?;
+                }
+            }
+            None => data.extend_from_slice(entry.operands),
+        }
+
+        push_operator(entry.operator, &mut data);
+    }
+
+    Ok(data)
+}
+
+/// Writes a Private DICT followed by its local subroutines.
+fn write_private_dict(dict: &PrivateDict, used: &[bool]) -> Result<PrivateData, SubsetError> {
+    let mut data = write_dict(&dict.entries, |operator| {
+        if operator == dict_operator::LOCAL_SUBROUTINES_OFFSET {
+            Some(Vec::new())
+        } else {
+            None
+        }
+    })?;
+
+    let mut local_subrs = Vec::new();
+    if dict.local_subrs.is_some() {
+        // An offset is relative to the Private DICT, so subroutines are right after it.
+        let offset = data.len() + 6;
+        push_int(offset, &mut data)?;
+        push_operator(dict_operator::LOCAL_SUBROUTINES_OFFSET, &mut data);
+        local_subrs = write_index(&prune(dict.local_subrs, used))?;
+    }
+
+    Ok(PrivateData {
+        dict: data,
+        local_subrs,
+    })
+}
+
+/// A written Private DICT followed by its local subroutines.
+struct PrivateData {
+    dict: Vec<u8>,
+    local_subrs: Vec<u8>,
+}
+
+impl PrivateData {
+    #[inline]
+    fn len(&self) -> usize {
+        self.dict.len() + self.local_subrs.len()
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Offsets {
+    charset: usize,
+    fd_select: usize,
+    char_strings: usize,
+    fd_array: usize,
+    // The first Private DICT.
+    private_dicts: usize,
+}
+
+/// Writes a subset `CFF ` table.
+///
+/// The Encoding is not written, since it's not used by OpenType fonts.
+pub(crate) fn subset(data: &[u8], map: &GlyphMap) -> Result<Vec<u8>, SubsetError> {
+    let table = Table::parse(data).ok_or(SubsetError::MalformedFont)?;
+    let is_cid = table.fd_select.is_some();
+
+    let mut usage = Usage::new(&table);
+    let mut char_strings = Vec::with_capacity(usize::from(map.len()));
+    let mut charset = vec![0]; // format
+    let mut fd_ranges: Vec<(u16, u8)> = Vec::new();
+    for (new_id, glyph_id) in map.old_glyphs().enumerate() {
+        table
+            .scan(glyph_id, &mut usage)
+            .ok_or(SubsetError::MalformedFont)?;
+        char_strings.push(
+            table
+                .char_strings
+                .get(u32::from(glyph_id.0))
+                .ok_or(SubsetError::MalformedFont)?,
+        );
+
+        // `.notdef` is omitted.
+        if new_id != 0 {
+            let sid = table
+                .charset
+                .gid_to_sid(glyph_id)
+                .ok_or(SubsetError::MalformedFont)?;
+            charset.extend_from_slice(&sid.0.to_be_bytes());
+        }
+
+        if is_cid {
+            let index = table
+                .font_dict_index(glyph_id)
+                .ok_or(SubsetError::MalformedFont)? as u8;
+            if fd_ranges.last().map_or(true, |range| range.1 != index) {
+                fd_ranges.push((new_id as u16, index));
+            }
+        }
+    }
+
+    let char_strings = write_index(&char_strings)?;
+    let global_subrs = write_index(&prune(Some(table.global_subrs), &usage.global))?;
+
+    let mut fd_select = Vec::new();
+    if is_cid {
+        fd_select.push(3); // format
+        fd_select.extend_from_slice(&(fd_ranges.len() as u16).to_be_bytes());
+        for (first, index) in &fd_ranges {
+            fd_select.extend_from_slice(&first.to_be_bytes());
+            fd_select.push(*index);
+        }
+        // Sentinel.
+        fd_select.extend_from_slice(&map.len().to_be_bytes());
+    }
+
+    let mut private_dicts = Vec::with_capacity(table.private_dicts.len());
+    for (dict, used) in table.private_dicts.iter().zip(&usage.local) {
+        match dict {
+            Some(dict) => private_dicts.push(Some(write_private_dict(dict, used)?)),
+            None => private_dicts.push(None),
+        }
+    }
+
+    // DICT sizes do not depend on offsets, so we can write them twice.
+    let write_top_dict = |offsets: Offsets| {
+        write_dict(&table.top_dict, |operator| match operator {
+            dict_operator::CHARSET_OFFSET => Some(vec![offsets.charset]),
+            dict_operator::ENCODING_OFFSET => Some(Vec::new()),
+            dict_operator::CHAR_STRINGS_OFFSET => Some(vec![offsets.char_strings]),
+            dict_operator::PRIVATE_DICT_SIZE_AND_OFFSET => match private_dicts.get(0) {
+                Some(Some(dict)) if !is_cid => Some(vec![dict.dict.len(), offsets.private_dicts]),
+                _ => Some(Vec::new()),
+            },
+            dict_operator::FD_ARRAY => Some(vec![offsets.fd_array]),
+            dict_operator::FD_SELECT => Some(vec![offsets.fd_select]),
+            _ => None,
+        })
+    };
+
+    let write_fd_array = |offsets: Offsets| -> Result<Vec<u8>, SubsetError> {
+        let mut font_dicts = Vec::with_capacity(table.font_dicts.len());
+        let mut offset = offsets.private_dicts;
+        for (font_dict, private_dict) in table.font_dicts.iter().zip(&private_dicts) {
+            font_dicts.push(write_dict(font_dict, |operator| {
+                if operator != dict_operator::PRIVATE_DICT_SIZE_AND_OFFSET {
+                    return None;
+                }
+
+                match private_dict {
+                    Some(dict) => Some(vec![dict.dict.len(), offset]),
+                    None => Some(Vec::new()),
+                }
+            })?);
+
+            offset += private_dict.as_ref().map_or(0, |dict| dict.len());
+        }
+
+        let font_dicts: Vec<&[u8]> = font_dicts.iter().map(|dict| dict.as_slice()).collect();
+        write_index(&font_dicts)
+    };
+
+    let top_dict_index_len = write_index(&[&write_top_dict(Offsets::default())?])?.len();
+    let fd_array_len = if is_cid {
+        write_fd_array(Offsets::default())?.len()
+    } else {
+        0
+    };
+
+    let mut offsets = Offsets::default();
+    let mut offset = usize::from(HEADER_SIZE)
+        + table.name_index.len()
+        + top_dict_index_len
+        + table.string_index.len()
+        + global_subrs.len();
+    offsets.charset = offset;
+    offset += charset.len();
+    offsets.fd_select = offset;
+    offset += fd_select.len();
+    offsets.char_strings = offset;
+    offset += char_strings.len();
+    offsets.fd_array = offset;
+    offset += fd_array_len;
+    offsets.private_dicts = offset;
+
+    let mut data = vec![1, 0, HEADER_SIZE, 4]; // version, header size, offset size
+    data.extend_from_slice(table.name_index);
+    data.extend_from_slice(&write_index(&[&write_top_dict(offsets)?])?);
+    data.extend_from_slice(table.string_index);
+    data.extend_from_slice(&global_subrs);
+    data.extend_from_slice(&charset);
+    data.extend_from_slice(&fd_select);
+    data.extend_from_slice(&char_strings);
+    if is_cid {
+        data.extend_from_slice(&write_fd_array(offsets)?);
+    }
+    for dict in private_dicts.iter().flatten() {
+        data.extend_from_slice(&dict.dict);
+        data.extend_from_slice(&dict.local_subrs);
+    }
+
+    Ok(data)
+}
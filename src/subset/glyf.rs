@@ -0,0 +1,168 @@
+//! `glyf` and `loca` tables subsetting.
+
+use std::collections::BTreeSet;
+use std::vec::Vec;
+
+use super::{GlyphMap, SubsetError};
+use crate::parser::Stream;
+use crate::{loca, Face, GlyphId, IndexToLocationFormat, Tag};
+
+// https://docs.microsoft.com/en-us/typography/opentype/spec/glyf#composite-glyph-description
+pub(crate) const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+pub(crate) const WE_HAVE_A_SCALE: u16 = 0x0008;
+pub(crate) const MORE_COMPONENTS: u16 = 0x0020;
+pub(crate) const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+pub(crate) const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+pub(crate) struct GlyfTable<'a> {
+    loca: loca::Table<'a>,
+    data: &'a [u8],
+}
+
+impl<'a> GlyfTable<'a> {
+    pub fn parse(face: &Face<'a>) -> Result<Self, SubsetError> {
+        let raw_face = face.raw_face();
+        let data = raw_face
+            .table(Tag::from_bytes(b"glyf"))
+            .ok_or(SubsetError::MalformedFont)?;
+        let loca = raw_face
+            .table(Tag::from_bytes(b"loca"))
+            .and_then(|data| {
+                loca::Table::parse(
+                    face.tables().maxp.number_of_glyphs,
+                    face.tables().head.index_to_location_format,
+                    data,
+                )
+            })
+            .ok_or(SubsetError::MalformedFont)?;
+        Ok(GlyfTable { loca, data })
+    }
+
+    pub fn glyph(&self, glyph_id: GlyphId) -> &'a [u8] {
+        self.loca
+            .glyph_range(glyph_id)
+            .and_then(|range| self.data.get(range))
+            .unwrap_or_default()
+    }
+}
+
+/// Returns offsets of component glyph IDs in a composite glyph.
+pub(crate) fn components(data: &[u8]) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut s = Stream::new(data);
+    // A simple glyph or an empty one.
+    if s.read::<i16>().map_or(true, |n| n >= 0) {
+        return offsets;
+    }
+
+    s.advance(8); // bbox
+    while let Some(flags) = s.read::<u16>() {
+        offsets.push(s.offset());
+        let mut len = 2; // glyphIndex
+        len += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+            4
+        } else {
+            2
+        };
+        if flags & WE_HAVE_A_SCALE != 0 {
+            len += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            len += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            len += 8;
+        }
+
+        if s.advance_checked(len).is_none() {
+            // A truncated record.
+            offsets.pop();
+            break;
+        }
+
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+
+    offsets
+}
+
+/// Adds components of composite glyphs.
+pub(crate) fn closure(face: &Face, glyphs: &mut BTreeSet<GlyphId>) -> Result<(), SubsetError> {
+    let table = GlyfTable::parse(face)?;
+    let mut queue: Vec<GlyphId> = glyphs.iter().copied().collect();
+    while let Some(glyph_id) = queue.pop() {
+        let data = table.glyph(glyph_id);
+        for offset in components(data) {
+            if let Some(component) = Stream::read_at::<GlyphId>(data, offset) {
+                if component.0 < face.number_of_glyphs() && glyphs.insert(component) {
+                    queue.push(component);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes `glyf` and `loca` tables.
+///
+/// Component glyph IDs are remapped, everything else is copied as is.
+pub(crate) fn subset(
+    face: &Face,
+    map: &GlyphMap,
+) -> Result<(Vec<u8>, Vec<u8>, IndexToLocationFormat), SubsetError> {
+    let table = GlyfTable::parse(face)?;
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(usize::from(map.len()) + 1);
+    offsets.push(0);
+    for glyph_id in map.old_glyphs() {
+        let data = table.glyph(glyph_id);
+        let start = glyf.len();
+        glyf.extend_from_slice(data);
+        for offset in components(data) {
+            let component = Stream::read_at::<GlyphId>(data, offset)
+                .and_then(|id| map.get(id))
+                .ok_or(SubsetError::MalformedFont)?;
+            glyf[start + offset..start + offset + 2].copy_from_slice(&component.0.to_be_bytes());
+        }
+
+        // Short offsets are stored divided by two.
+        if glyf.len() % 2 != 0 {
+            glyf.push(0);
+        }
+
+        offsets.push(glyf.len());
+    }
+
+    let (loca, format) = loca(&glyf, offsets)?;
+    Ok((glyf, loca, format))
+}
+
+/// Writes a `loca` table for glyph offsets in `glyf`.
+///
+/// Glyphs must be padded to 2 bytes.
+pub(crate) fn loca(
+    glyf: &[u8],
+    offsets: Vec<usize>,
+) -> Result<(Vec<u8>, IndexToLocationFormat), SubsetError> {
+    if glyf.len() > u32::MAX as usize {
+        return Err(SubsetError::Overflow);
+    }
+
+    let mut loca = Vec::new();
+    let format = if glyf.len() / 2 <= usize::from(u16::MAX) {
+        for offset in offsets {
+            loca.extend_from_slice(&((offset / 2) as u16).to_be_bytes());
+        }
+
+        IndexToLocationFormat::Short
+    } else {
+        for offset in offsets {
+            loca.extend_from_slice(&(offset as u32).to_be_bytes());
+        }
+
+        IndexToLocationFormat::Long
+    };
+
+    Ok((loca, format))
+}
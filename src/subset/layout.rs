@@ -0,0 +1,1792 @@
+//! `GDEF`, `GPOS` and `GSUB` tables subsetting.
+//!
+//! Tables are written from scratch. Scripts, features and lookups are kept as is,
+//! so feature and lookup indices do not change. Coverage and class definition tables
+//! are remapped, while rules and records that reference removed glyphs are dropped.
+//! Lookup subtables are always written as extension ones, which prevents offsets overflow.
+//!
+//! When instancing, `FeatureVariations` and `VariationIndex` device tables are resolved
+//! at the instance location instead.
+
+use core::convert::TryFrom;
+use std::collections::BTreeSet;
+use std::vec::Vec;
+
+use super::serializer::{Graph, Object, ObjectId};
+use super::{GlyphMap, SubsetError};
+use crate::gsub::SubstitutionSubtable;
+use crate::opentype_layout::{ClassDefinition, Coverage};
+use crate::parser::{FromSlice, LazyArray16, Stream};
+#[cfg(feature = "instance")]
+use crate::var_store::ItemVariationStore;
+#[cfg(feature = "instance")]
+use crate::NormalizedCoordinate;
+use crate::{Face, GlyphId, Tag};
+
+const GDEF: Tag = Tag::from_bytes(b"GDEF");
+const GSUB: Tag = Tag::from_bytes(b"GSUB");
+const USE_MARK_FILTERING_SET: u16 = 0x0010;
+const SEQUENCE_LOOKUP_RECORD_SIZE: usize = 4;
+#[cfg(feature = "instance")]
+const VARIATION_INDEX_FORMAT: u16 = 0x8000;
+
+/// Adds glyphs reachable via `GSUB` lookups.
+///
+/// Lookups are applied to all glyphs regardless of features and contexts,
+/// which may add a few unreachable glyphs, but never misses a reachable one.
+pub(crate) fn closure(face: &Face, glyphs: &mut BTreeSet<GlyphId>) {
+    let gsub = match face.tables().gsub {
+        Some(table) => table,
+        None => return,
+    };
+
+    let mut added = Vec::new();
+    loop {
+        for lookup in gsub.lookups {
+            for subtable in lookup.subtables.into_iter::<SubstitutionSubtable>() {
+                for glyph in glyphs.iter() {
+                    substitutes(&subtable, *glyph, glyphs, &mut added);
+                }
+            }
+        }
+
+        let len = glyphs.len();
+        glyphs.extend(
+            added
+                .drain(..)
+                .filter(|glyph| glyph.0 < face.number_of_glyphs()),
+        );
+        if glyphs.len() == len {
+            break;
+        }
+    }
+}
+
+/// Collects all glyphs that `glyph` can be substituted with.
+fn substitutes(
+    subtable: &SubstitutionSubtable,
+    glyph: GlyphId,
+    glyphs: &BTreeSet<GlyphId>,
+    added: &mut Vec<GlyphId>,
+) {
+    let index = match subtable.coverage().get(glyph) {
+        Some(index) => index,
+        None => return,
+    };
+
+    use crate::gsub::SingleSubstitution;
+    match subtable {
+        SubstitutionSubtable::Single(SingleSubstitution::Format1 { delta, .. }) => {
+            added.push(GlyphId((i32::from(glyph.0) + i32::from(*delta)) as u16));
+        }
+        SubstitutionSubtable::Single(SingleSubstitution::Format2 { substitutes, .. }) => {
+            added.extend(substitutes.get(index));
+        }
+        SubstitutionSubtable::Multiple(subtable) => {
+            if let Some(sequence) = subtable.sequences.get(index) {
+                added.extend(sequence.substitutes);
+            }
+        }
+        SubstitutionSubtable::Alternate(subtable) => {
+            if let Some(set) = subtable.alternate_sets.get(index) {
+                added.extend(set.alternates);
+            }
+        }
+        SubstitutionSubtable::Ligature(subtable) => {
+            if let Some(set) = subtable.ligature_sets.get(index) {
+                for ligature in set {
+                    if ligature.components.into_iter().all(|g| glyphs.contains(&g)) {
+                        added.push(ligature.glyph);
+                    }
+                }
+            }
+        }
+        SubstitutionSubtable::ReverseChainSingle(subtable) => {
+            added.extend(subtable.substitutes.get(index));
+        }
+        // Nested lookups are applied on their own.
+        SubstitutionSubtable::Context(_) | SubstitutionSubtable::ChainContext(_) => {}
+    }
+}
+
+/// Writes a subset `GDEF`, `GPOS` or `GSUB` table.
+///
+/// Name IDs referenced by feature parameters are added to `name_ids`.
+/// Returns `None` when the table is malformed.
+pub(crate) fn subset(
+    tag: Tag,
+    data: &[u8],
+    map: &GlyphMap,
+    name_ids: &mut BTreeSet<u16>,
+) -> Result<Option<Vec<u8>>, SubsetError> {
+    write(tag, data, Writer::new(map, name_ids))
+}
+
+/// A location to resolve variable values at.
+#[cfg(feature = "instance")]
+pub(crate) struct Variations<'a> {
+    pub coordinates: &'a [NormalizedCoordinate],
+    /// The `GDEF` item variation store.
+    pub store: Option<ItemVariationStore<'a>>,
+}
+
+/// Writes a `GDEF`, `GPOS` or `GSUB` table with variations resolved at a location.
+///
+/// Returns `None` when the table is malformed.
+#[cfg(feature = "instance")]
+pub(crate) fn instance(
+    tag: Tag,
+    data: &[u8],
+    map: &GlyphMap,
+    variations: &Variations,
+) -> Result<Option<Vec<u8>>, SubsetError> {
+    let mut name_ids = BTreeSet::new();
+    let mut writer = Writer::new(map, &mut name_ids);
+    writer.variations = Some(variations);
+    write(tag, data, writer)
+}
+
+fn write(tag: Tag, data: &[u8], mut writer: Writer) -> Result<Option<Vec<u8>>, SubsetError> {
+    let root = if tag == GDEF {
+        writer.gdef(data)
+    } else if tag == GSUB {
+        writer.layout_table(data, Kind::Substitution)
+    } else {
+        writer.layout_table(data, Kind::Positioning)
+    };
+
+    match root {
+        Some(root) => writer
+            .graph
+            .serialize(root)
+            .map(Some)
+            .ok_or(SubsetError::Overflow),
+        None => Ok(None),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Substitution,
+    Positioning,
+}
+
+impl Kind {
+    fn extension(self) -> u16 {
+        match self {
+            Kind::Substitution => 7,
+            Kind::Positioning => 9,
+        }
+    }
+}
+
+/// Returns data at offset. A zero offset is a null one.
+#[inline]
+fn at(data: &[u8], offset: u16) -> Option<&[u8]> {
+    if offset == 0 {
+        None
+    } else {
+        data.get(usize::from(offset)..)
+    }
+}
+
+#[inline]
+fn at32(data: &[u8], offset: u32) -> Option<&[u8]> {
+    if offset == 0 {
+        None
+    } else {
+        data.get(usize::try_from(offset).ok()?..)
+    }
+}
+
+/// Adds a rounded variation delta to a value.
+#[inline]
+fn apply_delta(value: i16, delta: f32) -> i16 {
+    (f32::from(value) + delta).round() as i16
+}
+
+/// Returns the size of a value record in bytes.
+#[inline]
+fn value_record_size(format: u16) -> usize {
+    (format & 0xFF).count_ones() as usize * 2
+}
+
+/// A covered glyph.
+#[derive(Clone, Copy)]
+struct Covered {
+    old: GlyphId,
+    new: GlyphId,
+    index: u16,
+}
+
+struct Writer<'a> {
+    graph: Graph,
+    map: &'a GlyphMap,
+    name_ids: &'a mut BTreeSet<u16>,
+    #[cfg(feature = "instance")]
+    variations: Option<&'a Variations<'a>>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(map: &'a GlyphMap, name_ids: &'a mut BTreeSet<u16>) -> Self {
+        Writer {
+            graph: Graph::new(),
+            map,
+            name_ids,
+            #[cfg(feature = "instance")]
+            variations: None,
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, object: Object) -> ObjectId {
+        self.graph.push(object)
+    }
+
+    #[inline]
+    fn is_instancing(&self) -> bool {
+        #[cfg(feature = "instance")]
+        {
+            self.variations.is_some()
+        }
+        #[cfg(not(feature = "instance"))]
+        {
+            false
+        }
+    }
+
+    /// Returns a delta stored by a `VariationIndex` table when instancing.
+    ///
+    /// Returns `None` for other device tables.
+    fn variation_delta(&self, device: &[u8]) -> Option<f32> {
+        #[cfg(feature = "instance")]
+        {
+            let variations = self.variations?;
+            let mut s = Stream::new(device);
+            let outer_index = s.read::<u16>()?;
+            let inner_index = s.read::<u16>()?;
+            if s.read::<u16>()? != VARIATION_INDEX_FORMAT {
+                return None;
+            }
+
+            let delta = variations.store.as_ref().and_then(|store| {
+                store.parse_delta(outer_index, inner_index, variations.coordinates)
+            });
+            Some(delta.unwrap_or(0.0))
+        }
+        #[cfg(not(feature = "instance"))]
+        {
+            let _ = device;
+            None
+        }
+    }
+
+    /// Returns a `FeatureTableSubstitution` table that matches the instance location.
+    fn matching_substitution<'d>(&self, data: &'d [u8]) -> Option<&'d [u8]> {
+        #[cfg(feature = "instance")]
+        {
+            let coordinates = self.variations?.coordinates;
+            let mut s = Stream::new(data);
+            s.skip::<u32>(); // version
+            let count = s.read::<u32>()?;
+            for _ in 0..count {
+                let conditions = at32(data, s.read::<u32>()?);
+                let substitution = at32(data, s.read::<u32>()?);
+                // A null condition set matches all locations.
+                let matches = conditions.map_or(true, |data| {
+                    matches_condition_set(data, coordinates).unwrap_or(false)
+                });
+                if matches {
+                    return substitution;
+                }
+            }
+
+            None
+        }
+        #[cfg(not(feature = "instance"))]
+        {
+            let _ = data;
+            None
+        }
+    }
+
+    /// Resolves a variable value or copies its device table.
+    fn resolve(&mut self, value: i16, device: Option<&[u8]>) -> (i16, Option<ObjectId>) {
+        match device {
+            Some(device) => match self.variation_delta(device) {
+                Some(delta) => (apply_delta(value, delta), None),
+                None => (value, self.device(device)),
+            },
+            None => (value, None),
+        }
+    }
+
+    fn layout_table(&mut self, data: &[u8], kind: Kind) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let major_version = s.read::<u16>()?;
+        let minor_version = s.read::<u16>()?;
+        if major_version != 1 {
+            return None;
+        }
+
+        let scripts = at(data, s.read::<u16>()?);
+        let features = at(data, s.read::<u16>()?);
+        let lookups = at(data, s.read::<u16>()?);
+        let variations = if minor_version != 0 {
+            at32(data, s.read::<u32>()?)
+        } else {
+            None
+        };
+
+        let feature_tags = features.map(feature_tags).unwrap_or_default();
+        // Feature variations are applied when instancing.
+        let (minor_version, substitution) = if self.is_instancing() {
+            (
+                0,
+                variations.and_then(|data| self.matching_substitution(data)),
+            )
+        } else {
+            (minor_version.min(1), None)
+        };
+
+        let mut object = Object::new();
+        object.u16(major_version);
+        object.u16(minor_version);
+        let scripts = scripts.and_then(|data| self.script_list(data));
+        object.offset16(scripts);
+        let features = features.and_then(|data| self.feature_list(data, substitution));
+        object.offset16(features);
+        let lookups = lookups.and_then(|data| self.lookup_list(data, kind));
+        object.offset16(lookups);
+        if minor_version != 0 {
+            let variations =
+                variations.and_then(|data| self.feature_variations(data, &feature_tags));
+            object.offset32(variations);
+        }
+
+        Some(self.push(object))
+    }
+
+    fn script_list(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let count = s.read::<u16>()?;
+        let mut object = Object::new();
+        object.u16(count);
+        for _ in 0..count {
+            let tag = s.read::<u32>()?;
+            let script = self.script(at(data, s.read::<u16>()?)?)?;
+            object.u32(tag);
+            object.offset16(Some(script));
+        }
+
+        Some(self.push(object))
+    }
+
+    fn script(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let default = at(data, s.read::<u16>()?).and_then(|data| self.language_system(data));
+        let count = s.read::<u16>()?;
+        let mut object = Object::new();
+        object.offset16(default);
+        object.u16(count);
+        for _ in 0..count {
+            let tag = s.read::<u32>()?;
+            let language = self.language_system(at(data, s.read::<u16>()?)?)?;
+            object.u32(tag);
+            object.offset16(Some(language));
+        }
+
+        Some(self.push(object))
+    }
+
+    fn language_system(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        s.skip::<u16>(); // lookupOrderOffset
+        s.skip::<u16>(); // requiredFeatureIndex
+        let count = s.read::<u16>()?;
+        s.advance_checked(usize::from(count) * 2)?;
+
+        let mut object = Object::new();
+        object.bytes(data.get(..s.offset())?);
+        Some(self.push(object))
+    }
+
+    /// Writes a feature list. Features are replaced by alternate ones from `substitution`.
+    fn feature_list(&mut self, data: &[u8], substitution: Option<&[u8]>) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let count = s.read::<u16>()?;
+        let mut object = Object::new();
+        object.u16(count);
+        for index in 0..count {
+            let tag = s.read::<Tag>()?;
+            let feature = match substitution.and_then(|data| alternate_feature(data, index)) {
+                Some(feature) => {
+                    s.skip::<u16>(); // offset
+                    feature
+                }
+                None => at(data, s.read::<u16>()?)?,
+            };
+            let feature = self.feature(tag, feature)?;
+            object.u32(tag.0);
+            object.offset16(Some(feature));
+        }
+
+        Some(self.push(object))
+    }
+
+    fn feature(&mut self, tag: Tag, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let params = at(data, s.read::<u16>()?).and_then(|data| self.feature_params(tag, data));
+        let count = s.read::<u16>()?;
+        let mut object = Object::new();
+        object.offset16(params);
+        object.u16(count);
+        object.bytes(s.read_bytes(usize::from(count) * 2)?);
+        Some(self.push(object))
+    }
+
+    /// Copies feature parameters and collects name IDs referenced by them.
+    ///
+    /// Only the `size`, `ssXX` and `cvXX` features have parameters.
+    fn feature_params(&mut self, tag: Tag, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let bytes = tag.to_bytes();
+        let len = if &bytes == b"size" {
+            s.skip::<u16>(); // designSize
+            s.skip::<u16>(); // subfamilyIdentifier
+            let name_id = s.read::<u16>()?;
+            if name_id != 0 {
+                self.name_ids.insert(name_id);
+            }
+
+            10
+        } else if &bytes[..2] == b"ss" {
+            s.skip::<u16>(); // version
+            self.name_ids.insert(s.read::<u16>()?);
+            4
+        } else if &bytes[..2] == b"cv" {
+            s.skip::<u16>(); // format
+            for _ in 0..3 {
+                let name_id = s.read::<u16>()?;
+                if name_id != 0 {
+                    self.name_ids.insert(name_id);
+                }
+            }
+
+            let count = s.read::<u16>()?;
+            let first = s.read::<u16>()?;
+            if first != 0 {
+                for i in 0..count {
+                    self.name_ids.insert(first.checked_add(i)?);
+                }
+            }
+
+            let chars_count = s.read::<u16>()?;
+            s.offset() + usize::from(chars_count) * 3
+        } else {
+            return None;
+        };
+
+        let mut object = Object::new();
+        object.bytes(data.get(..len)?);
+        Some(self.push(object))
+    }
+
+    fn feature_variations(&mut self, data: &[u8], feature_tags: &[Tag]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let version = s.read::<u32>()?;
+        let count = s.read::<u32>()?;
+        let mut records = Vec::new();
+        for _ in 0..count {
+            let conditions = at32(data, s.read::<u32>()?);
+            let substitutions = at32(data, s.read::<u32>()?);
+            let conditions = conditions.and_then(|data| self.condition_set(data));
+            let substitutions =
+                substitutions.and_then(|data| self.feature_table_substitution(data, feature_tags));
+            // Records with unsupported conditions are dropped.
+            if let (Some(conditions), Some(substitutions)) = (conditions, substitutions) {
+                records.push((conditions, substitutions));
+            }
+        }
+
+        let mut object = Object::new();
+        object.u32(version);
+        object.u32(records.len() as u32);
+        for (conditions, substitutions) in records {
+            object.offset32(Some(conditions));
+            object.offset32(Some(substitutions));
+        }
+
+        Some(self.push(object))
+    }
+
+    fn condition_set(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let count = s.read::<u16>()?;
+        let mut object = Object::new();
+        object.u16(count);
+        for _ in 0..count {
+            let condition = at32(data, s.read::<u32>()?)?;
+            // Only the axis range format is supported.
+            if Stream::read_at::<u16>(condition, 0)? != 1 {
+                return None;
+            }
+
+            let mut condition_object = Object::new();
+            condition_object.bytes(condition.get(..8)?);
+            let condition = self.push(condition_object);
+            object.offset32(Some(condition));
+        }
+
+        Some(self.push(object))
+    }
+
+    fn feature_table_substitution(
+        &mut self,
+        data: &[u8],
+        feature_tags: &[Tag],
+    ) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let version = s.read::<u32>()?;
+        let count = s.read::<u16>()?;
+        let mut object = Object::new();
+        object.u32(version);
+        object.u16(count);
+        for _ in 0..count {
+            let index = s.read::<u16>()?;
+            let tag = feature_tags
+                .get(usize::from(index))
+                .copied()
+                .unwrap_or(Tag(0));
+            let feature = self.feature(tag, at32(data, s.read::<u32>()?)?)?;
+            object.u16(index);
+            object.offset32(Some(feature));
+        }
+
+        Some(self.push(object))
+    }
+
+    fn lookup_list(&mut self, data: &[u8], kind: Kind) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let count = s.read::<u16>()?;
+        let mut object = Object::new();
+        object.u16(count);
+        for _ in 0..count {
+            let lookup = at(data, s.read::<u16>()?).and_then(|data| self.lookup(data, kind));
+            let lookup = match lookup {
+                Some(lookup) => lookup,
+                None => {
+                    // Lookup indices must be preserved, so a malformed lookup becomes an empty one.
+                    let mut object = Object::new();
+                    object.u16(kind.extension());
+                    object.u16(0); // lookupFlag
+                    object.u16(0); // subTableCount
+                    self.push(object)
+                }
+            };
+
+            object.offset16(Some(lookup));
+        }
+
+        Some(self.push(object))
+    }
+
+    fn lookup(&mut self, data: &[u8], kind: Kind) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let lookup_type = s.read::<u16>()?;
+        let flags = s.read::<u16>()?;
+        let count = s.read::<u16>()?;
+        let offsets = s.read_array16::<u16>(count)?;
+        let mark_filtering_set = if flags & USE_MARK_FILTERING_SET != 0 {
+            Some(s.read::<u16>()?)
+        } else {
+            None
+        };
+
+        let mut subtables = Vec::new();
+        for offset in offsets {
+            let mut subtable_type = lookup_type;
+            let mut subtable = match at(data, offset) {
+                Some(data) => data,
+                None => continue,
+            };
+
+            if lookup_type == kind.extension() {
+                let mut s = Stream::new(subtable);
+                s.skip::<u16>(); // format
+                let extension = s
+                    .read::<u16>()
+                    .and_then(|kind| Some((kind, at32(subtable, s.read::<u32>()?)?)));
+                match extension {
+                    Some((kind, data)) => {
+                        subtable_type = kind;
+                        subtable = data;
+                    }
+                    None => continue,
+                }
+            }
+
+            self.graph.start_scope();
+            if let Some(subtable) = self.subtable(kind, subtable_type, subtable) {
+                let mut extension = Object::new();
+                extension.u16(1); // format
+                extension.u16(subtable_type);
+                extension.offset32(Some(subtable));
+                subtables.push(self.push(extension));
+            }
+        }
+
+        let mut object = Object::new();
+        object.u16(if subtables.is_empty() {
+            lookup_type
+        } else {
+            kind.extension()
+        });
+        object.u16(flags);
+        object.u16(subtables.len() as u16);
+        for subtable in subtables {
+            object.offset16(Some(subtable));
+        }
+
+        if let Some(set) = mark_filtering_set {
+            object.u16(set);
+        }
+
+        Some(self.push(object))
+    }
+
+    fn subtable(&mut self, kind: Kind, subtable_type: u16, data: &[u8]) -> Option<ObjectId> {
+        match (kind, subtable_type) {
+            (Kind::Substitution, 1) => self.single_substitution(data),
+            (Kind::Substitution, 2) => self.sequence_substitution(data, false),
+            (Kind::Substitution, 3) => self.sequence_substitution(data, true),
+            (Kind::Substitution, 4) => self.ligature_substitution(data),
+            (Kind::Substitution, 5) | (Kind::Positioning, 7) => self.context(data),
+            (Kind::Substitution, 6) | (Kind::Positioning, 8) => self.chained_context(data),
+            (Kind::Substitution, 8) => self.reverse_chain_single_substitution(data),
+            (Kind::Positioning, 1) => self.single_adjustment(data),
+            (Kind::Positioning, 2) => self.pair_adjustment(data),
+            (Kind::Positioning, 3) => self.cursive_adjustment(data),
+            (Kind::Positioning, 4) | (Kind::Positioning, 6) => self.mark_to_base_adjustment(data),
+            (Kind::Positioning, 5) => self.mark_to_ligature_adjustment(data),
+            _ => None,
+        }
+    }
+
+    /// Returns remaining covered glyphs in the new order.
+    fn covered(&self, data: &[u8]) -> Option<Vec<Covered>> {
+        let mut glyphs = Vec::new();
+        let mut cover = |old: GlyphId, index: u16| {
+            if let Some(new) = self.map.get(old) {
+                glyphs.push(Covered { old, new, index });
+            }
+        };
+
+        match Coverage::parse(data)? {
+            Coverage::Format1 { glyphs } => {
+                for (index, glyph) in glyphs.into_iter().enumerate() {
+                    cover(glyph, index as u16);
+                }
+            }
+            Coverage::Format2 { records } => {
+                for record in records {
+                    for (i, glyph) in (record.start.0..=record.end.0).enumerate() {
+                        cover(GlyphId(glyph), record.value.wrapping_add(i as u16));
+                    }
+                }
+            }
+        }
+
+        glyphs.sort_by_key(|covered| covered.new);
+        glyphs.dedup_by_key(|covered| covered.new);
+        Some(glyphs)
+    }
+
+    /// Writes a coverage table. `glyphs` must be sorted.
+    fn coverage(&mut self, glyphs: &[GlyphId]) -> ObjectId {
+        let mut ranges: Vec<(GlyphId, GlyphId)> = Vec::new();
+        for glyph in glyphs {
+            match ranges.last_mut() {
+                Some(range) if u32::from(range.1 .0) + 1 == u32::from(glyph.0) => range.1 = *glyph,
+                _ => ranges.push((*glyph, *glyph)),
+            }
+        }
+
+        let mut object = Object::new();
+        if ranges.len() * 3 < glyphs.len() {
+            object.u16(2);
+            object.u16(ranges.len() as u16);
+            let mut index = 0;
+            for (start, end) in ranges {
+                object.u16(start.0);
+                object.u16(end.0);
+                object.u16(index);
+                index += end.0 - start.0 + 1;
+            }
+        } else {
+            object.u16(1);
+            object.u16(glyphs.len() as u16);
+            for glyph in glyphs {
+                object.u16(glyph.0);
+            }
+        }
+
+        self.push(object)
+    }
+
+    /// Writes a coverage table with remaining glyphs.
+    ///
+    /// Returns `None` when no glyphs are left.
+    fn subset_coverage(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let glyphs: Vec<GlyphId> = self.covered(data)?.iter().map(|c| c.new).collect();
+        if glyphs.is_empty() {
+            return None;
+        }
+
+        Some(self.coverage(&glyphs))
+    }
+
+    /// Writes coverage tables referenced by an offsets array.
+    ///
+    /// Returns `None` when any of them has no glyphs left.
+    fn coverages(&mut self, object: &mut Object, data: &[u8], s: &mut Stream) -> Option<()> {
+        let count = s.read::<u16>()?;
+        object.u16(count);
+        for _ in 0..count {
+            let coverage = self.subset_coverage(at(data, s.read::<u16>()?)?)?;
+            object.offset16(Some(coverage));
+        }
+
+        Some(())
+    }
+
+    fn class_definition(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let class_definition = ClassDefinition::parse(data)?;
+        let classes: Vec<(u16, u16)> = self
+            .map
+            .old_glyphs()
+            .enumerate()
+            .map(|(new, old)| (new as u16, class_definition.get(old)))
+            .filter(|(_, class)| *class != 0)
+            .collect();
+
+        let mut ranges: Vec<(u16, u16, u16)> = Vec::new();
+        for (glyph, class) in &classes {
+            match ranges.last_mut() {
+                Some(range) if u32::from(range.1) + 1 == u32::from(*glyph) && range.2 == *class => {
+                    range.1 = *glyph
+                }
+                _ => ranges.push((*glyph, *glyph, *class)),
+            }
+        }
+
+        let mut object = Object::new();
+        match (classes.first(), classes.last()) {
+            (Some(first), Some(last)) if usize::from(last.0 - first.0) < ranges.len() * 3 => {
+                object.u16(1);
+                object.u16(first.0);
+                object.u16(last.0 - first.0 + 1);
+                let mut classes = classes.iter().peekable();
+                for glyph in first.0..=last.0 {
+                    match classes.peek() {
+                        Some((g, class)) if *g == glyph => {
+                            object.u16(*class);
+                            classes.next();
+                        }
+                        _ => object.u16(0),
+                    }
+                }
+            }
+            _ => {
+                object.u16(2);
+                object.u16(ranges.len() as u16);
+                for (start, end, class) in ranges {
+                    object.u16(start);
+                    object.u16(end);
+                    object.u16(class);
+                }
+            }
+        }
+
+        Some(self.push(object))
+    }
+
+    fn device(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let start_size = s.read::<u16>()?;
+        let end_size = s.read::<u16>()?;
+        let format = s.read::<u16>()?;
+        let len = match format {
+            1..=3 => {
+                let count = usize::from(end_size.checked_sub(start_size)?) + 1;
+                let bits = 1 << format;
+                6 + (count * bits + 15) / 16 * 2
+            }
+            // VariationIndex table.
+            0x8000 if !self.is_instancing() => 6,
+            _ => return None,
+        };
+
+        let mut object = Object::new();
+        object.bytes(data.get(..len)?);
+        Some(self.push(object))
+    }
+
+    fn anchor(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut object = Object::new();
+        match Stream::read_at::<u16>(data, 0)? {
+            1 => object.bytes(data.get(..6)?),
+            2 => object.bytes(data.get(..8)?),
+            3 => {
+                let mut s = Stream::new_at(data, 2)?;
+                let x = s.read::<i16>()?;
+                let y = s.read::<i16>()?;
+                let x_device = at(data, s.read::<u16>()?);
+                let y_device = at(data, s.read::<u16>()?);
+                let (x, x_device) = self.resolve(x, x_device);
+                let (y, y_device) = self.resolve(y, y_device);
+                object.u16(3);
+                object.u16(x as u16);
+                object.u16(y as u16);
+                object.offset16(x_device);
+                object.offset16(y_device);
+            }
+            _ => return None,
+        }
+
+        Some(self.push(object))
+    }
+
+    /// Copies anchor offsets relative to `data`.
+    fn anchors(
+        &mut self,
+        object: &mut Object,
+        data: &[u8],
+        s: &mut Stream,
+        count: u16,
+    ) -> Option<()> {
+        for _ in 0..count {
+            let anchor = at(data, s.read::<u16>()?).and_then(|data| self.anchor(data));
+            object.offset16(anchor);
+        }
+
+        Some(())
+    }
+
+    /// Copies a value record. Device offsets are relative to `data`.
+    fn value_record(
+        &mut self,
+        object: &mut Object,
+        data: &[u8],
+        s: &mut Stream,
+        format: u16,
+    ) -> Option<()> {
+        let mut values = [0i16; 4];
+        for (i, value) in values.iter_mut().enumerate() {
+            if format & (1 << i) != 0 {
+                *value = s.read::<i16>()?;
+            }
+        }
+
+        // Each device table adjusts the value with the same index.
+        let mut devices = [None; 4];
+        for (i, (value, device)) in values.iter_mut().zip(devices.iter_mut()).enumerate() {
+            if format & (1 << (i + 4)) != 0 {
+                let (resolved, object) = self.resolve(*value, at(data, s.read::<u16>()?));
+                *value = resolved;
+                *device = object;
+            }
+        }
+
+        for (i, value) in values.iter().enumerate() {
+            if format & (1 << i) != 0 {
+                object.u16(*value as u16);
+            }
+        }
+
+        for (i, device) in devices.iter().enumerate() {
+            if format & (1 << (i + 4)) != 0 {
+                object.offset16(*device);
+            }
+        }
+
+        Some(())
+    }
+
+    /// Copies glyphs, failing when any of them was removed.
+    fn glyphs(&self, object: &mut Object, s: &mut Stream, count: u16) -> Option<()> {
+        for _ in 0..count {
+            let glyph = self.map.get(s.read::<GlyphId>()?)?;
+            object.u16(glyph.0);
+        }
+
+        Some(())
+    }
+
+    /// Subsets a coverage table and a parallel array of offsets.
+    ///
+    /// Glyphs are removed from the coverage when `f` returns `None` for their item.
+    fn covered_items(
+        &mut self,
+        data: &[u8],
+        coverage: &[u8],
+        offsets: LazyArray16<u16>,
+        mut f: impl FnMut(&mut Self, &[u8]) -> Option<ObjectId>,
+    ) -> Option<(ObjectId, Vec<ObjectId>)> {
+        let mut glyphs = Vec::new();
+        let mut items = Vec::new();
+        for covered in self.covered(coverage)? {
+            let item = offsets
+                .get(covered.index)
+                .and_then(|offset| at(data, offset))
+                .and_then(|data| f(self, data));
+            if let Some(item) = item {
+                glyphs.push(covered.new);
+                items.push(item);
+            }
+        }
+
+        if glyphs.is_empty() {
+            return None;
+        }
+
+        Some((self.coverage(&glyphs), items))
+    }
+
+    /// Writes a `format, coverage, count, offsets` subtable.
+    fn covered_items_subtable(
+        &mut self,
+        format: u16,
+        coverage: ObjectId,
+        items: Vec<ObjectId>,
+    ) -> ObjectId {
+        let mut object = Object::new();
+        object.u16(format);
+        object.offset16(Some(coverage));
+        object.u16(items.len() as u16);
+        for item in items {
+            object.offset16(Some(item));
+        }
+
+        self.push(object)
+    }
+
+    /// Writes a `count, offsets` table, dropping items for which `f` returns `None`.
+    ///
+    /// Returns `None` when no items are left.
+    fn items(
+        &mut self,
+        data: &[u8],
+        mut f: impl FnMut(&mut Self, &[u8]) -> Option<ObjectId>,
+    ) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let count = s.read::<u16>()?;
+        let mut items = Vec::new();
+        for offset in s.read_array16::<u16>(count)? {
+            if let Some(item) = at(data, offset).and_then(|data| f(self, data)) {
+                items.push(item);
+            }
+        }
+
+        if items.is_empty() {
+            return None;
+        }
+
+        let mut object = Object::new();
+        object.u16(items.len() as u16);
+        for item in items {
+            object.offset16(Some(item));
+        }
+
+        Some(self.push(object))
+    }
+
+    fn single_substitution(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let format = s.read::<u16>()?;
+        let covered = self.covered(at(data, s.read::<u16>()?)?)?;
+        let mut pairs = Vec::new();
+        match format {
+            1 => {
+                let delta = s.read::<i16>()?;
+                for c in covered {
+                    let substitute = GlyphId((i32::from(c.old.0) + i32::from(delta)) as u16);
+                    if let Some(substitute) = self.map.get(substitute) {
+                        pairs.push((c.new, substitute));
+                    }
+                }
+            }
+            2 => {
+                let count = s.read::<u16>()?;
+                let substitutes = s.read_array16::<GlyphId>(count)?;
+                for c in covered {
+                    if let Some(substitute) = substitutes.get(c.index).and_then(|g| self.map.get(g))
+                    {
+                        pairs.push((c.new, substitute));
+                    }
+                }
+            }
+            _ => return None,
+        }
+
+        if pairs.is_empty() {
+            return None;
+        }
+
+        let glyphs: Vec<GlyphId> = pairs.iter().map(|pair| pair.0).collect();
+        let coverage = self.coverage(&glyphs);
+        let mut object = Object::new();
+        object.u16(2);
+        object.offset16(Some(coverage));
+        object.u16(pairs.len() as u16);
+        for (_, substitute) in pairs {
+            object.u16(substitute.0);
+        }
+
+        Some(self.push(object))
+    }
+
+    /// Writes a multiple or an alternate substitution subtable.
+    ///
+    /// Alternates can be removed one by one, while sequences are removed as a whole.
+    fn sequence_substitution(&mut self, data: &[u8], alternates: bool) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        if s.read::<u16>()? != 1 {
+            return None;
+        }
+
+        let coverage = at(data, s.read::<u16>()?)?;
+        let count = s.read::<u16>()?;
+        let offsets = s.read_array16::<u16>(count)?;
+        let (coverage, items) = self.covered_items(data, coverage, offsets, |w, data| {
+            let mut s = Stream::new(data);
+            let count = s.read::<u16>()?;
+            let mut glyphs = Vec::new();
+            for glyph in s.read_array16::<GlyphId>(count)? {
+                match w.map.get(glyph) {
+                    Some(glyph) => glyphs.push(glyph),
+                    None if alternates => {}
+                    None => return None,
+                }
+            }
+
+            if alternates && glyphs.is_empty() {
+                return None;
+            }
+
+            let mut object = Object::new();
+            object.u16(glyphs.len() as u16);
+            for glyph in glyphs {
+                object.u16(glyph.0);
+            }
+
+            Some(w.push(object))
+        })?;
+
+        Some(self.covered_items_subtable(1, coverage, items))
+    }
+
+    fn ligature_substitution(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        if s.read::<u16>()? != 1 {
+            return None;
+        }
+
+        let coverage = at(data, s.read::<u16>()?)?;
+        let count = s.read::<u16>()?;
+        let offsets = s.read_array16::<u16>(count)?;
+        let (coverage, items) = self.covered_items(data, coverage, offsets, |w, data| {
+            w.items(data, |w, data| {
+                let mut s = Stream::new(data);
+                let glyph = w.map.get(s.read::<GlyphId>()?)?;
+                let count = s.read::<u16>()?;
+                let mut object = Object::new();
+                object.u16(glyph.0);
+                object.u16(count);
+                w.glyphs(&mut object, &mut s, count.checked_sub(1)?)?;
+                Some(w.push(object))
+            })
+        })?;
+
+        Some(self.covered_items_subtable(1, coverage, items))
+    }
+
+    fn reverse_chain_single_substitution(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        if s.read::<u16>()? != 1 {
+            return None;
+        }
+
+        let covered = self.covered(at(data, s.read::<u16>()?)?)?;
+        let mut contexts = Object::new();
+        self.coverages(&mut contexts, data, &mut s)?; // backtrack
+        self.coverages(&mut contexts, data, &mut s)?; // lookahead
+        let count = s.read::<u16>()?;
+        let substitutes = s.read_array16::<GlyphId>(count)?;
+
+        let mut pairs = Vec::new();
+        for c in covered {
+            if let Some(substitute) = substitutes.get(c.index).and_then(|g| self.map.get(g)) {
+                pairs.push((c.new, substitute));
+            }
+        }
+
+        if pairs.is_empty() {
+            return None;
+        }
+
+        let glyphs: Vec<GlyphId> = pairs.iter().map(|pair| pair.0).collect();
+        let coverage = self.coverage(&glyphs);
+        let mut object = Object::new();
+        object.u16(1);
+        object.offset16(Some(coverage));
+        object.append(contexts);
+        object.u16(pairs.len() as u16);
+        for (_, substitute) in pairs {
+            object.u16(substitute.0);
+        }
+
+        Some(self.push(object))
+    }
+
+    fn sequence_rule(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let glyph_count = s.read::<u16>()?;
+        let lookup_count = s.read::<u16>()?;
+        let mut object = Object::new();
+        object.u16(glyph_count);
+        object.u16(lookup_count);
+        self.glyphs(&mut object, &mut s, glyph_count.checked_sub(1)?)?;
+        object.bytes(s.read_bytes(usize::from(lookup_count) * SEQUENCE_LOOKUP_RECORD_SIZE)?);
+        Some(self.push(object))
+    }
+
+    fn chained_sequence_rule(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let mut object = Object::new();
+        let backtrack_count = s.read::<u16>()?;
+        object.u16(backtrack_count);
+        self.glyphs(&mut object, &mut s, backtrack_count)?;
+        let input_count = s.read::<u16>()?;
+        object.u16(input_count);
+        self.glyphs(&mut object, &mut s, input_count.checked_sub(1)?)?;
+        let lookahead_count = s.read::<u16>()?;
+        object.u16(lookahead_count);
+        self.glyphs(&mut object, &mut s, lookahead_count)?;
+        let lookup_count = s.read::<u16>()?;
+        object.u16(lookup_count);
+        object.bytes(s.read_bytes(usize::from(lookup_count) * SEQUENCE_LOOKUP_RECORD_SIZE)?);
+        Some(self.push(object))
+    }
+
+    /// Copies a class-based rule, which doesn't depend on glyph IDs.
+    fn class_sequence_rule(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let glyph_count = s.read::<u16>()?;
+        let lookup_count = s.read::<u16>()?;
+        s.advance_checked(usize::from(glyph_count.checked_sub(1)?) * 2)?;
+        s.advance_checked(usize::from(lookup_count) * SEQUENCE_LOOKUP_RECORD_SIZE)?;
+
+        let mut object = Object::new();
+        object.bytes(data.get(..s.offset())?);
+        Some(self.push(object))
+    }
+
+    /// Copies a class-based chained rule, which doesn't depend on glyph IDs.
+    fn chained_class_sequence_rule(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let backtrack_count = s.read::<u16>()?;
+        s.advance_checked(usize::from(backtrack_count) * 2)?;
+        let input_count = s.read::<u16>()?;
+        s.advance_checked(usize::from(input_count.checked_sub(1)?) * 2)?;
+        let lookahead_count = s.read::<u16>()?;
+        s.advance_checked(usize::from(lookahead_count) * 2)?;
+        let lookup_count = s.read::<u16>()?;
+        s.advance_checked(usize::from(lookup_count) * SEQUENCE_LOOKUP_RECORD_SIZE)?;
+
+        let mut object = Object::new();
+        object.bytes(data.get(..s.offset())?);
+        Some(self.push(object))
+    }
+
+    fn context(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        match s.read::<u16>()? {
+            1 => {
+                let coverage = at(data, s.read::<u16>()?)?;
+                let count = s.read::<u16>()?;
+                let offsets = s.read_array16::<u16>(count)?;
+                let (coverage, items) =
+                    self.covered_items(data, coverage, offsets, |w, data| {
+                        w.items(data, Self::sequence_rule)
+                    })?;
+                Some(self.covered_items_subtable(1, coverage, items))
+            }
+            2 => {
+                let coverage = self.subset_coverage(at(data, s.read::<u16>()?)?)?;
+                let classes = self.class_definition(at(data, s.read::<u16>()?)?)?;
+                let count = s.read::<u16>()?;
+                let mut object = Object::new();
+                object.u16(2);
+                object.offset16(Some(coverage));
+                object.offset16(Some(classes));
+                object.u16(count);
+                for offset in s.read_array16::<u16>(count)? {
+                    let set = at(data, offset)
+                        .and_then(|data| self.items(data, Self::class_sequence_rule));
+                    object.offset16(set);
+                }
+
+                Some(self.push(object))
+            }
+            3 => {
+                let glyph_count = s.read::<u16>()?;
+                let lookup_count = s.read::<u16>()?;
+                let mut object = Object::new();
+                object.u16(3);
+                object.u16(glyph_count);
+                object.u16(lookup_count);
+                for _ in 0..glyph_count {
+                    let coverage = self.subset_coverage(at(data, s.read::<u16>()?)?)?;
+                    object.offset16(Some(coverage));
+                }
+                object
+                    .bytes(s.read_bytes(usize::from(lookup_count) * SEQUENCE_LOOKUP_RECORD_SIZE)?);
+                Some(self.push(object))
+            }
+            _ => None,
+        }
+    }
+
+    fn chained_context(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        match s.read::<u16>()? {
+            1 => {
+                let coverage = at(data, s.read::<u16>()?)?;
+                let count = s.read::<u16>()?;
+                let offsets = s.read_array16::<u16>(count)?;
+                let (coverage, items) =
+                    self.covered_items(data, coverage, offsets, |w, data| {
+                        w.items(data, Self::chained_sequence_rule)
+                    })?;
+                Some(self.covered_items_subtable(1, coverage, items))
+            }
+            2 => {
+                let coverage = self.subset_coverage(at(data, s.read::<u16>()?)?)?;
+                let mut object = Object::new();
+                object.u16(2);
+                object.offset16(Some(coverage));
+                for _ in 0..3 {
+                    // Backtrack and lookahead class definitions can be omitted.
+                    let classes = at(data, s.read::<u16>()?);
+                    let classes = match classes {
+                        Some(data) => Some(self.class_definition(data)?),
+                        None => None,
+                    };
+                    object.offset16(classes);
+                }
+
+                let count = s.read::<u16>()?;
+                object.u16(count);
+                for offset in s.read_array16::<u16>(count)? {
+                    let set = at(data, offset)
+                        .and_then(|data| self.items(data, Self::chained_class_sequence_rule));
+                    object.offset16(set);
+                }
+
+                Some(self.push(object))
+            }
+            3 => {
+                let mut object = Object::new();
+                object.u16(3);
+                self.coverages(&mut object, data, &mut s)?; // backtrack
+                self.coverages(&mut object, data, &mut s)?; // input
+                self.coverages(&mut object, data, &mut s)?; // lookahead
+                let lookup_count = s.read::<u16>()?;
+                object.u16(lookup_count);
+                object
+                    .bytes(s.read_bytes(usize::from(lookup_count) * SEQUENCE_LOOKUP_RECORD_SIZE)?);
+                Some(self.push(object))
+            }
+            _ => None,
+        }
+    }
+
+    fn single_adjustment(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let format = s.read::<u16>()?;
+        let covered = self.covered(at(data, s.read::<u16>()?)?)?;
+        let value_format = s.read::<u16>()?;
+        if covered.is_empty() {
+            return None;
+        }
+
+        let glyphs: Vec<GlyphId> = covered.iter().map(|c| c.new).collect();
+        let coverage = self.coverage(&glyphs);
+        let mut object = Object::new();
+        object.u16(format);
+        object.offset16(Some(coverage));
+        object.u16(value_format);
+        match format {
+            1 => self.value_record(&mut object, data, &mut s, value_format)?,
+            2 => {
+                object.u16(covered.len() as u16);
+                let size = value_record_size(value_format);
+                for c in covered {
+                    let mut s = Stream::new_at(data, 8 + usize::from(c.index) * size)?;
+                    self.value_record(&mut object, data, &mut s, value_format)?;
+                }
+            }
+            _ => return None,
+        }
+
+        Some(self.push(object))
+    }
+
+    fn pair_adjustment(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let format = s.read::<u16>()?;
+        let coverage = at(data, s.read::<u16>()?)?;
+        let value_formats = (s.read::<u16>()?, s.read::<u16>()?);
+        match format {
+            1 => {
+                let count = s.read::<u16>()?;
+                let offsets = s.read_array16::<u16>(count)?;
+                let (coverage, items) =
+                    self.covered_items(data, coverage, offsets, |w, data| {
+                        // Device offsets are relative to the pair set.
+                        let mut s = Stream::new(data);
+                        let count = s.read::<u16>()?;
+                        let mut records = Object::new();
+                        let mut kept = 0;
+                        for _ in 0..count {
+                            let second = w.map.get(s.read::<GlyphId>()?);
+                            let mut record = Object::new();
+                            if let Some(second) = second {
+                                record.u16(second.0);
+                            }
+                            w.value_record(&mut record, data, &mut s, value_formats.0)?;
+                            w.value_record(&mut record, data, &mut s, value_formats.1)?;
+                            if second.is_some() {
+                                records.append(record);
+                                kept += 1;
+                            }
+                        }
+
+                        if kept == 0 {
+                            return None;
+                        }
+
+                        let mut object = Object::new();
+                        object.u16(kept);
+                        object.append(records);
+                        Some(w.push(object))
+                    })?;
+
+                let mut object = Object::new();
+                object.u16(1);
+                object.offset16(Some(coverage));
+                object.u16(value_formats.0);
+                object.u16(value_formats.1);
+                object.u16(items.len() as u16);
+                for item in items {
+                    object.offset16(Some(item));
+                }
+
+                Some(self.push(object))
+            }
+            2 => {
+                let coverage = self.subset_coverage(coverage)?;
+                let classes1 = self.class_definition(at(data, s.read::<u16>()?)?)?;
+                let classes2 = self.class_definition(at(data, s.read::<u16>()?)?)?;
+                let counts = (s.read::<u16>()?, s.read::<u16>()?);
+
+                let mut object = Object::new();
+                object.u16(2);
+                object.offset16(Some(coverage));
+                object.u16(value_formats.0);
+                object.u16(value_formats.1);
+                object.offset16(Some(classes1));
+                object.offset16(Some(classes2));
+                object.u16(counts.0);
+                object.u16(counts.1);
+                for _ in 0..u32::from(counts.0) * u32::from(counts.1) {
+                    self.value_record(&mut object, data, &mut s, value_formats.0)?;
+                    self.value_record(&mut object, data, &mut s, value_formats.1)?;
+                }
+
+                Some(self.push(object))
+            }
+            _ => None,
+        }
+    }
+
+    fn cursive_adjustment(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        if s.read::<u16>()? != 1 {
+            return None;
+        }
+
+        let covered = self.covered(at(data, s.read::<u16>()?)?)?;
+        if covered.is_empty() {
+            return None;
+        }
+
+        let glyphs: Vec<GlyphId> = covered.iter().map(|c| c.new).collect();
+        let coverage = self.coverage(&glyphs);
+        let mut object = Object::new();
+        object.u16(1);
+        object.offset16(Some(coverage));
+        object.u16(covered.len() as u16);
+        for c in covered {
+            // Entry and exit anchors.
+            let mut s = Stream::new_at(data, 6 + usize::from(c.index) * 4)?;
+            self.anchors(&mut object, data, &mut s, 2)?;
+        }
+
+        Some(self.push(object))
+    }
+
+    /// Writes a mark array with the remaining marks.
+    fn mark_array(&mut self, data: &[u8], marks: &[Covered]) -> Option<ObjectId> {
+        let mut object = Object::new();
+        object.u16(marks.len() as u16);
+        for mark in marks {
+            let mut s = Stream::new_at(data, 2 + usize::from(mark.index) * 4)?;
+            object.u16(s.read::<u16>()?); // class
+            self.anchors(&mut object, data, &mut s, 1)?;
+        }
+
+        Some(self.push(object))
+    }
+
+    /// Writes a mark-to-base or a mark-to-mark subtable.
+    fn mark_to_base_adjustment(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        if s.read::<u16>()? != 1 {
+            return None;
+        }
+
+        let marks = self.covered(at(data, s.read::<u16>()?)?)?;
+        let bases = self.covered(at(data, s.read::<u16>()?)?)?;
+        let class_count = s.read::<u16>()?;
+        let mark_array = at(data, s.read::<u16>()?)?;
+        let base_array = at(data, s.read::<u16>()?)?;
+        if marks.is_empty() || bases.is_empty() {
+            return None;
+        }
+
+        let glyphs: Vec<GlyphId> = marks.iter().map(|c| c.new).collect();
+        let mark_coverage = self.coverage(&glyphs);
+        let glyphs: Vec<GlyphId> = bases.iter().map(|c| c.new).collect();
+        let base_coverage = self.coverage(&glyphs);
+        let mark_array = self.mark_array(mark_array, &marks)?;
+
+        let mut object = Object::new();
+        object.u16(bases.len() as u16);
+        let row_size = usize::from(class_count) * 2;
+        for base in &bases {
+            let mut s = Stream::new_at(base_array, 2 + usize::from(base.index) * row_size)?;
+            self.anchors(&mut object, base_array, &mut s, class_count)?;
+        }
+        let base_array = self.push(object);
+
+        let mut object = Object::new();
+        object.u16(1);
+        object.offset16(Some(mark_coverage));
+        object.offset16(Some(base_coverage));
+        object.u16(class_count);
+        object.offset16(Some(mark_array));
+        object.offset16(Some(base_array));
+        Some(self.push(object))
+    }
+
+    fn mark_to_ligature_adjustment(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        if s.read::<u16>()? != 1 {
+            return None;
+        }
+
+        let marks = self.covered(at(data, s.read::<u16>()?)?)?;
+        let ligatures = at(data, s.read::<u16>()?)?;
+        let class_count = s.read::<u16>()?;
+        let mark_array = at(data, s.read::<u16>()?)?;
+        let ligature_array = at(data, s.read::<u16>()?)?;
+        if marks.is_empty() {
+            return None;
+        }
+
+        let mut s = Stream::new(ligature_array);
+        let count = s.read::<u16>()?;
+        let offsets = s.read_array16::<u16>(count)?;
+        let (ligature_coverage, items) =
+            self.covered_items(ligature_array, ligatures, offsets, |w, data| {
+                let mut s = Stream::new(data);
+                let component_count = s.read::<u16>()?;
+                let mut object = Object::new();
+                object.u16(component_count);
+                for _ in 0..component_count {
+                    w.anchors(&mut object, data, &mut s, class_count)?;
+                }
+
+                Some(w.push(object))
+            })?;
+
+        let glyphs: Vec<GlyphId> = marks.iter().map(|c| c.new).collect();
+        let mark_coverage = self.coverage(&glyphs);
+        let mark_array = self.mark_array(mark_array, &marks)?;
+
+        let mut object = Object::new();
+        object.u16(items.len() as u16);
+        for item in items {
+            object.offset16(Some(item));
+        }
+        let ligature_array = self.push(object);
+
+        let mut object = Object::new();
+        object.u16(1);
+        object.offset16(Some(mark_coverage));
+        object.offset16(Some(ligature_coverage));
+        object.u16(class_count);
+        object.offset16(Some(mark_array));
+        object.offset16(Some(ligature_array));
+        Some(self.push(object))
+    }
+
+    fn gdef(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let version = s.read::<u32>()?;
+        if !(version == 0x00010000 || version == 0x00010002 || version == 0x00010003) {
+            return None;
+        }
+
+        let glyph_classes = at(data, s.read::<u16>()?);
+        let attach_list = at(data, s.read::<u16>()?);
+        let ligature_carets = at(data, s.read::<u16>()?);
+        let mark_attach_classes = at(data, s.read::<u16>()?);
+        let mark_glyph_sets = if version >= 0x00010002 {
+            at(data, s.read::<u16>()?)
+        } else {
+            None
+        };
+        let variation_store = if version >= 0x00010003 {
+            at32(data, s.read::<u32>()?)
+        } else {
+            None
+        };
+
+        // The item variation store is applied when instancing.
+        let version = if self.is_instancing() {
+            version.min(0x00010002)
+        } else {
+            version
+        };
+
+        let mut object = Object::new();
+        object.u32(version);
+        let glyph_classes = glyph_classes.and_then(|data| self.class_definition(data));
+        object.offset16(glyph_classes);
+        let attach_list = attach_list.and_then(|data| self.attach_list(data));
+        object.offset16(attach_list);
+        let ligature_carets = ligature_carets.and_then(|data| self.ligature_caret_list(data));
+        object.offset16(ligature_carets);
+        let mark_attach_classes = mark_attach_classes.and_then(|data| self.class_definition(data));
+        object.offset16(mark_attach_classes);
+        if version >= 0x00010002 {
+            let mark_glyph_sets = mark_glyph_sets.and_then(|data| self.mark_glyph_sets(data));
+            object.offset16(mark_glyph_sets);
+        }
+        if version >= 0x00010003 {
+            let variation_store = variation_store.and_then(|data| self.variation_store(data));
+            object.offset32(variation_store);
+        }
+
+        Some(self.push(object))
+    }
+
+    fn attach_list(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let coverage = at(data, s.read::<u16>()?)?;
+        let count = s.read::<u16>()?;
+        let offsets = s.read_array16::<u16>(count)?;
+        let (coverage, items) = self.covered_items(data, coverage, offsets, |w, data| {
+            let count = Stream::read_at::<u16>(data, 0)?;
+            let mut object = Object::new();
+            object.bytes(data.get(..2 + usize::from(count) * 2)?);
+            Some(w.push(object))
+        })?;
+
+        let mut object = Object::new();
+        object.offset16(Some(coverage));
+        object.u16(items.len() as u16);
+        for item in items {
+            object.offset16(Some(item));
+        }
+
+        Some(self.push(object))
+    }
+
+    fn ligature_caret_list(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let coverage = at(data, s.read::<u16>()?)?;
+        let count = s.read::<u16>()?;
+        let offsets = s.read_array16::<u16>(count)?;
+        let (coverage, items) = self.covered_items(data, coverage, offsets, |w, data| {
+            w.items(data, |w, data| {
+                let mut object = Object::new();
+                // Only format 3 has a device table.
+                if Stream::read_at::<u16>(data, 0)? == 3 {
+                    let coordinate = Stream::read_at::<i16>(data, 2)?;
+                    let device = at(data, Stream::read_at::<u16>(data, 4)?);
+                    let (coordinate, device) = w.resolve(coordinate, device);
+                    object.u16(3);
+                    object.u16(coordinate as u16);
+                    object.offset16(device);
+                } else {
+                    object.bytes(data.get(..4)?);
+                }
+
+                Some(w.push(object))
+            })
+        })?;
+
+        let mut object = Object::new();
+        object.offset16(Some(coverage));
+        object.u16(items.len() as u16);
+        for item in items {
+            object.offset16(Some(item));
+        }
+
+        Some(self.push(object))
+    }
+
+    /// Writes mark glyph sets.
+    ///
+    /// Sets are referenced by index, so empty ones are kept.
+    fn mark_glyph_sets(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let format = s.read::<u16>()?;
+        let count = s.read::<u16>()?;
+        let mut object = Object::new();
+        object.u16(format);
+        object.u16(count);
+        for _ in 0..count {
+            let glyphs: Vec<GlyphId> = self
+                .covered(at32(data, s.read::<u32>()?)?)?
+                .iter()
+                .map(|c| c.new)
+                .collect();
+            let coverage = self.coverage(&glyphs);
+            object.offset32(Some(coverage));
+        }
+
+        Some(self.push(object))
+    }
+
+    /// Copies an item variation store, which doesn't depend on glyph IDs.
+    fn variation_store(&mut self, data: &[u8]) -> Option<ObjectId> {
+        let mut s = Stream::new(data);
+        let format = s.read::<u16>()?;
+        let regions = at32(data, s.read::<u32>()?)?;
+        let count = s.read::<u16>()?;
+
+        let mut rs = Stream::new(regions);
+        let axis_count = rs.read::<u16>()?;
+        let region_count = rs.read::<u16>()?;
+        let len = 4 + usize::from(axis_count) * usize::from(region_count) * 6;
+        let mut object = Object::new();
+        object.bytes(regions.get(..len)?);
+        let regions = self.push(object);
+
+        let mut object = Object::new();
+        object.u16(format);
+        object.offset32(Some(regions));
+        object.u16(count);
+        for _ in 0..count {
+            let data = at32(data, s.read::<u32>()?)?;
+            let mut s = Stream::new(data);
+            let item_count = s.read::<u16>()?;
+            let word_delta_count = s.read::<u16>()?;
+            let region_index_count = s.read::<u16>()?;
+
+            let long_words = word_delta_count & 0x8000 != 0;
+            let word_count = usize::from(word_delta_count & 0x7FFF);
+            let region_index_count = usize::from(region_index_count);
+            let short_count = region_index_count.checked_sub(word_count)?;
+            let row_size = if long_words {
+                word_count * 4 + short_count * 2
+            } else {
+                word_count * 2 + short_count
+            };
+
+            let len = 6 + region_index_count * 2 + usize::from(item_count) * row_size;
+            let mut item_variation_data = Object::new();
+            item_variation_data.bytes(data.get(..len)?);
+            let item_variation_data = self.push(item_variation_data);
+            object.offset32(Some(item_variation_data));
+        }
+
+        Some(self.push(object))
+    }
+}
+
+/// Returns feature tags from a FeatureList.
+fn feature_tags(data: &[u8]) -> Vec<Tag> {
+    let mut s = Stream::new(data);
+    let count = s.read::<u16>().unwrap_or(0);
+    let mut tags = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        match s.read::<Tag>() {
+            Some(tag) => tags.push(tag),
+            None => break,
+        }
+
+        s.skip::<u16>(); // offset
+    }
+
+    tags
+}
+
+/// Returns an alternate feature table for a feature index.
+fn alternate_feature(data: &[u8], feature_index: u16) -> Option<&[u8]> {
+    let mut s = Stream::new(data);
+    s.skip::<u32>(); // version
+    let count = s.read::<u16>()?;
+    for _ in 0..count {
+        let index = s.read::<u16>()?;
+        let offset = s.read::<u32>()?;
+        if index == feature_index {
+            return at32(data, offset);
+        }
+    }
+
+    None
+}
+
+/// Checks that all conditions of a condition set match the location.
+///
+/// Conditions of unknown formats never match.
+#[cfg(feature = "instance")]
+fn matches_condition_set(data: &[u8], coordinates: &[NormalizedCoordinate]) -> Option<bool> {
+    let mut s = Stream::new(data);
+    let count = s.read::<u16>()?;
+    for _ in 0..count {
+        let condition = at32(data, s.read::<u32>()?)?;
+        let mut s = Stream::new(condition);
+        if s.read::<u16>()? != 1 {
+            return Some(false);
+        }
+
+        let axis_index = s.read::<u16>()?;
+        let min = s.read::<i16>()?;
+        let max = s.read::<i16>()?;
+        let coordinate = coordinates
+            .get(usize::from(axis_index))
+            .map_or(0, |c| c.get());
+        if coordinate < min || coordinate > max {
+            return Some(false);
+        }
+    }
+
+    Some(true)
+}
@@ -0,0 +1,328 @@
+//! A font subsetter.
+//!
+//! Produces a font that contains only the requested glyphs,
+//! like the ones used by a PDF document. Requires heap allocations.
+//!
+//! ```no_run
+//! use ttf_parser::{subset::Subsetter, Face};
+//!
+//! let data = std::fs::read("font.ttf").unwrap();
+//! let face = Face::parse(&data, 0).unwrap();
+//! let mut subsetter = Subsetter::new(&face).unwrap();
+//! for c in "Hello".chars() {
+//!     subsetter.add_codepoint(c);
+//! }
+//!
+//! let subset = subsetter.build().unwrap();
+//! std::fs::write("font-subset.ttf", subset.data()).unwrap();
+//! ```
+
+pub(crate) mod cff;
+pub(crate) mod glyf;
+pub(crate) mod layout;
+mod serializer;
+pub(crate) mod tables;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::vec::Vec;
+
+use crate::writer::FontBuilder;
+use crate::{Face, GlyphId, Tag};
+
+const TRUETYPE_FLAVOR: u32 = 0x00010000;
+const CFF_FLAVOR: u32 = 0x4F54544F; // OTTO
+
+/// Tables that do not depend on glyph IDs and are copied as is.
+const COPIED_TABLES: &[&[u8; 4]] = &[b"cvt ", b"fpgm", b"prep", b"gasp"];
+
+/// A list of errors that can occur during subsetting.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SubsetError {
+    /// The font doesn't allow subsetting.
+    ///
+    /// See [`Face::is_subsetting_allowed`].
+    NotAllowed,
+    /// A table required for subsetting is missing or malformed.
+    MalformedFont,
+    /// The font has no `glyf` or `CFF ` table.
+    ///
+    /// `CFF2` fonts are not supported.
+    UnsupportedFormat,
+    /// An offset or a table size overflowed.
+    Overflow,
+}
+
+impl core::fmt::Display for SubsetError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SubsetError::NotAllowed => write!(f, "font doesn't allow subsetting"),
+            SubsetError::MalformedFont => write!(f, "malformed font"),
+            SubsetError::UnsupportedFormat => write!(f, "unsupported outlines format"),
+            SubsetError::Overflow => write!(f, "offset overflow"),
+        }
+    }
+}
+
+impl std::error::Error for SubsetError {}
+
+/// A font subsetter.
+///
+/// The subset includes:
+///
+/// - glyph 0, which is `.notdef`
+/// - requested glyphs and glyphs mapped to requested codepoints
+/// - glyphs reachable via `GSUB` lookups
+/// - components of composite `glyf` glyphs and of CFF `seac` glyphs
+///
+/// Glyphs are renumbered, but keep their relative order.
+///
+/// Only `glyf`/`loca`, `CFF `, `cmap`, `head`, `hhea`/`hmtx`, `vhea`/`vmtx`, `maxp`,
+/// `name`, `OS/2`, `post`, `GDEF`, `GPOS`, `GSUB` and the hinting tables
+/// (`cvt `, `fpgm`, `prep` and `gasp`) are written. All other tables, including
+/// the variation ones, are dropped, so a variable font becomes its default instance.
+pub struct Subsetter<'a> {
+    face: &'a Face<'a>,
+    glyphs: BTreeSet<GlyphId>,
+    codepoints: BTreeMap<u32, GlyphId>,
+}
+
+impl<'a> Subsetter<'a> {
+    /// Creates a new subsetter.
+    ///
+    /// Returns [`SubsetError::NotAllowed`] when [`Face::is_subsetting_allowed`]
+    /// is `false`.
+    pub fn new(face: &'a Face<'a>) -> Result<Self, SubsetError> {
+        if !face.is_subsetting_allowed() {
+            return Err(SubsetError::NotAllowed);
+        }
+
+        Ok(Subsetter {
+            face,
+            glyphs: BTreeSet::new(),
+            codepoints: BTreeMap::new(),
+        })
+    }
+
+    /// Adds a glyph to the subset.
+    ///
+    /// Glyphs outside of the font are ignored.
+    #[inline]
+    pub fn add_glyph(&mut self, glyph_id: GlyphId) {
+        if glyph_id.0 < self.face.number_of_glyphs() {
+            self.glyphs.insert(glyph_id);
+        }
+    }
+
+    /// Adds a codepoint to the subset.
+    ///
+    /// The codepoint is added to the `cmap` table along with its glyph.
+    /// Codepoints that are not mapped by the font are ignored.
+    pub fn add_codepoint(&mut self, c: char) {
+        if let Some(glyph_id) = self.face.glyph_index(c) {
+            if glyph_id.0 < self.face.number_of_glyphs() {
+                self.codepoints.insert(u32::from(c), glyph_id);
+            }
+        }
+    }
+
+    /// Writes the subset font.
+    pub fn build(&self) -> Result<Subset, SubsetError> {
+        let face = self.face;
+        let raw_face = face.raw_face();
+        let cff_data = raw_face.table(Tag::from_bytes(b"CFF "));
+        let has_glyf = face.tables().glyf.is_some();
+        if !has_glyf && cff_data.is_none() {
+            return Err(SubsetError::UnsupportedFormat);
+        }
+
+        let mut glyphs = self.glyphs.clone();
+        glyphs.insert(GlyphId(0));
+        glyphs.extend(self.codepoints.values());
+
+        layout::closure(face, &mut glyphs);
+        if has_glyf {
+            glyf::closure(face, &mut glyphs)?;
+        } else if let Some(data) = cff_data {
+            cff::closure(data, &mut glyphs)?;
+        }
+
+        let map = GlyphMap {
+            glyphs: glyphs.into_iter().collect(),
+        };
+
+        let codepoints: Vec<(u32, GlyphId)> = self
+            .codepoints
+            .iter()
+            .filter_map(|(c, glyph_id)| Some((*c, map.get(*glyph_id)?)))
+            .collect();
+
+        let mut builder = FontBuilder::new(TRUETYPE_FLAVOR);
+        let mut index_to_location_format = None;
+        if has_glyf {
+            let (glyf, loca, format) = glyf::subset(face, &map)?;
+            builder.set_table(Tag::from_bytes(b"glyf"), glyf);
+            builder.set_table(Tag::from_bytes(b"loca"), loca);
+            index_to_location_format = Some(format);
+        } else if let Some(data) = cff_data {
+            builder = FontBuilder::new(CFF_FLAVOR);
+            builder.set_table(Tag::from_bytes(b"CFF "), cff::subset(data, &map)?);
+        }
+
+        let head = raw_face
+            .table(Tag::from_bytes(b"head"))
+            .ok_or(SubsetError::MalformedFont)?;
+        builder.set_table(
+            Tag::from_bytes(b"head"),
+            tables::head(head, index_to_location_format)?,
+        );
+
+        let maxp = raw_face
+            .table(Tag::from_bytes(b"maxp"))
+            .ok_or(SubsetError::MalformedFont)?;
+        builder.set_table(Tag::from_bytes(b"maxp"), tables::maxp(maxp, &map)?);
+
+        let hhea = raw_face
+            .table(Tag::from_bytes(b"hhea"))
+            .ok_or(SubsetError::MalformedFont)?;
+        let hmtx = face.tables().hmtx.ok_or(SubsetError::MalformedFont)?;
+        let (hhea, hmtx) = tables::metrics(hhea, hmtx, &map)?;
+        builder.set_table(Tag::from_bytes(b"hhea"), hhea);
+        builder.set_table(Tag::from_bytes(b"hmtx"), hmtx);
+
+        if let (Some(vhea), Some(vmtx)) =
+            (raw_face.table(Tag::from_bytes(b"vhea")), face.tables().vmtx)
+        {
+            let (vhea, vmtx) = tables::metrics(vhea, vmtx, &map)?;
+            builder.set_table(Tag::from_bytes(b"vhea"), vhea);
+            builder.set_table(Tag::from_bytes(b"vmtx"), vmtx);
+        }
+
+        builder.set_table(Tag::from_bytes(b"cmap"), tables::cmap(&codepoints));
+
+        if let Some(data) = raw_face.table(Tag::from_bytes(b"OS/2")) {
+            builder.set_table(Tag::from_bytes(b"OS/2"), tables::os2(data, &codepoints));
+        }
+
+        if let Some(data) = raw_face.table(Tag::from_bytes(b"post")) {
+            builder.set_table(Tag::from_bytes(b"post"), tables::post(data, &map)?);
+        }
+
+        let mut name_ids = BTreeSet::new();
+        for tag in &[b"GDEF", b"GPOS", b"GSUB"] {
+            let tag = Tag::from_bytes(tag);
+            if let Some(data) = raw_face.table(tag) {
+                if let Some(data) = layout::subset(tag, data, &map, &mut name_ids)? {
+                    builder.set_table(tag, data);
+                }
+            }
+        }
+
+        if let Some(data) = raw_face.table(Tag::from_bytes(b"name")) {
+            builder.set_table(Tag::from_bytes(b"name"), tables::name(data, &name_ids)?);
+        }
+
+        for tag in COPIED_TABLES {
+            let tag = Tag::from_bytes(tag);
+            if let Some(data) = raw_face.table(tag) {
+                builder.set_table(tag, data);
+            }
+        }
+
+        let data = builder.build().ok_or(SubsetError::Overflow)?;
+        Ok(Subset {
+            data,
+            glyphs: map.glyphs,
+        })
+    }
+}
+
+impl core::fmt::Debug for Subsetter<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Subsetter {{ ... }}")
+    }
+}
+
+/// A subset font.
+#[derive(Clone)]
+pub struct Subset {
+    data: Vec<u8>,
+    // Original glyph IDs, indexed by new glyph IDs.
+    glyphs: Vec<GlyphId>,
+}
+
+impl Subset {
+    /// Returns the font data.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the font data, consuming the subset.
+    #[inline]
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Returns the number of glyphs in the subset.
+    #[inline]
+    pub fn number_of_glyphs(&self) -> u16 {
+        // Cannot overflow, since the original font has the same or a larger number of glyphs.
+        self.glyphs.len() as u16
+    }
+
+    /// Returns the new ID of an original glyph.
+    ///
+    /// Returns `None` when the glyph is not in the subset.
+    #[inline]
+    pub fn new_glyph_id(&self, old: GlyphId) -> Option<GlyphId> {
+        let index = self.glyphs.binary_search(&old).ok()?;
+        Some(GlyphId(index as u16))
+    }
+
+    /// Returns the original ID of a subset glyph.
+    #[inline]
+    pub fn old_glyph_id(&self, new: GlyphId) -> Option<GlyphId> {
+        self.glyphs.get(usize::from(new.0)).copied()
+    }
+}
+
+impl core::fmt::Debug for Subset {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Subset {{ ... }}")
+    }
+}
+
+/// A mapping from original to new glyph IDs.
+pub(crate) struct GlyphMap {
+    // Sorted original glyph IDs, indexed by new glyph IDs.
+    glyphs: Vec<GlyphId>,
+}
+
+impl GlyphMap {
+    /// Creates a mapping that keeps all glyphs as is.
+    #[cfg(feature = "instance")]
+    pub fn all(number_of_glyphs: u16) -> Self {
+        GlyphMap {
+            glyphs: (0..number_of_glyphs).map(GlyphId).collect(),
+        }
+    }
+
+    /// Returns the new ID of an original glyph.
+    #[inline]
+    pub fn get(&self, old: GlyphId) -> Option<GlyphId> {
+        let index = self.glyphs.binary_search(&old).ok()?;
+        Some(GlyphId(index as u16))
+    }
+
+    /// Returns the number of glyphs in the subset.
+    #[inline]
+    pub fn len(&self) -> u16 {
+        self.glyphs.len() as u16
+    }
+
+    /// Iterates over original glyph IDs in the new order.
+    #[inline]
+    pub fn old_glyphs(&self) -> impl Iterator<Item = GlyphId> + '_ {
+        self.glyphs.iter().copied()
+    }
+}
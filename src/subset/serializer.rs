@@ -0,0 +1,185 @@
+//! A serializer for tables built out of objects linked by offsets.
+//!
+//! Objects are pushed children first and are written parents first,
+//! since offsets are unsigned and relative to the object that stores them.
+
+use core::convert::TryFrom;
+use std::collections::{HashMap, VecDeque};
+use std::vec::Vec;
+
+pub(crate) type ObjectId = usize;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Link {
+    position: usize,
+    width: u8,
+    target: ObjectId,
+}
+
+/// A serialized object, like a subtable or a coverage table.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Object {
+    data: Vec<u8>,
+    links: Vec<Link>,
+}
+
+impl Object {
+    #[inline]
+    pub fn new() -> Self {
+        Object::default()
+    }
+
+    #[inline]
+    pub fn u16(&mut self, n: u16) {
+        self.data.extend_from_slice(&n.to_be_bytes());
+    }
+
+    #[inline]
+    pub fn u32(&mut self, n: u32) {
+        self.data.extend_from_slice(&n.to_be_bytes());
+    }
+
+    #[inline]
+    pub fn bytes(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data);
+    }
+
+    /// Appends data and links of another object.
+    pub fn append(&mut self, other: Object) {
+        let start = self.data.len();
+        self.data.extend_from_slice(&other.data);
+        self.links.extend(other.links.into_iter().map(|link| Link {
+            position: start + link.position,
+            ..link
+        }));
+    }
+
+    /// Writes an `Offset16` to `target` or a null offset.
+    #[inline]
+    pub fn offset16(&mut self, target: Option<ObjectId>) {
+        self.offset(target, 2);
+    }
+
+    /// Writes an `Offset32` to `target` or a null offset.
+    #[inline]
+    pub fn offset32(&mut self, target: Option<ObjectId>) {
+        self.offset(target, 4);
+    }
+
+    fn offset(&mut self, target: Option<ObjectId>, width: u8) {
+        if let Some(target) = target {
+            self.links.push(Link {
+                position: self.data.len(),
+                width,
+                target,
+            });
+        }
+
+        self.data.resize(self.data.len() + usize::from(width), 0);
+    }
+}
+
+/// A graph of objects.
+#[derive(Default)]
+pub(crate) struct Graph {
+    objects: Vec<Object>,
+    dedup: HashMap<Object, ObjectId>,
+}
+
+impl Graph {
+    #[inline]
+    pub fn new() -> Self {
+        Graph::default()
+    }
+
+    /// Adds an object to the graph.
+    ///
+    /// Identical objects within a scope are shared.
+    pub fn push(&mut self, object: Object) -> ObjectId {
+        if let Some(id) = self.dedup.get(&object) {
+            return *id;
+        }
+
+        let id = self.objects.len();
+        self.objects.push(object.clone());
+        self.dedup.insert(object, id);
+        id
+    }
+
+    /// Starts a new deduplication scope.
+    ///
+    /// A shared object is written after all of its parents,
+    /// so sharing objects between large subtables can overflow 16-bit offsets.
+    #[inline]
+    pub fn start_scope(&mut self) {
+        self.dedup.clear();
+    }
+
+    /// Writes all objects reachable from `root`.
+    ///
+    /// Objects behind 32-bit offsets are written after everything else,
+    /// which keeps 16-bit offsets short.
+    ///
+    /// Returns `None` when an offset overflows.
+    pub fn serialize(&self, root: ObjectId) -> Option<Vec<u8>> {
+        let mut incoming = vec![0usize; self.objects.len()];
+        let mut visited = vec![false; self.objects.len()];
+        visited[root] = true;
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            for link in &self.objects[id].links {
+                incoming[link.target] += 1;
+                if !visited[link.target] {
+                    visited[link.target] = true;
+                    stack.push(link.target);
+                }
+            }
+        }
+
+        let mut positions = vec![0; self.objects.len()];
+        let mut order = Vec::new();
+        let mut size = 0;
+        let mut ready = vec![root];
+        let mut deferred = VecDeque::new();
+        let mut children = Vec::new();
+        while let Some(id) = ready.pop().or_else(|| deferred.pop_front()) {
+            positions[id] = size;
+            size += self.objects[id].data.len();
+            order.push(id);
+
+            for link in &self.objects[id].links {
+                incoming[link.target] -= 1;
+                if incoming[link.target] == 0 {
+                    if link.width == 4 {
+                        deferred.push_back(link.target);
+                    } else {
+                        children.push(link.target);
+                    }
+                }
+            }
+
+            // The first child is written first.
+            ready.extend(children.drain(..).rev());
+        }
+
+        let mut data = Vec::with_capacity(size);
+        for id in order {
+            let object = &self.objects[id];
+            let start = data.len();
+            data.extend_from_slice(&object.data);
+            for link in &object.links {
+                let offset = positions[link.target] - positions[id];
+                let position = start + link.position;
+                if link.width == 2 {
+                    let offset = u16::try_from(offset).ok()?;
+                    data[position..position + 2].copy_from_slice(&offset.to_be_bytes());
+                } else {
+                    let offset = u32::try_from(offset).ok()?;
+                    data[position..position + 4].copy_from_slice(&offset.to_be_bytes());
+                }
+            }
+        }
+
+        Some(data)
+    }
+}
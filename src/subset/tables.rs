@@ -0,0 +1,429 @@
+//! Subsetting of tables that store per-glyph or per-codepoint data.
+
+use core::convert::TryFrom;
+use std::collections::BTreeSet;
+use std::vec::Vec;
+
+use super::{GlyphMap, SubsetError};
+use crate::parser::Stream;
+use crate::{hmtx, GlyphId, IndexToLocationFormat};
+
+const CHECKSUM_ADJUSTMENT_OFFSET: usize = 8;
+const INDEX_TO_LOC_FORMAT_OFFSET: usize = 50;
+const NUM_GLYPHS_OFFSET: usize = 4;
+const NUMBER_OF_METRICS_OFFSET: usize = 34;
+const FIRST_CHAR_INDEX_OFFSET: usize = 64;
+const POST_HEADER_SIZE: usize = 32;
+const MACINTOSH_NAMES_LEN: u16 = 258;
+const NAME_RECORD_SIZE: usize = 12;
+
+/// The last name ID that is always kept.
+///
+/// Everything up to the full font name and the PostScript name.
+const LAST_KEPT_NAME_ID: u16 = 6;
+
+#[inline]
+pub(crate) fn set_u16(data: &mut [u8], offset: usize, n: u16) {
+    data[offset..offset + 2].copy_from_slice(&n.to_be_bytes());
+}
+
+/// Writes a `head` table.
+///
+/// `index_to_location_format` is set only for TrueType fonts.
+pub(crate) fn head(
+    data: &[u8],
+    index_to_location_format: Option<IndexToLocationFormat>,
+) -> Result<Vec<u8>, SubsetError> {
+    if data.len() < INDEX_TO_LOC_FORMAT_OFFSET + 2 {
+        return Err(SubsetError::MalformedFont);
+    }
+
+    let mut data = data.to_vec();
+    // Will be recalculated by the writer.
+    data[CHECKSUM_ADJUSTMENT_OFFSET..CHECKSUM_ADJUSTMENT_OFFSET + 4].copy_from_slice(&[0; 4]);
+    match index_to_location_format {
+        Some(IndexToLocationFormat::Short) => set_u16(&mut data, INDEX_TO_LOC_FORMAT_OFFSET, 0),
+        Some(IndexToLocationFormat::Long) => set_u16(&mut data, INDEX_TO_LOC_FORMAT_OFFSET, 1),
+        None => {}
+    }
+
+    Ok(data)
+}
+
+/// Writes a `maxp` table.
+pub(crate) fn maxp(data: &[u8], map: &GlyphMap) -> Result<Vec<u8>, SubsetError> {
+    if data.len() < NUM_GLYPHS_OFFSET + 2 {
+        return Err(SubsetError::MalformedFont);
+    }
+
+    let mut data = data.to_vec();
+    set_u16(&mut data, NUM_GLYPHS_OFFSET, map.len());
+    Ok(data)
+}
+
+/// Writes `hhea` and `hmtx` or `vhea` and `vmtx` tables.
+///
+/// Trailing metrics with the same advance are stored as side bearings only.
+pub(crate) fn metrics(
+    header: &[u8],
+    table: hmtx::Table,
+    map: &GlyphMap,
+) -> Result<(Vec<u8>, Vec<u8>), SubsetError> {
+    if header.len() < NUMBER_OF_METRICS_OFFSET + 2 {
+        return Err(SubsetError::MalformedFont);
+    }
+
+    let metrics: Vec<(u16, i16)> = map
+        .old_glyphs()
+        .map(|glyph_id| {
+            (
+                table.advance(glyph_id).unwrap_or(0),
+                table.side_bearing(glyph_id).unwrap_or(0),
+            )
+        })
+        .collect();
+
+    write_metrics(header, &metrics)
+}
+
+/// Writes `hhea` and `hmtx` or `vhea` and `vmtx` tables from advances and side bearings.
+pub(crate) fn write_metrics(
+    header: &[u8],
+    metrics: &[(u16, i16)],
+) -> Result<(Vec<u8>, Vec<u8>), SubsetError> {
+    if header.len() < NUMBER_OF_METRICS_OFFSET + 2 {
+        return Err(SubsetError::MalformedFont);
+    }
+
+    let mut number_of_metrics = metrics.len();
+    while number_of_metrics > 1
+        && metrics[number_of_metrics - 2].0 == metrics[number_of_metrics - 1].0
+    {
+        number_of_metrics -= 1;
+    }
+
+    let mut data = Vec::with_capacity(metrics.len() * 4);
+    for (i, (advance, side_bearing)) in metrics.iter().enumerate() {
+        if i < number_of_metrics {
+            data.extend_from_slice(&advance.to_be_bytes());
+        }
+
+        data.extend_from_slice(&side_bearing.to_be_bytes());
+    }
+
+    let mut header = header.to_vec();
+    set_u16(
+        &mut header,
+        NUMBER_OF_METRICS_OFFSET,
+        number_of_metrics as u16,
+    );
+    Ok((header, data))
+}
+
+/// Writes a `cmap` table.
+///
+/// Contains a segment mapping to delta values subtable for the Basic Multilingual Plane
+/// and a segmented coverage subtable when there are codepoints outside of it.
+/// `codepoints` must be sorted.
+pub(crate) fn cmap(codepoints: &[(u32, GlyphId)]) -> Vec<u8> {
+    let bmp: Vec<(u32, GlyphId)> = codepoints
+        .iter()
+        .copied()
+        .filter(|(c, _)| *c < 0xFFFF)
+        .collect();
+
+    let format4 = segment_mapping_to_delta_values(&bmp);
+    let format12 = if format4.is_none() || bmp.len() != codepoints.len() {
+        Some(segmented_coverage(codepoints))
+    } else {
+        None
+    };
+
+    // Encoding records must be sorted by platform and encoding IDs.
+    let mut records = Vec::new();
+    if format4.is_some() {
+        records.push((0, 3, 4));
+    }
+    if format12.is_some() {
+        records.push((0, 4, 12));
+    }
+    if format4.is_some() {
+        records.push((3, 1, 4));
+    }
+    if format12.is_some() {
+        records.push((3, 10, 12));
+    }
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&0u16.to_be_bytes()); // version
+    data.extend_from_slice(&(records.len() as u16).to_be_bytes());
+
+    let mut offset = 4 + 8 * records.len();
+    let format4_offset = offset;
+    if let Some(ref subtable) = format4 {
+        offset += subtable.len();
+    }
+    let format12_offset = offset;
+
+    for (platform_id, encoding_id, format) in records {
+        let offset = if format == 4 {
+            format4_offset
+        } else {
+            format12_offset
+        };
+        data.extend_from_slice(&(platform_id as u16).to_be_bytes());
+        data.extend_from_slice(&(encoding_id as u16).to_be_bytes());
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+    }
+
+    if let Some(subtable) = format4 {
+        data.extend_from_slice(&subtable);
+    }
+    if let Some(subtable) = format12 {
+        data.extend_from_slice(&subtable);
+    }
+
+    data
+}
+
+/// Groups codepoints that map to consecutive glyphs.
+fn ranges(codepoints: &[(u32, GlyphId)]) -> Vec<(u32, u32, GlyphId)> {
+    let mut ranges: Vec<(u32, u32, GlyphId)> = Vec::new();
+    for (c, glyph_id) in codepoints {
+        if let Some(last) = ranges.last_mut() {
+            if last.1 + 1 == *c && u32::from(last.2 .0) + (*c - last.0) == u32::from(glyph_id.0) {
+                last.1 = *c;
+                continue;
+            }
+        }
+
+        ranges.push((*c, *c, *glyph_id));
+    }
+
+    ranges
+}
+
+/// Writes a format 4 subtable.
+///
+/// Returns `None` when the subtable would exceed 64 KiB.
+fn segment_mapping_to_delta_values(codepoints: &[(u32, GlyphId)]) -> Option<Vec<u8>> {
+    let mut segments: Vec<(u16, u16, u16)> = ranges(codepoints)
+        .into_iter()
+        .map(|(start, end, glyph_id)| {
+            let delta = glyph_id.0.wrapping_sub(start as u16);
+            (start as u16, end as u16, delta)
+        })
+        .collect();
+    // 'The final start code and endCode values must be 0xFFFF.'
+    segments.push((0xFFFF, 0xFFFF, 1));
+
+    let seg_count = u16::try_from(segments.len()).ok()?;
+    let len = u16::try_from(16 + 8 * segments.len()).ok()?;
+    let entry_selector = 15 - seg_count.leading_zeros() as u16;
+    let search_range = 2 << entry_selector;
+
+    let mut data = Vec::with_capacity(usize::from(len));
+    for n in &[
+        4,
+        len,
+        0, // language
+        seg_count * 2,
+        search_range,
+        entry_selector,
+        seg_count * 2 - search_range,
+    ] {
+        data.extend_from_slice(&n.to_be_bytes());
+    }
+
+    for (_, end, _) in &segments {
+        data.extend_from_slice(&end.to_be_bytes());
+    }
+    data.extend_from_slice(&0u16.to_be_bytes()); // reservedPad
+    for (start, _, _) in &segments {
+        data.extend_from_slice(&start.to_be_bytes());
+    }
+    for (_, _, delta) in &segments {
+        data.extend_from_slice(&delta.to_be_bytes());
+    }
+    for _ in &segments {
+        data.extend_from_slice(&0u16.to_be_bytes()); // idRangeOffset
+    }
+
+    Some(data)
+}
+
+/// Writes a format 12 subtable.
+fn segmented_coverage(codepoints: &[(u32, GlyphId)]) -> Vec<u8> {
+    let groups = ranges(codepoints);
+    let len = 16 + 12 * groups.len();
+
+    let mut data = Vec::with_capacity(len);
+    data.extend_from_slice(&12u16.to_be_bytes());
+    data.extend_from_slice(&0u16.to_be_bytes()); // reserved
+    data.extend_from_slice(&(len as u32).to_be_bytes());
+    data.extend_from_slice(&0u32.to_be_bytes()); // language
+    data.extend_from_slice(&(groups.len() as u32).to_be_bytes());
+    for (start, end, glyph_id) in groups {
+        data.extend_from_slice(&start.to_be_bytes());
+        data.extend_from_slice(&end.to_be_bytes());
+        data.extend_from_slice(&u32::from(glyph_id.0).to_be_bytes());
+    }
+
+    data
+}
+
+/// Writes an `OS/2` table.
+///
+/// Updates the first and the last character indices.
+pub(crate) fn os2(data: &[u8], codepoints: &[(u32, GlyphId)]) -> Vec<u8> {
+    let mut data = data.to_vec();
+    if let (Some(first), Some(last)) = (codepoints.first(), codepoints.last()) {
+        if data.len() >= FIRST_CHAR_INDEX_OFFSET + 4 {
+            let first = first.0.min(0xFFFF) as u16;
+            let last = last.0.min(0xFFFF) as u16;
+            set_u16(&mut data, FIRST_CHAR_INDEX_OFFSET, first);
+            set_u16(&mut data, FIRST_CHAR_INDEX_OFFSET + 2, last);
+        }
+    }
+
+    data
+}
+
+/// Writes a `post` table.
+///
+/// Glyph names are preserved for versions 1.0 and 2.0,
+/// otherwise a version 3.0 table without glyph names is written.
+pub(crate) fn post(data: &[u8], map: &GlyphMap) -> Result<Vec<u8>, SubsetError> {
+    let header = data
+        .get(..POST_HEADER_SIZE)
+        .ok_or(SubsetError::MalformedFont)?;
+    let version = Stream::read_at::<u32>(header, 0).ok_or(SubsetError::MalformedFont)?;
+
+    // Name indices, indexed by original glyph IDs, and custom names.
+    let (indices, names): (Vec<u16>, Vec<&[u8]>) = match version {
+        0x00010000 => {
+            // Glyphs are in the standard Macintosh order.
+            let indices = (0..MACINTOSH_NAMES_LEN).collect();
+            (indices, Vec::new())
+        }
+        0x00020000 => {
+            let mut s = Stream::new_at(data, POST_HEADER_SIZE).ok_or(SubsetError::MalformedFont)?;
+            let count = s.read::<u16>().ok_or(SubsetError::MalformedFont)?;
+            let indices = s
+                .read_array16::<u16>(count)
+                .ok_or(SubsetError::MalformedFont)?
+                .into_iter()
+                .collect();
+
+            let mut names = Vec::new();
+            while let Some(len) = s.read::<u8>() {
+                match s.read_bytes(usize::from(len)) {
+                    Some(name) => names.push(name),
+                    None => break,
+                }
+            }
+
+            (indices, names)
+        }
+        _ => {
+            let mut data = header.to_vec();
+            data[..4].copy_from_slice(&0x00030000u32.to_be_bytes());
+            return Ok(data);
+        }
+    };
+
+    let mut new_indices = Vec::with_capacity(usize::from(map.len()));
+    let mut new_names = Vec::new();
+    for glyph_id in map.old_glyphs() {
+        let index = indices.get(usize::from(glyph_id.0)).copied().unwrap_or(0);
+        if index < MACINTOSH_NAMES_LEN {
+            new_indices.push(index);
+        } else {
+            match names.get(usize::from(index - MACINTOSH_NAMES_LEN)) {
+                Some(name) => {
+                    new_indices.push(MACINTOSH_NAMES_LEN + new_names.len() as u16);
+                    new_names.push(*name);
+                }
+                None => new_indices.push(0),
+            }
+        }
+    }
+
+    let mut data = header.to_vec();
+    data[..4].copy_from_slice(&0x00020000u32.to_be_bytes());
+    data.extend_from_slice(&map.len().to_be_bytes());
+    for index in new_indices {
+        data.extend_from_slice(&index.to_be_bytes());
+    }
+    for name in new_names {
+        data.push(name.len() as u8);
+        data.extend_from_slice(name);
+    }
+
+    Ok(data)
+}
+
+/// Writes a `name` table.
+///
+/// Keeps names up to the PostScript name and names from `name_ids`.
+/// Language-tag records are dropped, so a version 0 table is written.
+pub(crate) fn name(data: &[u8], name_ids: &BTreeSet<u16>) -> Result<Vec<u8>, SubsetError> {
+    let mut s = Stream::new(data);
+    let version = s.read::<u16>().ok_or(SubsetError::MalformedFont)?;
+    let count = s.read::<u16>().ok_or(SubsetError::MalformedFont)?;
+    let storage_offset = s.read::<u16>().ok_or(SubsetError::MalformedFont)?;
+    let records = s
+        .read_bytes(usize::from(count) * NAME_RECORD_SIZE)
+        .ok_or(SubsetError::MalformedFont)?;
+    let storage = data
+        .get(usize::from(storage_offset)..)
+        .ok_or(SubsetError::MalformedFont)?;
+
+    let mut kept = Vec::new();
+    for record in records.chunks(NAME_RECORD_SIZE) {
+        let mut s = Stream::new(record);
+        let header = s.read_bytes(6).ok_or(SubsetError::MalformedFont)?;
+        let language_id = Stream::read_at::<u16>(header, 4).ok_or(SubsetError::MalformedFont)?;
+        let name_id = s.read::<u16>().ok_or(SubsetError::MalformedFont)?;
+        let length = s.read::<u16>().ok_or(SubsetError::MalformedFont)?;
+        let offset = s.read::<u16>().ok_or(SubsetError::MalformedFont)?;
+
+        // Language IDs starting from 0x8000 reference language-tag records.
+        if version != 0 && language_id >= 0x8000 {
+            continue;
+        }
+
+        if name_id > LAST_KEPT_NAME_ID && !name_ids.contains(&name_id) {
+            continue;
+        }
+
+        let start = usize::from(offset);
+        let string = storage
+            .get(start..start + usize::from(length))
+            .ok_or(SubsetError::MalformedFont)?;
+        kept.push((header, name_id, string));
+    }
+
+    let storage_offset = 6 + kept.len() * NAME_RECORD_SIZE;
+    let mut data = Vec::new();
+    data.extend_from_slice(&0u16.to_be_bytes()); // version
+    data.extend_from_slice(&(kept.len() as u16).to_be_bytes());
+    data.extend_from_slice(
+        &u16::try_from(storage_offset)
+            .map_err(|_| SubsetError::Overflow)?
+            .to_be_bytes(),
+    );
+
+    let mut storage = Vec::new();
+    for (header, name_id, string) in kept {
+        let offset = u16::try_from(storage.len()).map_err(|_| SubsetError::Overflow)?;
+        data.extend_from_slice(header);
+        data.extend_from_slice(&name_id.to_be_bytes());
+        data.extend_from_slice(&(string.len() as u16).to_be_bytes());
+        data.extend_from_slice(&offset.to_be_bytes());
+        storage.extend_from_slice(string);
+    }
+
+    data.extend_from_slice(&storage);
+    Ok(data)
+}
@@ -3,8 +3,13 @@
 
 use core::convert::TryFrom;
 
-use crate::parser::{FromData, LazyArray16, Stream};
-use crate::NormalizedCoordinate;
+#[cfg(not(feature = "std"))]
+use core_maths::CoreFloat;
+
+use crate::delta_set::DeltaSetIndexMap;
+use crate::parser::{FromData, LazyArray16, Offset, Offset32, Stream};
+use crate::var_store::ItemVariationStore;
+use crate::{NormalizedCoordinate, MAX_VAR_COORDS};
 
 /// An axis value map.
 #[derive(Clone, Copy, Debug)]
@@ -87,11 +92,16 @@ impl<'a> Iterator for SegmentMapsIter<'a> {
 
 /// An [Axis Variations Table](
 /// https://docs.microsoft.com/en-us/typography/opentype/spec/avar).
+///
+/// Both version 1 and version 2 are supported.
 #[derive(Clone, Copy, Debug)]
 pub struct Table<'a> {
     /// The segment maps array — one segment map for each axis
     /// in the order of axes specified in the `fvar` table.
     pub segment_maps: SegmentMaps<'a>,
+    // avar2 only.
+    index_map: Option<DeltaSetIndexMap<'a>>,
+    variation_store: Option<ItemVariationStore<'a>>,
 }
 
 impl<'a> Table<'a> {
@@ -99,31 +109,102 @@ impl<'a> Table<'a> {
     pub fn parse(data: &'a [u8]) -> Option<Self> {
         let mut s = Stream::new(data);
 
-        let version = s.read::<u32>()?;
-        if version != 0x00010000 {
+        let major_version = s.read::<u16>()?;
+        let minor_version = s.read::<u16>()?;
+        if !(major_version == 1 || major_version == 2) || minor_version != 0 {
             return None;
         }
 
         s.skip::<u16>(); // reserved
+
+        // TODO: check that `axisCount` is the same as in `fvar`?
+        let count = s.read::<u16>()?;
+        let segment_maps = SegmentMaps {
+            count,
+            data: s.tail()?,
+        };
+
+        let mut index_map = None;
+        let mut variation_store = None;
+        if major_version == 2 {
+            // Skip segment maps.
+            for _ in 0..count {
+                let maps_count = s.read::<u16>()?;
+                s.advance_checked(usize::from(maps_count) * AxisValueMap::SIZE)?;
+            }
+
+            if let Some(offset) = s.read::<Option<Offset32>>()? {
+                index_map = Some(DeltaSetIndexMap::new(data.get(offset.to_usize()..)?));
+            }
+
+            if let Some(offset) = s.read::<Option<Offset32>>()? {
+                let s = Stream::new_at(data, offset.to_usize())?;
+                variation_store = Some(ItemVariationStore::parse(s)?);
+            }
+        }
+
         Some(Self {
-            segment_maps: SegmentMaps {
-                // TODO: check that `axisCount` is the same as in `fvar`?
-                count: s.read::<u16>()?,
-                data: s.tail()?,
-            },
+            segment_maps,
+            index_map,
+            variation_store,
         })
     }
 
     /// Maps coordinates.
+    ///
+    /// `coordinates` must be normalized using the `fvar` table.
+    /// In case of version 2, the segment maps are applied first,
+    /// and then cross-axis deltas from the item variation store.
     pub fn map_coordinates(&self, coordinates: &mut [NormalizedCoordinate]) -> Option<()> {
         if usize::from(self.segment_maps.count) != coordinates.len() {
             return None;
         }
 
-        for (map, coord) in self.segment_maps.into_iter().zip(coordinates) {
+        for (map, coord) in self.segment_maps.into_iter().zip(coordinates.iter_mut()) {
             *coord = NormalizedCoordinate::from(map_value(&map, coord.0)?);
         }
 
+        if let Some(ref variation_store) = self.variation_store {
+            self.apply_variation_store(variation_store, coordinates)?;
+        }
+
+        Some(())
+    }
+
+    fn apply_variation_store(
+        &self,
+        variation_store: &ItemVariationStore,
+        coordinates: &mut [NormalizedCoordinate],
+    ) -> Option<()> {
+        // Deltas for all axes are evaluated at coordinates mapped by the segment maps,
+        // so we have to store a copy of them.
+        let mut mapped = [NormalizedCoordinate::default(); MAX_VAR_COORDS];
+        let mapped = mapped.get_mut(..coordinates.len())?;
+        mapped.copy_from_slice(coordinates);
+
+        for (i, coord) in coordinates.iter_mut().enumerate() {
+            let index = u32::try_from(i).ok()?;
+            let (outer_index, inner_index) = match self.index_map {
+                Some(map) => match map.map(index) {
+                    Some(v) => v,
+                    None => continue,
+                },
+                // Without a map, the axis index is used directly.
+                None => ((index >> 16) as u16, (index & 0xFFFF) as u16),
+            };
+
+            // 0xFFFF/0xFFFF is `NO_VARIATION_INDEX`.
+            if outer_index == 0xFFFF && inner_index == 0xFFFF {
+                continue;
+            }
+
+            let delta = variation_store
+                .parse_delta(outer_index, inner_index, mapped)
+                .unwrap_or(0.0);
+            let value = i32::from(coord.0) + delta.round() as i32;
+            *coord = NormalizedCoordinate::from(value.clamp(-16384, 16384) as i16);
+        }
+
         Some(())
     }
 }
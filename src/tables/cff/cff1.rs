@@ -18,7 +18,10 @@ use super::encoding::{parse_encoding, Encoding, STANDARD_ENCODING};
 use super::index::{parse_index, skip_index, Index};
 #[cfg(feature = "glyph-names")]
 use super::std_names::STANDARD_NAMES;
-use super::{calc_subroutine_bias, conv_subroutine_index, Builder, CFFError, IsEven, StringId};
+use super::{
+    calc_subroutine_bias, conv_subroutine_index, Builder, CFFError, IsEven, NoHints, StringId,
+};
+pub use super::{BlueZones, HintingBuilder, HintingParameters};
 use crate::parser::{LazyArray16, NumFrom, Stream, TryNumFrom};
 use crate::{DummyOutline, GlyphId, OutlineBuilder, Rect, RectF};
 
@@ -106,6 +109,7 @@ pub(crate) enum FontKind<'a> {
 
 #[derive(Clone, Copy, Default, Debug)]
 pub(crate) struct SIDMetadata<'a> {
+    private_dict: &'a [u8],
     local_subrs: Index<'a>,
     /// Can be zero.
     default_width: f32,
@@ -285,6 +289,7 @@ struct PrivateDict {
     local_subroutines_offset: Option<usize>,
     default_width: Option<f32>,
     nominal_width: Option<f32>,
+    hinting: HintingParameters,
 }
 
 fn parse_private_dict(data: &[u8]) -> PrivateDict {
@@ -298,6 +303,10 @@ fn parse_private_dict(data: &[u8]) -> PrivateDict {
             dict.default_width = dict_parser.parse_number().map(|n| n as f32);
         } else if operator.get() == private_dict_operator::NOMINAL_WIDTH {
             dict.nominal_width = dict_parser.parse_number().map(|n| n as f32);
+        } else if HintingParameters::is_hinting_operator(operator.get())
+            && dict_parser.parse_operands().is_some()
+        {
+            dict.hinting.set(operator.get(), dict_parser.operands());
         }
     }
 
@@ -327,9 +336,7 @@ fn parse_cid_local_subrs<'a>(
     glyph_id: GlyphId,
     cid: &CIDMetadata,
 ) -> Option<Index<'a>> {
-    let font_dict_index = cid.fd_select.font_dict_index(glyph_id)?;
-    let font_dict_data = cid.fd_array.get(u32::from(font_dict_index))?;
-    let private_dict_range = parse_font_dict(font_dict_data)?;
+    let private_dict_range = cid_private_dict_range(glyph_id, cid)?;
     let private_dict_data = data.get(private_dict_range.clone())?;
     let private_dict = parse_private_dict(private_dict_data);
     let subroutines_offset = private_dict.local_subroutines_offset?;
@@ -342,6 +349,12 @@ fn parse_cid_local_subrs<'a>(
     parse_index::<u16>(&mut s)
 }
 
+fn cid_private_dict_range(glyph_id: GlyphId, cid: &CIDMetadata) -> Option<Range<usize>> {
+    let font_dict_index = cid.fd_select.font_dict_index(glyph_id)?;
+    let font_dict_data = cid.fd_array.get(u32::from(font_dict_index))?;
+    parse_font_dict(font_dict_data)
+}
+
 struct CharStringParserContext<'a> {
     metadata: &'a Table<'a>,
     width: Option<f32>,
@@ -357,7 +370,7 @@ fn parse_char_string(
     metadata: &Table,
     glyph_id: GlyphId,
     width_only: bool,
-    builder: &mut dyn OutlineBuilder,
+    builder: &mut dyn HintingBuilder,
 ) -> Result<(Rect, Option<f32>), CFFError> {
     let local_subrs = match metadata.kind {
         FontKind::SID(ref sid) => Some(sid.local_subrs),
@@ -438,16 +451,16 @@ fn _parse_char_string(
                 // x dx {dxa dxb}* vstemhm
 
                 // If the stack length is uneven, than the first value is a `width`.
-                let len = if p.stack.len().is_odd() && ctx.width.is_none() {
+                let offset = if p.stack.len().is_odd() && ctx.width.is_none() {
                     ctx.width = Some(p.stack.at(0));
-                    p.stack.len() - 1
+                    1
                 } else {
-                    p.stack.len()
+                    0
                 };
 
-                ctx.stems_len += len as u32 >> 1;
-
-                // We are ignoring the hint operators.
+                let horizontal =
+                    op == operator::HORIZONTAL_STEM || op == operator::HORIZONTAL_STEM_HINT_MASK;
+                ctx.stems_len += p.parse_stems(offset, horizontal);
                 p.stack.clear();
             }
             operator::VERTICAL_MOVE_TO => {
@@ -578,22 +591,27 @@ fn _parse_char_string(
                 break;
             }
             operator::HINT_MASK | operator::COUNTER_MASK => {
-                let mut len = p.stack.len();
-
-                // We are ignoring the hint operators.
-                p.stack.clear();
-
                 // If the stack length is uneven, than the first value is a `width`.
-                if len.is_odd() {
-                    len -= 1;
+                let mut offset = 0;
+                if p.stack.len().is_odd() {
+                    offset = 1;
                     if ctx.width.is_none() {
                         ctx.width = Some(p.stack.at(0));
                     }
                 }
 
-                ctx.stems_len += len as u32 >> 1;
+                // Values on the stack are implicit `vstem` hints.
+                ctx.stems_len += p.parse_stems(offset, false);
+                p.stack.clear();
 
-                s.advance(usize::num_from((ctx.stems_len + 7) >> 3));
+                let mask = s
+                    .read_bytes(usize::num_from((ctx.stems_len + 7) >> 3))
+                    .ok_or(CFFError::ReadOutOfBounds)?;
+                if op == operator::HINT_MASK {
+                    p.builder.hint_mask(mask);
+                } else {
+                    p.builder.counter_mask(mask);
+                }
             }
             operator::MOVE_TO => {
                 let mut i = 0;
@@ -689,7 +707,7 @@ fn _parse_char_string(
     Ok(())
 }
 
-fn seac_code_to_glyph_id(charset: &Charset, n: f32) -> Option<GlyphId> {
+pub(crate) fn seac_code_to_glyph_id(charset: &Charset, n: f32) -> Option<GlyphId> {
     let code = u8::try_num_from(n)?;
 
     let sid = STANDARD_ENCODING[usize::from(code)];
@@ -710,7 +728,7 @@ fn seac_code_to_glyph_id(charset: &Charset, n: f32) -> Option<GlyphId> {
 }
 
 #[derive(Clone, Copy, Debug)]
-enum FDSelect<'a> {
+pub(crate) enum FDSelect<'a> {
     Format0(LazyArray16<'a, u8>),
     Format3(&'a [u8]), // It's easier to parse it in-place.
 }
@@ -722,7 +740,7 @@ impl Default for FDSelect<'_> {
 }
 
 impl FDSelect<'_> {
-    fn font_dict_index(&self, glyph_id: GlyphId) -> Option<u8> {
+    pub(crate) fn font_dict_index(&self, glyph_id: GlyphId) -> Option<u8> {
         match self {
             FDSelect::Format0(ref array) => array.get(glyph_id.0),
             FDSelect::Format3(data) => {
@@ -757,7 +775,10 @@ impl FDSelect<'_> {
     }
 }
 
-fn parse_fd_select<'a>(number_of_glyphs: u16, s: &mut Stream<'a>) -> Option<FDSelect<'a>> {
+pub(crate) fn parse_fd_select<'a>(
+    number_of_glyphs: u16,
+    s: &mut Stream<'a>,
+) -> Option<FDSelect<'a>> {
     let format = s.read::<u8>()?;
     match format {
         0 => Some(FDSelect::Format0(s.read_array16::<u8>(number_of_glyphs)?)),
@@ -780,6 +801,11 @@ fn parse_sid_metadata<'a>(
         return Some(FontKind::SID(metadata));
     };
 
+    metadata.private_dict = top_dict
+        .private_dict_range
+        .clone()
+        .and_then(|range| data.get(range))
+        .unwrap_or_default();
     metadata.default_width = private_dict.default_width.unwrap_or(0.0);
     metadata.nominal_width = private_dict.nominal_width.unwrap_or(0.0);
 
@@ -799,7 +825,11 @@ fn parse_sid_metadata<'a>(
     Some(FontKind::SID(metadata))
 }
 
-fn parse_cid_metadata(data: &[u8], top_dict: TopDict, number_of_glyphs: u16) -> Option<FontKind> {
+fn parse_cid_metadata(
+    data: &[u8],
+    top_dict: TopDict,
+    number_of_glyphs: u16,
+) -> Option<FontKind<'_>> {
     let (charset_offset, fd_array_offset, fd_select_offset) = match (
         top_dict.charset_offset,
         top_dict.fd_array_offset,
@@ -941,6 +971,12 @@ impl<'a> Table<'a> {
         self.number_of_glyphs.get()
     }
 
+    /// Returns a raw charstring.
+    #[cfg(feature = "validate")]
+    pub(crate) fn char_string(&self, glyph_id: GlyphId) -> Option<&'a [u8]> {
+        self.char_strings.get(u32::from(glyph_id.0))
+    }
+
     /// Returns a font transformation matrix.
     #[inline]
     pub fn matrix(&self) -> Matrix {
@@ -952,6 +988,21 @@ impl<'a> Table<'a> {
         &self,
         glyph_id: GlyphId,
         builder: &mut dyn OutlineBuilder,
+    ) -> Result<Rect, CFFError> {
+        let data = self
+            .char_strings
+            .get(u32::from(glyph_id.0))
+            .ok_or(CFFError::NoGlyph)?;
+        parse_char_string(data, self, glyph_id, false, &mut NoHints(builder)).map(|v| v.0)
+    }
+
+    /// Outlines a glyph and reports its stem hints.
+    ///
+    /// Hints of `seac` components are reported as is.
+    pub fn outline_with_hints(
+        &self,
+        glyph_id: GlyphId,
+        builder: &mut dyn HintingBuilder,
     ) -> Result<Rect, CFFError> {
         let data = self
             .char_strings
@@ -960,6 +1011,25 @@ impl<'a> Table<'a> {
         parse_char_string(data, self, glyph_id, false, builder).map(|v| v.0)
     }
 
+    /// Returns hinting parameters from the glyph's Private DICT.
+    ///
+    /// Glyphs of a CID-keyed font can use different Private DICTs.
+    /// Returns `None` when there is no Private DICT.
+    pub fn hinting_parameters(&self, glyph_id: GlyphId) -> Option<HintingParameters> {
+        let data = match self.kind {
+            FontKind::SID(ref sid) => sid.private_dict,
+            FontKind::CID(ref cid) => self
+                .table_data
+                .get(cid_private_dict_range(glyph_id, cid)?)?,
+        };
+
+        if data.is_empty() {
+            return None;
+        }
+
+        Some(parse_private_dict(data).hinting)
+    }
+
     /// Resolves a Glyph ID for a code point.
     ///
     /// Similar to [`Face::glyph_index`](crate::Face::glyph_index) but 8bit
@@ -990,7 +1060,8 @@ impl<'a> Table<'a> {
             FontKind::SID(ref sid) => {
                 let data = self.char_strings.get(u32::from(glyph_id.0))?;
                 let (_, width) =
-                    parse_char_string(data, self, glyph_id, true, &mut DummyOutline).ok()?;
+                    parse_char_string(data, self, glyph_id, true, &mut NoHints(&mut DummyOutline))
+                        .ok()?;
                 let width = width
                     .map(|w| sid.nominal_width + w)
                     .unwrap_or(sid.default_width);
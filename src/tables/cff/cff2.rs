@@ -7,10 +7,12 @@ use core::convert::TryFrom;
 use core::ops::Range;
 
 use super::argstack::ArgumentsStack;
+use super::cff1::{parse_fd_select, FDSelect};
 use super::charstring::CharStringParser;
 use super::dict::DictionaryParser;
 use super::index::{parse_index, Index};
-use super::{calc_subroutine_bias, conv_subroutine_index, Builder, CFFError};
+use super::{calc_subroutine_bias, conv_subroutine_index, Builder, CFFError, NoHints};
+pub use super::{BlueZones, HintingBuilder, HintingParameters};
 use crate::parser::{NumFrom, Stream, TryNumFrom};
 use crate::var_store::*;
 use crate::{GlyphId, NormalizedCoordinate, OutlineBuilder, Rect, RectF};
@@ -63,6 +65,7 @@ mod top_dict_operator {
     pub const CHAR_STRINGS_OFFSET: u16 = 17;
     pub const VARIATION_STORE_OFFSET: u16 = 24;
     pub const FONT_DICT_INDEX_OFFSET: u16 = 1236;
+    pub const FONT_DICT_SELECT_OFFSET: u16 = 1237;
 }
 
 // https://docs.microsoft.com/en-us/typography/opentype/spec/cff2#table-10-font-dict-operator-entries
@@ -73,12 +76,18 @@ mod font_dict_operator {
 // https://docs.microsoft.com/en-us/typography/opentype/spec/cff2#table-16-private-dict-operators
 mod private_dict_operator {
     pub const LOCAL_SUBROUTINES_OFFSET: u16 = 19;
+    pub const VS_INDEX: u16 = 22;
+    pub const BLEND: u16 = 23;
 }
 
+// BlueValues is the longest hinting operator.
+const MAX_HINTING_OPERANDS: usize = 14;
+
 #[derive(Clone, Copy, Default)]
 struct TopDictData {
     char_strings_offset: usize,
     font_dict_index_offset: Option<usize>,
+    font_dict_select_offset: Option<usize>,
     variation_store_offset: Option<usize>,
 }
 
@@ -92,6 +101,8 @@ fn parse_top_dict(data: &[u8]) -> Option<TopDictData> {
             dict_data.char_strings_offset = dict_parser.parse_offset()?;
         } else if operator.get() == top_dict_operator::FONT_DICT_INDEX_OFFSET {
             dict_data.font_dict_index_offset = dict_parser.parse_offset();
+        } else if operator.get() == top_dict_operator::FONT_DICT_SELECT_OFFSET {
+            dict_data.font_dict_select_offset = dict_parser.parse_offset();
         } else if operator.get() == top_dict_operator::VARIATION_STORE_OFFSET {
             dict_data.variation_store_offset = dict_parser.parse_offset();
         }
@@ -149,6 +160,73 @@ fn parse_private_dict(data: &[u8]) -> Option<usize> {
     subroutines_offset
 }
 
+fn parse_hinting_parameters(data: &[u8], store: &ItemVariationStore) -> HintingParameters {
+    let mut params = HintingParameters::default();
+
+    // Default values of blended operands. Deltas are ignored.
+    let mut blended = [0.0; MAX_HINTING_OPERANDS];
+    let mut blended_len = 0;
+
+    // The number of regions used by `blend`, which depends on `vsindex`.
+    let mut regions = store.region_indices(0).map(|v| usize::from(v.len()));
+
+    let mut operands_buffer = [0.0; MAX_OPERANDS_LEN];
+    let mut dict_parser = DictionaryParser::new(data, &mut operands_buffer);
+    while let Some(operator) = dict_parser.parse_next() {
+        let operator = operator.get();
+        if operator == private_dict_operator::VS_INDEX {
+            if dict_parser.parse_operands().is_some() {
+                if let Some(index) = dict_parser.operands().first() {
+                    regions = u16::try_from(*index as i32)
+                        .ok()
+                        .and_then(|index| store.region_indices(index))
+                        .map(|v| usize::from(v.len()));
+                }
+            }
+        } else if operator == private_dict_operator::BLEND {
+            // ..., num(0)..num(n-1), deltas(0)..deltas(n * regions - 1), n blend
+            // Operands preceding the blended ones are kept as is.
+            if let (Some(regions), Some(_)) = (regions, dict_parser.parse_operands()) {
+                let operands = dict_parser.operands();
+                let n = operands.last().map(|n| *n as usize).unwrap_or(0);
+                let start = n
+                    .checked_mul(regions + 1)
+                    .and_then(|len| len.checked_add(1))
+                    .and_then(|len| operands.len().checked_sub(len));
+                if let Some(start) = start {
+                    for value in &operands[..start + n] {
+                        if blended_len < MAX_HINTING_OPERANDS {
+                            blended[blended_len] = *value;
+                            blended_len += 1;
+                        }
+                    }
+                }
+            }
+
+            continue;
+        }
+
+        if HintingParameters::is_hinting_operator(operator)
+            && dict_parser.parse_operands().is_some()
+        {
+            let mut operands = blended;
+            let mut len = blended_len;
+            for value in dict_parser.operands() {
+                if len < MAX_HINTING_OPERANDS {
+                    operands[len] = *value;
+                    len += 1;
+                }
+            }
+
+            params.set(operator, &operands[..len]);
+        }
+
+        blended_len = 0;
+    }
+
+    params
+}
+
 /// CFF2 allows up to 65535 scalars, but an average font will have 3-5.
 /// So 64 is more than enough.
 const SCALARS_MAX: u8 = 64;
@@ -233,7 +311,7 @@ fn parse_char_string(
     data: &[u8],
     metadata: &Table,
     coordinates: &[NormalizedCoordinate],
-    builder: &mut dyn OutlineBuilder,
+    builder: &mut dyn HintingBuilder,
 ) -> Result<Rect, CFFError> {
     let mut ctx = CharStringParserContext {
         metadata,
@@ -302,9 +380,9 @@ fn _parse_char_string(
                 // y dy {dya dyb}* hstemhm
                 // x dx {dxa dxb}* vstemhm
 
-                ctx.stems_len += p.stack.len() as u32 >> 1;
-
-                // We are ignoring the hint operators.
+                let horizontal =
+                    op == operator::HORIZONTAL_STEM || op == operator::HORIZONTAL_STEM_HINT_MASK;
+                ctx.stems_len += p.parse_stems(0, horizontal);
                 p.stack.clear();
             }
             operator::VERTICAL_MOVE_TO => {
@@ -397,11 +475,18 @@ fn _parse_char_string(
                 }
             }
             operator::HINT_MASK | operator::COUNTER_MASK => {
-                ctx.stems_len += p.stack.len() as u32 >> 1;
-                s.advance(usize::num_from((ctx.stems_len + 7) >> 3));
-
-                // We are ignoring the hint operators.
+                // Values on the stack are implicit `vstem` hints.
+                ctx.stems_len += p.parse_stems(0, false);
                 p.stack.clear();
+
+                let mask = s
+                    .read_bytes(usize::num_from((ctx.stems_len + 7) >> 3))
+                    .ok_or(CFFError::ReadOutOfBounds)?;
+                if op == operator::HINT_MASK {
+                    p.builder.hint_mask(mask);
+                } else {
+                    p.builder.counter_mask(mask);
+                }
             }
             operator::MOVE_TO => {
                 p.parse_move_to(0)?;
@@ -471,6 +556,10 @@ fn _parse_char_string(
 /// https://docs.microsoft.com/en-us/typography/opentype/spec/cff2).
 #[derive(Clone, Copy, Default)]
 pub struct Table<'a> {
+    table_data: &'a [u8],
+    private_dict: &'a [u8],
+    font_dicts: Index<'a>,
+    fd_select: Option<FDSelect<'a>>,
     global_subrs: Index<'a>,
     local_subrs: Index<'a>,
     char_strings: Index<'a>,
@@ -500,7 +589,10 @@ impl<'a> Table<'a> {
         let top_dict_data = s.read_bytes(usize::from(top_dict_length))?;
         let top_dict = parse_top_dict(top_dict_data)?;
 
-        let mut metadata = Self::default();
+        let mut metadata = Self {
+            table_data: data,
+            ..Self::default()
+        };
 
         // Parse Global Subroutines INDEX.
         metadata.global_subrs = parse_index::<u32>(&mut s)?;
@@ -519,10 +611,15 @@ impl<'a> Table<'a> {
         // TODO: simplify
         if let Some(offset) = top_dict.font_dict_index_offset {
             let mut s = Stream::new_at(data, offset)?;
-            'outer: for font_dict_data in parse_index::<u32>(&mut s)? {
+            metadata.font_dicts = parse_index::<u32>(&mut s)?;
+            'outer: for font_dict_data in metadata.font_dicts {
                 if let Some(private_dict_range) = parse_font_dict(font_dict_data) {
                     // 'Private DICT size and offset, from start of the CFF2 table.'
                     let private_dict_data = data.get(private_dict_range.clone())?;
+                    if metadata.private_dict.is_empty() {
+                        metadata.private_dict = private_dict_data;
+                    }
+
                     if let Some(subroutines_offset) = parse_private_dict(private_dict_data) {
                         // 'The local subroutines offset is relative to the beginning
                         // of the Private DICT data.'
@@ -539,6 +636,14 @@ impl<'a> Table<'a> {
             }
         }
 
+        // 'Only present if there are multiple Font DICTs.'
+        if let Some(offset) = top_dict.font_dict_select_offset {
+            let mut s = Stream::new_at(data, offset)?;
+            metadata.fd_select = u16::try_from(metadata.char_strings.len())
+                .ok()
+                .and_then(|number_of_glyphs| parse_fd_select(number_of_glyphs, &mut s));
+        }
+
         Some(metadata)
     }
 
@@ -548,6 +653,20 @@ impl<'a> Table<'a> {
         coordinates: &[NormalizedCoordinate],
         glyph_id: GlyphId,
         builder: &mut dyn OutlineBuilder,
+    ) -> Result<Rect, CFFError> {
+        let data = self
+            .char_strings
+            .get(u32::from(glyph_id.0))
+            .ok_or(CFFError::NoGlyph)?;
+        parse_char_string(data, self, coordinates, &mut NoHints(builder))
+    }
+
+    /// Outlines a glyph and reports its stem hints.
+    pub fn outline_with_hints(
+        &self,
+        coordinates: &[NormalizedCoordinate],
+        glyph_id: GlyphId,
+        builder: &mut dyn HintingBuilder,
     ) -> Result<Rect, CFFError> {
         let data = self
             .char_strings
@@ -555,6 +674,51 @@ impl<'a> Table<'a> {
             .ok_or(CFFError::NoGlyph)?;
         parse_char_string(data, self, coordinates, builder)
     }
+
+    /// Returns the first Private DICT data.
+    #[cfg(feature = "instance")]
+    pub(crate) fn private_dict_data(&self) -> &'a [u8] {
+        self.private_dict
+    }
+
+    /// Returns the item variation store used by `blend` operators.
+    #[cfg(feature = "instance")]
+    pub(crate) fn variation_store(&self) -> &ItemVariationStore<'a> {
+        &self.item_variation_store
+    }
+
+    /// Returns the number of glyphs in the CharStrings INDEX.
+    #[cfg(feature = "validate")]
+    pub(crate) fn number_of_glyphs(&self) -> u32 {
+        self.char_strings.len()
+    }
+
+    /// Returns a raw charstring.
+    #[cfg(feature = "validate")]
+    pub(crate) fn char_string(&self, glyph_id: GlyphId) -> Option<&'a [u8]> {
+        self.char_strings.get(u32::from(glyph_id.0))
+    }
+
+    /// Returns hinting parameters from the glyph's Private DICT.
+    ///
+    /// The Private DICT is selected via FDSelect, which must use format 0 or 3.
+    /// Blended values are returned at the default location.
+    /// Returns `None` when there is no Private DICT.
+    pub fn hinting_parameters(&self, glyph_id: GlyphId) -> Option<HintingParameters> {
+        let font_dict_index = match self.fd_select {
+            Some(ref fd_select) => fd_select.font_dict_index(glyph_id)?,
+            None if self.font_dicts.len() == 1 => 0,
+            None => return None,
+        };
+
+        let font_dict_data = self.font_dicts.get(u32::from(font_dict_index))?;
+        let data = self.table_data.get(parse_font_dict(font_dict_data)?)?;
+        if data.is_empty() {
+            return None;
+        }
+
+        Some(parse_hinting_parameters(data, &self.item_variation_store))
+    }
 }
 
 impl core::fmt::Debug for Table<'_> {
@@ -562,3 +726,77 @@ impl core::fmt::Debug for Table<'_> {
         write!(f, "Table {{ ... }}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variation_store(data: &[u8]) -> ItemVariationStore<'_> {
+        ItemVariationStore::parse(Stream::new(data)).unwrap()
+    }
+
+    #[test]
+    fn blended_hinting_parameters() {
+        let store = &[
+            0x00, 0x01, // format: 1
+            0x00, 0x00, 0x00, 0x0C, // region list offset: 12
+            0x00, 0x01, // item variation data count: 1
+            0x00, 0x00, 0x00, 0x16, // item variation data offset: 22
+            // Region list
+            0x00, 0x01, // axis count: 1
+            0x00, 0x01, // region count: 1
+            0x00, 0x00, 0x40, 0x00, 0x40, 0x00, // region 0: 0 1 1
+            // Item variation data
+            0x00, 0x00, // item count: 0
+            0x00, 0x00, // short delta count: 0
+            0x00, 0x01, // region index count: 1
+            0x00, 0x00, // region index: 0
+        ];
+
+        let data = &[
+            0x7C, 0x9A, 0xF8, 0x88, 0x9A, // -15 15 500 15
+            0x8C, 0x8C, 0x8C, 0x8C, // deltas: 1 1 1 1
+            0x8F, 0x17, // 4 blend
+            0x06, // BlueValues
+            0xDB, 0x0B, // 80 StdVW
+        ];
+
+        let params = parse_hinting_parameters(data, &variation_store(store));
+        assert_eq!(params.blue_values.get(0), Some((-15.0, 0.0)));
+        assert_eq!(params.blue_values.get(1), Some((500.0, 515.0)));
+        assert_eq!(params.std_vw, Some(80.0));
+        assert_eq!(params.std_hw, None);
+    }
+
+    #[test]
+    fn blended_hinting_parameters_with_prefix() {
+        let store = &[
+            0x00, 0x01, // format: 1
+            0x00, 0x00, 0x00, 0x0C, // region list offset: 12
+            0x00, 0x01, // item variation data count: 1
+            0x00, 0x00, 0x00, 0x1C, // item variation data offset: 28
+            // Region list
+            0x00, 0x01, // axis count: 1
+            0x00, 0x02, // region count: 2
+            0x00, 0x00, 0x40, 0x00, 0x40, 0x00, // region 0: 0 1 1
+            0xC0, 0x00, 0xC0, 0x00, 0x00, 0x00, // region 1: -1 -1 0
+            // Item variation data
+            0x00, 0x00, // item count: 0
+            0x00, 0x00, // short delta count: 0
+            0x00, 0x02, // region index count: 2
+            0x00, 0x00, 0x00, 0x01, // region indices: 0 1
+        ];
+
+        let data = &[
+            0x7C, 0x9A, // -15 15
+            0xF8, 0x88, 0x9A, // 500 15
+            0x8C, 0x8C, 0x8C, 0x8C, // deltas: 1 1 1 1
+            0x8D, 0x17, // 2 blend
+            0x06, // BlueValues
+        ];
+
+        let params = parse_hinting_parameters(data, &variation_store(store));
+        assert_eq!(params.blue_values.get(0), Some((-15.0, 0.0)));
+        assert_eq!(params.blue_values.get(1), Some((500.0, 515.0)));
+    }
+}
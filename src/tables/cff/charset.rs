@@ -5,7 +5,7 @@ use core::num::NonZeroU16;
 
 /// The Expert Encoding conversion as defined in the Adobe Technical Note #5176 Appendix C.
 #[rustfmt::skip]
-#[cfg(feature = "glyph-names")]
+#[cfg(any(feature = "glyph-names", feature = "subset"))]
 const EXPERT_ENCODING: &[u16] = &[
       0,    1,  229,  230,  231,  232,  233,  234,  235,  236,  237,  238,   13,   14,   15,   99,
     239,  240,  241,  242,  243,  244,  245,  246,  247,  248,   27,   28,  249,  250,  251,  252,
@@ -22,7 +22,7 @@ const EXPERT_ENCODING: &[u16] = &[
 
 /// The Expert Subset Encoding conversion as defined in the Adobe Technical Note #5176 Appendix C.
 #[rustfmt::skip]
-#[cfg(feature = "glyph-names")]
+#[cfg(any(feature = "glyph-names", feature = "subset"))]
 const EXPERT_SUBSET_ENCODING: &[u16] = &[
       0,    1,  231,  232,  235,  236,  237,  238,   13,   14,   15,   99,  239,  240,  241,  242,
     243,  244,  245,  246,  247,  248,   27,   28,  249,  250,  251,  253,  254,  255,  256,  257,
@@ -127,7 +127,7 @@ impl Charset<'_> {
         }
     }
 
-    #[cfg(feature = "glyph-names")]
+    #[cfg(any(feature = "glyph-names", feature = "subset"))]
     pub fn gid_to_sid(&self, gid: GlyphId) -> Option<StringId> {
         match self {
             Charset::ISOAdobe => {
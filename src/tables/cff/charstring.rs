@@ -13,6 +13,24 @@ pub(crate) struct CharStringParser<'a> {
 }
 
 impl CharStringParser<'_> {
+    /// Reports stem hints starting from `offset` and returns their number.
+    #[inline]
+    pub fn parse_stems(&mut self, offset: usize, horizontal: bool) -> u32 {
+        // y dy {dya dyb}*
+        // Each stem is relative to the previous one, and the first one is relative to zero.
+        let mut position = 0.0;
+        let mut i = offset;
+        while i + 1 < self.stack.len() {
+            position += self.stack.at(i);
+            let len = self.stack.at(i + 1);
+            self.builder.stem(horizontal, position, len);
+            position += len;
+            i += 2;
+        }
+
+        (self.stack.len().saturating_sub(offset) >> 1) as u32
+    }
+
     #[inline]
     pub fn parse_move_to(&mut self, offset: usize) -> Result<(), CFFError> {
         // dx1 dy1
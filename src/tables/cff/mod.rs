@@ -2,13 +2,13 @@ mod argstack;
 pub mod cff1;
 #[cfg(feature = "variable-fonts")]
 pub mod cff2;
-mod charset;
+pub(crate) mod charset;
 mod charstring;
-mod dict;
+pub(crate) mod dict;
 mod encoding;
-mod index;
+pub(crate) mod index;
 #[cfg(feature = "glyph-names")]
-mod std_names;
+pub(crate) mod std_names;
 
 use core::convert::TryFrom;
 
@@ -42,8 +42,73 @@ pub enum CFFError {
     BlendRegionsLimitReached,
 }
 
+/// A trait for glyph outline construction with stem hints.
+///
+/// Hints are reported in the charstring order, interleaved with outline segments.
+/// So a hint mask applies to all segments that follow it.
+pub trait HintingBuilder: OutlineBuilder {
+    /// Appends a horizontal stem hint.
+    ///
+    /// `y` is the bottom edge and `dy` is the stem height.
+    /// Ghost stems have a negative height of -20 or -21.
+    fn horizontal_stem(&mut self, y: f32, dy: f32);
+
+    /// Appends a vertical stem hint.
+    ///
+    /// `x` is the left edge and `dx` is the stem width.
+    fn vertical_stem(&mut self, x: f32, dx: f32);
+
+    /// Sets the active stem hints.
+    ///
+    /// Each bit corresponds to a stem in the order they were reported,
+    /// starting from the most significant bit of the first byte.
+    fn hint_mask(&mut self, mask: &[u8]);
+
+    /// Sets stem hints that form a counter group.
+    ///
+    /// Uses the same bit order as [`hint_mask`](HintingBuilder::hint_mask).
+    fn counter_mask(&mut self, mask: &[u8]);
+}
+
+/// Adapts an [`OutlineBuilder`] by ignoring hints.
+pub(crate) struct NoHints<'a>(pub &'a mut dyn OutlineBuilder);
+
+impl OutlineBuilder for NoHints<'_> {
+    #[inline]
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.move_to(x, y);
+    }
+
+    #[inline]
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.line_to(x, y);
+    }
+
+    #[inline]
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.0.quad_to(x1, y1, x, y);
+    }
+
+    #[inline]
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.0.curve_to(x1, y1, x2, y2, x, y);
+    }
+
+    #[inline]
+    fn close(&mut self) {
+        self.0.close();
+    }
+}
+
+impl HintingBuilder for NoHints<'_> {
+    fn horizontal_stem(&mut self, _: f32, _: f32) {}
+    fn vertical_stem(&mut self, _: f32, _: f32) {}
+    fn hint_mask(&mut self, _: &[u8]) {}
+    fn counter_mask(&mut self, _: &[u8]) {}
+}
+
 pub(crate) struct Builder<'a> {
-    builder: &'a mut dyn OutlineBuilder,
+    builder: &'a mut dyn HintingBuilder,
     bbox: RectF,
 }
 
@@ -72,11 +137,177 @@ impl<'a> Builder<'a> {
     fn close(&mut self) {
         self.builder.close();
     }
+
+    #[inline]
+    fn stem(&mut self, horizontal: bool, position: f32, len: f32) {
+        if horizontal {
+            self.builder.horizontal_stem(position, len);
+        } else {
+            self.builder.vertical_stem(position, len);
+        }
+    }
+
+    #[inline]
+    fn hint_mask(&mut self, mask: &[u8]) {
+        self.builder.hint_mask(mask);
+    }
+
+    #[inline]
+    fn counter_mask(&mut self, mask: &[u8]) {
+        self.builder.counter_mask(mask);
+    }
+}
+
+/// Enumerates hinting operators defined in the Adobe Technical Note #5176,
+/// Table 23 Private DICT Operators
+mod hinting_operator {
+    pub const BLUE_VALUES: u16 = 6;
+    pub const OTHER_BLUES: u16 = 7;
+    pub const FAMILY_BLUES: u16 = 8;
+    pub const FAMILY_OTHER_BLUES: u16 = 9;
+    pub const STD_HW: u16 = 10;
+    pub const STD_VW: u16 = 11;
+    pub const BLUE_SCALE: u16 = 1209;
+    pub const BLUE_SHIFT: u16 = 1210;
+    pub const BLUE_FUZZ: u16 = 1211;
+}
+
+// 'The maximum number of blue values is 14.'
+const MAX_BLUE_VALUES: usize = 14;
+
+/// A list of alignment zones.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct BlueZones {
+    values: [f32; MAX_BLUE_VALUES],
+    len: u8,
+}
+
+impl BlueZones {
+    fn parse(operands: &[f64]) -> Self {
+        let mut zones = BlueZones::default();
+        // Values are stored as deltas and must come in pairs.
+        let len = operands.len().min(MAX_BLUE_VALUES) & !1;
+        let mut value = 0.0;
+        for (i, delta) in operands[..len].iter().enumerate() {
+            value += *delta as f32;
+            zones.values[i] = value;
+        }
+
+        zones.len = len as u8;
+        zones
+    }
+
+    /// Returns the number of zones.
+    #[inline]
+    pub fn len(&self) -> usize {
+        usize::from(self.len) / 2
+    }
+
+    /// Checks if there are any zones.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a zone at an index as a `(bottom, top)` pair.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<(f32, f32)> {
+        let values = &self.values[..usize::from(self.len)];
+        let bottom = *values.get(index.checked_mul(2)?)?;
+        let top = *values.get(index * 2 + 1)?;
+        Some((bottom, top))
+    }
+
+    /// Returns an iterator over zones as `(bottom, top)` pairs.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.values[..usize::from(self.len)]
+            .chunks(2)
+            .map(|pair| (pair[0], pair[1]))
+    }
+}
+
+/// Hinting parameters from a Private DICT.
+///
+/// All values are in font units.
+#[derive(Clone, Copy, Debug)]
+pub struct HintingParameters {
+    /// Baseline and top alignment zones.
+    ///
+    /// The first zone is the baseline overshoot zone.
+    pub blue_values: BlueZones,
+    /// Bottom alignment zones.
+    pub other_blues: BlueZones,
+    /// Family-wide baseline and top alignment zones.
+    pub family_blues: BlueZones,
+    /// Family-wide bottom alignment zones.
+    pub family_other_blues: BlueZones,
+    /// Controls overshoot suppression. Defaults to 0.039625.
+    pub blue_scale: f32,
+    /// Controls overshoot enforcement. Defaults to 7.
+    pub blue_shift: f32,
+    /// Extends alignment zones. Defaults to 1.
+    pub blue_fuzz: f32,
+    /// The dominant horizontal stem width.
+    pub std_hw: Option<f32>,
+    /// The dominant vertical stem width.
+    pub std_vw: Option<f32>,
+}
+
+impl Default for HintingParameters {
+    fn default() -> Self {
+        HintingParameters {
+            blue_values: BlueZones::default(),
+            other_blues: BlueZones::default(),
+            family_blues: BlueZones::default(),
+            family_other_blues: BlueZones::default(),
+            blue_scale: 0.039625,
+            blue_shift: 7.0,
+            blue_fuzz: 1.0,
+            std_hw: None,
+            std_vw: None,
+        }
+    }
+}
+
+impl HintingParameters {
+    #[inline]
+    pub(crate) fn is_hinting_operator(operator: u16) -> bool {
+        matches!(
+            operator,
+            hinting_operator::BLUE_VALUES..=hinting_operator::STD_VW
+                | hinting_operator::BLUE_SCALE..=hinting_operator::BLUE_FUZZ
+        )
+    }
+
+    pub(crate) fn set(&mut self, operator: u16, operands: &[f64]) {
+        let number = operands.first().map(|n| *n as f32);
+        match operator {
+            hinting_operator::BLUE_VALUES => self.blue_values = BlueZones::parse(operands),
+            hinting_operator::OTHER_BLUES => self.other_blues = BlueZones::parse(operands),
+            hinting_operator::FAMILY_BLUES => self.family_blues = BlueZones::parse(operands),
+            hinting_operator::FAMILY_OTHER_BLUES => {
+                self.family_other_blues = BlueZones::parse(operands)
+            }
+            hinting_operator::STD_HW => self.std_hw = number,
+            hinting_operator::STD_VW => self.std_vw = number,
+            hinting_operator::BLUE_SCALE => {
+                self.blue_scale = number.unwrap_or(self.blue_scale);
+            }
+            hinting_operator::BLUE_SHIFT => {
+                self.blue_shift = number.unwrap_or(self.blue_shift);
+            }
+            hinting_operator::BLUE_FUZZ => {
+                self.blue_fuzz = number.unwrap_or(self.blue_fuzz);
+            }
+            _ => {}
+        }
+    }
 }
 
 /// A type-safe wrapper for string ID.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Debug)]
-pub struct StringId(u16);
+pub struct StringId(pub(crate) u16);
 
 impl FromData for StringId {
     const SIZE: usize = 2;
@@ -0,0 +1,72 @@
+//! A [CVT Variations Table](
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/cvar) implementation.
+
+use core::convert::TryFrom;
+
+use crate::gvar::{PackedDeltasIter, TupleVariationStore};
+use crate::parser::{LazyArray16, Stream};
+use crate::NormalizedCoordinate;
+
+/// A [CVT Variations Table](https://docs.microsoft.com/en-us/typography/opentype/spec/cvar).
+#[derive(Clone, Copy)]
+pub struct Table<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Table<'a> {
+    /// Parses a table from raw data.
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        let mut s = Stream::new(data);
+        let major_version = s.read::<u16>()?;
+        s.skip::<u16>(); // minor version
+        if major_version != 1 {
+            return None;
+        }
+
+        Some(Table { data })
+    }
+
+    /// Adds deltas for the provided coordinates to CVT `values`.
+    ///
+    /// `values` must have an entry for each CVT value.
+    /// Deltas for values outside of `values` are ignored.
+    ///
+    /// Returns `None` when the table is malformed.
+    pub fn apply(&self, coordinates: &[NormalizedCoordinate], values: &mut [f32]) -> Option<()> {
+        let count = u16::try_from(values.len()).ok()?;
+        // The tuple variation store header goes right after the table version.
+        let store = TupleVariationStore::parse(self.data, Stream::new_at(self.data, 4)?)?;
+        // `cvar` cannot reference shared tuples.
+        let shared_tuple_records = LazyArray16::default();
+        store.parse_tuples(coordinates, &shared_tuple_records, count, &mut |tuple| {
+            let mut deltas = PackedDeltasIter::new_single(tuple.scalar, tuple.deltas_data);
+            if let Some(point_numbers) = tuple.point_numbers {
+                // Indices are stored as differences from the previous one.
+                let mut index = 0u16;
+                for n in point_numbers {
+                    index = index.wrapping_add(n);
+                    let delta = match deltas.next_single() {
+                        Some(v) => v,
+                        None => break,
+                    };
+
+                    if let Some(value) = values.get_mut(usize::from(index)) {
+                        *value += delta;
+                    }
+                }
+            } else {
+                for value in values.iter_mut() {
+                    if let Some(delta) = deltas.next_single() {
+                        *value += delta;
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl core::fmt::Debug for Table<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Table {{ ... }}")
+    }
+}
@@ -0,0 +1,27 @@
+//! A [Control Value Table](
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/cvt) implementation.
+
+use core::convert::TryFrom;
+
+use crate::parser::{LazyArray16, Stream};
+
+/// A [Control Value Table](https://docs.microsoft.com/en-us/typography/opentype/spec/cvt).
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Table<'a> {
+    /// A list of values referenceable by instructions.
+    ///
+    /// In font units.
+    pub values: LazyArray16<'a, i16>,
+}
+
+impl<'a> Table<'a> {
+    /// Parses a table from raw data.
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        // The table has no header, the number of values is derived from the table length.
+        // Ignore values that cannot be addressed by a 16-bit index.
+        let count = u16::try_from(data.len() / 2).unwrap_or(u16::MAX);
+        let mut s = Stream::new(data);
+        let values = s.read_array16::<i16>(count)?;
+        Some(Table { values })
+    }
+}
@@ -62,12 +62,123 @@ impl VariationAxis {
     }
 }
 
+/// A [named instance](https://docs.microsoft.com/en-us/typography/opentype/spec/fvar#instancerecord).
+#[derive(Clone, Copy, Debug)]
+pub struct NamedInstance<'a> {
+    /// An instance subfamily name in the `name` table, like *Bold Condensed*.
+    pub subfamily_name_id: u16,
+    /// An instance PostScript name in the `name` table.
+    ///
+    /// `None` when not set.
+    pub post_script_name_id: Option<u16>,
+    /// Reserved flags.
+    pub flags: u16,
+    /// User-space coordinates, one per axis.
+    pub coordinates: LazyArray16<'a, Fixed>,
+}
+
+/// A list of [named instances](
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/fvar#instancerecord).
+#[derive(Clone, Copy, Default)]
+pub struct NamedInstances<'a> {
+    data: &'a [u8],
+    len: u16,
+    record_size: u16,
+    axis_count: u16,
+}
+
+impl<'a> NamedInstances<'a> {
+    /// Returns a named instance at index.
+    pub fn get(&self, index: u16) -> Option<NamedInstance<'a>> {
+        if index >= self.len {
+            return None;
+        }
+
+        let start = usize::from(index) * usize::from(self.record_size);
+        let data = self
+            .data
+            .get(start..start + usize::from(self.record_size))?;
+        let mut s = Stream::new(data);
+        let subfamily_name_id = s.read::<u16>()?;
+        let flags = s.read::<u16>()?;
+        let coordinates = s.read_array16::<Fixed>(self.axis_count)?;
+        // `postScriptNameID` is optional and indicated by the record size.
+        let post_script_name_id = s.read::<u16>().filter(|id| *id != 0xFFFF);
+
+        Some(NamedInstance {
+            subfamily_name_id,
+            post_script_name_id,
+            flags,
+            coordinates,
+        })
+    }
+
+    /// Returns the number of named instances.
+    pub fn len(&self) -> u16 {
+        self.len
+    }
+
+    /// Checks if there are any named instances.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl core::fmt::Debug for NamedInstances<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list().entries(*self).finish()
+    }
+}
+
+impl<'a> IntoIterator for NamedInstances<'a> {
+    type Item = NamedInstance<'a>;
+    type IntoIter = NamedInstancesIter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        NamedInstancesIter {
+            instances: self,
+            index: 0,
+        }
+    }
+}
+
+/// An iterator over named instances.
+#[derive(Clone, Copy)]
+#[allow(missing_debug_implementations)]
+pub struct NamedInstancesIter<'a> {
+    instances: NamedInstances<'a>,
+    index: u16,
+}
+
+impl<'a> Iterator for NamedInstancesIter<'a> {
+    type Item = NamedInstance<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index < self.instances.len() {
+            self.index += 1;
+            self.instances.get(self.index - 1)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        usize::from(self.instances.len().saturating_sub(self.index))
+    }
+}
+
 /// A [Font Variations Table](
 /// https://docs.microsoft.com/en-us/typography/opentype/spec/fvar).
 #[derive(Clone, Copy, Debug)]
 pub struct Table<'a> {
     /// A list of variation axes.
     pub axes: LazyArray16<'a, VariationAxis>,
+    /// A list of named instances.
+    ///
+    /// Empty when instance records are malformed.
+    pub named_instances: NamedInstances<'a>,
 }
 
 impl<'a> Table<'a> {
@@ -82,6 +193,9 @@ impl<'a> Table<'a> {
         let axes_array_offset = s.read::<Offset16>()?;
         s.skip::<u16>(); // reserved
         let axis_count = s.read::<u16>()?;
+        let axis_size = s.read::<u16>()?;
+        let instance_count = s.read::<u16>()?;
+        let instance_size = s.read::<u16>()?;
 
         // 'If axisCount is zero, then the font is not functional as a variable font,
         // and must be treated as a non-variable font;
@@ -91,6 +205,44 @@ impl<'a> Table<'a> {
         let mut s = Stream::new_at(data, axes_array_offset.to_usize())?;
         let axes = s.read_array16::<VariationAxis>(axis_count.get())?;
 
-        Some(Table { axes })
+        let named_instances = parse_named_instances(
+            data,
+            axes_array_offset.to_usize(),
+            axis_count.get(),
+            axis_size,
+            instance_count,
+            instance_size,
+        )
+        .unwrap_or_default();
+
+        Some(Table {
+            axes,
+            named_instances,
+        })
     }
 }
+
+fn parse_named_instances(
+    data: &[u8],
+    axes_array_offset: usize,
+    axis_count: u16,
+    axis_size: u16,
+    count: u16,
+    record_size: u16,
+) -> Option<NamedInstances<'_>> {
+    // An instance record has a 4 bytes header, followed by coordinates
+    // and an optional 2 bytes `postScriptNameID`.
+    let min_record_size = usize::from(axis_count) * Fixed::SIZE + 4;
+    if usize::from(record_size) < min_record_size {
+        return None;
+    }
+
+    let start = axes_array_offset + usize::from(axis_count) * usize::from(axis_size);
+    let end = start + usize::from(count) * usize::from(record_size);
+    Some(NamedInstances {
+        data: data.get(start..end)?,
+        len: count,
+        record_size,
+        axis_count,
+    })
+}
@@ -143,6 +143,10 @@ pub(crate) struct CompositeGlyphInfo {
     pub transform: Transform,
     #[allow(dead_code)]
     pub flags: CompositeGlyphFlags,
+    /// Parent and child point numbers used for component positioning
+    /// when `ARGS_ARE_XY_VALUES` is not set.
+    #[allow(dead_code)]
+    pub matching_points: Option<(u16, u16)>,
 }
 
 #[derive(Clone)]
@@ -168,6 +172,7 @@ impl<'a> Iterator for CompositeGlyphIter<'a> {
         let glyph_id = self.stream.read::<GlyphId>()?;
 
         let mut ts = Transform::default();
+        let mut matching_points = None;
 
         if flags.args_are_xy_values() {
             if flags.arg_1_and_2_are_words() {
@@ -177,6 +182,13 @@ impl<'a> Iterator for CompositeGlyphIter<'a> {
                 ts.e = f32::from(self.stream.read::<i8>()?);
                 ts.f = f32::from(self.stream.read::<i8>()?);
             }
+        } else if flags.arg_1_and_2_are_words() {
+            matching_points = Some((self.stream.read::<u16>()?, self.stream.read::<u16>()?));
+        } else {
+            matching_points = Some((
+                u16::from(self.stream.read::<u8>()?),
+                u16::from(self.stream.read::<u8>()?),
+            ));
         }
 
         if flags.we_have_a_two_by_two() {
@@ -201,6 +213,7 @@ impl<'a> Iterator for CompositeGlyphIter<'a> {
             glyph_id,
             transform: ts,
             flags,
+            matching_points,
         })
     }
 }
@@ -424,6 +437,15 @@ impl CompositeGlyphFlags {
     #[inline] pub fn we_have_a_two_by_two(self) -> bool { self.0 & 0x0080 != 0 }
 }
 
+#[cfg(feature = "hinting")]
+#[rustfmt::skip]
+impl CompositeGlyphFlags {
+    #[inline] pub fn round_xy_to_grid(self) -> bool { self.0 & 0x0004 != 0 }
+    #[inline] pub fn we_have_instructions(self) -> bool { self.0 & 0x0100 != 0 }
+    #[inline] pub fn use_my_metrics(self) -> bool { self.0 & 0x0200 != 0 }
+    #[inline] pub fn scaled_component_offset(self) -> bool { self.0 & 0x0800 != 0 }
+}
+
 // It's not defined in the spec, so we are using our own value.
 pub(crate) const MAX_COMPONENTS: u8 = 32;
 
@@ -484,7 +506,7 @@ fn outline_impl(
 pub(crate) fn parse_simple_outline(
     glyph_data: &[u8],
     number_of_contours: NonZeroU16,
-) -> Option<GlyphPointsIter> {
+) -> Option<GlyphPointsIter<'_>> {
     let mut s = Stream::new(glyph_data);
     let endpoints = s.read_array16::<u16>(number_of_contours.get())?;
 
@@ -515,6 +537,51 @@ pub(crate) fn parse_simple_outline(
     })
 }
 
+/// Returns simple glyph instructions.
+#[cfg(feature = "hinting")]
+pub(crate) fn simple_glyph_instructions(
+    glyph_data: &[u8],
+    number_of_contours: NonZeroU16,
+) -> Option<&[u8]> {
+    let mut s = Stream::new(glyph_data);
+    s.advance_checked(usize::from(number_of_contours.get()) * 2)?; // endpoints
+    let instructions_len = s.read::<u16>()?;
+    s.read_bytes(usize::from(instructions_len))
+}
+
+/// Returns composite glyph instructions.
+///
+/// Instructions are stored after the last component.
+#[cfg(feature = "hinting")]
+pub(crate) fn composite_glyph_instructions(glyph_data: &[u8]) -> Option<&[u8]> {
+    let mut s = Stream::new(glyph_data);
+    loop {
+        let flags = CompositeGlyphFlags(s.read::<u16>()?);
+        s.skip::<GlyphId>();
+
+        let args_len = if flags.arg_1_and_2_are_words() { 4 } else { 2 };
+        let transform_len = if flags.we_have_a_two_by_two() {
+            8
+        } else if flags.we_have_an_x_and_y_scale() {
+            4
+        } else if flags.we_have_a_scale() {
+            2
+        } else {
+            0
+        };
+        s.advance_checked(args_len + transform_len)?;
+
+        if !flags.more_components() {
+            if !flags.we_have_instructions() {
+                return Some(&[]);
+            }
+
+            let instructions_len = s.read::<u16>()?;
+            return s.read_bytes(usize::from(instructions_len));
+        }
+    }
+}
+
 /// Resolves coordinate arrays length.
 ///
 /// The length depends on *Simple Glyph Flags*, so we have to process them all to find it.
@@ -12,6 +12,8 @@ use crate::parser::{
     FromData, FromSlice, LazyArray16, LazyArray32, NumFrom, Offset, Offset16, Stream,
 };
 use crate::GlyphId;
+#[cfg(feature = "variable-fonts")]
+use crate::{gdef, NormalizedCoordinate};
 
 /// A [Device Table](
 /// https://docs.microsoft.com/en-us/typography/opentype/spec/chapter2#devVarIdxTbls)
@@ -75,6 +77,17 @@ pub struct VariationDevice {
     pub inner_index: u16,
 }
 
+#[cfg(feature = "variable-fonts")]
+impl VariationDevice {
+    /// Returns a delta at the specified coordinates.
+    ///
+    /// Deltas are stored in the `GDEF` table Item Variation Store.
+    #[inline]
+    pub fn delta(&self, gdef: &gdef::Table, coordinates: &[NormalizedCoordinate]) -> Option<f32> {
+        gdef.glyph_variation_delta(self.outer_index, self.inner_index, coordinates)
+    }
+}
+
 /// A [Device Table](
 /// https://docs.microsoft.com/en-us/typography/opentype/spec/chapter2#devVarIdxTbls).
 #[allow(missing_docs)]
@@ -110,6 +123,36 @@ impl<'a> Device<'a> {
             _ => None,
         }
     }
+
+    /// Returns a variation delta at the specified coordinates.
+    ///
+    /// Returns `None` for hinting devices.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn variation_delta(
+        &self,
+        gdef: &gdef::Table,
+        coordinates: &[NormalizedCoordinate],
+    ) -> Option<f32> {
+        match self {
+            Self::Hinting(_) => None,
+            Self::Variation(device) => device.delta(gdef, coordinates),
+        }
+    }
+}
+
+// Applies a variation delta from an optional device to a value.
+#[cfg(feature = "variable-fonts")]
+fn apply_variation(
+    value: i16,
+    device: Option<Device>,
+    gdef: &gdef::Table,
+    coordinates: &[NormalizedCoordinate],
+) -> f32 {
+    let delta = device
+        .and_then(|device| device.variation_delta(gdef, coordinates))
+        .unwrap_or(0.0);
+    f32::from(value) + delta
 }
 
 #[derive(Clone, Copy, Default, Debug)]
@@ -221,6 +264,43 @@ impl<'a> ValueRecord<'a> {
 
         Some(record)
     }
+
+    /// Returns horizontal adjustment for placement with variation deltas applied.
+    ///
+    /// Deltas are resolved using the `GDEF` table Item Variation Store.
+    /// Hinting devices are ignored.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn x_placement_at(&self, gdef: &gdef::Table, coordinates: &[NormalizedCoordinate]) -> f32 {
+        apply_variation(self.x_placement, self.x_placement_device, gdef, coordinates)
+    }
+
+    /// Returns vertical adjustment for placement with variation deltas applied.
+    ///
+    /// See [`ValueRecord::x_placement_at`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn y_placement_at(&self, gdef: &gdef::Table, coordinates: &[NormalizedCoordinate]) -> f32 {
+        apply_variation(self.y_placement, self.y_placement_device, gdef, coordinates)
+    }
+
+    /// Returns horizontal adjustment for advance with variation deltas applied.
+    ///
+    /// See [`ValueRecord::x_placement_at`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn x_advance_at(&self, gdef: &gdef::Table, coordinates: &[NormalizedCoordinate]) -> f32 {
+        apply_variation(self.x_advance, self.x_advance_device, gdef, coordinates)
+    }
+
+    /// Returns vertical adjustment for advance with variation deltas applied.
+    ///
+    /// See [`ValueRecord::x_placement_at`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn y_advance_at(&self, gdef: &gdef::Table, coordinates: &[NormalizedCoordinate]) -> f32 {
+        apply_variation(self.y_advance, self.y_advance_device, gdef, coordinates)
+    }
 }
 
 /// An array of
@@ -332,6 +412,8 @@ impl<'a> SingleAdjustment<'a> {
 /// A [`ValueRecord`] pairs set used by [`PairAdjustment`].
 #[derive(Clone, Copy)]
 pub struct PairSet<'a> {
+    // Offsets in ValueRecords are from the beginning of the PairSet table.
+    table_data: &'a [u8],
     data: &'a [u8],
     flags: (ValueFormatFlags, ValueFormatFlags),
     record_len: u8,
@@ -343,9 +425,10 @@ impl<'a> PairSet<'a> {
         let count = s.read::<u16>()?;
         // Max len is 34, so u8 is just enough.
         let record_len = (GlyphId::SIZE + flags.0.size() + flags.1.size()) as u8;
-        let data = s.read_bytes(usize::from(count) * usize::from(record_len))?;
+        let records = s.read_bytes(usize::from(count) * usize::from(record_len))?;
         Some(Self {
-            data,
+            table_data: data,
+            data: records,
             flags,
             record_len,
         })
@@ -399,8 +482,8 @@ impl<'a> PairSet<'a> {
         let mut s = Stream::new(record_data);
         s.skip::<GlyphId>();
         Some((
-            ValueRecord::parse(self.data, &mut s, self.flags.0)?,
-            ValueRecord::parse(self.data, &mut s, self.flags.1)?,
+            ValueRecord::parse(self.table_data, &mut s, self.flags.0)?,
+            ValueRecord::parse(self.table_data, &mut s, self.flags.1)?,
         ))
     }
 }
@@ -573,6 +656,37 @@ impl<'a> PairAdjustment<'a> {
             Self::Format2 { coverage, .. } => *coverage,
         }
     }
+
+    /// Returns the value format of the second glyph, i.e. `valueFormat2`.
+    #[cfg(feature = "shaping")]
+    pub(crate) fn second_value_format(&self) -> u16 {
+        let flags = match self {
+            Self::Format1 { sets, .. } => sets.flags.1,
+            Self::Format2 { matrix, .. } => matrix.flags.1,
+        };
+        u16::from(flags.0)
+    }
+
+    /// Returns value records for a pair of glyphs.
+    ///
+    /// Returns `None` when the first glyph is not covered or the pair is not set.
+    pub fn get(
+        &self,
+        first: GlyphId,
+        second: GlyphId,
+    ) -> Option<(ValueRecord<'a>, ValueRecord<'a>)> {
+        match self {
+            Self::Format1 { coverage, sets } => sets.get(coverage.get(first)?)?.get(second),
+            Self::Format2 {
+                coverage,
+                classes,
+                matrix,
+            } => {
+                coverage.get(first)?;
+                matrix.get((classes.0.get(first), classes.1.get(second)))
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -879,6 +993,25 @@ impl<'a> Anchor<'a> {
 
         Some(table)
     }
+
+    /// Returns horizontal value with variation deltas applied.
+    ///
+    /// Deltas are resolved using the `GDEF` table Item Variation Store.
+    /// Hinting devices are ignored.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn x_at(&self, gdef: &gdef::Table, coordinates: &[NormalizedCoordinate]) -> f32 {
+        apply_variation(self.x, self.x_device, gdef, coordinates)
+    }
+
+    /// Returns vertical value with variation deltas applied.
+    ///
+    /// See [`Anchor::x_at`] for details.
+    #[cfg(feature = "variable-fonts")]
+    #[inline]
+    pub fn y_at(&self, gdef: &gdef::Table, coordinates: &[NormalizedCoordinate]) -> f32 {
+        apply_variation(self.y, self.y_device, gdef, coordinates)
+    }
 }
 
 /// An [`Anchor`] parsing helper.
@@ -907,7 +1040,7 @@ impl<'a> AnchorMatrix<'a> {
     }
 
     /// Returns an [`Anchor`] at position.
-    pub fn get(&self, row: u16, col: u16) -> Option<Anchor> {
+    pub fn get(&self, row: u16, col: u16) -> Option<Anchor<'_>> {
         let idx = u32::from(row) * u32::from(self.cols) + u32::from(col);
         let offset = self.matrix.get(idx)??.to_usize();
         Anchor::parse(self.data.get(offset..)?)
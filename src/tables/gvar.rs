@@ -47,32 +47,41 @@ struct VariationTuple<'a> {
     prev_point: Option<PointAndDelta>,
 }
 
-/// The maximum number of variation tuples stored on the stack.
+/// The maximum number of variation tuples processed in a single pass.
 ///
-/// The TrueType spec allows up to 4095 tuples, which is way larger
-/// than we do. But in reality, an average font will have less than 10 tuples.
-/// We can avoid heap allocations if the number of tuples is less than this number.
+/// The TrueType spec allows up to 4095 tuples, but an average font will have less than 10 of them.
+/// When a glyph has more tuples, they are processed in batches of this size,
+/// which requires multiple passes over the glyph points, but no heap allocations.
 const MAX_STACK_TUPLES_LEN: u16 = 32;
 
-/// A list of variation tuples, possibly stored on the heap.
+/// The maximum number of tuple batches iterated in parallel.
 ///
-/// This is the only part of the `gvar` algorithm that actually allocates a data.
-/// This is probably unavoidable due to `gvar` structure,
-/// since we have to iterate all tuples in parallel.
-enum VariationTuples<'a> {
-    Stack {
-        headers: [VariationTuple<'a>; MAX_STACK_TUPLES_LEN as usize],
-        len: u16,
-    },
-    #[cfg(feature = "gvar-alloc")]
-    Heap {
-        vec: std::vec::Vec<VariationTuple<'a>>,
-    },
+/// A glyph with up to `MAX_TUPLES_GROUP_LEN` tuples is processed in a single pass over its points.
+const MAX_TUPLE_BATCHES: u16 = 4;
+
+/// The maximum number of variation tuples iterated in parallel.
+const MAX_TUPLES_GROUP_LEN: u16 = MAX_STACK_TUPLES_LEN * MAX_TUPLE_BATCHES;
+
+/// The number of points whose deltas are accumulated at once
+/// when a glyph has more than `MAX_TUPLES_GROUP_LEN` tuples.
+const POINTS_WINDOW_LEN: u16 = 128;
+
+/// The number of component offsets that are calculated at once
+/// when a composite glyph has more than `MAX_STACK_TUPLES_LEN` tuples.
+const COMPONENTS_WINDOW_LEN: u16 = 16;
+
+/// A batch of variation tuples stored on the stack.
+///
+/// Due to `gvar` structure, we have to iterate all tuples in parallel.
+#[derive(Clone, Copy)]
+struct VariationTuples<'a> {
+    headers: [VariationTuple<'a>; MAX_STACK_TUPLES_LEN as usize],
+    len: u16,
 }
 
 impl<'a> Default for VariationTuples<'a> {
     fn default() -> Self {
-        Self::Stack {
+        VariationTuples {
             headers: [VariationTuple::default(); MAX_STACK_TUPLES_LEN as usize],
             len: 0,
         }
@@ -80,108 +89,37 @@ impl<'a> Default for VariationTuples<'a> {
 }
 
 impl<'a> VariationTuples<'a> {
-    /// Attempt to reserve up to `capacity` total slots for variation tuples.
-    #[cfg(feature = "gvar-alloc")]
-    fn reserve(&mut self, capacity: u16) -> bool {
-        // If the requested capacity exceeds the configured maximum stack tuple size ...
-        if capacity > MAX_STACK_TUPLES_LEN {
-            // ... and we're currently on the stack, move to the heap.
-            if let Self::Stack { headers, len } = self {
-                let mut vec = std::vec::Vec::with_capacity(capacity as usize);
-                for header in headers.iter_mut().take(*len as usize) {
-                    let header = core::mem::take(header);
-                    vec.push(header);
-                }
-
-                *self = Self::Heap { vec };
-                return true;
-            }
-        }
-
-        // Otherwise ...
-        match self {
-            // ... extend the vec capacity to hold our new elements ...
-            Self::Heap { vec } if vec.len() < capacity as usize => {
-                vec.reserve(capacity as usize - vec.len());
-                true
-            }
-            // ... or do nothing if the vec is already large enough or we're on the stack.
-            _ => true,
-        }
-    }
-
-    /// Attempt to reserve up to `capacity` total slots for variation tuples.
-    #[cfg(not(feature = "gvar-alloc"))]
-    fn reserve(&mut self, capacity: u16) -> bool {
-        capacity <= MAX_STACK_TUPLES_LEN
-    }
-
-    /// Get the number of tuples stored in the structure.
-    #[cfg_attr(not(feature = "gvar-alloc"), allow(dead_code))]
-    fn len(&self) -> u16 {
-        match self {
-            Self::Stack { len, .. } => *len,
-            #[cfg(feature = "gvar-alloc")]
-            Self::Heap { vec } => vec.len() as u16,
-        }
-    }
-
-    /// Append a new tuple header to the list.
-    /// This may panic if the list can't hold a new header.
-    #[cfg(feature = "gvar-alloc")]
-    fn push(&mut self, header: VariationTuple<'a>) {
-        // Reserve space for the new element.
-        // This may fail and result in a later panic, but that matches pre-heap behavior.
-        self.reserve(self.len() + 1);
-
-        match self {
-            Self::Stack { headers, len } => {
-                headers[usize::from(*len)] = header;
-                *len += 1;
-            }
-            Self::Heap { vec } => vec.push(header),
-        }
-    }
-
-    /// Append a new tuple header to the list.
-    /// This may panic if the list can't hold a new header.
-    #[cfg(not(feature = "gvar-alloc"))]
+    /// Appends a new tuple header to the list.
+    ///
+    /// Does nothing when the list is full.
     #[inline]
     fn push(&mut self, header: VariationTuple<'a>) {
-        match self {
-            Self::Stack { headers, len } => {
-                headers[usize::from(*len)] = header;
-                *len += 1;
-            }
+        if let Some(v) = self.headers.get_mut(usize::from(self.len)) {
+            *v = header;
+            self.len += 1;
         }
     }
 
-    /// Remove all tuples from the structure.
+    /// Removes all tuples from the list.
     fn clear(&mut self) {
-        match self {
-            Self::Stack { len, .. } => *len = 0,
-            #[cfg(feature = "gvar-alloc")]
-            Self::Heap { vec } => vec.clear(),
-        }
+        self.len = 0;
     }
 
     #[inline]
     fn as_mut_slice(&mut self) -> &mut [VariationTuple<'a>] {
-        match self {
-            Self::Stack { headers, len } => &mut headers[0..usize::from(*len)],
-            #[cfg(feature = "gvar-alloc")]
-            Self::Heap { vec } => vec.as_mut_slice(),
-        }
+        &mut self.headers[0..usize::from(self.len)]
     }
 
+    /// Adds deltas of the next point from each tuple to `base`.
     fn apply(
         &mut self,
         all_points: glyf::GlyphPointsIter,
         points: glyf::GlyphPointsIter,
         point: glyf::GlyphPoint,
+        base: PointF,
     ) -> Option<PointF> {
-        let mut x = f32::from(point.x);
-        let mut y = f32::from(point.y);
+        let mut x = base.x;
+        let mut y = base.y;
 
         for tuple in self.as_mut_slice() {
             if let Some(ref mut set_points) = tuple.set_points {
@@ -235,6 +173,35 @@ impl<'a> VariationTuples<'a> {
         Some(PointF { x, y })
     }
 
+    /// Advances all tuples by one point without calculating its deltas.
+    ///
+    /// The last set point of each tuple is still remembered,
+    /// since it's required to infer deltas of the following points.
+    fn skip(&mut self, point: glyf::GlyphPoint) -> Option<()> {
+        for tuple in self.as_mut_slice() {
+            if let Some(ref mut set_points) = tuple.set_points {
+                if set_points.next()? {
+                    if let Some((x_delta, y_delta)) = tuple.deltas.next() {
+                        tuple.prev_point = Some(PointAndDelta {
+                            x: point.x,
+                            y: point.y,
+                            x_delta,
+                            y_delta,
+                        });
+                    }
+                }
+
+                if point.last_point {
+                    tuple.prev_point = None;
+                }
+            } else {
+                let _ = tuple.deltas.next();
+            }
+        }
+
+        Some(())
+    }
+
     // This is just like `apply()`, but without `infer_deltas`,
     // since we use it only for component points and not a contour.
     // And since there are no contour and no points, `infer_deltas()` will do nothing.
@@ -262,6 +229,62 @@ impl<'a> VariationTuples<'a> {
     }
 }
 
+/// Multiple batches of variation tuples iterated in parallel.
+///
+/// Used when a glyph has more tuples than a single batch can hold.
+struct TupleBatches<'a> {
+    batches: [VariationTuples<'a>; MAX_TUPLE_BATCHES as usize],
+}
+
+impl<'a> Default for TupleBatches<'a> {
+    fn default() -> Self {
+        TupleBatches {
+            batches: [VariationTuples::default(); MAX_TUPLE_BATCHES as usize],
+        }
+    }
+}
+
+impl<'a> TupleBatches<'a> {
+    /// Adds deltas of the next point from each tuple to `base`.
+    ///
+    /// See [`VariationTuples::apply`] for details.
+    fn apply(
+        &mut self,
+        all_points: glyf::GlyphPointsIter,
+        points: glyf::GlyphPointsIter,
+        point: glyf::GlyphPoint,
+        base: PointF,
+    ) -> Option<PointF> {
+        let mut p = base;
+        for tuples in &mut self.batches {
+            p = tuples.apply(all_points.clone(), points.clone(), point, p)?;
+        }
+
+        Some(p)
+    }
+
+    /// Advances all tuples by one point without calculating its deltas.
+    fn skip(&mut self, point: glyf::GlyphPoint) -> Option<()> {
+        for tuples in &mut self.batches {
+            tuples.skip(point)?;
+        }
+
+        Some(())
+    }
+
+    /// Returns deltas of the next point that doesn't belong to a contour.
+    fn apply_null(&mut self) -> Option<PointF> {
+        let mut delta = PointF { x: 0.0, y: 0.0 };
+        for tuples in &mut self.batches {
+            let d = tuples.apply_null()?;
+            delta.x += d.x;
+            delta.y += d.y;
+        }
+
+        Some(delta)
+    }
+}
+
 #[derive(Clone, Copy, Default, Debug)]
 struct TupleVariationHeaderData {
     scalar: f32,
@@ -269,8 +292,19 @@ struct TupleVariationHeaderData {
     serialized_data_len: u16,
 }
 
+/// A tuple variation with a resolved scalar and serialized data.
+#[derive(Clone, Copy)]
+pub(crate) struct TupleVariation<'a> {
+    pub scalar: f32,
+    /// `None` indicates that the tuple applies to all points.
+    pub point_numbers: Option<PackedPointsIter<'a>>,
+    /// The number of referenced points.
+    pub deltas_count: u16,
+    pub deltas_data: &'a [u8],
+}
+
 // https://docs.microsoft.com/en-us/typography/opentype/spec/otvarcommonformats#tuplevariationheader
-fn parse_variation_tuples<'a>(
+pub(crate) fn parse_variation_tuples<'a>(
     count: u16,
     coordinates: &[NormalizedCoordinate],
     shared_tuple_records: &LazyArray16<F2DOT14>,
@@ -278,10 +312,8 @@ fn parse_variation_tuples<'a>(
     points_len: u16,
     mut main_s: Stream<'a>,
     mut serialized_s: Stream<'a>,
-    tuples: &mut VariationTuples<'a>,
+    f: &mut dyn FnMut(TupleVariation<'a>),
 ) -> Option<()> {
-    debug_assert!(core::mem::size_of::<VariationTuple>() <= 80);
-
     // `TupleVariationHeader` has a variable size, so we cannot use a `LazyArray`.
     for _ in 0..count {
         let header = parse_tuple_variation_header(coordinates, shared_tuple_records, &mut main_s)?;
@@ -300,34 +332,23 @@ fn parse_variation_tuples<'a>(
             shared_point_numbers.clone()
         };
 
-        // TODO: this
-        // Since the packed representation can include zero values,
-        // it is possible for a given point number to be repeated in the derived point number list.
-        // In that case, there will be multiple delta values in the deltas data
-        // associated with that point number. All of these deltas must be applied
-        // cumulatively to the given point.
-
         let deltas_count = if let Some(point_numbers) = point_numbers.clone() {
             u16::try_from(point_numbers.clone().count()).ok()?
         } else {
             points_len
         };
 
-        let deltas = {
-            // Use `checked_sub` in case we went over the `serialized_data_len`.
-            let left = usize::from(header.serialized_data_len)
-                .checked_sub(serialized_s.offset() - serialized_data_start)?;
-            let deltas_data = serialized_s.read_bytes(left)?;
-            PackedDeltasIter::new(header.scalar, deltas_count, deltas_data)
-        };
-
-        let tuple = VariationTuple {
-            set_points: point_numbers.map(SetPointsIter::new),
-            deltas,
-            prev_point: None,
-        };
-
-        tuples.push(tuple);
+        // Use `checked_sub` in case we went over the `serialized_data_len`.
+        let left = usize::from(header.serialized_data_len)
+            .checked_sub(serialized_s.offset() - serialized_data_start)?;
+        let deltas_data = serialized_s.read_bytes(left)?;
+
+        f(TupleVariation {
+            scalar: header.scalar,
+            point_numbers,
+            deltas_count,
+            deltas_data,
+        });
     }
 
     Some(())
@@ -425,7 +446,7 @@ fn parse_tuple_variation_header(
 }
 
 // https://docs.microsoft.com/en-us/typography/opentype/spec/otvarcommonformats#packed-point-numbers
-mod packed_points {
+pub(crate) mod packed_points {
     use crate::parser::{FromData, Stream};
 
     struct Control(u8);
@@ -792,16 +813,12 @@ mod packed_points {
                 deltas_are_words: false,
                 run_count: 100,
             }));
-            for _ in 0..100 {
-                data.push(2);
-            }
+            data.extend(core::iter::repeat(2).take(100));
             data.push(gen_control(NewControl {
                 deltas_are_words: false,
                 run_count: 50,
             }));
-            for _ in 0..50 {
-                data.push(2);
-            }
+            data.extend(core::iter::repeat(2).take(50));
             let points_iter = PackedPointsIter::new(&mut Stream::new(&data))
                 .unwrap()
                 .unwrap();
@@ -892,10 +909,10 @@ mod packed_points {
     }
 }
 
-use packed_points::*;
+pub(crate) use packed_points::*;
 
 // https://docs.microsoft.com/en-us/typography/opentype/spec/otvarcommonformats#packed-deltas
-mod packed_deltas {
+pub(crate) mod packed_deltas {
     use crate::parser::Stream;
 
     struct Control(u8);
@@ -1032,11 +1049,28 @@ mod packed_deltas {
             iter
         }
 
+        /// Creates an iterator over a single series of deltas.
+        ///
+        /// Unlike `gvar`, `cvar` stores only one value per referenced item.
+        pub fn new_single(scalar: f32, data: &'a [u8]) -> Self {
+            PackedDeltasIter {
+                data,
+                scalar,
+                ..PackedDeltasIter::default()
+            }
+        }
+
         #[inline]
         pub fn restart(self) -> Self {
             PackedDeltasIter::new(self.scalar, self.total_count, self.data)
         }
 
+        /// Returns the next delta of a single series iterator.
+        #[inline]
+        pub fn next_single(&mut self) -> Option<f32> {
+            self.x_run.next(self.data, self.scalar)
+        }
+
         #[inline]
         pub fn next(&mut self) -> Option<(f32, f32)> {
             let x = self.x_run.next(self.data, self.scalar)?;
@@ -1432,7 +1466,7 @@ mod packed_deltas {
     }
 }
 
-use packed_deltas::PackedDeltasIter;
+pub(crate) use packed_deltas::PackedDeltasIter;
 
 /// Infer unreferenced deltas.
 ///
@@ -1689,20 +1723,92 @@ impl<'a> Table<'a> {
         })
     }
 
+    /// Parses batches of applicable tuples, starting from the `first` one.
+    ///
+    /// Returns the total number of applicable tuples.
     #[inline]
     fn parse_variation_data(
         &self,
         glyph_id: GlyphId,
         coordinates: &[NormalizedCoordinate],
         points_len: u16,
-        tuples: &mut VariationTuples<'a>,
-    ) -> Option<()> {
-        tuples.clear();
+        first: u16,
+        batches: &mut [VariationTuples<'a>],
+    ) -> Option<u16> {
+        for tuples in batches.iter_mut() {
+            tuples.clear();
+        }
 
         if coordinates.len() != usize::from(self.axis_count.get()) {
             return None;
         }
 
+        let data = self.glyph_variation_data(glyph_id)?;
+
+        // Ignore empty data.
+        if data.is_empty() {
+            return Some(0);
+        }
+
+        parse_variation_data(
+            coordinates,
+            &self.shared_tuple_records,
+            points_len,
+            data,
+            first,
+            batches,
+        )
+    }
+
+    /// Calculates deltas of consecutive points that don't belong to a contour,
+    /// like component offsets or phantom points, starting from the `index` one.
+    ///
+    /// Used when a glyph has more tuples than a single batch can hold.
+    /// All tuples are processed in a single pass, unless there are more than
+    /// `MAX_TUPLES_GROUP_LEN` of them.
+    #[inline(never)]
+    fn point_deltas(
+        &self,
+        glyph_id: GlyphId,
+        coordinates: &[NormalizedCoordinate],
+        points_len: u16,
+        index: u16,
+        tuples_count: u16,
+        deltas: &mut [PointF],
+    ) -> Option<()> {
+        for delta in deltas.iter_mut() {
+            *delta = PointF { x: 0.0, y: 0.0 };
+        }
+
+        let mut batches = TupleBatches::default();
+        let mut first = 0;
+        while first < tuples_count {
+            self.parse_variation_data(
+                glyph_id,
+                coordinates,
+                points_len,
+                first,
+                &mut batches.batches,
+            )?;
+
+            for _ in 0..index {
+                batches.apply_null()?;
+            }
+
+            for delta in deltas.iter_mut() {
+                let d = batches.apply_null()?;
+                delta.x += d.x;
+                delta.y += d.y;
+            }
+
+            first = first.saturating_add(MAX_TUPLES_GROUP_LEN);
+        }
+
+        Some(())
+    }
+
+    #[inline]
+    fn glyph_variation_data(&self, glyph_id: GlyphId) -> Option<&'a [u8]> {
         let next_glyph_id = glyph_id.0.checked_add(1)?;
 
         let (start, end) = match self.offsets {
@@ -1720,19 +1826,7 @@ impl<'a> Table<'a> {
             ),
         };
 
-        // Ignore empty data.
-        if start == end {
-            return Some(());
-        }
-
-        let data = self.glyphs_variation_data.get(start..end)?;
-        parse_variation_data(
-            coordinates,
-            &self.shared_tuple_records,
-            points_len,
-            data,
-            tuples,
-        )
+        self.glyphs_variation_data.get(start..end)
     }
 
     /// Outlines a glyph.
@@ -1757,6 +1851,79 @@ impl<'a> Table<'a> {
         b.bbox.to_rect()
     }
 
+    /// Outlines a glyph using a cache of decoded deltas.
+    ///
+    /// Produces the same outline as `outline`, but deltas of each glyph
+    /// are decoded only once for the current `coordinates`.
+    #[cfg(feature = "std")]
+    pub fn outline_with_cache(
+        &self,
+        glyf_table: glyf::Table,
+        coordinates: &[NormalizedCoordinate],
+        glyph_id: GlyphId,
+        cache: &mut DeltaCache,
+        builder: &mut dyn OutlineBuilder,
+    ) -> Option<Rect> {
+        cache.set_coordinates(coordinates);
+
+        let mut b = glyf::Builder::new(Transform::default(), RectF::new(), builder);
+        let glyph_data = glyf_table.get(glyph_id)?;
+        outline_cached_impl(glyf_table, self, glyph_id, glyph_data, cache, 0, &mut b);
+        b.bbox.to_rect()
+    }
+
+    /// Calculates variation deltas for all points of a glyph.
+    ///
+    /// `points` are the original points of a simple glyph and must be empty
+    /// for a composite one. `deltas` must have an entry for each point
+    /// or component, followed by four phantom points.
+    ///
+    /// Deltas are calculated the same way as in `outline`.
+    #[cfg(feature = "std")]
+    pub(crate) fn glyph_deltas(
+        &self,
+        glyph_id: GlyphId,
+        coordinates: &[NormalizedCoordinate],
+        points: glyf::GlyphPointsIter,
+        deltas: &mut [PointF],
+    ) -> Option<()> {
+        for delta in deltas.iter_mut() {
+            *delta = PointF { x: 0.0, y: 0.0 };
+        }
+
+        let points_len = u16::try_from(deltas.len().checked_sub(PHANTOM_POINTS_LEN)?).ok()?;
+        let mut batches = TupleBatches::default();
+        let mut first = 0;
+        loop {
+            let tuples_count = self.parse_variation_data(
+                glyph_id,
+                coordinates,
+                points_len,
+                first,
+                &mut batches.batches,
+            )?;
+
+            let mut glyph_points = points.clone();
+            let mut deltas_iter = deltas.iter_mut();
+            while let Some(point) = glyph_points.next() {
+                let delta = deltas_iter.next()?;
+                *delta = batches.apply(points.clone(), glyph_points.clone(), point, *delta)?;
+            }
+
+            // Component offsets and phantom points.
+            for delta in deltas_iter {
+                let d = batches.apply_null()?;
+                delta.x += d.x;
+                delta.y += d.y;
+            }
+
+            first = first.saturating_add(MAX_TUPLES_GROUP_LEN);
+            if first >= tuples_count {
+                return Some(());
+            }
+        }
+    }
+
     pub(crate) fn phantom_points(
         &self,
         glyf_table: glyf::Table,
@@ -1765,7 +1932,32 @@ impl<'a> Table<'a> {
     ) -> Option<PhantomPoints> {
         let outline_points = glyf_table.outline_points(glyph_id);
         let mut tuples = VariationTuples::default();
-        self.parse_variation_data(glyph_id, coordinates, outline_points, &mut tuples)?;
+        let tuples_count = self.parse_variation_data(
+            glyph_id,
+            coordinates,
+            outline_points,
+            0,
+            core::slice::from_mut(&mut tuples),
+        )?;
+
+        if tuples_count > MAX_STACK_TUPLES_LEN {
+            let mut deltas = [PointF { x: 0.0, y: 0.0 }; PHANTOM_POINTS_LEN];
+            self.point_deltas(
+                glyph_id,
+                coordinates,
+                outline_points,
+                outline_points,
+                tuples_count,
+                &mut deltas,
+            )?;
+
+            return Some(PhantomPoints {
+                left: deltas[0],
+                right: deltas[1],
+                top: deltas[2],
+                bottom: deltas[3],
+            });
+        }
 
         // Skip all outline deltas.
         for _ in 0..outline_points {
@@ -1787,6 +1979,110 @@ impl core::fmt::Debug for Table<'_> {
     }
 }
 
+/// A cache of decoded glyph variation deltas.
+///
+/// Outlining a glyph using `gvar` requires decoding packed point numbers and deltas
+/// and inferring deltas of unreferenced points, which is quite expensive.
+/// This cache stores resolved deltas of each outlined glyph,
+/// so the next time the same glyph is outlined at the same coordinates
+/// only the `glyf` data has to be parsed.
+///
+/// The cache is cleared automatically when variation coordinates change
+/// or when it holds the maximum number of glyphs and a new one has to be added.
+/// It must not be shared between different font faces.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct DeltaCache {
+    coordinates: std::vec::Vec<NormalizedCoordinate>,
+    glyphs: std::collections::HashMap<GlyphId, std::vec::Vec<PointF>>,
+    max_glyphs: usize,
+}
+
+#[cfg(feature = "std")]
+impl Default for DeltaCache {
+    #[inline]
+    fn default() -> Self {
+        DeltaCache {
+            coordinates: std::vec::Vec::new(),
+            glyphs: std::collections::HashMap::new(),
+            max_glyphs: usize::MAX,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl DeltaCache {
+    /// Creates a new, empty cache without a glyphs limit.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new, empty cache that holds deltas of up to `max_glyphs` glyphs.
+    ///
+    /// Deltas of a single glyph are always cached, even when `max_glyphs` is zero.
+    #[inline]
+    pub fn with_max_glyphs(max_glyphs: usize) -> Self {
+        DeltaCache {
+            max_glyphs,
+            ..Self::default()
+        }
+    }
+
+    /// Removes all cached deltas.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+    }
+
+    /// Returns the number of cached glyphs.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Checks that the cache is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    fn set_coordinates(&mut self, coordinates: &[NormalizedCoordinate]) {
+        if self.coordinates != coordinates {
+            self.glyphs.clear();
+            self.coordinates.clear();
+            self.coordinates.extend_from_slice(coordinates);
+        }
+    }
+
+    /// Returns deltas of a glyph with `count` points or components, decoding them when needed.
+    ///
+    /// `points` must be empty for a composite glyph.
+    fn glyph_deltas(
+        &mut self,
+        gvar_table: &Table,
+        glyph_id: GlyphId,
+        count: usize,
+        points: glyf::GlyphPointsIter,
+    ) -> Option<&[PointF]> {
+        if self.glyphs.len() >= self.max_glyphs && !self.glyphs.contains_key(&glyph_id) {
+            self.glyphs.clear();
+        }
+
+        let deltas = match self.glyphs.entry(glyph_id) {
+            std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                // Each glyph is followed by four phantom points.
+                let mut deltas = std::vec![PointF { x: 0.0, y: 0.0 }; count + PHANTOM_POINTS_LEN];
+                gvar_table.glyph_deltas(glyph_id, &self.coordinates, points, &mut deltas)?;
+                e.insert(deltas)
+            }
+        };
+
+        Some(deltas)
+    }
+}
+
 #[allow(clippy::comparison_chain)]
 fn outline_var_impl(
     glyf_table: glyf::Table,
@@ -1811,7 +2107,7 @@ fn outline_var_impl(
     // Instead, we have to manually calculate outline's bbox.
     s.advance(8);
 
-    // TODO: This is the most expensive part. Find a way to allocate it only once.
+    // This is the most expensive part. Use `DeltaCache` to decode deltas only once.
     // `VariationTuples` is a very large struct, so allocate it once.
     let mut tuples = VariationTuples::default();
 
@@ -1822,10 +2118,31 @@ fn outline_var_impl(
         let mut glyph_points = glyf::parse_simple_outline(s.tail()?, number_of_contours)?;
         let all_glyph_points = glyph_points.clone();
         let points_len = glyph_points.points_left;
-        gvar_table.parse_variation_data(glyph_id, coordinates, points_len, &mut tuples)?;
+        let tuples_count = gvar_table.parse_variation_data(
+            glyph_id,
+            coordinates,
+            points_len,
+            0,
+            core::slice::from_mut(&mut tuples),
+        )?;
+
+        if tuples_count > MAX_STACK_TUPLES_LEN {
+            return outline_points_batched(
+                gvar_table,
+                glyph_id,
+                coordinates,
+                glyph_points,
+                tuples_count,
+                builder,
+            );
+        }
 
         while let Some(point) = glyph_points.next() {
-            let p = tuples.apply(all_glyph_points.clone(), glyph_points.clone(), point)?;
+            let base = PointF {
+                x: f32::from(point.x),
+                y: f32::from(point.y),
+            };
+            let p = tuples.apply(all_glyph_points.clone(), glyph_points.clone(), point, base)?;
             builder.push_point(p.x, p.y, point.on_curve_point, point.last_point);
         }
 
@@ -1843,10 +2160,35 @@ fn outline_var_impl(
 
         let components = glyf::CompositeGlyphIter::new(s.tail()?);
         let components_count = components.clone().count() as u16;
-        gvar_table.parse_variation_data(glyph_id, coordinates, components_count, &mut tuples)?;
+        let tuples_count = gvar_table.parse_variation_data(
+            glyph_id,
+            coordinates,
+            components_count,
+            0,
+            core::slice::from_mut(&mut tuples),
+        )?;
+
+        // Offsets of the current components window, when tuples are processed in batches.
+        let mut offsets = [PointF { x: 0.0, y: 0.0 }; COMPONENTS_WINDOW_LEN as usize];
+
+        for (index, component) in components.enumerate() {
+            let t = if tuples_count > MAX_STACK_TUPLES_LEN {
+                let index = index as u16;
+                if index % COMPONENTS_WINDOW_LEN == 0 {
+                    gvar_table.point_deltas(
+                        glyph_id,
+                        coordinates,
+                        components_count,
+                        index,
+                        tuples_count,
+                        &mut offsets,
+                    )?;
+                }
 
-        for component in components {
-            let t = tuples.apply_null()?;
+                offsets[usize::from(index % COMPONENTS_WINDOW_LEN)]
+            } else {
+                tuples.apply_null()?
+            };
 
             let mut transform = builder.transform;
 
@@ -1882,59 +2224,305 @@ fn outline_var_impl(
     }
 }
 
-// https://docs.microsoft.com/en-us/typography/opentype/spec/otvarcommonformats#tuple-variation-store-header
-fn parse_variation_data<'a>(
+/// Outlines a simple glyph that has more tuples than a single batch can hold.
+///
+/// Up to `MAX_TUPLES_GROUP_LEN` tuples are iterated in parallel in a single pass over the points.
+/// When a glyph has even more tuples, they are processed in groups
+/// and deltas are accumulated for a window of points at a time.
+/// In which case each group has to skip the points before the window,
+/// which is still much cheaper than inferring their deltas.
+#[inline(never)]
+fn outline_points_batched(
+    gvar_table: &Table,
+    glyph_id: GlyphId,
     coordinates: &[NormalizedCoordinate],
-    shared_tuple_records: &LazyArray16<F2DOT14>,
-    points_len: u16,
-    data: &'a [u8],
-    tuples: &mut VariationTuples<'a>,
+    glyph_points: glyf::GlyphPointsIter,
+    tuples_count: u16,
+    builder: &mut glyf::Builder,
 ) -> Option<()> {
-    const SHARED_POINT_NUMBERS_FLAG: u16 = 0x8000;
-    const COUNT_MASK: u16 = 0x0FFF;
+    let points_len = glyph_points.points_left;
+    let mut batches = TupleBatches::default();
 
-    let mut main_stream = Stream::new(data);
-    let tuple_variation_count = main_stream.read::<u16>()?;
-    let data_offset = main_stream.read::<Offset16>()?;
+    if tuples_count <= MAX_TUPLES_GROUP_LEN {
+        gvar_table.parse_variation_data(
+            glyph_id,
+            coordinates,
+            points_len,
+            0,
+            &mut batches.batches,
+        )?;
+
+        let mut points = glyph_points.clone();
+        while let Some(point) = points.next() {
+            let base = PointF {
+                x: f32::from(point.x),
+                y: f32::from(point.y),
+            };
+            let p = batches.apply(glyph_points.clone(), points.clone(), point, base)?;
+            builder.push_point(p.x, p.y, point.on_curve_point, point.last_point);
+        }
 
-    // 'The high 4 bits are flags, and the low 12 bits
-    // are the number of tuple variation tables for this glyph.'
-    let has_shared_point_numbers = tuple_variation_count & SHARED_POINT_NUMBERS_FLAG != 0;
-    let tuple_variation_count = tuple_variation_count & COUNT_MASK;
+        return Some(());
+    }
 
-    // 'The number of tuple variation tables can be any number between 1 and 4095.'
-    // No need to check for 4095, because this is 0x0FFF that we masked before.
-    if tuple_variation_count == 0 {
-        return None;
+    let mut window = [PointF { x: 0.0, y: 0.0 }; POINTS_WINDOW_LEN as usize];
+    let mut output_points = glyph_points.clone();
+
+    let mut window_start = 0;
+    while window_start < points_len {
+        let window_end = cmp::min(window_start.saturating_add(POINTS_WINDOW_LEN), points_len);
+
+        let mut first = 0;
+        while first < tuples_count {
+            gvar_table.parse_variation_data(
+                glyph_id,
+                coordinates,
+                points_len,
+                first,
+                &mut batches.batches,
+            )?;
+
+            let mut points = glyph_points.clone();
+            for i in 0..window_end {
+                let point = points.next()?;
+                if i < window_start {
+                    batches.skip(point)?;
+                    continue;
+                }
+
+                let p = &mut window[usize::from(i - window_start)];
+                let base = if first == 0 {
+                    PointF {
+                        x: f32::from(point.x),
+                        y: f32::from(point.y),
+                    }
+                } else {
+                    *p
+                };
+                *p = batches.apply(glyph_points.clone(), points.clone(), point, base)?;
+            }
+
+            first = first.saturating_add(MAX_TUPLES_GROUP_LEN);
+        }
+
+        for p in &window[..usize::from(window_end - window_start)] {
+            let point = output_points.next()?;
+            builder.push_point(p.x, p.y, point.on_curve_point, point.last_point);
+        }
+
+        window_start = window_end;
     }
 
-    // Attempt to reserve space for the tuples we're about to parse.
-    // If it fails, bail out.
-    if !tuples.reserve(tuple_variation_count) {
+    Some(())
+}
+
+#[cfg(feature = "std")]
+fn outline_cached_impl(
+    glyf_table: glyf::Table,
+    gvar_table: &Table,
+    glyph_id: GlyphId,
+    data: &[u8],
+    cache: &mut DeltaCache,
+    depth: u8,
+    builder: &mut glyf::Builder,
+) -> Option<()> {
+    if depth >= glyf::MAX_COMPONENTS {
         return None;
     }
 
-    // A glyph variation data consists of three parts: header + variation tuples + serialized data.
-    // Each tuple has it's own chunk in the serialized data.
-    // Because of that, we are using two parsing streams: one for tuples and one for serialized data.
-    // So we can parse them in parallel and avoid needless allocations.
-    let mut serialized_stream = Stream::new_at(data, data_offset.to_usize())?;
-
-    // All tuples in the variation data can reference the same point numbers,
-    // which are defined at the start of the serialized data.
-    let mut shared_point_numbers = None;
-    if has_shared_point_numbers {
-        shared_point_numbers = PackedPointsIter::new(&mut serialized_stream)?;
+    let mut s = Stream::new(data);
+    let number_of_contours = s.read::<i16>()?;
+
+    // Skip bbox. See `outline_var_impl` for details.
+    s.advance(8);
+
+    if number_of_contours > 0 {
+        // Simple glyph.
+
+        let number_of_contours = NonZeroU16::new(number_of_contours as u16)?;
+        let glyph_points = glyf::parse_simple_outline(s.tail()?, number_of_contours)?;
+        let points_len = usize::from(glyph_points.points_left);
+        let deltas = cache.glyph_deltas(gvar_table, glyph_id, points_len, glyph_points.clone())?;
+
+        for (point, delta) in glyph_points.zip(deltas) {
+            builder.push_point(
+                f32::from(point.x) + delta.x,
+                f32::from(point.y) + delta.y,
+                point.on_curve_point,
+                point.last_point,
+            );
+        }
+
+        Some(())
+    } else if number_of_contours < 0 {
+        // Composite glyph.
+
+        let components = glyf::CompositeGlyphIter::new(s.tail()?);
+        let components_count = components.clone().count();
+
+        for (index, component) in components.enumerate() {
+            // Deltas are requested for each component, since outlining the previous one
+            // could have evicted them from the cache.
+            let t = *cache
+                .glyph_deltas(
+                    gvar_table,
+                    glyph_id,
+                    components_count,
+                    glyf::GlyphPointsIter::default(),
+                )?
+                .get(index)?;
+
+            let mut transform = builder.transform;
+
+            // Variation component offset should be applied only when
+            // the ARGS_ARE_XY_VALUES flag is set.
+            if component.flags.args_are_xy_values() {
+                transform = Transform::combine(transform, Transform::new_translate(t.x, t.y));
+            }
+
+            transform = Transform::combine(transform, component.transform);
+
+            let mut b = glyf::Builder::new(transform, builder.bbox, builder.builder);
+            if let Some(glyph_data) = glyf_table.get(component.glyph_id) {
+                outline_cached_impl(
+                    glyf_table,
+                    gvar_table,
+                    component.glyph_id,
+                    glyph_data,
+                    cache,
+                    depth + 1,
+                    &mut b,
+                )?;
+
+                // Take updated bbox.
+                builder.bbox = b.bbox;
+            }
+        }
+
+        Some(())
+    } else {
+        // An empty glyph.
+        None
+    }
+}
+
+// https://docs.microsoft.com/en-us/typography/opentype/spec/otvarcommonformats#tuple-variation-store-header
+pub(crate) struct TupleVariationStore<'a> {
+    count: u16,
+    shared_point_numbers: Option<PackedPointsIter<'a>>,
+    main_stream: Stream<'a>,
+    serialized_stream: Stream<'a>,
+}
+
+impl<'a> TupleVariationStore<'a> {
+    /// Parses a store header.
+    ///
+    /// `data` is the data the serialized data offset is relative to
+    /// and `s` must be positioned at the tuple variation count.
+    pub(crate) fn parse(data: &'a [u8], mut s: Stream<'a>) -> Option<Self> {
+        const SHARED_POINT_NUMBERS_FLAG: u16 = 0x8000;
+        const COUNT_MASK: u16 = 0x0FFF;
+
+        let tuple_variation_count = s.read::<u16>()?;
+        let data_offset = s.read::<Offset16>()?;
+
+        // 'The high 4 bits are flags, and the low 12 bits
+        // are the number of tuple variation tables for this glyph.'
+        let has_shared_point_numbers = tuple_variation_count & SHARED_POINT_NUMBERS_FLAG != 0;
+        let tuple_variation_count = tuple_variation_count & COUNT_MASK;
+
+        // 'The number of tuple variation tables can be any number between 1 and 4095.'
+        // No need to check for 4095, because this is 0x0FFF that we masked before.
+        if tuple_variation_count == 0 {
+            return None;
+        }
+
+        // A variation data consists of three parts: header + variation tuples + serialized data.
+        // Each tuple has it's own chunk in the serialized data.
+        // Because of that, we are using two parsing streams: one for tuples and one for serialized data.
+        // So we can parse them in parallel and avoid needless allocations.
+        let mut serialized_stream = Stream::new_at(data, data_offset.to_usize())?;
+
+        // All tuples in the variation data can reference the same point numbers,
+        // which are defined at the start of the serialized data.
+        let mut shared_point_numbers = None;
+        if has_shared_point_numbers {
+            shared_point_numbers = PackedPointsIter::new(&mut serialized_stream)?;
+        }
+
+        Some(TupleVariationStore {
+            count: tuple_variation_count,
+            shared_point_numbers,
+            main_stream: s,
+            serialized_stream,
+        })
     }
 
-    parse_variation_tuples(
-        tuple_variation_count,
+    /// Calls `f` for each tuple that is applicable to the provided coordinates.
+    pub(crate) fn parse_tuples(
+        self,
+        coordinates: &[NormalizedCoordinate],
+        shared_tuple_records: &LazyArray16<F2DOT14>,
+        points_len: u16,
+        f: &mut dyn FnMut(TupleVariation<'a>),
+    ) -> Option<()> {
+        parse_variation_tuples(
+            self.count,
+            coordinates,
+            shared_tuple_records,
+            self.shared_point_numbers,
+            points_len,
+            self.main_stream,
+            self.serialized_stream,
+            f,
+        )
+    }
+}
+
+fn parse_variation_data<'a>(
+    coordinates: &[NormalizedCoordinate],
+    shared_tuple_records: &LazyArray16<F2DOT14>,
+    points_len: u16,
+    data: &'a [u8],
+    first: u16,
+    batches: &mut [VariationTuples<'a>],
+) -> Option<u16> {
+    debug_assert!(core::mem::size_of::<VariationTuple>() <= 80);
+
+    let store = TupleVariationStore::parse(data, Stream::new(data))?;
+
+    let mut count = 0u16;
+    store.parse_tuples(
         coordinates,
         shared_tuple_records,
-        shared_point_numbers,
         points_len.checked_add(PHANTOM_POINTS_LEN as u16)?,
-        main_stream,
-        serialized_stream,
-        tuples,
-    )
+        &mut |tuple| {
+            // TODO: this
+            // Since the packed representation can include zero values,
+            // it is possible for a given point number to be repeated in the derived point number list.
+            // In that case, there will be multiple delta values in the deltas data
+            // associated with that point number. All of these deltas must be applied
+            // cumulatively to the given point.
+
+            // Tuples outside of the current batches are only counted.
+            let batch = count
+                .checked_sub(first)
+                .and_then(|n| batches.get_mut(usize::from(n / MAX_STACK_TUPLES_LEN)));
+            if let Some(tuples) = batch {
+                tuples.push(VariationTuple {
+                    set_points: tuple.point_numbers.map(SetPointsIter::new),
+                    deltas: PackedDeltasIter::new(
+                        tuple.scalar,
+                        tuple.deltas_count,
+                        tuple.deltas_data,
+                    ),
+                    prev_point: None,
+                });
+            }
+
+            count += 1;
+        },
+    )?;
+
+    Some(count)
 }
@@ -6,10 +6,40 @@ use core::num::NonZeroU16;
 use crate::parser::Stream;
 
 /// A [Maximum Profile Table](https://docs.microsoft.com/en-us/typography/opentype/spec/maxp).
+///
+/// All fields except `number_of_glyphs` are set only by the version 1.0 table
+/// and will be zero otherwise.
 #[derive(Clone, Copy, Debug)]
 pub struct Table {
     /// The total number of glyphs in the face.
     pub number_of_glyphs: NonZeroU16,
+    /// Maximum points in a non-composite glyph.
+    pub max_points: u16,
+    /// Maximum contours in a non-composite glyph.
+    pub max_contours: u16,
+    /// Maximum points in a composite glyph.
+    pub max_composite_points: u16,
+    /// Maximum contours in a composite glyph.
+    pub max_composite_contours: u16,
+    /// 1 if instructions do not use the twilight zone, 2 otherwise.
+    pub max_zones: u16,
+    /// Maximum points used in the twilight zone.
+    pub max_twilight_points: u16,
+    /// Number of Storage Area locations.
+    pub max_storage: u16,
+    /// Number of function definitions.
+    pub max_function_defs: u16,
+    /// Number of instruction definitions.
+    pub max_instruction_defs: u16,
+    /// Maximum stack depth across the font program, the CVT program
+    /// and all glyph instructions.
+    pub max_stack_elements: u16,
+    /// Maximum byte count for glyph instructions.
+    pub max_size_of_instructions: u16,
+    /// Maximum number of components referenced at "top level" for any composite glyph.
+    pub max_component_elements: u16,
+    /// Maximum levels of recursion.
+    pub max_component_depth: u16,
 }
 
 impl Table {
@@ -23,6 +53,65 @@ impl Table {
 
         let n = s.read::<u16>()?;
         let number_of_glyphs = NonZeroU16::new(n)?;
-        Some(Table { number_of_glyphs })
+
+        let mut table = Table {
+            number_of_glyphs,
+            max_points: 0,
+            max_contours: 0,
+            max_composite_points: 0,
+            max_composite_contours: 0,
+            max_zones: 0,
+            max_twilight_points: 0,
+            max_storage: 0,
+            max_function_defs: 0,
+            max_instruction_defs: 0,
+            max_stack_elements: 0,
+            max_size_of_instructions: 0,
+            max_component_elements: 0,
+            max_component_depth: 0,
+        };
+
+        // Some fonts have a version 1.0 table trimmed right after the number of glyphs.
+        // This is not an error, so we simply keep the defaults.
+        if version == 0x00010000 {
+            let _ = table.parse_version_1(&mut s);
+        }
+
+        Some(table)
+    }
+
+    fn parse_version_1(&mut self, s: &mut Stream) -> Option<()> {
+        let max_points = s.read::<u16>()?;
+        let max_contours = s.read::<u16>()?;
+        let max_composite_points = s.read::<u16>()?;
+        let max_composite_contours = s.read::<u16>()?;
+        let max_zones = s.read::<u16>()?;
+        let max_twilight_points = s.read::<u16>()?;
+        let max_storage = s.read::<u16>()?;
+        let max_function_defs = s.read::<u16>()?;
+        let max_instruction_defs = s.read::<u16>()?;
+        let max_stack_elements = s.read::<u16>()?;
+        let max_size_of_instructions = s.read::<u16>()?;
+        let max_component_elements = s.read::<u16>()?;
+        let max_component_depth = s.read::<u16>()?;
+
+        *self = Table {
+            number_of_glyphs: self.number_of_glyphs,
+            max_points,
+            max_contours,
+            max_composite_points,
+            max_composite_contours,
+            max_zones,
+            max_twilight_points,
+            max_storage,
+            max_function_defs,
+            max_instruction_defs,
+            max_stack_elements,
+            max_size_of_instructions,
+            max_component_elements,
+            max_component_depth,
+        };
+
+        Some(())
     }
 }
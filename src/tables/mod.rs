@@ -1,9 +1,10 @@
 pub mod cbdt;
 pub mod cblc;
-mod cff;
+pub(crate) mod cff;
 pub mod cmap;
 pub mod colr;
 pub mod cpal;
+pub mod cvt;
 pub mod glyf;
 pub mod head;
 pub mod hhea;
@@ -43,6 +44,8 @@ pub mod trak;
 #[cfg(feature = "variable-fonts")]
 pub mod avar;
 #[cfg(feature = "variable-fonts")]
+pub mod cvar;
+#[cfg(feature = "variable-fonts")]
 pub mod fvar;
 #[cfg(feature = "variable-fonts")]
 pub mod gvar;
@@ -50,6 +53,8 @@ pub mod gvar;
 pub mod hvar;
 #[cfg(feature = "variable-fonts")]
 pub mod mvar;
+#[cfg(all(feature = "opentype-layout", feature = "variable-fonts"))]
+pub mod varc;
 #[cfg(feature = "variable-fonts")]
 pub mod vvar;
 
@@ -0,0 +1,558 @@
+//! A [Variable Composites Table](
+//! https://github.com/harfbuzz/boring-expansion-spec/blob/main/VARC.md) implementation.
+
+use core::convert::TryFrom;
+
+#[cfg(not(feature = "std"))]
+use core_maths::CoreFloat;
+
+use crate::opentype_layout::Coverage;
+use crate::parser::{FromSlice, LazyArray32, NumFrom, Offset, Offset32, Stream, F2DOT14, U24};
+use crate::tables::cff::index::{parse_index, Index};
+use crate::var_store::{MultiItemVariationStore, TupleValues};
+use crate::{
+    GlyphId, NormalizedCoordinate, OutlineBuilder, Rect, RectF, Transform, MAX_VAR_COORDS,
+};
+
+// Variable component flags.
+const RESET_UNSPECIFIED_AXES: u32 = 1 << 0;
+const HAVE_AXES: u32 = 1 << 1;
+const AXIS_VALUES_HAVE_VARIATION: u32 = 1 << 2;
+const TRANSFORM_HAS_VARIATION: u32 = 1 << 3;
+const HAVE_TRANSLATE_X: u32 = 1 << 4;
+const HAVE_TRANSLATE_Y: u32 = 1 << 5;
+const HAVE_ROTATION: u32 = 1 << 6;
+const HAVE_CONDITION: u32 = 1 << 7;
+const HAVE_SCALE_X: u32 = 1 << 8;
+const HAVE_SCALE_Y: u32 = 1 << 9;
+const HAVE_TCENTER_X: u32 = 1 << 10;
+const HAVE_TCENTER_Y: u32 = 1 << 11;
+const GID_IS_24BIT: u32 = 1 << 12;
+const HAVE_SKEW_X: u32 = 1 << 13;
+const HAVE_SKEW_Y: u32 = 1 << 14;
+const RESERVED_MASK: u32 = !((1 << 15) - 1);
+
+// Transform fields in the order they are stored in.
+const TRANSFORM_FIELDS: [u32; 9] = [
+    HAVE_TRANSLATE_X,
+    HAVE_TRANSLATE_Y,
+    HAVE_ROTATION,
+    HAVE_SCALE_X,
+    HAVE_SCALE_Y,
+    HAVE_SKEW_X,
+    HAVE_SKEW_Y,
+    HAVE_TCENTER_X,
+    HAVE_TCENTER_Y,
+];
+
+const NO_VARIATION_INDEX: u32 = 0xFFFFFFFF;
+
+// Outlines a non-composite glyph at the specified coordinates.
+type OutlineBase<'a> = dyn FnMut(GlyphId, &[NormalizedCoordinate], &mut dyn OutlineBuilder) + 'a;
+
+// Limits the recursion depth, so we would not overflow the stack on malicious fonts.
+const MAX_NESTING_LEVEL: u8 = 32;
+const MAX_CONDITION_NESTING_LEVEL: u8 = 32;
+
+/// A [Variable Composites Table](
+/// https://github.com/harfbuzz/boring-expansion-spec/blob/main/VARC.md).
+///
+/// Glyphs covered by this table are built from other glyphs,
+/// each with its own variation coordinates and transform.
+#[derive(Clone, Copy)]
+pub struct Table<'a> {
+    /// Glyphs defined as variable composites.
+    pub coverage: Coverage<'a>,
+    var_store: Option<MultiItemVariationStore<'a>>,
+    conditions: Option<ConditionList<'a>>,
+    axis_indices: Index<'a>,
+    glyphs: Index<'a>,
+}
+
+impl<'a> Table<'a> {
+    /// Parses a table from raw data.
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        let mut s = Stream::new(data);
+        let major_version = s.read::<u16>()?;
+        s.skip::<u16>(); // minor version
+        if major_version != 1 {
+            return None;
+        }
+
+        let coverage_offset = s.read::<Offset32>()?;
+        let var_store_offset = s.read::<Option<Offset32>>()?;
+        let conditions_offset = s.read::<Option<Offset32>>()?;
+        let axis_indices_offset = s.read::<Option<Offset32>>()?;
+        let glyphs_offset = s.read::<Offset32>()?;
+
+        let coverage = Coverage::parse(data.get(coverage_offset.to_usize()..)?)?;
+
+        let var_store = match var_store_offset {
+            Some(offset) => Some(MultiItemVariationStore::parse(
+                data.get(offset.to_usize()..)?,
+            )?),
+            None => None,
+        };
+
+        let conditions = match conditions_offset {
+            Some(offset) => {
+                let data = data.get(offset.to_usize()..)?;
+                let mut s = Stream::new(data);
+                let count = s.read::<u32>()?;
+                Some(ConditionList {
+                    data,
+                    offsets: s.read_array32::<Offset32>(count)?,
+                })
+            }
+            None => None,
+        };
+
+        let axis_indices = match axis_indices_offset {
+            Some(offset) => parse_index::<u32>(&mut Stream::new_at(data, offset.to_usize())?)?,
+            None => Index::default(),
+        };
+
+        let glyphs = parse_index::<u32>(&mut Stream::new_at(data, glyphs_offset.to_usize())?)?;
+
+        Some(Table {
+            coverage,
+            var_store,
+            conditions,
+            axis_indices,
+            glyphs,
+        })
+    }
+
+    /// Outlines a variable composite glyph.
+    ///
+    /// Components that are not variable composites themselves
+    /// are outlined via `outline_base` using component's coordinates.
+    pub(crate) fn outline(
+        &self,
+        coordinates: &[NormalizedCoordinate],
+        glyph_id: GlyphId,
+        builder: &mut dyn OutlineBuilder,
+        outline_base: &mut OutlineBase,
+    ) -> Option<Rect> {
+        if coordinates.len() > MAX_VAR_COORDS {
+            return None;
+        }
+
+        let mut b = Builder {
+            builder,
+            transform: Transform::default(),
+            bbox: RectF::new(),
+        };
+        self.outline_impl(coordinates, glyph_id, 0, &mut b, outline_base)?;
+        b.bbox.to_rect()
+    }
+
+    fn outline_impl(
+        &self,
+        coordinates: &[NormalizedCoordinate],
+        glyph_id: GlyphId,
+        depth: u8,
+        builder: &mut Builder,
+        outline_base: &mut OutlineBase,
+    ) -> Option<()> {
+        if depth >= MAX_NESTING_LEVEL {
+            return None;
+        }
+
+        let index = self.coverage.get(glyph_id)?;
+        let mut s = Stream::new(self.glyphs.get(u32::from(index))?);
+        let parent_transform = builder.transform;
+        while !s.at_end() {
+            let component = Component::parse(&mut s, self.axis_indices)?;
+
+            if let Some(index) = component.condition_index {
+                if !self.evaluate_condition(index, coordinates) {
+                    continue;
+                }
+            }
+
+            // We do not support 24-bit glyph IDs.
+            let component_id = match u16::try_from(component.glyph_id) {
+                Ok(id) => GlyphId(id),
+                Err(_) => continue,
+            };
+
+            let mut component_coordinates = [NormalizedCoordinate::default(); MAX_VAR_COORDS];
+            let component_coordinates = &mut component_coordinates[..coordinates.len()];
+            self.component_coordinates(&component, coordinates, component_coordinates)?;
+
+            let transform = self.component_transform(&component, coordinates)?;
+            builder.transform = Transform::combine(parent_transform, transform);
+
+            // A component can reference its own glyph to use the base outline.
+            if component_id != glyph_id && self.coverage.contains(component_id) {
+                self.outline_impl(
+                    component_coordinates,
+                    component_id,
+                    depth + 1,
+                    builder,
+                    outline_base,
+                )?;
+            } else {
+                outline_base(component_id, component_coordinates, builder);
+            }
+        }
+
+        builder.transform = parent_transform;
+        Some(())
+    }
+
+    fn component_coordinates(
+        &self,
+        component: &Component,
+        coordinates: &[NormalizedCoordinate],
+        component_coordinates: &mut [NormalizedCoordinate],
+    ) -> Option<()> {
+        if component.flags & RESET_UNSPECIFIED_AXES == 0 {
+            component_coordinates.copy_from_slice(coordinates);
+        }
+
+        let count = usize::from(component.axis_count);
+        let mut values = component.axis_values;
+        if let Some(index) = component.axis_values_var_index {
+            self.add_deltas(index, coordinates, &mut values[..count])?;
+        }
+
+        for (index, value) in component.axis_indices[..count].iter().zip(&values[..count]) {
+            // Axes that are not present in the font are ignored.
+            if let Some(coord) = component_coordinates.get_mut(usize::from(*index)) {
+                *coord = NormalizedCoordinate::from(value.round().clamp(-16384.0, 16384.0) as i16);
+            }
+        }
+
+        Some(())
+    }
+
+    fn component_transform(
+        &self,
+        component: &Component,
+        coordinates: &[NormalizedCoordinate],
+    ) -> Option<Transform> {
+        let count = usize::from(component.transform_count);
+        let mut values = component.transform_values;
+        if let Some(index) = component.transform_var_index {
+            self.add_deltas(index, coordinates, &mut values[..count])?;
+        }
+
+        // Only present fields are stored.
+        let mut fields = [0.0; TRANSFORM_FIELDS.len()];
+        let mut values = values[..count].iter();
+        for (field, flag) in fields.iter_mut().zip(TRANSFORM_FIELDS.iter()) {
+            if component.flags & flag != 0 {
+                *field = *values.next()?;
+            }
+        }
+
+        let translate_x = fields[0];
+        let translate_y = fields[1];
+        // F4DOT12, in multiples of Pi.
+        let rotation = fields[2] / 4096.0;
+        // F6DOT10.
+        let scale_x = if component.flags & HAVE_SCALE_X != 0 {
+            fields[3] / 1024.0
+        } else {
+            1.0
+        };
+        let scale_y = if component.flags & HAVE_SCALE_Y != 0 {
+            fields[4] / 1024.0
+        } else {
+            scale_x
+        };
+        // F4DOT12, in multiples of Pi.
+        let skew_x = fields[5] / 4096.0;
+        let skew_y = fields[6] / 4096.0;
+        let center_x = fields[7];
+        let center_y = fields[8];
+
+        let mut ts = Transform::new_translate(translate_x + center_x, translate_y + center_y);
+        if rotation != 0.0 {
+            ts = Transform::combine(ts, Transform::new_rotate(rotation));
+        }
+
+        if scale_x != 1.0 || scale_y != 1.0 {
+            ts = Transform::combine(ts, Transform::new_scale(scale_x, scale_y));
+        }
+
+        if skew_x != 0.0 || skew_y != 0.0 {
+            ts = Transform::combine(ts, Transform::new_skew(skew_x, skew_y));
+        }
+
+        if center_x != 0.0 || center_y != 0.0 {
+            ts = Transform::combine(ts, Transform::new_translate(-center_x, -center_y));
+        }
+
+        Some(ts)
+    }
+
+    fn add_deltas(
+        &self,
+        index: u32,
+        coordinates: &[NormalizedCoordinate],
+        values: &mut [f32],
+    ) -> Option<()> {
+        if index == NO_VARIATION_INDEX {
+            return Some(());
+        }
+
+        self.var_store?.add_deltas(index, coordinates, values)
+    }
+
+    fn evaluate_condition(&self, index: u32, coordinates: &[NormalizedCoordinate]) -> bool {
+        let conditions = match self.conditions {
+            Some(v) => v,
+            None => return false,
+        };
+
+        conditions
+            .offsets
+            .get(index)
+            .and_then(|offset| {
+                self.evaluate_condition_at(conditions.data, offset.to_usize(), coordinates, 0)
+            })
+            .unwrap_or(false)
+    }
+
+    fn evaluate_condition_at(
+        &self,
+        data: &[u8],
+        offset: usize,
+        coordinates: &[NormalizedCoordinate],
+        depth: u8,
+    ) -> Option<bool> {
+        if depth >= MAX_CONDITION_NESTING_LEVEL {
+            return None;
+        }
+
+        let data = data.get(offset..)?;
+        let mut s = Stream::new(data);
+        match s.read::<u16>()? {
+            1 => {
+                // Axis range.
+                let axis_index = s.read::<u16>()?;
+                let min = s.read::<F2DOT14>()?.0;
+                let max = s.read::<F2DOT14>()?.0;
+                let coord = coordinates
+                    .get(usize::from(axis_index))
+                    .map(|c| c.get())
+                    .unwrap_or(0);
+                Some(min <= coord && coord <= max)
+            }
+            2 => {
+                // Variable value.
+                let value = s.read::<i16>()?;
+                let var_index = s.read::<u32>()?;
+                let mut value = [f32::from(value)];
+                self.add_deltas(var_index, coordinates, &mut value)?;
+                Some(value[0] > 0.0)
+            }
+            format @ 3 | format @ 4 => {
+                // AND and OR.
+                let is_and = format == 3;
+                let count = s.read::<u8>()?;
+                for _ in 0..count {
+                    let offset = usize::num_from(s.read::<U24>()?.0);
+                    if self.evaluate_condition_at(data, offset, coordinates, depth + 1)? != is_and {
+                        return Some(!is_and);
+                    }
+                }
+
+                Some(is_and)
+            }
+            5 => {
+                // Negation.
+                let offset = usize::num_from(s.read::<U24>()?.0);
+                self.evaluate_condition_at(data, offset, coordinates, depth + 1)
+                    .map(|v| !v)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Debug for Table<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Table {{ ... }}")
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ConditionList<'a> {
+    data: &'a [u8],
+    offsets: LazyArray32<'a, Offset32>,
+}
+
+struct Component {
+    flags: u32,
+    glyph_id: u32,
+    condition_index: Option<u32>,
+    axis_count: u8,
+    axis_indices: [u16; MAX_VAR_COORDS],
+    axis_values: [f32; MAX_VAR_COORDS],
+    axis_values_var_index: Option<u32>,
+    transform_count: u8,
+    transform_values: [f32; TRANSFORM_FIELDS.len()],
+    transform_var_index: Option<u32>,
+}
+
+impl Component {
+    fn parse(s: &mut Stream, axis_indices_list: Index) -> Option<Self> {
+        let flags = read_u32_var(s)?;
+        let glyph_id = if flags & GID_IS_24BIT != 0 {
+            s.read::<U24>()?.0
+        } else {
+            u32::from(s.read::<u16>()?)
+        };
+
+        let condition_index = if flags & HAVE_CONDITION != 0 {
+            Some(read_u32_var(s)?)
+        } else {
+            None
+        };
+
+        let mut axis_count = 0;
+        let mut axis_indices = [0; MAX_VAR_COORDS];
+        let mut axis_values = [0.0; MAX_VAR_COORDS];
+        if flags & HAVE_AXES != 0 {
+            let index = read_u32_var(s)?;
+            for axis_index in TupleValues::new(axis_indices_list.get(index)?) {
+                *axis_indices.get_mut(axis_count)? = u16::try_from(axis_index).ok()?;
+                axis_count += 1;
+            }
+
+            // The number of values is defined by the number of axis indices.
+            let mut values = TupleValues::new(s.tail()?);
+            for value in &mut axis_values[..axis_count] {
+                *value = values.next()? as f32;
+            }
+
+            if !values.is_run_finished() {
+                return None;
+            }
+
+            s.advance(values.offset());
+        }
+
+        let axis_values_var_index = if flags & AXIS_VALUES_HAVE_VARIATION != 0 {
+            Some(read_u32_var(s)?)
+        } else {
+            None
+        };
+
+        let transform_var_index = if flags & TRANSFORM_HAS_VARIATION != 0 {
+            Some(read_u32_var(s)?)
+        } else {
+            None
+        };
+
+        let mut transform_count = 0;
+        let mut transform_values = [0.0; TRANSFORM_FIELDS.len()];
+        for flag in TRANSFORM_FIELDS.iter() {
+            if flags & flag != 0 {
+                transform_values[transform_count] = f32::from(s.read::<i16>()?);
+                transform_count += 1;
+            }
+        }
+
+        // Each reserved flag indicates an additional field, which we have to skip.
+        for _ in 0..(flags & RESERVED_MASK).count_ones() {
+            read_u32_var(s)?;
+        }
+
+        Some(Component {
+            flags,
+            glyph_id,
+            condition_index,
+            axis_count: axis_count as u8,
+            axis_indices,
+            axis_values,
+            axis_values_var_index,
+            transform_count: transform_count as u8,
+            transform_values,
+            transform_var_index,
+        })
+    }
+}
+
+// A variable-length `uint32`, where the number of leading set bits
+// in the first byte indicates the number of additional bytes.
+fn read_u32_var(s: &mut Stream) -> Option<u32> {
+    let first = u32::from(s.read::<u8>()?);
+    let (mut value, len) = if first < 0x80 {
+        (first, 0)
+    } else if first < 0xC0 {
+        (first & 0x3F, 1)
+    } else if first < 0xE0 {
+        (first & 0x1F, 2)
+    } else if first < 0xF0 {
+        (first & 0x0F, 3)
+    } else {
+        (0, 4)
+    };
+
+    for _ in 0..len {
+        value = (value << 8) | u32::from(s.read::<u8>()?);
+    }
+
+    Some(value)
+}
+
+// Applies a component transform and calculates the bbox.
+struct Builder<'a> {
+    builder: &'a mut dyn OutlineBuilder,
+    transform: Transform,
+    bbox: RectF,
+}
+
+impl OutlineBuilder for Builder<'_> {
+    #[inline]
+    fn move_to(&mut self, mut x: f32, mut y: f32) {
+        self.transform.apply_to(&mut x, &mut y);
+        self.bbox.extend_by(x, y);
+        self.builder.move_to(x, y);
+    }
+
+    #[inline]
+    fn line_to(&mut self, mut x: f32, mut y: f32) {
+        self.transform.apply_to(&mut x, &mut y);
+        self.bbox.extend_by(x, y);
+        self.builder.line_to(x, y);
+    }
+
+    #[inline]
+    fn quad_to(&mut self, mut x1: f32, mut y1: f32, mut x: f32, mut y: f32) {
+        self.transform.apply_to(&mut x1, &mut y1);
+        self.transform.apply_to(&mut x, &mut y);
+        self.bbox.extend_by(x1, y1);
+        self.bbox.extend_by(x, y);
+        self.builder.quad_to(x1, y1, x, y);
+    }
+
+    #[inline]
+    fn curve_to(
+        &mut self,
+        mut x1: f32,
+        mut y1: f32,
+        mut x2: f32,
+        mut y2: f32,
+        mut x: f32,
+        mut y: f32,
+    ) {
+        self.transform.apply_to(&mut x1, &mut y1);
+        self.transform.apply_to(&mut x2, &mut y2);
+        self.transform.apply_to(&mut x, &mut y);
+        self.bbox.extend_by(x1, y1);
+        self.bbox.extend_by(x2, y2);
+        self.bbox.extend_by(x, y);
+        self.builder.curve_to(x1, y1, x2, y2, x, y);
+    }
+
+    #[inline]
+    fn close(&mut self) {
+        self.builder.close();
+    }
+}
@@ -0,0 +1,464 @@
+//! Font validation.
+//!
+//! Parsing functions in this crate do not explain why something has failed.
+//! A malformed table is usually reported as `None` or ignored altogether.
+//! [`validate`] walks the tables supported by this crate instead
+//! and reports everything suspicious as a list of [`Diagnostic`]s.
+//!
+//! ```no_run
+//! let data = std::fs::read("font.ttf").unwrap();
+//! let face = ttf_parser::RawFace::parse(&data, 0).unwrap();
+//! for diagnostic in ttf_parser::validate::validate(&face) {
+//!     println!("{}", diagnostic);
+//! }
+//! ```
+
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use crate::parser::{NumFrom, Stream};
+use crate::{
+    cmap, loca, CFFError, DummyOutline, Face, FailedTables, GlyphId, RawFace,
+    TableParseErrorReason, Tag,
+};
+
+/// A diagnostic severity.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    /// The font violates the spec, but can still be used.
+    Warning,
+    /// The font data is malformed and will be ignored, at least partially.
+    Error,
+}
+
+impl core::fmt::Display for Severity {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A validation diagnostic.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    /// A table the diagnostic belongs to.
+    pub tag: Tag,
+    /// An offset of the problematic data from the start of the font data.
+    ///
+    /// `None` when the diagnostic isn't tied to a specific location,
+    /// like a missing table.
+    pub offset: Option<usize>,
+    /// A diagnostic severity.
+    pub severity: Severity,
+    /// A human-readable description.
+    pub message: String,
+}
+
+impl core::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} in '{}'", self.severity, self.tag)?;
+        if let Some(offset) = self.offset {
+            write!(f, " at {:#x}", offset)?;
+        }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Validates a font.
+///
+/// Checks the table directory, table checksums, `head.checkSumAdjustment`,
+/// the presence of required tables
+/// and parses every table supported by this crate.
+/// Some tables are checked further:
+///
+/// - `cmap` format 4 segments must be sorted.
+/// - `loca` offsets must be monotonic and must not exceed the `glyf` length.
+/// - `glyf`, `CFF ` and `CFF2` glyphs must be outlinable.
+///
+/// Diagnostics are returned in no particular order.
+/// An empty list means that no issues were found.
+pub fn validate(face: &RawFace) -> Vec<Diagnostic> {
+    let mut v = Validator {
+        data: face.data,
+        diagnostics: Vec::new(),
+    };
+
+    v.check_table_records(face);
+
+    for tag in &[b"head", b"hhea", b"maxp"] {
+        if face.table(Tag::from_bytes(tag)).is_none() {
+            v.error(Tag::from_bytes(tag), None, "missing required table");
+        }
+    }
+
+    for tag in &[b"cmap", b"hmtx", b"name", b"OS/2", b"post"] {
+        if face.table(Tag::from_bytes(tag)).is_none() {
+            v.warning(Tag::from_bytes(tag), None, "missing required table");
+        }
+    }
+
+    let (parsed, failed) = match Face::from_raw_face_with_report(*face) {
+        Ok(v) => v,
+        Err(_) => {
+            for tag in &[b"head", b"hhea", b"maxp"] {
+                if let Some(data) = face.table(Tag::from_bytes(tag)) {
+                    v.malformed(Tag::from_bytes(tag), data);
+                }
+            }
+
+            return v.diagnostics;
+        }
+    };
+
+    v.check_tables(&failed);
+    if let Some(data) = face.table(Tag::from_bytes(b"cmap")) {
+        v.check_cmap(data, parsed.tables().cmap);
+    }
+    if let Some(data) = face.table(Tag::from_bytes(b"loca")) {
+        v.check_loca(data, face.table(Tag::from_bytes(b"glyf")), &parsed);
+    }
+    v.check_glyphs(face, &parsed);
+
+    v.diagnostics
+}
+
+struct Validator<'a> {
+    data: &'a [u8],
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'a> {
+    fn push(&mut self, tag: Tag, offset: Option<usize>, severity: Severity, message: String) {
+        self.diagnostics.push(Diagnostic {
+            tag,
+            offset,
+            severity,
+            message,
+        });
+    }
+
+    fn warning(&mut self, tag: Tag, offset: Option<usize>, message: &str) {
+        self.push(tag, offset, Severity::Warning, message.to_string());
+    }
+
+    fn error(&mut self, tag: Tag, offset: Option<usize>, message: &str) {
+        self.push(tag, offset, Severity::Error, message.to_string());
+    }
+
+    fn malformed(&mut self, tag: Tag, table: &[u8]) {
+        let offset = self.offset_of(table);
+        self.error(tag, Some(offset), "malformed table");
+    }
+
+    /// Returns an offset of a subslice from the start of the font data.
+    fn offset_of(&self, data: &[u8]) -> usize {
+        (data.as_ptr() as usize).wrapping_sub(self.data.as_ptr() as usize)
+    }
+
+    fn check_table_records(&mut self, face: &RawFace) {
+        let mut prev_tag: Option<Tag> = None;
+        for record in face.table_records {
+            if let Some(prev_tag) = prev_tag {
+                if record.tag == prev_tag {
+                    self.warning(record.tag, None, "duplicated table record");
+                } else if record.tag < prev_tag {
+                    self.warning(record.tag, None, "table records are not sorted by tag");
+                }
+            }
+            prev_tag = Some(record.tag);
+
+            let start = usize::num_from(record.offset);
+            let sum = match face.calculate_table_checksum(&record) {
+                Some(sum) => sum,
+                None => {
+                    self.error(record.tag, Some(start), "table data is out of bounds");
+                    continue;
+                }
+            };
+
+            if start % 4 != 0 {
+                self.warning(record.tag, Some(start), "table is not 4-byte aligned");
+            }
+
+            if sum != record.check_sum {
+                self.push(
+                    record.tag,
+                    Some(start),
+                    Severity::Warning,
+                    format!(
+                        "checksum mismatch: expected {:#010x}, got {:#010x}",
+                        record.check_sum, sum
+                    ),
+                );
+            }
+        }
+
+        // Collections usually keep adjustments of the original fonts.
+        if self.data.starts_with(b"ttcf") {
+            return;
+        }
+
+        if let Some(head) = face.table(Tag::from_bytes(b"head")) {
+            if face.is_checksum_adjustment_valid() == Some(false) {
+                let offset = self.offset_of(head) + 8;
+                self.warning(
+                    Tag::from_bytes(b"head"),
+                    Some(offset),
+                    "checkSumAdjustment mismatch",
+                );
+            }
+        }
+    }
+
+    fn check_tables(&mut self, failed: &FailedTables) {
+        for e in failed.iter() {
+            let offset = Some(usize::num_from(e.table_offset));
+            match e.reason {
+                // Already reported.
+                TableParseErrorReason::OutOfBounds => {}
+                TableParseErrorReason::MissingDependency(tag) => self.push(
+                    e.tag,
+                    offset,
+                    Severity::Error,
+                    format!("table requires a valid '{}' table", tag),
+                ),
+                TableParseErrorReason::Malformed => self.error(e.tag, offset, "malformed table"),
+            }
+        }
+    }
+
+    fn check_cmap(&mut self, data: &[u8], table: Option<cmap::Table>) {
+        let tag = Tag::from_bytes(b"cmap");
+        let table = match table {
+            Some(table) => table,
+            None => return, // already reported
+        };
+
+        let mut s = Stream::new(data);
+        s.skip::<u16>(); // version
+        let count = s.read::<u16>().unwrap_or(0);
+        let mut offsets = Vec::new();
+        for index in 0..count {
+            s.skip::<u16>(); // platformID
+            s.skip::<u16>(); // encodingID
+            let offset = match s.read::<u32>() {
+                Some(offset) => usize::num_from(offset),
+                None => break,
+            };
+
+            if table.subtables.get(index).is_none() {
+                let offset = self.offset_of(data) + offset;
+                self.error(tag, Some(offset), "malformed or unsupported subtable");
+                continue;
+            }
+
+            // Subtables can be shared by multiple encoding records.
+            if !offsets.contains(&offset) {
+                offsets.push(offset);
+            }
+        }
+
+        for offset in offsets {
+            if let Some(subtable) = data.get(offset..) {
+                if Stream::read_at::<u16>(subtable, 0) == Some(4) {
+                    self.check_cmap_format4(subtable);
+                }
+            }
+        }
+    }
+
+    fn check_cmap_format4(&mut self, data: &[u8]) {
+        let tag = Tag::from_bytes(b"cmap");
+        let base = self.offset_of(data);
+
+        // The subtable is already parsed, so the data is valid.
+        let seg_count = Stream::read_at::<u16>(data, 6).unwrap_or(0) / 2;
+        let end_codes_offset = 14;
+        let start_codes_offset = end_codes_offset + usize::from(seg_count) * 2 + 2;
+
+        let mut prev_end_code = None;
+        for i in 0..usize::from(seg_count) {
+            let end_offset = end_codes_offset + i * 2;
+            let start_offset = start_codes_offset + i * 2;
+            let (end_code, start_code) = match (
+                Stream::read_at::<u16>(data, end_offset),
+                Stream::read_at::<u16>(data, start_offset),
+            ) {
+                (Some(end_code), Some(start_code)) => (end_code, start_code),
+                _ => return,
+            };
+
+            if let Some(prev_end_code) = prev_end_code {
+                if start_code <= prev_end_code {
+                    self.push(
+                        tag,
+                        Some(base + start_offset),
+                        Severity::Error,
+                        format!("format 4 segment {} is not sorted or overlaps", i),
+                    );
+                }
+            }
+
+            if start_code > end_code {
+                self.push(
+                    tag,
+                    Some(base + start_offset),
+                    Severity::Error,
+                    format!("format 4 segment {} starts after its end", i),
+                );
+            }
+
+            prev_end_code = Some(end_code);
+        }
+
+        if prev_end_code != Some(0xFFFF) {
+            self.warning(
+                tag,
+                Some(base + end_codes_offset),
+                "format 4 subtable doesn't end with a 0xFFFF segment",
+            );
+        }
+    }
+
+    fn check_loca(&mut self, data: &[u8], glyf: Option<&[u8]>, parsed: &Face) {
+        let tag = Tag::from_bytes(b"loca");
+        let tables = parsed.tables();
+        let number_of_glyphs = tables.maxp.number_of_glyphs;
+        let table = match loca::Table::parse(
+            number_of_glyphs,
+            tables.head.index_to_location_format,
+            data,
+        ) {
+            Some(table) => table,
+            None => return, // already reported
+        };
+
+        let item_size = match table {
+            loca::Table::Short(_) => 2,
+            loca::Table::Long(_) => 4,
+        };
+        let get = |i: u16| match table {
+            loca::Table::Short(ref array) => array.get(i).map(|n| usize::from(n) * 2),
+            loca::Table::Long(ref array) => array.get(i).map(usize::num_from),
+        };
+
+        if u32::from(table.len()) < u32::from(number_of_glyphs.get()) + 1 {
+            self.push(
+                tag,
+                None,
+                Severity::Warning,
+                format!(
+                    "expected {} offsets, got {}",
+                    u32::from(number_of_glyphs.get()) + 1,
+                    table.len()
+                ),
+            );
+        }
+
+        let base = self.offset_of(data);
+        let glyf_len = glyf.map(|data| data.len()).unwrap_or(0);
+        let mut prev = 0;
+        for i in 0..table.len() {
+            let offset = match get(i) {
+                Some(offset) => offset,
+                None => break,
+            };
+
+            let location = Some(base + usize::from(i) * item_size);
+            if offset < prev {
+                self.push(
+                    tag,
+                    location,
+                    Severity::Error,
+                    format!("offset {} is smaller than the previous one", i),
+                );
+            }
+
+            if offset > glyf_len {
+                self.push(
+                    tag,
+                    location,
+                    Severity::Error,
+                    format!("offset {} is past the end of the 'glyf' table", i),
+                );
+            }
+
+            prev = offset;
+        }
+    }
+
+    fn check_glyphs(&mut self, face: &RawFace, parsed: &Face) {
+        let tables = parsed.tables();
+
+        if let (Some(glyf), Some(glyf_data), Some(loca_data)) = (
+            tables.glyf,
+            face.table(Tag::from_bytes(b"glyf")),
+            face.table(Tag::from_bytes(b"loca")),
+        ) {
+            let loca = loca::Table::parse(
+                tables.maxp.number_of_glyphs,
+                tables.head.index_to_location_format,
+                loca_data,
+            );
+
+            for id in 0..tables.maxp.number_of_glyphs.get() {
+                let glyph_id = GlyphId(id);
+                let range = match loca.and_then(|loca| loca.glyph_range(glyph_id)) {
+                    Some(range) => range,
+                    None => continue, // empty glyph
+                };
+
+                if glyf.outline(glyph_id, &mut DummyOutline).is_none() {
+                    let offset = self.offset_of(glyf_data) + range.start;
+                    self.push(
+                        Tag::from_bytes(b"glyf"),
+                        Some(offset),
+                        Severity::Error,
+                        format!("glyph {} is malformed", id),
+                    );
+                }
+            }
+        }
+
+        if let Some(cff) = tables.cff {
+            for id in 0..cff.number_of_glyphs() {
+                let glyph_id = GlyphId(id);
+                if let Err(e) = cff.outline(glyph_id, &mut DummyOutline) {
+                    let offset = cff.char_string(glyph_id).map(|data| self.offset_of(data));
+                    self.char_string_error(Tag::from_bytes(b"CFF "), offset, id, e);
+                }
+            }
+        }
+
+        #[cfg(feature = "variable-fonts")]
+        {
+            if let Some(cff2) = tables.cff2 {
+                let count = cff2.number_of_glyphs().min(u32::from(u16::MAX)) as u16;
+                for id in 0..count {
+                    let glyph_id = GlyphId(id);
+                    if let Err(e) = cff2.outline(&[], glyph_id, &mut DummyOutline) {
+                        let offset = cff2.char_string(glyph_id).map(|data| self.offset_of(data));
+                        self.char_string_error(Tag::from_bytes(b"CFF2"), offset, id, e);
+                    }
+                }
+            }
+        }
+    }
+
+    fn char_string_error(&mut self, tag: Tag, offset: Option<usize>, id: u16, e: CFFError) {
+        // Empty glyphs, like space, have no bbox.
+        if e == CFFError::ZeroBBox {
+            return;
+        }
+
+        self.push(
+            tag,
+            offset,
+            Severity::Error,
+            format!("glyph {} charstring is malformed: {:?}", id, e),
+        );
+    }
+}
@@ -5,6 +5,9 @@
 use crate::parser::{FromData, LazyArray16, NumFrom, Stream};
 use crate::NormalizedCoordinate;
 
+#[cfg(feature = "opentype-layout")]
+use crate::tables::cff::index::{parse_index, Index};
+
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct ItemVariationStore<'a> {
     data: &'a [u8],
@@ -60,7 +63,7 @@ impl<'a> ItemVariationStore<'a> {
         })
     }
 
-    pub fn region_indices(&self, index: u16) -> Option<LazyArray16<u16>> {
+    pub fn region_indices(&self, index: u16) -> Option<LazyArray16<'_, u16>> {
         // Offsets in bytes from the start of the item variation store
         // to each item variation data subtable.
         let offset = self.data_offsets.get(index)?;
@@ -209,3 +212,193 @@ impl FromData for RegionAxisCoordinatesRecord {
         })
     }
 }
+
+/// A [Multi Item Variation Store](
+/// https://github.com/harfbuzz/boring-expansion-spec/blob/main/VARC.md#multiitemvariationstore).
+///
+/// Unlike [`ItemVariationStore`], stores a list of deltas per item
+/// and uses sparse regions.
+#[cfg(feature = "opentype-layout")]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MultiItemVariationStore<'a> {
+    data: &'a [u8],
+    data_offsets: LazyArray16<'a, u32>,
+    regions: SparseVariationRegionList<'a>,
+}
+
+#[cfg(feature = "opentype-layout")]
+impl<'a> MultiItemVariationStore<'a> {
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        let mut s = Stream::new(data);
+        let format = s.read::<u16>()?;
+        if format != 1 {
+            return None;
+        }
+
+        let region_list_offset = s.read::<u32>()?;
+        let count = s.read::<u16>()?;
+        let data_offsets = s.read_array16::<u32>(count)?;
+
+        let regions = {
+            let data = data.get(usize::num_from(region_list_offset)..)?;
+            let mut s = Stream::new(data);
+            let count = s.read::<u16>()?;
+            SparseVariationRegionList {
+                data,
+                offsets: s.read_array16::<u32>(count)?,
+            }
+        };
+
+        Some(MultiItemVariationStore {
+            data,
+            data_offsets,
+            regions,
+        })
+    }
+
+    /// Adds deltas of an item to `values`.
+    ///
+    /// The number of deltas must be equal to the number of `values`.
+    pub fn add_deltas(
+        &self,
+        index: u32,
+        coordinates: &[NormalizedCoordinate],
+        values: &mut [f32],
+    ) -> Option<()> {
+        let outer_index = (index >> 16) as u16;
+        let inner_index = index & 0xFFFF;
+
+        let offset = self.data_offsets.get(outer_index)?;
+        let mut s = Stream::new_at(self.data, usize::num_from(offset))?;
+        let format = s.read::<u8>()?;
+        if format != 1 {
+            return None;
+        }
+
+        let region_index_count = s.read::<u16>()?;
+        let region_indices = s.read_array16::<u16>(region_index_count)?;
+        let delta_sets: Index = parse_index::<u32>(&mut s)?;
+
+        // Deltas are stored per region.
+        let mut deltas = TupleValues::new(delta_sets.get(inner_index)?);
+        for region_index in region_indices {
+            let scalar = self.regions.evaluate_region(region_index, coordinates);
+            for value in values.iter_mut() {
+                let delta = deltas.next()?;
+                if scalar != 0.0 {
+                    *value += delta as f32 * scalar;
+                }
+            }
+        }
+
+        Some(())
+    }
+}
+
+#[cfg(feature = "opentype-layout")]
+#[derive(Clone, Copy, Debug)]
+struct SparseVariationRegionList<'a> {
+    data: &'a [u8],
+    offsets: LazyArray16<'a, u32>,
+}
+
+#[cfg(feature = "opentype-layout")]
+impl SparseVariationRegionList<'_> {
+    fn evaluate_region(&self, index: u16, coordinates: &[NormalizedCoordinate]) -> f32 {
+        self.evaluate_region_impl(index, coordinates).unwrap_or(0.0)
+    }
+
+    fn evaluate_region_impl(
+        &self,
+        index: u16,
+        coordinates: &[NormalizedCoordinate],
+    ) -> Option<f32> {
+        let offset = self.offsets.get(index)?;
+        let mut s = Stream::new_at(self.data, usize::num_from(offset))?;
+        let count = s.read::<u16>()?;
+
+        let mut v = 1.0;
+        for _ in 0..count {
+            // Unlike in `VariationRegionList`, each record has an explicit axis index.
+            let axis_index = s.read::<u16>()?;
+            let region = s.read::<RegionAxisCoordinatesRecord>()?;
+            let coord = coordinates
+                .get(usize::from(axis_index))
+                .map(|c| c.get())
+                .unwrap_or(0);
+
+            let factor = region.evaluate_axis(coord);
+            if factor == 0.0 {
+                return Some(0.0);
+            }
+
+            v *= factor;
+        }
+
+        Some(v)
+    }
+}
+
+/// An iterator over [packed values](
+/// https://github.com/harfbuzz/boring-expansion-spec/blob/main/VARC.md#tuplevalues).
+///
+/// Same as packed deltas in `gvar`, but also supports 32-bit values.
+#[cfg(feature = "opentype-layout")]
+#[derive(Clone)]
+pub(crate) struct TupleValues<'a> {
+    s: Stream<'a>,
+    control: u8,
+    run_left: u8,
+}
+
+#[cfg(feature = "opentype-layout")]
+impl<'a> TupleValues<'a> {
+    const ARE_ZEROS: u8 = 0x80;
+    const ARE_WORDS: u8 = 0x40;
+    const ARE_LONGS: u8 = 0xC0;
+    const SIZE_MASK: u8 = 0xC0;
+    const RUN_COUNT_MASK: u8 = 0x3F;
+
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        TupleValues {
+            s: Stream::new(data),
+            control: 0,
+            run_left: 0,
+        }
+    }
+
+    /// Returns the number of bytes consumed so far.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.s.offset()
+    }
+
+    /// Checks that the last run was fully consumed.
+    #[inline]
+    pub fn is_run_finished(&self) -> bool {
+        self.run_left == 0
+    }
+}
+
+#[cfg(feature = "opentype-layout")]
+impl Iterator for TupleValues<'_> {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.run_left == 0 {
+            self.control = self.s.read::<u8>()?;
+            self.run_left = (self.control & Self::RUN_COUNT_MASK) + 1;
+        }
+
+        let value = match self.control & Self::SIZE_MASK {
+            Self::ARE_ZEROS => 0,
+            Self::ARE_WORDS => i32::from(self.s.read::<i16>()?),
+            Self::ARE_LONGS => self.s.read::<i32>()?,
+            _ => i32::from(self.s.read::<i8>()?),
+        };
+
+        self.run_left -= 1;
+        Some(value)
+    }
+}
@@ -0,0 +1,270 @@
+//! A [WOFF 1.0](https://www.w3.org/TR/WOFF/) container decoder.
+//!
+//! WOFF stores tables compressed with zlib, so unlike the rest of the crate,
+//! decoding requires heap allocations. The decoded font data is stored by [`Woff`]
+//! and can be parsed via [`Woff::raw_face`] or [`Woff::face`].
+//!
+//! ```no_run
+//! let data = std::fs::read("font.woff").unwrap();
+//! let woff = ttf_parser::woff::Woff::parse(&data).unwrap();
+//! let face = woff.face().unwrap();
+//! println!("{}", face.number_of_glyphs());
+//! ```
+
+use std::vec::Vec;
+
+use crate::parser::{FromData, NumFrom, Stream};
+use crate::writer::{pad4, write_sfnt_header, SFNT_HEADER_SIZE, TABLE_RECORD_SIZE};
+use crate::{Face, FaceParsingError, RawFace, Tag};
+
+const SIGNATURE: u32 = 0x774F4646; // wOFF
+
+/// Checks that the data starts with a WOFF 1.0 signature.
+#[inline]
+pub fn is_woff(data: &[u8]) -> bool {
+    Stream::new(data).read::<u32>() == Some(SIGNATURE)
+}
+
+struct Header {
+    flavor: u32,
+    num_tables: u16,
+    major_version: u16,
+    minor_version: u16,
+    meta_offset: u32,
+    meta_length: u32,
+    meta_orig_length: u32,
+    private_offset: u32,
+    private_length: u32,
+}
+
+impl Header {
+    fn parse(s: &mut Stream) -> Option<Self> {
+        let flavor = s.read::<u32>()?;
+        s.skip::<u32>(); // length
+        let num_tables = s.read::<u16>()?;
+        s.skip::<u16>(); // reserved
+        s.skip::<u32>(); // total sfnt size
+        Some(Header {
+            flavor,
+            num_tables,
+            major_version: s.read::<u16>()?,
+            minor_version: s.read::<u16>()?,
+            meta_offset: s.read::<u32>()?,
+            meta_length: s.read::<u32>()?,
+            meta_orig_length: s.read::<u32>()?,
+            private_offset: s.read::<u32>()?,
+            private_length: s.read::<u32>()?,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct TableDirectoryEntry {
+    tag: Tag,
+    offset: u32,
+    comp_length: u32,
+    orig_length: u32,
+    orig_checksum: u32,
+}
+
+impl FromData for TableDirectoryEntry {
+    const SIZE: usize = 20;
+
+    #[inline]
+    fn parse(data: &[u8]) -> Option<Self> {
+        let mut s = Stream::new(data);
+        Some(TableDirectoryEntry {
+            tag: s.read::<Tag>()?,
+            offset: s.read::<u32>()?,
+            comp_length: s.read::<u32>()?,
+            orig_length: s.read::<u32>()?,
+            orig_checksum: s.read::<u32>()?,
+        })
+    }
+}
+
+/// A decoded WOFF 1.0 font.
+#[derive(Clone)]
+pub struct Woff {
+    data: Vec<u8>,
+    flavor: u32,
+    major_version: u16,
+    minor_version: u16,
+    metadata: Option<Vec<u8>>,
+    private_data: Option<Vec<u8>>,
+}
+
+impl Woff {
+    /// Decodes a WOFF 1.0 font.
+    ///
+    /// Returns [`FaceParsingError::UnknownMagic`] when data doesn't start with `wOFF`.
+    pub fn parse(data: &[u8]) -> Result<Self, FaceParsingError> {
+        let mut s = Stream::new(data);
+        if s.read::<u32>() != Some(SIGNATURE) {
+            return Err(FaceParsingError::UnknownMagic);
+        }
+
+        let header = Header::parse(&mut s).ok_or(FaceParsingError::MalformedFont)?;
+        let entries = s
+            .read_array16::<TableDirectoryEntry>(header.num_tables)
+            .ok_or(FaceParsingError::MalformedFont)?;
+
+        let mut sfnt = Vec::new();
+        write_sfnt_header(header.flavor, entries.len(), &mut sfnt);
+
+        // Table records must be sorted by tag, just like WOFF table directory entries.
+        let mut offset = SFNT_HEADER_SIZE + TABLE_RECORD_SIZE * usize::from(entries.len());
+        let mut prev_tag = None;
+        for entry in entries {
+            if prev_tag.map_or(false, |tag| tag >= entry.tag) {
+                return Err(FaceParsingError::MalformedFont);
+            }
+            prev_tag = Some(entry.tag);
+
+            sfnt.extend_from_slice(&entry.tag.0.to_be_bytes());
+            sfnt.extend_from_slice(&entry.orig_checksum.to_be_bytes());
+            sfnt.extend_from_slice(&(offset as u32).to_be_bytes());
+            sfnt.extend_from_slice(&entry.orig_length.to_be_bytes());
+
+            offset = usize::num_from(entry.orig_length)
+                .checked_add(offset)
+                .and_then(|n| n.checked_add(3))
+                .map(|n| n & !3)
+                .filter(|n| *n <= u32::MAX as usize)
+                .ok_or(FaceParsingError::MalformedFont)?;
+        }
+
+        for entry in entries {
+            decode_table(data, entry, &mut sfnt).ok_or(FaceParsingError::MalformedFont)?;
+            pad4(&mut sfnt);
+        }
+
+        let metadata = if header.meta_offset != 0 && header.meta_length != 0 {
+            // Metadata is always compressed.
+            let mut buf = Vec::new();
+            let compressed = slice(data, header.meta_offset, header.meta_length)
+                .ok_or(FaceParsingError::MalformedFont)?;
+            decompress(compressed, header.meta_orig_length, &mut buf)
+                .ok_or(FaceParsingError::MalformedFont)?;
+            Some(buf)
+        } else {
+            None
+        };
+
+        let private_data = if header.private_offset != 0 && header.private_length != 0 {
+            let block = slice(data, header.private_offset, header.private_length)
+                .ok_or(FaceParsingError::MalformedFont)?;
+            Some(block.to_vec())
+        } else {
+            None
+        };
+
+        Ok(Woff {
+            data: sfnt,
+            flavor: header.flavor,
+            major_version: header.major_version,
+            minor_version: header.minor_version,
+            metadata,
+            private_data,
+        })
+    }
+
+    /// Returns the decoded font data.
+    ///
+    /// Has the same layout as a TrueType/OpenType font file,
+    /// except for the `head` table's checksum adjustment, which is kept as is.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the decoded font data, consuming the container.
+    #[inline]
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Parses the decoded font data as a [`RawFace`].
+    #[inline]
+    pub fn raw_face(&self) -> Result<RawFace<'_>, FaceParsingError> {
+        RawFace::parse(&self.data, 0)
+    }
+
+    /// Parses the decoded font data as a [`Face`].
+    #[inline]
+    pub fn face(&self) -> Result<Face<'_>, FaceParsingError> {
+        Face::parse(&self.data, 0)
+    }
+
+    /// Returns the "sfnt version" of the original font.
+    ///
+    /// Like `0x00010000` for TrueType and `OTTO` for CFF-based fonts.
+    #[inline]
+    pub fn flavor(&self) -> u32 {
+        self.flavor
+    }
+
+    /// Returns the major version of the WOFF font, not the WOFF format.
+    #[inline]
+    pub fn major_version(&self) -> u16 {
+        self.major_version
+    }
+
+    /// Returns the minor version of the WOFF font, not the WOFF format.
+    #[inline]
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version
+    }
+
+    /// Returns the decompressed extended metadata block.
+    ///
+    /// An UTF-8 encoded XML document.
+    #[inline]
+    pub fn metadata(&self) -> Option<&[u8]> {
+        self.metadata.as_deref()
+    }
+
+    /// Returns the private data block.
+    #[inline]
+    pub fn private_data(&self) -> Option<&[u8]> {
+        self.private_data.as_deref()
+    }
+}
+
+impl core::fmt::Debug for Woff {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Woff {{ ... }}")
+    }
+}
+
+#[inline]
+fn slice(data: &[u8], offset: u32, length: u32) -> Option<&[u8]> {
+    let start = usize::num_from(offset);
+    let end = start.checked_add(usize::num_from(length))?;
+    data.get(start..end)
+}
+
+fn decode_table(data: &[u8], entry: TableDirectoryEntry, out: &mut Vec<u8>) -> Option<()> {
+    let table = slice(data, entry.offset, entry.comp_length)?;
+    if entry.comp_length == entry.orig_length {
+        // Stored uncompressed.
+        out.extend_from_slice(table);
+        Some(())
+    } else if entry.comp_length < entry.orig_length {
+        decompress(table, entry.orig_length, out)
+    } else {
+        None
+    }
+}
+
+fn decompress(compressed: &[u8], orig_length: u32, out: &mut Vec<u8>) -> Option<()> {
+    let orig_length = usize::num_from(orig_length);
+    let decompressed =
+        miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(compressed, orig_length).ok()?;
+    if decompressed.len() != orig_length {
+        return None;
+    }
+
+    out.extend_from_slice(&decompressed);
+    Some(())
+}
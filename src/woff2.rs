@@ -0,0 +1,880 @@
+//! A [WOFF 2.0](https://www.w3.org/TR/WOFF2/) container decoder.
+//!
+//! WOFF 2.0 stores tables compressed with Brotli and may apply transforms
+//! to the `glyf`, `loca` and `hmtx` tables. Decoding reverses them and produces
+//! a regular TrueType/OpenType font or font collection, which requires heap allocations.
+//! The decoded font data is stored by [`Woff2`] and can be parsed
+//! via [`Woff2::raw_face`] or [`Woff2::face`].
+//!
+//! ```no_run
+//! let data = std::fs::read("font.woff2").unwrap();
+//! let woff = ttf_parser::woff2::Woff2::parse(&data).unwrap();
+//! let face = woff.face(0).unwrap();
+//! println!("{}", face.number_of_glyphs());
+//! ```
+
+use core::convert::TryFrom;
+use std::borrow::Cow;
+use std::vec::Vec;
+
+use crate::parser::{NumFrom, Stream};
+use crate::tables::{head, hhea, loca, maxp};
+use crate::writer::{pad4, write_fonts, Font, Table};
+use crate::{Face, FaceParsingError, GlyphId, RawFace, Tag};
+
+const SIGNATURE: u32 = 0x774F4632; // wOF2
+const COLLECTION_FLAVOR: u32 = 0x74746366; // ttcf
+
+const GLYF: Tag = Tag::from_bytes(b"glyf");
+const LOCA: Tag = Tag::from_bytes(b"loca");
+const HMTX: Tag = Tag::from_bytes(b"hmtx");
+const HEAD: Tag = Tag::from_bytes(b"head");
+const HHEA: Tag = Tag::from_bytes(b"hhea");
+const MAXP: Tag = Tag::from_bytes(b"maxp");
+
+/// Tags that can be referenced by an index in a table directory entry.
+#[rustfmt::skip]
+const KNOWN_TAGS: [Tag; 63] = [
+    Tag::from_bytes(b"cmap"), Tag::from_bytes(b"head"), Tag::from_bytes(b"hhea"),
+    Tag::from_bytes(b"hmtx"), Tag::from_bytes(b"maxp"), Tag::from_bytes(b"name"),
+    Tag::from_bytes(b"OS/2"), Tag::from_bytes(b"post"), Tag::from_bytes(b"cvt "),
+    Tag::from_bytes(b"fpgm"), Tag::from_bytes(b"glyf"), Tag::from_bytes(b"loca"),
+    Tag::from_bytes(b"prep"), Tag::from_bytes(b"CFF "), Tag::from_bytes(b"VORG"),
+    Tag::from_bytes(b"EBDT"), Tag::from_bytes(b"EBLC"), Tag::from_bytes(b"gasp"),
+    Tag::from_bytes(b"hdmx"), Tag::from_bytes(b"kern"), Tag::from_bytes(b"LTSH"),
+    Tag::from_bytes(b"PCLT"), Tag::from_bytes(b"VDMX"), Tag::from_bytes(b"vhea"),
+    Tag::from_bytes(b"vmtx"), Tag::from_bytes(b"BASE"), Tag::from_bytes(b"GDEF"),
+    Tag::from_bytes(b"GPOS"), Tag::from_bytes(b"GSUB"), Tag::from_bytes(b"EBSC"),
+    Tag::from_bytes(b"JSTF"), Tag::from_bytes(b"MATH"), Tag::from_bytes(b"CBDT"),
+    Tag::from_bytes(b"CBLC"), Tag::from_bytes(b"COLR"), Tag::from_bytes(b"CPAL"),
+    Tag::from_bytes(b"SVG "), Tag::from_bytes(b"sbix"), Tag::from_bytes(b"acnt"),
+    Tag::from_bytes(b"avar"), Tag::from_bytes(b"bdat"), Tag::from_bytes(b"bloc"),
+    Tag::from_bytes(b"bsln"), Tag::from_bytes(b"cvar"), Tag::from_bytes(b"fdsc"),
+    Tag::from_bytes(b"feat"), Tag::from_bytes(b"fmtx"), Tag::from_bytes(b"fvar"),
+    Tag::from_bytes(b"gvar"), Tag::from_bytes(b"hsty"), Tag::from_bytes(b"just"),
+    Tag::from_bytes(b"lcar"), Tag::from_bytes(b"mort"), Tag::from_bytes(b"morx"),
+    Tag::from_bytes(b"opbd"), Tag::from_bytes(b"prop"), Tag::from_bytes(b"trak"),
+    Tag::from_bytes(b"Zapf"), Tag::from_bytes(b"Silf"), Tag::from_bytes(b"Glat"),
+    Tag::from_bytes(b"Gloc"), Tag::from_bytes(b"Feat"), Tag::from_bytes(b"Sill"),
+];
+
+/// Checks that the data starts with a WOFF 2.0 signature.
+#[inline]
+pub fn is_woff2(data: &[u8]) -> bool {
+    Stream::new(data).read::<u32>() == Some(SIGNATURE)
+}
+
+struct Header {
+    flavor: u32,
+    num_tables: u16,
+    total_compressed_size: u32,
+    major_version: u16,
+    minor_version: u16,
+    meta_offset: u32,
+    meta_length: u32,
+    meta_orig_length: u32,
+    private_offset: u32,
+    private_length: u32,
+}
+
+impl Header {
+    fn parse(s: &mut Stream) -> Option<Self> {
+        let flavor = s.read::<u32>()?;
+        s.skip::<u32>(); // length
+        let num_tables = s.read::<u16>()?;
+        s.skip::<u16>(); // reserved
+        s.skip::<u32>(); // total sfnt size
+        Some(Header {
+            flavor,
+            num_tables,
+            total_compressed_size: s.read::<u32>()?,
+            major_version: s.read::<u16>()?,
+            minor_version: s.read::<u16>()?,
+            meta_offset: s.read::<u32>()?,
+            meta_length: s.read::<u32>()?,
+            meta_orig_length: s.read::<u32>()?,
+            private_offset: s.read::<u32>()?,
+            private_length: s.read::<u32>()?,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct TableDirectoryEntry {
+    tag: Tag,
+    orig_length: u32,
+    // Set only for transformed tables.
+    transform_length: Option<u32>,
+}
+
+impl TableDirectoryEntry {
+    fn parse(s: &mut Stream) -> Option<Self> {
+        let flags = s.read::<u8>()?;
+        let tag = match flags & 0x3F {
+            63 => s.read::<Tag>()?,
+            index => KNOWN_TAGS[usize::from(index)],
+        };
+
+        // 'For all tables in a font, except for 'glyf' and 'loca' tables,
+        // transformation version 0 indicates the null transform.'
+        let transform_version = flags >> 6;
+        let is_transformed = match (tag, transform_version) {
+            (GLYF, 0) | (LOCA, 0) => true,
+            (GLYF, 3) | (LOCA, 3) => false,
+            (HMTX, 1) => true,
+            (_, 0) => false,
+            _ => return None,
+        };
+
+        let orig_length = read_uint_base128(s)?;
+        let transform_length = if is_transformed {
+            Some(read_uint_base128(s)?)
+        } else {
+            None
+        };
+
+        Some(TableDirectoryEntry {
+            tag,
+            orig_length,
+            transform_length,
+        })
+    }
+
+    #[inline]
+    fn is_transformed(&self) -> bool {
+        self.transform_length.is_some()
+    }
+
+    #[inline]
+    fn data_length(&self) -> u32 {
+        self.transform_length.unwrap_or(self.orig_length)
+    }
+}
+
+/// A decoded WOFF 2.0 font or font collection.
+#[derive(Clone)]
+pub struct Woff2 {
+    data: Vec<u8>,
+    flavor: u32,
+    major_version: u16,
+    minor_version: u16,
+    metadata: Option<Vec<u8>>,
+    private_data: Option<Vec<u8>>,
+}
+
+impl Woff2 {
+    /// Decodes a WOFF 2.0 font or font collection.
+    ///
+    /// Returns [`FaceParsingError::UnknownMagic`] when data doesn't start with `wOF2`.
+    pub fn parse(data: &[u8]) -> Result<Self, FaceParsingError> {
+        let mut s = Stream::new(data);
+        if s.read::<u32>() != Some(SIGNATURE) {
+            return Err(FaceParsingError::UnknownMagic);
+        }
+
+        let header = Header::parse(&mut s).ok_or(FaceParsingError::MalformedFont)?;
+        let decoded = decode(&mut s, &header).ok_or(FaceParsingError::MalformedFont)?;
+
+        let metadata = if header.meta_offset != 0 && header.meta_length != 0 {
+            // Metadata is always compressed.
+            let compressed = slice(data, header.meta_offset, header.meta_length)
+                .ok_or(FaceParsingError::MalformedFont)?;
+            let metadata = decompress(compressed, usize::num_from(header.meta_orig_length))
+                .ok_or(FaceParsingError::MalformedFont)?;
+            Some(metadata)
+        } else {
+            None
+        };
+
+        let private_data = if header.private_offset != 0 && header.private_length != 0 {
+            let block = slice(data, header.private_offset, header.private_length)
+                .ok_or(FaceParsingError::MalformedFont)?;
+            Some(block.to_vec())
+        } else {
+            None
+        };
+
+        Ok(Woff2 {
+            data: decoded,
+            flavor: header.flavor,
+            major_version: header.major_version,
+            minor_version: header.minor_version,
+            metadata,
+            private_data,
+        })
+    }
+
+    /// Returns the decoded font data.
+    ///
+    /// Has the same layout as a TrueType/OpenType font or font collection file.
+    /// Table checksums and the `head` table's checksum adjustment are recalculated.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the decoded font data, consuming the container.
+    #[inline]
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Parses a font from the decoded font data as a [`RawFace`].
+    ///
+    /// `index` is a font index in a font collection. Set to 0 if unsure.
+    #[inline]
+    pub fn raw_face(&self, index: u32) -> Result<RawFace<'_>, FaceParsingError> {
+        RawFace::parse(&self.data, index)
+    }
+
+    /// Parses a font from the decoded font data as a [`Face`].
+    ///
+    /// `index` is a font index in a font collection. Set to 0 if unsure.
+    #[inline]
+    pub fn face(&self, index: u32) -> Result<Face<'_>, FaceParsingError> {
+        Face::parse(&self.data, index)
+    }
+
+    /// Returns the number of fonts stored in a font collection.
+    ///
+    /// Returns `None` when the container doesn't store a collection.
+    #[inline]
+    pub fn fonts_in_collection(&self) -> Option<u32> {
+        crate::fonts_in_collection(&self.data)
+    }
+
+    /// Returns the "sfnt version" of the original font.
+    ///
+    /// Like `0x00010000` for TrueType, `OTTO` for CFF-based fonts
+    /// and `ttcf` for font collections.
+    #[inline]
+    pub fn flavor(&self) -> u32 {
+        self.flavor
+    }
+
+    /// Returns the major version of the WOFF font, not the WOFF format.
+    #[inline]
+    pub fn major_version(&self) -> u16 {
+        self.major_version
+    }
+
+    /// Returns the minor version of the WOFF font, not the WOFF format.
+    #[inline]
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version
+    }
+
+    /// Returns the decompressed extended metadata block.
+    ///
+    /// An UTF-8 encoded XML document.
+    #[inline]
+    pub fn metadata(&self) -> Option<&[u8]> {
+        self.metadata.as_deref()
+    }
+
+    /// Returns the private data block.
+    #[inline]
+    pub fn private_data(&self) -> Option<&[u8]> {
+        self.private_data.as_deref()
+    }
+}
+
+impl core::fmt::Debug for Woff2 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Woff2 {{ ... }}")
+    }
+}
+
+fn decode(s: &mut Stream, header: &Header) -> Option<Vec<u8>> {
+    let mut entries = Vec::with_capacity(usize::from(header.num_tables));
+    for _ in 0..header.num_tables {
+        entries.push(TableDirectoryEntry::parse(s)?);
+    }
+
+    let mut collection_version = None;
+    let mut fonts = Vec::new();
+    if header.flavor == COLLECTION_FLAVOR {
+        collection_version = Some(s.read::<u32>()?);
+        let num_fonts = read_255_u16(s)?;
+        for _ in 0..num_fonts {
+            let num_tables = read_255_u16(s)?;
+            let flavor = s.read::<u32>()?;
+            let mut tables = Vec::with_capacity(usize::from(num_tables));
+            for _ in 0..num_tables {
+                let index = usize::from(read_255_u16(s)?);
+                if index >= entries.len() {
+                    return None;
+                }
+
+                tables.push(index);
+            }
+
+            fonts.push(Font { flavor, tables });
+        }
+    } else {
+        fonts.push(Font {
+            flavor: header.flavor,
+            tables: (0..entries.len()).collect(),
+        });
+    }
+
+    // All tables are stored in a single compressed stream, without padding.
+    let mut total_length = 0usize;
+    for entry in &entries {
+        total_length = total_length.checked_add(usize::num_from(entry.data_length()))?;
+    }
+
+    let compressed = s.read_bytes(usize::num_from(header.total_compressed_size))?;
+    let decompressed = decompress(compressed, total_length)?;
+
+    let mut offset = 0;
+    let mut tables = Vec::with_capacity(entries.len());
+    let mut transformed = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let end = offset + usize::num_from(entry.data_length());
+        let table_data = &decompressed[offset..end];
+        offset = end;
+
+        tables.push(Table {
+            tag: entry.tag,
+            data: Cow::Borrowed(table_data),
+        });
+        transformed.push(if entry.is_transformed() {
+            Some(table_data)
+        } else {
+            None
+        });
+    }
+
+    let find = |font: &Font, tag: Tag| font.tables.iter().copied().find(|i| entries[*i].tag == tag);
+    for font in &fonts {
+        // `loca` is reconstructed alongside `glyf`.
+        if let (Some(glyf), Some(loca)) = (find(font, GLYF), find(font, LOCA)) {
+            if let Some(glyf_data) = transformed[glyf] {
+                if transformed[loca].is_none() || entries[loca].transform_length != Some(0) {
+                    return None;
+                }
+
+                let (glyf_data, loca_data) = reconstruct_glyf(glyf_data)?;
+                tables[glyf].data = Cow::Owned(glyf_data);
+                tables[loca].data = Cow::Owned(loca_data);
+                transformed[glyf] = None;
+                transformed[loca] = None;
+            }
+        }
+
+        if let Some(hmtx) = find(font, HMTX) {
+            if let Some(hmtx_data) = transformed[hmtx] {
+                let table = |tag| find(font, tag).map(|i| tables[i].data.as_ref());
+                let hmtx_data = reconstruct_hmtx(
+                    hmtx_data,
+                    table(HEAD)?,
+                    table(HHEA)?,
+                    table(MAXP)?,
+                    table(GLYF)?,
+                    table(LOCA)?,
+                )?;
+                tables[hmtx].data = Cow::Owned(hmtx_data);
+                transformed[hmtx] = None;
+            }
+        }
+    }
+
+    // A transformed table without its dependencies.
+    if transformed.iter().any(|t| t.is_some()) {
+        return None;
+    }
+
+    write_fonts(&tables, &fonts, collection_version)
+}
+
+// 'Glyph Data' transform, which is applied to `glyf` and `loca` tables.
+fn reconstruct_glyf(data: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut s = Stream::new(data);
+    s.skip::<u16>(); // reserved
+    let option_flags = s.read::<u16>()?;
+    let num_glyphs = s.read::<u16>()?;
+    let index_format = s.read::<u16>()?;
+
+    let mut sizes = [0u32; 7];
+    for size in &mut sizes {
+        *size = s.read::<u32>()?;
+    }
+
+    let mut streams = [&[][..]; 7];
+    for (stream, size) in streams.iter_mut().zip(sizes.iter()) {
+        *stream = s.read_bytes(usize::num_from(*size))?;
+    }
+
+    let [contours, points, flags, glyphs, composites, bboxes, instructions] = streams;
+    let overlap_bitmap = if option_flags & 1 != 0 {
+        Some(s.read_bytes((usize::from(num_glyphs) + 7) / 8)?)
+    } else {
+        None
+    };
+
+    let mut streams = GlyphStreams {
+        contours: Stream::new(contours),
+        points: Stream::new(points),
+        flags: Stream::new(flags),
+        glyphs: Stream::new(glyphs),
+        composites: Stream::new(composites),
+        composite_data: composites,
+        bboxes: Stream::new(bboxes),
+        instructions: Stream::new(instructions),
+    };
+
+    let bbox_bitmap = streams
+        .bboxes
+        .read_bytes((usize::from(num_glyphs) + 31) / 32 * 4)?;
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(usize::from(num_glyphs) + 1);
+    for glyph_id in 0..num_glyphs {
+        offsets.push(glyf.len());
+
+        let has_bbox = is_bit_set(bbox_bitmap, glyph_id);
+        let has_overlap = overlap_bitmap.map_or(false, |bitmap| is_bit_set(bitmap, glyph_id));
+        let number_of_contours = streams.contours.read::<i16>()?;
+        match number_of_contours {
+            0 => {
+                // An empty glyph cannot have a bounding box.
+                if has_bbox {
+                    return None;
+                }
+            }
+            -1 => {
+                // Composite glyphs must have an explicit bounding box.
+                if !has_bbox {
+                    return None;
+                }
+
+                streams.write_composite_glyph(&mut glyf)?;
+            }
+            n if n > 0 => {
+                streams.write_simple_glyph(n as u16, has_bbox, has_overlap, &mut glyf)?;
+            }
+            _ => return None,
+        }
+
+        pad4(&mut glyf);
+    }
+    offsets.push(glyf.len());
+
+    let mut loca = Vec::new();
+    match index_format {
+        0 => {
+            for offset in offsets {
+                // 'The actual local offset divided by 2 is stored.'
+                loca.extend_from_slice(&u16::try_from(offset / 2).ok()?.to_be_bytes());
+            }
+        }
+        1 => {
+            for offset in offsets {
+                loca.extend_from_slice(&u32::try_from(offset).ok()?.to_be_bytes());
+            }
+        }
+        _ => return None,
+    }
+
+    Some((glyf, loca))
+}
+
+struct GlyphStreams<'a> {
+    contours: Stream<'a>,
+    points: Stream<'a>,
+    flags: Stream<'a>,
+    glyphs: Stream<'a>,
+    composites: Stream<'a>,
+    // `composites` data, since components are copied as is.
+    composite_data: &'a [u8],
+    bboxes: Stream<'a>,
+    instructions: Stream<'a>,
+}
+
+impl GlyphStreams<'_> {
+    fn write_simple_glyph(
+        &mut self,
+        number_of_contours: u16,
+        has_bbox: bool,
+        has_overlap: bool,
+        out: &mut Vec<u8>,
+    ) -> Option<()> {
+        let mut end_points = Vec::with_capacity(usize::from(number_of_contours));
+        let mut number_of_points = 0u32;
+        for _ in 0..number_of_contours {
+            number_of_points += u32::from(read_255_u16(&mut self.points)?);
+            end_points.push(u16::try_from(number_of_points.checked_sub(1)?).ok()?);
+        }
+
+        let point_flags = self.flags.read_bytes(usize::num_from(number_of_points))?;
+        let mut deltas = Vec::with_capacity(point_flags.len());
+        let (mut x, mut y) = (0i32, 0i32);
+        let (mut x_min, mut y_min, mut x_max, mut y_max) = (0, 0, 0, 0);
+        for (i, flag) in point_flags.iter().enumerate() {
+            let (dx, dy) = read_triplet(*flag & 0x7F, &mut self.glyphs)?;
+            x += dx;
+            y += dy;
+            if i == 0 {
+                x_min = x;
+                x_max = x;
+                y_min = y;
+                y_max = y;
+            } else {
+                x_min = x_min.min(x);
+                x_max = x_max.max(x);
+                y_min = y_min.min(y);
+                y_max = y_max.max(y);
+            }
+
+            let on_curve = *flag & 0x80 == 0;
+            deltas.push((i16::try_from(dx).ok()?, i16::try_from(dy).ok()?, on_curve));
+        }
+
+        let instructions_length = read_255_u16(&mut self.glyphs)?;
+        let instructions = self
+            .instructions
+            .read_bytes(usize::from(instructions_length))?;
+
+        out.extend_from_slice(&(number_of_contours as i16).to_be_bytes());
+        if has_bbox {
+            out.extend_from_slice(self.bboxes.read_bytes(8)?);
+        } else {
+            for n in [x_min, y_min, x_max, y_max].iter() {
+                out.extend_from_slice(&i16::try_from(*n).ok()?.to_be_bytes());
+            }
+        }
+
+        for end_point in end_points {
+            out.extend_from_slice(&end_point.to_be_bytes());
+        }
+
+        out.extend_from_slice(&instructions_length.to_be_bytes());
+        out.extend_from_slice(instructions);
+        write_glyph_points(&deltas, has_overlap, out);
+        Some(())
+    }
+
+    fn write_composite_glyph(&mut self, out: &mut Vec<u8>) -> Option<()> {
+        const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+        const WE_HAVE_A_SCALE: u16 = 0x0008;
+        const MORE_COMPONENTS: u16 = 0x0020;
+        const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+        const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+        const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+        let start = self.composites.offset();
+        let mut have_instructions = false;
+        loop {
+            let flags = self.composites.read::<u16>()?;
+            let mut length = 2; // glyph index
+            length += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                4
+            } else {
+                2
+            };
+
+            if flags & WE_HAVE_A_SCALE != 0 {
+                length += 2;
+            } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                length += 4;
+            } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                length += 8;
+            }
+
+            self.composites.advance_checked(length)?;
+            have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+            if flags & MORE_COMPONENTS == 0 {
+                break;
+            }
+        }
+        let end = self.composites.offset();
+
+        out.extend_from_slice(&(-1i16).to_be_bytes());
+        out.extend_from_slice(self.bboxes.read_bytes(8)?);
+        out.extend_from_slice(self.composite_data.get(start..end)?);
+
+        if have_instructions {
+            let instructions_length = read_255_u16(&mut self.glyphs)?;
+            let instructions = self
+                .instructions
+                .read_bytes(usize::from(instructions_length))?;
+            out.extend_from_slice(&instructions_length.to_be_bytes());
+            out.extend_from_slice(instructions);
+        }
+
+        Some(())
+    }
+}
+
+fn write_glyph_points(deltas: &[(i16, i16, bool)], has_overlap: bool, out: &mut Vec<u8>) {
+    const ON_CURVE_POINT: u8 = 0x01;
+    const X_SHORT_VECTOR: u8 = 0x02;
+    const Y_SHORT_VECTOR: u8 = 0x04;
+    const REPEAT_FLAG: u8 = 0x08;
+    const X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR: u8 = 0x10;
+    const Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR: u8 = 0x20;
+    const OVERLAP_SIMPLE: u8 = 0x40;
+
+    let mut last_flag = None;
+    let mut repeat_count = 0;
+    let mut last_flag_offset = 0;
+    for (i, (dx, dy, on_curve)) in deltas.iter().copied().enumerate() {
+        let mut flag = if on_curve { ON_CURVE_POINT } else { 0 };
+        if i == 0 && has_overlap {
+            flag |= OVERLAP_SIMPLE;
+        }
+
+        if dx == 0 {
+            flag |= X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR;
+        } else if dx.unsigned_abs() <= 255 {
+            flag |= X_SHORT_VECTOR;
+            if dx > 0 {
+                flag |= X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR;
+            }
+        }
+
+        if dy == 0 {
+            flag |= Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR;
+        } else if dy.unsigned_abs() <= 255 {
+            flag |= Y_SHORT_VECTOR;
+            if dy > 0 {
+                flag |= Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR;
+            }
+        }
+
+        if last_flag == Some(flag) && repeat_count < 255 {
+            if repeat_count == 0 {
+                out[last_flag_offset] |= REPEAT_FLAG;
+                out.push(0);
+            }
+
+            repeat_count += 1;
+            *out.last_mut().unwrap() = repeat_count;
+        } else {
+            last_flag = Some(flag);
+            last_flag_offset = out.len();
+            repeat_count = 0;
+            out.push(flag);
+        }
+    }
+
+    for (dx, _, _) in deltas {
+        write_coordinate(*dx, out);
+    }
+
+    for (_, dy, _) in deltas {
+        write_coordinate(*dy, out);
+    }
+}
+
+#[inline]
+fn write_coordinate(delta: i16, out: &mut Vec<u8>) {
+    if delta == 0 {
+        // Stored in flags.
+    } else if delta.unsigned_abs() <= 255 {
+        out.push(delta.unsigned_abs() as u8);
+    } else {
+        out.extend_from_slice(&delta.to_be_bytes());
+    }
+}
+
+// Decodes a point from the glyph stream using a flag from the flag stream.
+//
+// See the 'Triplet Encoding' table in the spec.
+fn read_triplet(flag: u8, s: &mut Stream) -> Option<(i32, i32)> {
+    #[inline]
+    fn with_sign(flag: u8, value: i32) -> i32 {
+        if flag & 1 != 0 {
+            value
+        } else {
+            -value
+        }
+    }
+
+    let mut read = || s.read::<u8>().map(i32::from);
+    let point = if flag < 10 {
+        let dy = (i32::from(flag & 14) << 7) + read()?;
+        (0, with_sign(flag, dy))
+    } else if flag < 20 {
+        let dx = (i32::from((flag - 10) & 14) << 7) + read()?;
+        (with_sign(flag, dx), 0)
+    } else if flag < 84 {
+        let b0 = i32::from(flag - 20);
+        let b1 = read()?;
+        (
+            with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+            with_sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b1 & 0x0F)),
+        )
+    } else if flag < 120 {
+        let b0 = i32::from(flag - 84);
+        let b1 = read()?;
+        let b2 = read()?;
+        (
+            with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+            with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2),
+        )
+    } else if flag < 124 {
+        let b1 = read()?;
+        let b2 = read()?;
+        let b3 = read()?;
+        (
+            with_sign(flag, (b1 << 4) + (b2 >> 4)),
+            with_sign(flag >> 1, ((b2 & 0x0F) << 8) + b3),
+        )
+    } else {
+        let b1 = read()?;
+        let b2 = read()?;
+        let b3 = read()?;
+        let b4 = read()?;
+        (
+            with_sign(flag, (b1 << 8) + b2),
+            with_sign(flag >> 1, (b3 << 8) + b4),
+        )
+    };
+
+    Some(point)
+}
+
+// 'Horizontal Metrics' transform, which omits left side bearings
+// that are equal to glyphs' `xMin`.
+fn reconstruct_hmtx(
+    data: &[u8],
+    head: &[u8],
+    hhea: &[u8],
+    maxp: &[u8],
+    glyf: &[u8],
+    loca: &[u8],
+) -> Option<Vec<u8>> {
+    let head = head::Table::parse(head)?;
+    let number_of_metrics = hhea::Table::parse(hhea)?.number_of_metrics;
+    let number_of_glyphs = maxp::Table::parse(maxp)?.number_of_glyphs;
+    let loca = loca::Table::parse(number_of_glyphs, head.index_to_location_format, loca)?;
+    if number_of_metrics == 0 || number_of_metrics > number_of_glyphs.get() {
+        return None;
+    }
+
+    let mut s = Stream::new(data);
+    let flags = s.read::<u8>()?;
+    // Reserved bits must be zero and at least one array must be omitted.
+    if flags & 0xFC != 0 || flags & 0x03 == 0 {
+        return None;
+    }
+
+    let advances = s.read_array16::<u16>(number_of_metrics)?;
+    let proportional_lsbs = if flags & 0x01 == 0 {
+        Some(s.read_array16::<i16>(number_of_metrics)?)
+    } else {
+        None
+    };
+    let monospaced_lsbs = if flags & 0x02 == 0 {
+        Some(s.read_array16::<i16>(number_of_glyphs.get() - number_of_metrics)?)
+    } else {
+        None
+    };
+
+    let x_min = |glyph_id: u16| -> Option<i16> {
+        match loca.glyph_range(GlyphId(glyph_id)) {
+            // `xMin` follows `numberOfContours`.
+            Some(range) => Stream::read_at::<i16>(glyf.get(range)?, 2),
+            // Empty glyphs have no bounding box.
+            None => Some(0),
+        }
+    };
+
+    let mut hmtx = Vec::with_capacity(
+        usize::from(number_of_glyphs.get()) * 2 + 2 * usize::from(number_of_metrics),
+    );
+    for (i, advance) in advances.into_iter().enumerate() {
+        let i = i as u16;
+        let lsb = match proportional_lsbs {
+            Some(lsbs) => lsbs.get(i)?,
+            None => x_min(i)?,
+        };
+
+        hmtx.extend_from_slice(&advance.to_be_bytes());
+        hmtx.extend_from_slice(&lsb.to_be_bytes());
+    }
+
+    for i in number_of_metrics..number_of_glyphs.get() {
+        let lsb = match monospaced_lsbs {
+            Some(lsbs) => lsbs.get(i - number_of_metrics)?,
+            None => x_min(i)?,
+        };
+
+        hmtx.extend_from_slice(&lsb.to_be_bytes());
+    }
+
+    Some(hmtx)
+}
+
+#[inline]
+fn is_bit_set(bitmap: &[u8], index: u16) -> bool {
+    let index = usize::from(index);
+    bitmap
+        .get(index / 8)
+        .map_or(false, |byte| byte & (0x80 >> (index % 8)) != 0)
+}
+
+// A variable-length encoding of a 32-bit unsigned integer.
+fn read_uint_base128(s: &mut Stream) -> Option<u32> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let byte = s.read::<u8>()?;
+        // No leading zeros.
+        if i == 0 && byte == 0x80 {
+            return None;
+        }
+
+        // Would overflow.
+        if value & 0xFE000000 != 0 {
+            return None;
+        }
+
+        value = (value << 7) | u32::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    // Longer than 5 bytes.
+    None
+}
+
+// A variable-length encoding of a 16-bit unsigned integer.
+fn read_255_u16(s: &mut Stream) -> Option<u16> {
+    const ONE_MORE_BYTE_CODE1: u8 = 255;
+    const ONE_MORE_BYTE_CODE2: u8 = 254;
+    const WORD_CODE: u8 = 253;
+    const LOWEST_U_CODE: u16 = 253;
+
+    let code = s.read::<u8>()?;
+    match code {
+        WORD_CODE => s.read::<u16>(),
+        ONE_MORE_BYTE_CODE1 => Some(LOWEST_U_CODE + u16::from(s.read::<u8>()?)),
+        ONE_MORE_BYTE_CODE2 => Some(LOWEST_U_CODE * 2 + u16::from(s.read::<u8>()?)),
+        _ => Some(u16::from(code)),
+    }
+}
+
+#[inline]
+fn slice(data: &[u8], offset: u32, length: u32) -> Option<&[u8]> {
+    let start = usize::num_from(offset);
+    let end = start.checked_add(usize::num_from(length))?;
+    data.get(start..end)
+}
+
+fn decompress(compressed: &[u8], length: usize) -> Option<Vec<u8>> {
+    use std::io::Read;
+
+    let mut decompressed = Vec::new();
+    let decoder = brotli_decompressor::Decompressor::new(compressed, 4096);
+    // Read one more byte to detect overlong data.
+    let mut decoder = decoder.take(length as u64 + 1);
+    decoder.read_to_end(&mut decompressed).ok()?;
+    if decompressed.len() != length {
+        return None;
+    }
+
+    Some(decompressed)
+}
@@ -0,0 +1,354 @@
+//! An sfnt font writer.
+//!
+//! Unlike the rest of the crate, writing requires heap allocations.
+//!
+//! ```no_run
+//! use ttf_parser::{writer::FontBuilder, RawFace, Tag};
+//!
+//! let data = std::fs::read("font.ttf").unwrap();
+//! let face = RawFace::parse(&data, 0).unwrap();
+//! let mut builder = FontBuilder::from_raw_face(&face);
+//! builder.remove_table(Tag::from_bytes(b"DSIG"));
+//! let data = builder.build().unwrap();
+//! ```
+
+use core::convert::TryFrom;
+use std::borrow::Cow;
+use std::vec::Vec;
+
+use crate::parser::Stream;
+use crate::{checksum, RawFace, Tag};
+
+pub(crate) const SFNT_HEADER_SIZE: usize = 12;
+pub(crate) const TABLE_RECORD_SIZE: usize = 16;
+const COLLECTION_TAG: u32 = 0x74746366; // ttcf
+
+// 'To calculate the checkSum for the 'head' table which itself includes the
+// checkSumAdjustment entry for the entire font, do the following: ...'
+const CHECKSUM_MAGIC: u32 = 0xB1B0AFBA;
+
+const CHECKSUM_ADJUSTMENT_OFFSET: usize = 8;
+const HEAD: Tag = Tag::from_bytes(b"head");
+const TRUETYPE_FLAVOR: u32 = 0x00010000;
+const CFF_FLAVOR: u32 = 0x4F54544F; // OTTO
+
+/// A builder for TrueType/OpenType fonts and font collections.
+///
+/// Tables are written as is, while table records, checksums and
+/// `head.checkSumAdjustment` are calculated during [`build`](FontBuilder::build).
+///
+/// A builder starts with a single font. Use [`add_font`](FontBuilder::add_font)
+/// or [`add_raw_face`](FontBuilder::add_raw_face) to write a font collection,
+/// in which case identical tables are shared between fonts.
+/// All table methods operate on the last added font.
+#[derive(Clone)]
+pub struct FontBuilder<'a> {
+    fonts: Vec<FontTables<'a>>,
+}
+
+#[derive(Clone)]
+struct FontTables<'a> {
+    flavor: u32,
+    tables: Vec<Table<'a>>,
+}
+
+impl<'a> FontBuilder<'a> {
+    /// Creates a new builder with a single empty font.
+    ///
+    /// `flavor` is the "sfnt version", like `0x00010000` for TrueType
+    /// and `OTTO` for CFF-based fonts.
+    #[inline]
+    pub fn new(flavor: u32) -> Self {
+        FontBuilder {
+            fonts: vec![FontTables {
+                flavor,
+                tables: Vec::new(),
+            }],
+        }
+    }
+
+    /// Creates a new builder with a single font that contains all tables from `face`.
+    ///
+    /// See [`add_raw_face`](FontBuilder::add_raw_face) for the flavor.
+    /// Tables with records pointing outside of the font data are skipped.
+    /// Use [`add_raw_face`](FontBuilder::add_raw_face) to detect them.
+    #[inline]
+    pub fn from_raw_face(face: &RawFace<'a>) -> Self {
+        let mut builder = FontBuilder { fonts: Vec::new() };
+        builder.add_raw_face(face);
+        builder
+    }
+
+    /// Starts a new empty font.
+    #[inline]
+    pub fn add_font(&mut self, flavor: u32) {
+        self.fonts.push(FontTables {
+            flavor,
+            tables: Vec::new(),
+        });
+    }
+
+    /// Starts a new font that contains all tables from `face`.
+    ///
+    /// The flavor is the `face` "sfnt version". When it is not available,
+    /// the flavor is `OTTO` for faces with a `CFF ` or `CFF2` table and `0x00010000` otherwise.
+    ///
+    /// Returns `false` when some table records point outside of the font data.
+    /// Such tables are skipped, while the rest are still added.
+    pub fn add_raw_face(&mut self, face: &RawFace<'a>) -> bool {
+        let mut tables = Vec::with_capacity(usize::from(face.table_records.len()));
+        let mut is_valid = true;
+        for record in face.table_records {
+            match face.table(record.tag) {
+                Some(data) => tables.push(Table {
+                    tag: record.tag,
+                    data: Cow::Borrowed(data),
+                }),
+                None => is_valid = false,
+            }
+        }
+
+        let flavor = face.sfnt_version().unwrap_or_else(|| {
+            let is_cff = tables.iter().any(|table| {
+                table.tag == Tag::from_bytes(b"CFF ") || table.tag == Tag::from_bytes(b"CFF2")
+            });
+            if is_cff {
+                CFF_FLAVOR
+            } else {
+                TRUETYPE_FLAVOR
+            }
+        });
+
+        self.fonts.push(FontTables { flavor, tables });
+
+        is_valid
+    }
+
+    /// Returns table's data.
+    #[inline]
+    pub fn table(&self, tag: Tag) -> Option<&[u8]> {
+        let font = self.fonts.last()?;
+        let table = font.tables.iter().find(|table| table.tag == tag)?;
+        Some(&table.data)
+    }
+
+    /// Adds a table or replaces an existing one.
+    pub fn set_table(&mut self, tag: Tag, data: impl Into<Cow<'a, [u8]>>) {
+        let data = data.into();
+        if let Some(font) = self.fonts.last_mut() {
+            match font.tables.iter_mut().find(|table| table.tag == tag) {
+                Some(table) => table.data = data,
+                None => font.tables.push(Table { tag, data }),
+            }
+        }
+    }
+
+    /// Removes a table.
+    ///
+    /// Returns `false` when there is no such table.
+    pub fn remove_table(&mut self, tag: Tag) -> bool {
+        if let Some(font) = self.fonts.last_mut() {
+            if let Some(index) = font.tables.iter().position(|table| table.tag == tag) {
+                font.tables.remove(index);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Writes a font or a font collection.
+    ///
+    /// Returns `None` when a font has more than 65535 tables, when a `head` table
+    /// is too short to store a checksum adjustment or when the resulting data
+    /// would exceed 4 GiB.
+    pub fn build(&self) -> Option<Vec<u8>> {
+        let mut tables: Vec<Table> = Vec::new();
+        let mut fonts = Vec::with_capacity(self.fonts.len());
+        for font in &self.fonts {
+            let mut indices = Vec::with_capacity(font.tables.len());
+            for table in &font.tables {
+                let index = match tables
+                    .iter()
+                    .position(|t| t.tag == table.tag && t.data == table.data)
+                {
+                    Some(index) => index,
+                    None => {
+                        tables.push(Table {
+                            tag: table.tag,
+                            data: Cow::Borrowed(&table.data),
+                        });
+                        tables.len() - 1
+                    }
+                };
+
+                indices.push(index);
+            }
+
+            fonts.push(Font {
+                flavor: font.flavor,
+                tables: indices,
+            });
+        }
+
+        let collection_version = if fonts.len() > 1 {
+            Some(0x00010000)
+        } else {
+            None
+        };
+
+        write_fonts(&tables, &fonts, collection_version)
+    }
+}
+
+impl core::fmt::Debug for FontBuilder<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "FontBuilder {{ ... }}")
+    }
+}
+
+/// A font table to write.
+#[derive(Clone)]
+pub(crate) struct Table<'a> {
+    pub tag: Tag,
+    pub data: Cow<'a, [u8]>,
+}
+
+/// A font in a collection, referencing tables by index.
+pub(crate) struct Font {
+    pub flavor: u32,
+    pub tables: Vec<usize>,
+}
+
+/// Writes an sfnt offset table header.
+pub(crate) fn write_sfnt_header(flavor: u32, num_tables: u16, data: &mut Vec<u8>) {
+    // Largest power of two not greater than the number of tables.
+    let entry_selector = if num_tables == 0 {
+        0
+    } else {
+        15 - num_tables.leading_zeros() as u16
+    };
+    let search_range = (1u16 << entry_selector).wrapping_mul(16);
+    let range_shift = num_tables.wrapping_mul(16).wrapping_sub(search_range);
+
+    data.extend_from_slice(&flavor.to_be_bytes());
+    data.extend_from_slice(&num_tables.to_be_bytes());
+    data.extend_from_slice(&search_range.to_be_bytes());
+    data.extend_from_slice(&entry_selector.to_be_bytes());
+    data.extend_from_slice(&range_shift.to_be_bytes());
+}
+
+#[inline]
+pub(crate) fn pad4(data: &mut Vec<u8>) {
+    data.resize((data.len() + 3) & !3, 0);
+}
+
+/// Writes fonts that share `tables`.
+///
+/// A single font is written as is, unless `collection_version` is set,
+/// in which case a font collection is written.
+/// Table checksums and `head.checkSumAdjustment` are recalculated.
+///
+/// Returns `None` when the resulting data would exceed 4 GiB
+/// or when a font references a missing table.
+pub(crate) fn write_fonts(
+    tables: &[Table],
+    fonts: &[Font],
+    collection_version: Option<u32>,
+) -> Option<Vec<u8>> {
+    if fonts
+        .iter()
+        .flat_map(|font| &font.tables)
+        .any(|index| *index >= tables.len())
+    {
+        return None;
+    }
+
+    let mut offset = match collection_version {
+        Some(version) => {
+            let dsig_size = if version >= 0x00020000 { 12 } else { 0 };
+            SFNT_HEADER_SIZE + 4 * fonts.len() + dsig_size
+        }
+        None => 0,
+    };
+
+    let mut font_offsets = Vec::with_capacity(fonts.len());
+    for font in fonts {
+        font_offsets.push(offset);
+        offset += SFNT_HEADER_SIZE + TABLE_RECORD_SIZE * font.tables.len();
+    }
+
+    let mut table_offsets = Vec::with_capacity(tables.len());
+    let mut checksums = Vec::with_capacity(tables.len());
+    for table in tables {
+        table_offsets.push(offset);
+        offset = offset.checked_add(table.data.len())?.checked_add(3)? & !3;
+
+        let mut sum = checksum(&table.data);
+        if table.tag == HEAD {
+            // The adjustment itself is treated as zero.
+            let adjustment = Stream::read_at::<u32>(&table.data, CHECKSUM_ADJUSTMENT_OFFSET)?;
+            sum = sum.wrapping_sub(adjustment);
+        }
+        checksums.push(sum);
+    }
+
+    if offset > u32::MAX as usize {
+        return None;
+    }
+
+    let mut data = Vec::with_capacity(offset);
+    if let Some(version) = collection_version {
+        data.extend_from_slice(&COLLECTION_TAG.to_be_bytes());
+        data.extend_from_slice(&version.to_be_bytes());
+        data.extend_from_slice(&(fonts.len() as u32).to_be_bytes());
+        for offset in &font_offsets {
+            data.extend_from_slice(&(*offset as u32).to_be_bytes());
+        }
+
+        if version >= 0x00020000 {
+            // No DSIG table.
+            data.extend_from_slice(&[0; 12]);
+        }
+    }
+
+    let mut adjustments = Vec::with_capacity(fonts.len());
+    for font in fonts {
+        // Table records must be sorted by tag.
+        let mut indices = font.tables.clone();
+        indices.sort_by_key(|index| tables[*index].tag);
+
+        let start = data.len();
+        write_sfnt_header(font.flavor, u16::try_from(indices.len()).ok()?, &mut data);
+        for index in &indices {
+            let table = &tables[*index];
+            data.extend_from_slice(&table.tag.0.to_be_bytes());
+            data.extend_from_slice(&checksums[*index].to_be_bytes());
+            data.extend_from_slice(&(table_offsets[*index] as u32).to_be_bytes());
+            data.extend_from_slice(&(table.data.len() as u32).to_be_bytes());
+        }
+
+        let mut sum = checksum(&data[start..]);
+        for index in &indices {
+            sum = sum.wrapping_add(checksums[*index]);
+        }
+
+        if let Some(head) = indices.iter().find(|index| tables[**index].tag == HEAD) {
+            adjustments.push((*head, CHECKSUM_MAGIC.wrapping_sub(sum)));
+        }
+    }
+
+    for table in tables {
+        data.extend_from_slice(&table.data);
+        pad4(&mut data);
+    }
+
+    // When a `head` table is shared by multiple fonts, the first one wins.
+    adjustments.reverse();
+    for (index, adjustment) in adjustments {
+        let offset = table_offsets[index] + CHECKSUM_ADJUSTMENT_OFFSET;
+        data[offset..offset + 4].copy_from_slice(&adjustment.to_be_bytes());
+    }
+
+    Some(data)
+}
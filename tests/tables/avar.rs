@@ -0,0 +1,168 @@
+use ttf_parser::avar::Table;
+use ttf_parser::{Face, NormalizedCoordinate, Tag};
+use crate::{convert, Unit, Unit::*};
+
+fn map(table: &Table, coordinates: &[i16]) -> Option<Vec<i16>> {
+    let mut coordinates: Vec<_> = coordinates.iter().map(|c| NormalizedCoordinate::from(*c)).collect();
+    table.map_coordinates(&mut coordinates)?;
+    Some(coordinates.iter().map(|c| c.get()).collect())
+}
+
+#[test]
+fn version_1() {
+    let data = convert(&[
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt16(0), // reserved
+        UInt16(1), // axis count
+
+        // SegmentMaps [0]
+        UInt16(3), // position map count
+        Int16(-16384), // from coordinate [0]
+        Int16(-16384), // to coordinate [0]
+        Int16(0), // from coordinate [1]
+        Int16(0), // to coordinate [1]
+        Int16(16384), // from coordinate [2]
+        Int16(8192), // to coordinate [2]
+    ]);
+
+    let table = Table::parse(&data).unwrap();
+    assert_eq!(table.segment_maps.len(), 1);
+    assert_eq!(map(&table, &[16384]), Some(vec![8192]));
+    assert_eq!(map(&table, &[-8192]), Some(vec![-8192]));
+    // Wrong number of axes.
+    assert_eq!(map(&table, &[0, 0]), None);
+}
+
+#[test]
+fn unsupported_version() {
+    let data = convert(&[
+        UInt16(3), // major version
+        UInt16(0), // minor version
+        UInt16(0), // reserved
+        UInt16(0), // axis count
+    ]);
+
+    assert!(Table::parse(&data).is_none());
+}
+
+// An avar2 table with two axes, where the second axis is moved
+// by -0.5 when the first one is at 1.0.
+fn version_2(index_map: &[Unit]) -> Vec<u8> {
+    let index_map_offset = if index_map.is_empty() { 0 } else { 20 + 40 };
+    let mut units = vec![
+        UInt16(2), // major version
+        UInt16(0), // minor version
+        UInt16(0), // reserved
+        UInt16(2), // axis count
+
+        // SegmentMaps [0]
+        UInt16(0), // position map count
+
+        // SegmentMaps [1]
+        UInt16(0), // position map count
+
+        UInt32(index_map_offset), // axis index map offset
+        UInt32(20), // item variation store offset
+
+        // ItemVariationStore
+        UInt16(1), // format
+        UInt32(12), // variation region list offset
+        UInt16(1), // item variation data count
+        UInt32(28), // item variation data offset [0]
+
+        // VariationRegionList
+        UInt16(2), // axis count
+        UInt16(1), // region count
+        // Region [0]
+        Int16(0), // start coordinate [0]
+        Int16(16384), // peak coordinate [0]
+        Int16(16384), // end coordinate [0]
+        Int16(0), // start coordinate [1]
+        Int16(0), // peak coordinate [1]
+        Int16(0), // end coordinate [1]
+
+        // ItemVariationData [0]
+        UInt16(2), // item count
+        UInt16(1), // word delta count
+        UInt16(1), // region index count
+        UInt16(0), // region index [0]
+        Int16(0), // delta set [0]
+        Int16(-8192), // delta set [1]
+    ];
+    units.extend_from_slice(index_map);
+    convert(&units)
+}
+
+#[test]
+fn version_2_without_index_map() {
+    let data = version_2(&[]);
+    let table = Table::parse(&data).unwrap();
+    assert_eq!(map(&table, &[16384, 0]), Some(vec![16384, -8192]));
+    assert_eq!(map(&table, &[8192, 4096]), Some(vec![8192, 0]));
+    assert_eq!(map(&table, &[0, 4096]), Some(vec![0, 4096]));
+    // Clamped.
+    assert_eq!(map(&table, &[16384, -16384]), Some(vec![16384, -16384]));
+}
+
+#[test]
+fn version_2_with_index_map() {
+    let data = version_2(&[
+        // DeltaSetIndexMap
+        UInt8(0), // format
+        UInt8(0), // entry format: 1 byte entries with 1 bit inner index
+        UInt16(2), // map count
+        UInt8(1), // map data [0]
+        UInt8(0), // map data [1]
+    ]);
+
+    let table = Table::parse(&data).unwrap();
+    // Deltas are swapped now.
+    assert_eq!(map(&table, &[16384, 0]), Some(vec![8192, 0]));
+}
+
+#[test]
+fn set_variation_with_version_2() {
+    let fvar = convert(&[
+        Fixed(1.0), // version
+        UInt16(16), // axes array offset
+        UInt16(2), // reserved
+        UInt16(2), // axis count
+        UInt16(20), // axis size
+        UInt16(0), // instance count
+        UInt16(8), // instance size
+
+        // VariationAxisRecord [0]
+        Raw(b"wght"), // axis tag
+        Fixed(100.0), // min value
+        Fixed(400.0), // default value
+        Fixed(900.0), // max value
+        UInt16(0), // flags
+        UInt16(256), // axis name ID
+
+        // VariationAxisRecord [1]
+        Raw(b"wdth"), // axis tag
+        Fixed(50.0), // min value
+        Fixed(100.0), // default value
+        Fixed(200.0), // max value
+        UInt16(0), // flags
+        UInt16(257), // axis name ID
+    ]);
+    let data = crate::build_demo_font(&[(b"avar", &version_2(&[])), (b"fvar", &fvar)]);
+
+    let mut face = Face::parse(&data, 0).unwrap();
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    let coordinates: Vec<_> = face.variation_coordinates().iter().map(|c| c.get()).collect();
+    assert_eq!(coordinates, [16384, -8192]);
+
+    // Mapping must not be applied to already mapped coordinates.
+    face.set_variation(Tag::from_bytes(b"wdth"), 150.0).unwrap();
+    let coordinates: Vec<_> = face.variation_coordinates().iter().map(|c| c.get()).collect();
+    assert_eq!(coordinates, [16384, 0]);
+
+    let location = face.variation_location(&[
+        ttf_parser::Variation { axis: Tag::from_bytes(b"wght"), value: 900.0 },
+        ttf_parser::Variation { axis: Tag::from_bytes(b"wdth"), value: 150.0 },
+    ]).unwrap();
+    assert_eq!(location.coordinates(), face.variation_coordinates());
+}
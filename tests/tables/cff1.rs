@@ -71,6 +71,10 @@ mod top_dict_operator {
 }
 
 mod private_dict_operator {
+    pub const BLUE_VALUES: u16              = 6;
+    pub const OTHER_BLUES: u16              = 7;
+    pub const STD_HW: u16                   = 10;
+    pub const STD_VW: u16                   = 11;
     pub const LOCAL_SUBROUTINES_OFFSET: u16 = 19;
 }
 
@@ -998,3 +1002,150 @@ fn invalid_char_string_offset() {
 // TODO: FLEX
 // TODO: HFLEX1
 // TODO: FLEX1
+
+struct HintingBuilder(String);
+impl ttf_parser::OutlineBuilder for HintingBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        write!(&mut self.0, "M {} {} ", x, y).unwrap();
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        write!(&mut self.0, "L {} {} ", x, y).unwrap();
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        write!(&mut self.0, "Q {} {} {} {} ", x1, y1, x, y).unwrap();
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        write!(&mut self.0, "C {} {} {} {} {} {} ", x1, y1, x2, y2, x, y).unwrap();
+    }
+
+    fn close(&mut self) {
+        write!(&mut self.0, "Z ").unwrap();
+    }
+}
+
+impl cff::HintingBuilder for HintingBuilder {
+    fn horizontal_stem(&mut self, y: f32, dy: f32) {
+        write!(&mut self.0, "H {} {} ", y, dy).unwrap();
+    }
+
+    fn vertical_stem(&mut self, x: f32, dx: f32) {
+        write!(&mut self.0, "V {} {} ", x, dx).unwrap();
+    }
+
+    fn hint_mask(&mut self, mask: &[u8]) {
+        write!(&mut self.0, "HM {:?} ", mask).unwrap();
+    }
+
+    fn counter_mask(&mut self, mask: &[u8]) {
+        write!(&mut self.0, "CM {:?} ", mask).unwrap();
+    }
+}
+
+#[test]
+fn stem_hints() {
+    let data = gen_cff(&[], &[], &[
+        CFFInt(5), // width
+        CFFInt(10), CFFInt(20), CFFInt(30), CFFInt(40), UInt8(operator::HORIZONTAL_STEM_HINT_MASK),
+        CFFInt(100), CFFInt(50), UInt8(operator::HINT_MASK), UInt8(0b1110_0000),
+        UInt8(operator::COUNTER_MASK), UInt8(0b1100_0000),
+        CFFInt(10), CFFInt(20), UInt8(operator::MOVE_TO),
+        UInt8(operator::HINT_MASK), UInt8(0b1010_0000),
+        CFFInt(30), CFFInt(40), UInt8(operator::LINE_TO),
+        UInt8(operator::ENDCHAR),
+    ]);
+
+    let table = cff::Table::parse(&data).unwrap();
+    let mut builder = HintingBuilder(String::new());
+    let bbox = table.outline_with_hints(GlyphId(0), &mut builder).unwrap();
+
+    assert_eq!(builder.0, "H 10 20 H 60 40 V 100 50 HM [224] CM [192] \
+                           M 10 20 HM [160] L 40 60 Z ");
+    assert_eq!(bbox, rect(10, 20, 40, 60));
+
+    // Hints are not required to outline a glyph.
+    let mut builder = Builder(String::new());
+    table.outline(GlyphId(0), &mut builder).unwrap();
+    assert_eq!(builder.0, "M 10 20 L 40 60 Z ");
+}
+
+test_cs_err!(truncated_hint_mask, &[
+    CFFInt(10), CFFInt(20), UInt8(operator::HORIZONTAL_STEM_HINT_MASK),
+    UInt8(operator::HINT_MASK),
+], CFFError::ReadOutOfBounds);
+
+#[test]
+fn hinting_parameters() {
+    let data = convert(&[
+        // Header
+        UInt8(1), // major version
+        UInt8(0), // minor version
+        UInt8(4), // header size
+        UInt8(0), // absolute offset
+
+        // Name INDEX
+        UInt16(0), // count
+
+        // Top DICT
+        // INDEX
+        UInt16(1), // count
+        UInt8(1), // offset size
+        UInt8(1), // index[0]
+        UInt8(6), // index[1]
+        // Data
+        CFFInt(20),
+        UInt8(top_dict_operator::CHAR_STRINGS_OFFSET as u8),
+        CFFInt(14), // length
+        CFFInt(28), // offset
+        UInt8(top_dict_operator::PRIVATE_DICT_SIZE_AND_OFFSET as u8),
+
+        // String INDEX
+        UInt16(0), // count
+
+        // Global Subroutines INDEX
+        UInt16(0), // count
+
+        // CharString INDEX
+        UInt16(1), // count
+        UInt8(1), // offset size
+        UInt8(1), // index[0]
+        UInt8(4), // index[1]
+        // Data
+        CFFInt(10),
+        UInt8(operator::HORIZONTAL_MOVE_TO),
+        UInt8(operator::ENDCHAR),
+
+        // Private DICT
+        CFFInt(-15), CFFInt(15), CFFInt(500), CFFInt(15),
+        UInt8(private_dict_operator::BLUE_VALUES as u8),
+        CFFInt(-250), CFFInt(10),
+        UInt8(private_dict_operator::OTHER_BLUES as u8),
+        CFFInt(50),
+        UInt8(private_dict_operator::STD_HW as u8),
+        CFFInt(80),
+        UInt8(private_dict_operator::STD_VW as u8),
+    ]);
+
+    let table = cff::Table::parse(&data).unwrap();
+    let params = table.hinting_parameters(GlyphId(0)).unwrap();
+    assert_eq!(params.blue_values.len(), 2);
+    assert_eq!(params.blue_values.iter().collect::<Vec<_>>(), vec![(-15.0, 0.0), (500.0, 515.0)]);
+    assert_eq!(params.other_blues.get(0), Some((-250.0, -240.0)));
+    assert_eq!(params.other_blues.get(1), None);
+    assert!(params.family_blues.is_empty());
+    assert_eq!(params.std_hw, Some(50.0));
+    assert_eq!(params.std_vw, Some(80.0));
+    assert_eq!(params.blue_scale, 0.039625);
+    assert_eq!(params.blue_shift, 7.0);
+    assert_eq!(params.blue_fuzz, 1.0);
+
+    // No Private DICT.
+    let data = gen_cff(&[], &[], &[
+        CFFInt(10), UInt8(operator::HORIZONTAL_MOVE_TO),
+        UInt8(operator::ENDCHAR),
+    ]);
+    let table = cff::Table::parse(&data).unwrap();
+    assert!(table.hinting_parameters(GlyphId(0)).is_none());
+}
@@ -383,7 +383,7 @@ mod colr1_static {
 
 
 mod colr1_variable {
-    use ttf_parser::{Face, GlyphId, RgbaColor, Tag};
+    use ttf_parser::{Face, GlyphId, RgbaColor, Tag, Variation};
     use ttf_parser::colr::ClipBox;
     use ttf_parser::colr::GradientExtend::*;
     use crate::colr::{COLR1_STATIC, COLR1_VARIABLE, CustomStop, VecPainter};
@@ -452,6 +452,17 @@ mod colr1_variable {
         assert!(vec_painter.0.contains(&Transform(ttf_parser::Transform::new_rotate(0.87341005))))
     }
 
+    #[test]
+    fn rotate_at_location() {
+        let face = Face::parse(COLR1_VARIABLE, 0).unwrap();
+        let variation = Variation { axis: Tag::from_bytes(b"ROTA"), value: 150.0 };
+        let location = face.variation_location(&[variation]).unwrap();
+        let mut vec_painter = VecPainter(vec![]);
+        face.paint_color_glyph_at(GlyphId(99), 0, RgbaColor::new(0, 0, 0, 255), &mut vec_painter, &location);
+        assert!(vec_painter.0.contains(&Transform(ttf_parser::Transform::new_rotate(0.87341005))));
+        assert!(!face.has_non_default_variation_coordinates());
+    }
+
     #[test]
     fn rotate_around_center() {
         let mut face = Face::parse(COLR1_VARIABLE, 0).unwrap();
@@ -0,0 +1,111 @@
+use ttf_parser::cvar::Table;
+use ttf_parser::{Face, NormalizedCoordinate, Tag, Variation};
+use crate::{convert, Unit::*};
+
+pub fn cvar_data() -> Vec<u8> {
+    convert(&[
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt16(2), // tuple variation count
+        UInt16(20), // data offset
+
+        // TupleVariationHeader [0]
+        UInt16(5), // variation data size
+        UInt16(0xA000), // embedded peak tuple and private point numbers
+        Int16(16384), // peak tuple
+
+        // TupleVariationHeader [1]
+        UInt16(5), // variation data size
+        UInt16(0xA000), // embedded peak tuple and private point numbers
+        Int16(16384), // peak tuple
+
+        // Serialized data [0]
+        UInt8(0), // all points
+        UInt8(2), // 3 byte deltas
+        Int8(10),
+        Int8(-20),
+        Int8(30),
+
+        // Serialized data [1]
+        UInt8(1), // one point
+        UInt8(0), // 1 byte point number
+        UInt8(2),
+        UInt8(0), // 1 byte delta
+        Int8(5),
+    ])
+}
+
+fn apply(table: &Table, coordinate: i16, len: usize) -> Option<Vec<f32>> {
+    let mut values = vec![0.0; len];
+    table.apply(&[NormalizedCoordinate::from(coordinate)], &mut values)?;
+    Some(values)
+}
+
+#[test]
+fn basic() {
+    let data = cvar_data();
+    let table = Table::parse(&data).unwrap();
+    assert_eq!(apply(&table, 16384, 3), Some(vec![10.0, -20.0, 35.0]));
+    assert_eq!(apply(&table, 8192, 3), Some(vec![5.0, -10.0, 17.5]));
+    assert_eq!(apply(&table, 0, 3), Some(vec![0.0, 0.0, 0.0]));
+    assert_eq!(apply(&table, -16384, 3), Some(vec![0.0, 0.0, 0.0]));
+}
+
+#[test]
+fn fewer_values() {
+    let data = cvar_data();
+    let table = Table::parse(&data).unwrap();
+    assert_eq!(apply(&table, 16384, 2), Some(vec![10.0, -20.0]));
+}
+
+#[test]
+fn unsupported_version() {
+    let data = convert(&[
+        UInt16(2), // major version
+        UInt16(0), // minor version
+        UInt16(0), // tuple variation count
+        UInt16(8), // data offset
+    ]);
+
+    assert!(Table::parse(&data).is_none());
+}
+
+#[test]
+fn face_cvt_deltas() {
+    let fvar = crate::fvar();
+    let cvt = convert(&[
+        Int16(100),
+        Int16(200),
+        Int16(300),
+    ]);
+    let cvar = cvar_data();
+    let data = crate::build_demo_font(&[
+        (b"cvar", &cvar),
+        (b"cvt ", &cvt),
+        (b"fvar", &fvar),
+    ]);
+
+    let mut face = Face::parse(&data, 0).unwrap();
+    assert_eq!(face.tables().cvt.unwrap().values.len(), 3);
+
+    let mut deltas = [1.0; 3];
+    face.cvt_deltas(&mut deltas).unwrap();
+    assert_eq!(deltas, [0.0, 0.0, 0.0]);
+
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    face.cvt_deltas(&mut deltas).unwrap();
+    assert_eq!(deltas, [10.0, -20.0, 35.0]);
+
+    let location = face.variation_location(&[
+        Variation { axis: Tag::from_bytes(b"wght"), value: 650.0 },
+    ]).unwrap();
+    face.cvt_deltas_at(&mut deltas, &location).unwrap();
+    assert_eq!(deltas, [5.0, -10.0, 17.5]);
+}
+
+#[test]
+fn face_without_cvar() {
+    let face = Face::parse(crate::DEMO_FONT, 0).unwrap();
+    let mut deltas = [0.0; 3];
+    assert!(face.cvt_deltas(&mut deltas).is_none());
+}
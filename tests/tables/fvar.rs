@@ -0,0 +1,124 @@
+use ttf_parser::fvar::Table;
+use ttf_parser::Face;
+use crate::{convert, Unit, Unit::*};
+
+fn fvar(instance_count: u16, instance_size: u16, instances: &[Unit]) -> Vec<u8> {
+    let mut units = vec![
+        Fixed(1.0), // version
+        UInt16(16), // axes array offset
+        UInt16(2), // reserved
+        UInt16(2), // axis count
+        UInt16(20), // axis size
+        UInt16(instance_count), // instance count
+        UInt16(instance_size), // instance size
+
+        // VariationAxisRecord [0]
+        Raw(b"wght"), // axis tag
+        Fixed(100.0), // min value
+        Fixed(400.0), // default value
+        Fixed(900.0), // max value
+        UInt16(0), // flags
+        UInt16(256), // axis name ID
+
+        // VariationAxisRecord [1]
+        Raw(b"wdth"), // axis tag
+        Fixed(50.0), // min value
+        Fixed(100.0), // default value
+        Fixed(200.0), // max value
+        UInt16(0), // flags
+        UInt16(257), // axis name ID
+    ];
+    units.extend_from_slice(instances);
+    convert(&units)
+}
+
+fn bold_condensed() -> Vec<u8> {
+    fvar(2, 14, &[
+        // InstanceRecord [0]
+        UInt16(258), // subfamily name ID
+        UInt16(0), // flags
+        Fixed(400.0), // coordinate [0]
+        Fixed(100.0), // coordinate [1]
+        UInt16(0xFFFF), // PostScript name ID
+
+        // InstanceRecord [1]
+        UInt16(259), // subfamily name ID
+        UInt16(0), // flags
+        Fixed(700.0), // coordinate [0]
+        Fixed(75.0), // coordinate [1]
+        UInt16(260), // PostScript name ID
+    ])
+}
+
+#[test]
+fn named_instances() {
+    let data = bold_condensed();
+    let table = Table::parse(&data).unwrap();
+    assert_eq!(table.axes.len(), 2);
+    assert_eq!(table.named_instances.len(), 2);
+
+    let regular = table.named_instances.get(0).unwrap();
+    assert_eq!(regular.subfamily_name_id, 258);
+    assert_eq!(regular.post_script_name_id, None);
+
+    let bold_condensed = table.named_instances.get(1).unwrap();
+    assert_eq!(bold_condensed.subfamily_name_id, 259);
+    assert_eq!(bold_condensed.post_script_name_id, Some(260));
+    assert_eq!(bold_condensed.flags, 0);
+    let coordinates: Vec<_> = bold_condensed.coordinates.into_iter().map(|c| c.0).collect();
+    assert_eq!(coordinates, [700.0, 75.0]);
+
+    assert!(table.named_instances.get(2).is_none());
+    assert_eq!(table.named_instances.into_iter().count(), 2);
+}
+
+#[test]
+fn named_instance_without_post_script_name() {
+    let data = fvar(1, 12, &[
+        // InstanceRecord [0]
+        UInt16(258), // subfamily name ID
+        UInt16(0), // flags
+        Fixed(700.0), // coordinate [0]
+        Fixed(75.0), // coordinate [1]
+    ]);
+
+    let table = Table::parse(&data).unwrap();
+    let instance = table.named_instances.get(0).unwrap();
+    assert_eq!(instance.subfamily_name_id, 258);
+    assert_eq!(instance.post_script_name_id, None);
+    assert_eq!(instance.coordinates.len(), 2);
+}
+
+#[test]
+fn malformed_named_instances() {
+    // Instance records are out of bounds, but axes are still usable.
+    let data = fvar(3, 14, &[]);
+    let table = Table::parse(&data).unwrap();
+    assert_eq!(table.axes.len(), 2);
+    assert!(table.named_instances.is_empty());
+
+    // Instance size is too small for two coordinates.
+    let data = fvar(1, 8, &[UInt16(258), UInt16(0), Fixed(700.0)]);
+    let table = Table::parse(&data).unwrap();
+    assert!(table.named_instances.is_empty());
+}
+
+#[test]
+fn set_named_instance() {
+    let data = crate::build_demo_font(&[(b"fvar", &bold_condensed())]);
+    let mut face = Face::parse(&data, 0).unwrap();
+    assert_eq!(face.named_instances().len(), 2);
+
+    let location = face.named_instance_location(1).unwrap();
+    let coordinates: Vec<_> = location.coordinates().iter().map(|c| c.get()).collect();
+    assert_eq!(coordinates, [9830, -8192]); // 0.6 and -0.5
+
+    face.set_named_instance(1).unwrap();
+    assert_eq!(face.variation_coordinates(), location.coordinates());
+
+    face.set_named_instance(0).unwrap();
+    assert!(!face.has_non_default_variation_coordinates());
+
+    assert!(face.set_named_instance(2).is_none());
+    assert!(face.named_instance_location(2).is_none());
+}
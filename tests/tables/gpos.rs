@@ -0,0 +1,303 @@
+use ttf_parser::gpos::{Device, PairAdjustment, PositioningSubtable, SingleAdjustment};
+use ttf_parser::{Face, GlyphId, Tag};
+use crate::{convert, Unit::*};
+
+pub fn gdef() -> Vec<u8> {
+    convert(&[
+        UInt32(0x00010003), // version
+        UInt16(0), // glyph class definition offset
+        UInt16(0), // attachment point list offset
+        UInt16(0), // ligature caret list offset
+        UInt16(0), // mark attachment class definition offset
+        UInt16(0), // mark glyph sets definition offset
+        UInt32(18), // item variation store offset
+
+        // ItemVariationStore
+        UInt16(1), // format
+        UInt32(12), // variation region list offset
+        UInt16(1), // item variation data count
+        UInt32(22), // item variation data offset [0]
+
+        // VariationRegionList
+        UInt16(1), // axis count
+        UInt16(1), // region count
+        Int16(0), // start coord
+        Int16(16384), // peak coord
+        Int16(16384), // end coord
+
+        // ItemVariationData [0]
+        UInt16(2), // item count
+        UInt16(0), // word delta count
+        UInt16(1), // region index count
+        UInt16(0), // region index [0]
+        Int8(50), // delta set [0]
+        Int8(-20), // delta set [1]
+    ])
+}
+
+pub fn gpos() -> Vec<u8> {
+    convert(&[
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt16(10), // script list offset
+        UInt16(12), // feature list offset
+        UInt16(14), // lookup list offset
+
+        // ScriptList
+        UInt16(0), // count
+
+        // FeatureList
+        UInt16(0), // count
+
+        // LookupList
+        UInt16(2), // count
+        UInt16(6), // lookup offset [0]
+        UInt16(36), // lookup offset [1]
+
+        // Lookup [0]
+        UInt16(1), // type: single adjustment
+        UInt16(0), // flags
+        UInt16(1), // subtables count
+        UInt16(8), // subtable offset [0]
+
+        // SinglePosFormat1
+        UInt16(1), // format
+        UInt16(10), // coverage offset
+        UInt16(0x0044), // value format: X_ADVANCE | X_ADVANCE_DEVICE
+        Int16(100), // x advance
+        UInt16(16), // x advance device offset
+
+        // Coverage
+        UInt16(1), // format
+        UInt16(1), // count
+        UInt16(1), // glyph [0]
+
+        // VariationIndex
+        UInt16(0), // outer index
+        UInt16(0), // inner index
+        UInt16(0x8000), // format
+
+        // Lookup [1]
+        UInt16(3), // type: cursive attachment
+        UInt16(0), // flags
+        UInt16(1), // subtables count
+        UInt16(8), // subtable offset [0]
+
+        // CursivePosFormat1
+        UInt16(1), // format
+        UInt16(10), // coverage offset
+        UInt16(1), // entry exit count
+        UInt16(16), // entry anchor offset [0]
+        UInt16(0), // exit anchor offset [0]
+
+        // Coverage
+        UInt16(1), // format
+        UInt16(1), // count
+        UInt16(1), // glyph [0]
+
+        // AnchorFormat3
+        UInt16(3), // format
+        Int16(10), // x
+        Int16(20), // y
+        UInt16(10), // x device offset
+        UInt16(0), // y device offset
+
+        // VariationIndex
+        UInt16(0), // outer index
+        UInt16(1), // inner index
+        UInt16(0x8000), // format
+    ])
+}
+
+fn gpos_kern() -> Vec<u8> {
+    convert(&[
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt16(10), // script list offset
+        UInt16(30), // feature list offset
+        UInt16(44), // lookup list offset
+
+        // ScriptList
+        UInt16(1), // count
+        Raw(b"DFLT"), // script tag [0]
+        UInt16(8), // script offset [0]
+
+        // Script
+        UInt16(4), // default language system offset
+        UInt16(0), // language system count
+
+        // LangSys
+        UInt16(0), // lookup order offset
+        UInt16(0xFFFF), // required feature index
+        UInt16(1), // feature index count
+        UInt16(0), // feature index [0]
+
+        // FeatureList
+        UInt16(1), // count
+        Raw(b"kern"), // feature tag [0]
+        UInt16(8), // feature offset [0]
+
+        // Feature
+        UInt16(0), // feature params offset
+        UInt16(1), // lookup index count
+        UInt16(0), // lookup index [0]
+
+        // LookupList
+        UInt16(1), // count
+        UInt16(4), // lookup offset [0]
+
+        // Lookup [0]
+        UInt16(2), // type: pair adjustment
+        UInt16(0), // flags
+        UInt16(1), // subtables count
+        UInt16(8), // subtable offset [0]
+
+        // PairPosFormat1
+        UInt16(1), // format
+        UInt16(12), // coverage offset
+        UInt16(0x0044), // value format 1: X_ADVANCE | X_ADVANCE_DEVICE
+        UInt16(0), // value format 2
+        UInt16(1), // pair set count
+        UInt16(18), // pair set offset [0]
+
+        // Coverage
+        UInt16(1), // format
+        UInt16(1), // count
+        UInt16(1), // glyph [0]
+
+        // PairSet [0]
+        UInt16(1), // count
+        UInt16(2), // second glyph [0]
+        Int16(-80), // x advance [0]
+        UInt16(8), // x advance device offset [0]
+
+        // VariationIndex
+        UInt16(0), // outer index
+        UInt16(0), // inner index
+        UInt16(0x8000), // format
+    ])
+}
+
+#[test]
+fn glyphs_kerning() {
+    let (gdef, gpos, fvar, kern) = (gdef(), gpos_kern(), crate::fvar(), crate::kern::kern());
+    let data = crate::build_demo_font(&[
+        (b"GDEF", &gdef),
+        (b"GPOS", &gpos),
+        (b"fvar", &fvar),
+        (b"kern", &kern),
+    ]);
+    let mut face = Face::parse(&data, 0).unwrap();
+
+    assert_eq!(face.glyphs_kerning(GlyphId(1), GlyphId(2)), Some(-80.0));
+    // GPOS has a `kern` feature, so the `kern` table is ignored.
+    assert_eq!(face.glyphs_kerning(GlyphId(1), GlyphId(3)), None);
+    // But not for vertical kerning, since there is no `vkrn` feature.
+    assert_eq!(face.glyphs_ver_kerning(GlyphId(1), GlyphId(2)), Some(-10.0));
+
+    face.set_variation(Tag::from_bytes(b"wght"), 650.0).unwrap();
+    assert_eq!(face.glyphs_kerning(GlyphId(1), GlyphId(2)), Some(-55.0));
+
+    let location = face.variation_location(&[]).unwrap();
+    assert_eq!(face.glyphs_kerning_at(GlyphId(1), GlyphId(2), &location), Some(-80.0));
+}
+
+#[test]
+fn variation_devices() {
+    let (gdef, gpos, fvar) = (gdef(), gpos(), crate::fvar());
+    let data = crate::build_demo_font(&[(b"GDEF", &gdef), (b"GPOS", &gpos), (b"fvar", &fvar)]);
+    let mut face = Face::parse(&data, 0).unwrap();
+    let gdef = face.tables().gdef.unwrap();
+    let lookups = face.tables().gpos.unwrap().lookups;
+
+    let value = match lookups.get(0).unwrap().subtables.get::<PositioningSubtable>(0) {
+        Some(PositioningSubtable::Single(SingleAdjustment::Format1 { value, .. })) => value,
+        _ => panic!("invalid subtable"),
+    };
+    let anchor = match lookups.get(1).unwrap().subtables.get::<PositioningSubtable>(0) {
+        Some(PositioningSubtable::Cursive(adjustment)) => adjustment.sets.entry(0).unwrap(),
+        _ => panic!("invalid subtable"),
+    };
+
+    assert!(matches!(value.x_advance_device, Some(Device::Variation(_))));
+    assert_eq!(value.x_advance_at(&gdef, face.variation_coordinates()), 100.0);
+    assert_eq!(anchor.x_at(&gdef, face.variation_coordinates()), 10.0);
+
+    face.set_variation(Tag::from_bytes(b"wght"), 650.0).unwrap();
+    assert_eq!(value.x_advance_at(&gdef, face.variation_coordinates()), 125.0);
+    assert_eq!(anchor.x_at(&gdef, face.variation_coordinates()), 0.0);
+
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    assert_eq!(value.x_advance, 100);
+    assert_eq!(value.x_advance_at(&gdef, face.variation_coordinates()), 150.0);
+    assert_eq!(value.y_advance_at(&gdef, face.variation_coordinates()), 0.0);
+    assert_eq!(anchor.x_at(&gdef, face.variation_coordinates()), -10.0);
+    // No device.
+    assert_eq!(anchor.y_at(&gdef, face.variation_coordinates()), 20.0);
+}
+
+#[test]
+fn pair_set_variation_devices() {
+    let gpos = convert(&[
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt16(10), // script list offset
+        UInt16(12), // feature list offset
+        UInt16(14), // lookup list offset
+
+        // ScriptList
+        UInt16(0), // count
+
+        // FeatureList
+        UInt16(0), // count
+
+        // LookupList
+        UInt16(1), // count
+        UInt16(4), // lookup offset [0]
+
+        // Lookup [0]
+        UInt16(2), // type: pair adjustment
+        UInt16(0), // flags
+        UInt16(1), // subtables count
+        UInt16(8), // subtable offset [0]
+
+        // PairPosFormat1
+        UInt16(1), // format
+        UInt16(12), // coverage offset
+        UInt16(0x0044), // value format 1: X_ADVANCE | X_ADVANCE_DEVICE
+        UInt16(0), // value format 2
+        UInt16(1), // pair set count
+        UInt16(18), // pair set offset [0]
+
+        // Coverage
+        UInt16(1), // format
+        UInt16(1), // count
+        UInt16(1), // glyph [0]
+
+        // PairSet [0]
+        UInt16(1), // count
+        UInt16(2), // second glyph [0]
+        Int16(-80), // x advance [0]
+        UInt16(8), // x advance device offset [0], from the beginning of the PairSet
+
+        // VariationIndex
+        UInt16(0), // outer index
+        UInt16(0), // inner index
+        UInt16(0x8000), // format
+    ]);
+    let (gdef, fvar) = (gdef(), crate::fvar());
+    let data = crate::build_demo_font(&[(b"GDEF", &gdef), (b"GPOS", &gpos), (b"fvar", &fvar)]);
+    let mut face = Face::parse(&data, 0).unwrap();
+    let gdef = face.tables().gdef.unwrap();
+    let lookups = face.tables().gpos.unwrap().lookups;
+
+    let set = match lookups.get(0).unwrap().subtables.get::<PositioningSubtable>(0) {
+        Some(PositioningSubtable::Pair(PairAdjustment::Format1 { sets, .. })) => sets.get(0).unwrap(),
+        _ => panic!("invalid subtable"),
+    };
+    let value = set.get(GlyphId(2)).unwrap().0;
+    assert!(matches!(value.x_advance_device, Some(Device::Variation(_))));
+
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    assert_eq!(value.x_advance_at(&gdef, face.variation_coordinates()), -30.0);
+}
@@ -0,0 +1,104 @@
+use ttf_parser::{Face, NormalizedCoordinate, Tag};
+use crate::{convert, Unit::*};
+
+// A GSUB table with a single `rclt` feature in the `DFLT` script,
+// which is substituted when `wght` is at least halfway to its maximum.
+// Lookups are not needed.
+pub fn gsub_with_feature_variations() -> Vec<u8> {
+    convert(&[
+        UInt16(1), // major version
+        UInt16(1), // minor version
+        UInt16(14), // script list offset
+        UInt16(34), // feature list offset
+        UInt16(48), // lookup list offset
+        UInt32(50), // feature variations offset
+
+        // ScriptList
+        UInt16(1), // count
+        Raw(b"DFLT"), // script tag [0]
+        UInt16(8), // script offset [0]
+
+        // Script
+        UInt16(4), // default language system offset
+        UInt16(0), // language systems count
+
+        // LangSys
+        UInt16(0), // lookup order offset
+        UInt16(0xFFFF), // required feature index
+        UInt16(1), // feature indices count
+        UInt16(0), // feature index [0]
+
+        // FeatureList
+        UInt16(1), // count
+        Raw(b"rclt"), // feature tag [0]
+        UInt16(8), // feature offset [0]
+
+        // Feature
+        UInt16(0), // feature params offset
+        UInt16(1), // lookup indices count
+        UInt16(0), // lookup index [0]
+
+        // LookupList
+        UInt16(0), // count
+
+        // FeatureVariations
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt32(1), // records count
+        UInt32(16), // condition set offset [0]
+        UInt32(30), // feature table substitution offset [0]
+
+        // ConditionSet
+        UInt16(1), // count
+        UInt32(6), // condition offset [0]
+
+        // ConditionFormat1
+        UInt16(1), // format
+        UInt16(0), // axis index
+        Int16(8192), // filter range min
+        Int16(16384), // filter range max
+
+        // FeatureTableSubstitution
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt16(1), // substitutions count
+        UInt16(0), // feature index [0]
+        UInt32(12), // alternate feature offset [0]
+
+        // Feature
+        UInt16(0), // feature params offset
+        UInt16(1), // lookup indices count
+        UInt16(1), // lookup index [0]
+    ])
+}
+
+#[test]
+fn feature_lookups() {
+    let (gsub, fvar) = (gsub_with_feature_variations(), crate::fvar());
+    let data = crate::build_demo_font(&[(b"GSUB", &gsub), (b"fvar", &fvar)]);
+    let mut face = Face::parse(&data, 0).unwrap();
+    let gsub = face.tables().gsub.unwrap();
+
+    let script = Tag::from_bytes(b"latn");
+    let language = Some(Tag::from_bytes(b"DEU "));
+    let rclt = Tag::from_bytes(b"rclt");
+    let lookups = |coords: &[NormalizedCoordinate]| -> Vec<u16> {
+        gsub.feature_lookups_at(script, language, rclt, coords).unwrap().into_iter().collect()
+    };
+
+    // Unknown script and language fall back to defaults.
+    let indices: Vec<u16> = gsub.feature_lookups(script, language, rclt).unwrap().into_iter().collect();
+    assert_eq!(indices, vec![0]);
+    assert!(gsub.feature_lookups(script, None, Tag::from_bytes(b"liga")).is_none());
+
+    assert_eq!(lookups(face.variation_coordinates()), vec![0]);
+
+    face.set_variation(Tag::from_bytes(b"wght"), 650.0).unwrap();
+    assert_eq!(lookups(face.variation_coordinates()), vec![1]);
+
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    assert_eq!(lookups(face.variation_coordinates()), vec![1]);
+    // Feature variations are ignored.
+    let indices: Vec<u16> = gsub.feature_lookups(script, None, rclt).unwrap().into_iter().collect();
+    assert_eq!(indices, vec![0]);
+}
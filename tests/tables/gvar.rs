@@ -0,0 +1,307 @@
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+use ttf_parser::{Face, GlyphId, RawFace, Rect, Tag};
+use crate::{convert, Unit::*};
+
+struct Builder(String);
+
+impl ttf_parser::OutlineBuilder for Builder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        write!(&mut self.0, "M {} {} ", x, y).unwrap();
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        write!(&mut self.0, "L {} {} ", x, y).unwrap();
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        write!(&mut self.0, "Q {} {} {} {} ", x1, y1, x, y).unwrap();
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        write!(&mut self.0, "C {} {} {} {} {} {} ", x1, y1, x2, y2, x, y).unwrap();
+    }
+
+    fn close(&mut self) {
+        write!(&mut self.0, "Z ").unwrap();
+    }
+}
+
+// Glyph variation data with `count` identical tuples, which are using shared point numbers.
+fn glyph_variation_data(count: u16, shared_points: &[u8], tuple_data: &[u8]) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(0x8000 | count), // shared point numbers and tuple variation count
+        UInt16(4 + 6 * count), // data offset
+    ]);
+
+    for _ in 0..count {
+        data.extend_from_slice(&convert(&[
+            UInt16(tuple_data.len() as u16), // variation data size
+            UInt16(0x8000), // embedded peak tuple
+            Int16(16384), // peak tuple
+        ]));
+    }
+
+    data.extend_from_slice(shared_points);
+    for _ in 0..count {
+        data.extend_from_slice(tuple_data);
+    }
+
+    data.resize((data.len() + 1) & !1, 0);
+    data
+}
+
+// A gvar table with variation data for glyph 1.
+fn gvar(glyph_variation_data: &[u8]) -> Vec<u8> {
+    let mut data = convert(&[
+        Fixed(1.0), // version
+        UInt16(1), // axis count
+        UInt16(0), // shared tuple count
+        UInt32(26), // shared tuples offset
+        UInt16(2), // glyph count
+        UInt16(0), // flags
+        UInt32(26), // glyph variation data array offset
+        UInt16(0), // offset [0]
+        UInt16(0), // offset [1]
+        UInt16(glyph_variation_data.len() as u16 / 2), // offset [2]
+    ]);
+    data.extend_from_slice(glyph_variation_data);
+    data
+}
+
+fn build_font(gvar: &[u8], glyf: &[u8], loca: &[u8]) -> Vec<u8> {
+    let fvar = crate::fvar();
+    crate::build_demo_font(&[
+        (b"fvar", &fvar),
+        (b"glyf", glyf),
+        (b"gvar", gvar),
+        (b"loca", loca),
+    ])
+}
+
+fn build_demo_glyph_font(gvar: &[u8]) -> Vec<u8> {
+    let demo = RawFace::parse(crate::DEMO_FONT, 0).unwrap();
+    build_font(
+        gvar,
+        demo.table(Tag::from_bytes(b"glyf")).unwrap(),
+        demo.table(Tag::from_bytes(b"loca")).unwrap(),
+    )
+}
+
+// A font with glyph 1 being a single contour of 150 points from (1, 0) to (150, 0).
+fn build_long_glyph_font(gvar: &[u8]) -> Vec<u8> {
+    let mut glyf = convert(&[
+        Int16(1), // number of contours
+        Int16(1), // x min
+        Int16(0), // y min
+        Int16(150), // x max
+        Int16(0), // y max
+        UInt16(149), // end point [0]
+        UInt16(0), // instructions length
+        UInt8(0x3B), // flags: ON_CURVE_POINT | X_SHORT | REPEAT | X_POSITIVE | Y_SAME
+        UInt8(149), // repeat count
+    ]);
+    glyf.extend_from_slice(&[1; 150]); // x coordinates
+
+    let loca = convert(&[
+        UInt16(0), // offset [0]
+        UInt16(0), // offset [1]
+        UInt16(glyf.len() as u16 / 2), // offset [2]
+    ]);
+
+    build_font(gvar, &glyf, &loca)
+}
+
+// A font with glyph 0 being a triangle and glyph 1 consisting of 20 copies of it.
+fn build_composite_glyph_font(gvar: &[u8]) -> Vec<u8> {
+    let mut glyf = convert(&[
+        Int16(1), // number of contours
+        Int16(0), // x min
+        Int16(0), // y min
+        Int16(10), // x max
+        Int16(10), // y max
+        UInt16(2), // end point [0]
+        UInt16(0), // instructions length
+        UInt8(0x37), // flags: ON_CURVE_POINT | X_SHORT | Y_SHORT | X_POSITIVE | Y_POSITIVE
+        UInt8(0x37),
+        UInt8(0x37),
+        UInt8(0), UInt8(10), UInt8(0), // x coordinates
+        UInt8(0), UInt8(0), UInt8(10), // y coordinates
+        UInt8(0), // padding
+    ]);
+    let glyph_1_offset = glyf.len() as u16 / 2;
+    glyf.extend_from_slice(&convert(&[
+        Int16(-1), // number of contours
+        Int16(0), Int16(0), Int16(10), Int16(10), // bbox
+    ]));
+    for i in 0..20 {
+        let more_components = if i == 19 { 0 } else { 0x0020 };
+        glyf.extend_from_slice(&convert(&[
+            UInt16(0x0002 | more_components), // flags: ARGS_ARE_XY_VALUES | MORE_COMPONENTS
+            UInt16(0), // glyph ID
+            UInt8(0), UInt8(0), // offset
+        ]));
+    }
+    let loca = convert(&[
+        UInt16(0), // offset [0]
+        UInt16(glyph_1_offset), // offset [1]
+        UInt16(glyf.len() as u16 / 2), // offset [2]
+    ]);
+
+    build_font(gvar, &glyf, &loca)
+}
+
+// Glyph variation data with `count` identical tuples,
+// which are moving component `n` of the composite glyph by `n * delta` horizontally.
+// Phantom points are not moved.
+fn composite_glyph_variation_data(count: u16, delta: i16) -> Vec<u8> {
+    let mut tuple_data = convert(&[UInt8(0x40 | 19)]); // 20 two-byte deltas
+    for n in 1..=20 {
+        tuple_data.extend_from_slice(&convert(&[Int16(n * delta)]));
+    }
+    tuple_data.extend_from_slice(&convert(&[UInt8(0x80 | 27)])); // 28 zero deltas
+    glyph_variation_data(count, &[0], &tuple_data)
+}
+
+fn outline(data: &[u8]) -> (String, Rect) {
+    let mut face = Face::parse(data, 0).unwrap();
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    let mut builder = Builder(String::new());
+    let bbox = face.outline_glyph(GlyphId(1), &mut builder).unwrap();
+    (builder.0, bbox)
+}
+
+#[test]
+fn more_than_32_tuples() {
+    let tuple_data = |delta: i8| {
+        let mut data = vec![14]; // 15 one-byte deltas
+        data.extend_from_slice(&[delta as u8; 15]);
+        data.push(0x80 | 14); // 15 zero deltas
+        data
+    };
+
+    // 40 tuples that move all points by 1 are the same as a single one that moves them by 40.
+    let many = build_demo_glyph_font(&gvar(&glyph_variation_data(40, &[0], &tuple_data(1))));
+    let single = build_demo_glyph_font(&gvar(&glyph_variation_data(1, &[0], &tuple_data(40))));
+
+    let (path, bbox) = outline(&many);
+    assert_eq!(path, outline(&single).0);
+    assert_eq!(bbox, Rect { x_min: 46, y_min: 0, x_max: 581, y_max: 656 });
+
+    // Phantom points are affected as well.
+    let mut face = Face::parse(&many, 0).unwrap();
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    let points = face.glyph_phantom_points(GlyphId(1)).unwrap();
+    assert_eq!(points.left.x, 40.0);
+    assert_eq!(points.bottom.x, 40.0);
+}
+
+#[test]
+fn more_than_32_tuples_with_inferred_deltas() {
+    // Only the first and the last points are referenced.
+    let shared_points = [
+        2, // points count
+        1, // 2 one-byte point numbers
+        0, 149,
+    ];
+    let tuple_data = |delta: i8| vec![
+        1, delta as u8, delta as u8, // 2 one-byte deltas
+        0x80 | 1, // 2 zero deltas
+    ];
+
+    // 200 tuples are processed in groups and a window of points at a time.
+    for &count in &[40, 200] {
+        let data = build_long_glyph_font(&gvar(&glyph_variation_data(count, &shared_points, &tuple_data(1))));
+
+        // All points are moved by `count`, including the ones outside of the first points window.
+        let shift = i16::try_from(count).unwrap();
+        let mut expected = String::new();
+        write!(&mut expected, "M {} 0 ", 1 + shift).unwrap();
+        for x in 2..=150 {
+            write!(&mut expected, "L {} 0 ", x + shift).unwrap();
+        }
+        write!(&mut expected, "L {} 0 Z ", 1 + shift).unwrap();
+
+        let (path, bbox) = outline(&data);
+        assert_eq!(path, expected);
+        assert_eq!(bbox, Rect { x_min: 1 + shift, y_min: 0, x_max: 150 + shift, y_max: 0 });
+    }
+}
+
+#[test]
+fn more_than_32_tuples_in_composite_glyph() {
+    let build = |count: u16, delta: i16| {
+        build_composite_glyph_font(&gvar(&composite_glyph_variation_data(count, delta)))
+    };
+
+    // 40 tuples that move components by `n` are the same as a single one that moves them by `n * 40`.
+    let (path, bbox) = outline(&build(40, 1));
+    assert_eq!(path, outline(&build(1, 40)).0);
+    assert!(path.starts_with("M 40 0 L 50 0 L 50 10 L 40 0 Z M 80 0 "));
+    assert_eq!(bbox, Rect { x_min: 40, y_min: 0, x_max: 810, y_max: 10 });
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn delta_cache() {
+    let shared_points = [
+        2, // points count
+        1, // 2 one-byte point numbers
+        0, 149,
+    ];
+    let tuple_data = [
+        1, 10, 20, // 2 one-byte deltas
+        0x80 | 1, // 2 zero deltas
+    ];
+    let data = build_long_glyph_font(&gvar(&glyph_variation_data(1, &shared_points, &tuple_data)));
+    let mut face = Face::parse(&data, 0).unwrap();
+    let mut cache = ttf_parser::gvar::DeltaCache::new();
+
+    let outline_cached = |face: &Face, cache: &mut ttf_parser::gvar::DeltaCache| {
+        let mut builder = Builder(String::new());
+        let bbox = face.outline_glyph_with_cache(GlyphId(1), cache, &mut builder);
+        (builder.0, bbox)
+    };
+    let outline = |face: &Face| {
+        let mut builder = Builder(String::new());
+        let bbox = face.outline_glyph(GlyphId(1), &mut builder);
+        (builder.0, bbox)
+    };
+
+    assert_eq!(outline_cached(&face, &mut cache), outline(&face));
+    assert_eq!(cache.len(), 1);
+
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    let (path, bbox) = outline_cached(&face, &mut cache);
+    assert!(path.starts_with("M 11 0 L 12.067114 0 L 13.134228 0 "));
+    assert!(path.ends_with("L 170 0 L 11 0 Z "));
+    assert_eq!(bbox, Some(Rect { x_min: 11, y_min: 0, x_max: 170, y_max: 0 }));
+    assert_eq!(cache.len(), 1);
+    assert_eq!((path.clone(), bbox), outline(&face));
+
+    // Cached deltas are reused.
+    assert_eq!(outline_cached(&face, &mut cache), (path, bbox));
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn delta_cache_with_max_glyphs() {
+    let data = build_composite_glyph_font(&gvar(&composite_glyph_variation_data(40, 1)));
+    let mut face = Face::parse(&data, 0).unwrap();
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+
+    let mut builder = Builder(String::new());
+    let bbox = face.outline_glyph(GlyphId(1), &mut builder);
+
+    // Deltas of the composite glyph are evicted by its component,
+    // but they are still applied to all components.
+    let mut cache = ttf_parser::gvar::DeltaCache::with_max_glyphs(1);
+    let mut cached_builder = Builder(String::new());
+    let cached_bbox = face.outline_glyph_with_cache(GlyphId(1), &mut cache, &mut cached_builder);
+    assert_eq!((cached_builder.0, cached_bbox), (builder.0, bbox));
+    assert_eq!(cache.len(), 1);
+}
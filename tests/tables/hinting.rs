@@ -0,0 +1,228 @@
+use std::fmt::Write;
+
+use ttf_parser::hinting::HintingInstance;
+use ttf_parser::{Face, GlyphId};
+use crate::{convert, Unit::*};
+
+struct Builder(String);
+
+impl ttf_parser::OutlineBuilder for Builder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        write!(&mut self.0, "M {} {} ", x, y).unwrap();
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        write!(&mut self.0, "L {} {} ", x, y).unwrap();
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        write!(&mut self.0, "Q {} {} {} {} ", x1, y1, x, y).unwrap();
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        write!(&mut self.0, "C {} {} {} {} {} {} ", x1, y1, x2, y2, x, y).unwrap();
+    }
+
+    fn close(&mut self) {
+        write!(&mut self.0, "Z ").unwrap();
+    }
+}
+
+// A font with a single 550x550 square glyph and 1000 units per em.
+fn font(fpgm: &'static [u8], prep: &'static [u8], instructions: &'static [u8]) -> Vec<u8> {
+    let mut glyf = convert(&[
+        Int16(1), // number of contours
+        Int16(0), // x min
+        Int16(0), // y min
+        Int16(550), // x max
+        Int16(550), // y max
+        UInt16(3), // end point [0]
+        UInt16(instructions.len() as u16), // instructions length
+        Raw(instructions),
+        Raw(&[0x01, 0x01, 0x01, 0x01]), // flags
+        Int16(0), Int16(0), Int16(550), Int16(0), // x deltas
+        Int16(0), Int16(550), Int16(0), Int16(-550), // y deltas
+    ]);
+    if glyf.len() % 2 != 0 {
+        glyf.push(0);
+    }
+
+    let head = convert(&[
+        Fixed(1.0), // version
+        Fixed(1.0), // font revision
+        UInt32(0), // checksum adjustment
+        UInt32(0x5F0F3CF5), // magic number
+        UInt16(0), // flags
+        UInt16(1000), // units per em
+        Raw(&[0; 16]), // created and modified time
+        Int16(0), // x min
+        Int16(0), // y min
+        Int16(550), // x max
+        Int16(550), // y max
+        UInt16(0), // mac style
+        UInt16(6), // lowest PPEM
+        Int16(2), // font direction hint
+        Int16(0), // index to location format
+        Int16(0), // glyph data format
+    ]);
+
+    let hhea = convert(&[
+        Fixed(1.0), // version
+        Int16(800), // ascender
+        Int16(-200), // descender
+        Int16(0), // line gap
+        Raw(&[0; 24]),
+        UInt16(1), // number of metrics
+    ]);
+
+    let maxp = convert(&[
+        Fixed(1.0), // version
+        UInt16(1), // number of glyphs
+        UInt16(4), // max points
+        UInt16(1), // max contours
+        UInt16(0), // max composite points
+        UInt16(0), // max composite contours
+        UInt16(2), // max zones
+        UInt16(0), // max twilight points
+        UInt16(0), // max storage
+        UInt16(1), // max function defs
+        UInt16(0), // max instruction defs
+        UInt16(8), // max stack elements
+        UInt16(instructions.len() as u16), // max size of instructions
+        UInt16(0), // max component elements
+        UInt16(0), // max component depth
+    ]);
+
+    let hmtx = convert(&[
+        UInt16(600), // advance width
+        Int16(0), // left side bearing
+    ]);
+
+    let loca = convert(&[
+        UInt16(0), // offset [0]
+        UInt16(glyf.len() as u16 / 2), // offset [1]
+    ]);
+
+    let mut tables: Vec<(&[u8; 4], &[u8])> = vec![
+        (b"glyf", &glyf),
+        (b"head", &head),
+        (b"hhea", &hhea),
+        (b"hmtx", &hmtx),
+        (b"loca", &loca),
+        (b"maxp", &maxp),
+    ];
+    if !fpgm.is_empty() {
+        tables.push((b"fpgm", fpgm));
+    }
+    if !prep.is_empty() {
+        tables.push((b"prep", prep));
+    }
+    tables.sort_by_key(|(tag, _)| *tag);
+
+    let mut data = convert(&[
+        Raw(&[0x00, 0x01, 0x00, 0x00]), // magic
+        UInt16(tables.len() as u16), // numTables
+        UInt16(0), // searchRange
+        UInt16(0), // entrySelector
+        UInt16(0), // rangeShift
+    ]);
+
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, table) in &tables {
+        data.extend_from_slice(&convert(&[
+            Raw(*tag),
+            UInt32(0), // checksum
+            UInt32(offset as u32), // offset
+            UInt32(table.len() as u32), // length
+        ]));
+        offset += (table.len() + 3) & !3;
+    }
+
+    for (_, table) in &tables {
+        data.extend_from_slice(table);
+        data.resize((data.len() + 3) & !3, 0);
+    }
+
+    data
+}
+
+fn outline(data: &[u8], ppem: f32) -> String {
+    let face = Face::parse(data, 0).unwrap();
+    let instance = HintingInstance::new(&face, ppem).unwrap();
+    let mut builder = Builder(String::new());
+    instance.outline_glyph(&face, GlyphId(0), &mut builder).unwrap();
+    builder.0
+}
+
+// Rounds the points 0 and 2 on the x axis and the points 0 and 1 on the y axis.
+const ROUND_POINTS: &[u8] = &[
+    0x00, // SVTCA[y]
+    0xB1, 1, 0, // PUSHB[2] 1 0
+    0x2F, // MDAP[rnd]
+    0x2F, // MDAP[rnd]
+    0x01, // SVTCA[x]
+    0xB1, 2, 0, // PUSHB[2] 2 0
+    0x2F, // MDAP[rnd]
+    0x2F, // MDAP[rnd]
+    0x30, // IUP[y]
+    0x31, // IUP[x]
+];
+
+#[test]
+fn no_instructions() {
+    let data = font(&[], &[], &[]);
+    assert_eq!(outline(&data, 10.0), "M 0 0 L 0 5.5 L 5.5 5.5 L 5.5 0 L 0 0 Z ");
+}
+
+#[test]
+fn glyph_program() {
+    let data = font(&[], &[], ROUND_POINTS);
+    assert_eq!(outline(&data, 10.0), "M 0 0 L 0 6 L 6 6 L 6 0 L 0 0 Z ");
+    assert_eq!(outline(&data, 9.0), "M 0 0 L 0 5 L 5 5 L 5 0 L 0 0 Z ");
+}
+
+#[test]
+fn font_program_function() {
+    let fpgm = &[
+        0xB0, 0, // PUSHB[1] 0
+        0x2C, // FDEF
+        0x2F, // MDAP[rnd]
+        0x2D, // ENDF
+    ];
+    let instructions = &[
+        0x00, // SVTCA[y]
+        0xB3, 0, 0, 1, 0, // PUSHB[4] 0 0 1 0
+        0x2B, // CALL
+        0x2B, // CALL
+        0x30, // IUP[y]
+    ];
+    let data = font(fpgm, &[], instructions);
+    assert_eq!(outline(&data, 10.0), "M 0 0 L 0 6 L 5.5 6 L 5.5 0 L 0 0 Z ");
+}
+
+#[test]
+fn invalid_font_program() {
+    let data = font(&[0x2B], &[], &[]); // CALL without a function
+    let face = Face::parse(&data, 0).unwrap();
+    assert!(HintingInstance::new(&face, 10.0).is_none());
+}
+
+#[test]
+fn disabled_by_control_value_program() {
+    let prep = &[
+        0xB1, 1, 1, // PUSHB[2] 1 1
+        0x8E, // INSTCTRL
+    ];
+    let data = font(&[], prep, ROUND_POINTS);
+    let face = Face::parse(&data, 0).unwrap();
+    assert!(!HintingInstance::new(&face, 10.0).unwrap().is_hinting_enabled());
+    assert_eq!(outline(&data, 10.0), "M 0 0 L 0 5.5 L 5.5 5.5 L 5.5 0 L 0 0 Z ");
+}
+
+#[test]
+fn invalid_ppem() {
+    let data = font(&[], &[], &[]);
+    let face = Face::parse(&data, 0).unwrap();
+    assert!(HintingInstance::new(&face, 0.0).is_none());
+    assert!(HintingInstance::new(&face, -1.0).is_none());
+}
@@ -0,0 +1,266 @@
+use ttf_parser::gpos::{PositioningSubtable, SingleAdjustment};
+use ttf_parser::instance::{InstanceError, Instancer};
+use ttf_parser::{Face, GlyphId, RawFace, Rect, Tag};
+use crate::{convert, outline, Unit::*};
+
+// Builds a font with a `wght` axis and instances it at the maximum weight.
+fn instance(tables: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let demo = RawFace::parse(crate::DEMO_FONT, 0).unwrap();
+    let fvar = crate::fvar();
+    let mut tables = tables.to_vec();
+    tables.push((b"fvar", &fvar));
+    tables.push((b"hmtx", demo.table(Tag::from_bytes(b"hmtx")).unwrap()));
+    let data = crate::build_demo_font(&tables);
+
+    let face = Face::parse(&data, 0).unwrap();
+    let mut instancer = Instancer::new(&face).unwrap();
+    instancer.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    instancer.build().unwrap()
+}
+
+fn assert_same_glyphs(variable: &Face, instance: &Face) {
+    assert_eq!(variable.number_of_glyphs(), instance.number_of_glyphs());
+    for id in 0..variable.number_of_glyphs() {
+        let glyph_id = GlyphId(id);
+        assert_eq!(outline(variable, glyph_id), outline(instance, glyph_id));
+        assert_eq!(variable.glyph_hor_advance(glyph_id), instance.glyph_hor_advance(glyph_id));
+    }
+}
+
+#[test]
+fn not_variable() {
+    let face = Face::parse(crate::DEMO_FONT, 0).unwrap();
+    assert_eq!(Instancer::new(&face).unwrap_err(), InstanceError::NotVariable);
+}
+
+#[test]
+fn unknown_axis() {
+    let face = Face::parse(crate::VARIABLE_FONT, 0).unwrap();
+    let mut instancer = Instancer::new(&face).unwrap();
+    assert_eq!(instancer.set_variation(Tag::from_bytes(b"wght"), 700.0), None);
+}
+
+#[test]
+fn default_location() {
+    let face = Face::parse(crate::VARIABLE_FONT, 0).unwrap();
+    let data = Instancer::new(&face).unwrap().build().unwrap();
+    let instance = Face::parse(&data, 0).unwrap();
+    assert!(!instance.is_variable());
+    assert_same_glyphs(&face, &instance);
+
+    for tag in &[b"fvar", b"gvar", b"HVAR"] {
+        assert!(instance.raw_face().table(Tag::from_bytes(tag)).is_none());
+    }
+}
+
+#[test]
+fn ignores_face_coordinates() {
+    let face = Face::parse(crate::VARIABLE_FONT, 0).unwrap();
+    let mut variable = face.clone();
+    for axis in face.variation_axes() {
+        variable.set_variation(axis.tag, axis.max_value).unwrap();
+    }
+
+    let data = Instancer::new(&variable).unwrap().build().unwrap();
+    let instance = Face::parse(&data, 0).unwrap();
+    assert_same_glyphs(&face, &instance);
+}
+
+#[test]
+fn glyf() {
+    let face = Face::parse(crate::VARIABLE_FONT, 0).unwrap();
+    let mut variable = face.clone();
+    let mut instancer = Instancer::new(&face).unwrap();
+    for axis in face.variation_axes() {
+        variable.set_variation(axis.tag, axis.max_value).unwrap();
+        instancer.set_variation(axis.tag, axis.max_value).unwrap();
+    }
+
+    let data = instancer.build().unwrap();
+    let instance = Face::parse(&data, 0).unwrap();
+    assert_same_glyphs(&variable, &instance);
+
+    // Glyph 161 is moved by the variation.
+    assert_ne!(outline(&face, GlyphId(161)), outline(&instance, GlyphId(161)));
+    let bbox = instance.glyph_bounding_box(GlyphId(161)).unwrap();
+    assert_eq!(instance.glyph_hor_side_bearing(GlyphId(161)), Some(bbox.x_min));
+}
+
+#[test]
+fn cff2() {
+    let cff2 = convert(&[
+        // Header
+        UInt8(2), // major version
+        UInt8(0), // minor version
+        UInt8(5), // header size
+        UInt16(19), // top DICT length
+
+        // Top DICT
+        UInt8(29), Int32(28), UInt8(17), // CharStrings offset
+        UInt8(29), Int32(101), UInt8(12), UInt8(36), // FDArray offset
+        UInt8(29), Int32(69), UInt8(24), // VariationStore offset
+
+        // Global Subrs INDEX
+        UInt32(0), // count
+
+        // CharStrings INDEX
+        UInt32(2), // count
+        UInt8(1), // offset size
+        UInt8(1), // offset [0]
+        UInt8(16), // offset [1]
+        UInt8(34), // offset [2]
+
+        // Glyph 0
+        UInt8(239), UInt8(239), UInt8(21), // 100 100 rmoveto
+        UInt8(247), UInt8(92), UInt8(139), UInt8(5), // 200 0 rlineto
+        UInt8(139), UInt8(247), UInt8(92), UInt8(5), // 0 200 rlineto
+        UInt8(251), UInt8(92), UInt8(139), UInt8(5), // -200 0 rlineto
+
+        // Glyph 1
+        UInt8(239), UInt8(239), UInt8(21), // 100 100 rmoveto
+        UInt8(247), UInt8(92), UInt8(189), UInt8(140), UInt8(16), // 200 50 1 blend
+        UInt8(139), UInt8(5), // 0 rlineto
+        UInt8(139), UInt8(247), UInt8(92), UInt8(5), // 0 200 rlineto
+        UInt8(251), UInt8(92), UInt8(139), UInt8(5), // -200 0 rlineto
+
+        // VariationStore
+        UInt16(30), // length
+        UInt16(1), // format
+        UInt32(12), // variation region list offset
+        UInt16(1), // item variation data count
+        UInt32(22), // item variation data offset [0]
+
+        // VariationRegionList
+        UInt16(1), // axis count
+        UInt16(1), // region count
+        Int16(0), // start coord
+        Int16(16384), // peak coord
+        Int16(16384), // end coord
+
+        // ItemVariationData [0]
+        UInt16(0), // item count
+        UInt16(0), // word delta count
+        UInt16(1), // region index count
+        UInt16(0), // region index [0]
+
+        // FDArray INDEX
+        UInt32(1), // count
+        UInt8(1), // offset size
+        UInt8(1), // offset [0]
+        UInt8(12), // offset [1]
+
+        // Font DICT
+        UInt8(29), Int32(5), // Private DICT size
+        UInt8(29), Int32(119), UInt8(18), // Private DICT offset
+
+        // Private DICT
+        UInt8(189), UInt8(149), UInt8(140), UInt8(23), UInt8(11), // 50 10 1 blend StdVW
+    ]);
+
+    let data = crate::build_demo_font(&[(b"CFF2", &cff2), (b"fvar", &crate::fvar())]);
+    let mut variable = Face::parse(&data, 0).unwrap();
+    variable.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+
+    let data = instance(&[(b"CFF2", &cff2)]);
+    let face = Face::parse(&data, 0).unwrap();
+    assert!(face.tables().cff2.is_none());
+    let cff = face.tables().cff.unwrap();
+    for id in 0..2 {
+        let glyph_id = GlyphId(id);
+        assert_eq!(face.glyph_bounding_box(glyph_id), variable.glyph_bounding_box(glyph_id));
+    }
+    let bbox = Rect { x_min: 100, y_min: 100, x_max: 350, y_max: 300 };
+    assert_eq!(face.glyph_bounding_box(GlyphId(1)), Some(bbox));
+    assert_eq!(cff.hinting_parameters(GlyphId(1)).unwrap().std_vw, Some(60.0));
+}
+
+#[test]
+fn mvar() {
+    let mvar = convert(&[
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt16(0), // reserved
+        UInt16(8), // value record size
+        UInt16(1), // value records count
+        UInt16(20), // item variation store offset
+
+        // ValueRecord [0]
+        Raw(b"hcrs"), // value tag
+        UInt16(0), // delta set outer index
+        UInt16(0), // delta set inner index
+
+        // ItemVariationStore
+        UInt16(1), // format
+        UInt32(12), // variation region list offset
+        UInt16(1), // item variation data count
+        UInt32(22), // item variation data offset [0]
+
+        // VariationRegionList
+        UInt16(1), // axis count
+        UInt16(1), // region count
+        Int16(0), // start coord
+        Int16(16384), // peak coord
+        Int16(16384), // end coord
+
+        // ItemVariationData [0]
+        UInt16(1), // item count
+        UInt16(0), // word delta count
+        UInt16(1), // region index count
+        UInt16(0), // region index [0]
+        Int8(5), // delta set [0]
+    ]);
+
+    let data = instance(&[(b"MVAR", &mvar)]);
+    let face = RawFace::parse(&data, 0).unwrap();
+    assert!(face.table(Tag::from_bytes(b"MVAR")).is_none());
+    // caretSlopeRise is 1 in the original font.
+    let hhea = face.table(Tag::from_bytes(b"hhea")).unwrap();
+    assert_eq!(&hhea[18..20], &6i16.to_be_bytes());
+}
+
+#[test]
+fn cvar() {
+    let cvt = convert(&[Int16(100), Int16(200), Int16(300)]);
+    let data = instance(&[(b"cvar", &crate::cvar::cvar_data()), (b"cvt ", &cvt)]);
+    let face = Face::parse(&data, 0).unwrap();
+    assert!(face.tables().cvar.is_none());
+    let values: Vec<i16> = face.tables().cvt.unwrap().values.into_iter().collect();
+    assert_eq!(values, vec![110, 180, 335]);
+}
+
+#[test]
+fn variation_devices() {
+    let (gdef, gpos) = (crate::gpos::gdef(), crate::gpos::gpos());
+    let data = instance(&[(b"GDEF", &gdef), (b"GPOS", &gpos)]);
+    let face = Face::parse(&data, 0).unwrap();
+    let lookups = face.tables().gpos.unwrap().lookups;
+
+    let value = match lookups.get(0).unwrap().subtables.get::<PositioningSubtable>(0) {
+        Some(PositioningSubtable::Single(SingleAdjustment::Format1 { value, .. })) => value,
+        _ => panic!("invalid subtable"),
+    };
+    let anchor = match lookups.get(1).unwrap().subtables.get::<PositioningSubtable>(0) {
+        Some(PositioningSubtable::Cursive(adjustment)) => adjustment.sets.entry(0).unwrap(),
+        _ => panic!("invalid subtable"),
+    };
+
+    assert_eq!(value.x_advance, 150);
+    assert!(value.x_advance_device.is_none());
+    assert_eq!(anchor.x, -10);
+    assert!(anchor.x_device.is_none());
+    assert_eq!(anchor.y, 20);
+}
+
+#[test]
+fn feature_variations() {
+    let gsub = crate::gsub::gsub_with_feature_variations();
+    let data = instance(&[(b"GSUB", &gsub)]);
+    let face = Face::parse(&data, 0).unwrap();
+    let gsub = face.tables().gsub.unwrap();
+
+    let script = Tag::from_bytes(b"DFLT");
+    let rclt = Tag::from_bytes(b"rclt");
+    let indices: Vec<u16> = gsub.feature_lookups(script, None, rclt).unwrap().into_iter().collect();
+    assert_eq!(indices, vec![1]);
+    assert!(gsub.variations.is_none());
+}
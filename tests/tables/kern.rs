@@ -0,0 +1,71 @@
+use ttf_parser::{Face, GlyphId};
+use crate::{convert, Unit::*};
+
+pub fn kern() -> Vec<u8> {
+    convert(&[
+        UInt16(0), // version
+        UInt16(3), // number of subtables
+
+        // Subtable [0]
+        UInt16(0), // version
+        UInt16(26), // length
+        UInt8(0), // format
+        UInt8(0x01), // coverage: horizontal
+        UInt16(2), // number of pairs
+        UInt16(0), // search range
+        UInt16(0), // entry selector
+        UInt16(0), // range shift
+        UInt16(1), // left [0]
+        UInt16(2), // right [0]
+        Int16(-50), // value [0]
+        UInt16(1), // left [1]
+        UInt16(3), // right [1]
+        Int16(20), // value [1]
+
+        // Subtable [1]
+        UInt16(0), // version
+        UInt16(20), // length
+        UInt8(0), // format
+        UInt8(0x00), // coverage: vertical
+        UInt16(1), // number of pairs
+        UInt16(0), // search range
+        UInt16(0), // entry selector
+        UInt16(0), // range shift
+        UInt16(1), // left [0]
+        UInt16(2), // right [0]
+        Int16(-10), // value [0]
+
+        // Subtable [2]
+        UInt16(0), // version
+        UInt16(20), // length
+        UInt8(0), // format
+        UInt8(0x01), // coverage: horizontal
+        UInt16(1), // number of pairs
+        UInt16(0), // search range
+        UInt16(0), // entry selector
+        UInt16(0), // range shift
+        UInt16(1), // left [0]
+        UInt16(2), // right [0]
+        Int16(-5), // value [0]
+    ])
+}
+
+#[test]
+fn glyphs_kerning() {
+    let kern = kern();
+    let data = crate::build_demo_font(&[(b"kern", &kern)]);
+    let face = Face::parse(&data, 0).unwrap();
+
+    // Values from all subtables are summed.
+    assert_eq!(face.glyphs_kerning(GlyphId(1), GlyphId(2)), Some(-55.0));
+    assert_eq!(face.glyphs_kerning(GlyphId(1), GlyphId(3)), Some(20.0));
+    assert_eq!(face.glyphs_kerning(GlyphId(2), GlyphId(1)), None);
+    assert_eq!(face.glyphs_ver_kerning(GlyphId(1), GlyphId(2)), Some(-10.0));
+    assert_eq!(face.glyphs_ver_kerning(GlyphId(1), GlyphId(3)), None);
+}
+
+#[test]
+fn glyphs_kerning_without_tables() {
+    let face = Face::parse(crate::DEMO_FONT, 0).unwrap();
+    assert_eq!(face.glyphs_kerning(GlyphId(1), GlyphId(2)), None);
+}
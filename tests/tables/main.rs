@@ -1,16 +1,55 @@
 #[rustfmt::skip] mod aat;
 #[rustfmt::skip] mod ankr;
+#[cfg(feature = "variable-fonts")]
+#[rustfmt::skip] mod avar;
 #[rustfmt::skip] mod cff1;
 #[rustfmt::skip] mod cmap;
 #[rustfmt::skip] mod colr;
+#[cfg(feature = "variable-fonts")]
+#[rustfmt::skip] mod cvar;
 #[rustfmt::skip] mod feat;
+#[cfg(feature = "variable-fonts")]
+#[rustfmt::skip] mod fvar;
 #[rustfmt::skip] mod glyf;
+#[cfg(all(feature = "opentype-layout", feature = "variable-fonts"))]
+#[rustfmt::skip] mod gpos;
+#[cfg(all(feature = "opentype-layout", feature = "variable-fonts"))]
+#[rustfmt::skip] mod gsub;
+#[cfg(feature = "variable-fonts")]
+#[rustfmt::skip] mod gvar;
+#[cfg(feature = "hinting")]
+#[rustfmt::skip] mod hinting;
 #[rustfmt::skip] mod hmtx;
+#[cfg(feature = "instance")]
+#[rustfmt::skip] mod instance;
+#[rustfmt::skip] mod kern;
 #[rustfmt::skip] mod maxp;
+#[rustfmt::skip] mod owned;
+#[rustfmt::skip] mod resource_fork;
 #[rustfmt::skip] mod sbix;
+#[cfg(feature = "shaping")]
+#[rustfmt::skip] mod shaping;
+#[cfg(feature = "subset")]
+#[rustfmt::skip] mod subset;
 #[rustfmt::skip] mod trak;
+#[cfg(feature = "validate")]
+#[rustfmt::skip] mod validate;
+#[cfg(all(feature = "opentype-layout", feature = "variable-fonts"))]
+#[rustfmt::skip] mod varc;
+#[cfg(feature = "woff")]
+#[rustfmt::skip] mod woff;
+#[cfg(feature = "woff2")]
+#[rustfmt::skip] mod woff2;
+#[cfg(feature = "writer")]
+#[rustfmt::skip] mod writer;
 
-use ttf_parser::{fonts_in_collection, Face, FaceParsingError};
+#[cfg(any(feature = "subset", feature = "woff2"))]
+use std::fmt::Write;
+
+use ttf_parser::{
+    fonts_in_collection, Face, FaceParsingError, GlyphId, RawFace, TableParseError,
+    TableParseErrorReason, Tag,
+};
 
 #[allow(dead_code)]
 #[derive(Clone, Copy)]
@@ -63,6 +102,24 @@ fn convert_unit(unit: Unit, data: &mut Vec<u8>) {
     }
 }
 
+pub fn push_u16(data: &mut Vec<u8>, n: u16) {
+    data.extend_from_slice(&n.to_be_bytes());
+}
+
+pub fn push_u32(data: &mut Vec<u8>, n: u32) {
+    data.extend_from_slice(&n.to_be_bytes());
+}
+
+// Calculates a checksum of a table or a whole font, padding data to a multiple of four bytes.
+#[cfg(any(feature = "woff2", feature = "writer"))]
+pub fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
 #[test]
 fn empty_font() {
     assert_eq!(
@@ -187,3 +244,280 @@ fn font_index_overflow_on_regular_font() {
         FaceParsingError::FaceIndexOutOfBounds
     );
 }
+
+static DEMO_FONT: &[u8] = include_bytes!("../fonts/demo.ttf");
+
+// Table records of the demo font.
+const HMTX_RECORD: usize = 12 + 4 * 16;
+const LOCA_RECORD: usize = 12 + 5 * 16;
+
+#[test]
+fn no_failed_tables() {
+    let (_, failed) = Face::parse_with_report(DEMO_FONT, 0).unwrap();
+    assert!(failed.is_empty());
+}
+
+#[test]
+fn malformed_table() {
+    let mut data = DEMO_FONT.to_vec();
+    data[HMTX_RECORD + 12..HMTX_RECORD + 16].copy_from_slice(&2u32.to_be_bytes()); // length
+
+    let (face, failed) = Face::parse_with_report(&data, 0).unwrap();
+    assert!(face.tables().hmtx.is_none());
+    assert_eq!(failed.len(), 1);
+    assert_eq!(
+        failed.get(Tag::from_bytes(b"hmtx")),
+        Some(TableParseError {
+            tag: Tag::from_bytes(b"hmtx"),
+            table_offset: 248,
+            reason: TableParseErrorReason::Malformed,
+        })
+    );
+}
+
+#[test]
+fn out_of_bounds_table() {
+    let mut data = DEMO_FONT.to_vec();
+    data[HMTX_RECORD + 12..HMTX_RECORD + 16].copy_from_slice(&u32::MAX.to_be_bytes()); // length
+
+    let (_, failed) = Face::parse_with_report(&data, 0).unwrap();
+    let error = failed.get(Tag::from_bytes(b"hmtx")).unwrap();
+    assert_eq!(error.table_offset, 248);
+    assert_eq!(error.reason, TableParseErrorReason::OutOfBounds);
+}
+
+#[test]
+fn missing_table_dependency() {
+    let mut data = DEMO_FONT.to_vec();
+    data[LOCA_RECORD..LOCA_RECORD + 4].copy_from_slice(b"xxxx"); // tag
+
+    let (face, failed) = Face::parse_with_report(&data, 0).unwrap();
+    assert!(face.tables().glyf.is_none());
+    let error = failed.get(Tag::from_bytes(b"glyf")).unwrap();
+    assert_eq!(error.table_offset, 308);
+    assert_eq!(
+        error.reason,
+        TableParseErrorReason::MissingDependency(Tag::from_bytes(b"loca"))
+    );
+    assert_eq!(
+        error.to_string(),
+        "the 'glyf' table at offset 308 requires a valid 'loca' table"
+    );
+}
+
+#[test]
+fn valid_checksums() {
+    let face = RawFace::parse(DEMO_FONT, 0).unwrap();
+    assert_eq!(face.mismatched_table_checksums().count(), 0);
+    assert_eq!(face.is_checksum_adjustment_valid(), Some(true));
+}
+
+#[test]
+fn table_checksum_mismatch() {
+    let mut data = DEMO_FONT.to_vec();
+    let glyf = face_table_offset(&data, b"glyf");
+    data[glyf] ^= 0xFF;
+
+    let face = RawFace::parse(&data, 0).unwrap();
+    let tags: Vec<_> = face.mismatched_table_checksums().map(|r| r.tag).collect();
+    assert_eq!(tags, [Tag::from_bytes(b"glyf")]);
+    assert_eq!(face.is_checksum_adjustment_valid(), Some(false));
+}
+
+#[test]
+fn head_checksum_adjustment_mismatch() {
+    let mut data = DEMO_FONT.to_vec();
+    let head = face_table_offset(&data, b"head");
+    data[head + 8] ^= 0xFF; // checkSumAdjustment
+
+    // `checkSumAdjustment` isn't a part of the `head` checksum.
+    let face = RawFace::parse(&data, 0).unwrap();
+    assert_eq!(face.mismatched_table_checksums().count(), 0);
+    assert_eq!(face.is_checksum_adjustment_valid(), Some(false));
+}
+
+#[test]
+fn out_of_bounds_table_checksum() {
+    let mut data = DEMO_FONT.to_vec();
+    data[HMTX_RECORD + 12..HMTX_RECORD + 16].copy_from_slice(&u32::MAX.to_be_bytes()); // length
+
+    let face = RawFace::parse(&data, 0).unwrap();
+    let tags: Vec<_> = face.mismatched_table_checksums().map(|r| r.tag).collect();
+    assert_eq!(tags, [Tag::from_bytes(b"hmtx")]);
+    assert_eq!(face.calculate_checksum_adjustment(), None);
+}
+
+// Builds a font from the demo font mandatory tables and the provided tables.
+#[cfg(feature = "variable-fonts")]
+fn build_demo_font(tables: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let demo = RawFace::parse(DEMO_FONT, 0).unwrap();
+    let mut tables = tables.to_vec();
+    for tag in [b"head", b"hhea", b"maxp"].iter() {
+        tables.push((*tag, demo.table(Tag::from_bytes(tag)).unwrap()));
+    }
+    tables.sort_by_key(|(tag, _)| *tag);
+
+    let mut data = convert(&[
+        Unit::UInt32(0x00010000),          // magic
+        Unit::UInt16(tables.len() as u16), // number of tables
+        Unit::UInt16(0),                   // search range
+        Unit::UInt16(0),                   // entry selector
+        Unit::UInt16(0),                   // range shift
+    ]);
+
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, table) in &tables {
+        data.extend_from_slice(*tag);
+        data.extend_from_slice(&[0; 4]); // checksum
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+        data.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        offset += (table.len() + 3) & !3;
+    }
+
+    for (_, table) in &tables {
+        data.extend_from_slice(table);
+        data.resize((data.len() + 3) & !3, 0);
+    }
+
+    data
+}
+
+// An `fvar` table with a single `wght` axis: 100..400..900.
+#[cfg(feature = "variable-fonts")]
+fn fvar() -> Vec<u8> {
+    use Unit::*;
+    convert(&[
+        Fixed(1.0), // version
+        UInt16(16), // axes array offset
+        UInt16(2),  // reserved
+        UInt16(1),  // axis count
+        UInt16(20), // axis size
+        UInt16(0),  // instance count
+        UInt16(8),  // instance size
+        // VariationAxisRecord [0]
+        Raw(b"wght"), // axis tag
+        Fixed(100.0), // min value
+        Fixed(400.0), // default value
+        Fixed(900.0), // max value
+        UInt16(0),    // flags
+        UInt16(256),  // axis name ID
+    ])
+}
+
+#[cfg(any(feature = "subset", feature = "woff2"))]
+struct PathBuilder(String);
+
+#[cfg(any(feature = "subset", feature = "woff2"))]
+impl ttf_parser::OutlineBuilder for PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        write!(&mut self.0, "M {} {} ", x, y).unwrap();
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        write!(&mut self.0, "L {} {} ", x, y).unwrap();
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        write!(&mut self.0, "Q {} {} {} {} ", x1, y1, x, y).unwrap();
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        write!(&mut self.0, "C {} {} {} {} {} {} ", x1, y1, x2, y2, x, y).unwrap();
+    }
+
+    fn close(&mut self) {
+        write!(&mut self.0, "Z ").unwrap();
+    }
+}
+
+// Outlines a glyph into an SVG-like path. An empty glyph produces an empty path.
+#[cfg(any(feature = "subset", feature = "woff2"))]
+pub fn outline(face: &Face, glyph_id: GlyphId) -> String {
+    let mut builder = PathBuilder(String::new());
+    face.outline_glyph(glyph_id, &mut builder);
+    builder.0
+}
+
+fn face_table_offset(data: &[u8], tag: &[u8; 4]) -> usize {
+    let face = RawFace::parse(data, 0).unwrap();
+    let table = face.table(Tag::from_bytes(tag)).unwrap();
+    table.as_ptr() as usize - data.as_ptr() as usize
+}
+
+#[cfg(feature = "variable-fonts")]
+static VARIABLE_FONT: &[u8] = include_bytes!("../fonts/colr_1_variable.ttf");
+
+#[cfg(feature = "variable-fonts")]
+#[test]
+fn variation_location() {
+    use ttf_parser::Variation;
+
+    let face = Face::parse(VARIABLE_FONT, 0).unwrap();
+    let axes: Vec<_> = face.variation_axes().into_iter().collect();
+    // Only these axes affect outlines.
+    let variations: Vec<_> = axes
+        .iter()
+        .filter(|axis| axis.tag == Tag::from_bytes(b"CLXI") || axis.tag == Tag::from_bytes(b"CLYA"))
+        .map(|axis| Variation {
+            axis: axis.tag,
+            value: axis.max_value,
+        })
+        .collect();
+    assert_eq!(variations.len(), 2);
+
+    let location = face.variation_location(&variations).unwrap();
+    assert_eq!(location.coordinates().len(), axes.len());
+    assert!(location.has_non_default_coordinates());
+
+    let default_location = face.variation_location(&[]).unwrap();
+    assert!(!default_location.has_non_default_coordinates());
+
+    let mut varied = face.clone();
+    for v in &variations {
+        varied.set_variation(v.axis, v.value).unwrap();
+    }
+    assert_eq!(location.coordinates(), varied.variation_coordinates());
+    assert_eq!(ttf_parser::VariationLocation::new(&varied), location);
+    assert_eq!(ttf_parser::VariationLocation::new(&face), default_location);
+
+    let mut changed = false;
+    for id in 0..face.number_of_glyphs() {
+        let id = GlyphId(id);
+        let bbox = face.glyph_bounding_box_at(id, &location);
+        assert_eq!(bbox, varied.glyph_bounding_box(id));
+        assert_eq!(
+            face.glyph_hor_advance_at(id, &location),
+            varied.glyph_hor_advance(id)
+        );
+        assert_eq!(
+            face.glyph_hor_side_bearing_at(id, &location),
+            varied.glyph_hor_side_bearing(id)
+        );
+        assert_eq!(
+            face.glyph_phantom_points_at(id, &location)
+                .map(|p| (p.right.x, p.bottom.y)),
+            varied
+                .glyph_phantom_points(id)
+                .map(|p| (p.right.x, p.bottom.y))
+        );
+        assert_eq!(
+            face.glyph_bounding_box_at(id, &default_location),
+            face.glyph_bounding_box(id)
+        );
+        changed |= bbox != face.glyph_bounding_box(id);
+    }
+    assert!(changed);
+
+    assert_eq!(face.ascender_at(&location), varied.ascender());
+    assert_eq!(
+        face.underline_metrics_at(&location),
+        varied.underline_metrics()
+    );
+}
+
+#[cfg(feature = "variable-fonts")]
+#[test]
+fn variation_location_on_static_font() {
+    let face = Face::parse(DEMO_FONT, 0).unwrap();
+    assert_eq!(face.variation_location(&[]), None);
+}
@@ -33,6 +33,33 @@ fn version_1_full() {
     assert_eq!(table.number_of_glyphs, NonZeroU16::new(1).unwrap());
 }
 
+#[test]
+fn version_1_hinting_limits() {
+    let table = Table::parse(&convert(&[
+        Fixed(1.0), // version
+        UInt16(1), // number of glyphs
+        UInt16(10), // maximum points in a non-composite glyph
+        UInt16(2), // maximum contours in a non-composite glyph
+        UInt16(20), // maximum points in a composite glyph
+        UInt16(4), // maximum contours in a composite glyph
+        UInt16(2), // maximum zones
+        UInt16(16), // maximum twilight points
+        UInt16(32), // number of Storage Area locations
+        UInt16(64), // number of FDEFs
+        UInt16(0), // number of IDEFs
+        UInt16(256), // maximum stack depth
+        UInt16(512), // maximum byte count for glyph instructions
+        UInt16(3), // maximum number of components
+        UInt16(1), // maximum levels of recursion
+    ])).unwrap();
+    assert_eq!(table.max_zones, 2);
+    assert_eq!(table.max_twilight_points, 16);
+    assert_eq!(table.max_storage, 32);
+    assert_eq!(table.max_function_defs, 64);
+    assert_eq!(table.max_stack_elements, 256);
+    assert_eq!(table.max_component_depth, 1);
+}
+
 #[test]
 fn version_1_trimmed() {
     // We don't really care about the data after the number of glyphs.
@@ -41,6 +68,7 @@ fn version_1_trimmed() {
         UInt16(1), // number of glyphs
     ])).unwrap();
     assert_eq!(table.number_of_glyphs, NonZeroU16::new(1).unwrap());
+    assert_eq!(table.max_stack_elements, 0);
 }
 
 #[test]
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use ttf_parser::{Face, FaceParsingError, GlyphId, OwnedFace};
+use crate::DEMO_FONT;
+
+#[test]
+fn parse() {
+    let face = OwnedFace::parse(DEMO_FONT.to_vec(), 0).unwrap();
+    assert_eq!(face.index(), 0);
+    assert_eq!(face.data().len(), DEMO_FONT.len());
+    assert_eq!(face.as_face().number_of_glyphs(), 2);
+    assert!(face.as_face().tables().cmap.is_some());
+}
+
+#[test]
+fn parse_malformed() {
+    assert_eq!(
+        OwnedFace::parse(vec![0u8; 4], 0).unwrap_err(),
+        FaceParsingError::UnknownMagic
+    );
+}
+
+#[test]
+fn clone_outlives_original() {
+    let face = OwnedFace::parse(DEMO_FONT.to_vec(), 0).unwrap();
+    let cloned = face.clone();
+    drop(face);
+    assert_eq!(cloned.as_face().number_of_glyphs(), 2);
+    assert!(cloned.as_face().tables().cmap.is_some());
+    assert_eq!(cloned.into_data(), DEMO_FONT);
+}
+
+#[test]
+fn shared_data() {
+    let data: Arc<[u8]> = DEMO_FONT.into();
+    let face = OwnedFace::parse(data.clone(), 0).unwrap();
+    let handle = std::thread::spawn(move || face.as_face().glyph_hor_advance(GlyphId(1)));
+    let advance = handle.join().unwrap();
+    assert_eq!(
+        advance,
+        Face::parse(&data, 0).unwrap().glyph_hor_advance(GlyphId(1))
+    );
+}
+
+#[cfg(feature = "variable-fonts")]
+#[test]
+fn variation_coordinates() {
+    let face = OwnedFace::parse(Arc::<[u8]>::from(crate::VARIABLE_FONT), 0).unwrap();
+    let axis = face.as_face().variation_axes().get(0).unwrap();
+
+    let mut varied = face.clone();
+    varied.set_variation(axis.tag, axis.max_value).unwrap();
+    assert!(face
+        .as_face()
+        .variation_coordinates()
+        .iter()
+        .all(|c| c.get() == 0));
+
+    let mut expected = Face::parse(crate::VARIABLE_FONT, 0).unwrap();
+    expected.set_variation(axis.tag, axis.max_value).unwrap();
+    assert_eq!(
+        varied.variation_coordinates(),
+        expected.variation_coordinates()
+    );
+    assert_eq!(
+        varied.as_face().variation_coordinates(),
+        expected.variation_coordinates()
+    );
+}
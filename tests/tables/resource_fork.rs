@@ -0,0 +1,108 @@
+use ttf_parser::{fonts_in_resource_fork, Face, FaceParsingError, RawFace};
+use crate::{push_u16, push_u32, DEMO_FONT};
+
+// Builds a resource fork with `sfnt` resources and a single unrelated resource.
+fn build_resource_fork(fonts: &[&[u8]]) -> Vec<u8> {
+    let mut resources: Vec<(&[u8; 4], &[u8])> = vec![(b"vers", &[1, 2, 3])];
+    for font in fonts {
+        resources.push((b"sfnt", font));
+    }
+
+    let mut resources_data = Vec::new();
+    let mut offsets = Vec::new();
+    for (_, data) in &resources {
+        offsets.push(resources_data.len() as u32);
+        push_u32(&mut resources_data, data.len() as u32);
+        resources_data.extend_from_slice(data);
+    }
+
+    const HEADER_SIZE: u32 = 256;
+    const MAP_HEADER_SIZE: u16 = 28;
+    let map_offset = HEADER_SIZE + resources_data.len() as u32;
+
+    let mut type_list = Vec::new();
+    push_u16(&mut type_list, 1); // the number of types minus one
+    let references_start = 2 + 8 * 2;
+    // vers
+    type_list.extend_from_slice(b"vers");
+    push_u16(&mut type_list, 0);
+    push_u16(&mut type_list, references_start);
+    // sfnt
+    type_list.extend_from_slice(b"sfnt");
+    push_u16(&mut type_list, fonts.len() as u16 - 1);
+    push_u16(&mut type_list, references_start + 12);
+    for (i, offset) in offsets.iter().enumerate() {
+        push_u16(&mut type_list, 128 + i as u16); // ID
+        push_u16(&mut type_list, 0xFFFF); // no name
+        type_list.extend_from_slice(&offset.to_be_bytes()); // attributes + offset
+        push_u32(&mut type_list, 0); // handle
+    }
+
+    let map_length = u32::from(MAP_HEADER_SIZE) + type_list.len() as u32;
+    let mut header = Vec::new();
+    push_u32(&mut header, HEADER_SIZE);
+    push_u32(&mut header, map_offset);
+    push_u32(&mut header, resources_data.len() as u32);
+    push_u32(&mut header, map_length);
+
+    let mut data = header.clone();
+    data.resize(HEADER_SIZE as usize, 0);
+    data.extend_from_slice(&resources_data);
+    data.extend_from_slice(&header);
+    push_u32(&mut data, 0); // next resource map
+    push_u16(&mut data, 0); // file reference number
+    push_u16(&mut data, 0); // attributes
+    push_u16(&mut data, MAP_HEADER_SIZE); // type list offset
+    push_u16(&mut data, map_length as u16); // name list offset
+    data.extend_from_slice(&type_list);
+    data
+}
+
+#[test]
+fn single_font() {
+    let data = build_resource_fork(&[DEMO_FONT]);
+    assert_eq!(fonts_in_resource_fork(&data), Some(1));
+
+    let face = RawFace::parse_resource_fork(&data, 0).unwrap();
+    assert_eq!(face.data, DEMO_FONT);
+
+    let face = Face::parse_resource_fork(&data, 0).unwrap();
+    assert_eq!(face.number_of_glyphs(), 2);
+}
+
+#[test]
+fn multiple_fonts() {
+    let mut second = DEMO_FONT.to_vec();
+    second.extend_from_slice(&[0; 4]);
+    let data = build_resource_fork(&[DEMO_FONT, &second]);
+    assert_eq!(fonts_in_resource_fork(&data), Some(2));
+    assert_eq!(
+        RawFace::parse_resource_fork(&data, 1).unwrap().data,
+        &second[..]
+    );
+    assert_eq!(
+        RawFace::parse_resource_fork(&data, 2).unwrap_err(),
+        FaceParsingError::FaceIndexOutOfBounds
+    );
+}
+
+#[test]
+fn not_a_resource_fork() {
+    assert_eq!(fonts_in_resource_fork(DEMO_FONT), None);
+    assert_eq!(
+        Face::parse_resource_fork(DEMO_FONT, 0).unwrap_err(),
+        FaceParsingError::MalformedFont
+    );
+}
+
+#[test]
+fn truncated_resource() {
+    let mut data = build_resource_fork(&[DEMO_FONT]);
+    // Make the resource length larger than the data section.
+    let offset = 256 + 4 + 3; // after the `vers` resource
+    data[offset..offset + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert_eq!(
+        RawFace::parse_resource_fork(&data, 0).unwrap_err(),
+        FaceParsingError::MalformedFont
+    );
+}
@@ -0,0 +1,765 @@
+use ttf_parser::shaping::{self, Buffer, Feature, Positioner, Substitutor};
+use ttf_parser::{Face, GlyphId, RawFace, Tag};
+use crate::{convert, Unit::*};
+
+const IGNORE_MARKS: u16 = 0x0008;
+const USE_MARK_FILTERING_SET: u16 = 0x0010;
+
+// A GSUB or GPOS table with empty script and feature lists.
+fn layout_table(lookups: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt16(10), // script list offset
+        UInt16(12), // feature list offset
+        UInt16(14), // lookup list offset
+        UInt16(0), // script list: count
+        UInt16(0), // feature list: count
+        UInt16(lookups.len() as u16), // lookup list: count
+    ]);
+
+    let mut offset = 2 + 2 * lookups.len();
+    for lookup in lookups {
+        data.extend_from_slice(&convert(&[UInt16(offset as u16)]));
+        offset += lookup.len();
+    }
+
+    for lookup in lookups {
+        data.extend_from_slice(lookup);
+    }
+
+    data
+}
+
+// A lookup with a single subtable. Uses the first mark filtering set when requested.
+fn lookup(kind: u16, flags: u16, subtable: &[u8]) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(kind), // type
+        UInt16(flags), // flags
+        UInt16(1), // subtables count
+    ]);
+
+    if flags & USE_MARK_FILTERING_SET != 0 {
+        data.extend_from_slice(&convert(&[UInt16(10), UInt16(0)]));
+    } else {
+        data.extend_from_slice(&convert(&[UInt16(8)]));
+    }
+
+    data.extend_from_slice(subtable);
+    data
+}
+
+fn coverage(glyphs: &[u16]) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(glyphs.len() as u16), // count
+    ]);
+    for glyph in glyphs {
+        data.extend_from_slice(&convert(&[UInt16(*glyph)]));
+    }
+    data
+}
+
+fn single(from: &[u16], to: &[u16]) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(2), // format
+        UInt16(6 + 2 * to.len() as u16), // coverage offset
+        UInt16(to.len() as u16), // count
+    ]);
+    for glyph in to {
+        data.extend_from_slice(&convert(&[UInt16(*glyph)]));
+    }
+    data.extend_from_slice(&coverage(from));
+    data
+}
+
+// Multiple and Alternate substitution subtables have the same layout.
+fn sequence(glyph: u16, glyphs: &[u16]) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(10 + 2 * glyphs.len() as u16), // coverage offset
+        UInt16(1), // count
+        UInt16(8), // sequence offset [0]
+        UInt16(glyphs.len() as u16), // glyphs count
+    ]);
+    for glyph in glyphs {
+        data.extend_from_slice(&convert(&[UInt16(*glyph)]));
+    }
+    data.extend_from_slice(&coverage(&[glyph]));
+    data
+}
+
+fn ligature(components: &[u16], glyph: u16) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(14 + 2 * components.len() as u16), // coverage offset
+        UInt16(1), // ligature sets count
+        UInt16(8), // ligature set offset [0]
+        UInt16(1), // ligatures count
+        UInt16(4), // ligature offset [0]
+        UInt16(glyph), // ligature glyph
+        UInt16(components.len() as u16), // components count
+    ]);
+    for component in &components[1..] {
+        data.extend_from_slice(&convert(&[UInt16(*component)]));
+    }
+    data.extend_from_slice(&coverage(&components[..1]));
+    data
+}
+
+// A format 3 subtable, where each coverage has a single glyph.
+fn chained_context(
+    backtrack: &[u16],
+    input: &[u16],
+    lookahead: &[u16],
+    records: &[(u16, u16)],
+) -> Vec<u8> {
+    let header_len = 10 + 2 * (backtrack.len() + input.len() + lookahead.len()) + 4 * records.len();
+    let mut data = convert(&[UInt16(3)]); // format
+    let mut coverages = Vec::new();
+    for glyphs in &[backtrack, input, lookahead] {
+        data.extend_from_slice(&convert(&[UInt16(glyphs.len() as u16)]));
+        for glyph in glyphs.iter() {
+            let offset = header_len + coverages.len();
+            data.extend_from_slice(&convert(&[UInt16(offset as u16)]));
+            coverages.extend_from_slice(&coverage(&[*glyph]));
+        }
+    }
+
+    data.extend_from_slice(&convert(&[UInt16(records.len() as u16)]));
+    for (sequence_index, lookup_index) in records {
+        data.extend_from_slice(&convert(&[UInt16(*sequence_index), UInt16(*lookup_index)]));
+    }
+
+    data.extend_from_slice(&coverages);
+    data
+}
+
+fn reverse_chain_single(glyph: u16, lookahead: u16, substitute: u16) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(14), // coverage offset
+        UInt16(0), // backtrack count
+        UInt16(1), // lookahead count
+        UInt16(20), // lookahead coverage offset [0]
+        UInt16(1), // glyphs count
+        UInt16(substitute), // substitute [0]
+    ]);
+    data.extend_from_slice(&coverage(&[glyph]));
+    data.extend_from_slice(&coverage(&[lookahead]));
+    data
+}
+
+fn extension(kind: u16, subtable: &[u8]) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(kind), // extension lookup type
+        UInt32(8), // extension offset
+    ]);
+    data.extend_from_slice(subtable);
+    data
+}
+
+fn anchor(x: i16, y: i16) -> Vec<u8> {
+    convert(&[
+        UInt16(1), // format
+        Int16(x), // x
+        Int16(y), // y
+    ])
+}
+
+fn single_pos(glyph: u16, x_placement: i16, x_advance: i16) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(10), // coverage offset
+        UInt16(0x0005), // value format: X_PLACEMENT | X_ADVANCE
+        Int16(x_placement), // x placement
+        Int16(x_advance), // x advance
+    ]);
+    data.extend_from_slice(&coverage(&[glyph]));
+    data
+}
+
+fn pair_pos(first: u16, second: u16, x_advance: i16) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(18), // coverage offset
+        UInt16(0x0004), // value format 1: X_ADVANCE
+        UInt16(0), // value format 2
+        UInt16(1), // pair sets count
+        UInt16(12), // pair set offset [0]
+        UInt16(1), // pair value records count
+        UInt16(second), // second glyph
+        Int16(x_advance), // x advance
+    ]);
+    data.extend_from_slice(&coverage(&[first]));
+    data
+}
+
+fn pair_pos_with_second_value(first: u16, second: u16, x_advance: (i16, i16)) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(20), // coverage offset
+        UInt16(0x0004), // value format 1: X_ADVANCE
+        UInt16(0x0004), // value format 2: X_ADVANCE
+        UInt16(1), // pair sets count
+        UInt16(12), // pair set offset [0]
+        UInt16(1), // pair value records count
+        UInt16(second), // second glyph
+        Int16(x_advance.0), // x advance [0]
+        Int16(x_advance.1), // x advance [1]
+    ]);
+    data.extend_from_slice(&coverage(&[first]));
+    data
+}
+
+fn cursive_pos(glyph: u16, entry: (i16, i16), exit: (i16, i16)) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(22), // coverage offset
+        UInt16(1), // entry/exit records count
+        UInt16(10), // entry anchor offset [0]
+        UInt16(16), // exit anchor offset [0]
+    ]);
+    data.extend_from_slice(&anchor(entry.0, entry.1));
+    data.extend_from_slice(&anchor(exit.0, exit.1));
+    data.extend_from_slice(&coverage(&[glyph]));
+    data
+}
+
+// Mark-to-base and mark-to-mark subtables have the same layout.
+// A single mark class is used.
+fn mark_pos(mark: u16, mark_anchor: (i16, i16), base: u16, base_anchor: (i16, i16)) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(34), // mark coverage offset
+        UInt16(40), // base coverage offset
+        UInt16(1), // mark classes count
+        UInt16(12), // mark array offset
+        UInt16(24), // base array offset
+
+        // MarkArray
+        UInt16(1), // count
+        UInt16(0), // mark class [0]
+        UInt16(6), // mark anchor offset [0]
+    ]);
+    data.extend_from_slice(&anchor(mark_anchor.0, mark_anchor.1));
+    data.extend_from_slice(&convert(&[
+        // BaseArray
+        UInt16(1), // count
+        UInt16(4), // base anchor offset [0]
+    ]));
+    data.extend_from_slice(&anchor(base_anchor.0, base_anchor.1));
+    data.extend_from_slice(&coverage(&[mark]));
+    data.extend_from_slice(&coverage(&[base]));
+    data
+}
+
+fn mark_to_ligature_pos(
+    mark: u16,
+    mark_anchor: (i16, i16),
+    ligature: u16,
+    component_anchors: &[(i16, i16)],
+) -> Vec<u8> {
+    let count = component_anchors.len() as u16;
+    let coverage_offset = 30 + 8 * count;
+    let mut data = convert(&[
+        UInt16(1), // format
+        UInt16(coverage_offset), // mark coverage offset
+        UInt16(coverage_offset + 6), // ligature coverage offset
+        UInt16(1), // mark classes count
+        UInt16(12), // mark array offset
+        UInt16(24), // ligature array offset
+
+        // MarkArray
+        UInt16(1), // count
+        UInt16(0), // mark class [0]
+        UInt16(6), // mark anchor offset [0]
+    ]);
+    data.extend_from_slice(&anchor(mark_anchor.0, mark_anchor.1));
+    data.extend_from_slice(&convert(&[
+        // LigatureArray
+        UInt16(1), // count
+        UInt16(4), // ligature attach offset [0]
+
+        // LigatureAttach
+        UInt16(count), // components count
+    ]));
+    for i in 0..count {
+        data.extend_from_slice(&convert(&[UInt16(2 + 2 * count + 6 * i)]));
+    }
+    for (x, y) in component_anchors {
+        data.extend_from_slice(&anchor(*x, *y));
+    }
+    data.extend_from_slice(&coverage(&[mark]));
+    data.extend_from_slice(&coverage(&[ligature]));
+    data
+}
+
+fn class_definition(classes: &[(u16, u16)]) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt16(2), // format
+        UInt16(classes.len() as u16), // ranges count
+    ]);
+    for (glyph, class) in classes {
+        data.extend_from_slice(&convert(&[UInt16(*glyph), UInt16(*glyph), UInt16(*class)]));
+    }
+    data
+}
+
+// Glyphs 1 and 10..=13 are base glyphs, 40 is a ligature and 50..=51 are marks.
+// Mark 50 is in the first mark glyph set and has mark attachment class 1, mark 51 has class 2.
+fn gdef() -> Vec<u8> {
+    let classes = class_definition(&[
+        (1, 1), (10, 1), (11, 1), (12, 1), (13, 1), (40, 2), (50, 3), (51, 3),
+    ]);
+    let mark_classes = class_definition(&[(50, 1), (51, 2)]);
+    let mut data = convert(&[
+        UInt32(0x00010002), // version
+        UInt16(14), // glyph class definition offset
+        UInt16(0), // attachment point list offset
+        UInt16(0), // ligature caret list offset
+        UInt16(14 + classes.len() as u16), // mark attachment class definition offset
+        UInt16(14 + (classes.len() + mark_classes.len()) as u16), // mark glyph sets offset
+    ]);
+    data.extend_from_slice(&classes);
+    data.extend_from_slice(&mark_classes);
+    data.extend_from_slice(&convert(&[
+        UInt16(1), // format
+        UInt16(1), // mark glyph sets count
+        UInt32(8), // coverage offset [0]
+    ]));
+    data.extend_from_slice(&coverage(&[50]));
+    data
+}
+
+fn buffer(glyphs: &[u16]) -> Buffer {
+    let mut buffer = Buffer::new();
+    for (i, glyph) in glyphs.iter().enumerate() {
+        buffer.push(GlyphId(*glyph), i as u32);
+    }
+    buffer
+}
+
+fn glyphs(buffer: &Buffer) -> Vec<u16> {
+    buffer.glyphs().iter().map(|info| info.glyph_id.0).collect()
+}
+
+fn clusters(buffer: &Buffer) -> Vec<u32> {
+    buffer.glyphs().iter().map(|info| info.cluster).collect()
+}
+
+// Maps `A` to 1, `B` to 2, `f` to 10, `i` to 11, `x` to 12 and U+0301 to 50.
+fn cmap() -> Vec<u8> {
+    let groups = [(0x41, 0x42, 1), (0x66, 0x66, 10), (0x69, 0x69, 11), (0x78, 0x78, 12), (0x301, 0x301, 50)];
+    let mut data = convert(&[
+        UInt16(0), // version
+        UInt16(1), // number of tables
+        UInt16(3), // platform ID
+        UInt16(10), // encoding ID
+        UInt32(12), // offset
+
+        // Format 12
+        UInt16(12), // format
+        UInt16(0), // reserved
+        UInt32(16 + 12 * groups.len() as u32), // length
+        UInt32(0), // language
+        UInt32(groups.len() as u32), // number of groups
+    ]);
+    for (start, end, glyph) in groups.iter() {
+        data.extend_from_slice(&convert(&[UInt32(*start), UInt32(*end), UInt32(*glyph)]));
+    }
+    data
+}
+
+// Uses advances from the demo font, which has only two glyphs.
+fn build_font(gsub: &[u8], gpos: &[u8]) -> Vec<u8> {
+    let (cmap, gdef, kern) = (cmap(), gdef(), crate::kern::kern());
+    let demo = RawFace::parse(crate::DEMO_FONT, 0).unwrap();
+    crate::build_demo_font(&[
+        (b"GDEF", &gdef),
+        (b"GPOS", gpos),
+        (b"GSUB", gsub),
+        (b"cmap", &cmap),
+        (b"hmtx", demo.table(Tag::from_bytes(b"hmtx")).unwrap()),
+        (b"kern", &kern),
+    ])
+}
+
+#[test]
+fn substitutions() {
+    let data = build_font(&layout_table(&[
+        lookup(1, 0, &single(&[10], &[20])),
+        lookup(2, 0, &sequence(11, &[21, 22])),
+        lookup(7, 0, &extension(4, &ligature(&[22, 12], 31))),
+        lookup(2, 0, &sequence(20, &[])),
+    ]), &layout_table(&[]));
+    let face = Face::parse(&data, 0).unwrap();
+    let substitutor = Substitutor::from_face(&face).unwrap();
+    let mut buffer = buffer(&[10, 11, 12]);
+
+    substitutor.apply_lookup(0, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![20, 11, 12]);
+
+    substitutor.apply_lookup(1, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![20, 21, 22, 12]);
+    assert_eq!(clusters(&buffer), vec![0, 1, 1, 2]);
+    assert_eq!(buffer.glyphs()[1].ligature_component(), 1);
+    assert_eq!(buffer.glyphs()[2].ligature_component(), 2);
+
+    substitutor.apply_lookup(2, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![20, 21, 31]);
+    assert_eq!(clusters(&buffer), vec![0, 1, 1]);
+    assert_ne!(buffer.glyphs()[2].ligature_id(), 0);
+    assert_eq!(buffer.glyphs()[2].ligature_component(), 0);
+
+    // An empty sequence removes a glyph.
+    substitutor.apply_lookup(3, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![21, 31]);
+
+    // Nonexistent lookups are ignored.
+    substitutor.apply(vec![4, 0], &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![21, 31]);
+}
+
+#[test]
+fn alternates() {
+    let data = build_font(
+        &layout_table(&[lookup(3, 0, &sequence(10, &[20, 21]))]),
+        &layout_table(&[]),
+    );
+    let face = Face::parse(&data, 0).unwrap();
+    let mut substitutor = Substitutor::from_face(&face).unwrap();
+
+    let mut buffer = buffer(&[10]);
+    substitutor.apply_lookup(0, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![20]);
+
+    substitutor.set_alternate_index(1);
+    let mut buffer = self::buffer(&[10]);
+    substitutor.apply_lookup(0, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![21]);
+
+    substitutor.set_alternate_index(2);
+    let mut buffer = self::buffer(&[10]);
+    substitutor.apply_lookup(0, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![10]);
+}
+
+#[test]
+fn ligatures_with_marks() {
+    let data = build_font(&layout_table(&[
+        lookup(4, IGNORE_MARKS, &ligature(&[10, 11, 12], 40)),
+        lookup(4, 0, &ligature(&[10, 11, 12], 40)),
+        lookup(4, USE_MARK_FILTERING_SET, &ligature(&[10, 11], 41)),
+        lookup(4, 0x0100, &ligature(&[10, 11], 42)), // mark attachment type 1
+    ]), &layout_table(&[]));
+    let face = Face::parse(&data, 0).unwrap();
+    let substitutor = Substitutor::from_face(&face).unwrap();
+
+    // Marks are preserved and attached to the preceding components.
+    let mut buffer = buffer(&[10, 50, 11, 51, 12, 13]);
+    substitutor.apply_lookup(0, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![40, 50, 51, 13]);
+    assert_eq!(clusters(&buffer), vec![0, 0, 0, 5]);
+    let ligature_id = buffer.glyphs()[0].ligature_id();
+    assert_ne!(ligature_id, 0);
+    let components: Vec<_> = buffer.glyphs().iter()
+        .map(|info| (info.ligature_id(), info.ligature_component()))
+        .collect();
+    assert_eq!(components, vec![(ligature_id, 0), (ligature_id, 1), (ligature_id, 2), (0, 0)]);
+
+    // Marks are not ignored.
+    let mut buffer = self::buffer(&[10, 50, 11, 51, 12]);
+    substitutor.apply_lookup(1, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![10, 50, 11, 51, 12]);
+
+    // Marks outside of the mark filtering set are ignored.
+    let mut buffer = self::buffer(&[10, 51, 11, 10, 50, 11]);
+    substitutor.apply_lookup(2, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![41, 51, 10, 50, 11]);
+
+    // Marks of other mark attachment classes are ignored.
+    let mut buffer = self::buffer(&[10, 51, 11, 10, 50, 11]);
+    substitutor.apply_lookup(3, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![42, 51, 10, 50, 11]);
+}
+
+#[test]
+fn chained_contexts() {
+    let data = build_font(&layout_table(&[
+        lookup(6, 0, &chained_context(&[9], &[10, 11], &[12], &[(0, 1), (1, 2)])),
+        lookup(2, 0, &sequence(10, &[20, 21])),
+        lookup(1, 0, &single(&[21], &[23])),
+        lookup(6, 0, &chained_context(&[], &[10, 11, 13], &[], &[(0, 4), (1, 5)])),
+        lookup(4, 0, &ligature(&[10, 11], 30)),
+        lookup(1, 0, &single(&[13], &[33])),
+        lookup(6, 0, &chained_context(&[], &[10], &[], &[(0, 6)])),
+    ]), &layout_table(&[]));
+    let face = Face::parse(&data, 0).unwrap();
+    let substitutor = Substitutor::from_face(&face).unwrap();
+
+    // Input positions are adjusted after a multiple substitution.
+    let mut buffer = buffer(&[9, 10, 11, 12]);
+    substitutor.apply_lookup(0, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![9, 20, 23, 11, 12]);
+    assert_eq!(clusters(&buffer), vec![0, 1, 1, 2, 3]);
+
+    // Backtrack doesn't match.
+    let mut buffer = self::buffer(&[8, 10, 11, 12]);
+    substitutor.apply_lookup(0, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![8, 10, 11, 12]);
+
+    // Input positions are adjusted after a ligature substitution.
+    let mut buffer = self::buffer(&[10, 11, 13]);
+    substitutor.apply_lookup(3, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![30, 33]);
+    assert_eq!(clusters(&buffer), vec![0, 2]);
+
+    // Recursive lookups are limited.
+    let mut buffer = self::buffer(&[10]);
+    substitutor.apply_lookup(6, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![10]);
+}
+
+#[test]
+fn reverse_chaining() {
+    let data = build_font(
+        &layout_table(&[lookup(8, 0, &reverse_chain_single(10, 10, 20))]),
+        &layout_table(&[]),
+    );
+    let face = Face::parse(&data, 0).unwrap();
+    let substitutor = Substitutor::from_face(&face).unwrap();
+
+    // Glyphs are processed from the end, therefore the first glyph has 20 as a lookahead.
+    let mut buffer = buffer(&[10, 10, 10]);
+    substitutor.apply_lookup(0, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![10, 20, 10]);
+}
+
+fn positions(buffer: &Buffer) -> Vec<(f32, f32, f32)> {
+    buffer.positions().iter().map(|pos| (pos.x_advance, pos.x_offset, pos.y_offset)).collect()
+}
+
+#[test]
+fn single_and_pair_adjustments() {
+    let data = build_font(&layout_table(&[]), &layout_table(&[
+        lookup(1, 0, &single_pos(1, 10, 20)),
+        lookup(2, 0, &pair_pos(1, 1, -50)),
+        lookup(2, IGNORE_MARKS, &pair_pos(1, 1, -50)),
+    ]));
+    let face = Face::parse(&data, 0).unwrap();
+    let advance = f32::from(face.glyph_hor_advance(GlyphId(1)).unwrap());
+    let positioner = Positioner::from_face(&face).unwrap();
+
+    let mut buffer = buffer(&[1, 1]);
+    buffer.reset_positions(&face);
+    positioner.apply(vec![0, 1], &mut buffer);
+    assert_eq!(positions(&buffer), vec![
+        (advance - 30.0, 10.0, 0.0),
+        (advance + 20.0, 10.0, 0.0),
+    ]);
+
+    // Marks are ignored.
+    let mut buffer = self::buffer(&[1, 50, 1]);
+    buffer.reset_positions(&face);
+    positioner.apply(vec![1, 2], &mut buffer);
+    assert_eq!(positions(&buffer), vec![
+        (advance - 50.0, 0.0, 0.0),
+        (0.0, 0.0, 0.0),
+        (advance, 0.0, 0.0),
+    ]);
+}
+
+#[test]
+fn pair_adjustment_with_second_value() {
+    let data = build_font(
+        &layout_table(&[]),
+        &layout_table(&[lookup(2, 0, &pair_pos_with_second_value(1, 1, (-50, 0)))]),
+    );
+    let face = Face::parse(&data, 0).unwrap();
+    let advance = f32::from(face.glyph_hor_advance(GlyphId(1)).unwrap());
+    let positioner = Positioner::from_face(&face).unwrap();
+
+    // The second glyph has a value record, even a zero one, so it cannot start a new pair.
+    let mut buffer = buffer(&[1, 1, 1, 1]);
+    buffer.reset_positions(&face);
+    positioner.apply_lookup(0, &mut buffer);
+    assert_eq!(positions(&buffer), vec![
+        (advance - 50.0, 0.0, 0.0),
+        (advance, 0.0, 0.0),
+        (advance - 50.0, 0.0, 0.0),
+        (advance, 0.0, 0.0),
+    ]);
+}
+
+#[test]
+fn cursive_attachment() {
+    let data = build_font(
+        &layout_table(&[]),
+        &layout_table(&[lookup(3, 0, &cursive_pos(1, (10, 100), (500, 300)))]),
+    );
+    let face = Face::parse(&data, 0).unwrap();
+    let advance = f32::from(face.glyph_hor_advance(GlyphId(1)).unwrap());
+    let positioner = Positioner::from_face(&face).unwrap();
+
+    // Each glyph's entry is aligned with the previous glyph's exit.
+    let mut buffer = buffer(&[1, 1, 1]);
+    buffer.reset_positions(&face);
+    positioner.apply_lookup(0, &mut buffer);
+    assert_eq!(positions(&buffer), vec![
+        (500.0, 0.0, 0.0),
+        (490.0, -10.0, 200.0),
+        (advance - 10.0, -10.0, 400.0),
+    ]);
+}
+
+#[test]
+fn mark_attachments() {
+    let data = build_font(&layout_table(&[
+        lookup(4, IGNORE_MARKS, &ligature(&[10, 11], 40)),
+    ]), &layout_table(&[
+        lookup(4, 0, &mark_pos(50, (10, 20), 1, (300, 500))),
+        lookup(6, 0, &mark_pos(51, (0, 0), 50, (5, 50))),
+        lookup(5, 0, &mark_to_ligature_pos(50, (10, 20), 40, &[(100, 600), (400, 600)])),
+    ]));
+    let face = Face::parse(&data, 0).unwrap();
+    let advance = f32::from(face.glyph_hor_advance(GlyphId(1)).unwrap());
+    let positioner = Positioner::from_face(&face).unwrap();
+
+    // Offsets are relative to the current pen position, therefore the base advance is subtracted.
+    let mut buffer = buffer(&[1, 50, 51]);
+    buffer.reset_positions(&face);
+    positioner.apply(vec![0, 1], &mut buffer);
+    assert_eq!(positions(&buffer), vec![
+        (advance, 0.0, 0.0),
+        (0.0, 290.0 - advance, 480.0),
+        (0.0, 295.0 - advance, 530.0),
+    ]);
+
+    // A mark between ligature components is attached to the first component
+    // and a mark after the ligature to the last one.
+    let mut buffer = self::buffer(&[10, 50, 11, 50]);
+    Substitutor::from_face(&face).unwrap().apply_lookup(0, &mut buffer);
+    assert_eq!(glyphs(&buffer), vec![40, 50, 50]);
+    buffer.reset_positions(&face);
+    positioner.apply_lookup(2, &mut buffer);
+    assert_eq!(positions(&buffer), vec![
+        (0.0, 0.0, 0.0),
+        (0.0, 90.0, 580.0),
+        (0.0, 390.0, 580.0),
+    ]);
+}
+
+#[test]
+fn contextual_positioning() {
+    let data = build_font(&layout_table(&[]), &layout_table(&[
+        lookup(8, 0, &chained_context(&[], &[1, 1], &[], &[(1, 1)])),
+        lookup(1, 0, &single_pos(1, 10, 20)),
+    ]));
+    let face = Face::parse(&data, 0).unwrap();
+    let advance = f32::from(face.glyph_hor_advance(GlyphId(1)).unwrap());
+    let positioner = Positioner::from_face(&face).unwrap();
+
+    // Only the second glyph of each pair is adjusted.
+    let mut buffer = buffer(&[1, 1, 1]);
+    buffer.reset_positions(&face);
+    positioner.apply_lookup(0, &mut buffer);
+    assert_eq!(positions(&buffer), vec![
+        (advance, 0.0, 0.0),
+        (advance + 20.0, 10.0, 0.0),
+        (advance, 0.0, 0.0),
+    ]);
+}
+
+// A layout table with the `DFLT` script, which default language system uses all features.
+fn featured_layout_table(features: &[(&[u8; 4], &[u16])], lookups: &[Vec<u8>]) -> Vec<u8> {
+    let scripts_len = 18 + 2 * features.len();
+    let features_len: usize = features.iter().map(|(_, v)| 10 + 2 * v.len()).sum::<usize>() + 2;
+    let mut data = convert(&[
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt16(10), // script list offset
+        UInt16(10 + scripts_len as u16), // feature list offset
+        UInt16((10 + scripts_len + features_len) as u16), // lookup list offset
+
+        // ScriptList
+        UInt16(1), // count
+        Raw(b"DFLT"), // script tag [0]
+        UInt16(8), // script offset [0]
+
+        // Script
+        UInt16(4), // default language system offset
+        UInt16(0), // language system count
+
+        // LangSys
+        UInt16(0), // lookup order offset
+        UInt16(0xFFFF), // required feature index
+        UInt16(features.len() as u16), // feature index count
+    ]);
+    for i in 0..features.len() {
+        data.extend_from_slice(&convert(&[UInt16(i as u16)]));
+    }
+
+    data.extend_from_slice(&convert(&[UInt16(features.len() as u16)]));
+    let mut offset = 2 + 6 * features.len();
+    for (tag, indices) in features {
+        data.extend_from_slice(*tag);
+        data.extend_from_slice(&convert(&[UInt16(offset as u16)]));
+        offset += 4 + 2 * indices.len();
+    }
+
+    for (_, indices) in features {
+        data.extend_from_slice(&convert(&[
+            UInt16(0), // feature params offset
+            UInt16(indices.len() as u16), // lookup index count
+        ]));
+        for index in indices.iter() {
+            data.extend_from_slice(&convert(&[UInt16(*index)]));
+        }
+    }
+
+    // Reuse the lookup list of a table without features.
+    data.extend_from_slice(&layout_table(lookups)[14..]);
+    data
+}
+
+#[test]
+fn shape() {
+    let gsub = featured_layout_table(&[(b"liga", &[0]), (b"smcp", &[1])], &[
+        lookup(4, 0, &ligature(&[10, 11], 40)),
+        lookup(1, 0, &single(&[12], &[13])),
+    ]);
+    let gpos = featured_layout_table(&[(b"kern", &[0]), (b"mark", &[1])], &[
+        lookup(2, 0, &pair_pos(1, 1, -50)),
+        lookup(4, 0, &mark_pos(50, (10, 20), 1, (300, 500))),
+    ]);
+    let data = build_font(&gsub, &gpos);
+    let face = Face::parse(&data, 0).unwrap();
+    let advance = |glyph| f32::from(face.glyph_hor_advance(GlyphId(glyph)).unwrap_or(0));
+    let (script, smcp) = (Tag::from_bytes(b"latn"), Tag::from_bytes(b"smcp"));
+
+    let buffer = shaping::shape(&face, "fixAA\u{301}", script, None, &[Feature::new(smcp, 1)]);
+    assert_eq!(glyphs(&buffer), vec![40, 13, 1, 1, 50]);
+    assert_eq!(clusters(&buffer), vec![0, 2, 3, 4, 5]);
+    assert_eq!(positions(&buffer), vec![
+        (advance(40), 0.0, 0.0),
+        (advance(13), 0.0, 0.0),
+        (advance(1) - 50.0, 0.0, 0.0),
+        (advance(1), 0.0, 0.0),
+        (0.0, 290.0 - advance(1), 480.0),
+    ]);
+
+    // Default features can be disabled.
+    let features = [Feature::new(Tag::from_bytes(b"liga"), 0), Feature::new(Tag::from_bytes(b"kern"), 0)];
+    let buffer = shaping::shape(&face, "fixAA", script, None, &features);
+    assert_eq!(glyphs(&buffer), vec![10, 11, 12, 1, 1]);
+    assert_eq!(positions(&buffer)[3], (advance(1), 0.0, 0.0));
+
+    // Without the `GPOS` `kern` feature, the `kern` table is used.
+    let gpos = featured_layout_table(&[], &[]);
+    let data = build_font(&gsub, &gpos);
+    let face = Face::parse(&data, 0).unwrap();
+    let buffer = shaping::shape(&face, "AB", script, None, &[]);
+    assert_eq!(positions(&buffer)[0], (advance(1) - 55.0, 0.0, 0.0));
+}
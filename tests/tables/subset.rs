@@ -0,0 +1,132 @@
+use ttf_parser::subset::{SubsetError, Subsetter};
+use ttf_parser::writer::FontBuilder;
+use ttf_parser::{Face, GlyphId, RawFace, Tag};
+use crate::{outline, DEMO_FONT};
+
+// Returns `demo.ttf` with an `OS/2` table, since subsetting is not allowed without it.
+fn font_data(fs_type: u16, gsub: Option<&[u8]>) -> Vec<u8> {
+    let mut os2 = vec![0; 78]; // version 0
+    os2[8..10].copy_from_slice(&fs_type.to_be_bytes());
+
+    let raw_face = RawFace::parse(DEMO_FONT, 0).unwrap();
+    let mut builder = FontBuilder::from_raw_face(&raw_face);
+    builder.set_table(Tag::from_bytes(b"OS/2"), os2);
+    if let Some(gsub) = gsub {
+        builder.set_table(Tag::from_bytes(b"GSUB"), gsub);
+    }
+
+    builder.build().unwrap()
+}
+
+#[test]
+fn not_allowed() {
+    // No OS/2 table.
+    let face = Face::parse(DEMO_FONT, 0).unwrap();
+    assert_eq!(Subsetter::new(&face).unwrap_err(), SubsetError::NotAllowed);
+
+    // fsType has the 'No subsetting' bit set, which is respected since version 2.
+    let mut data = font_data(0, None);
+    let raw_face = RawFace::parse(&data, 0).unwrap();
+    let record = raw_face
+        .table_records
+        .into_iter()
+        .find(|record| record.tag == Tag::from_bytes(b"OS/2"))
+        .unwrap();
+    let offset = record.offset as usize;
+    data[offset..offset + 2].copy_from_slice(&2u16.to_be_bytes());
+    data[offset + 8..offset + 10].copy_from_slice(&0x0100u16.to_be_bytes());
+    let face = Face::parse(&data, 0).unwrap();
+    assert_eq!(Subsetter::new(&face).unwrap_err(), SubsetError::NotAllowed);
+}
+
+#[test]
+fn notdef_only() {
+    let data = font_data(0, None);
+    let face = Face::parse(&data, 0).unwrap();
+    let subset = Subsetter::new(&face).unwrap().build().unwrap();
+    assert_eq!(subset.number_of_glyphs(), 1);
+    assert_eq!(subset.new_glyph_id(GlyphId(1)), None);
+
+    let subset_face = Face::parse(subset.data(), 0).unwrap();
+    assert_eq!(subset_face.number_of_glyphs(), 1);
+    assert_eq!(subset_face.glyph_index('A'), None);
+    assert_eq!(
+        outline(&face, GlyphId(0)),
+        outline(&subset_face, GlyphId(0))
+    );
+}
+
+#[test]
+fn codepoints() {
+    let data = font_data(0, None);
+    let face = Face::parse(&data, 0).unwrap();
+    let mut subsetter = Subsetter::new(&face).unwrap();
+    subsetter.add_codepoint('A');
+    subsetter.add_codepoint('B'); // not in the font
+    subsetter.add_glyph(GlyphId(100)); // not in the font
+    let subset = subsetter.build().unwrap();
+    assert_eq!(subset.number_of_glyphs(), 2);
+    assert_eq!(subset.new_glyph_id(GlyphId(1)), Some(GlyphId(1)));
+    assert_eq!(subset.old_glyph_id(GlyphId(1)), Some(GlyphId(1)));
+    assert_eq!(subset.old_glyph_id(GlyphId(2)), None);
+
+    let subset_face = Face::parse(subset.data(), 0).unwrap();
+    assert_eq!(subset_face.glyph_index('A'), Some(GlyphId(1)));
+    assert_eq!(subset_face.glyph_index('B'), None);
+    assert!(subset_face.is_subsetting_allowed());
+    assert_eq!(
+        face.glyph_hor_advance(GlyphId(1)),
+        subset_face.glyph_hor_advance(GlyphId(1))
+    );
+    assert_eq!(
+        outline(&face, GlyphId(1)),
+        outline(&subset_face, GlyphId(1))
+    );
+
+    let os2 = subset_face.tables().os2.unwrap();
+    assert!(os2.is_subsetting_allowed());
+}
+
+#[test]
+fn gsub_closure() {
+    // A single substitution from .notdef to A.
+    let gsub = &[
+        0x00, 0x01, 0x00, 0x00, // version 1.0
+        0x00, 0x0A, // script list offset
+        0x00, 0x0C, // feature list offset
+        0x00, 0x0E, // lookup list offset
+        0x00, 0x00, // script list: count
+        0x00, 0x00, // feature list: count
+        0x00, 0x01, // lookup list: count
+        0x00, 0x04, // lookup offset
+        0x00, 0x01, // lookup: type
+        0x00, 0x00, // lookup: flags
+        0x00, 0x01, // lookup: subtables count
+        0x00, 0x08, // subtable offset
+        0x00, 0x02, // subtable: format
+        0x00, 0x08, // subtable: coverage offset
+        0x00, 0x01, // subtable: count
+        0x00, 0x01, // subtable: substitute
+        0x00, 0x01, // coverage: format
+        0x00, 0x01, // coverage: count
+        0x00, 0x00, // coverage: glyph
+    ];
+
+    let data = font_data(0, Some(gsub));
+    let face = Face::parse(&data, 0).unwrap();
+    let subset = Subsetter::new(&face).unwrap().build().unwrap();
+    assert_eq!(subset.number_of_glyphs(), 2);
+
+    let subset_face = Face::parse(subset.data(), 0).unwrap();
+    // No codepoints were requested.
+    assert_eq!(subset_face.glyph_index('A'), None);
+
+    let gsub = subset_face.tables().gsub.unwrap();
+    assert_eq!(gsub.lookups.len(), 1);
+    let lookup = gsub.lookups.get(0).unwrap();
+    let subtable = lookup
+        .subtables
+        .get::<ttf_parser::gsub::SubstitutionSubtable>(0)
+        .unwrap();
+    assert_eq!(subtable.coverage().get(GlyphId(0)), Some(0));
+}
@@ -0,0 +1,180 @@
+use ttf_parser::validate::{validate, Diagnostic, Severity};
+use ttf_parser::{RawFace, Tag};
+
+// Returns the table record position and the table offset.
+fn find_table(data: &[u8], tag: &[u8; 4]) -> (usize, usize) {
+    let face = RawFace::parse(data, 0).unwrap();
+    let index = face
+        .table_records
+        .into_iter()
+        .position(|record| record.tag == Tag::from_bytes(tag))
+        .unwrap();
+    let offset = face.table_records.get(index as u16).unwrap().offset;
+    (12 + index * 16, offset as usize)
+}
+
+fn set_u16(data: &mut [u8], offset: usize, n: u16) {
+    data[offset..offset + 2].copy_from_slice(&n.to_be_bytes());
+}
+
+fn set_u32(data: &mut [u8], offset: usize, n: u32) {
+    data[offset..offset + 4].copy_from_slice(&n.to_be_bytes());
+}
+
+fn run(data: &[u8]) -> Vec<Diagnostic> {
+    validate(&RawFace::parse(data, 0).unwrap())
+}
+
+fn has(
+    diagnostics: &[Diagnostic],
+    tag: &[u8; 4],
+    offset: Option<usize>,
+    severity: Severity,
+) -> bool {
+    diagnostics
+        .iter()
+        .any(|d| d.tag == Tag::from_bytes(tag) && d.offset == offset && d.severity == severity)
+}
+
+#[test]
+fn valid() {
+    let diagnostics = run(crate::DEMO_FONT);
+    // The font has no name, OS/2 and post tables.
+    assert_eq!(diagnostics.len(), 3);
+    for d in &diagnostics {
+        assert_eq!(d.severity, Severity::Warning);
+        assert_eq!(d.offset, None);
+        assert_eq!(d.message, "missing required table");
+    }
+}
+
+#[test]
+fn checksum_mismatch() {
+    let mut data = crate::DEMO_FONT.to_vec();
+    let (record, offset) = find_table(&data, b"glyf");
+    set_u32(&mut data, record + 4, 0);
+
+    let diagnostics = run(&data);
+    assert_eq!(diagnostics.len(), 5);
+    assert!(has(&diagnostics, b"glyf", Some(offset), Severity::Warning));
+
+    // The table directory is a part of the font checksum.
+    let (_, head_offset) = find_table(&data, b"head");
+    assert!(has(
+        &diagnostics,
+        b"head",
+        Some(head_offset + 8),
+        Severity::Warning
+    ));
+}
+
+#[test]
+fn table_out_of_bounds() {
+    let mut data = crate::DEMO_FONT.to_vec();
+    let (record, offset) = find_table(&data, b"hmtx");
+    set_u32(&mut data, record + 12, 0xFFFF);
+
+    let diagnostics = run(&data);
+    assert!(has(&diagnostics, b"hmtx", Some(offset), Severity::Error));
+}
+
+#[test]
+fn malformed_table() {
+    let mut data = crate::DEMO_FONT.to_vec();
+    let (record, offset) = find_table(&data, b"hmtx");
+    set_u32(&mut data, record + 12, 2);
+
+    let diagnostics = run(&data);
+    assert!(diagnostics.iter().any(|d| d.tag == Tag::from_bytes(b"hmtx")
+        && d.offset == Some(offset)
+        && d.message == "malformed table"));
+}
+
+#[test]
+fn missing_head() {
+    let mut data = crate::DEMO_FONT.to_vec();
+    let (record, _) = find_table(&data, b"head");
+    data[record..record + 4].copy_from_slice(b"xxxx");
+
+    let diagnostics = run(&data);
+    assert!(has(&diagnostics, b"head", None, Severity::Error));
+}
+
+#[test]
+fn cmap_unsorted_segments() {
+    let mut data = crate::DEMO_FONT.to_vec();
+    let (_, offset) = find_table(&data, b"cmap");
+    // A format 4 subtable with 2 segments.
+    let subtable = offset + 12;
+    let end_codes = subtable + 14;
+    let start_codes = end_codes + 6;
+    set_u16(&mut data, end_codes, 0xFFFF);
+    set_u16(&mut data, end_codes + 2, 65);
+    set_u16(&mut data, start_codes, 0xFFFF);
+    set_u16(&mut data, start_codes + 2, 65);
+
+    let diagnostics = run(&data);
+    assert!(has(
+        &diagnostics,
+        b"cmap",
+        Some(start_codes + 2),
+        Severity::Error
+    ));
+    assert!(has(
+        &diagnostics,
+        b"cmap",
+        Some(end_codes),
+        Severity::Warning
+    ));
+}
+
+#[test]
+fn loca_not_monotonic() {
+    let mut data = crate::DEMO_FONT.to_vec();
+    let (_, offset) = find_table(&data, b"loca");
+    // Short offsets.
+    set_u16(&mut data, offset + 4, 10);
+
+    let diagnostics = run(&data);
+    assert!(has(
+        &diagnostics,
+        b"loca",
+        Some(offset + 4),
+        Severity::Error
+    ));
+}
+
+#[test]
+fn loca_out_of_bounds() {
+    let mut data = crate::DEMO_FONT.to_vec();
+    let (_, offset) = find_table(&data, b"loca");
+    set_u16(&mut data, offset + 4, 1000);
+
+    let diagnostics = run(&data);
+    assert!(diagnostics.iter().any(|d| d.tag == Tag::from_bytes(b"loca")
+        && d.offset == Some(offset + 4)
+        && d.message.contains("past the end")));
+}
+
+#[test]
+fn cff_stack_overflow() {
+    let mut cff = Vec::new();
+    cff.extend_from_slice(&[1, 0, 4, 0]); // header
+    cff.extend_from_slice(&[0, 0]); // Name INDEX
+    cff.extend_from_slice(&[0, 1, 1, 1, 3, 17 + 139, 17]); // Top DICT INDEX with a CharStrings offset
+    cff.extend_from_slice(&[0, 0]); // String INDEX
+    cff.extend_from_slice(&[0, 0]); // Global Subrs INDEX
+    cff.extend_from_slice(&[0, 1, 1, 1, 52]); // CharStrings INDEX
+    // 50 zero operands, while only 48 are allowed.
+    cff.extend_from_slice(&[139; 50]);
+    cff.push(14); // endchar
+
+    let data = crate::build_demo_font(&[(b"CFF ", &cff)]);
+
+    let diagnostics = run(&data);
+    let (_, offset) = find_table(&data, b"CFF ");
+    assert!(diagnostics.iter().any(|d| d.tag == Tag::from_bytes(b"CFF ")
+        && d.offset == Some(offset + 22)
+        && d.severity == Severity::Error
+        && d.message.contains("ArgumentsStackLimitReached")));
+}
@@ -0,0 +1,261 @@
+use std::fmt::Write;
+
+use ttf_parser::{Face, GlyphId, RawFace, Rect, Tag, Variation};
+use crate::{convert, Unit::*};
+
+struct Builder(String);
+
+impl ttf_parser::OutlineBuilder for Builder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        write!(&mut self.0, "M {} {} ", x, y).unwrap();
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        write!(&mut self.0, "L {} {} ", x, y).unwrap();
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        write!(&mut self.0, "Q {} {} {} {} ", x1, y1, x, y).unwrap();
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        write!(&mut self.0, "C {} {} {} {} {} {} ", x1, y1, x2, y2, x, y).unwrap();
+    }
+
+    fn close(&mut self) {
+        write!(&mut self.0, "Z ").unwrap();
+    }
+}
+
+// A CFF2 INDEX with 1-byte offsets.
+fn index(items: &[&[u8]]) -> Vec<u8> {
+    let mut data = convert(&[
+        UInt32(items.len() as u32), // count
+        UInt8(1), // offset size
+    ]);
+
+    let mut offset = 1;
+    data.push(offset);
+    for item in items {
+        offset += item.len() as u8;
+        data.push(offset);
+    }
+
+    for item in items {
+        data.extend_from_slice(item);
+    }
+
+    data
+}
+
+fn varc(
+    coverage: &[u16],
+    var_store: &[u8],
+    conditions: &[u8],
+    axis_indices: &[u8],
+    glyphs: &[u8],
+) -> Vec<u8> {
+    let mut coverage_data = convert(&[
+        UInt16(1), // format
+        UInt16(coverage.len() as u16), // count
+    ]);
+    for glyph in coverage {
+        coverage_data.extend_from_slice(&glyph.to_be_bytes());
+    }
+
+    let mut data = Vec::new();
+    let mut offsets = Vec::new();
+    let mut offset = 24;
+    for subtable in [&coverage_data[..], var_store, conditions, axis_indices, glyphs].iter() {
+        offsets.push(if subtable.is_empty() { 0 } else { offset });
+        offset += subtable.len() as u32;
+        data.extend_from_slice(subtable);
+    }
+
+    let mut header = convert(&[
+        UInt16(1), // major version
+        UInt16(0), // minor version
+        UInt32(offsets[0]), // coverage offset
+        UInt32(offsets[1]), // multi variation store offset
+        UInt32(offsets[2]), // condition list offset
+        UInt32(offsets[3]), // axis indices list offset
+        UInt32(offsets[4]), // variable composite glyphs offset
+    ]);
+    header.extend_from_slice(&data);
+    header
+}
+
+fn build_font(varc: &[u8]) -> Vec<u8> {
+    let demo = RawFace::parse(crate::DEMO_FONT, 0).unwrap();
+    let fvar = crate::fvar();
+    crate::build_demo_font(&[
+        (b"VARC", varc),
+        (b"fvar", &fvar),
+        (b"glyf", demo.table(Tag::from_bytes(b"glyf")).unwrap()),
+        (b"loca", demo.table(Tag::from_bytes(b"loca")).unwrap()),
+    ])
+}
+
+fn location(face: &Face, value: f32) -> ttf_parser::VariationLocation {
+    face.variation_location(&[Variation { axis: Tag::from_bytes(b"wght"), value }]).unwrap()
+}
+
+#[test]
+fn unsupported_version() {
+    let mut data = varc(&[2], &[], &[], &[], &index(&[]));
+    data[1] = 2;
+    assert!(ttf_parser::varc::Table::parse(&data).is_none());
+}
+
+#[test]
+fn transforms() {
+    let glyph = convert(&[
+        // VarComponent [0]
+        UInt8(0x10), // flags: HAVE_TRANSLATE_X
+        UInt16(1), // glyph ID
+        Int16(100), // translate x
+
+        // VarComponent [1]
+        UInt8(0x81), UInt8(0x00), // flags: HAVE_SCALE_X
+        UInt16(1), // glyph ID
+        Int16(512), // scale x
+    ]);
+    let data = build_font(&varc(&[2], &[], &[], &[], &index(&[&glyph])));
+    let face = Face::parse(&data, 0).unwrap();
+    assert!(face.tables().varc.unwrap().coverage.contains(GlyphId(2)));
+
+    let mut builder = Builder(String::new());
+    let bbox = face.outline_glyph(GlyphId(2), &mut builder).unwrap();
+    assert_eq!(builder.0,
+        "M 273 267 L 469 267 L 370 587 L 273 267 Z M 106 0 L 324 656 \
+         L 420 656 L 641 0 L 552 0 L 490 200 L 251 200 L 185 0 L 106 0 Z \
+         M 86.5 133.5 L 184.5 133.5 L 135 293.5 L 86.5 133.5 Z M 3 0 L 112 328 \
+         L 160 328 L 270.5 0 L 226 0 L 195 100 L 75.5 100 L 42.5 0 L 3 0 Z ");
+    assert_eq!(bbox, Rect { x_min: 3, y_min: 0, x_max: 641, y_max: 656 });
+
+    // Not covered glyphs are not affected.
+    let mut builder = Builder(String::new());
+    let bbox = face.outline_glyph(GlyphId(1), &mut builder).unwrap();
+    assert_eq!(bbox, Rect { x_min: 6, y_min: 0, x_max: 541, y_max: 656 });
+}
+
+#[test]
+fn rotation() {
+    let glyph = convert(&[
+        UInt8(0x40), // flags: HAVE_ROTATION
+        UInt16(1), // glyph ID
+        Int16(2048), // rotation, 0.5 * Pi
+    ]);
+    let data = build_font(&varc(&[2], &[], &[], &[], &index(&[&glyph])));
+    let face = Face::parse(&data, 0).unwrap();
+
+    let bbox = face.glyph_bounding_box(GlyphId(2)).unwrap();
+    assert_eq!(bbox, Rect { x_min: -656, y_min: 6, x_max: 0, y_max: 541 });
+}
+
+#[test]
+fn transform_variation() {
+    let var_store = convert(&[
+        UInt16(1), // format
+        UInt32(12), // region list offset
+        UInt16(1), // item variation data count
+        UInt32(28), // item variation data offset [0]
+
+        // SparseVariationRegionList
+        UInt16(1), // region count
+        UInt32(6), // region offset [0]
+
+        // SparseVariationRegion [0]
+        UInt16(1), // region axis count
+        UInt16(0), // axis index
+        Int16(0), // start coord
+        Int16(16384), // peak coord
+        Int16(16384), // end coord
+
+        // MultiItemVariationData [0]
+        UInt8(1), // format
+        UInt16(1), // region index count
+        UInt16(0), // region index [0]
+        UInt32(1), // delta sets count
+        UInt8(1), // offset size
+        UInt8(1), // offset [0]
+        UInt8(3), // offset [1]
+        UInt8(0), // 1 byte value
+        Int8(50), // delta
+    ]);
+    let glyph = convert(&[
+        UInt8(0x18), // flags: TRANSFORM_HAS_VARIATION | HAVE_TRANSLATE_X
+        UInt16(1), // glyph ID
+        UInt8(0), // transform variation index
+        Int16(0), // translate x
+    ]);
+    let data = build_font(&varc(&[2], &var_store, &[], &[], &index(&[&glyph])));
+    let mut face = Face::parse(&data, 0).unwrap();
+
+    let bbox = face.glyph_bounding_box(GlyphId(2)).unwrap();
+    assert_eq!(bbox, Rect { x_min: 6, y_min: 0, x_max: 541, y_max: 656 });
+
+    let bbox = face.glyph_bounding_box_at(GlyphId(2), &location(&face, 650.0)).unwrap();
+    assert_eq!(bbox, Rect { x_min: 31, y_min: 0, x_max: 566, y_max: 656 });
+
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    let bbox = face.glyph_bounding_box(GlyphId(2)).unwrap();
+    assert_eq!(bbox, Rect { x_min: 56, y_min: 0, x_max: 591, y_max: 656 });
+}
+
+#[test]
+fn nested_with_axes_and_conditions() {
+    let conditions = convert(&[
+        UInt32(1), // condition count
+        UInt32(8), // condition offset [0]
+
+        // ConditionAxisRange
+        UInt16(1), // format
+        UInt16(0), // axis index
+        Int16(8192), // filter range min
+        Int16(16384), // filter range max
+    ]);
+    let axis_indices = index(&[&[
+        0x00, // 1 byte value
+        0, // axis index [0]
+    ]]);
+    let glyph_2 = convert(&[
+        UInt8(0x80), UInt8(0x80), // flags: HAVE_CONDITION
+        UInt16(1), // glyph ID
+        UInt8(0), // condition index
+    ]);
+    let glyph_3 = convert(&[
+        UInt8(0x03), // flags: RESET_UNSPECIFIED_AXES | HAVE_AXES
+        UInt16(2), // glyph ID
+        UInt8(0), // axis indices index
+        UInt8(0x40), // 2 byte value
+        Int16(16384), // axis value [0]
+    ]);
+    let data = build_font(&varc(&[2, 3], &[], &conditions, &axis_indices, &index(&[&glyph_2, &glyph_3])));
+    let face = Face::parse(&data, 0).unwrap();
+
+    // The condition is not met at the default location.
+    assert_eq!(face.glyph_bounding_box(GlyphId(2)), None);
+    assert_eq!(face.glyph_bounding_box_at(GlyphId(2), &location(&face, 900.0)),
+               Some(Rect { x_min: 6, y_min: 0, x_max: 541, y_max: 656 }));
+
+    // Glyph 3 sets the axis for its component.
+    assert_eq!(face.glyph_bounding_box(GlyphId(3)),
+               Some(Rect { x_min: 6, y_min: 0, x_max: 541, y_max: 656 }));
+}
+
+#[test]
+fn recursion_limit() {
+    // Glyph 2 references glyph 3 and vice versa.
+    let glyph_2 = convert(&[
+        UInt8(0x00), // flags
+        UInt16(3), // glyph ID
+    ]);
+    let glyph_3 = convert(&[
+        UInt8(0x00), // flags
+        UInt16(2), // glyph ID
+    ]);
+    let data = build_font(&varc(&[2, 3], &[], &[], &[], &index(&[&glyph_2, &glyph_3])));
+    let face = Face::parse(&data, 0).unwrap();
+    assert_eq!(face.glyph_bounding_box(GlyphId(2)), None);
+}
@@ -0,0 +1,155 @@
+use ttf_parser::woff::{is_woff, Woff};
+use ttf_parser::{Face, FaceParsingError, RawFace};
+use crate::{push_u16, push_u32, DEMO_FONT};
+
+const HEADER_SIZE: usize = 44;
+const ENTRY_SIZE: usize = 20;
+
+// Converts a TrueType font into WOFF, compressing tables when it makes them smaller.
+fn to_woff(font: &[u8], metadata: &[u8], private_data: &[u8]) -> Vec<u8> {
+    let face = RawFace::parse(font, 0).unwrap();
+
+    let mut blocks = Vec::new();
+    for record in face.table_records {
+        let table = face.table(record.tag).unwrap();
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(table, 6);
+        let block = if compressed.len() < table.len() {
+            compressed
+        } else {
+            table.to_vec()
+        };
+        blocks.push((record, table.len(), block));
+    }
+
+    let mut offset = HEADER_SIZE + ENTRY_SIZE * blocks.len();
+    let mut directory = Vec::new();
+    let mut tables = Vec::new();
+    for (record, orig_length, block) in &blocks {
+        push_u32(&mut directory, record.tag.0);
+        push_u32(&mut directory, (offset + tables.len()) as u32);
+        push_u32(&mut directory, block.len() as u32);
+        push_u32(&mut directory, *orig_length as u32);
+        push_u32(&mut directory, record.check_sum);
+        tables.extend_from_slice(block);
+        tables.resize((tables.len() + 3) & !3, 0);
+    }
+    offset += tables.len();
+
+    let compressed_metadata = miniz_oxide::deflate::compress_to_vec_zlib(metadata, 6);
+    let (meta_offset, meta_length) = if metadata.is_empty() {
+        (0, 0)
+    } else {
+        (offset, compressed_metadata.len())
+    };
+    offset += (meta_length + 3) & !3;
+
+    let private_offset = if private_data.is_empty() { 0 } else { offset };
+    offset += private_data.len();
+
+    let mut data = Vec::new();
+    data.extend_from_slice(b"wOFF");
+    data.extend_from_slice(&font[0..4]); // flavor
+    push_u32(&mut data, offset as u32); // length
+    push_u16(&mut data, blocks.len() as u16); // numTables
+    push_u16(&mut data, 0); // reserved
+    push_u32(&mut data, font.len() as u32); // totalSfntSize
+    push_u16(&mut data, 1); // majorVersion
+    push_u16(&mut data, 2); // minorVersion
+    push_u32(&mut data, meta_offset as u32); // metaOffset
+    push_u32(&mut data, meta_length as u32); // metaLength
+    push_u32(&mut data, metadata.len() as u32); // metaOrigLength
+    push_u32(&mut data, private_offset as u32); // privOffset
+    push_u32(&mut data, private_data.len() as u32); // privLength
+    data.extend_from_slice(&directory);
+    data.extend_from_slice(&tables);
+    if !metadata.is_empty() {
+        data.extend_from_slice(&compressed_metadata);
+        data.resize((data.len() + 3) & !3, 0);
+    }
+    data.extend_from_slice(private_data);
+    data
+}
+
+#[test]
+fn tables_roundtrip() {
+    let data = to_woff(DEMO_FONT, &[], &[]);
+    assert!(is_woff(&data));
+    assert!(!is_woff(DEMO_FONT));
+
+    let woff = Woff::parse(&data).unwrap();
+    assert_eq!(woff.flavor(), 0x00010000);
+    assert_eq!(woff.major_version(), 1);
+    assert_eq!(woff.minor_version(), 2);
+    assert_eq!(woff.metadata(), None);
+    assert_eq!(woff.private_data(), None);
+
+    let original = RawFace::parse(DEMO_FONT, 0).unwrap();
+    let decoded = woff.raw_face().unwrap();
+    assert_eq!(decoded.table_records.len(), original.table_records.len());
+    for record in original.table_records {
+        assert_eq!(decoded.table(record.tag), original.table(record.tag));
+    }
+
+    let original = Face::parse(DEMO_FONT, 0).unwrap();
+    let decoded = woff.face().unwrap();
+    assert_eq!(decoded.number_of_glyphs(), original.number_of_glyphs());
+    assert_eq!(decoded.glyph_index('A'), original.glyph_index('A'));
+}
+
+#[test]
+fn metadata_and_private_data() {
+    let metadata = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?><metadata version=\"1.0\"/>";
+    let data = to_woff(DEMO_FONT, metadata, b"private");
+    let woff = Woff::parse(&data).unwrap();
+    assert_eq!(woff.metadata(), Some(&metadata[..]));
+    assert_eq!(woff.private_data(), Some(&b"private"[..]));
+}
+
+#[test]
+fn not_woff() {
+    assert_eq!(
+        Woff::parse(DEMO_FONT).unwrap_err(),
+        FaceParsingError::UnknownMagic
+    );
+
+    let data = to_woff(DEMO_FONT, &[], &[]);
+    assert_eq!(
+        RawFace::parse(&data, 0).unwrap_err(),
+        FaceParsingError::WoffData
+    );
+    assert_eq!(
+        Face::parse(&data, 0).unwrap_err(),
+        FaceParsingError::WoffData
+    );
+}
+
+#[test]
+fn truncated() {
+    let data = to_woff(DEMO_FONT, &[], &[]);
+    assert_eq!(
+        Woff::parse(&data[..HEADER_SIZE]).unwrap_err(),
+        FaceParsingError::MalformedFont
+    );
+    assert_eq!(
+        Woff::parse(&data[..data.len() - 8]).unwrap_err(),
+        FaceParsingError::MalformedFont
+    );
+}
+
+#[test]
+fn corrupted_table() {
+    let mut data = to_woff(DEMO_FONT, &[], &[]);
+    // Make the first table's compressed length larger than the original one.
+    let orig_length = &data[HEADER_SIZE + 12..HEADER_SIZE + 16];
+    let n = u32::from_be_bytes([
+        orig_length[0],
+        orig_length[1],
+        orig_length[2],
+        orig_length[3],
+    ]);
+    data[HEADER_SIZE + 8..HEADER_SIZE + 12].copy_from_slice(&(n + 1).to_be_bytes());
+    assert_eq!(
+        Woff::parse(&data).unwrap_err(),
+        FaceParsingError::MalformedFont
+    );
+}
@@ -0,0 +1,401 @@
+use ttf_parser::woff2::{is_woff2, Woff2};
+use ttf_parser::{Face, FaceParsingError, GlyphId, RawFace, Rect, Tag};
+use crate::{checksum, outline, push_u16, push_u32, DEMO_FONT};
+
+const HEADER_SIZE: usize = 48;
+const ARBITRARY_TAG: u8 = 63;
+
+fn push_uint_base128(data: &mut Vec<u8>, n: u32) {
+    let mut bytes = vec![(n & 0x7F) as u8];
+    let mut n = n >> 7;
+    while n != 0 {
+        bytes.push((n & 0x7F) as u8 | 0x80);
+        n >>= 7;
+    }
+
+    bytes.reverse();
+    data.extend_from_slice(&bytes);
+}
+
+fn push_entry(
+    data: &mut Vec<u8>,
+    tag: Tag,
+    version: u8,
+    orig_length: u32,
+    transform_length: Option<u32>,
+) {
+    data.push(ARBITRARY_TAG | (version << 6));
+    push_u32(data, tag.0);
+    push_uint_base128(data, orig_length);
+    if let Some(length) = transform_length {
+        push_uint_base128(data, length);
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    data: Vec<u8>,
+    bit: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u8) {
+        for i in 0..bits {
+            if self.bit == 0 {
+                self.data.push(0);
+            }
+
+            *self.data.last_mut().unwrap() |= (((value >> i) & 1) as u8) << self.bit;
+            self.bit = (self.bit + 1) % 8;
+        }
+    }
+}
+
+// Creates a Brotli stream made of uncompressed meta-blocks.
+fn brotli_stored(data: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter::default();
+    w.write(0, 1); // WBITS = 16
+    for chunk in data.chunks(0x10000) {
+        w.write(0, 1); // ISLAST
+        w.write(0, 2); // MNIBBLES = 4
+        w.write(chunk.len() as u32 - 1, 16); // MLEN - 1
+        w.write(1, 1); // ISUNCOMPRESSED
+        w.bit = 0;
+        w.data.extend_from_slice(chunk);
+    }
+    w.write(1, 1); // ISLAST
+    w.write(1, 1); // ISLASTEMPTY
+    w.data
+}
+
+struct Woff2Builder {
+    flavor: u32,
+    directory: Vec<u8>,
+    num_tables: u16,
+    collection_directory: Vec<u8>,
+    tables: Vec<u8>,
+    metadata: Vec<u8>,
+    private_data: Vec<u8>,
+}
+
+impl Woff2Builder {
+    fn new(flavor: u32) -> Self {
+        Woff2Builder {
+            flavor,
+            directory: Vec::new(),
+            num_tables: 0,
+            collection_directory: Vec::new(),
+            tables: Vec::new(),
+            metadata: Vec::new(),
+            private_data: Vec::new(),
+        }
+    }
+
+    fn table(&mut self, tag: Tag, data: &[u8]) {
+        // `glyf` and `loca` use 3 as the null transform.
+        let version = if tag == Tag::from_bytes(b"glyf") || tag == Tag::from_bytes(b"loca") {
+            3
+        } else {
+            0
+        };
+
+        push_entry(&mut self.directory, tag, version, data.len() as u32, None);
+        self.tables.extend_from_slice(data);
+        self.num_tables += 1;
+    }
+
+    fn transformed_table(&mut self, tag: Tag, version: u8, orig_length: u32, data: &[u8]) {
+        push_entry(
+            &mut self.directory,
+            tag,
+            version,
+            orig_length,
+            Some(data.len() as u32),
+        );
+        self.tables.extend_from_slice(data);
+        self.num_tables += 1;
+    }
+
+    fn build(&self) -> Vec<u8> {
+        let compressed = brotli_stored(&self.tables);
+        let mut offset = HEADER_SIZE + self.directory.len() + self.collection_directory.len();
+        offset += compressed.len();
+        offset = (offset + 3) & !3;
+
+        let compressed_metadata = brotli_stored(&self.metadata);
+        let (meta_offset, meta_length) = if self.metadata.is_empty() {
+            (0, 0)
+        } else {
+            (offset, compressed_metadata.len())
+        };
+        offset += (meta_length + 3) & !3;
+
+        let private_offset = if self.private_data.is_empty() {
+            0
+        } else {
+            offset
+        };
+        offset += self.private_data.len();
+
+        let mut data = Vec::new();
+        data.extend_from_slice(b"wOF2");
+        push_u32(&mut data, self.flavor);
+        push_u32(&mut data, offset as u32); // length
+        push_u16(&mut data, self.num_tables);
+        push_u16(&mut data, 0); // reserved
+        push_u32(&mut data, 0); // totalSfntSize
+        push_u32(&mut data, compressed.len() as u32);
+        push_u16(&mut data, 1); // majorVersion
+        push_u16(&mut data, 2); // minorVersion
+        push_u32(&mut data, meta_offset as u32);
+        push_u32(&mut data, meta_length as u32);
+        push_u32(&mut data, self.metadata.len() as u32);
+        push_u32(&mut data, private_offset as u32);
+        push_u32(&mut data, self.private_data.len() as u32);
+        data.extend_from_slice(&self.directory);
+        data.extend_from_slice(&self.collection_directory);
+        data.extend_from_slice(&compressed);
+        data.resize((data.len() + 3) & !3, 0);
+        if !self.metadata.is_empty() {
+            data.extend_from_slice(&compressed_metadata);
+            data.resize((data.len() + 3) & !3, 0);
+        }
+        data.extend_from_slice(&self.private_data);
+        data
+    }
+}
+
+#[test]
+fn null_transform() {
+    let original = RawFace::parse(DEMO_FONT, 0).unwrap();
+    let mut builder = Woff2Builder::new(0x00010000);
+    for record in original.table_records {
+        builder.table(record.tag, original.table(record.tag).unwrap());
+    }
+    builder.metadata =
+        b"<?xml version=\"1.0\" encoding=\"UTF-8\"?><metadata version=\"1.0\"/>".to_vec();
+    builder.private_data = b"private".to_vec();
+
+    let data = builder.build();
+    assert!(is_woff2(&data));
+    assert!(!is_woff2(DEMO_FONT));
+
+    let woff = Woff2::parse(&data).unwrap();
+    assert_eq!(woff.flavor(), 0x00010000);
+    assert_eq!(woff.major_version(), 1);
+    assert_eq!(woff.minor_version(), 2);
+    assert_eq!(woff.metadata(), Some(&builder.metadata[..]));
+    assert_eq!(woff.private_data(), Some(&b"private"[..]));
+    assert_eq!(woff.fonts_in_collection(), None);
+    // 'The sum of the entire font as uint32 values must equal 0xB1B0AFBA.'
+    assert_eq!(checksum(woff.data()), 0xB1B0AFBA);
+
+    let decoded = woff.raw_face(0).unwrap();
+    assert_eq!(decoded.table_records.len(), original.table_records.len());
+    for record in original.table_records {
+        let mut table = original.table(record.tag).unwrap().to_vec();
+        let decoded_table = decoded.table(record.tag).unwrap();
+        if record.tag == Tag::from_bytes(b"head") {
+            // checkSumAdjustment is recalculated.
+            table[8..12].copy_from_slice(&decoded_table[8..12]);
+        }
+
+        assert_eq!(decoded_table, &table[..]);
+    }
+
+    let original = Face::parse(DEMO_FONT, 0).unwrap();
+    let decoded = woff.face(0).unwrap();
+    assert_eq!(decoded.number_of_glyphs(), original.number_of_glyphs());
+    for id in 0..original.number_of_glyphs() {
+        assert_eq!(outline(&decoded, GlyphId(id)), outline(&original, GlyphId(id)));
+    }
+}
+
+#[test]
+fn transformed_glyf_and_hmtx() {
+    let original = RawFace::parse(DEMO_FONT, 0).unwrap();
+    let mut head = original.table(Tag::from_bytes(b"head")).unwrap().to_vec();
+    head[50..52].copy_from_slice(&0u16.to_be_bytes()); // indexToLocFormat
+    let mut hhea = original.table(Tag::from_bytes(b"hhea")).unwrap().to_vec();
+    hhea[34..36].copy_from_slice(&2u16.to_be_bytes()); // numberOfHMetrics
+    let mut maxp = original.table(Tag::from_bytes(b"maxp")).unwrap().to_vec();
+    maxp[4..6].copy_from_slice(&3u16.to_be_bytes()); // numGlyphs
+
+    let contours = [0, 0, 0, 1, 0xFF, 0xFF]; // 0, 1, -1
+    let points = [4];
+    let flags = [11, 5, 13, 4 | 0x80];
+    let glyphs = [50, 188, 144, 188, 0];
+    let composites = [
+        0x00, 0x03, // flags: ARG_1_AND_2_ARE_WORDS | ARGS_ARE_XY_VALUES
+        0x00, 0x01, // glyph index
+        0x00, 0x64, // dx: 100
+        0x00, 0x00, // dy: 0
+    ];
+    let bboxes = [
+        0x20, 0x00, 0x00, 0x00, // bitmap
+        0x00, 0x96, 0x00, 0x00, 0x02, 0x26, 0x02, 0xBC, // 150 0 550 700
+    ];
+    let instructions = [];
+    let overlap_bitmap = [0x40];
+
+    let mut glyf = Vec::new();
+    push_u16(&mut glyf, 0); // reserved
+    push_u16(&mut glyf, 1); // optionFlags
+    push_u16(&mut glyf, 3); // numGlyphs
+    push_u16(&mut glyf, 0); // indexFormat
+    let streams: [&[u8]; 7] = [
+        &contours,
+        &points,
+        &flags,
+        &glyphs,
+        &composites,
+        &bboxes,
+        &instructions,
+    ];
+    for stream in &streams {
+        push_u32(&mut glyf, stream.len() as u32);
+    }
+    for stream in &streams {
+        glyf.extend_from_slice(stream);
+    }
+    glyf.extend_from_slice(&overlap_bitmap);
+
+    let mut hmtx = vec![0x03]; // no lsb arrays
+    push_u16(&mut hmtx, 500);
+    push_u16(&mut hmtx, 600);
+
+    let mut builder = Woff2Builder::new(0x00010000);
+    builder.table(Tag::from_bytes(b"head"), &head);
+    builder.table(Tag::from_bytes(b"hhea"), &hhea);
+    builder.transformed_table(Tag::from_bytes(b"hmtx"), 1, 10, &hmtx);
+    builder.table(Tag::from_bytes(b"maxp"), &maxp);
+    builder.transformed_table(Tag::from_bytes(b"glyf"), 0, 0, &glyf);
+    builder.transformed_table(Tag::from_bytes(b"loca"), 0, 8, &[]);
+
+    let woff = Woff2::parse(&builder.build()).unwrap();
+    assert_eq!(checksum(woff.data()), 0xB1B0AFBA);
+    let face = woff.face(0).unwrap();
+    assert_eq!(face.number_of_glyphs(), 3);
+
+    assert_eq!(outline(&face, GlyphId(0)), "");
+    assert_eq!(
+        outline(&face, GlyphId(1)),
+        "M 50 0 L 50 700 L 450 700 Q 450 0 50 0 Z "
+    );
+    assert_eq!(
+        outline(&face, GlyphId(2)),
+        "M 150 0 L 150 700 L 550 700 Q 550 0 150 0 Z "
+    );
+    assert_eq!(
+        face.glyph_bounding_box(GlyphId(1)),
+        Some(Rect {
+            x_min: 50,
+            y_min: 0,
+            x_max: 450,
+            y_max: 700
+        })
+    );
+
+    assert_eq!(face.glyph_hor_advance(GlyphId(1)), Some(600));
+    assert_eq!(face.glyph_hor_advance(GlyphId(2)), Some(600));
+    assert_eq!(face.glyph_hor_side_bearing(GlyphId(0)), Some(0));
+    assert_eq!(face.glyph_hor_side_bearing(GlyphId(1)), Some(50));
+    assert_eq!(face.glyph_hor_side_bearing(GlyphId(2)), Some(150));
+
+    // OVERLAP_SIMPLE is set on the first flag.
+    let glyf = face.raw_face().table(Tag::from_bytes(b"glyf")).unwrap();
+    assert_eq!(glyf[14] & 0x40, 0x40);
+}
+
+#[test]
+fn collection() {
+    let original = RawFace::parse(DEMO_FONT, 0).unwrap();
+    let mut builder = Woff2Builder::new(0x74746366);
+    for record in original.table_records {
+        builder.table(record.tag, original.table(record.tag).unwrap());
+    }
+    let mut post = vec![0; 32];
+    post[1] = 3; // version 3.0
+    builder.table(Tag::from_bytes(b"post"), &post);
+
+    let num_tables = builder.num_tables;
+    let dir = &mut builder.collection_directory;
+    push_u32(dir, 0x00010000); // ttcVersion
+    dir.push(2); // numFonts
+                 // The first font references all tables.
+    dir.push(num_tables as u8);
+    push_u32(dir, 0x00010000);
+    for i in 0..num_tables {
+        dir.push(i as u8);
+    }
+    // The second one doesn't have `post`.
+    dir.push(num_tables as u8 - 1);
+    push_u32(dir, 0x00010000);
+    for i in 0..num_tables - 1 {
+        dir.push(i as u8);
+    }
+
+    let woff = Woff2::parse(&builder.build()).unwrap();
+    assert_eq!(woff.flavor(), 0x74746366);
+    assert_eq!(woff.fonts_in_collection(), Some(2));
+
+    let post = Tag::from_bytes(b"post");
+    let glyf = Tag::from_bytes(b"glyf");
+    let first = woff.raw_face(0).unwrap();
+    let second = woff.raw_face(1).unwrap();
+    assert!(first.table(post).is_some());
+    assert!(second.table(post).is_none());
+    // Tables are shared.
+    assert_eq!(
+        first.table(glyf).unwrap().as_ptr(),
+        second.table(glyf).unwrap().as_ptr()
+    );
+
+    let face = woff.face(1).unwrap();
+    assert_eq!(
+        outline(&face, GlyphId(1)),
+        outline(&Face::parse(DEMO_FONT, 0).unwrap(), GlyphId(1))
+    );
+    assert!(woff.face(2).is_err());
+}
+
+#[test]
+fn not_woff2() {
+    assert_eq!(
+        Woff2::parse(DEMO_FONT).unwrap_err(),
+        FaceParsingError::UnknownMagic
+    );
+
+    let data = Woff2Builder::new(0x00010000).build();
+    assert_eq!(
+        Face::parse(&data, 0).unwrap_err(),
+        FaceParsingError::WoffData
+    );
+}
+
+#[test]
+fn malformed() {
+    let original = RawFace::parse(DEMO_FONT, 0).unwrap();
+    let mut builder = Woff2Builder::new(0x00010000);
+    for record in original.table_records {
+        builder.table(record.tag, original.table(record.tag).unwrap());
+    }
+
+    let data = builder.build();
+    assert_eq!(
+        Woff2::parse(&data[..HEADER_SIZE]).unwrap_err(),
+        FaceParsingError::MalformedFont
+    );
+    assert_eq!(
+        Woff2::parse(&data[..data.len() - 8]).unwrap_err(),
+        FaceParsingError::MalformedFont
+    );
+
+    // `cmap` doesn't have transforms.
+    let mut data = data;
+    data[HEADER_SIZE] |= 1 << 6;
+    assert_eq!(
+        Woff2::parse(&data).unwrap_err(),
+        FaceParsingError::MalformedFont
+    );
+}
@@ -0,0 +1,167 @@
+use ttf_parser::writer::FontBuilder;
+use ttf_parser::{fonts_in_collection, Face, RawFace, Tag};
+use crate::{checksum, DEMO_FONT};
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+#[test]
+fn roundtrip() {
+    let original = RawFace::parse(DEMO_FONT, 0).unwrap();
+    let data = FontBuilder::from_raw_face(&original).build().unwrap();
+
+    assert_eq!(read_u32(&data, 0), 0x00010000); // sfntVersion
+    assert_eq!(read_u16(&data, 4), 7); // numTables
+    assert_eq!(read_u16(&data, 6), 64); // searchRange
+    assert_eq!(read_u16(&data, 8), 2); // entrySelector
+    assert_eq!(read_u16(&data, 10), 48); // rangeShift
+
+    // 'The sum of the entire font as uint32 values must equal 0xB1B0AFBA.'
+    assert_eq!(checksum(&data), 0xB1B0AFBA);
+
+    let face = RawFace::parse(&data, 0).unwrap();
+    assert_eq!(face.table_records.len(), original.table_records.len());
+    for record in face.table_records {
+        assert_eq!(record.offset % 4, 0);
+
+        let mut table = original.table(record.tag).unwrap().to_vec();
+        if record.tag == Tag::from_bytes(b"head") {
+            // checkSumAdjustment is recalculated and treated as zero.
+            table[8..12].copy_from_slice(&[0; 4]);
+            assert_eq!(record.check_sum, checksum(&table));
+            table[8..12].copy_from_slice(&face.table(record.tag).unwrap()[8..12]);
+        } else {
+            assert_eq!(record.check_sum, checksum(&table));
+        }
+
+        assert_eq!(face.table(record.tag).unwrap(), &table[..]);
+    }
+
+    assert!(Face::parse(&data, 0).is_ok());
+}
+
+#[test]
+fn replace_tables() {
+    let original = RawFace::parse(DEMO_FONT, 0).unwrap();
+    let mut builder = FontBuilder::from_raw_face(&original);
+    assert!(builder.remove_table(Tag::from_bytes(b"cmap")));
+    assert!(!builder.remove_table(Tag::from_bytes(b"cmap")));
+    builder.set_table(Tag::from_bytes(b"TEST"), vec![1, 2, 3, 4, 5]);
+    builder.set_table(Tag::from_bytes(b"zzzz"), &[6, 7][..]);
+    builder.set_table(Tag::from_bytes(b"TEST"), vec![1, 2, 3]);
+    assert_eq!(
+        builder.table(Tag::from_bytes(b"TEST")),
+        Some(&[1, 2, 3][..])
+    );
+
+    let data = builder.build().unwrap();
+    assert_eq!(checksum(&data), 0xB1B0AFBA);
+
+    let face = RawFace::parse(&data, 0).unwrap();
+    assert_eq!(face.table_records.len(), 8);
+    assert_eq!(face.table(Tag::from_bytes(b"cmap")), None);
+    assert_eq!(face.table(Tag::from_bytes(b"TEST")), Some(&[1, 2, 3][..]));
+    assert_eq!(face.table(Tag::from_bytes(b"zzzz")), Some(&[6, 7][..]));
+    for record in face.table_records {
+        assert_eq!(record.offset % 4, 0);
+    }
+
+    // Records are sorted, otherwise binary search would fail.
+    let tags: Vec<_> = face.table_records.into_iter().map(|r| r.tag).collect();
+    let mut sorted = tags.clone();
+    sorted.sort();
+    assert_eq!(tags, sorted);
+
+    let face = Face::parse(&data, 0).unwrap();
+    assert_eq!(face.glyph_index('A'), None);
+}
+
+#[test]
+fn collection() {
+    let original = RawFace::parse(DEMO_FONT, 0).unwrap();
+    let mut builder = FontBuilder::from_raw_face(&original);
+    assert!(builder.add_raw_face(&original));
+    builder.set_table(Tag::from_bytes(b"TEST"), vec![1, 2, 3, 4]);
+    builder.add_font(0x00010000);
+
+    let data = builder.build().unwrap();
+    assert_eq!(fonts_in_collection(&data), Some(3));
+
+    let first = RawFace::parse(&data, 0).unwrap();
+    let second = RawFace::parse(&data, 1).unwrap();
+    let third = RawFace::parse(&data, 2).unwrap();
+    assert_eq!(first.table_records.len(), 7);
+    assert_eq!(second.table_records.len(), 8);
+    assert_eq!(third.table_records.len(), 0);
+    assert_eq!(third.sfnt_version(), Some(0x00010000));
+    assert_eq!(first.table(Tag::from_bytes(b"TEST")), None);
+    assert_eq!(
+        second.table(Tag::from_bytes(b"TEST")),
+        Some(&[1, 2, 3, 4][..])
+    );
+
+    // Identical tables are shared.
+    for record in first.table_records {
+        let (_, other) = second
+            .table_records
+            .binary_search_by(|r| r.tag.cmp(&record.tag))
+            .unwrap();
+        assert_eq!(record.offset, other.offset);
+    }
+
+    assert!(Face::parse(&data, 1).is_ok());
+}
+
+#[test]
+fn sfnt_version() {
+    let mut data = DEMO_FONT.to_vec();
+    data[0..4].copy_from_slice(b"true");
+
+    let original = RawFace::parse(&data, 0).unwrap();
+    assert_eq!(original.sfnt_version(), Some(0x74727565));
+
+    let data = FontBuilder::from_raw_face(&original).build().unwrap();
+    assert_eq!(read_u32(&data, 0), 0x74727565);
+
+    // Not available when table records are not stored in the font data.
+    let manual = RawFace {
+        data: &data,
+        table_records: Default::default(),
+    };
+    assert_eq!(manual.sfnt_version(), None);
+    let data = FontBuilder::from_raw_face(&manual).build().unwrap();
+    assert_eq!(read_u32(&data, 0), 0x00010000);
+}
+
+#[test]
+fn out_of_bounds_table() {
+    let mut data = DEMO_FONT.to_vec();
+    // The first table record length.
+    data[24..28].copy_from_slice(&u32::MAX.to_be_bytes());
+
+    let original = RawFace::parse(&data, 0).unwrap();
+    let mut builder = FontBuilder::new(0x00010000);
+    assert!(!builder.add_raw_face(&original));
+
+    let data = builder.build().unwrap();
+    assert_eq!(fonts_in_collection(&data), Some(2));
+    let face = RawFace::parse(&data, 1).unwrap();
+    assert_eq!(face.table_records.len(), original.table_records.len() - 1);
+}
+
+#[test]
+fn short_head() {
+    let mut builder = FontBuilder::new(0x00010000);
+    builder.set_table(Tag::from_bytes(b"head"), &[0; 8][..]);
+    assert!(builder.build().is_none());
+}